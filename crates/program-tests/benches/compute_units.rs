@@ -0,0 +1,287 @@
+//! Compute units of each seka and sentinel instruction across parameter ranges: proof
+//! depth and claim bitmap size for seka, peers per `finalize_cycle` for sentinel.
+//!
+//! `cargo bench --manifest-path crates/program-tests/Cargo.toml --bench compute_units`
+//!
+//! Runs on LiteSVM against the deployed SBF builds (see `svm`). Every measurement is
+//! compared with `compute_units.baseline` next to this file and the run fails when one
+//! costs more than its baseline plus `CU_TOLERANCE_PCT` percent (default 2), or has no
+//! baseline at all. `CU_BLESS=1` rewrites the baseline instead; commit it with the
+//! change that moved the costs.
+
+use seka_merkle::{Leaf, MerkleTree};
+use sentinelkarma_program_tests::svm::Svm;
+use sentinelkarma_program_tests::{sentinel_ix, Sent, SEKA_DECIMALS, SENTINEL_DECIMALS};
+use sentinelkarma_sdk::seka::{self, LeafFormat};
+use sentinelkarma_sdk::sentinel;
+use solana_sdk::clock::Clock;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+const BASELINE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/benches/compute_units.baseline"
+);
+
+const PROOF_DEPTHS: [u32; 8] = [0, 1, 2, 4, 8, 12, 16, 20];
+const BITMAP_LENS: [u32; 5] = [1, 64, 512, 4096, 8192];
+const FINALIZE_PEERS: [usize; 4] = [1, 2, 4, 8];
+/// Largest claim bitmap a cycle state gets: `set_cycle_root` creates it in one CPI,
+/// which allocates at most 10 KiB, so trees past depth 16 only cover their first leaves.
+const MAX_BITMAP_LEN: u32 = 8192;
+
+#[derive(Default)]
+struct Bench {
+    units: BTreeMap<String, u64>,
+}
+
+impl Bench {
+    #[track_caller]
+    fn record(&mut self, name: impl Into<String>, sent: Sent) {
+        let units = sent.compute_units;
+        let name = name.into();
+        if let Err(e) = &sent.result {
+            panic!("{name} failed: {e}\n{}", sent.logs.join("\n"));
+        }
+        println!("{name:<48} {units:>8}");
+        self.units.insert(name, units);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// seka
+// ----------------------------------------------------------------------------
+
+/// seka with `alice` holding 100 SEKA.
+fn seka_setup(bench: Option<&mut Bench>) -> (Svm, Keypair, Keypair, Pubkey) {
+    let mut svm = Svm::start();
+    let governor = svm.funded(LAMPORTS_PER_SOL);
+    let alice = svm.funded(LAMPORTS_PER_SOL);
+    let mint = Keypair::new();
+    let start_ts = svm.svm.get_sysvar::<Clock>().unix_timestamp;
+    let ix = seka::initialize(
+        &svm.payer(),
+        &mint.pubkey(),
+        &seka::associated_token_address(&alice.pubkey(), &mint.pubkey()),
+        governor.pubkey(),
+        alice.pubkey(),
+        start_ts,
+        SEKA_DECIMALS,
+        100,
+    );
+    let sent = svm.send(&[ix], &[&mint]);
+    match bench {
+        Some(bench) => bench.record("seka/initialize", sent),
+        None => {
+            sent.ok();
+        }
+    }
+    (svm, governor, alice, mint.pubkey())
+}
+
+/// Root of `leaves` deltas (the first for `owner`) with a bitmap of at least
+/// `bitmap_len` bytes, up to `MAX_BITMAP_LEN`, then the claim of leaf 0.
+fn claim_cost(leaves: u32, bitmap_len: u32, owner: Pubkey) -> (Svm, Sent, Pubkey) {
+    let (mut svm, governor, _, mint) = seka_setup(None);
+    let tree = MerkleTree::new(
+        1,
+        (0..leaves)
+            .map(|i| Leaf {
+                owner: if i == 0 { owner } else { Pubkey::new_unique() },
+                delta_points: if i == 0 { 100 } else { 0 },
+            })
+            .collect(),
+    )
+    .unwrap();
+    let len = tree.claims_bitmap_len().min(MAX_BITMAP_LEN).max(bitmap_len);
+    let ix = seka::set_cycle_root(
+        &governor.pubkey(),
+        1,
+        tree.root(),
+        tree.total_points_declared().unwrap(),
+        len,
+        tree.leaf_count().min(len * 8),
+        tree.format(),
+    );
+    svm.send(&[ix], &[&governor]).ok();
+    let proof = tree.proof(0).unwrap();
+    let ix = seka::claim_karma(
+        &svm.payer(),
+        proof.owner,
+        1,
+        proof.delta_points,
+        proof.leaf_index,
+        proof.proof,
+    );
+    let sent = svm.send(&[ix], &[]);
+    (svm, sent, mint)
+}
+
+fn seka(bench: &mut Bench) {
+    let (mut svm, governor, alice, mint) = seka_setup(Some(&mut *bench));
+    let ix = seka::join_network(&alice.pubkey(), &mint, alice.pubkey());
+    bench.record("seka/join_network", svm.send(&[ix], &[&alice]));
+    let ix = seka::deactivate_membership(&governor.pubkey(), alice.pubkey());
+    bench.record("seka/deactivate_membership", svm.send(&[ix], &[&governor]));
+
+    for len in BITMAP_LENS {
+        let (mut svm, governor, _, _) = seka_setup(None);
+        let ix = seka::set_cycle_root(&governor.pubkey(), 1, [1; 32], 0, len, 1, LeafFormat::Solana);
+        bench.record(
+            format!("seka/set_cycle_root/bitmap={len}"),
+            svm.send(&[ix], &[&governor]),
+        );
+    }
+
+    for depth in PROOF_DEPTHS {
+        let (_, sent, _) = claim_cost(1 << depth, 0, Pubkey::new_unique());
+        bench.record(format!("seka/claim_karma/depth={depth}"), sent);
+    }
+    for len in BITMAP_LENS {
+        let (_, sent, _) = claim_cost(2, len, Pubkey::new_unique());
+        bench.record(format!("seka/claim_karma/bitmap={len}"), sent);
+    }
+
+    let owner = Pubkey::new_unique();
+    let (mut svm, _, mint) = claim_cost(2, 0, owner);
+    let ata = svm.create_ata(&owner, &mint);
+    let ix = seka::convert_points_to_tokens(owner, &mint, &ata, 1);
+    bench.record("seka/convert_points_to_tokens", svm.send(&[ix], &[]));
+}
+
+// ----------------------------------------------------------------------------
+// sentinel
+// ----------------------------------------------------------------------------
+
+fn sentinel(bench: &mut Bench) {
+    let token = 10u64.pow(SENTINEL_DECIMALS as u32);
+    let mut svm = Svm::start().with_sentinel();
+    let authority = svm.funded(10 * LAMPORTS_PER_SOL);
+    let alice = svm.funded(LAMPORTS_PER_SOL);
+    let bob = svm.funded(LAMPORTS_PER_SOL);
+
+    let mint = Keypair::new();
+    let state = sentinel::state_pda();
+    svm.create_mint(&mint, &authority.pubkey(), Some(&state), SENTINEL_DECIMALS);
+    let ix = sentinel_ix::initialize(&authority.pubkey(), &mint.pubkey());
+    bench.record("sentinel/initialize", svm.send(&[ix], &[&authority]));
+    let mint = mint.pubkey();
+
+    for user in [&alice, &bob] {
+        svm.distribute_sentinel(&authority, &mint, &user.pubkey(), 2_000 * token);
+    }
+    let ix = sentinel_ix::join(&alice.pubkey(), &mint);
+    bench.record("sentinel/join_network", svm.send(&[ix], &[&alice]));
+    svm.send(&[sentinel_ix::join(&bob.pubkey(), &mint)], &[&bob])
+        .ok();
+
+    let nft_mint = Keypair::new();
+    svm.create_mint(&nft_mint, &alice.pubkey(), None, 0);
+    let ix = sentinel_ix::mint_nft(&alice.pubkey(), &nft_mint.pubkey(), &mint);
+    bench.record("sentinel/mint_nft", svm.send(&[ix], &[&alice]));
+    let post = sentinel::post_pda(&nft_mint.pubkey());
+    let ix = sentinel_ix::like(&bob.pubkey(), &post, &alice.pubkey());
+    bench.record("sentinel/like_nft", svm.send(&[ix], &[&bob]));
+
+    let state: sentinel::State = svm.account(&state);
+    let ix = sentinel_ix::enable_crank(&authority.pubkey());
+    svm.send(&[ix], &[&authority]).ok();
+    svm.advance_clock(state.cycle_seconds);
+    let ix = sentinel_ix::crank_finalize(&svm.payer(), 0);
+    bench.record("sentinel/crank_finalize_cycle", svm.send(&[ix], &[]));
+    let ix = sentinel_ix::crank_reset(&alice.pubkey(), 0, &mint);
+    bench.record("sentinel/crank_reset_karma", svm.send(&[ix], &[]));
+
+    for peers in FINALIZE_PEERS {
+        let mut svm = Svm::start().with_sentinel();
+        let authority = svm.funded(10 * LAMPORTS_PER_SOL);
+        let mint = svm.init_sentinel(&authority);
+        let rewards: Vec<(Pubkey, u64)> = (0..peers)
+            .map(|i| {
+                let wallet = Pubkey::new_unique();
+                svm.create_ata(&wallet, &mint);
+                (wallet, i as u64 + 1)
+            })
+            .collect();
+        svm.advance_clock(state.cycle_seconds);
+        let ix = sentinel_ix::finalize_cycle(&authority.pubkey(), &svm.payer(), &mint, 0, &rewards);
+        bench.record(
+            format!("sentinel/finalize_cycle/peers={peers}"),
+            svm.send(&[ix], &[&authority]),
+        );
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Baseline
+// ----------------------------------------------------------------------------
+
+fn read_baseline(path: &Path) -> BTreeMap<String, u64> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, units) = line
+                .split_once(char::is_whitespace)
+                .unwrap_or_else(|| panic!("malformed baseline line {line:?}"));
+            let units = units.trim().parse().expect("baseline units");
+            (name.to_string(), units)
+        })
+        .collect()
+}
+
+fn write_baseline(path: &Path, units: &BTreeMap<String, u64>) {
+    let mut text = String::from("# Compute units per instruction; rewrite with CU_BLESS=1\n");
+    for (name, units) in units {
+        text.push_str(&format!("{name} {units}\n"));
+    }
+    std::fs::write(path, text).expect("writing baseline");
+}
+
+fn main() -> ExitCode {
+    let mut bench = Bench::default();
+    seka(&mut bench);
+    sentinel(&mut bench);
+
+    let path = Path::new(BASELINE);
+    if std::env::var_os("CU_BLESS").is_some() {
+        write_baseline(path, &bench.units);
+        println!("wrote {}", path.display());
+        return ExitCode::SUCCESS;
+    }
+
+    let tolerance: u64 = std::env::var("CU_TOLERANCE_PCT")
+        .map(|v| v.parse().expect("CU_TOLERANCE_PCT"))
+        .unwrap_or(2);
+    let baseline = read_baseline(path);
+    let mut failed = 0;
+    for (name, &units) in &bench.units {
+        match baseline.get(name) {
+            None => {
+                println!("FAIL {name}: {units} CU, no baseline");
+                failed += 1;
+            }
+            Some(&base) if units * 100 > base * (100 + tolerance) => {
+                println!("FAIL {name}: {units} CU, baseline {base} (+{tolerance}% allowed)");
+                failed += 1;
+            }
+            Some(&base) if units < base => {
+                println!("     {name}: {units} CU, down from {base}; bless to lock it in");
+            }
+            Some(_) => {}
+        }
+    }
+    if failed > 0 {
+        println!(
+            "{failed} regressions; if intended, rerun with CU_BLESS=1 and commit the baseline"
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
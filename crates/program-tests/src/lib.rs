@@ -0,0 +1,330 @@
+//! solana-program-test harness for the seka and sentinel programs.
+//!
+//! Both programs run natively in the test validator through their real entrypoints, next
+//! to the SPL Token, Token-2022 and associated token programs it bundles. Instructions
+//! are built with `sentinelkarma-sdk`, so every flow also checks the SDK's account order
+//! against the programs'.
+//!
+//! [`svm::Svm`] is the fast counterpart on LiteSVM, for property tests that need a
+//! transaction per generated case.
+//! The `compute_units` bench uses it too, to hold instruction costs to a baseline. Both
+//! build their fixtures with [`setup`].
+//!
+//! Not a workspace member, as it compiles the validator runtime; run it with
+//! `cargo test --manifest-path crates/program-tests/Cargo.toml`.
+
+use anchor_lang::prelude::AccountInfo;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sentinelkarma_sdk::{seka, sentinel, Versioned};
+use setup::Step;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use std::sync::Once;
+
+pub mod sentinel_ix;
+pub mod setup;
+pub mod svm;
+
+pub use seka_program::ErrorCode as SekaError;
+pub use sentinel::SentinelError;
+
+pub const SEKA_DECIMALS: u8 = 6;
+pub const SENTINEL_DECIMALS: u8 = 9;
+
+// Anchor's `entry` ties the slice and `AccountInfo` lifetimes together, which the
+// `processor!` signature cannot; leaking the slice (test process only) satisfies it.
+fn seka_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    seka_program::entry(program_id, accounts, data)
+}
+
+fn sentinel_entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    sentinel::entry(program_id, accounts, data)
+}
+
+/// solana-program-test's syscall stubs, but with `sol_log_data` logged as well. Natively
+/// run programs otherwise print their `emit!` events to stdout, out of the transaction
+/// logs the events are decoded from.
+struct LogDataStubs(Box<dyn SyscallStubs>);
+
+/// Prefix `sol_log` puts on the `Program data: ` lines of [`LogDataStubs`].
+const STUB_DATA_PREFIX: &str = "Program log: Program data: ";
+
+impl SyscallStubs for LogDataStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<_> = fields.iter().map(|f| STANDARD.encode(f)).collect();
+        self.0
+            .sol_log(&format!("Program data: {}", fields.join(" ")))
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+/// Outcome of one transaction, with its logs either way.
+pub struct Sent {
+    pub result: Result<(), TransactionError>,
+    pub logs: Vec<String>,
+    /// Compute units consumed across all its instructions
+    pub compute_units: u64,
+}
+
+impl Sent {
+    /// Logs of a successful transaction; panics with the logs otherwise.
+    #[track_caller]
+    pub fn ok(self) -> Vec<String> {
+        if let Err(e) = &self.result {
+            panic!("transaction failed: {e}\n{}", self.logs.join("\n"));
+        }
+        self.logs
+    }
+
+    /// Program error code of a failed instruction (Anchor codes start at 6000).
+    pub fn custom_error(&self) -> Option<u32> {
+        match self.result {
+            Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+                Some(code)
+            }
+            _ => None,
+        }
+    }
+
+    #[track_caller]
+    pub fn expect_error(&self, code: impl Into<u32>) {
+        let code = code.into();
+        assert_eq!(
+            self.custom_error(),
+            Some(code),
+            "expected error {code}, got {:?}\n{}",
+            self.result,
+            self.logs.join("\n")
+        );
+    }
+}
+
+pub struct Env {
+    pub ctx: ProgramTestContext,
+}
+
+impl Env {
+    pub async fn start() -> Self {
+        let mut test = ProgramTest::default();
+        test.prefer_bpf(false);
+        test.add_program("seka", seka::ID, processor!(seka_entry));
+        test.add_program("sentinel", sentinel::ID, processor!(sentinel_entry));
+        let ctx = test.start_with_context().await;
+        // The first start installs solana-program-test's stubs, process-wide
+        static LOG_DATA: Once = Once::new();
+        LOG_DATA.call_once(|| {
+            struct Swapping;
+            impl SyscallStubs for Swapping {}
+            let stubs = program_stubs::set_syscall_stubs(Box::new(Swapping));
+            program_stubs::set_syscall_stubs(Box::new(LogDataStubs(stubs)));
+        });
+        Self { ctx }
+    }
+
+    /// Fee payer of every transaction sent through [`Env::send`].
+    pub fn payer(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    /// Sends `ixs` paid by the context payer; `signers` sign as well.
+    pub async fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Sent {
+        let mut all = vec![&self.ctx.payer];
+        all.extend(signers.iter().copied());
+        let blockhash = self
+            .ctx
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .expect("blockhash");
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&self.payer()), &all, blockhash);
+        let outcome = self
+            .ctx
+            .banks_client
+            .process_transaction_with_metadata(tx)
+            .await
+            .expect("banks client");
+        let (logs, compute_units) = outcome
+            .metadata
+            .map(|m| (m.log_messages, m.compute_units_consumed))
+            .unwrap_or_default();
+        let logs = logs
+            .into_iter()
+            .map(|line| match line.strip_prefix(STUB_DATA_PREFIX) {
+                Some(data) => format!("Program data: {data}"),
+                None => line,
+            })
+            .collect();
+        Sent {
+            result: outcome.result,
+            logs,
+            compute_units,
+        }
+    }
+
+    pub async fn data(&mut self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .expect("banks client")
+            .map(|a| a.data)
+    }
+
+    pub async fn account<T: AccountDeserialize + Versioned>(&mut self, address: &Pubkey) -> T {
+        setup::account(self.data(address).await)
+    }
+
+    pub async fn zero_copy<T: ZeroCopy + Versioned>(&mut self, address: &Pubkey) -> T {
+        setup::zero_copy(self.data(address).await)
+    }
+
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        setup::token_balance(self.data(token_account).await)
+    }
+
+    /// A new keypair holding `lamports` from the payer.
+    pub async fn funded(&mut self, lamports: u64) -> Keypair {
+        let keypair = Keypair::new();
+        let ix = system_instruction::transfer(&self.payer(), &keypair.pubkey(), lamports);
+        self.send(&[ix], &[]).await.ok();
+        keypair
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.ctx.banks_client.get_sysvar().await.expect("clock")
+    }
+
+    /// Moves to the next slot with the clock `seconds` ahead.
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let slot = self.clock().await.slot;
+        self.ctx.warp_to_slot(slot + 1).expect("warp");
+        let mut clock = self.clock().await;
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    // ------------------------------------------------------------------------
+    // Fixtures
+    // ------------------------------------------------------------------------
+
+    /// Sends `steps` in order; panics with the logs of the first that fails.
+    pub async fn run<'a>(&mut self, steps: impl IntoIterator<Item = Step<'a>>) {
+        for (ixs, signers) in steps {
+            self.send(&ixs, &signers).await.ok();
+        }
+    }
+
+    /// [`setup::create_mint`]
+    pub async fn create_mint(
+        &mut self,
+        mint: &Keypair,
+        authority: &Pubkey,
+        freeze_authority: Option<&Pubkey>,
+        decimals: u8,
+    ) {
+        let step = setup::create_mint(&self.payer(), mint, authority, freeze_authority, decimals);
+        self.run([step]).await;
+    }
+
+    /// [`setup::create_ata`]
+    pub async fn create_ata(&mut self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let (step, ata) = setup::create_ata(&self.payer(), wallet, mint);
+        self.run([step]).await;
+        ata
+    }
+
+    /// [`setup::init_seka`] starting now; returns the new SEKA mint.
+    pub async fn init_seka(
+        &mut self,
+        governor: &Pubkey,
+        recipient: &Pubkey,
+        airdrop_whole_tokens: u64,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let start_ts = self.clock().await.unix_timestamp;
+        let step = setup::init_seka(
+            &self.payer(),
+            &mint,
+            governor,
+            recipient,
+            start_ts,
+            airdrop_whole_tokens,
+        );
+        self.run([step]).await;
+        mint.pubkey()
+    }
+
+    /// [`setup::init_sentinel`]; returns the new mint.
+    pub async fn init_sentinel(&mut self, authority: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        self.run(setup::init_sentinel(&self.payer(), authority, &mint))
+            .await;
+        mint.pubkey()
+    }
+
+    /// [`setup::distribute_sentinel`]
+    pub async fn distribute_sentinel(
+        &mut self,
+        authority: &Keypair,
+        mint: &Pubkey,
+        wallet: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let (steps, recipient) =
+            setup::distribute_sentinel(&self.payer(), authority, mint, wallet, amount);
+        self.run(steps).await;
+        recipient
+    }
+}
@@ -0,0 +1,257 @@
+//! sentinel instructions for the flows the harnesses drive, SPL Token mint and no
+//! optional accounts unless asked for.
+
+use anchor_spl::token::spl_token;
+use sentinelkarma_sdk::seka::ASSOCIATED_TOKEN_PROGRAM_ID;
+use sentinelkarma_sdk::sentinel::{
+    self, accounts, instruction, ContentAddress, ContentScheme, Genesis, ParamsUpdate,
+};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{system_program, sysvar};
+
+fn ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    sentinel::associated_token_address(wallet, mint, &spl_token::ID)
+}
+
+/// `initialize` on `mint`, with the genesis supply held by the treasury.
+pub fn initialize(authority: &Pubkey, mint: &Pubkey) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::Initialize {
+            authority: *authority,
+            state: sentinel::state_pda(),
+            sentinel_mint: *mint,
+            treasury_vault: treasury,
+            vesting: None,
+            vesting_sentinel_ata: None,
+            treasury_sentinel_ata: ata(&treasury, mint),
+            token_program: spl_token::ID,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::Initialize {
+            genesis: Genesis::Treasury,
+        },
+    )
+}
+
+pub fn update_params(authority: &Pubkey, params: ParamsUpdate) -> Instruction {
+    sentinel::instruction(
+        accounts::UpdateParams {
+            authority: *authority,
+            state: sentinel::state_pda(),
+        },
+        instruction::UpdateParams { params },
+    )
+}
+
+/// `update_params` turning on the keyless `crank_finalize_cycle`.
+pub fn enable_crank(authority: &Pubkey) -> Instruction {
+    let params = ParamsUpdate {
+        crank_finalize: Some(true),
+        ..ParamsUpdate::default()
+    };
+    update_params(authority, params)
+}
+
+/// `distribute_initial_supply` of `amount` to the token account `recipient`.
+pub fn distribute(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::DistributeInitialSupply {
+            authority: *authority,
+            state: sentinel::state_pda(),
+            treasury_vault: treasury,
+            treasury_sentinel_ata: ata(&treasury, mint),
+            recipient: *recipient,
+            sentinel_mint: *mint,
+            token_program: spl_token::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::DistributeInitialSupply {
+            amount,
+            reason_code: 0,
+        },
+    )
+}
+
+pub fn join(user: &Pubkey, mint: &Pubkey) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::JoinNetwork {
+            user: *user,
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            user_sentinel_ata: ata(user, mint),
+            sentinel_mint: *mint,
+            treasury_vault: treasury,
+            treasury_sentinel_ata: ata(&treasury, mint),
+            invite: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::JoinNetwork {},
+    )
+}
+
+/// `user`'s first `mint_nft`, of an existing 0-decimal mint whose authority is `user`.
+pub fn mint_nft(user: &Pubkey, nft_mint: &Pubkey, mint: &Pubkey) -> Instruction {
+    let post = sentinel::post_pda(nft_mint);
+    let mut cid = vec![0x01, 0x55, 0x12, 32];
+    cid.extend_from_slice(&[9; 32]);
+    sentinel::instruction(
+        accounts::MintNft {
+            user: *user,
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            nft_mint: *nft_mint,
+            user_nft_ata: ata(user, nft_mint),
+            post,
+            author_index: sentinel::author_index_pda(user),
+            user_post: sentinel::user_post_pda(user, 0),
+            sentinel_mint: *mint,
+            user_sentinel_ata: ata(user, mint),
+            bond_escrow: sentinel::bond_escrow_pda(&post),
+            post_commit: None,
+            storage_provider: None,
+            instructions: None,
+            tag_counter: None,
+            token_program: spl_token::ID,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MintNft {
+            hash: [1; 32],
+            content: ContentAddress {
+                scheme: ContentScheme::Ipfs,
+                addr: cid,
+            },
+            soulbound: false,
+            royalty_bps: 0,
+            tag: [0; sentinel::TAG_LEN],
+        },
+    )
+}
+
+/// `like_nft` of an untagged post.
+pub fn like(liker: &Pubkey, post: &Pubkey, author: &Pubkey) -> Instruction {
+    sentinel::instruction(
+        accounts::LikeNft {
+            liker: *liker,
+            state: sentinel::state_pda(),
+            like: sentinel::like_pda(liker, post),
+            post: *post,
+            like_pair: sentinel::like_pair_pda(liker, author),
+            top_posts: None,
+            liked_peer: sentinel::peer_pda(author),
+            liker_peer: sentinel::peer_pda(liker),
+            tag_counter: None,
+            tag_karma: None,
+            system_program: system_program::ID,
+        },
+        instruction::LikeNft {},
+    )
+}
+
+pub fn crank_finalize(payer: &Pubkey, cycle_index: u64) -> Instruction {
+    sentinel::instruction(
+        accounts::CrankFinalizeCycle {
+            payer: *payer,
+            state: sentinel::state_pda(),
+            top_posts: None,
+            cycle_record: sentinel::cycle_record_pda(cycle_index),
+            system_program: system_program::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::CrankFinalizeCycle {},
+    )
+}
+
+/// `crank_reset_karma` of `user`, whose karma was earned in `karma_cycle`.
+pub fn crank_reset(user: &Pubkey, karma_cycle: u64, mint: &Pubkey) -> Instruction {
+    sentinel::instruction(
+        accounts::CrankResetKarma {
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            karma_history: sentinel::karma_history_pda(user),
+            cycle_record: Some(sentinel::cycle_record_pda(karma_cycle)),
+            sentinel_mint: *mint,
+            peer_sentinel_ata: ata(user, mint),
+            token_program: spl_token::ID,
+            seka_config: None,
+            seka_mint_authority: None,
+            seka_program: None,
+        },
+        instruction::CrankResetKarma {},
+    )
+}
+
+/// Authority `finalize_cycle` of `cycle_index`, paying each `(wallet, karma)` to its ATA.
+pub fn finalize_cycle(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    cycle_index: u64,
+    rewards: &[(Pubkey, u64)],
+) -> Instruction {
+    let mut ix = sentinel::instruction(
+        accounts::FinalizeCycle {
+            authority: *authority,
+            payer: *payer,
+            state: sentinel::state_pda(),
+            sentinel_mint: *mint,
+            top_posts: None,
+            cycle_record: sentinel::cycle_record_pda(cycle_index),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            seka_config: None,
+            seka_mint_authority: None,
+            seka_program: None,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::FinalizeCycle {
+            peers: rewards.iter().map(|(wallet, _)| *wallet).collect(),
+            karmas: rewards.iter().map(|(_, karma)| *karma).collect(),
+        },
+    );
+    ix.accounts.extend(
+        rewards
+            .iter()
+            .map(|(wallet, _)| AccountMeta::new(ata(wallet, mint), false)),
+    );
+    ix
+}
+
+/// `migrate_accounts` of the state and `accounts`, `payer` covering the rent.
+pub fn migrate_accounts(authority: &Pubkey, payer: &Pubkey, accounts: &[Pubkey]) -> Instruction {
+    let mut ix = sentinel::instruction(
+        accounts::MigrateAccounts {
+            authority: *authority,
+            payer: *payer,
+            state: sentinel::state_pda(),
+            system_program: system_program::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::MigrateAccounts {},
+    );
+    ix.accounts.extend(
+        accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    ix
+}
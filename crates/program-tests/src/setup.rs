@@ -0,0 +1,136 @@
+//! Fixture transactions and account decoding shared by [`crate::Env`] and
+//! [`crate::svm::Svm`].
+//!
+//! The two harnesses differ only in how they send transactions and read accounts and
+//! sysvars, so fixtures are built here as [`Step`]s for either to send in order. Both
+//! banks start with the default rent, which mint accounts are funded from.
+
+use crate::{sentinel_ix, SEKA_DECIMALS, SENTINEL_DECIMALS};
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use anchor_spl::token::spl_token;
+use anchor_spl::token::TokenAccount;
+use sentinelkarma_sdk::{decode_account, decode_zero_copy, seka, sentinel, Versioned};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+
+/// One fixture transaction: its instructions and the keypairs signing next to the payer.
+pub type Step<'a> = (Vec<Instruction>, Vec<&'a Keypair>);
+
+/// Account creation and `initialize_mint2` of an SPL Token mint at `mint`.
+pub fn create_mint<'a>(
+    payer: &Pubkey,
+    mint: &'a Keypair,
+    authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+) -> Step<'a> {
+    let len = spl_token::state::Mint::LEN;
+    let lamports = Rent::default().minimum_balance(len);
+    let ixs = vec![
+        system_instruction::create_account(
+            payer,
+            &mint.pubkey(),
+            lamports,
+            len as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint.pubkey(),
+            authority,
+            freeze_authority,
+            decimals,
+        )
+        .expect("initialize_mint2"),
+    ];
+    (ixs, vec![mint])
+}
+
+/// `wallet`'s SPL Token associated account of `mint`, created if missing; returns the
+/// account too.
+pub fn create_ata(payer: &Pubkey, wallet: &Pubkey, mint: &Pubkey) -> (Step<'static>, Pubkey) {
+    let ix =
+        sentinel::create_associated_token_account_idempotent(payer, wallet, mint, &spl_token::ID);
+    let ata = sentinel::associated_token_address(wallet, mint, &spl_token::ID);
+    ((vec![ix], vec![]), ata)
+}
+
+/// seka `initialize` of the SEKA mint `mint` with `governor`, starting at `start_ts` and
+/// airdropping `airdrop_whole_tokens` to `recipient`.
+pub fn init_seka<'a>(
+    payer: &Pubkey,
+    mint: &'a Keypair,
+    governor: &Pubkey,
+    recipient: &Pubkey,
+    start_ts: i64,
+    airdrop_whole_tokens: u64,
+) -> Step<'a> {
+    let ix = seka::initialize(
+        payer,
+        &mint.pubkey(),
+        &seka::associated_token_address(recipient, &mint.pubkey()),
+        *governor,
+        *recipient,
+        start_ts,
+        SEKA_DECIMALS,
+        airdrop_whole_tokens,
+    );
+    (vec![ix], vec![mint])
+}
+
+/// sentinel `initialize` on a fresh SPL Token mint at `mint`, with the genesis supply
+/// held by the treasury.
+pub fn init_sentinel<'a>(
+    payer: &Pubkey,
+    authority: &'a Keypair,
+    mint: &'a Keypair,
+) -> [Step<'a>; 2] {
+    let state = sentinel::state_pda();
+    let ix = sentinel_ix::initialize(&authority.pubkey(), &mint.pubkey());
+    [
+        create_mint(
+            payer,
+            mint,
+            &authority.pubkey(),
+            Some(&state),
+            SENTINEL_DECIMALS,
+        ),
+        (vec![ix], vec![authority]),
+    ]
+}
+
+/// Pays `amount` of the genesis supply to `wallet`'s ATA, created first; returns the
+/// ATA too.
+pub fn distribute_sentinel<'a>(
+    payer: &Pubkey,
+    authority: &'a Keypair,
+    mint: &Pubkey,
+    wallet: &Pubkey,
+    amount: u64,
+) -> ([Step<'a>; 2], Pubkey) {
+    let (create, recipient) = create_ata(payer, wallet, mint);
+    let ix = sentinel_ix::distribute(&authority.pubkey(), mint, &recipient, amount);
+    ([create, (vec![ix], vec![authority])], recipient)
+}
+
+#[track_caller]
+pub fn account<T: AccountDeserialize + Versioned>(data: Option<Vec<u8>>) -> T {
+    decode_account(&data.expect("account exists")).expect("account decodes")
+}
+
+#[track_caller]
+pub fn zero_copy<T: ZeroCopy + Versioned>(data: Option<Vec<u8>>) -> T {
+    decode_zero_copy(&data.expect("account exists")).expect("account decodes")
+}
+
+#[track_caller]
+pub fn token_balance(data: Option<Vec<u8>>) -> u64 {
+    let data = data.expect("account exists");
+    TokenAccount::try_deserialize(&mut &data[..])
+        .expect("token account decodes")
+        .amount
+}
@@ -0,0 +1,208 @@
+//! LiteSVM harness for property tests that send thousands of transactions.
+//!
+//! Unlike [`crate::Env`] it runs the deployed SBF builds, so build the programs first
+//! (`anchor build` at the root and in `sentinel/`). Binaries are looked up in
+//! `SBF_OUT_DIR` when set, then in each workspace's `target/deploy`. There is no bank
+//! or runtime to start, so a fresh [`Svm`] per proptest case is cheap.
+
+use crate::setup::{self, Step};
+use crate::Sent;
+use anchor_lang::AccountDeserialize;
+use litesvm::LiteSVM;
+use sentinelkarma_sdk::{seka, sentinel, Versioned};
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// `<name>.so` from `SBF_OUT_DIR`, else from `<workspace>/target/deploy`.
+fn program_bytes(name: &str, workspace: &str) -> Vec<u8> {
+    let file = format!("{name}.so");
+    let path = match std::env::var_os("SBF_OUT_DIR") {
+        Some(dir) => PathBuf::from(dir).join(&file),
+        None => [
+            env!("CARGO_MANIFEST_DIR"),
+            "../..",
+            workspace,
+            "target/deploy",
+            &file,
+        ]
+        .iter()
+        .collect(),
+    };
+    std::fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "reading {}: {e}; build the programs with `anchor build` first",
+            path.display()
+        )
+    })
+}
+
+fn seka_so() -> &'static [u8] {
+    static BYTES: OnceLock<Vec<u8>> = OnceLock::new();
+    BYTES.get_or_init(|| program_bytes("seka", "."))
+}
+
+fn sentinel_so() -> &'static [u8] {
+    static BYTES: OnceLock<Vec<u8>> = OnceLock::new();
+    BYTES.get_or_init(|| program_bytes("sentinel", "sentinel"))
+}
+
+pub struct Svm {
+    pub svm: LiteSVM,
+    pub payer: Keypair,
+}
+
+impl Svm {
+    /// LiteSVM with the SPL programs and seka loaded, and a payer holding 100 SOL.
+    pub fn start() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program(seka::ID, seka_so());
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .expect("airdrop");
+        Self { svm, payer }
+    }
+
+    /// Also loads sentinel.
+    pub fn with_sentinel(mut self) -> Self {
+        self.svm.add_program(sentinel::ID, sentinel_so());
+        self
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    /// Sends `ixs` paid by the payer; `signers` sign as well.
+    ///
+    /// LiteSVM rejects a transaction it has already processed, as a validator would; call
+    /// [`Svm::expire_blockhash`] before resending an identical one.
+    pub fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Sent {
+        let mut all = vec![&self.payer];
+        all.extend(signers.iter().copied());
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&self.payer()),
+            &all,
+            self.svm.latest_blockhash(),
+        );
+        match self.svm.send_transaction(tx) {
+            Ok(meta) => Sent {
+                result: Ok(()),
+                logs: meta.logs,
+                compute_units: meta.compute_units_consumed,
+            },
+            Err(failed) => Sent {
+                result: Err(failed.err),
+                logs: failed.meta.logs,
+                compute_units: failed.meta.compute_units_consumed,
+            },
+        }
+    }
+
+    pub fn expire_blockhash(&mut self) {
+        self.svm.expire_blockhash();
+    }
+
+    pub fn data(&self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.svm.get_account(address).map(|a| a.data)
+    }
+
+    pub fn account<T: AccountDeserialize + Versioned>(&self, address: &Pubkey) -> T {
+        setup::account(self.data(address))
+    }
+
+    pub fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        setup::token_balance(self.data(token_account))
+    }
+
+    /// A new keypair holding `lamports`.
+    pub fn funded(&mut self, lamports: u64) -> Keypair {
+        let keypair = Keypair::new();
+        self.svm
+            .airdrop(&keypair.pubkey(), lamports)
+            .expect("airdrop");
+        keypair
+    }
+
+    /// Moves the clock `seconds` ahead.
+    pub fn advance_clock(&mut self, seconds: i64) {
+        let mut clock: Clock = self.svm.get_sysvar();
+        clock.slot += 1;
+        clock.unix_timestamp += seconds;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Sends `steps` in order; panics with the logs of the first that fails.
+    pub fn run<'a>(&mut self, steps: impl IntoIterator<Item = Step<'a>>) {
+        for (ixs, signers) in steps {
+            self.send(&ixs, &signers).ok();
+        }
+    }
+
+    /// [`setup::create_mint`]
+    pub fn create_mint(
+        &mut self,
+        mint: &Keypair,
+        authority: &Pubkey,
+        freeze_authority: Option<&Pubkey>,
+        decimals: u8,
+    ) {
+        let step = setup::create_mint(&self.payer(), mint, authority, freeze_authority, decimals);
+        self.run([step]);
+    }
+
+    /// [`setup::create_ata`]
+    pub fn create_ata(&mut self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let (step, ata) = setup::create_ata(&self.payer(), wallet, mint);
+        self.run([step]);
+        ata
+    }
+
+    /// [`setup::init_seka`] starting now; returns the new SEKA mint.
+    pub fn init_seka(
+        &mut self,
+        governor: &Pubkey,
+        recipient: &Pubkey,
+        airdrop_whole_tokens: u64,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let start_ts = self.svm.get_sysvar::<Clock>().unix_timestamp;
+        let step = setup::init_seka(
+            &self.payer(),
+            &mint,
+            governor,
+            recipient,
+            start_ts,
+            airdrop_whole_tokens,
+        );
+        self.run([step]);
+        mint.pubkey()
+    }
+
+    /// [`setup::init_sentinel`]; returns the new mint. Needs [`Svm::with_sentinel`].
+    pub fn init_sentinel(&mut self, authority: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        self.run(setup::init_sentinel(&self.payer(), authority, &mint));
+        mint.pubkey()
+    }
+
+    /// [`setup::distribute_sentinel`]
+    pub fn distribute_sentinel(
+        &mut self,
+        authority: &Keypair,
+        mint: &Pubkey,
+        wallet: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let (steps, recipient) =
+            setup::distribute_sentinel(&self.payer(), authority, mint, wallet, amount);
+        self.run(steps);
+        recipient
+    }
+}
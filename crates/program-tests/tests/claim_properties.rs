@@ -0,0 +1,156 @@
+//! `claim_karma` under random trees, claim orders and proof corruptions, on LiteSVM.
+
+use proptest::prelude::*;
+use seka_merkle::{Leaf, MerkleTree, Proof};
+use sentinelkarma_program_tests::svm::Svm;
+use sentinelkarma_program_tests::{SekaError, Sent};
+use sentinelkarma_sdk::seka;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+/// Default `per_peer_cycle_cap`; larger deltas are rejected before the proof is checked.
+const PER_PEER_CAP: i32 = 100;
+
+/// Fresh seka with the root of `deltas` (one new owner each) set for cycle 1.
+fn with_root(deltas: &[i32]) -> (Svm, MerkleTree) {
+    let mut svm = Svm::start();
+    let governor = svm.funded(LAMPORTS_PER_SOL);
+    svm.init_seka(&governor.pubkey(), &Pubkey::new_unique(), 0);
+    let leaves = deltas
+        .iter()
+        .map(|&delta_points| Leaf {
+            owner: Pubkey::new_unique(),
+            delta_points,
+        })
+        .collect();
+    let tree = MerkleTree::new(1, leaves).unwrap();
+    let ix = seka::set_cycle_root(
+        &governor.pubkey(),
+        1,
+        tree.root(),
+        tree.total_points_declared().unwrap(),
+        tree.claims_bitmap_len(),
+        tree.leaf_count(),
+        tree.format(),
+    );
+    svm.send(&[ix], &[&governor]).ok();
+    (svm, tree)
+}
+
+fn claim(svm: &mut Svm, proof: &Proof) -> Sent {
+    let ix = seka::claim_karma(
+        &svm.payer(),
+        proof.owner,
+        proof.cycle_index,
+        proof.delta_points,
+        proof.leaf_index,
+        proof.proof.clone(),
+    );
+    svm.send(&[ix], &[])
+}
+
+#[derive(Clone, Debug)]
+enum Corruption {
+    FlipBit { sibling: usize, bit: usize },
+    Delta(i32),
+    LeafIndex(u32),
+    Owner,
+    DropSibling,
+    ExtraSibling([u8; 32]),
+}
+
+impl Corruption {
+    /// `None` when it would leave the claim unchanged.
+    fn apply(&self, proof: &Proof) -> Option<Proof> {
+        let mut bad = proof.clone();
+        match *self {
+            Corruption::FlipBit { sibling, bit } => {
+                let node = bad.proof.get_mut(sibling % proof.proof.len().max(1))?;
+                node[bit / 8] ^= 1 << (bit % 8);
+            }
+            Corruption::Delta(delta) if delta != proof.delta_points => bad.delta_points = delta,
+            Corruption::LeafIndex(index) if index != proof.leaf_index => bad.leaf_index = index,
+            Corruption::Owner => bad.owner = Pubkey::new_unique(),
+            Corruption::DropSibling => {
+                bad.proof.pop()?;
+            }
+            Corruption::ExtraSibling(node) => bad.proof.push(node),
+            _ => return None,
+        }
+        Some(bad)
+    }
+}
+
+fn corruption() -> impl Strategy<Value = Corruption> {
+    prop_oneof![
+        (any::<usize>(), 0..256usize)
+            .prop_map(|(sibling, bit)| Corruption::FlipBit { sibling, bit }),
+        (-PER_PEER_CAP..=PER_PEER_CAP).prop_map(Corruption::Delta),
+        (0..64u32).prop_map(Corruption::LeafIndex),
+        Just(Corruption::Owner),
+        Just(Corruption::DropSibling),
+        any::<[u8; 32]>().prop_map(Corruption::ExtraSibling),
+    ]
+}
+
+/// Deltas of one cycle with a shuffled claim order.
+fn cycle() -> impl Strategy<Value = (Vec<i32>, Vec<usize>)> {
+    prop::collection::vec(-PER_PEER_CAP..=PER_PEER_CAP, 1..48).prop_flat_map(|deltas| {
+        let order = (0..deltas.len()).collect::<Vec<_>>();
+        (Just(deltas), Just(order).prop_shuffle())
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    /// Every leaf claims exactly once in any order, each into its own ledger.
+    #[test]
+    fn claims_succeed_in_any_order((deltas, order) in cycle()) {
+        let (mut svm, tree) = with_root(&deltas);
+        let proofs: Vec<Proof> = tree.proofs().collect();
+
+        for &i in &order {
+            claim(&mut svm, &proofs[i]).ok();
+        }
+
+        let cycle: seka::CycleState = svm.account(&seka::cycle_state_pda(1));
+        for proof in &proofs {
+            prop_assert!(cycle.is_claimed(proof.leaf_index));
+            let ledger: seka::PeerLedger = svm.account(&seka::peer_pda(&proof.owner));
+            prop_assert_eq!(ledger.points, i64::from(proof.delta_points));
+            prop_assert_eq!(ledger.last_cycle_claimed, 1);
+        }
+        // No bit beyond the last leaf
+        prop_assert!(!cycle.is_claimed(deltas.len() as u32));
+
+        svm.expire_blockhash();
+        let replay = &proofs[order[0]];
+        claim(&mut svm, replay).expect_error(SekaError::ClaimAlreadyProcessed);
+    }
+
+    /// A corrupted claim is rejected without a trace; the genuine one still succeeds.
+    #[test]
+    fn corrupted_proofs_are_rejected(
+        deltas in prop::collection::vec(-PER_PEER_CAP..=PER_PEER_CAP, 1..48),
+        leaf in any::<prop::sample::Index>(),
+        corruptions in prop::collection::vec(corruption(), 1..8),
+    ) {
+        let (mut svm, tree) = with_root(&deltas);
+        let proof = tree.proof(leaf.index(deltas.len()) as u32).unwrap();
+
+        for corruption in &corruptions {
+            let Some(bad) = corruption.apply(&proof) else { continue };
+            svm.expire_blockhash();
+            claim(&mut svm, &bad).expect_error(SekaError::InvalidMerkleProof);
+            prop_assert!(svm.data(&seka::peer_pda(&bad.owner)).is_none());
+        }
+        let cycle: seka::CycleState = svm.account(&seka::cycle_state_pda(1));
+        prop_assert!(cycle.claims_bitmap.iter().all(|&b| b == 0));
+
+        claim(&mut svm, &proof).ok();
+        let ledger: seka::PeerLedger = svm.account(&seka::peer_pda(&proof.owner));
+        prop_assert_eq!(ledger.points, i64::from(proof.delta_points));
+    }
+}
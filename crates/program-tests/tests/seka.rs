@@ -0,0 +1,489 @@
+use seka_merkle::{Leaf, LeafFormat, MerkleTree, Proof};
+use sentinelkarma_program_tests::{Env, SekaError, SEKA_DECIMALS};
+use sentinelkarma_sdk::seka::{self, SekaEvent};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+const SEKA: u64 = 10u64.pow(SEKA_DECIMALS as u32);
+
+fn claim(payer: &Pubkey, proof: &Proof) -> Instruction {
+    seka::claim_karma(
+        payer,
+        proof.owner,
+        proof.cycle_index,
+        proof.delta_points,
+        proof.leaf_index,
+        proof.proof.clone(),
+    )
+}
+
+fn set_root(governor: &Pubkey, tree: &MerkleTree) -> Instruction {
+    seka::set_cycle_root(
+        governor,
+        tree.cycle_index(),
+        tree.root(),
+        tree.total_points_declared().unwrap(),
+        tree.claims_bitmap_len(),
+        tree.leaf_count(),
+        tree.format(),
+    )
+}
+
+struct Setup {
+    env: Env,
+    governor: Keypair,
+    alice: Keypair,
+    mint: Pubkey,
+}
+
+/// seka initialized with 100 SEKA airdropped to alice.
+async fn setup() -> Setup {
+    let mut env = Env::start().await;
+    let governor = env.funded(LAMPORTS_PER_SOL).await;
+    let alice = env.funded(LAMPORTS_PER_SOL).await;
+    let mint = env
+        .init_seka(&governor.pubkey(), &alice.pubkey(), 100)
+        .await;
+    Setup {
+        env,
+        governor,
+        alice,
+        mint,
+    }
+}
+
+#[tokio::test]
+async fn initialize_join_set_root_claim_convert() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        mint,
+    } = setup().await;
+    let alice_ata = seka::associated_token_address(&alice.pubkey(), &mint);
+    let treasury_ata = seka::associated_token_address(&seka::treasury_pda(), &mint);
+
+    let config: seka::GlobalConfig = env.account(&seka::config_pda()).await;
+    assert_eq!(config.governor, governor.pubkey());
+    assert_eq!(config.mint, mint);
+    assert!(config.airdrop_done);
+    assert_eq!(env.token_balance(&alice_ata).await, 100 * SEKA);
+
+    // Joining moves the 10 SEKA join cost into the treasury
+    let logs = env
+        .send(
+            &[seka::join_network(&alice.pubkey(), &mint, alice.pubkey())],
+            &[&alice],
+        )
+        .await
+        .ok();
+    assert!(matches!(
+        SekaEvent::from_logs(&logs)[..],
+        [SekaEvent::Joined(ref e)] if e.member == alice.pubkey()
+    ));
+    let membership: seka::Membership = env.account(&seka::membership_pda(&alice.pubkey())).await;
+    assert!(membership.active);
+    assert_eq!(env.token_balance(&alice_ata).await, 90 * SEKA);
+    assert_eq!(env.token_balance(&treasury_ata).await, 10 * SEKA);
+
+    let bob = Pubkey::new_unique();
+    let tree = MerkleTree::new(
+        1,
+        vec![
+            Leaf {
+                owner: alice.pubkey(),
+                delta_points: 100,
+            },
+            Leaf {
+                owner: bob,
+                delta_points: -5,
+            },
+        ],
+    )
+    .unwrap();
+    let logs = env
+        .send(&[set_root(&governor.pubkey(), &tree)], &[&governor])
+        .await
+        .ok();
+    assert!(matches!(
+        SekaEvent::from_logs(&logs)[..],
+        [SekaEvent::CycleRootSet(ref e)] if e.cycle_index == 1 && e.total_points_declared == 100
+    ));
+
+    // Anyone may submit a claim; the payer funds the ledger
+    let payer = env.payer();
+    let proof = tree.proof_for(&alice.pubkey()).unwrap();
+    let logs = env.send(&[claim(&payer, &proof)], &[]).await.ok();
+    assert!(matches!(
+        SekaEvent::from_logs(&logs)[..],
+        [SekaEvent::KarmaClaimed(ref e)] if e.owner == alice.pubkey() && e.new_points == 100
+    ));
+    let ledger: seka::PeerLedger = env.account(&seka::peer_pda(&alice.pubkey())).await;
+    assert_eq!((ledger.points, ledger.last_cycle_claimed), (100, 1));
+    let cycle: seka::CycleState = env.account(&seka::cycle_state_pda(1)).await;
+    assert!(cycle.is_claimed(proof.leaf_index));
+    assert!(!cycle.is_claimed(1));
+
+    // 100 points buy 1 SEKA at the default ratio
+    let logs = env
+        .send(
+            &[seka::convert_points_to_tokens(
+                alice.pubkey(),
+                &mint,
+                &alice_ata,
+                1,
+            )],
+            &[],
+        )
+        .await
+        .ok();
+    assert!(matches!(
+        SekaEvent::from_logs(&logs)[..],
+        [SekaEvent::PointsConverted(ref e)] if e.tokens_minted == 1 && e.points_spent == 100
+    ));
+    let ledger: seka::PeerLedger = env.account(&seka::peer_pda(&alice.pubkey())).await;
+    assert_eq!(ledger.points, 0);
+    assert_eq!(env.token_balance(&alice_ata).await, 91 * SEKA);
+}
+
+#[tokio::test]
+async fn set_cycle_root_errors() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        ..
+    } = setup().await;
+    let tree = MerkleTree::new(
+        1,
+        vec![Leaf {
+            owner: alice.pubkey(),
+            delta_points: 10,
+        }],
+    )
+    .unwrap();
+
+    env.send(&[set_root(&alice.pubkey(), &tree)], &[&alice])
+        .await
+        .expect_error(SekaError::Unauthorized);
+
+    let over_cap = seka::set_cycle_root(
+        &governor.pubkey(),
+        2,
+        tree.root(),
+        10_001,
+        1,
+        1,
+        LeafFormat::Solana,
+    );
+    env.send(&[over_cap], &[&governor])
+        .await
+        .expect_error(SekaError::TotalPointsExceedsCycleCap);
+    // A bitmap byte holds 8 claims
+    for leaf_count in [0, 9] {
+        let bad = seka::set_cycle_root(
+            &governor.pubkey(),
+            2,
+            tree.root(),
+            10,
+            1,
+            leaf_count,
+            LeafFormat::Solana,
+        );
+        env.send(&[bad], &[&governor])
+            .await
+            .expect_error(SekaError::InvalidLeafCount);
+    }
+
+    env.send(&[set_root(&governor.pubkey(), &tree)], &[&governor])
+        .await
+        .ok();
+    // `init` refuses a second root for the same cycle
+    let again = seka::set_cycle_root(&governor.pubkey(), 1, [7; 32], 10, 1, 1, LeafFormat::Solana);
+    assert!(env.send(&[again], &[&governor]).await.result.is_err());
+}
+
+#[tokio::test]
+async fn claim_karma_errors() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        mint,
+    } = setup().await;
+    let bob = env.funded(LAMPORTS_PER_SOL).await;
+    let tree = MerkleTree::new(
+        1,
+        vec![
+            Leaf {
+                owner: alice.pubkey(),
+                delta_points: 40,
+            },
+            Leaf {
+                owner: bob.pubkey(),
+                delta_points: 101,
+            },
+        ],
+    )
+    .unwrap();
+    env.send(&[set_root(&governor.pubkey(), &tree)], &[&governor])
+        .await
+        .ok();
+    let payer = env.payer();
+
+    let good = tree.proof_for(&alice.pubkey()).unwrap();
+
+    let mut wrong_delta = good.clone();
+    wrong_delta.delta_points = 41;
+    env.send(&[claim(&payer, &wrong_delta)], &[])
+        .await
+        .expect_error(SekaError::InvalidMerkleProof);
+
+    let mut corrupted = good.clone();
+    corrupted.proof[0][0] ^= 1;
+    env.send(&[claim(&payer, &corrupted)], &[])
+        .await
+        .expect_error(SekaError::InvalidMerkleProof);
+
+    let mut wrong_index = good.clone();
+    wrong_index.leaf_index = 1;
+    env.send(&[claim(&payer, &wrong_index)], &[])
+        .await
+        .expect_error(SekaError::InvalidMerkleProof);
+
+    // Committed in the root, but over the default per-peer cap of 100
+    let over_cap = tree.proof_for(&bob.pubkey()).unwrap();
+    env.send(&[claim(&payer, &over_cap)], &[])
+        .await
+        .expect_error(SekaError::DeltaExceedsPerPeerCap);
+
+    env.send(&[claim(&payer, &good)], &[]).await.ok();
+    // Replayed by another submitter, so it is not deduplicated as the same transaction
+    env.send(&[claim(&bob.pubkey(), &good)], &[&bob])
+        .await
+        .expect_error(SekaError::ClaimAlreadyProcessed);
+
+    // 40 points are not enough for 1 SEKA at 100 points each
+    let alice_ata = seka::associated_token_address(&alice.pubkey(), &mint);
+    env.send(
+        &[seka::convert_points_to_tokens(
+            alice.pubkey(),
+            &mint,
+            &alice_ata,
+            1,
+        )],
+        &[],
+    )
+    .await
+    .expect_error(SekaError::InsufficientPointsToConvert);
+}
+
+#[tokio::test]
+async fn evm_format_root_claims() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        ..
+    } = setup().await;
+    let bob = Pubkey::new_unique();
+    let leaves = vec![
+        Leaf {
+            owner: alice.pubkey(),
+            delta_points: 30,
+        },
+        Leaf {
+            owner: bob,
+            delta_points: 20,
+        },
+    ];
+    let tree = MerkleTree::with_format(LeafFormat::Evm, 1, leaves.clone()).unwrap();
+
+    // The format byte is the last argument; the program only takes ones it knows
+    let mut unknown = set_root(&governor.pubkey(), &tree);
+    *unknown.data.last_mut().unwrap() = 2;
+    env.send(&[unknown], &[&governor])
+        .await
+        .expect_error(SekaError::InvalidLeafFormat);
+
+    env.send(&[set_root(&governor.pubkey(), &tree)], &[&governor])
+        .await
+        .ok();
+    let cycle: seka::CycleState = env.account(&seka::cycle_state_pda(1)).await;
+    assert_eq!(cycle.leaf_format(), Some(LeafFormat::Evm));
+    assert_eq!(cycle.version, 2);
+
+    // A proof of the same dataset in the Solana format does not verify
+    let payer = env.payer();
+    let solana = MerkleTree::new(1, leaves).unwrap();
+    env.send(&[claim(&payer, &solana.proof_for(&alice.pubkey()).unwrap())], &[])
+        .await
+        .expect_error(SekaError::InvalidMerkleProof);
+
+    env.send(&[claim(&payer, &tree.proof_for(&alice.pubkey()).unwrap())], &[])
+        .await
+        .ok();
+    let ledger: seka::PeerLedger = env.account(&seka::peer_pda(&alice.pubkey())).await;
+    assert_eq!(ledger.points, 30);
+}
+
+#[tokio::test]
+async fn close_cycle_once_claimed_or_expired() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        ..
+    } = setup().await;
+    let bob = env.funded(LAMPORTS_PER_SOL).await;
+    let leaves = vec![
+        Leaf {
+            owner: alice.pubkey(),
+            delta_points: 10,
+        },
+        Leaf {
+            owner: bob.pubkey(),
+            delta_points: 20,
+        },
+    ];
+    let claimed = MerkleTree::new(1, leaves.clone()).unwrap();
+    let open = MerkleTree::new(2, leaves).unwrap();
+    env.send(
+        &[
+            set_root(&governor.pubkey(), &claimed),
+            set_root(&governor.pubkey(), &open),
+        ],
+        &[&governor],
+    )
+    .await
+    .ok();
+    let payer = env.payer();
+    env.send(&[claim(&payer, &claimed.proof_for(&alice.pubkey()).unwrap())], &[])
+        .await
+        .ok();
+
+    env.send(&[seka::close_cycle(&governor.pubkey(), 1)], &[&governor])
+        .await
+        .expect_error(SekaError::CycleStillClaimable);
+    env.send(&[seka::close_cycle(&alice.pubkey(), 1)], &[&alice])
+        .await
+        .expect_error(SekaError::Unauthorized);
+
+    env.send(&[claim(&payer, &claimed.proof_for(&bob.pubkey()).unwrap())], &[])
+        .await
+        .ok();
+    let logs = env
+        .send(&[seka::close_cycle(&governor.pubkey(), 1)], &[&governor])
+        .await
+        .ok();
+    assert!(matches!(
+        SekaEvent::from_logs(&logs)[..],
+        [SekaEvent::CycleClosed(ref e)] if e.cycle_index == 1 && e.fully_claimed && e.lamports > 0
+    ));
+    assert!(env.data(&seka::cycle_state_pda(1)).await.is_none());
+
+    // Nothing claimed from cycle 2, so it closes only once its claim window has passed
+    let config: seka::GlobalConfig = env.account(&seka::config_pda()).await;
+    let window = (seka::CLAIM_WINDOW_CYCLES + 2) as i64 * config.cycle_secs as i64;
+    env.advance_clock(window).await;
+    env.send(&[seka::close_cycle(&governor.pubkey(), 2)], &[&governor])
+        .await
+        .ok();
+    assert!(env.data(&seka::cycle_state_pda(2)).await.is_none());
+}
+
+#[tokio::test]
+async fn closed_cycles_cannot_be_republished() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        ..
+    } = setup().await;
+    let leaves = vec![Leaf {
+        owner: alice.pubkey(),
+        delta_points: 10,
+    }];
+    let tree = MerkleTree::new(2, leaves.clone()).unwrap();
+    env.send(&[set_root(&governor.pubkey(), &tree)], &[&governor])
+        .await
+        .ok();
+    let payer = env.payer();
+    env.send(&[claim(&payer, &tree.proof_for(&alice.pubkey()).unwrap())], &[])
+        .await
+        .ok();
+    env.send(&[seka::close_cycle(&governor.pubkey(), 2)], &[&governor])
+        .await
+        .ok();
+    let config: seka::GlobalConfig = env.account(&seka::config_pda()).await;
+    assert_eq!(config.first_open_cycle, 3);
+
+    // Republishing would hand out alice's claim a second time, and cycle 1 was skipped
+    for cycle_index in [2, 1] {
+        let tree = MerkleTree::new(cycle_index, leaves.clone()).unwrap();
+        env.send(&[set_root(&governor.pubkey(), &tree)], &[&governor])
+            .await
+            .expect_error(SekaError::CycleAlreadyClosed);
+    }
+    let next = MerkleTree::new(3, leaves).unwrap();
+    env.send(&[set_root(&governor.pubkey(), &next)], &[&governor])
+        .await
+        .ok();
+}
+
+#[tokio::test]
+async fn membership_deactivation() {
+    let Setup {
+        mut env,
+        governor,
+        alice,
+        mint,
+    } = setup().await;
+    env.send(
+        &[seka::join_network(&alice.pubkey(), &mint, alice.pubkey())],
+        &[&alice],
+    )
+    .await
+    .ok();
+
+    env.send(
+        &[seka::deactivate_membership(&alice.pubkey(), alice.pubkey())],
+        &[&alice],
+    )
+    .await
+    .expect_error(SekaError::Unauthorized);
+
+    let logs = env
+        .send(
+            &[seka::deactivate_membership(
+                &governor.pubkey(),
+                alice.pubkey(),
+            )],
+            &[&governor],
+        )
+        .await
+        .ok();
+    assert!(matches!(
+        SekaEvent::from_logs(&logs)[..],
+        [SekaEvent::MembershipDeactivated(ref e)] if e.actor == governor.pubkey()
+    ));
+    let membership: seka::Membership = env.account(&seka::membership_pda(&alice.pubkey())).await;
+    assert!(!membership.active);
+
+    // Rejoining reactivates the same account; leaving again needs no governor. The
+    // rejoin repeats the first join byte for byte, so it needs a newer blockhash
+    env.ctx.get_new_latest_blockhash().await.unwrap();
+    env.send(
+        &[seka::join_network(&alice.pubkey(), &mint, alice.pubkey())],
+        &[&alice],
+    )
+    .await
+    .ok();
+    env.send(&[seka::deactivate_self(&alice.pubkey())], &[&alice])
+        .await
+        .ok();
+    let membership: seka::Membership = env.account(&seka::membership_pda(&alice.pubkey())).await;
+    assert!(!membership.active);
+}
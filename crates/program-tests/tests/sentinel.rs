@@ -0,0 +1,261 @@
+use anchor_lang::Discriminator;
+use anchor_spl::token::spl_token;
+use sentinelkarma_program_tests::sentinel_ix::{
+    crank_finalize, crank_reset, distribute, enable_crank, join, like, migrate_accounts, mint_nft,
+};
+use sentinelkarma_program_tests::{Env, SentinelError, SENTINEL_DECIMALS};
+use sentinelkarma_sdk::sentinel::{self, CycleRecord, PeerState, Post, State};
+use solana_sdk::account::Account;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+const SENTINEL: u64 = 10u64.pow(SENTINEL_DECIMALS as u32);
+
+#[tokio::test]
+async fn join_post_like_finalize() {
+    let mut env = Env::start().await;
+    let authority = env.funded(10 * LAMPORTS_PER_SOL).await;
+    let alice = env.funded(LAMPORTS_PER_SOL).await;
+    let bob = env.funded(LAMPORTS_PER_SOL).await;
+    let mint = env.init_sentinel(&authority).await;
+    let treasury_ata =
+        sentinel::associated_token_address(&sentinel::treasury_vault_pda(), &mint, &spl_token::ID);
+
+    // Genesis supply sits in the treasury; each join pays the default 1000 SENTINEL back
+    let mut atas = Vec::new();
+    for user in [&alice, &bob] {
+        let ata = env
+            .distribute_sentinel(&authority, &mint, &user.pubkey(), 2_000 * SENTINEL)
+            .await;
+        env.send(&[join(&user.pubkey(), &mint)], &[user]).await.ok();
+        assert_eq!(env.token_balance(&ata).await, 1_000 * SENTINEL);
+        atas.push(ata);
+    }
+    let state: State = env.account(&sentinel::state_pda()).await;
+    assert_eq!(state.total_peers, 2);
+    assert_eq!(
+        env.token_balance(&treasury_ata).await,
+        100_000 * SENTINEL - 2 * 2_000 * SENTINEL + 2 * 1_000 * SENTINEL
+    );
+
+    let nft_mint = Keypair::new();
+    env.create_mint(&nft_mint, &alice.pubkey(), None, 0).await;
+    env.send(
+        &[mint_nft(&alice.pubkey(), &nft_mint.pubkey(), &mint)],
+        &[&alice],
+    )
+    .await
+    .ok();
+    let post_address = sentinel::post_pda(&nft_mint.pubkey());
+    let post_account: Post = env.zero_copy(&post_address).await;
+    assert_eq!(post_account.owner, alice.pubkey());
+    assert_eq!(post_account.cycle_index, 0);
+
+    env.send(
+        &[like(&alice.pubkey(), &post_address, &alice.pubkey())],
+        &[&alice],
+    )
+    .await
+    .expect_error(SentinelError::CannotLikeSelf);
+    env.send(
+        &[like(&bob.pubkey(), &post_address, &alice.pubkey())],
+        &[&bob],
+    )
+    .await
+    .ok();
+    // The like account already exists; retried on a new blockhash so it is a new transaction
+    env.ctx.get_new_latest_blockhash().await.unwrap();
+    let again = env
+        .send(
+            &[like(&bob.pubkey(), &post_address, &alice.pubkey())],
+            &[&bob],
+        )
+        .await;
+    assert!(again.result.is_err());
+
+    let post_account: Post = env.zero_copy(&post_address).await;
+    assert_eq!(post_account.likes, 1);
+    let peer: PeerState = env.zero_copy(&sentinel::peer_pda(&alice.pubkey())).await;
+    assert_eq!((peer.karma, peer.karma_cycle), (1, 0));
+    let state: State = env.account(&sentinel::state_pda()).await;
+    assert_eq!(state.cycle_karma, 1);
+
+    // The keyless crank is off until the authority turns it on
+    let payer = env.payer();
+    env.send(&[crank_finalize(&payer, 0)], &[])
+        .await
+        .expect_error(SentinelError::CrankFinalizeDisabled);
+    env.send(&[enable_crank(&authority.pubkey())], &[&authority])
+        .await
+        .ok();
+
+    // Then it is a no-op until the cycle has run its length
+    env.ctx.get_new_latest_blockhash().await.unwrap();
+    env.send(&[crank_finalize(&payer, 0)], &[]).await.ok();
+    let state: State = env.account(&sentinel::state_pda()).await;
+    assert_eq!(state.cycle_index, 0);
+
+    env.advance_clock(state.cycle_seconds).await;
+    env.send(&[crank_finalize(&payer, 0)], &[]).await.ok();
+    let state: State = env.account(&sentinel::state_pda()).await;
+    assert_eq!((state.cycle_index, state.cycle_karma), (1, 0));
+    let record: CycleRecord = env.account(&sentinel::cycle_record_pda(0)).await;
+    assert_eq!(record.total_karma, 1);
+    assert!(record.onchain_rewards);
+
+    // Sole earner: the whole cycle reward, capped at 10% of it
+    env.send(&[crank_reset(&alice.pubkey(), 0, &mint)], &[])
+        .await
+        .ok();
+    let reward = state.cycle_reward_total / 10;
+    assert_eq!(env.token_balance(&atas[0]).await, 1_000 * SENTINEL + reward);
+    let peer: PeerState = env.zero_copy(&sentinel::peer_pda(&alice.pubkey())).await;
+    assert_eq!((peer.karma, peer.karma_cycle), (0, 1));
+    let record: CycleRecord = env.account(&sentinel::cycle_record_pda(0)).await;
+    assert_eq!((record.total_minted, record.peers_rewarded), (reward, 1));
+    assert_eq!(record.top_recipient, alice.pubkey());
+
+    // Bob earned nothing; settling only moves his karma cycle forward
+    env.send(&[crank_reset(&bob.pubkey(), 0, &mint)], &[])
+        .await
+        .ok();
+    assert_eq!(env.token_balance(&atas[1]).await, 1_000 * SENTINEL);
+}
+
+#[tokio::test]
+async fn join_and_post_errors() {
+    let mut env = Env::start().await;
+    let authority = env.funded(10 * LAMPORTS_PER_SOL).await;
+    let alice = env.funded(LAMPORTS_PER_SOL).await;
+    let mint = env.init_sentinel(&authority).await;
+
+    // Only the authority may pay out genesis supply
+    let ata = env.create_ata(&alice.pubkey(), &mint).await;
+    let steal = distribute(&alice.pubkey(), &mint, &ata, SENTINEL);
+    env.send(&[steal], &[&alice])
+        .await
+        .expect_error(SentinelError::Unauthorized);
+
+    // Short of the join cost: the token transfer fails and no peer is created
+    env.distribute_sentinel(&authority, &mint, &alice.pubkey(), 999 * SENTINEL)
+        .await;
+    let short = env.send(&[join(&alice.pubkey(), &mint)], &[&alice]).await;
+    assert!(short.result.is_err());
+    assert!(env
+        .data(&sentinel::peer_pda(&alice.pubkey()))
+        .await
+        .is_none());
+
+    // Posting needs an active peer
+    let nft_mint = Keypair::new();
+    env.create_mint(&nft_mint, &alice.pubkey(), None, 0).await;
+    let not_peer = env
+        .send(
+            &[mint_nft(&alice.pubkey(), &nft_mint.pubkey(), &mint)],
+            &[&alice],
+        )
+        .await;
+    assert!(not_peer.result.is_err());
+}
+
+/// Replaces `address` with a rent-exempt sentinel account holding `data`.
+async fn set_sentinel_account(env: &mut Env, address: &Pubkey, data: Vec<u8>) {
+    let rent = env.ctx.banks_client.get_rent().await.unwrap();
+    let account = Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: sentinel::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.ctx.set_account(address, &account.into());
+}
+
+#[tokio::test]
+async fn baseline_accounts_migrate_and_stay_usable() {
+    let mut env = Env::start().await;
+    let authority = env.funded(10 * LAMPORTS_PER_SOL).await;
+    let alice = env.funded(LAMPORTS_PER_SOL).await;
+    let bob = env.funded(LAMPORTS_PER_SOL).await;
+    let mint = env.init_sentinel(&authority).await;
+    let mut atas = Vec::new();
+    for user in [&alice, &bob] {
+        let ata = env
+            .distribute_sentinel(&authority, &mint, &user.pubkey(), 2_000 * SENTINEL)
+            .await;
+        atas.push(ata);
+    }
+
+    // The state and a peer as first deployed: `{ authority, sentinel_mint,
+    // treasury_vault, cycle_start_ts, cycle_index }` and `{ user, active, karma }`
+    let state_address = sentinel::state_pda();
+    let state: State = env.account(&state_address).await;
+    let mut baseline = State::DISCRIMINATOR.to_vec();
+    baseline.extend_from_slice(state.authority.as_ref());
+    baseline.extend_from_slice(state.sentinel_mint.as_ref());
+    baseline.extend_from_slice(state.treasury_vault.as_ref());
+    baseline.extend_from_slice(&state.cycle_start_ts.to_le_bytes());
+    baseline.extend_from_slice(&state.cycle_index.to_le_bytes());
+    assert_eq!(baseline.len(), 8 + 112);
+    set_sentinel_account(&mut env, &state_address, baseline).await;
+    let carol = Pubkey::new_unique();
+    let peer_address = sentinel::peer_pda(&carol);
+    let mut peer = PeerState::DISCRIMINATOR.to_vec();
+    peer.extend_from_slice(carol.as_ref());
+    peer.push(1);
+    peer.extend_from_slice(&7u64.to_le_bytes());
+    set_sentinel_account(&mut env, &peer_address, peer).await;
+
+    let payer = env.payer();
+    env.send(
+        &[migrate_accounts(
+            &authority.pubkey(),
+            &payer,
+            &[peer_address],
+        )],
+        &[&authority],
+    )
+    .await
+    .ok();
+    let migrated: State = env.account(&state_address).await;
+    assert_eq!(migrated.bump, state.bump);
+    assert_eq!(migrated.join_cost_base, state.join_cost_base);
+    assert_eq!(migrated.moderator, authority.pubkey());
+    let peer: PeerState = env.zero_copy(&peer_address).await;
+    assert_eq!((peer.user, peer.active, peer.karma), (carol, 1, 7));
+
+    // Joining checks the state's bump and charges the default join cost
+    for (user, ata) in [&alice, &bob].into_iter().zip(&atas) {
+        env.send(&[join(&user.pubkey(), &mint)], &[user]).await.ok();
+        assert_eq!(env.token_balance(ata).await, 1_000 * SENTINEL);
+    }
+    let nft_mint = Keypair::new();
+    env.create_mint(&nft_mint, &alice.pubkey(), None, 0).await;
+    env.send(
+        &[mint_nft(&alice.pubkey(), &nft_mint.pubkey(), &mint)],
+        &[&alice],
+    )
+    .await
+    .ok();
+    let post_address = sentinel::post_pda(&nft_mint.pubkey());
+    env.send(
+        &[like(&bob.pubkey(), &post_address, &alice.pubkey())],
+        &[&bob],
+    )
+    .await
+    .ok();
+
+    // The reward is minted with the state signing as mint authority
+    assert!(!migrated.crank_finalize);
+    env.send(&[enable_crank(&authority.pubkey())], &[&authority])
+        .await
+        .ok();
+    env.advance_clock(migrated.cycle_seconds).await;
+    env.send(&[crank_finalize(&payer, 0)], &[]).await.ok();
+    let before = env.token_balance(&atas[0]).await;
+    env.send(&[crank_reset(&alice.pubkey(), 0, &mint)], &[])
+        .await
+        .ok();
+    assert!(env.token_balance(&atas[0]).await > before);
+}
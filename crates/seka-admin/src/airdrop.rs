@@ -0,0 +1,328 @@
+//! `airdrop`: pays SEKA to every wallet in a CSV, a few per transaction.
+//!
+//! The program mints its genesis airdrop to a single recipient; wider distributions go
+//! out from there. Each transaction creates the recipients' ATAs (idempotently) and
+//! either transfers from the signer's ATA or, with `--issue`, mints through
+//! `issue_tokens` when the signer is the configured issuer.
+//!
+//! Progress is kept in a checkpoint file next to the CSV. A batch is recorded with its
+//! signature and blockhash expiry before it is sent, so a rerun after a crash first
+//! settles it: if it landed, its recipients are done; if its blockhash has expired
+//! without it landing, it is safe to send again. No recipient is ever paid twice.
+
+use crate::Ctx;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::Args;
+use sentinelkarma_sdk::{seka, sentinel};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Args)]
+pub struct AirdropArgs {
+    /// CSV with a `wallet,amount` header; amounts in SEKA, e.g. `12.5`
+    recipients: PathBuf,
+    /// Defaults to `<recipients>.checkpoint.json`
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Recipients per transaction; ten is the most that fits, eight with `--issue`
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u8).range(1..=10))]
+    batch_size: u8,
+    /// Transactions per second at most
+    #[arg(long, default_value_t = 2.0)]
+    rate: f64,
+    /// Mint through `issue_tokens` instead of transferring; the signer must be the issuer
+    #[arg(long)]
+    issue: bool,
+}
+
+#[derive(Deserialize)]
+struct Row {
+    wallet: String,
+    amount: String,
+}
+
+/// A batch sent but not known to have landed.
+#[derive(Serialize, Deserialize)]
+struct Pending {
+    signature: String,
+    last_valid_block_height: u64,
+    wallets: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    mint: String,
+    /// Wallet to the signature that paid it
+    done: BTreeMap<String, String>,
+    pending: Option<Pending>,
+}
+
+impl Checkpoint {
+    fn load(path: &Path, mint: &Pubkey) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self {
+                mint: mint.to_string(),
+                done: BTreeMap::new(),
+                pending: None,
+            });
+        }
+        let checkpoint: Self = serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))?;
+        ensure!(
+            checkpoint.mint == mint.to_string(),
+            "{} is for mint {}, not {mint}",
+            path.display(),
+            checkpoint.mint
+        );
+        Ok(checkpoint)
+    }
+
+    /// Writes through a temporary file, so a crash never leaves half a checkpoint.
+    fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+pub fn run(ctx: &Ctx, args: &AirdropArgs) -> Result<()> {
+    let config = ctx.fetch_config()?;
+    let signer = ctx.signer.pubkey();
+    if args.issue {
+        ensure!(
+            args.batch_size <= 8,
+            "--issue fits at most 8 recipients per transaction"
+        );
+        ensure!(
+            config.issuer == signer,
+            "--issue needs the configured issuer {}, not {signer}",
+            config.issuer
+        );
+    }
+    let recipients = read_recipients(&args.recipients, config.decimals)?;
+    let checkpoint_path = args.checkpoint.clone().unwrap_or_else(|| {
+        let mut name = args.recipients.clone().into_os_string();
+        name.push(".checkpoint.json");
+        PathBuf::from(name)
+    });
+    let mut checkpoint = Checkpoint::load(&checkpoint_path, &config.mint)?;
+
+    if !ctx.dry_run {
+        settle_pending(ctx, &mut checkpoint, &checkpoint_path)?;
+    }
+    let remaining: Vec<(Pubkey, u64)> = recipients
+        .into_iter()
+        .filter(|(wallet, _)| !checkpoint.done.contains_key(&wallet.to_string()))
+        .collect();
+    let total: u64 = remaining.iter().map(|&(_, amount)| amount).sum();
+    let batches: Vec<_> = remaining.chunks(args.batch_size as usize).collect();
+    println!(
+        "{} recipients left ({} done), {} SEKA in {} transactions",
+        remaining.len(),
+        checkpoint.done.len(),
+        format_amount(total, config.decimals),
+        batches.len()
+    );
+    if remaining.is_empty() {
+        return Ok(());
+    }
+
+    let source = seka::associated_token_address(&signer, &config.mint);
+    if !args.issue {
+        let balance: u64 = ctx
+            .client
+            .get_token_account_balance(&source)
+            .with_context(|| format!("reading source ATA {source}"))?
+            .amount
+            .parse()?;
+        ensure!(
+            balance >= total,
+            "{source} holds {} SEKA, short of {}",
+            format_amount(balance, config.decimals),
+            format_amount(total, config.decimals)
+        );
+    }
+
+    let interval = Duration::from_secs_f64(1.0 / args.rate.max(0.01));
+    for (i, batch) in batches.iter().enumerate() {
+        let ixs: Vec<_> = batch
+            .iter()
+            .flat_map(|&(wallet, amount)| {
+                let ata = seka::associated_token_address(&wallet, &config.mint);
+                let pay = if args.issue {
+                    seka::issue_tokens(&signer, &config.mint, &ata, amount)
+                } else {
+                    spl_token::instruction::transfer(
+                        &spl_token::ID,
+                        &source,
+                        &ata,
+                        &signer,
+                        &[],
+                        amount,
+                    )
+                    .expect("static token program id")
+                };
+                [
+                    sentinel::create_associated_token_account_idempotent(
+                        &signer,
+                        &wallet,
+                        &config.mint,
+                        &seka::TOKEN_PROGRAM_ID,
+                    ),
+                    pay,
+                ]
+            })
+            .collect();
+
+        if ctx.dry_run {
+            // Later batches differ only in recipients
+            println!("batch 1 of {}:", batches.len());
+            return ctx.submit(&ixs, &[]);
+        }
+
+        let started = Instant::now();
+        let (blockhash, last_valid_block_height) = ctx
+            .client
+            .get_latest_blockhash_with_commitment(ctx.client.commitment())?;
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&signer),
+            &[ctx.signer.as_ref()],
+            blockhash,
+        );
+        let wallets: Vec<String> = batch.iter().map(|(w, _)| w.to_string()).collect();
+        checkpoint.pending = Some(Pending {
+            signature: tx.signatures[0].to_string(),
+            last_valid_block_height,
+            wallets,
+        });
+        checkpoint.save(&checkpoint_path)?;
+
+        // An error may hide a transaction that landed anyway; settling decides
+        let sent = ctx.client.send_and_confirm_transaction(&tx);
+        settle_pending(ctx, &mut checkpoint, &checkpoint_path)?;
+        if !checkpoint.done.contains_key(&batch[0].0.to_string()) {
+            let reason = sent.err().map_or_else(String::new, |e| format!(" ({e})"));
+            bail!(
+                "batch {} did not land{reason}; rerun to resume from {}",
+                i + 1,
+                checkpoint_path.display()
+            );
+        }
+        println!(
+            "batch {}/{}: {} recipients, {}",
+            i + 1,
+            batches.len(),
+            batch.len(),
+            tx.signatures[0]
+        );
+
+        if let Some(wait) = interval.checked_sub(started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+    println!("done; {} recipients paid", checkpoint.done.len());
+    Ok(())
+}
+
+/// Resolves the pending batch: done if it landed, dropped once its blockhash has expired
+/// without landing, waited on otherwise. Fails if it landed with an error.
+fn settle_pending(ctx: &Ctx, checkpoint: &mut Checkpoint, path: &Path) -> Result<()> {
+    let Some(pending) = &checkpoint.pending else {
+        return Ok(());
+    };
+    let signature = Signature::from_str(&pending.signature)?;
+    loop {
+        let status = ctx.client.get_signature_statuses(&[signature])?.value[0].clone();
+        match status {
+            Some(status) if status.err.is_some() => {
+                bail!(
+                    "batch {signature} failed: {}; fix it, then remove `pending` from {}",
+                    status.err.unwrap(),
+                    path.display()
+                );
+            }
+            Some(status) if status.satisfies_commitment(ctx.client.commitment()) => {
+                for wallet in &pending.wallets {
+                    checkpoint
+                        .done
+                        .insert(wallet.clone(), pending.signature.clone());
+                }
+                break;
+            }
+            Some(_) => {}
+            None => {
+                if ctx.client.get_block_height()? > pending.last_valid_block_height {
+                    println!("batch {signature} expired unsent; it will be resent");
+                    break;
+                }
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    checkpoint.pending = None;
+    checkpoint.save(path)
+}
+
+/// Recipients in file order, amounts in base units. Duplicate wallets are an error, so
+/// a typo cannot pay someone twice.
+fn read_recipients(path: &Path, decimals: u8) -> Result<Vec<(Pubkey, u64)>> {
+    let mut reader =
+        csv::Reader::from_path(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut seen = HashSet::new();
+    let mut recipients = Vec::new();
+    for (i, row) in reader.deserialize::<Row>().enumerate() {
+        let line = i + 2;
+        let row = row.with_context(|| format!("{}:{line}", path.display()))?;
+        let wallet = Pubkey::from_str(row.wallet.trim())
+            .with_context(|| format!("{}:{line}: invalid wallet", path.display()))?;
+        let amount = parse_amount(&row.amount, decimals)
+            .with_context(|| format!("{}:{line}", path.display()))?;
+        ensure!(amount > 0, "{}:{line}: zero amount", path.display());
+        ensure!(
+            seen.insert(wallet),
+            "{}:{line}: {wallet} is listed twice",
+            path.display()
+        );
+        recipients.push((wallet, amount));
+    }
+    Ok(recipients)
+}
+
+/// `"12.5"` with 6 decimals is 12_500_000.
+fn parse_amount(s: &str, decimals: u8) -> Result<u64> {
+    let s = s.trim();
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    ensure!(
+        fraction.len() <= decimals as usize,
+        "amount {s:?} has more than {decimals} decimals"
+    );
+    let digits = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    ensure!(
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()),
+        "invalid amount {s:?}"
+    );
+    digits
+        .parse()
+        .map_err(|_| anyhow!("amount {s:?} is too large"))
+}
+
+fn format_amount(base_units: u64, decimals: u8) -> String {
+    let one = 10u64.pow(u32::from(decimals));
+    format!(
+        "{}.{:0width$}",
+        base_units / one,
+        base_units % one,
+        width = decimals as usize
+    )
+}
@@ -0,0 +1,349 @@
+//! `bootstrap`: a usable localnet or devnet from scratch, and a fixtures file describing it.
+//!
+//! Deploys seka and sentinel with `solana program deploy` unless they are already live,
+//! initializes both (sentinel with its genesis supply in the treasury), creates `members`
+//! wallets funded with SOL, SEKA and SENTINEL that have joined both networks, and
+//! publishes a cycle root with one leaf per member. Everything is written to the
+//! fixtures file read by `sentinelkarma_sdk::fixtures` (`fixtures` feature).
+
+use crate::Ctx;
+use anyhow::{bail, ensure, Context, Result};
+use clap::Args;
+use seka_merkle::{Leaf, MerkleTree};
+// `initialize` requires these decimals on the mint it is handed
+use sentinelkarma_core::seka::DEFAULT_DECIMALS as SEKA_DECIMALS;
+use sentinelkarma_core::sentinel::SENTINEL_DECIMALS;
+use sentinelkarma_sdk::fixtures::{self, Fixtures};
+use sentinelkarma_sdk::seka::{self, ASSOCIATED_TOKEN_PROGRAM_ID};
+use sentinelkarma_sdk::sentinel::{self, accounts, instruction, Genesis};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::sol_to_lamports;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::{system_instruction, system_program, sysvar};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Args)]
+pub struct BootstrapArgs {
+    /// Test wallets to create
+    #[arg(long, default_value_t = 4)]
+    members: u32,
+    /// SOL sent to each member
+    #[arg(long, default_value_t = 1.0)]
+    member_sol: f64,
+    /// Whole SEKA sent to each member, before its join cost
+    #[arg(long, default_value_t = 100)]
+    member_seka: u64,
+    /// Whole SENTINEL paid to each member from genesis, before its join cost
+    #[arg(long, default_value_t = 2_000)]
+    member_sentinel: u64,
+    /// SEKA airdropped to the signer by `initialize`, in whole tokens
+    #[arg(long, default_value_t = 10_000)]
+    airdrop: u64,
+    /// Cycle of the sample root
+    #[arg(long, default_value_t = 0)]
+    cycle: u64,
+    /// Holds seka.so and seka-keypair.json, as `anchor build` leaves them
+    #[arg(long, default_value = "target/deploy")]
+    seka_deploy_dir: PathBuf,
+    /// Holds sentinel.so and sentinel-keypair.json
+    #[arg(long, default_value = "sentinel/target/deploy")]
+    sentinel_deploy_dir: PathBuf,
+    #[arg(long, default_value = "fixtures.json")]
+    out: PathBuf,
+}
+
+pub fn run(ctx: &Ctx, url: &str, keypair: &str, args: &BootstrapArgs) -> Result<()> {
+    ensure!(
+        !ctx.dry_run,
+        "bootstrap builds on its own transactions; it cannot be simulated"
+    );
+    ensure!(
+        !keypair.starts_with("usb://"),
+        "bootstrap deploys through the solana CLI and needs a keypair file"
+    );
+    let payer = ctx.signer.pubkey();
+
+    deploy(ctx, url, keypair, "seka", &seka::ID, &args.seka_deploy_dir)?;
+    deploy(
+        ctx,
+        url,
+        keypair,
+        "sentinel",
+        &sentinel::ID,
+        &args.sentinel_deploy_dir,
+    )?;
+    for (name, address) in [
+        ("seka", seka::config_pda()),
+        ("sentinel", sentinel::state_pda()),
+    ] {
+        if ctx.client.get_account(&address).is_ok() {
+            bail!("{name} is already initialized at {address}; bootstrap a fresh validator");
+        }
+    }
+
+    println!("== seka initialize");
+    let seka_mint = Keypair::new();
+    let start_ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    let payer_seka_ata = seka::associated_token_address(&payer, &seka_mint.pubkey());
+    let ix = seka::initialize(
+        &payer,
+        &seka_mint.pubkey(),
+        &payer_seka_ata,
+        payer,
+        payer,
+        start_ts,
+        SEKA_DECIMALS,
+        args.airdrop,
+    );
+    ctx.submit(&[ix], &[&seka_mint])?;
+    let config = ctx.fetch_config()?;
+    let seka_mint = seka_mint.pubkey();
+
+    println!("== sentinel initialize");
+    let sentinel_mint = Keypair::new();
+    let rent = ctx
+        .client
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+    let mut ixs = vec![
+        system_instruction::create_account(
+            &payer,
+            &sentinel_mint.pubkey(),
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &sentinel_mint.pubkey(),
+            &payer,
+            Some(&sentinel::state_pda()),
+            SENTINEL_DECIMALS,
+        )?,
+    ];
+    ixs.push(sentinel_initialize(&payer, &sentinel_mint.pubkey()));
+    ctx.submit(&ixs, &[&sentinel_mint])?;
+    let sentinel_mint = sentinel_mint.pubkey();
+
+    let mut members = Vec::new();
+    for i in 0..args.members {
+        println!("== member {i}");
+        let member = Keypair::new();
+        let wallet = member.pubkey();
+        let seka_ata = seka::associated_token_address(&wallet, &seka_mint);
+        let sentinel_ata = sentinel_ata(&wallet, &sentinel_mint);
+        let ixs = [
+            system_instruction::transfer(&payer, &wallet, sol_to_lamports(args.member_sol)),
+            create_ata(&payer, &wallet, &seka_mint),
+            create_ata(&payer, &wallet, &sentinel_mint),
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &payer_seka_ata,
+                &seka_ata,
+                &payer,
+                &[],
+                args.member_seka * 10u64.pow(u32::from(config.decimals)),
+            )?,
+            sentinel_distribute(
+                &payer,
+                &sentinel_mint,
+                &sentinel_ata,
+                args.member_sentinel * 10u64.pow(u32::from(SENTINEL_DECIMALS)),
+            ),
+        ];
+        ctx.submit(&ixs, &[])?;
+        let joins = [
+            seka::join_network(&wallet, &seka_mint, wallet),
+            sentinel_join(&wallet, &sentinel_mint),
+        ];
+        ctx.submit(&joins, &[&member])?;
+        members.push(fixtures::Member {
+            pubkey: wallet,
+            secret_key: member.to_bytes().to_vec(),
+            seka_ata,
+            sentinel_ata,
+        });
+    }
+
+    println!("== cycle {} root", args.cycle);
+    let cap = config.per_peer_cycle_cap.max(0);
+    let leaves = members
+        .iter()
+        .zip(1..)
+        .map(|(member, i)| Leaf {
+            owner: member.pubkey,
+            delta_points: (10 * i).min(cap),
+        })
+        .collect();
+    let tree = MerkleTree::new(args.cycle, leaves)?;
+    let total_points_declared = tree.total_points_declared()?;
+    ensure!(
+        total_points_declared <= config.max_points_per_cycle,
+        "sample root declares {total_points_declared} points, over max_points_per_cycle; use fewer members"
+    );
+    let ix = seka::set_cycle_root(
+        &payer,
+        args.cycle,
+        tree.root(),
+        total_points_declared,
+        tree.claims_bitmap_len(),
+        tree.leaf_count(),
+        tree.format(),
+    );
+    ctx.submit(&[ix], &[])?;
+
+    let fixtures = Fixtures {
+        rpc_url: url.to_string(),
+        payer,
+        seka: fixtures::Seka {
+            program_id: seka::ID,
+            config: seka::config_pda(),
+            mint: seka_mint,
+            decimals: config.decimals,
+            treasury_ata: seka::associated_token_address(&seka::treasury_pda(), &seka_mint),
+        },
+        sentinel: fixtures::Sentinel {
+            program_id: sentinel::ID,
+            state: sentinel::state_pda(),
+            mint: sentinel_mint,
+            decimals: SENTINEL_DECIMALS,
+            treasury_ata: sentinel_ata(&sentinel::treasury_vault_pda(), &sentinel_mint),
+        },
+        members,
+        cycle: fixtures::Cycle {
+            cycle_index: args.cycle,
+            merkle_root: hex::encode(tree.root()),
+            total_points_declared,
+            claims_bitmap_len: tree.claims_bitmap_len(),
+            claims: tree
+                .proofs()
+                .map(|p| fixtures::Claim {
+                    owner: p.owner,
+                    delta_points: p.delta_points,
+                    leaf_index: p.leaf_index,
+                    proof: p.proof.iter().map(hex::encode).collect(),
+                })
+                .collect(),
+        },
+    };
+    fixtures.write(&args.out)?;
+    println!("wrote {}", args.out.display());
+    Ok(())
+}
+
+/// `solana program deploy` of `<dir>/<name>.so` at `<dir>/<name>-keypair.json`, unless a
+/// program is already live at `id`.
+fn deploy(ctx: &Ctx, url: &str, keypair: &str, name: &str, id: &Pubkey, dir: &Path) -> Result<()> {
+    if ctx.client.get_account(id).is_ok_and(|a| a.executable) {
+        println!("{name}: already deployed at {id}");
+        return Ok(());
+    }
+    let program_keypair = dir.join(format!("{name}-keypair.json"));
+    let address = read_keypair_file(&program_keypair)
+        .map_err(|e| anyhow::anyhow!("reading {}: {e}", program_keypair.display()))?
+        .pubkey();
+    // The program rejects every instruction when deployed away from its declare_id!
+    ensure!(
+        address == *id,
+        "{} is {address}, but {name} declares {id}; deploy with the matching keypair",
+        program_keypair.display()
+    );
+    println!("{name}: deploying to {id}");
+    let status = Command::new("solana")
+        .args([
+            "program",
+            "deploy",
+            "--url",
+            url,
+            "--keypair",
+            keypair,
+            "--program-id",
+        ])
+        .arg(&program_keypair)
+        .arg(dir.join(format!("{name}.so")))
+        .status()
+        .context("running `solana program deploy`; is the Solana CLI installed?")?;
+    ensure!(status.success(), "deploying {name} failed ({status})");
+    Ok(())
+}
+
+fn sentinel_ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    sentinel::associated_token_address(wallet, mint, &spl_token::ID)
+}
+
+fn create_ata(payer: &Pubkey, wallet: &Pubkey, mint: &Pubkey) -> Instruction {
+    sentinel::create_associated_token_account_idempotent(payer, wallet, mint, &spl_token::ID)
+}
+
+fn sentinel_initialize(authority: &Pubkey, mint: &Pubkey) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::Initialize {
+            authority: *authority,
+            state: sentinel::state_pda(),
+            sentinel_mint: *mint,
+            treasury_vault: treasury,
+            vesting: None,
+            vesting_sentinel_ata: None,
+            treasury_sentinel_ata: sentinel_ata(&treasury, mint),
+            token_program: spl_token::ID,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::Initialize {
+            genesis: Genesis::Treasury,
+        },
+    )
+}
+
+fn sentinel_distribute(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::DistributeInitialSupply {
+            authority: *authority,
+            state: sentinel::state_pda(),
+            treasury_vault: treasury,
+            treasury_sentinel_ata: sentinel_ata(&treasury, mint),
+            recipient: *recipient,
+            sentinel_mint: *mint,
+            token_program: spl_token::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::DistributeInitialSupply {
+            amount,
+            reason_code: 0,
+        },
+    )
+}
+
+fn sentinel_join(user: &Pubkey, mint: &Pubkey) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::JoinNetwork {
+            user: *user,
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            user_sentinel_ata: sentinel_ata(user, mint),
+            sentinel_mint: *mint,
+            treasury_vault: treasury,
+            treasury_sentinel_ata: sentinel_ata(&treasury, mint),
+            invite: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::JoinNetwork {},
+    )
+}
@@ -0,0 +1,145 @@
+//! `governance`: governor instructions for a DAO whose governor is an SPL Governance
+//! (Realms) treasury, printed instead of sent.
+//!
+//! Realms' "Execute Custom Instruction" takes base64 of the Borsh-encoded governance
+//! `InstructionData` (program id, account metas, data), which is what this prints for
+//! each instruction, alongside the metas in readable form for reviewers. The governor is
+//! the governance's native treasury PDA, which signs when the proposal executes. It also
+//! pays the rent of `set_cycle_root`'s cycle state, so keep some SOL in it.
+
+use crate::{SetCycleRootArgs, UpdateConfigArgs};
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::{ArgGroup, Args, Subcommand};
+use sentinelkarma_sdk::seka;
+use serde_json::json;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const GOVERNANCE_PROGRAM_ID: Pubkey = pubkey!("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw");
+
+#[derive(Subcommand)]
+pub enum GovernanceCommand {
+    /// `update_config`; unset flags are left as they are
+    UpdateConfig {
+        #[command(flatten)]
+        proposal: ProposalArgs,
+        #[command(flatten)]
+        args: UpdateConfigArgs,
+    },
+    /// `set_cycle_root`
+    SetCycleRoot {
+        #[command(flatten)]
+        proposal: ProposalArgs,
+        #[command(flatten)]
+        args: SetCycleRootArgs,
+    },
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("signer").required(true).args(["governance", "governor"])))]
+pub struct ProposalArgs {
+    /// Governance account whose native treasury is the seka governor
+    #[arg(long)]
+    governance: Option<Pubkey>,
+    /// The governor itself, when it is not a native treasury
+    #[arg(long)]
+    governor: Option<Pubkey>,
+    #[arg(long, default_value_t = GOVERNANCE_PROGRAM_ID)]
+    governance_program: Pubkey,
+    /// Print a JSON object instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+impl ProposalArgs {
+    fn governor(&self) -> Pubkey {
+        match (self.governor, self.governance) {
+            (Some(governor), _) => governor,
+            (None, Some(governance)) => native_treasury(&self.governance_program, &governance),
+            (None, None) => unreachable!("clap requires one of them"),
+        }
+    }
+}
+
+/// The PDA a governance signs with when executing treasury instructions.
+pub fn native_treasury(governance_program: &Pubkey, governance: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"native-treasury", governance.as_ref()],
+        governance_program,
+    )
+    .0
+}
+
+/// Borsh `InstructionData` of spl-governance: program id, `Vec<AccountMetaData>` and
+/// `Vec<u8>` data, each vector prefixed with its u32 length.
+pub fn instruction_data(ix: &Instruction) -> Vec<u8> {
+    let mut out = ix.program_id.to_bytes().to_vec();
+    out.extend_from_slice(&(ix.accounts.len() as u32).to_le_bytes());
+    for meta in &ix.accounts {
+        out.extend_from_slice(meta.pubkey.as_ref());
+        out.push(meta.is_signer as u8);
+        out.push(meta.is_writable as u8);
+    }
+    out.extend_from_slice(&(ix.data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&ix.data);
+    out
+}
+
+pub fn run(command: &GovernanceCommand) -> Result<()> {
+    let (proposal, ix) = match command {
+        GovernanceCommand::UpdateConfig { proposal, args } => {
+            (proposal, seka::update_config(&proposal.governor(), args.params()))
+        }
+        GovernanceCommand::SetCycleRoot { proposal, args } => {
+            (proposal, args.instruction(&proposal.governor())?)
+        }
+    };
+    let name = seka::instruction_name(&ix.data).unwrap_or("unknown");
+    let encoded = STANDARD.encode(instruction_data(&ix));
+
+    if proposal.json {
+        let accounts: Vec<_> = ix
+            .accounts
+            .iter()
+            .map(|m| {
+                json!({
+                    "pubkey": m.pubkey.to_string(),
+                    "isSigner": m.is_signer,
+                    "isWritable": m.is_writable,
+                })
+            })
+            .collect();
+        let out = json!({
+            "instruction": name,
+            "programId": ix.program_id.to_string(),
+            "accounts": accounts,
+            "data": STANDARD.encode(&ix.data),
+            "instructionData": encoded,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    println!("instruction: {name}");
+    println!("program:     {}", ix.program_id);
+    println!("governor:    {}", proposal.governor());
+    println!("accounts:");
+    for meta in &ix.accounts {
+        let mut flags = Vec::new();
+        if meta.is_signer {
+            flags.push("signer");
+        }
+        if meta.is_writable {
+            flags.push("writable");
+        }
+        println!("  {:<44} {}", meta.pubkey, flags.join(", "));
+    }
+    println!("data (base64): {}", STANDARD.encode(&ix.data));
+    println!();
+    println!("Paste into Realms > Add instruction > Execute Custom Instruction:");
+    println!("{encoded}");
+    Ok(())
+}
@@ -0,0 +1,481 @@
+//! seka-admin: operator CLI for the seka program.
+//!
+//! Every state-changing command can be run with `--dry-run`, which simulates the
+//! transaction without signatures and prints the program logs and decoded events.
+
+mod airdrop;
+mod bootstrap;
+mod governance;
+mod reclaim;
+mod rotate;
+mod simulate;
+mod snapshot;
+mod squads;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use seka_proofs::parse_leaf_format;
+use sentinelkarma_sdk::{seka, signer};
+use serde::Deserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::UiTransactionEncoding;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Parser)]
+#[command(name = "seka-admin", about = "Operate the seka program")]
+struct Cli {
+    #[arg(long, short = 'u', env = "SEKA_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair file, `usb://ledger[?key=0/0]` or a remote signer
+    /// `https://…?pubkey=<base58>`; signs as governor and pays fees
+    #[arg(long, short = 'k', env = "SEKA_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    /// processed, confirmed or finalized
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+    /// Simulate instead of sending
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Propose through this Squads v4 multisig, whose vault is the governor, instead of
+    /// signing; the keypair then only needs to be a member that can initiate
+    #[arg(long, env = "SEKA_SQUADS_MULTISIG", global = true)]
+    squads_multisig: Option<Pubkey>,
+    #[arg(long, default_value_t = 0, global = true)]
+    squads_vault_index: u8,
+    /// Shown to voters with the proposal
+    #[arg(long, global = true, requires = "squads_multisig")]
+    squads_memo: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the config, SEKA mint, and treasury, and mint the initial airdrop
+    Initialize {
+        /// Defaults to the signer
+        #[arg(long)]
+        governor: Option<Pubkey>,
+        /// Defaults to the signer; its ATA for the new mint must exist
+        #[arg(long)]
+        airdrop_recipient: Option<Pubkey>,
+        /// Unix time of cycle 0; defaults to now
+        #[arg(long)]
+        start_ts: Option<i64>,
+        #[arg(long, default_value_t = 6)]
+        decimals: u8,
+        /// Whole tokens
+        #[arg(long, default_value_t = 10_000)]
+        airdrop: u64,
+        /// Keypair for the new mint; a fresh one is generated if omitted
+        #[arg(long)]
+        mint_keypair: Option<PathBuf>,
+    },
+    /// Change config parameters; unset flags are left as they are
+    UpdateConfig(UpdateConfigArgs),
+    /// Publish a cycle's Merkle root
+    SetCycleRoot(SetCycleRootArgs),
+    /// Mark a member inactive
+    DeactivateMembership {
+        #[arg(long)]
+        member: Pubkey,
+    },
+    /// Print the decoded config account
+    ShowConfig,
+    /// Hand the seka governor and sentinel authority to a new key, archive the
+    /// transactions and check the result
+    RotateGovernor(rotate::RotateArgs),
+    /// Close expired or fully claimed cycle accounts in batches and report the rent
+    /// recovered
+    ReclaimCycles(reclaim::ReclaimArgs),
+    /// Inspect or reassign the treasury
+    #[command(subcommand)]
+    Treasury(TreasuryCommand),
+    /// Pay SEKA to every wallet in a CSV in batched, resumable transactions
+    Airdrop(airdrop::AirdropArgs),
+    /// Deploy and initialize both programs, create funded test members, publish a
+    /// sample cycle root, and write a fixtures file for SDK examples and tests
+    Bootstrap(bootstrap::BootstrapArgs),
+    /// Print governor instructions as SPL Governance instruction data for a Realms
+    /// proposal; needs no keypair
+    #[command(subcommand)]
+    Governance(governance::GovernanceCommand),
+    /// Dry-run a peer's claim or conversion and explain why it would fail; needs no
+    /// keypair
+    #[command(subcommand)]
+    Simulate(simulate::SimulateCommand),
+    /// Export every PeerLedger, Membership, PeerState and Post account; needs no keypair
+    Snapshot {
+        #[arg(long, default_value = "snapshot")]
+        out_dir: PathBuf,
+        #[arg(long, value_enum, default_value = "json")]
+        format: snapshot::Format,
+        /// Fail unless the RPC node has reached this slot
+        #[arg(long)]
+        min_slot: Option<u64>,
+    },
+}
+
+#[derive(Args)]
+struct UpdateConfigArgs {
+    #[arg(long)]
+    cycle_secs: Option<u64>,
+    #[arg(long)]
+    max_points_per_cycle: Option<u32>,
+    #[arg(long, allow_hyphen_values = true)]
+    per_peer_cycle_cap: Option<i32>,
+    #[arg(long)]
+    conversion_ratio: Option<u32>,
+    /// Base units
+    #[arg(long)]
+    join_cost_tokens: Option<u64>,
+    #[arg(long)]
+    issuer: Option<Pubkey>,
+}
+
+impl UpdateConfigArgs {
+    fn params(&self) -> seka::UpdateParams {
+        seka::UpdateParams {
+            cycle_secs: self.cycle_secs,
+            max_points_per_cycle: self.max_points_per_cycle,
+            per_peer_cycle_cap: self.per_peer_cycle_cap,
+            conversion_ratio: self.conversion_ratio,
+            join_cost_tokens: self.join_cost_tokens,
+            treasury_owner: None,
+            issuer: self.issuer,
+        }
+    }
+}
+
+#[derive(Args)]
+struct SetCycleRootArgs {
+    /// root.json written by `seka-proofs build`
+    #[arg(long, conflicts_with_all = ["cycle", "root", "total_points", "bitmap_len", "leaf_count"])]
+    root_file: Option<PathBuf>,
+    #[arg(long, requires_all = ["root", "total_points", "bitmap_len", "leaf_count"])]
+    cycle: Option<u64>,
+    /// Hex-encoded root
+    #[arg(long)]
+    root: Option<String>,
+    #[arg(long)]
+    total_points: Option<u32>,
+    #[arg(long)]
+    bitmap_len: Option<u32>,
+    #[arg(long)]
+    leaf_count: Option<u32>,
+    /// Format the root was built in: `solana` or `evm`
+    #[arg(long, conflicts_with = "root_file", default_value = "solana", value_parser = parse_leaf_format)]
+    leaf_format: seka::LeafFormat,
+}
+
+impl SetCycleRootArgs {
+    fn instruction(&self, governor: &Pubkey) -> Result<Instruction> {
+        let file = match (
+            &self.root_file,
+            self.cycle,
+            &self.root,
+            self.total_points,
+            self.bitmap_len,
+            self.leaf_count,
+        ) {
+            (Some(path), ..) => serde_json::from_slice(&std::fs::read(path)?)
+                .with_context(|| format!("reading {}", path.display()))?,
+            (None, Some(cycle_index), Some(merkle_root), Some(total), Some(len), Some(count)) => RootFile {
+                cycle_index,
+                merkle_root: merkle_root.clone(),
+                total_points_declared: total,
+                claims_bitmap_len: len,
+                leaf_count: count,
+                leaf_format: Some(self.leaf_format.name().to_owned()),
+            },
+            _ => bail!("pass --root-file, or --cycle --root --total-points --bitmap-len --leaf-count"),
+        };
+        Ok(seka::set_cycle_root(
+            governor,
+            file.cycle_index,
+            parse_root(&file.merkle_root)?,
+            file.total_points_declared,
+            file.claims_bitmap_len,
+            file.leaf_count,
+            parse_leaf_format(file.leaf_format.as_deref().unwrap_or("solana"))?,
+        ))
+    }
+}
+
+#[derive(Subcommand)]
+enum TreasuryCommand {
+    /// Treasury PDA, its ATA and balance
+    Show,
+    /// Point `config.treasury` at a new owner
+    SetOwner {
+        #[arg(long)]
+        owner: Pubkey,
+    },
+}
+
+/// Subset of `seka-proofs` root.json used here.
+#[derive(Deserialize)]
+struct RootFile {
+    cycle_index: u64,
+    merkle_root: String,
+    total_points_declared: u32,
+    claims_bitmap_len: u32,
+    leaf_count: u32,
+    /// Absent from files written before leaf formats, which are all `solana`
+    #[serde(default)]
+    leaf_format: Option<String>,
+}
+
+struct Ctx {
+    client: RpcClient,
+    signer: Box<dyn Signer>,
+    dry_run: bool,
+    squads: Option<squads::Squads>,
+    squads_memo: Option<String>,
+}
+
+impl Ctx {
+    fn fetch_config(&self) -> Result<seka::GlobalConfig> {
+        sentinelkarma_sdk::fetch_account(&self.client, &seka::config_pda())
+    }
+
+    /// Signs as governor: the CLI signer, or the Squads vault when `--squads-multisig`
+    /// is set.
+    fn authority(&self) -> Pubkey {
+        match &self.squads {
+            Some(squads) => squads.vault(),
+            None => self.signer.pubkey(),
+        }
+    }
+
+    /// Simulates or sends `ixs` signed by the CLI signer plus `extra` signers, then
+    /// prints logs (dry run) and decoded events. With `--squads-multisig` it sends a
+    /// proposal to execute `ixs` from the vault instead.
+    fn submit(&self, ixs: &[Instruction], extra: &[&dyn Signer]) -> Result<()> {
+        let Some(squads) = &self.squads else {
+            return self.send(ixs, extra);
+        };
+        if !extra.is_empty() {
+            bail!(
+                "this command needs signers besides the governor; run it without --squads-multisig"
+            );
+        }
+        let index = squads.next_transaction_index(&self.client)?;
+        let proposal = squads.propose(
+            &self.signer.pubkey(),
+            index,
+            ixs,
+            self.squads_memo.as_deref(),
+        )?;
+        self.send(&proposal, &[])
+    }
+
+    fn send(&self, ixs: &[Instruction], extra: &[&dyn Signer]) -> Result<()> {
+        self.transact(ixs, extra).map(|_| ())
+    }
+
+    /// [`Ctx::send`], returning the signature unless it was a dry run.
+    fn transact(&self, ixs: &[Instruction], extra: &[&dyn Signer]) -> Result<Option<Signature>> {
+        let payer = self.signer.pubkey();
+        let (logs, signature) = if self.dry_run {
+            // Unsigned simulation, so a Ledger is never prompted for a dry run
+            let tx = Transaction::new_unsigned(Message::new(ixs, Some(&payer)));
+            let result = self
+                .client
+                .simulate_transaction_with_config(
+                    &tx,
+                    RpcSimulateTransactionConfig {
+                        sig_verify: false,
+                        replace_recent_blockhash: true,
+                        commitment: Some(self.client.commitment()),
+                        ..RpcSimulateTransactionConfig::default()
+                    },
+                )?
+                .value;
+            let logs = result.logs.unwrap_or_default();
+            for line in &logs {
+                println!("  {line}");
+            }
+            if let Some(units) = result.units_consumed {
+                println!("compute units: {units}");
+            }
+            if let Some(err) = result.err {
+                bail!("simulation failed: {err}");
+            }
+            println!("simulation ok");
+            (logs, None)
+        } else {
+            let mut signers: Vec<&dyn Signer> = vec![self.signer.as_ref()];
+            signers.extend_from_slice(extra);
+            let blockhash = self.client.get_latest_blockhash()?;
+            let tx = Transaction::new_signed_with_payer(ixs, Some(&payer), &signers, blockhash);
+            let signature = self
+                .client
+                .send_and_confirm_transaction_with_spinner(&tx)
+                .context("sending transaction")?;
+            println!("signature: {signature}");
+            (self.fetch_logs(&signature)?, Some(signature))
+        };
+
+        for event in seka::SekaEvent::from_logs(&logs) {
+            println!("event: {event}");
+        }
+        Ok(signature)
+    }
+
+    fn fetch_logs(&self, signature: &Signature) -> Result<Vec<String>> {
+        let tx = self.client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(self.client.commitment()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        Ok(tx
+            .transaction
+            .meta
+            .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+            .unwrap_or_default())
+    }
+}
+
+fn parse_root(s: &str) -> Result<[u8; 32]> {
+    hex::decode(s.trim().trim_start_matches("0x"))
+        .context("root is not hex")?
+        .try_into()
+        .map_err(|_| anyhow!("root must be 32 bytes"))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let commitment = CommitmentConfig::from_str(&cli.commitment)
+        .map_err(|_| anyhow!("unknown commitment {:?}", cli.commitment))?;
+    let client = RpcClient::new_with_commitment(cli.url.clone(), commitment);
+    // Read-only, so it needs no keypair
+    if let Command::Snapshot {
+        out_dir,
+        format,
+        min_slot,
+    } = &cli.command
+    {
+        return snapshot::run(&client, out_dir, *format, *min_slot);
+    }
+    if let Command::Governance(command) = &cli.command {
+        return governance::run(command);
+    }
+    if let Command::Simulate(command) = &cli.command {
+        return simulate::run(&client, command);
+    }
+    let ctx = Ctx {
+        client,
+        signer: signer::resolve(&cli.keypair)?,
+        dry_run: cli.dry_run,
+        squads: cli.squads_multisig.map(|multisig| squads::Squads {
+            multisig,
+            vault_index: cli.squads_vault_index,
+        }),
+        squads_memo: cli.squads_memo,
+    };
+    let direct_only = matches!(cli.command, Command::Airdrop(_) | Command::Bootstrap(_));
+    if ctx.squads.is_some() && direct_only {
+        bail!("airdrop and bootstrap sign directly and cannot go through --squads-multisig");
+    }
+    let authority = ctx.authority();
+
+    match cli.command {
+        Command::Initialize {
+            governor,
+            airdrop_recipient,
+            start_ts,
+            decimals,
+            airdrop,
+            mint_keypair,
+        } => {
+            let mint = match mint_keypair {
+                Some(path) => read_keypair_file(&path)
+                    .map_err(|e| anyhow!("reading {}: {e}", path.display()))?,
+                None => Keypair::new(),
+            };
+            let recipient = airdrop_recipient.unwrap_or(authority);
+            let start_ts = match start_ts {
+                Some(ts) => ts,
+                None => std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs() as i64,
+            };
+            println!("mint: {}", mint.pubkey());
+            let ix = seka::initialize(
+                &authority,
+                &mint.pubkey(),
+                &seka::associated_token_address(&recipient, &mint.pubkey()),
+                governor.unwrap_or(authority),
+                recipient,
+                start_ts,
+                decimals,
+                airdrop,
+            );
+            ctx.submit(&[ix], &[&mint])
+        }
+        Command::UpdateConfig(args) => {
+            ctx.submit(&[seka::update_config(&authority, args.params())], &[])
+        }
+        Command::SetCycleRoot(args) => ctx.submit(&[args.instruction(&authority)?], &[]),
+        Command::DeactivateMembership { member } => {
+            ctx.submit(&[seka::deactivate_membership(&authority, member)], &[])
+        }
+        Command::ShowConfig => {
+            let cfg = ctx.fetch_config()?;
+            println!("config:               {} (bump {})", seka::config_pda(), cfg.bump);
+            println!("governor:             {}", cfg.governor);
+            println!("issuer:               {}", cfg.issuer);
+            println!("mint:                 {} ({} decimals)", cfg.mint, cfg.decimals);
+            println!("mint authority:       {}", cfg.mint_authority);
+            println!("treasury:             {}", cfg.treasury);
+            println!("start_ts:             {}", cfg.start_ts);
+            println!("cycle_secs:           {}", cfg.cycle_secs);
+            println!("max_points_per_cycle: {}", cfg.max_points_per_cycle);
+            println!("per_peer_cycle_cap:   {}", cfg.per_peer_cycle_cap);
+            println!("conversion_ratio:     {}", cfg.conversion_ratio);
+            println!("join_cost_tokens:     {}", cfg.join_cost_tokens);
+            println!("airdrop_done:         {}", cfg.airdrop_done);
+            Ok(())
+        }
+        Command::Treasury(TreasuryCommand::Show) => {
+            let cfg = ctx.fetch_config()?;
+            let ata = seka::associated_token_address(&cfg.treasury, &cfg.mint);
+            println!("treasury owner: {}", cfg.treasury);
+            println!("treasury ATA:   {ata}");
+            match ctx.client.get_token_account_balance(&ata) {
+                Ok(balance) => println!("balance:        {} SEKA", balance.ui_amount_string),
+                Err(_) => println!("balance:        (ATA does not exist)"),
+            }
+            Ok(())
+        }
+        Command::Treasury(TreasuryCommand::SetOwner { owner }) => {
+            let params = seka::UpdateParams {
+                treasury_owner: Some(owner),
+                ..seka::UpdateParams::default()
+            };
+            ctx.submit(&[seka::update_config(&authority, params)], &[])
+        }
+        Command::RotateGovernor(args) => rotate::run(&ctx, &args),
+        Command::ReclaimCycles(args) => reclaim::run(&ctx, &args),
+        Command::Airdrop(args) => airdrop::run(&ctx, &args),
+        Command::Bootstrap(args) => {
+            let keypair = signer::expand_home(&cli.keypair);
+            bootstrap::run(&ctx, &cli.url, &keypair, &args)
+        }
+        Command::Snapshot { .. } | Command::Governance(_) | Command::Simulate(_) => {
+            unreachable!("handled before the signer is resolved")
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! `reclaim-cycles`: closes `CycleState` accounts nothing more can be claimed from and
+//! returns their rent to the governor.
+//!
+//! A cycle qualifies once it is `CLAIM_WINDOW_CYCLES` behind the current cycle, or once
+//! every leaf it was published with is claimed. Cycles are the only seka accounts the
+//! governor can close; sentinel likes are closed by their likers. Closes go out
+//! `--batch-size` to a transaction, each batch through `--dry-run` or
+//! `--squads-multisig` like any other governor command.
+
+use crate::Ctx;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use sentinelkarma_sdk::anchor_lang::Discriminator;
+use sentinelkarma_sdk::{decode_account, seka};
+use solana_sdk::clock::Clock;
+use solana_sdk::sysvar;
+
+/// Lamports per SOL, for the summary.
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+#[derive(Args)]
+pub struct ReclaimArgs {
+    /// Closes per transaction
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(1..=20))]
+    batch_size: u8,
+    /// Stop after this many cycles
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+struct Closable {
+    cycle_index: u64,
+    lamports: u64,
+    fully_claimed: bool,
+}
+
+pub fn run(ctx: &Ctx, args: &ReclaimArgs) -> Result<()> {
+    let config = ctx.fetch_config()?;
+    let governor = ctx.authority();
+    if governor != config.governor {
+        bail!("{governor} is not the governor {}", config.governor);
+    }
+    let clock = ctx.client.get_account(&sysvar::clock::ID)?;
+    let clock: Clock =
+        solana_sdk::account::from_account(&clock).context("decoding clock sysvar")?;
+    let current = config.current_cycle(clock.unix_timestamp);
+
+    let discriminator = seka::CycleState::DISCRIMINATOR;
+    let (slot, accounts) =
+        crate::snapshot::program_accounts(&ctx.client, &seka::ID, &discriminator, None)?;
+
+    let mut closable = Vec::new();
+    let mut open = 0;
+    for (address, account) in &accounts {
+        let state: seka::CycleState = match decode_account(&account.data) {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("warning: skipping {address}: {e:#}");
+                continue;
+            }
+        };
+        let fully_claimed =
+            state.leaf_count > 0 && (0..state.leaf_count).all(|i| state.is_claimed(i));
+        let expired = state.cycle_index.saturating_add(seka::CLAIM_WINDOW_CYCLES) <= current;
+        if expired || fully_claimed {
+            closable.push(Closable {
+                cycle_index: state.cycle_index,
+                lamports: account.lamports,
+                fully_claimed: fully_claimed && !expired,
+            });
+        } else {
+            open += 1;
+        }
+    }
+    closable.sort_by_key(|c| c.cycle_index);
+    if let Some(limit) = args.limit {
+        closable.truncate(limit);
+    }
+
+    let total: u64 = closable.iter().map(|c| c.lamports).sum();
+    println!(
+        "current cycle {current}, {} cycle accounts at slot {slot}: {} closable, {open} still claimable",
+        accounts.len(),
+        closable.len()
+    );
+    for c in &closable {
+        let why = if c.fully_claimed { "fully claimed" } else { "expired" };
+        println!("  cycle {:>6}: {why:<13} {} lamports", c.cycle_index, c.lamports);
+    }
+    if closable.is_empty() {
+        return Ok(());
+    }
+    println!("{:.6} SOL to recover", total as f64 / LAMPORTS_PER_SOL);
+
+    let batches: Vec<_> = closable.chunks(args.batch_size as usize).collect();
+    let mut recovered = 0;
+    for (i, batch) in batches.iter().enumerate() {
+        let ixs: Vec<_> = batch
+            .iter()
+            .map(|c| seka::close_cycle(&governor, c.cycle_index))
+            .collect();
+        println!("batch {} of {}:", i + 1, batches.len());
+        if ctx.dry_run {
+            // Later batches differ only in the cycles they close
+            return ctx.submit(&ixs, &[]);
+        }
+        ctx.submit(&ixs, &[])?;
+        recovered += batch.iter().map(|c| c.lamports).sum::<u64>();
+    }
+    if ctx.squads.is_some() {
+        let count = batches.len();
+        println!("{count} proposals created; rent returns to the vault as they execute");
+    } else {
+        let sol = recovered as f64 / LAMPORTS_PER_SOL;
+        println!("recovered {recovered} lamports ({sol:.6} SOL)");
+    }
+    Ok(())
+}
@@ -0,0 +1,464 @@
+//! `rotate-governor`: hands the seka governor and the sentinel authority to a new key.
+//!
+//! Both programs transfer in two steps: the current key nominates its successor
+//! (`nominate_governor`, `nominate_authority`) and the successor accepts
+//! (`accept_governor`, `accept_authority`), at which point the old key loses its rights.
+//! Per program, the flow
+//!
+//! 1. reads the current and pending keys; a program the new key already governs is left
+//!    alone, and a nomination of the new key already on-chain is not sent again;
+//! 2. nominates, signed by the current key, or proposed through `--squads-multisig`, in
+//!    which case the run stops there and is repeated once the proposal has executed;
+//! 3. accepts, signed by `--new-keypair`;
+//! 4. reads the accounts again: the new key must hold the role with nothing pending.
+//!
+//! Then admin actions are simulated, `update_config` with no changes and sentinel
+//! `pause`: as the new key they must pass, as the old key they must be rejected. The
+//! simulations change nothing.
+//!
+//! Each transaction sent is archived in `--archive-dir` as
+//! `<program>-<step>-<signature>.json`, as `getTransaction` returns it, and
+//! `rotation-<unix time>.json` records the run: keys, archived transactions and checks.
+//!
+//! A sentinel run by a committee (`threshold > 0`) takes committee signatures to
+//! nominate; nominate through the committee and rerun to accept.
+
+use crate::Ctx;
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use sentinelkarma_sdk::sentinel::{self, accounts, instruction};
+use sentinelkarma_sdk::{decode_account, seka, signer};
+use serde::Serialize;
+use solana_client::rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::UiTransactionEncoding;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct RotateArgs {
+    /// Incoming governor, as `--keypair`: keypair file, `usb://ledger[?key=0/0]` or a
+    /// remote signer; signs the accept steps
+    #[arg(long)]
+    new_keypair: String,
+    #[arg(long, value_enum, default_value = "both")]
+    programs: Programs,
+    /// Archived transactions and the rotation record
+    #[arg(long, default_value = "rotations")]
+    archive_dir: PathBuf,
+    /// Send without asking
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Programs {
+    Both,
+    Seka,
+    Sentinel,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Program {
+    Seka,
+    Sentinel,
+}
+
+impl Program {
+    fn name(self) -> &'static str {
+        match self {
+            Program::Seka => "seka",
+            Program::Sentinel => "sentinel",
+        }
+    }
+
+    fn role(self) -> &'static str {
+        match self {
+            Program::Seka => "governor",
+            Program::Sentinel => "authority",
+        }
+    }
+}
+
+/// Who holds a program's admin role.
+struct Role {
+    holder: Pubkey,
+    pending: Option<Pubkey>,
+    /// Sentinel committee threshold; 0 when the holder acts alone
+    threshold: u8,
+}
+
+#[derive(Serialize)]
+struct Record {
+    previous: String,
+    new: String,
+    started_at: i64,
+    programs: Vec<ProgramRecord>,
+}
+
+#[derive(Serialize)]
+struct ProgramRecord {
+    program: &'static str,
+    /// `rotated`, `already rotated`, `nomination proposed` or `dry run`
+    outcome: &'static str,
+    transactions: Vec<Archived>,
+    checks: Vec<Check>,
+}
+
+#[derive(Serialize)]
+struct Archived {
+    step: &'static str,
+    signature: String,
+    path: String,
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: String,
+    passed: bool,
+    detail: String,
+}
+
+pub fn run(ctx: &Ctx, args: &RotateArgs) -> Result<()> {
+    let new_signer = signer::resolve(&args.new_keypair)?;
+    let new = new_signer.pubkey();
+    let old = ctx.authority();
+    if new == old {
+        bail!("--new-keypair is the current signer {old}");
+    }
+    let programs: &[Program] = match args.programs {
+        Programs::Both => &[Program::Seka, Program::Sentinel],
+        Programs::Seka => &[Program::Seka],
+        Programs::Sentinel => &[Program::Sentinel],
+    };
+
+    println!("rotating {old} -> {new}");
+    for &program in programs {
+        let role = read_role(ctx, program)?;
+        let step = if role.holder == new {
+            "nothing to do, already rotated".to_string()
+        } else if role.pending == Some(new) {
+            format!("{} is nominated; accept", role.holder)
+        } else if role.holder != old {
+            bail!(
+                "{} {} is {}, not the signer {old}",
+                program.name(),
+                program.role(),
+                role.holder
+            );
+        } else if role.threshold > 0 {
+            bail!(
+                "sentinel is run by a committee (threshold {}); nominate {new} through it, then rerun",
+                role.threshold
+            );
+        } else {
+            "nominate, then accept".to_string()
+        };
+        println!(
+            "  {:<8} {:<9} {}: {step}",
+            program.name(),
+            program.role(),
+            role.holder
+        );
+    }
+    if !ctx.dry_run && !args.yes && !confirm("proceed?")? {
+        bail!("aborted");
+    }
+
+    fs::create_dir_all(&args.archive_dir)
+        .with_context(|| format!("creating {}", args.archive_dir.display()))?;
+    let mut record = Record {
+        previous: old.to_string(),
+        new: new.to_string(),
+        started_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64,
+        programs: Vec::new(),
+    };
+    let mut failed = false;
+    for &program in programs {
+        let entry = rotate(ctx, program, &old, new_signer.as_ref(), &args.archive_dir)?;
+        failed |= entry.checks.iter().any(|c| !c.passed);
+        record.programs.push(entry);
+    }
+
+    let path = args
+        .archive_dir
+        .join(format!("rotation-{}.json", record.started_at));
+    fs::write(&path, serde_json::to_string_pretty(&record)? + "\n")
+        .with_context(|| format!("writing {}", path.display()))?;
+    println!("record: {}", path.display());
+    if failed {
+        bail!("post-rotation checks failed; see above");
+    }
+    Ok(())
+}
+
+fn rotate(
+    ctx: &Ctx,
+    program: Program,
+    old: &Pubkey,
+    new_signer: &dyn Signer,
+    archive_dir: &Path,
+) -> Result<ProgramRecord> {
+    let new = new_signer.pubkey();
+    let mut entry = ProgramRecord {
+        program: program.name(),
+        outcome: "rotated",
+        transactions: Vec::new(),
+        checks: Vec::new(),
+    };
+    let role = read_role(ctx, program)?;
+    if role.holder == new {
+        println!("{}: already rotated", program.name());
+        entry.outcome = "already rotated";
+    } else {
+        if role.pending != Some(new) {
+            println!("{}: nominating {new}", program.name());
+            let ix = nominate(program, old, new);
+            if ctx.squads.is_some() {
+                ctx.submit(&[ix], &[])?;
+                println!(
+                    "{}: nomination proposed; rerun once it has executed",
+                    program.name()
+                );
+                entry.outcome = "nomination proposed";
+                return Ok(entry);
+            }
+            match ctx.transact(&[ix], &[])? {
+                Some(signature) => entry.transactions.push(archive(
+                    ctx,
+                    program,
+                    "nominate",
+                    &signature,
+                    archive_dir,
+                )?),
+                None => {
+                    println!(
+                        "{}: accept cannot be simulated before the nomination lands",
+                        program.name()
+                    );
+                    entry.outcome = "dry run";
+                    return Ok(entry);
+                }
+            }
+        }
+        println!("{}: accepting as {new}", program.name());
+        match ctx.transact(&[accept(program, &new)], &[new_signer])? {
+            Some(signature) => {
+                entry
+                    .transactions
+                    .push(archive(ctx, program, "accept", &signature, archive_dir)?)
+            }
+            None => {
+                entry.outcome = "dry run";
+                return Ok(entry);
+            }
+        }
+    }
+
+    let role = read_role(ctx, program)?;
+    entry.checks.push(Check {
+        name: format!("{} is the {}", new, program.role()),
+        passed: role.holder == new,
+        detail: format!("on-chain {} {}", program.role(), role.holder),
+    });
+    entry.checks.push(Check {
+        name: "no handover pending".into(),
+        passed: role.pending.is_none(),
+        detail: match role.pending {
+            Some(pending) => format!("{pending} is still nominated"),
+            None => "none".into(),
+        },
+    });
+    if program == Program::Sentinel && role.threshold > 0 {
+        entry.checks.push(Check {
+            name: "simulated admin actions".into(),
+            passed: true,
+            detail: format!(
+                "skipped: sentinel admin actions need {} committee signatures",
+                role.threshold
+            ),
+        });
+    } else {
+        let action = |signer: &Pubkey| admin_action(program, signer);
+        let as_new = simulate(ctx, &action(&new))?;
+        entry.checks.push(Check {
+            name: format!("new key can {}", action_name(program)),
+            passed: as_new.is_none(),
+            detail: as_new.unwrap_or_else(|| "simulation ok".into()),
+        });
+        let as_old = simulate(ctx, &action(old))?;
+        entry.checks.push(Check {
+            name: format!("old key cannot {}", action_name(program)),
+            passed: as_old
+                .as_deref()
+                .is_some_and(|e| e.contains("Unauthorized")),
+            detail: as_old.unwrap_or_else(|| "simulation succeeded".into()),
+        });
+    }
+    for check in &entry.checks {
+        let mark = if check.passed { "ok" } else { "FAILED" };
+        println!(
+            "{}: {mark}: {} ({})",
+            program.name(),
+            check.name,
+            check.detail
+        );
+    }
+    Ok(entry)
+}
+
+fn read_role(ctx: &Ctx, program: Program) -> Result<Role> {
+    match program {
+        Program::Seka => {
+            let config = ctx.fetch_config()?;
+            let transfer = ctx
+                .client
+                .get_account_with_commitment(
+                    &seka::governor_transfer_pda(),
+                    ctx.client.commitment(),
+                )?
+                .value
+                .map(|account| decode_account::<seka::GovernorTransfer>(&account.data))
+                .transpose()?;
+            Ok(Role {
+                holder: config.governor,
+                pending: transfer
+                    .map(|t| t.pending)
+                    .filter(|p| *p != Pubkey::default()),
+                threshold: 0,
+            })
+        }
+        Program::Sentinel => {
+            let state: sentinel::State =
+                sentinelkarma_sdk::fetch_account(&ctx.client, &sentinel::state_pda())?;
+            Ok(Role {
+                holder: state.authority,
+                pending: Some(state.pending_authority).filter(|p| *p != Pubkey::default()),
+                threshold: state.threshold,
+            })
+        }
+    }
+}
+
+fn nominate(program: Program, current: &Pubkey, new: Pubkey) -> Instruction {
+    match program {
+        Program::Seka => seka::nominate_governor(current, new),
+        Program::Sentinel => sentinel::instruction(
+            accounts::NominateAuthority {
+                authority: *current,
+                state: sentinel::state_pda(),
+                event_authority: sentinel::event_authority_pda(),
+                program: sentinel::ID,
+            },
+            instruction::NominateAuthority { new_authority: new },
+        ),
+    }
+}
+
+fn accept(program: Program, new: &Pubkey) -> Instruction {
+    match program {
+        Program::Seka => seka::accept_governor(new),
+        Program::Sentinel => sentinel::instruction(
+            accounts::AcceptAuthority {
+                pending_authority: *new,
+                state: sentinel::state_pda(),
+                event_authority: sentinel::event_authority_pda(),
+                program: sentinel::ID,
+            },
+            instruction::AcceptAuthority {},
+        ),
+    }
+}
+
+fn action_name(program: Program) -> &'static str {
+    match program {
+        Program::Seka => "update_config",
+        Program::Sentinel => "pause",
+    }
+}
+
+/// An admin instruction with no lasting effect when only simulated.
+fn admin_action(program: Program, signer: &Pubkey) -> Instruction {
+    match program {
+        Program::Seka => seka::update_config(signer, seka::UpdateParams::default()),
+        Program::Sentinel => sentinel::instruction(
+            accounts::SetPaused {
+                authority: *signer,
+                state: sentinel::state_pda(),
+                event_authority: sentinel::event_authority_pda(),
+                program: sentinel::ID,
+            },
+            instruction::Pause {},
+        ),
+    }
+}
+
+/// Simulates `ix` without signatures, paid by the CLI signer; the error and the program
+/// log lines mentioning it, or `None` if it would succeed.
+fn simulate(ctx: &Ctx, ix: &Instruction) -> Result<Option<String>> {
+    let payer = ctx.signer.pubkey();
+    let tx = Transaction::new_unsigned(Message::new(std::slice::from_ref(ix), Some(&payer)));
+    let result = ctx
+        .client
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(ctx.client.commitment()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    Ok(result.err.map(|err| {
+        let logs = result.logs.unwrap_or_default();
+        let reason = logs
+            .iter()
+            .find(|line| line.contains("Error Code:"))
+            .map(String::as_str)
+            .unwrap_or_default();
+        format!("{err} {reason}").trim().to_string()
+    }))
+}
+
+/// Writes the confirmed transaction as `getTransaction` returns it.
+fn archive(
+    ctx: &Ctx,
+    program: Program,
+    step: &'static str,
+    signature: &Signature,
+    archive_dir: &Path,
+) -> Result<Archived> {
+    let tx = ctx.client.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(ctx.client.commitment()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let path = archive_dir.join(format!("{}-{step}-{signature}.json", program.name()));
+    fs::write(&path, serde_json::to_string_pretty(&tx)? + "\n")
+        .with_context(|| format!("writing {}", path.display()))?;
+    println!("{}: archived {step} to {}", program.name(), path.display());
+    Ok(Archived {
+        step,
+        signature: signature.to_string(),
+        path: path.display().to_string(),
+    })
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,95 @@
+//! `simulate`: dry runs of the peer-side instructions, `claim_karma` and
+//! `convert_points_to_tokens`, with the failure explained and the resulting state shown.
+//!
+//! Neither needs a keypair: the simulation is unsigned, so support can check a claim
+//! that a peer reports failing with nothing but the peer's proof file.
+
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use sentinelkarma_sdk::{seka, simulate};
+use serde::Deserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Subcommand)]
+pub enum SimulateCommand {
+    /// `claim_karma` for one proof
+    Claim {
+        /// proofs/<owner>.json written by `seka-proofs build`
+        #[arg(long)]
+        proof: PathBuf,
+        /// Fee payer; defaults to the owner
+        #[arg(long)]
+        payer: Option<Pubkey>,
+    },
+    /// `convert_points_to_tokens`
+    Convert {
+        #[arg(long)]
+        owner: Pubkey,
+        /// Whole SEKA to mint
+        #[arg(long)]
+        tokens: u64,
+        /// Wallet whose ATA receives the tokens; defaults to the owner
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+    },
+}
+
+/// Subset of `seka-proofs` proofs/<owner>.json used here.
+#[derive(Deserialize)]
+struct ProofFile {
+    owner: String,
+    cycle_index: u64,
+    delta_points: i32,
+    leaf_index: u32,
+    proof: Vec<String>,
+}
+
+pub fn run(client: &RpcClient, command: &SimulateCommand) -> Result<()> {
+    let outcome = match command {
+        SimulateCommand::Claim { proof, payer } => {
+            let file: ProofFile = serde_json::from_slice(&std::fs::read(proof)?)
+                .with_context(|| format!("reading {}", proof.display()))?;
+            let owner = Pubkey::from_str(&file.owner).context("invalid owner in proof file")?;
+            let path = file
+                .proof
+                .iter()
+                .map(|hash| crate::parse_root(hash))
+                .collect::<Result<_>>()?;
+            simulate::claim(
+                client,
+                &payer.unwrap_or(owner),
+                owner,
+                file.cycle_index,
+                file.delta_points,
+                file.leaf_index,
+                path,
+            )?
+        }
+        SimulateCommand::Convert {
+            owner,
+            tokens,
+            recipient,
+        } => {
+            let config = sentinelkarma_sdk::fetch_account::<seka::GlobalConfig>(
+                client,
+                &seka::config_pda(),
+            )?;
+            let ata =
+                seka::associated_token_address(&recipient.unwrap_or(*owner), &config.mint);
+            simulate::convert(client, *owner, &ata, *tokens)?
+        }
+    };
+
+    print!("{outcome}");
+    if !outcome.ok() {
+        println!("logs:");
+        for line in &outcome.logs {
+            println!("  {line}");
+        }
+        bail!("simulation failed");
+    }
+    Ok(())
+}
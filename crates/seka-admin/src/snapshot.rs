@@ -0,0 +1,428 @@
+//! `snapshot`: every seka `PeerLedger` / `Membership` and sentinel `PeerState` / `Post`
+//! account, exported for audits, airdrops and governance snapshots.
+//!
+//! Each kind goes to `<out-dir>/<kind>.json` (an array of objects) or `<kind>.csv` (one
+//! header row of column names), and `manifest.json` lists the files with their column
+//! types, row counts and the slot each was read at. RPC nodes only serve current state,
+//! so the kinds are read one after another, each at or after the previous one's slot.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use sentinelkarma_sdk::anchor_lang::{AccountDeserialize, Discriminator, ZeroCopy};
+use sentinelkarma_sdk::{decode_account, decode_zero_copy, seka, sentinel, Versioned};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::{OptionalContext, RpcKeyedAccount};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+pub(crate) type Accounts = Vec<(Pubkey, Account)>;
+
+/// Column name and type: `pubkey`, `u8`..`u64`, `i64`, `bool`, `hex` or `string`.
+type Schema = &'static [(&'static str, &'static str)];
+
+#[derive(Serialize)]
+struct Column {
+    name: &'static str,
+    r#type: &'static str,
+}
+
+#[derive(Serialize)]
+struct FileEntry {
+    kind: &'static str,
+    program: String,
+    path: String,
+    slot: u64,
+    rows: usize,
+    /// Accounts with the discriminator that failed to decode (e.g. not yet migrated)
+    skipped: usize,
+    schema: Vec<Column>,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    format: &'static str,
+    files: Vec<FileEntry>,
+}
+
+struct Table {
+    kind: &'static str,
+    program: Pubkey,
+    schema: Schema,
+    slot: u64,
+    rows: Vec<Vec<Value>>,
+    skipped: usize,
+}
+
+const PEER_LEDGER: Schema = &[
+    ("address", "pubkey"),
+    ("owner", "pubkey"),
+    ("points", "i64"),
+    ("last_cycle_claimed", "u64"),
+];
+
+const MEMBERSHIP: Schema = &[
+    ("address", "pubkey"),
+    ("owner", "pubkey"),
+    ("joined_at", "i64"),
+    ("active", "bool"),
+];
+
+const PEER_STATE: Schema = &[
+    ("address", "pubkey"),
+    ("user", "pubkey"),
+    ("active", "bool"),
+    ("karma", "u64"),
+    ("karma_cycle", "u64"),
+    ("karma_earned", "u64"),
+    ("karma_synced", "u64"),
+    ("likes_cast", "u32"),
+    ("likes_given", "u64"),
+    ("likes_cycle", "u64"),
+    ("invites_issued", "u32"),
+    ("join_paid", "u64"),
+    ("joined_with_sol", "bool"),
+    ("has_username", "bool"),
+    ("version", "u8"),
+];
+
+const POST: Schema = &[
+    ("address", "pubkey"),
+    ("post_id", "u64"),
+    ("owner", "pubkey"),
+    ("creator", "pubkey"),
+    ("nft_mint", "pubkey"),
+    ("asset_kind", "u8"),
+    ("cycle_index", "u64"),
+    ("likes", "u64"),
+    ("pool_likes", "u64"),
+    ("comment_count", "u64"),
+    ("bond", "u64"),
+    ("removed", "bool"),
+    ("soulbound", "bool"),
+    ("edit_count", "u32"),
+    ("royalty_bps", "u16"),
+    ("tag", "string"),
+    ("hash", "hex"),
+    ("committed_ts", "i64"),
+    ("version", "u8"),
+];
+
+pub fn run(
+    client: &RpcClient,
+    out_dir: &Path,
+    format: Format,
+    min_slot: Option<u64>,
+) -> Result<()> {
+    fs::create_dir_all(out_dir).with_context(|| format!("creating {}", out_dir.display()))?;
+    let mut slot = min_slot;
+    let mut tables = Vec::new();
+
+    let table = fetch_borsh::<seka::PeerLedger>(
+        client,
+        seka::ID,
+        "peer_ledgers",
+        PEER_LEDGER,
+        slot,
+        |a, l| {
+            vec![
+                json!(a.to_string()),
+                json!(l.owner.to_string()),
+                json!(l.points),
+                json!(l.last_cycle_claimed),
+            ]
+        },
+    )?;
+    slot = Some(table.slot);
+    tables.push(table);
+
+    let table = fetch_borsh::<seka::Membership>(
+        client,
+        seka::ID,
+        "memberships",
+        MEMBERSHIP,
+        slot,
+        |a, m| {
+            vec![
+                json!(a.to_string()),
+                json!(m.owner.to_string()),
+                json!(m.joined_at),
+                json!(m.active),
+            ]
+        },
+    )?;
+    slot = Some(table.slot);
+    tables.push(table);
+
+    let table = fetch_zero_copy::<sentinel::PeerState>(
+        client,
+        sentinel::ID,
+        "peers",
+        PEER_STATE,
+        slot,
+        |a, p| {
+            vec![
+                json!(a.to_string()),
+                json!(p.user.to_string()),
+                json!(p.active != 0),
+                json!(p.karma),
+                json!(p.karma_cycle),
+                json!(p.karma_earned),
+                json!(p.karma_synced),
+                json!(p.likes_cast),
+                json!(p.likes_given),
+                json!(p.likes_cycle),
+                json!(p.invites_issued),
+                json!(p.join_paid),
+                json!(p.joined_with_sol != 0),
+                json!(p.has_username != 0),
+                json!(p.version),
+            ]
+        },
+    )?;
+    slot = Some(table.slot);
+    tables.push(table);
+
+    let table =
+        fetch_zero_copy::<sentinel::Post>(client, sentinel::ID, "posts", POST, slot, |a, p| {
+            let tag = String::from_utf8_lossy(&p.tag)
+                .trim_end_matches('\0')
+                .to_string();
+            vec![
+                json!(a.to_string()),
+                json!(p.post_id),
+                json!(p.owner.to_string()),
+                json!(p.creator.to_string()),
+                json!(p.nft_mint.to_string()),
+                json!(p.asset_kind),
+                json!(p.cycle_index),
+                json!(p.likes),
+                json!(p.pool_likes),
+                json!(p.comment_count),
+                json!(p.bond),
+                json!(p.removed != 0),
+                json!(p.soulbound != 0),
+                json!(p.edit_count),
+                json!(p.royalty_bps),
+                json!(tag),
+                json!(hex::encode(p.hash)),
+                json!(p.committed_ts),
+                json!(p.version),
+            ]
+        })?;
+    tables.push(table);
+
+    let mut files = Vec::new();
+    for table in tables {
+        let path = out_dir.join(format!("{}.{}", table.kind, format.extension()));
+        write_table(&table, &path, format)?;
+        println!(
+            "{:<13} {:>7} rows at slot {} -> {}{}",
+            table.kind,
+            table.rows.len(),
+            table.slot,
+            path.display(),
+            if table.skipped > 0 {
+                format!(" ({} undecodable skipped)", table.skipped)
+            } else {
+                String::new()
+            }
+        );
+        files.push(FileEntry {
+            kind: table.kind,
+            program: table.program.to_string(),
+            path: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            slot: table.slot,
+            rows: table.rows.len(),
+            skipped: table.skipped,
+            schema: table
+                .schema
+                .iter()
+                .map(|&(name, r#type)| Column { name, r#type })
+                .collect(),
+        });
+    }
+    let manifest = Manifest {
+        format: format.extension(),
+        files,
+    };
+    let path = out_dir.join("manifest.json");
+    fs::write(&path, serde_json::to_string_pretty(&manifest)? + "\n")
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
+
+/// Every account of `program` starting with `discriminator`, and the slot they were read at.
+pub(crate) fn program_accounts(
+    client: &RpcClient,
+    program: &Pubkey,
+    discriminator: &[u8],
+    min_slot: Option<u64>,
+) -> Result<(u64, Accounts)> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            discriminator.to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(client.commitment()),
+            min_context_slot: min_slot,
+            ..RpcAccountInfoConfig::default()
+        },
+        with_context: Some(true),
+    };
+    let response: OptionalContext<Vec<RpcKeyedAccount>> = client
+        .send(
+            RpcRequest::GetProgramAccounts,
+            json!([program.to_string(), config]),
+        )
+        .with_context(|| format!("fetching {program} accounts"))?;
+    let OptionalContext::Context(response) = response else {
+        bail!("RPC node ignored withContext");
+    };
+    let accounts = response
+        .value
+        .into_iter()
+        .map(|keyed| {
+            let address = Pubkey::from_str(&keyed.pubkey)?;
+            let account = keyed
+                .account
+                .decode()
+                .with_context(|| format!("decoding {address} data"))?;
+            Ok((address, account))
+        })
+        .collect::<Result<_>>()?;
+    Ok((response.context.slot, accounts))
+}
+
+fn fetch_borsh<T: AccountDeserialize + Discriminator + Versioned>(
+    client: &RpcClient,
+    program: Pubkey,
+    kind: &'static str,
+    schema: Schema,
+    min_slot: Option<u64>,
+    row: impl Fn(&Pubkey, &T) -> Vec<Value>,
+) -> Result<Table> {
+    let (slot, accounts) = program_accounts(client, &program, &T::DISCRIMINATOR, min_slot)?;
+    table(
+        kind,
+        program,
+        schema,
+        slot,
+        accounts,
+        |data| decode_account::<T>(data),
+        row,
+    )
+}
+
+fn fetch_zero_copy<T: ZeroCopy + Versioned>(
+    client: &RpcClient,
+    program: Pubkey,
+    kind: &'static str,
+    schema: Schema,
+    min_slot: Option<u64>,
+    row: impl Fn(&Pubkey, &T) -> Vec<Value>,
+) -> Result<Table> {
+    let (slot, accounts) = program_accounts(client, &program, &T::DISCRIMINATOR, min_slot)?;
+    table(
+        kind,
+        program,
+        schema,
+        slot,
+        accounts,
+        |data| decode_zero_copy::<T>(data),
+        row,
+    )
+}
+
+fn table<T>(
+    kind: &'static str,
+    program: Pubkey,
+    schema: Schema,
+    slot: u64,
+    mut accounts: Accounts,
+    decode: impl Fn(&[u8]) -> Result<T>,
+    row: impl Fn(&Pubkey, &T) -> Vec<Value>,
+) -> Result<Table> {
+    // RPC order is arbitrary; sorted output diffs cleanly between snapshots
+    accounts.sort_by_key(|(address, _)| address.to_bytes());
+    let mut rows = Vec::with_capacity(accounts.len());
+    let mut skipped = 0;
+    for (address, account) in &accounts {
+        match decode(&account.data) {
+            Ok(account) => rows.push(row(address, &account)),
+            Err(e) => {
+                eprintln!("skipping {kind} {address}: {e:#}");
+                skipped += 1;
+            }
+        }
+    }
+    Ok(Table {
+        kind,
+        program,
+        schema,
+        slot,
+        rows,
+        skipped,
+    })
+}
+
+fn write_table(table: &Table, path: &Path, format: Format) -> Result<()> {
+    match format {
+        Format::Json => {
+            let objects: Vec<Map<String, Value>> = table
+                .rows
+                .iter()
+                .map(|row| {
+                    table
+                        .schema
+                        .iter()
+                        .map(|&(name, _)| name.to_string())
+                        .zip(row.iter().cloned())
+                        .collect()
+                })
+                .collect();
+            fs::write(path, serde_json::to_string_pretty(&objects)? + "\n")
+        }
+        Format::Csv => {
+            let mut out = csv::Writer::from_path(path)?;
+            out.write_record(table.schema.iter().map(|&(name, _)| name))?;
+            for row in &table.rows {
+                out.write_record(row.iter().map(|value| match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                }))?;
+            }
+            out.flush()
+        }
+    }
+    .with_context(|| format!("writing {}", path.display()))
+}
@@ -0,0 +1,189 @@
+//! Squads v4 proposals: admin instructions wrapped in a vault transaction, for a governor
+//! that is a Squads vault rather than a key on this machine.
+//!
+//! `--squads-multisig` makes every command build its instructions with the vault as
+//! signer, then send `vault_transaction_create` + `proposal_create` signed by the CLI
+//! keypair, which only needs to be a multisig member with the Initiate permission.
+//! Members vote and execute in the Squads app as with any other proposal.
+//!
+//! No Squads crate is a dependency; the few instructions and the vault message format are
+//! encoded here from the v4 IDL.
+
+use anyhow::{bail, ensure, Context, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+
+pub const PROGRAM_ID: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+/// `Multisig.transaction_index`: after the discriminator, `create_key`,
+/// `config_authority`, `threshold: u16` and `time_lock: u32`.
+const TRANSACTION_INDEX_OFFSET: usize = 8 + 32 + 32 + 2 + 4;
+
+#[derive(Clone, Copy)]
+pub struct Squads {
+    pub multisig: Pubkey,
+    pub vault_index: u8,
+}
+
+impl Squads {
+    pub fn vault(&self) -> Pubkey {
+        pda(&[
+            b"multisig",
+            self.multisig.as_ref(),
+            b"vault",
+            &[self.vault_index],
+        ])
+    }
+
+    fn transaction(&self, index: u64) -> Pubkey {
+        pda(&[
+            b"multisig",
+            self.multisig.as_ref(),
+            b"transaction",
+            &index.to_le_bytes(),
+        ])
+    }
+
+    fn proposal(&self, index: u64) -> Pubkey {
+        pda(&[
+            b"multisig",
+            self.multisig.as_ref(),
+            b"transaction",
+            &index.to_le_bytes(),
+            b"proposal",
+        ])
+    }
+
+    /// Index the next vault transaction will take.
+    pub fn next_transaction_index(&self, client: &RpcClient) -> Result<u64> {
+        let account = client
+            .get_account(&self.multisig)
+            .with_context(|| format!("fetching multisig {}", self.multisig))?;
+        ensure!(
+            account.owner == PROGRAM_ID,
+            "{} is not a Squads v4 multisig",
+            self.multisig
+        );
+        let bytes = account
+            .data
+            .get(TRANSACTION_INDEX_OFFSET..TRANSACTION_INDEX_OFFSET + 8)
+            .context("multisig account too short")?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()) + 1)
+    }
+
+    /// `vault_transaction_create` and `proposal_create` for `ixs` as vault transaction
+    /// `index`, created and paid for by `creator`.
+    pub fn propose(
+        &self,
+        creator: &Pubkey,
+        index: u64,
+        ixs: &[Instruction],
+        memo: Option<&str>,
+    ) -> Result<Vec<Instruction>> {
+        let vault = self.vault();
+        for ix in ixs {
+            for meta in ix.accounts.iter().filter(|m| m.is_signer) {
+                if meta.pubkey != vault {
+                    bail!(
+                        "{} must sign as well; only the vault can sign a Squads proposal",
+                        meta.pubkey
+                    );
+                }
+            }
+        }
+
+        let mut data = discriminator("vault_transaction_create").to_vec();
+        data.push(self.vault_index);
+        data.push(0); // ephemeral signers
+        let message = transaction_message(&vault, ixs)?;
+        data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+        data.extend_from_slice(&message);
+        match memo {
+            Some(memo) => {
+                data.push(1);
+                data.extend_from_slice(&(memo.len() as u32).to_le_bytes());
+                data.extend_from_slice(memo.as_bytes());
+            }
+            None => data.push(0),
+        }
+        let create = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.multisig, false),
+                AccountMeta::new(self.transaction(index), false),
+                AccountMeta::new_readonly(*creator, true),
+                AccountMeta::new(*creator, true),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data,
+        };
+
+        let mut data = discriminator("proposal_create").to_vec();
+        data.extend_from_slice(&index.to_le_bytes());
+        data.push(0); // not a draft: open for voting right away
+        let propose = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.multisig, false),
+                AccountMeta::new(self.proposal(index), false),
+                AccountMeta::new_readonly(*creator, true),
+                AccountMeta::new(*creator, true),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data,
+        };
+        println!("squads vault:       {vault}");
+        println!("squads transaction: {index} ({})", self.transaction(index));
+        println!("squads proposal:    {}", self.proposal(index));
+        Ok(vec![create, propose])
+    }
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+}
+
+fn discriminator(name: &str) -> [u8; 8] {
+    hash(format!("global:{name}").as_bytes()).to_bytes()[..8]
+        .try_into()
+        .unwrap()
+}
+
+/// Squads' `TransactionMessage`: `ixs` compiled with the vault as payer, in the legacy
+/// account order, with u8 length prefixes except u16 for instruction data.
+fn transaction_message(vault: &Pubkey, ixs: &[Instruction]) -> Result<Vec<u8>> {
+    let message = Message::new(ixs, Some(vault));
+    let header = message.header;
+    let keys = &message.account_keys;
+    ensure!(
+        keys.len() <= u8::MAX as usize,
+        "too many accounts for one proposal"
+    );
+
+    let signers = header.num_required_signatures;
+    let mut out = vec![
+        signers,
+        signers - header.num_readonly_signed_accounts,
+        (keys.len() as u8) - signers - header.num_readonly_unsigned_accounts,
+        keys.len() as u8,
+    ];
+    for key in keys {
+        out.extend_from_slice(key.as_ref());
+    }
+    out.push(message.instructions.len() as u8);
+    for ix in &message.instructions {
+        out.push(ix.program_id_index);
+        out.push(ix.accounts.len() as u8);
+        out.extend_from_slice(&ix.accounts);
+        let len = u16::try_from(ix.data.len()).context("instruction data too long")?;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&ix.data);
+    }
+    out.push(0); // address table lookups
+    Ok(out)
+}
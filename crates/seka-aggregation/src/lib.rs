@@ -0,0 +1,469 @@
+//! Turns indexed sentinel activity into a seka cycle's leaf set.
+//!
+//! Sentinel tracks karma for its own rewards; seka claims need signed per-peer deltas
+//! for a cycle. This crate scores what the indexer recorded inside the cycle's time
+//! window under configurable [`Rules`], applies the `GlobalConfig` caps, and returns the
+//! exact leaves `seka_merkle::MerkleTree::new` should get.
+//!
+//! Activity is attributed to the post's owner at the time it happened: posts are
+//! assigned to their creator (`mint_nft`, `mint_core_post`, `reveal_post`) and follow
+//! `PostSold` / `PostOwnerSynced`. Ownership is replayed from the start of the index, so
+//! the store must hold the full history, not just the cycle.
+
+use anyhow::{Context, Result};
+use seka_merkle::Leaf;
+use seka_reputation::Signals;
+use sentinelkarma_indexer::projection::Model;
+use sentinelkarma_indexer::store::{EventRow, InstructionRow, Store};
+use sentinelkarma_sdk::decode_event;
+use sentinelkarma_sdk::sentinel::{
+    self, CommentCreated, CommentLiked, PostOwnerSynced, PostRemoved, PostSold, ReportResolved,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Post creation instructions; accounts are `user, state, peer, nft_mint | asset, ..`.
+const POST_CREATED: &[&str] = &["mint_nft", "mint_core_post", "reveal_post"];
+/// Accounts are `liker, state, like, post, ..`.
+const POST_LIKED: &str = "like_nft";
+const EVENTS: &[&str] = &[
+    "CommentCreated",
+    "CommentLiked",
+    "ReportResolved",
+    "PostRemoved",
+    "PostSold",
+    "PostOwnerSynced",
+];
+
+/// Points per action. Missing fields take the defaults, so a rules file only lists
+/// what it changes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    /// To the author, per post created
+    pub post_created: i64,
+    /// To the post's owner, per like
+    pub post_liked: i64,
+    /// To the author, per comment
+    pub comment_created: i64,
+    /// To the comment's author, per like
+    pub comment_liked: i64,
+    /// To the post's owner when a report against it is upheld
+    pub report_upheld_owner: i64,
+    /// To the reporter when their report is upheld
+    pub report_upheld_reporter: i64,
+    /// To the reporter when their report is rejected
+    pub report_rejected_reporter: i64,
+    /// To the post's owner when a moderator removes it
+    pub post_removed_owner: i64,
+    /// Likes from one liker to one recipient counted per cycle; 0 for no limit
+    pub max_likes_per_pair: u32,
+    /// JSON array of posts whose creation and likes score nothing, such as the
+    /// `unreachable.json` sentinel-pinning keeps; re-read on every aggregation
+    pub excluded_posts: Option<PathBuf>,
+    /// Likes from accounts whose reputation at the start of the cycle is under this
+    /// score nothing; 0 to count every like
+    pub min_liker_reputation: f64,
+    /// Weights for `min_liker_reputation`
+    pub reputation: seka_reputation::Params,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            post_created: 1,
+            post_liked: 2,
+            comment_created: 0,
+            comment_liked: 1,
+            report_upheld_owner: -10,
+            report_upheld_reporter: 3,
+            report_rejected_reporter: -2,
+            post_removed_owner: -10,
+            max_likes_per_pair: 5,
+            excluded_posts: None,
+            min_liker_reputation: 0.0,
+            reputation: seka_reputation::Params::default(),
+        }
+    }
+}
+
+impl Rules {
+    pub fn read(path: &Path) -> Result<Self> {
+        serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))
+    }
+
+    /// The posts listed in `excluded_posts`, if set.
+    pub fn read_excluded_posts(&self) -> Result<HashSet<Pubkey>> {
+        let Some(path) = &self.excluded_posts else {
+            return Ok(HashSet::new());
+        };
+        let posts: Vec<String> = serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))?;
+        posts
+            .iter()
+            .map(|p| Pubkey::from_str(p).with_context(|| format!("{}: {p}", path.display())))
+            .collect()
+    }
+}
+
+/// A closed seka cycle, `[start_ts, end_ts)` in cluster time, and the caps its leaves
+/// must respect.
+#[derive(Clone, Copy, Debug)]
+pub struct Window {
+    pub cycle_index: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    /// `GlobalConfig.per_peer_cycle_cap`
+    pub per_peer_cap: i32,
+    /// `GlobalConfig.max_points_per_cycle`
+    pub max_points: u32,
+}
+
+impl Window {
+    /// Cycle `cycle_index` of the seka schedule in `config`.
+    pub fn new(config: &sentinelkarma_sdk::seka::GlobalConfig, cycle_index: u64) -> Self {
+        let start_ts = config.start_ts + (cycle_index * config.cycle_secs) as i64;
+        Self {
+            cycle_index,
+            start_ts,
+            end_ts: start_ts + config.cycle_secs as i64,
+            per_peer_cap: config.per_peer_cycle_cap,
+            max_points: config.max_points_per_cycle,
+        }
+    }
+
+    fn contains(&self, time: i64) -> bool {
+        self.start_ts <= time && time < self.end_ts
+    }
+}
+
+/// What went into a cycle's leaves.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// Scored actions by kind
+    pub actions: BTreeMap<&'static str, u64>,
+    /// Likes past `max_likes_per_pair`
+    pub likes_over_limit: u64,
+    /// Actions on posts whose creation is not in the index
+    pub unattributed: u64,
+    /// Posts created and likes on posts in `excluded_posts`
+    pub excluded: u64,
+    /// Likes from accounts under `min_liker_reputation`
+    pub untrusted_likes: u64,
+    /// Rows without a block time, which cannot be placed in a cycle
+    pub undated: u64,
+    /// Peers clamped to the per-peer cap
+    pub capped: u64,
+    /// Positive total before scaling to `max_points`, when it was over
+    pub scaled_from: Option<u64>,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, n) in &self.actions {
+            writeln!(f, "{kind}: {n}")?;
+        }
+        writeln!(f, "likes over the pair limit: {}", self.likes_over_limit)?;
+        writeln!(f, "unattributed: {}", self.unattributed)?;
+        writeln!(f, "excluded: {}", self.excluded)?;
+        writeln!(f, "likes from low reputation: {}", self.untrusted_likes)?;
+        writeln!(f, "undated: {}", self.undated)?;
+        writeln!(f, "capped peers: {}", self.capped)?;
+        if let Some(total) = self.scaled_from {
+            writeln!(f, "scaled down from {total} points")?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Aggregation {
+    /// Non-zero deltas ordered by owner, so reruns build the same tree
+    pub leaves: Vec<Leaf>,
+    pub stats: Stats,
+}
+
+/// Scores `window` from everything in `store`.
+pub fn aggregate(store: &mut dyn Store, window: &Window, rules: &Rules) -> Result<Aggregation> {
+    let mut instructions = Vec::new();
+    for name in POST_CREATED.iter().chain([&POST_LIKED]) {
+        instructions.extend(store.instructions("sentinel", Some(name), 0)?);
+    }
+    let mut events = Vec::new();
+    for name in EVENTS {
+        events.extend(store.events("sentinel", Some(name), 0)?);
+    }
+    let mut filters = Filters {
+        excluded_posts: rules.read_excluded_posts()?,
+        untrusted_likers: HashSet::new(),
+    };
+    if rules.min_liker_reputation > 0.0 {
+        let model = Model::load(store)?;
+        let params = &rules.reputation;
+        let signals = Signals::collect(&model, params, window.start_ts);
+        let likers = instructions
+            .iter()
+            .filter(|r| r.name == POST_LIKED)
+            .filter_map(|r| r.accounts.first().and_then(|a| Pubkey::from_str(a).ok()))
+            .chain(
+                events
+                    .iter()
+                    .filter_map(|r| decode_event::<CommentLiked>(&r.payload).map(|e| e.liker)),
+            );
+        for liker in likers {
+            let signals = signals.get(&liker).cloned().unwrap_or_default();
+            if signals.score(params, window.start_ts).score < rules.min_liker_reputation {
+                filters.untrusted_likers.insert(liker);
+            }
+        }
+    }
+    Ok(score(&instructions, &events, window, rules, &filters))
+}
+
+/// Actions [`score`] leaves out. Penalties against excluded posts still apply.
+#[derive(Clone, Debug, Default)]
+pub struct Filters {
+    /// Posts whose creation and likes score nothing
+    pub excluded_posts: HashSet<Pubkey>,
+    /// Accounts whose post and comment likes score nothing
+    pub untrusted_likers: HashSet<Pubkey>,
+}
+
+enum Row<'a> {
+    Instruction(&'a InstructionRow),
+    Event(&'a EventRow),
+}
+
+impl Row<'_> {
+    fn position(&self) -> (u64, &str, u32, u8) {
+        match self {
+            Row::Instruction(r) => (r.slot, &r.signature, r.ix_index, 0),
+            Row::Event(r) => (r.slot, &r.signature, r.event_index, 1),
+        }
+    }
+
+    fn block_time(&self) -> Option<i64> {
+        match self {
+            Row::Instruction(r) => r.block_time,
+            Row::Event(r) => r.block_time,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    deltas: HashMap<Pubkey, i64>,
+    /// Counted likes per (liker, recipient)
+    pairs: HashMap<(Pubkey, Pubkey), u32>,
+    stats: Stats,
+}
+
+impl Tally {
+    fn add(&mut self, kind: &'static str, peer: Pubkey, points: i64) {
+        *self.stats.actions.entry(kind).or_default() += 1;
+        if points != 0 {
+            let delta = self.deltas.entry(peer).or_default();
+            *delta = delta.saturating_add(points);
+        }
+    }
+
+    fn like(
+        &mut self,
+        kind: &'static str,
+        liker: Pubkey,
+        recipient: Pubkey,
+        points: i64,
+        limit: u32,
+    ) {
+        let count = self.pairs.entry((liker, recipient)).or_default();
+        if limit > 0 && *count >= limit {
+            self.stats.likes_over_limit += 1;
+            return;
+        }
+        *count += 1;
+        self.add(kind, recipient, points);
+    }
+}
+
+/// Scores `window` from indexed rows. Rows outside the window still update post
+/// ownership, so pass the full history.
+pub fn score(
+    instructions: &[InstructionRow],
+    events: &[EventRow],
+    window: &Window,
+    rules: &Rules,
+    filters: &Filters,
+) -> Aggregation {
+    let excluded = &filters.excluded_posts;
+    let mut rows: Vec<Row> = instructions
+        .iter()
+        .filter(|r| r.program == "sentinel")
+        .map(Row::Instruction)
+        .chain(
+            events
+                .iter()
+                .filter(|r| r.program == "sentinel")
+                .map(Row::Event),
+        )
+        .collect();
+    // Transactions within a slot have no recorded order; signatures keep it stable
+    rows.sort_by(|a, b| a.position().cmp(&b.position()));
+
+    let mut owners: HashMap<Pubkey, Pubkey> = HashMap::new();
+    let mut tally = Tally::default();
+    for row in rows {
+        let in_window = match row.block_time() {
+            Some(time) => window.contains(time),
+            None => {
+                tally.stats.undated += 1;
+                false
+            }
+        };
+        match row {
+            Row::Instruction(r) => {
+                let account = |i: usize| r.accounts.get(i).and_then(|a| Pubkey::from_str(a).ok());
+                let (Some(user), Some(target)) = (account(0), account(3)) else {
+                    continue;
+                };
+                if r.name == POST_LIKED {
+                    if !in_window {
+                        continue;
+                    }
+                    if excluded.contains(&target) {
+                        tally.stats.excluded += 1;
+                        continue;
+                    }
+                    if filters.untrusted_likers.contains(&user) {
+                        tally.stats.untrusted_likes += 1;
+                        continue;
+                    }
+                    match owners.get(&target) {
+                        Some(&owner) => tally.like(
+                            "post_liked",
+                            user,
+                            owner,
+                            rules.post_liked,
+                            rules.max_likes_per_pair,
+                        ),
+                        None => tally.stats.unattributed += 1,
+                    }
+                } else {
+                    let post = sentinel::post_pda(&target);
+                    owners.insert(post, user);
+                    if in_window && excluded.contains(&post) {
+                        tally.stats.excluded += 1;
+                    } else if in_window {
+                        tally.add("post_created", user, rules.post_created);
+                    }
+                }
+            }
+            Row::Event(r) => match r.name.as_str() {
+                "PostSold" => {
+                    if let Some(e) = decode_event::<PostSold>(&r.payload) {
+                        owners.insert(e.post, e.buyer);
+                    }
+                }
+                "PostOwnerSynced" => {
+                    if let Some(e) = decode_event::<PostOwnerSynced>(&r.payload) {
+                        owners.insert(e.post, e.owner);
+                    }
+                }
+                _ if !in_window => {}
+                "CommentCreated" => {
+                    if let Some(e) = decode_event::<CommentCreated>(&r.payload) {
+                        tally.add("comment_created", e.author, rules.comment_created);
+                    }
+                }
+                "CommentLiked" => {
+                    if let Some(e) = decode_event::<CommentLiked>(&r.payload) {
+                        if filters.untrusted_likers.contains(&e.liker) {
+                            tally.stats.untrusted_likes += 1;
+                            continue;
+                        }
+                        tally.like(
+                            "comment_liked",
+                            e.liker,
+                            e.author,
+                            rules.comment_liked,
+                            rules.max_likes_per_pair,
+                        );
+                    }
+                }
+                "ReportResolved" => {
+                    let Some(e) = decode_event::<ReportResolved>(&r.payload) else {
+                        continue;
+                    };
+                    if !e.upheld {
+                        tally.add(
+                            "report_rejected",
+                            e.reporter,
+                            rules.report_rejected_reporter,
+                        );
+                        continue;
+                    }
+                    tally.add("report_upheld", e.reporter, rules.report_upheld_reporter);
+                    match owners.get(&e.post) {
+                        Some(&owner) => {
+                            tally.add("post_reported", owner, rules.report_upheld_owner)
+                        }
+                        None => tally.stats.unattributed += 1,
+                    }
+                }
+                "PostRemoved" => {
+                    let Some(e) = decode_event::<PostRemoved>(&r.payload) else {
+                        continue;
+                    };
+                    match owners.get(&e.post) {
+                        Some(&owner) => tally.add("post_removed", owner, rules.post_removed_owner),
+                        None => tally.stats.unattributed += 1,
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    let mut stats = tally.stats;
+    let leaves = apply_caps(tally.deltas, window, &mut stats);
+    Aggregation { leaves, stats }
+}
+
+/// Clamps each peer to the per-peer cap, then scales positive deltas down so they fit
+/// `max_points`. Zero deltas are dropped.
+fn apply_caps(deltas: HashMap<Pubkey, i64>, window: &Window, stats: &mut Stats) -> Vec<Leaf> {
+    let cap = i64::from(window.per_peer_cap.max(0));
+    let mut deltas: Vec<(Pubkey, i64)> = deltas
+        .into_iter()
+        .map(|(owner, delta)| {
+            if delta.abs() > cap {
+                stats.capped += 1;
+            }
+            (owner, delta.clamp(-cap, cap))
+        })
+        .collect();
+
+    let total: u64 = deltas.iter().map(|&(_, d)| d.max(0) as u64).sum();
+    let max = u64::from(window.max_points);
+    if total > max {
+        stats.scaled_from = Some(total);
+        for (_, delta) in deltas.iter_mut().filter(|(_, d)| *d > 0) {
+            // Rounds down, so the scaled total never exceeds max
+            *delta = (*delta as u128 * max as u128 / total as u128) as i64;
+        }
+    }
+
+    deltas.sort_by_key(|&(owner, _)| owner.to_bytes());
+    deltas
+        .into_iter()
+        .filter(|&(_, d)| d != 0)
+        .map(|(owner, delta)| Leaf {
+            owner,
+            delta_points: delta as i32,
+        })
+        .collect()
+}
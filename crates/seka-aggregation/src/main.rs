@@ -0,0 +1,67 @@
+//! seka-aggregate: writes one cycle's leaf set from the indexer database, in the input
+//! format of `seka-proofs build` and `seka-keeper --source file:`.
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use seka_aggregation::{aggregate, Rules, Window};
+use seka_proofs::{write_json, InputRow};
+use sentinelkarma_sdk::{fetch_account, seka};
+use solana_client::rpc_client::RpcClient;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Compute a seka cycle's karma deltas from indexed sentinel activity")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL", default_value = "sqlite:indexer.db")]
+    database_url: String,
+
+    #[arg(long)]
+    cycle: u64,
+
+    /// JSON scoring rules; see rules.example.json
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// `.csv` or `.json` output file
+    #[arg(long)]
+    out: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rules = match &cli.rules {
+        Some(path) => Rules::read(path)?,
+        None => Rules::default(),
+    };
+    let rpc = RpcClient::new(cli.rpc_url);
+    let config: seka::GlobalConfig = fetch_account(&rpc, &seka::config_pda())?;
+    let window = Window::new(&config, cli.cycle);
+
+    let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+    let aggregation = aggregate(store.as_mut(), &window, &rules)?;
+    let rows: Vec<InputRow> = aggregation.leaves.iter().map(InputRow::from).collect();
+    match cli.out.extension().and_then(|e| e.to_str()) {
+        Some("csv") => {
+            let mut out = csv::Writer::from_path(&cli.out)?;
+            for row in &rows {
+                out.serialize(row)?;
+            }
+            out.flush()?;
+        }
+        Some("json") => write_json(&cli.out, &rows)?,
+        _ => bail!("--out must be a .csv or .json file"),
+    }
+
+    print!("{}", aggregation.stats);
+    println!(
+        "cycle {}: {} peers written to {}",
+        cli.cycle,
+        rows.len(),
+        cli.out.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,390 @@
+//! seka-api: the REST endpoints a wallet or web client needs, over the indexer database.
+//!
+//! Served from the same in-memory projection as seka-graphql. Pubkeys are base58 and
+//! 64-bit integers are decimal strings. Lists take `limit` (default 50, at most 500)
+//! and `offset`.
+//!
+//! - `GET /status` returns the indexed slot and the current seka cycle
+//! - `GET /peers/top?by=points|karma|reputation[&cycle=N]` ranks members by ledger
+//!   points, by karma claimed (overall or in one cycle) or by reputation score
+//! - `GET /cycles/{idx}/trending` ranks live posts by likes received during cycle `idx`
+//!   (or `current`)
+//! - `GET /members/{owner}/claims` returns a member's ledger and claims, newest first
+//! - `GET /members/{owner}/reputation` returns a member's reputation score and the
+//!   signals behind it, under seka-reputation's default weights
+
+use anyhow::Result;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use clap::Parser;
+use seka_aggregation::Window;
+use seka_reputation::{Params, Signals};
+use sentinelkarma_indexer::projection::{self, Model};
+use sentinelkarma_sdk::{fetch_account, seka};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(Parser)]
+#[command(about = "Serve leaderboards, trending posts and claim history over HTTP")]
+struct Cli {
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// Read once at startup for the seka cycle schedule
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    #[arg(long, env = "LISTEN", default_value = "127.0.0.1:8093")]
+    listen: SocketAddr,
+
+    /// How often to read newly indexed rows
+    #[arg(long, default_value_t = 5)]
+    refresh_secs: u64,
+
+    /// How often to rebuild from every row, picking up backfills
+    #[arg(long, default_value_t = 3600)]
+    rebuild_secs: u64,
+}
+
+struct AppState {
+    model: Arc<RwLock<Model>>,
+    config: seka::GlobalConfig,
+}
+
+impl AppState {
+    fn current_cycle(&self) -> u64 {
+        (now() - self.config.start_ts).max(0) as u64 / self.config.cycle_secs.max(1)
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn parse_owner(owner: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(owner).map_err(|_| ApiError::BadRequest(format!("invalid pubkey {owner:?}")))
+}
+
+enum ApiError {
+    BadRequest(String),
+    NotFound(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct Page {
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+impl Page {
+    fn take<T>(&self, items: impl IntoIterator<Item = T>) -> Result<Vec<T>, ApiError> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit > MAX_LIMIT {
+            return Err(ApiError::BadRequest(format!(
+                "limit may be at most {MAX_LIMIT}"
+            )));
+        }
+        Ok(items.into_iter().skip(self.offset).take(limit).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct TopQuery {
+    by: String,
+    cycle: Option<u64>,
+}
+
+async fn get_status(State(state): State<Arc<AppState>>) -> Json<Value> {
+    let slot = state.model.read().unwrap().slot;
+    Json(json!({
+        "slot": slot.to_string(),
+        "current_cycle": state.current_cycle().to_string(),
+        "cycle_secs": state.config.cycle_secs.to_string(),
+    }))
+}
+
+async fn get_top_peers(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopQuery>,
+    Query(page): Query<Page>,
+) -> Result<Json<Value>, ApiError> {
+    let model = state.model.read().unwrap();
+    let mut totals: BTreeMap<Pubkey, i64> = BTreeMap::new();
+    match (query.by.as_str(), query.cycle) {
+        ("reputation", None) => return top_by_reputation(&model, &page),
+        ("reputation", Some(_)) => {
+            return Err(ApiError::BadRequest("reputation is not per cycle".into()));
+        }
+        ("points", None) => totals.extend(model.ledgers.iter().map(|(o, l)| (*o, l.points))),
+        ("points", Some(_)) => {
+            return Err(ApiError::BadRequest(
+                "points are not per cycle; use by=karma".into(),
+            ));
+        }
+        ("karma", None) => totals.extend(
+            model
+                .ledgers
+                .iter()
+                .map(|(o, l)| (*o, l.karma_claimed as i64)),
+        ),
+        ("karma", Some(cycle)) => {
+            for claim in model.claims.iter().filter(|c| c.cycle_index == cycle) {
+                *totals.entry(claim.owner).or_default() += claim.delta_points.max(0) as i64;
+            }
+        }
+        (by, _) => {
+            return Err(ApiError::BadRequest(format!(
+                "by must be points, karma or reputation, not {by:?}"
+            )));
+        }
+    }
+    let mut ranked: Vec<(Pubkey, i64)> = totals.into_iter().filter(|(_, v)| *v != 0).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let total = ranked.len();
+    let peers: Vec<Value> = page
+        .take(ranked.into_iter().enumerate())?
+        .into_iter()
+        .map(|(i, (owner, value))| {
+            json!({
+                "rank": i + 1,
+                "owner": owner.to_string(),
+                "value": value.to_string(),
+                "active": model.peers.get(&owner).is_some_and(|p| p.active),
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "by": query.by,
+        "cycle": query.cycle.map(|c| c.to_string()),
+        "total": total,
+        "peers": peers,
+    })))
+}
+
+fn top_by_reputation(model: &Model, page: &Page) -> Result<Json<Value>, ApiError> {
+    let mut ranked: Vec<(Pubkey, f64)> =
+        seka_reputation::score_all(model, &Params::default(), now())
+            .into_iter()
+            .filter(|(owner, _)| model.peers.contains_key(owner))
+            .map(|(owner, r)| (owner, r.score))
+            .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let total = ranked.len();
+    let peers: Vec<Value> = page
+        .take(ranked.into_iter().enumerate())?
+        .into_iter()
+        .map(|(i, (owner, score))| {
+            json!({
+                "rank": i + 1,
+                "owner": owner.to_string(),
+                "value": score.to_string(),
+                "active": model.peers.get(&owner).is_some_and(|p| p.active),
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "by": "reputation",
+        "cycle": null,
+        "total": total,
+        "peers": peers,
+    })))
+}
+
+async fn get_trending(
+    State(state): State<Arc<AppState>>,
+    Path(idx): Path<String>,
+    Query(page): Query<Page>,
+) -> Result<Json<Value>, ApiError> {
+    let cycle = match idx.as_str() {
+        "current" => state.current_cycle(),
+        idx => idx
+            .parse()
+            .map_err(|_| ApiError::BadRequest(format!("invalid cycle {idx:?}")))?,
+    };
+    let window = Window::new(&state.config, cycle);
+    let model = state.model.read().unwrap();
+    let mut likes: HashMap<Pubkey, u32> = HashMap::new();
+    for like in &model.likes {
+        if like
+            .at
+            .block_time
+            .is_some_and(|t| window.start_ts <= t && t < window.end_ts)
+        {
+            *likes.entry(like.post).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(&projection::Post, u32)> = likes
+        .into_iter()
+        .filter_map(|(post, n)| model.posts.get(&post).map(|p| (p, n)))
+        .filter(|(post, _)| post.status.is_none())
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.address.cmp(&b.0.address)));
+    let total = ranked.len();
+    let posts: Vec<Value> = page
+        .take(ranked.into_iter().enumerate())?
+        .into_iter()
+        .map(|(i, (post, n))| {
+            json!({
+                "rank": i + 1,
+                "post": post.address.to_string(),
+                "asset": post.asset.to_string(),
+                "author": post.author.to_string(),
+                "owner": post.owner.to_string(),
+                "cycle_likes": n,
+                "likes": post.likes,
+                "slot": post.created.slot.to_string(),
+                "block_time": post.created.block_time.map(|t| t.to_string()),
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "cycle": cycle.to_string(),
+        "start_ts": window.start_ts.to_string(),
+        "end_ts": window.end_ts.to_string(),
+        "total": total,
+        "posts": posts,
+    })))
+}
+
+async fn get_claims(
+    State(state): State<Arc<AppState>>,
+    Path(owner): Path<String>,
+    Query(page): Query<Page>,
+) -> Result<Json<Value>, ApiError> {
+    let owner = parse_owner(&owner)?;
+    let model = state.model.read().unwrap();
+    let Some(ledger) = model.ledgers.get(&owner) else {
+        return Err(ApiError::NotFound(format!("{owner} has no ledger")));
+    };
+    let history: Vec<_> = model
+        .claims
+        .iter()
+        .rev()
+        .filter(|c| c.owner == owner)
+        .collect();
+    let total = history.len();
+    let claims: Vec<Value> = page
+        .take(history)?
+        .into_iter()
+        .map(|claim| {
+            json!({
+                "cycle": claim.cycle_index.to_string(),
+                "delta_points": claim.delta_points,
+                "new_points": claim.new_points.to_string(),
+                "signature": claim.at.signature,
+                "slot": claim.at.slot.to_string(),
+                "block_time": claim.at.block_time.map(|t| t.to_string()),
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "owner": owner.to_string(),
+        "points": ledger.points.to_string(),
+        "last_cycle_claimed": ledger.last_cycle_claimed.to_string(),
+        "karma_claimed": ledger.karma_claimed.to_string(),
+        "total": total,
+        "claims": claims,
+    })))
+}
+
+async fn get_reputation(
+    State(state): State<Arc<AppState>>,
+    Path(owner): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let owner = parse_owner(&owner)?;
+    let model = state.model.read().unwrap();
+    if !model.peers.contains_key(&owner) {
+        return Err(ApiError::NotFound(format!("{owner} is not a member")));
+    }
+    let as_of = now();
+    let params = Params::default();
+    let signals = Signals::of(&model, &params, &owner, as_of);
+    let reputation = signals.score(&params, as_of);
+    Ok(Json(json!({
+        "owner": owner.to_string(),
+        "as_of": as_of.to_string(),
+        "score": reputation.score,
+        "components": {
+            "points": reputation.points,
+            "history": reputation.history,
+            "reports": reputation.reports,
+            "age": reputation.age,
+            "penalty": reputation.penalty,
+        },
+        "signals": {
+            "points": signals.points.to_string(),
+            "cycles_claimed": signals.cycles_claimed.to_string(),
+            "reports_upheld": signals.reports_upheld,
+            "reports_rejected": signals.reports_rejected,
+            "posts_upheld_against": signals.posts_upheld_against,
+            "joined_at": signals.joined_at.map(|t| t.to_string()),
+        },
+    })))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new(cli.rpc_url.clone());
+    let config: seka::GlobalConfig =
+        tokio::task::spawn_blocking(move || fetch_account(&rpc, &seka::config_pda())).await??;
+    let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+    let model = Model::load(store.as_mut())?;
+    println!(
+        "loaded {} ledgers, {} claims, {} posts up to slot {}",
+        model.ledgers.len(),
+        model.claims.len(),
+        model.posts.len(),
+        model.slot
+    );
+    drop(store);
+
+    let model = Arc::new(RwLock::new(model));
+    {
+        let model = model.clone();
+        let every = Duration::from_secs(cli.refresh_secs.max(1));
+        let rebuild = Duration::from_secs(cli.rebuild_secs.max(1));
+        let database_url = cli.database_url.clone();
+        std::thread::spawn(move || projection::follow(model, &database_url, every, rebuild));
+    }
+    let state = Arc::new(AppState { model, config });
+    let app = Router::new()
+        .route("/status", get(get_status))
+        .route("/peers/top", get(get_top_peers))
+        .route("/cycles/{idx}/trending", get(get_trending))
+        .route("/members/{owner}/claims", get(get_claims))
+        .route("/members/{owner}/reputation", get(get_reputation))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(cli.listen).await?;
+    println!("listening on http://{}", cli.listen);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,263 @@
+//! seka-audit: replays every indexed seka event and checks the result against the chain.
+//!
+//! Ledger balances and `last_cycle_claimed`, cycle roots and claim bitmaps, membership
+//! flags and the SEKA mint supply are rebuilt from the indexer's rows alone and compared
+//! with the live accounts. Any difference means the index missed a transaction or the
+//! program did something its events do not say; both are reported as drift and the
+//! process exits with status 1.
+//!
+//! The chain keeps moving while the audit runs. With `--catch-up` the index is brought
+//! up to date before each pass and, when drift is found, a second pass is made; only
+//! drift seen in both passes is reported.
+
+mod replay;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use replay::{Finding, Ledger, Replay};
+use sentinelkarma_indexer::{catch_up, Program, Store};
+use sentinelkarma_sdk::anchor_lang::{AccountDeserialize, Discriminator};
+use sentinelkarma_sdk::{decode_account, fetch_account, seka, Versioned};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Check that indexed seka events reproduce on-chain state")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL", default_value = "sqlite:indexer.db")]
+    database_url: String,
+
+    /// Index new seka transactions before each pass, and confirm drift with a second pass
+    #[arg(long)]
+    catch_up: bool,
+}
+
+/// Live seka accounts.
+struct Chain {
+    ledgers: BTreeMap<Pubkey, seka::PeerLedger>,
+    cycles: BTreeMap<u64, seka::CycleState>,
+    members: BTreeMap<Pubkey, seka::Membership>,
+    supply: u128,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.rpc_url, CommitmentConfig::confirmed());
+    let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+
+    let mut findings = pass(&rpc, store.as_mut(), cli.catch_up)?;
+    if cli.catch_up && !findings.is_empty() {
+        println!("{} findings; confirming with a second pass", findings.len());
+        std::thread::sleep(Duration::from_secs(5));
+        let first: BTreeSet<String> = findings.into_iter().map(|f| f.subject).collect();
+        findings = pass(&rpc, store.as_mut(), true)?;
+        findings.retain(|f| first.contains(&f.subject));
+    }
+
+    for finding in &findings {
+        println!("DRIFT {}: {}", finding.subject, finding.detail);
+    }
+    if findings.is_empty() {
+        println!("no drift");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("{} findings", findings.len());
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn pass(rpc: &RpcClient, store: &mut dyn Store, catch_up_first: bool) -> Result<Vec<Finding>> {
+    if catch_up_first {
+        let n = catch_up(rpc, store, Program::Seka)?;
+        println!("indexed {n} new seka transactions");
+    }
+    let mut replay = Replay::from_store(store)?;
+    let chain = Chain::fetch(rpc)?;
+    println!(
+        "replayed {} events: {} ledgers, {} cycles, {} members; chain has {}, {}, {}",
+        replay.events,
+        replay.ledgers.len(),
+        replay.cycles.len(),
+        replay.members.len(),
+        chain.ledgers.len(),
+        chain.cycles.len(),
+        chain.members.len()
+    );
+    compare(&mut replay, &chain);
+    Ok(replay.findings)
+}
+
+impl Chain {
+    fn fetch(rpc: &RpcClient) -> Result<Self> {
+        let config: seka::GlobalConfig = fetch_account(rpc, &seka::config_pda())?;
+        let supply = rpc.get_token_supply(&config.mint)?;
+        Ok(Self {
+            ledgers: program_accounts::<seka::PeerLedger>(rpc)?
+                .into_iter()
+                .map(|l| (l.owner, l))
+                .collect(),
+            cycles: program_accounts::<seka::CycleState>(rpc)?
+                .into_iter()
+                .map(|c| (c.cycle_index, c))
+                .collect(),
+            members: program_accounts::<seka::Membership>(rpc)?
+                .into_iter()
+                .map(|m| (m.owner, m))
+                .collect(),
+            supply: supply.amount.parse().context("parsing mint supply")?,
+        })
+    }
+}
+
+fn program_accounts<T: AccountDeserialize + Discriminator + Versioned>(rpc: &RpcClient) -> Result<Vec<T>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            T::DISCRIMINATOR.to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    rpc.get_program_accounts_with_config(&seka::ID, config)?
+        .into_iter()
+        .map(|(address, account)| {
+            decode_account(&account.data).with_context(|| format!("decoding {address}"))
+        })
+        .collect()
+}
+
+fn compare(replay: &mut Replay, chain: &Chain) {
+    let mut found = Vec::new();
+    let mut find = |subject: String, detail: String| found.push(Finding { subject, detail });
+
+    let owners: BTreeSet<&Pubkey> = replay.ledgers.keys().chain(chain.ledgers.keys()).collect();
+    for owner in owners {
+        let replayed = replay.ledgers.get(owner);
+        let live = chain.ledgers.get(owner).map(|l| Ledger {
+            points: l.points,
+            last_cycle_claimed: l.last_cycle_claimed,
+        });
+        match (replayed, live) {
+            (Some(r), Some(l)) if *r == l => {}
+            (Some(r), Some(l)) => find(
+                format!("ledger {owner}"),
+                format!(
+                    "chain has {} points (last cycle {}), events give {} (last cycle {})",
+                    l.points, l.last_cycle_claimed, r.points, r.last_cycle_claimed
+                ),
+            ),
+            (Some(r), None) => find(
+                format!("ledger {owner}"),
+                format!(
+                    "events give {} points but the account does not exist",
+                    r.points
+                ),
+            ),
+            (None, Some(l)) => find(
+                format!("ledger {owner}"),
+                format!("account has {} points but no events touch it", l.points),
+            ),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    let cycles: BTreeSet<u64> = replay
+        .cycles
+        .keys()
+        .chain(chain.cycles.keys())
+        .copied()
+        .collect();
+    for index in cycles {
+        let subject = format!("cycle {index}");
+        let (Some(r), Some(l)) = (replay.cycles.get(&index), chain.cycles.get(&index)) else {
+            let side = if chain.cycles.contains_key(&index) {
+                "has no indexed CycleRootSet"
+            } else {
+                "has no CycleState account"
+            };
+            find(subject, side.into());
+            continue;
+        };
+        if r.merkle_root != l.merkle_root || r.total_points_declared != l.total_points_declared {
+            find(
+                subject.clone(),
+                format!(
+                    "chain root {} for {} points, events {} for {}",
+                    hex::encode(l.merkle_root),
+                    l.total_points_declared,
+                    hex::encode(r.merkle_root),
+                    r.total_points_declared
+                ),
+            );
+        }
+        let bits: BTreeSet<u32> = (0..l.claims_bitmap.len() as u32 * 8)
+            .filter(|&i| l.is_claimed(i))
+            .collect();
+        if bits != r.claimed {
+            let missing: Vec<_> = bits.difference(&r.claimed).take(10).collect();
+            let extra: Vec<_> = r.claimed.difference(&bits).take(10).collect();
+            find(
+                subject.clone(),
+                format!("claimed leaves not indexed: {missing:?}; indexed but not set: {extra:?}"),
+            );
+        }
+        if r.claim_events as usize != bits.len() {
+            find(
+                subject.clone(),
+                format!(
+                    "{} claim bits set but {} KarmaClaimed events",
+                    bits.len(),
+                    r.claim_events
+                ),
+            );
+        }
+        if r.points_claimed > u64::from(l.total_points_declared) {
+            find(
+                subject,
+                format!(
+                    "{} points claimed, over the {} declared",
+                    r.points_claimed, l.total_points_declared
+                ),
+            );
+        }
+    }
+
+    let members: BTreeSet<&Pubkey> = replay.members.keys().chain(chain.members.keys()).collect();
+    for member in members {
+        let replayed = replay.members.get(member).copied();
+        let live = chain.members.get(member).map(|m| m.active);
+        if replayed != live {
+            find(
+                format!("membership {member}"),
+                format!("chain active = {live:?}, events give {replayed:?}"),
+            );
+        }
+    }
+
+    match replay.minted {
+        Some(minted) if minted != chain.supply => find(
+            "mint supply".into(),
+            format!(
+                "chain has {} base units, events account for {minted}",
+                chain.supply
+            ),
+        ),
+        Some(_) => {}
+        None => println!("initialize is not indexed; mint supply not checked"),
+    }
+    replay.findings.extend(found);
+}
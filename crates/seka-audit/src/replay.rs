@@ -0,0 +1,243 @@
+//! Rebuilds seka state from indexed events and instructions alone.
+
+use anyhow::Result;
+use sentinelkarma_indexer::store::{EventRow, InstructionRow, Store};
+use sentinelkarma_sdk::decode_event;
+use sentinelkarma_sdk::seka::{
+    self, ClaimKarmaArgs, CycleRootSet, InitializeArgs, Joined, KarmaClaimed,
+    MembershipDeactivated, PointsConverted, PointsCredited, TokensIssued,
+};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Something that should not have happened, keyed by what it is about.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub subject: String,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ledger {
+    pub points: i64,
+    pub last_cycle_claimed: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Cycle {
+    pub merkle_root: [u8; 32],
+    pub total_points_declared: u32,
+    /// Leaf indices of indexed `claim_karma` instructions
+    pub claimed: BTreeSet<u32>,
+    pub claim_events: u32,
+    /// Sum of positive claimed deltas
+    pub points_claimed: u64,
+}
+
+#[derive(Default)]
+pub struct Replay {
+    pub ledgers: BTreeMap<Pubkey, Ledger>,
+    pub cycles: BTreeMap<u64, Cycle>,
+    pub members: BTreeMap<Pubkey, bool>,
+    /// Base units of SEKA minted; `None` until the `initialize` transaction is seen
+    pub minted: Option<u128>,
+    pub findings: Vec<Finding>,
+    pub events: usize,
+    decimals: Option<u8>,
+}
+
+enum Row<'a> {
+    Instruction(&'a InstructionRow),
+    Event(&'a EventRow),
+}
+
+impl Row<'_> {
+    fn position(&self) -> (u64, &str, u32, u8) {
+        match self {
+            Row::Instruction(r) => (r.slot, &r.signature, r.ix_index, 0),
+            Row::Event(r) => (r.slot, &r.signature, r.event_index, 1),
+        }
+    }
+}
+
+/// `new_points` reported per owner within one slot, checked once the slot is replayed.
+#[derive(Default)]
+struct SlotCheck {
+    reported: HashMap<Pubkey, BTreeSet<i64>>,
+    /// Owners that also converted points, which reports no balance
+    unreported: BTreeSet<Pubkey>,
+}
+
+impl Replay {
+    pub fn from_store(store: &mut dyn Store) -> Result<Self> {
+        let events = store.events("seka", None, 0)?;
+        let mut instructions = store.instructions("seka", Some("initialize"), 0)?;
+        instructions.extend(store.instructions("seka", Some("claim_karma"), 0)?);
+        Ok(Self::new(&instructions, &events))
+    }
+
+    pub fn new(instructions: &[InstructionRow], events: &[EventRow]) -> Self {
+        let mut rows: Vec<Row> = instructions
+            .iter()
+            .map(Row::Instruction)
+            .chain(events.iter().map(Row::Event))
+            .collect();
+        rows.sort_by(|a, b| a.position().cmp(&b.position()));
+
+        let mut replay = Replay {
+            events: events.len(),
+            ..Replay::default()
+        };
+        let mut slot = None;
+        let mut check = SlotCheck::default();
+        for row in rows {
+            let row_slot = row.position().0;
+            if slot != Some(row_slot) {
+                replay.check_slot(std::mem::take(&mut check));
+                slot = Some(row_slot);
+            }
+            match row {
+                Row::Instruction(r) => replay.apply_instruction(r),
+                Row::Event(r) => replay.apply_event(r, &mut check),
+            }
+        }
+        replay.check_slot(check);
+        replay
+    }
+
+    fn find(&mut self, subject: String, detail: String) {
+        self.findings.push(Finding { subject, detail });
+    }
+
+    fn base_units(&self, whole: u64) -> Option<u128> {
+        Some(whole as u128 * 10u128.pow(self.decimals?.into()))
+    }
+
+    fn apply_instruction(&mut self, row: &InstructionRow) {
+        match row.name.as_str() {
+            "initialize" => {
+                let Some(args) = seka::decode_args::<InitializeArgs>(&row.data) else {
+                    return self.find(
+                        format!("tx {}", row.signature),
+                        "undecodable initialize".into(),
+                    );
+                };
+                self.decimals = Some(args.decimals);
+                self.minted = self.base_units(args.airdrop_whole_tokens);
+            }
+            "claim_karma" => {
+                let Some(args) = seka::decode_args::<ClaimKarmaArgs>(&row.data) else {
+                    return self.find(
+                        format!("tx {}", row.signature),
+                        "undecodable claim_karma".into(),
+                    );
+                };
+                let Some(cycle) = self.cycles.get_mut(&args.cycle_index) else {
+                    return self.find(
+                        format!("cycle {}", args.cycle_index),
+                        format!("claimed in {} before any indexed root", row.signature),
+                    );
+                };
+                if !cycle.claimed.insert(args.leaf_index) {
+                    self.find(
+                        format!("cycle {}", args.cycle_index),
+                        format!(
+                            "leaf {} claimed twice (again in {})",
+                            args.leaf_index, row.signature
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_event(&mut self, row: &EventRow, check: &mut SlotCheck) {
+        let payload = &row.payload;
+        let decoded = match row.name.as_str() {
+            "CycleRootSet" => decode_event::<CycleRootSet>(payload).map(|e| {
+                let cycle = Cycle {
+                    merkle_root: e.merkle_root,
+                    total_points_declared: e.total_points_declared,
+                    ..Cycle::default()
+                };
+                if self.cycles.insert(e.cycle_index, cycle).is_some() {
+                    self.find(format!("cycle {}", e.cycle_index), "root set twice".into());
+                }
+            }),
+            "KarmaClaimed" => decode_event::<KarmaClaimed>(payload).map(|e| {
+                let ledger = self.ledgers.entry(e.owner).or_default();
+                // Same arithmetic as `claim_karma`
+                ledger.points = if e.delta_points >= 0 {
+                    ledger.points.saturating_add(e.delta_points as i64)
+                } else {
+                    ledger.points.saturating_sub(-(e.delta_points as i64))
+                };
+                ledger.last_cycle_claimed = ledger.last_cycle_claimed.max(e.cycle_index);
+                check
+                    .reported
+                    .entry(e.owner)
+                    .or_default()
+                    .insert(e.new_points);
+                let cycle = self.cycles.entry(e.cycle_index).or_default();
+                cycle.claim_events += 1;
+                cycle.points_claimed += e.delta_points.max(0) as u64;
+            }),
+            "PointsCredited" => decode_event::<PointsCredited>(payload).map(|e| {
+                let ledger = self.ledgers.entry(e.owner).or_default();
+                ledger.points = ledger.points.saturating_add(e.points as i64);
+                check
+                    .reported
+                    .entry(e.owner)
+                    .or_default()
+                    .insert(e.new_points);
+            }),
+            "PointsConverted" => decode_event::<PointsConverted>(payload).map(|e| {
+                let ledger = self.ledgers.entry(e.owner).or_default();
+                ledger.points = ledger.points.saturating_sub(e.points_spent as i64);
+                check.unreported.insert(e.owner);
+                let minted = self.base_units(e.tokens_minted);
+                self.minted = self.minted.zip(minted).map(|(a, b)| a + b);
+            }),
+            "TokensIssued" => decode_event::<TokensIssued>(payload).map(|e| {
+                self.minted = self.minted.map(|m| m + e.amount as u128);
+            }),
+            "Joined" => decode_event::<Joined>(payload).map(|e| {
+                self.members.insert(e.member, true);
+            }),
+            "MembershipDeactivated" => decode_event::<MembershipDeactivated>(payload).map(|e| {
+                self.members.insert(e.member, false);
+            }),
+            _ => Some(()),
+        };
+        if decoded.is_none() {
+            self.find(
+                format!("tx {}", row.signature),
+                format!("undecodable {} event", row.name),
+            );
+        }
+    }
+
+    /// Transactions within a slot are replayed in signature order, not execution order,
+    /// so a balance is only checked against the set of balances the slot reported.
+    fn check_slot(&mut self, check: SlotCheck) {
+        for (owner, reported) in check.reported {
+            if check.unreported.contains(&owner) {
+                continue;
+            }
+            let ledger = self.ledgers.entry(owner).or_default();
+            if reported.contains(&ledger.points) {
+                continue;
+            }
+            let replayed = ledger.points;
+            if reported.len() == 1 {
+                // Resync so one missed event is reported once, not on every later event
+                ledger.points = *reported.first().expect("one element");
+            }
+            self.find(
+                format!("ledger {owner}"),
+                format!("events report balance {reported:?}, replay gives {replayed}; events are missing"),
+            );
+        }
+    }
+}
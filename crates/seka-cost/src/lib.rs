@@ -0,0 +1,409 @@
+//! Cost model for publishing and claiming seka cycles, for choosing how claims are
+//! recorded before a cycle outgrows the current layout.
+//!
+//! Three [`Mode`]s are compared for the same cycle size and claim rate:
+//!
+//! - `bitmap`, what the program does today: one `CycleState` per cycle holding a bit per
+//!   leaf. It is created in one instruction, so it cannot exceed the 10 KiB an
+//!   instruction may allocate, and every claim write-locks it.
+//! - `receipts`: a bitmap-free `CycleState` and one receipt PDA per claim, paid for by
+//!   the claim's payer. No shared account is written beyond the config.
+//! - `sharded`: the bitmap split across `shards` accounts, claims spreading their
+//!   write locks across them.
+//!
+//! Sizes follow the program's `space` arithmetic and claim transactions are packed with
+//! the SDK's [`Composer`], so transaction counts reflect real instruction sizes.
+//! Compute units are rough defaults unless calibrated with [`Units::from_baseline`]
+//! from the `compute_units` bench's baseline file. Throughput ceilings use the
+//! runtime's per-block limits: 48M compute units per block and 12M per writable
+//! account.
+
+use anyhow::{anyhow, Context, Result};
+use sentinelkarma_sdk::compose::{Chunk, Composer};
+use sentinelkarma_sdk::seka;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+const SECS_PER_YEAR: f64 = 365.0 * 86_400.0;
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Bytes one instruction may allocate, and so the largest account `init` can create
+const MAX_INIT_BYTES: usize = 10_240;
+const MAX_BLOCK_UNITS: u64 = 48_000_000;
+const MAX_WRITABLE_ACCOUNT_UNITS: u64 = 12_000_000;
+const SLOT_SECS: f64 = 0.4;
+/// Shard accounts created per `set_cycle_root` transaction in sharded mode
+const SHARDS_PER_TRANSACTION: u64 = 8;
+/// Claims packed per estimate; enough to fill any transaction
+const PACK_PROBE: u32 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Bitmap,
+    Receipts,
+    Sharded { shards: u32 },
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Bitmap => write!(f, "bitmap"),
+            Mode::Receipts => write!(f, "receipts"),
+            Mode::Sharded { shards } => write!(f, "sharded/{shards}"),
+        }
+    }
+}
+
+/// The cycle being costed.
+#[derive(Clone, Copy, Debug)]
+pub struct Inputs {
+    pub leaves: u32,
+    /// Fraction of leaves claimed
+    pub claim_rate: f64,
+    /// Fraction of claims that are an owner's first, creating its `PeerLedger`
+    pub new_ledger_rate: f64,
+    pub cycle_secs: u64,
+    /// Micro-lamports per compute unit
+    pub priority_fee: u64,
+    /// Whether `claim_karma` write-locks the config, as it does today
+    pub config_writable: bool,
+}
+
+/// Compute units per instruction.
+#[derive(Clone, Copy, Debug)]
+pub struct Units {
+    /// `claim_karma` with an empty proof, creating the ledger
+    pub claim_base: u64,
+    pub claim_per_level: u64,
+    /// Creating a receipt PDA through the system program
+    pub receipt_init: u64,
+    /// `set_cycle_root` with a one-byte bitmap
+    pub set_root_base: u64,
+    pub set_root_per_bitmap_byte: f64,
+}
+
+impl Default for Units {
+    /// Rough figures; calibrate with [`Units::from_baseline`].
+    fn default() -> Self {
+        Self {
+            claim_base: 30_000,
+            claim_per_level: 400,
+            receipt_init: 6_000,
+            set_root_base: 15_000,
+            set_root_per_bitmap_byte: 0.5,
+        }
+    }
+}
+
+impl Units {
+    /// Fits the defaults to `compute_units.baseline` from the program-tests bench: its
+    /// `seka/claim_karma/depth=*` and `seka/set_cycle_root/bitmap=*` lines.
+    pub fn from_baseline(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut depths = BTreeMap::new();
+        let mut bitmaps = BTreeMap::new();
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let Some((name, units)) = line.split_once(' ') else {
+                continue;
+            };
+            let units: u64 = units
+                .trim()
+                .parse()
+                .with_context(|| format!("parsing {line:?}"))?;
+            if let Some(depth) = name.strip_prefix("seka/claim_karma/depth=") {
+                depths.insert(depth.parse::<u64>()?, units);
+            } else if let Some(len) = name.strip_prefix("seka/set_cycle_root/bitmap=") {
+                bitmaps.insert(len.parse::<u64>()?, units);
+            }
+        }
+        let (claim_base, claim_per_level) = fit(&depths)
+            .ok_or_else(|| anyhow!("{} has no claim_karma depth measurements", path.display()))?;
+        let (set_root_base, set_root_per_bitmap_byte) = fit(&bitmaps)
+            .ok_or_else(|| anyhow!("{} has no set_cycle_root measurements", path.display()))?;
+        Ok(Self {
+            claim_base: claim_base.max(0.0) as u64,
+            claim_per_level: claim_per_level.max(0.0).ceil() as u64,
+            set_root_base: set_root_base.max(0.0) as u64,
+            set_root_per_bitmap_byte: set_root_per_bitmap_byte.max(0.0),
+            ..Self::default()
+        })
+    }
+}
+
+/// Least-squares line through `(x, y)` points: `(intercept, slope)`.
+fn fit(points: &BTreeMap<u64, u64>) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    match points.len() {
+        0 => None,
+        1 => points.values().next().map(|&y| (y as f64, 0.0)),
+        _ => {
+            let mean_x = points.keys().map(|&x| x as f64).sum::<f64>() / n;
+            let mean_y = points.values().map(|&y| y as f64).sum::<f64>() / n;
+            let (mut cov, mut var) = (0.0, 0.0);
+            for (&x, &y) in points {
+                cov += (x as f64 - mean_x) * (y as f64 - mean_y);
+                var += (x as f64 - mean_x).powi(2);
+            }
+            let slope = if var == 0.0 { 0.0 } else { cov / var };
+            Some((mean_y - slope * mean_x, slope))
+        }
+    }
+}
+
+/// Costs of one cycle in one mode. Lamport figures are per cycle.
+#[derive(Clone, Debug)]
+pub struct Estimate {
+    pub mode: Mode,
+    pub proof_depth: u32,
+    /// Bytes of every account `set_cycle_root` creates
+    pub root_account_bytes: usize,
+    /// Rent locked by the governor; nothing closes cycle accounts
+    pub root_rent: u64,
+    pub root_transactions: u64,
+    pub root_units: u64,
+    pub root_fees: u64,
+    pub claims: u64,
+    pub claim_units: u64,
+    pub claims_per_transaction: u64,
+    pub claim_transactions: u64,
+    pub claim_fees: u64,
+    /// Rent paid by claim payers: new ledgers, plus receipts
+    pub claimer_rent: u64,
+    /// Claims the runtime's per-block limits let through each second at most
+    pub claims_per_sec_ceiling: f64,
+    pub cycles_per_year: f64,
+    /// Why the mode cannot work for this cycle as costed
+    pub infeasible: Option<String>,
+}
+
+impl Estimate {
+    /// Lamports the keeper spends per cycle.
+    pub fn keeper_cost(&self) -> u64 {
+        self.root_rent + self.root_fees
+    }
+
+    /// Lamports claimers spend per cycle.
+    pub fn claimer_cost(&self) -> u64 {
+        self.claim_fees + self.claimer_rent
+    }
+
+    /// Seconds for every claim to land at the throughput ceiling.
+    pub fn claim_window_secs(&self) -> f64 {
+        self.claims as f64 / self.claims_per_sec_ceiling
+    }
+}
+
+pub fn estimate(inputs: &Inputs, units: &Units, rent: &Rent, mode: Mode) -> Result<Estimate> {
+    let leaves = u64::from(inputs.leaves.max(1));
+    let proof_depth = leaves.next_power_of_two().trailing_zeros();
+    let claims = (leaves as f64 * inputs.claim_rate.clamp(0.0, 1.0)).round() as u64;
+    let bitmap_bytes = leaves.div_ceil(8) as usize;
+    let fee = |units: u64, signatures: u64| {
+        LAMPORTS_PER_SIGNATURE * signatures + (units * inputs.priority_fee).div_ceil(1_000_000)
+    };
+    let set_root_units = |bitmap: usize| {
+        units.set_root_base + (bitmap as f64 * units.set_root_per_bitmap_byte).ceil() as u64
+    };
+
+    let mut infeasible = None;
+    let mut claim_units = units.claim_base + units.claim_per_level * u64::from(proof_depth);
+    let (root_account_bytes, root_rent, root_transactions, root_units);
+    // Accounts every claim write-locks besides its own ledger and receipt, with the share
+    // of claims that lock each one
+    let mut hot_share = if inputs.config_writable {
+        vec![1.0]
+    } else {
+        Vec::new()
+    };
+    match mode {
+        Mode::Bitmap => {
+            let space = seka::CycleState::space_for(bitmap_bytes as u32);
+            if space > MAX_INIT_BYTES {
+                infeasible = Some(format!(
+                    "a {space}-byte CycleState is over the {MAX_INIT_BYTES} bytes one instruction \
+                     can create; {} leaves at most",
+                    (MAX_INIT_BYTES - seka::CycleState::space_for(0)) * 8
+                ));
+            }
+            root_account_bytes = space;
+            root_rent = rent.minimum_balance(space);
+            root_transactions = 1;
+            root_units = set_root_units(bitmap_bytes);
+            hot_share.push(1.0);
+        }
+        Mode::Receipts => {
+            let space = seka::CycleState::space_for(0);
+            root_account_bytes = space;
+            root_rent = rent.minimum_balance(space);
+            root_transactions = 1;
+            root_units = set_root_units(0);
+            claim_units += units.receipt_init;
+        }
+        Mode::Sharded { shards } => {
+            let shards = u64::from(shards.max(1));
+            let shard_bitmap = leaves.div_ceil(shards).div_ceil(8) as usize;
+            let shard_space = shard_size(shard_bitmap);
+            if shard_space > MAX_INIT_BYTES {
+                infeasible = Some(format!(
+                    "{shard_space}-byte shards are over the {MAX_INIT_BYTES} bytes one \
+                     instruction can create; needs {} shards",
+                    min_shards(inputs.leaves)
+                ));
+            }
+            let state = seka::CycleState::space_for(0);
+            root_account_bytes = state + shard_space * shards as usize;
+            root_rent = rent.minimum_balance(state) + rent.minimum_balance(shard_space) * shards;
+            root_transactions = 1 + shards.div_ceil(SHARDS_PER_TRANSACTION);
+            root_units = set_root_units(0) + set_root_units(shard_bitmap) * shards;
+            hot_share.extend(std::iter::repeat_n(1.0 / shards as f64, shards as usize));
+        }
+    }
+
+    let claims_per_transaction = claims_per_transaction(mode, proof_depth, claim_units)?;
+    let claim_transactions = claims.div_ceil(claims_per_transaction);
+    let full = fee(claim_units * claims_per_transaction, 1) * (claims / claims_per_transaction);
+    let rest = claims % claims_per_transaction;
+    let claim_fees = full
+        + if rest > 0 {
+            fee(claim_units * rest, 1)
+        } else {
+            0
+        };
+    let new_ledgers = (claims as f64 * inputs.new_ledger_rate.clamp(0.0, 1.0)).round() as u64;
+    let mut claimer_rent = rent.minimum_balance(seka::PeerLedger::SPACE) * new_ledgers;
+    if mode == Mode::Receipts {
+        claimer_rent += rent.minimum_balance(RECEIPT_SPACE) * claims;
+    }
+
+    // The busiest write-locked account caps how many claims fit in a block
+    let busiest = hot_share.iter().copied().fold(0.0, f64::max);
+    let mut per_block = MAX_BLOCK_UNITS as f64 / claim_units as f64;
+    if busiest > 0.0 {
+        per_block = per_block.min(MAX_WRITABLE_ACCOUNT_UNITS as f64 / claim_units as f64 / busiest);
+    }
+
+    Ok(Estimate {
+        mode,
+        proof_depth,
+        root_account_bytes,
+        root_rent,
+        root_transactions,
+        root_units,
+        root_fees: fee(root_units, root_transactions),
+        claims,
+        claim_units,
+        claims_per_transaction,
+        claim_transactions,
+        claim_fees,
+        claimer_rent,
+        claims_per_sec_ceiling: per_block.floor() / SLOT_SECS,
+        cycles_per_year: SECS_PER_YEAR / inputs.cycle_secs.max(1) as f64,
+        infeasible,
+    })
+}
+
+/// Fewest shards whose accounts can each be created in one instruction.
+pub fn min_shards(leaves: u32) -> u32 {
+    let per_shard = (MAX_INIT_BYTES - shard_size(0)) as u32 * 8;
+    leaves.max(1).div_ceil(per_shard)
+}
+
+/// Receipt PDA: discriminator, bump and the program's usual padding.
+const RECEIPT_SPACE: usize = 8 + 1 + 16;
+
+/// Shard account: discriminator, bump, cycle index, shard index, bitmap and padding.
+fn shard_size(bitmap_bytes: usize) -> usize {
+    8 + 1 + 8 + 2 + 4 + bitmap_bytes + 16
+}
+
+/// Claims of `proof_depth` that one transaction holds, packed as the SDK would.
+fn claims_per_transaction(mode: Mode, proof_depth: u32, claim_units: u64) -> Result<u64> {
+    let payer = Pubkey::new_unique();
+    let chunks = (0..PACK_PROBE).map(|i| {
+        let mut ix = seka::claim_karma(
+            &payer,
+            Pubkey::new_unique(),
+            1,
+            1,
+            i,
+            vec![[0; 32]; proof_depth as usize],
+        );
+        // Receipts and shards each add one writable account to the claim
+        if mode != Mode::Bitmap {
+            ix.accounts
+                .push(AccountMeta::new(Pubkey::new_unique(), false));
+        }
+        Chunk {
+            instructions: vec![ix],
+            compute_units: claim_units as u32,
+        }
+    });
+    let batches = Composer::new(payer, Vec::new()).pack(chunks)?;
+    Ok(batches.first().map_or(1, |batch| batch.len() as u64))
+}
+
+/// A table row: label and how to render it for one estimate.
+type Row<'a> = (&'a str, &'a dyn Fn(&Estimate) -> String);
+
+/// Side-by-side estimates, per cycle and per year.
+pub struct Comparison<'a>(pub &'a [Estimate]);
+
+impl fmt::Display for Comparison<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.0.first() else {
+            return Ok(());
+        };
+        let sol = |lamports: u64| format!("{:.6}", lamports as f64 / LAMPORTS_PER_SOL as f64);
+        let yearly =
+            |e: &Estimate, lamports: u64| sol((lamports as f64 * e.cycles_per_year) as u64);
+        let rows: [Row; 16] = [
+            ("root accounts (bytes)", &|e| {
+                e.root_account_bytes.to_string()
+            }),
+            ("root rent (SOL)", &|e| sol(e.root_rent)),
+            ("root transactions", &|e| e.root_transactions.to_string()),
+            ("root compute units", &|e| e.root_units.to_string()),
+            ("root fees (SOL)", &|e| sol(e.root_fees)),
+            ("claims", &|e| e.claims.to_string()),
+            ("compute units per claim", &|e| e.claim_units.to_string()),
+            ("claims per transaction", &|e| {
+                e.claims_per_transaction.to_string()
+            }),
+            ("claim transactions", &|e| e.claim_transactions.to_string()),
+            ("claim fees (SOL)", &|e| sol(e.claim_fees)),
+            ("claimer rent (SOL)", &|e| sol(e.claimer_rent)),
+            ("claims/s ceiling", &|e| {
+                format!("{:.0}", e.claims_per_sec_ceiling)
+            }),
+            ("all claims in (s)", &|e| {
+                format!("{:.1}", e.claim_window_secs())
+            }),
+            ("keeper per cycle (SOL)", &|e| sol(e.keeper_cost())),
+            ("keeper per year (SOL)", &|e| yearly(e, e.keeper_cost())),
+            ("claimers per year (SOL)", &|e| yearly(e, e.claimer_cost())),
+        ];
+        write!(f, "{:<26}", format!("depth {}", first.proof_depth))?;
+        for estimate in self.0 {
+            write!(f, " {:>14}", estimate.mode.to_string())?;
+        }
+        writeln!(f)?;
+        for (label, value) in rows {
+            write!(f, "{label:<26}")?;
+            for estimate in self.0 {
+                write!(f, " {:>14}", value(estimate))?;
+            }
+            writeln!(f)?;
+        }
+        for estimate in self.0 {
+            if let Some(reason) = &estimate.infeasible {
+                writeln!(f, "{}: infeasible: {reason}", estimate.mode)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! seka-cost: compares what a cycle of a given size costs the keeper and claimers with
+//! claims recorded in a bitmap, in receipts, or in a sharded bitmap.
+
+use anyhow::Result;
+use clap::Parser;
+use seka_cost::{estimate, Comparison, Inputs, Mode, Units};
+use sentinelkarma_core::cluster::Cluster;
+use sentinelkarma_sdk::{fetch_account, seka};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::rent::Rent;
+use solana_sdk::sysvar;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Estimate rent, fees and compute for seka cycles under each claim mode")]
+struct Cli {
+    /// Leaves in the cycle's tree
+    #[arg(long)]
+    leaves: u32,
+
+    /// Fraction of leaves claimed
+    #[arg(long, default_value_t = 0.8)]
+    claim_rate: f64,
+
+    /// Fraction of claims that create the owner's ledger
+    #[arg(long, default_value_t = 0.1)]
+    new_ledger_rate: f64,
+
+    /// Defaults to the live config's with --rpc-url, else mainnet's default
+    #[arg(long)]
+    cycle_secs: Option<u64>,
+
+    /// Micro-lamports per compute unit
+    #[arg(long, default_value_t = 1_000)]
+    priority_fee: u64,
+
+    /// Shards for the sharded mode; defaults to the fewest that fit
+    #[arg(long)]
+    shards: Option<u32>,
+
+    /// `compute_units.baseline` from the program-tests bench, to use measured units
+    #[arg(long)]
+    units: Option<PathBuf>,
+
+    /// Model `claim_karma` taking the config read-only
+    #[arg(long)]
+    read_only_config: bool,
+
+    /// Read rent and the cycle length from this cluster
+    #[arg(long, env = "RPC_URL")]
+    rpc_url: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let (rent, live_cycle_secs) = match &cli.rpc_url {
+        Some(url) => {
+            let rpc = RpcClient::new(url.clone());
+            let rent: Rent =
+                solana_sdk::account::from_account(&rpc.get_account(&sysvar::rent::ID)?)
+                    .ok_or_else(|| anyhow::anyhow!("decoding the rent sysvar"))?;
+            let config: seka::GlobalConfig = fetch_account(&rpc, &seka::config_pda())?;
+            (rent, Some(config.cycle_secs))
+        }
+        None => (Rent::default(), None),
+    };
+    let units = match &cli.units {
+        Some(path) => Units::from_baseline(path)?,
+        None => Units::default(),
+    };
+    let inputs = Inputs {
+        leaves: cli.leaves,
+        claim_rate: cli.claim_rate,
+        new_ledger_rate: cli.new_ledger_rate,
+        cycle_secs: cli
+            .cycle_secs
+            .or(live_cycle_secs)
+            .unwrap_or(Cluster::Mainnet.seka_cycle_secs()),
+        priority_fee: cli.priority_fee,
+        config_writable: !cli.read_only_config,
+    };
+    let shards = cli
+        .shards
+        .unwrap_or_else(|| seka_cost::min_shards(cli.leaves).max(2));
+
+    let estimates = [Mode::Bitmap, Mode::Receipts, Mode::Sharded { shards }]
+        .into_iter()
+        .map(|mode| estimate(&inputs, &units, &rent, mode))
+        .collect::<Result<Vec<_>>>()?;
+    println!(
+        "{} leaves, {:.0}% claimed, one cycle every {}s, {} micro-lamports/CU",
+        inputs.leaves,
+        inputs.claim_rate * 100.0,
+        inputs.cycle_secs,
+        inputs.priority_fee
+    );
+    print!("{}", Comparison(&estimates));
+    if inputs.config_writable {
+        println!(
+            "claim_karma write-locks the config, which caps every mode alike; see \
+             --read-only-config"
+        );
+    }
+    if cli.units.is_none() {
+        println!("compute units are rough defaults; pass --units for measured ones");
+    }
+    Ok(())
+}
@@ -0,0 +1,427 @@
+//! seka-diff: compares two ledger snapshots or two cycle datasets and reports what moved.
+//!
+//! It writes the review report the dispute-window committee reads before attesting a
+//! root:
+//!
+//! - `ledgers OLD NEW`: two `seka-admin snapshot` directories. Points per ledger owner,
+//!   plus members that joined, were deactivated or reactivated in between.
+//! - `cycles OLD NEW`: two datasets as `seka-proofs build` reads them, e.g. consecutive
+//!   `<archive-dir>/<cycle>.json` files of seka-keeper. Deltas per owner, plus the root,
+//!   leaf count and `total_points_declared` the new dataset commits to.
+//!
+//! Stdout gets a summary and the largest `--top` changes per section; `--out` writes the
+//! whole report as JSON, amounts as decimal strings.
+
+mod snapshot;
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use seka_merkle::MerkleTree;
+use seka_proofs::{read_input, write_json, RootFile};
+use serde::Serialize;
+use snapshot::Snapshot;
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(
+    name = "seka-diff",
+    about = "Diff seka ledger snapshots or cycle datasets"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two `seka-admin snapshot` directories
+    Ledgers {
+        old: PathBuf,
+        new: PathBuf,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Compare two cycle datasets (CSV or JSON of owner, delta)
+    Cycles {
+        old: PathBuf,
+        new: PathBuf,
+        /// Cycle of NEW, for the root it commits to; defaults to the file name of a
+        /// seka-keeper archive (`<cycle>.json`)
+        #[arg(long)]
+        cycle: Option<u64>,
+        #[command(flatten)]
+        output: Output,
+    },
+}
+
+#[derive(Args)]
+struct Output {
+    /// Write the full report as JSON
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Entries listed per section on stdout
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+}
+
+// ============================================================================
+// Report
+// ============================================================================
+
+#[derive(Serialize)]
+struct Report {
+    /// `ledgers` or `cycles`
+    kind: &'static str,
+    old: Source,
+    new: Source,
+    summary: Summary,
+    /// Ledgers only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    membership: Option<Membership>,
+    /// Cycles only, when the new dataset's cycle is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root: Option<RootFile>,
+    /// Every owner whose points (ledgers) or delta (cycles) differ, largest move first
+    changes: Vec<Change>,
+}
+
+#[derive(Serialize)]
+struct Source {
+    path: String,
+    /// Ledgers only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slot: Option<String>,
+    entries: usize,
+}
+
+#[derive(Default, Serialize)]
+struct Summary {
+    /// Owners only in the new side
+    added: usize,
+    /// Owners only in the old side
+    removed: usize,
+    increased: usize,
+    decreased: usize,
+    unchanged: usize,
+    total_old: String,
+    total_new: String,
+    net: String,
+    /// Sum of the increases, added owners included
+    gained: String,
+    /// Sum of the decreases, removed owners included
+    lost: String,
+    /// Sum of positive values; `total_points_declared` for cycles
+    positive_old: String,
+    positive_new: String,
+}
+
+#[derive(Serialize)]
+struct Change {
+    owner: String,
+    /// Absent for added owners
+    old: Option<String>,
+    /// Absent for removed owners
+    new: Option<String>,
+    change: String,
+}
+
+#[derive(Default, Serialize)]
+struct Membership {
+    joined: Vec<Joined>,
+    deactivated: Vec<String>,
+    reactivated: Vec<String>,
+    /// Membership accounts gone from the new snapshot
+    closed: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Joined {
+    owner: String,
+    joined_at: String,
+}
+
+/// Per-owner changes between `old` and `new` and their totals.
+fn diff(old: &BTreeMap<Pubkey, i64>, new: &BTreeMap<Pubkey, i64>) -> (Summary, Vec<Change>) {
+    let mut summary = Summary::default();
+    let mut moved: Vec<(Pubkey, Option<i64>, Option<i64>, i128)> = Vec::new();
+    let (mut gained, mut lost) = (0i128, 0i128);
+    for owner in old
+        .keys()
+        .chain(new.keys().filter(|o| !old.contains_key(o)))
+    {
+        let (before, after) = (old.get(owner).copied(), new.get(owner).copied());
+        let change = i128::from(after.unwrap_or(0)) - i128::from(before.unwrap_or(0));
+        match (before, after) {
+            (None, _) => summary.added += 1,
+            (_, None) => summary.removed += 1,
+            _ if change > 0 => summary.increased += 1,
+            _ if change < 0 => summary.decreased += 1,
+            _ => summary.unchanged += 1,
+        }
+        if change > 0 {
+            gained += change;
+        } else {
+            lost -= change;
+        }
+        if change != 0 || before.is_none() || after.is_none() {
+            moved.push((*owner, before, after, change));
+        }
+    }
+    moved.sort_by(|a, b| b.3.abs().cmp(&a.3.abs()).then(a.0.cmp(&b.0)));
+
+    let total = |m: &BTreeMap<Pubkey, i64>| m.values().map(|&v| i128::from(v)).sum::<i128>();
+    let positive =
+        |m: &BTreeMap<Pubkey, i64>| m.values().map(|&v| i128::from(v.max(0))).sum::<i128>();
+    summary.total_old = total(old).to_string();
+    summary.total_new = total(new).to_string();
+    summary.net = (total(new) - total(old)).to_string();
+    summary.gained = gained.to_string();
+    summary.lost = lost.to_string();
+    summary.positive_old = positive(old).to_string();
+    summary.positive_new = positive(new).to_string();
+
+    let changes = moved
+        .into_iter()
+        .map(|(owner, before, after, change)| Change {
+            owner: owner.to_string(),
+            old: before.map(|v| v.to_string()),
+            new: after.map(|v| v.to_string()),
+            change: change.to_string(),
+        })
+        .collect();
+    (summary, changes)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+fn ledgers(old_dir: &Path, new_dir: &Path) -> Result<Report> {
+    let old = Snapshot::read(old_dir)?;
+    let new = Snapshot::read(new_dir)?;
+    if new.slot < old.slot {
+        bail!(
+            "{} (slot {}) is older than {} (slot {})",
+            new_dir.display(),
+            new.slot,
+            old_dir.display(),
+            old.slot
+        );
+    }
+    let (summary, changes) = diff(&old.points, &new.points);
+
+    let mut membership = Membership::default();
+    for (owner, member) in &new.members {
+        match old.members.get(owner) {
+            None => membership.joined.push(Joined {
+                owner: owner.to_string(),
+                joined_at: member.joined_at.to_string(),
+            }),
+            Some(before) if before.active && !member.active => {
+                membership.deactivated.push(owner.to_string())
+            }
+            Some(before) if !before.active && member.active => {
+                membership.reactivated.push(owner.to_string())
+            }
+            Some(_) => {}
+        }
+    }
+    membership.closed = old
+        .members
+        .keys()
+        .filter(|o| !new.members.contains_key(o))
+        .map(Pubkey::to_string)
+        .collect();
+
+    Ok(Report {
+        kind: "ledgers",
+        old: Source {
+            path: old_dir.display().to_string(),
+            slot: Some(old.slot.to_string()),
+            entries: old.points.len(),
+        },
+        new: Source {
+            path: new_dir.display().to_string(),
+            slot: Some(new.slot.to_string()),
+            entries: new.points.len(),
+        },
+        summary,
+        membership: Some(membership),
+        root: None,
+        changes,
+    })
+}
+
+fn cycles(old_path: &Path, new_path: &Path, cycle: Option<u64>) -> Result<Report> {
+    let read = |path: &Path| -> Result<(usize, BTreeMap<Pubkey, i64>)> {
+        let leaves = read_input(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut deltas = BTreeMap::new();
+        for leaf in &leaves {
+            if deltas
+                .insert(leaf.owner, i64::from(leaf.delta_points))
+                .is_some()
+            {
+                bail!(
+                    "{}: owner {} appears more than once",
+                    path.display(),
+                    leaf.owner
+                );
+            }
+        }
+        Ok((leaves.len(), deltas))
+    };
+    let (old_len, old) = read(old_path)?;
+    let (new_len, new) = read(new_path)?;
+    let (summary, changes) = diff(&old, &new);
+
+    let cycle = cycle.or_else(|| new_path.file_stem()?.to_str()?.parse().ok());
+    let root = match cycle {
+        Some(cycle) => {
+            let leaves = read_input(new_path)?;
+            Some(RootFile::new(&MerkleTree::new(cycle, leaves)?)?)
+        }
+        None => None,
+    };
+
+    Ok(Report {
+        kind: "cycles",
+        old: Source {
+            path: old_path.display().to_string(),
+            slot: None,
+            entries: old_len,
+        },
+        new: Source {
+            path: new_path.display().to_string(),
+            slot: None,
+            entries: new_len,
+        },
+        summary,
+        membership: None,
+        root,
+        changes,
+    })
+}
+
+// ============================================================================
+// Output
+// ============================================================================
+
+fn print(report: &Report, top: usize) {
+    let s = &report.summary;
+    let slot = |source: &Source| match &source.slot {
+        Some(slot) => format!(" (slot {slot})"),
+        None => String::new(),
+    };
+    println!(
+        "{}: {}{} -> {}{}",
+        report.kind,
+        report.old.path,
+        slot(&report.old),
+        report.new.path,
+        slot(&report.new)
+    );
+    println!(
+        "owners:    {} -> {} ({} added, {} removed)",
+        report.old.entries, report.new.entries, s.added, s.removed
+    );
+    println!(
+        "moved:     {} up, {} down, {} unchanged",
+        s.increased, s.decreased, s.unchanged
+    );
+    println!(
+        "total:     {} -> {} (net {}, gained {}, lost {})",
+        s.total_old, s.total_new, s.net, s.gained, s.lost
+    );
+    println!("positive:  {} -> {}", s.positive_old, s.positive_new);
+    if let Some(m) = &report.membership {
+        println!(
+            "members:   {} joined, {} deactivated, {} reactivated, {} closed",
+            m.joined.len(),
+            m.deactivated.len(),
+            m.reactivated.len(),
+            m.closed.len()
+        );
+    }
+    if let Some(root) = &report.root {
+        println!(
+            "root:      cycle {} {} ({} leaves, {} points declared)",
+            root.cycle_index, root.merkle_root, root.leaf_count, root.total_points_declared
+        );
+    }
+
+    let show = |v: &Option<String>| v.as_deref().unwrap_or("-").to_string();
+    let added: Vec<String> = report
+        .changes
+        .iter()
+        .filter(|c| c.old.is_none())
+        .map(|c| format!("{}  {}", c.owner, show(&c.new)))
+        .collect();
+    let removed: Vec<String> = report
+        .changes
+        .iter()
+        .filter(|c| c.new.is_none())
+        .map(|c| format!("{}  {}", c.owner, show(&c.old)))
+        .collect();
+    let changed: Vec<String> = report
+        .changes
+        .iter()
+        .filter(|c| c.old.is_some() && c.new.is_some())
+        .map(|c| {
+            format!(
+                "{}  {} -> {}  ({}{})",
+                c.owner,
+                show(&c.old),
+                show(&c.new),
+                if c.change.starts_with('-') { "" } else { "+" },
+                c.change
+            )
+        })
+        .collect();
+    section("added", &added, top);
+    section("removed", &removed, top);
+    section("changed, largest first", &changed, top);
+    if let Some(m) = &report.membership {
+        let joined: Vec<String> = m
+            .joined
+            .iter()
+            .map(|j| format!("{}  joined_at {}", j.owner, j.joined_at))
+            .collect();
+        section("joined", &joined, top);
+        section("deactivated", &m.deactivated, top);
+        section("reactivated", &m.reactivated, top);
+        section("closed", &m.closed, top);
+    }
+}
+
+fn section(title: &str, lines: &[String], top: usize) {
+    if lines.is_empty() {
+        return;
+    }
+    println!("\n{title} ({}):", lines.len());
+    for line in lines.iter().take(top) {
+        println!("  {line}");
+    }
+    if lines.len() > top {
+        println!("  ... and {} more", lines.len() - top);
+    }
+}
+
+fn main() -> Result<()> {
+    let (report, output) = match Cli::parse().command {
+        Command::Ledgers { old, new, output } => (ledgers(&old, &new)?, output),
+        Command::Cycles {
+            old,
+            new,
+            cycle,
+            output,
+        } => (cycles(&old, &new, cycle)?, output),
+    };
+    print(&report, output.top);
+    if let Some(path) = &output.out {
+        write_json(path, &report)?;
+        println!("\nreport written to {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Reads the seka side of a `seka-admin snapshot` directory: `peer_ledgers` and
+//! `memberships`, in whichever format `manifest.json` says they were written in.
+
+use anyhow::{bail, Context, Result};
+use seka_proofs::parse_pubkey;
+use serde::Deserialize;
+use serde_json::Value;
+use solana_program::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Deserialize)]
+struct Manifest {
+    format: String,
+    files: Vec<FileEntry>,
+}
+
+#[derive(Deserialize)]
+struct FileEntry {
+    kind: String,
+    path: String,
+    slot: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Member {
+    pub joined_at: i64,
+    pub active: bool,
+}
+
+pub struct Snapshot {
+    /// Slot `peer_ledgers` was read at
+    pub slot: u64,
+    /// Ledger points by owner
+    pub points: BTreeMap<Pubkey, i64>,
+    /// By owner
+    pub members: BTreeMap<Pubkey, Member>,
+}
+
+type Row = HashMap<String, String>;
+
+impl Snapshot {
+    pub fn read(dir: &Path) -> Result<Self> {
+        let path = dir.join("manifest.json");
+        let manifest: Manifest = serde_json::from_slice(
+            &fs::read(&path).with_context(|| format!("reading {}", path.display()))?,
+        )
+        .with_context(|| format!("parsing {}", path.display()))?;
+        let table = |kind: &str| -> Result<(u64, Vec<Row>)> {
+            let entry = manifest
+                .files
+                .iter()
+                .find(|f| f.kind == kind)
+                .with_context(|| format!("{} lists no {kind}", path.display()))?;
+            let rows = read_rows(&dir.join(&entry.path), &manifest.format)?;
+            Ok((entry.slot, rows))
+        };
+
+        let (slot, rows) = table("peer_ledgers")?;
+        let mut points = BTreeMap::new();
+        for row in rows {
+            points.insert(
+                parse_pubkey(row_str(&row, "owner")?)?,
+                field(&row, "points")?,
+            );
+        }
+        let (_, rows) = table("memberships")?;
+        let mut members = BTreeMap::new();
+        for row in rows {
+            let member = Member {
+                joined_at: field(&row, "joined_at")?,
+                active: field(&row, "active")?,
+            };
+            members.insert(parse_pubkey(row_str(&row, "owner")?)?, member);
+        }
+        Ok(Self {
+            slot,
+            points,
+            members,
+        })
+    }
+}
+
+/// Every row as column name to text, as CSV would have it.
+fn read_rows(path: &Path, format: &str) -> Result<Vec<Row>> {
+    let rows = match format {
+        "json" => {
+            let objects: Vec<serde_json::Map<String, Value>> =
+                serde_json::from_slice(&fs::read(path)?)?;
+            objects
+                .into_iter()
+                .map(|object| {
+                    object
+                        .into_iter()
+                        .map(|(k, v)| match v {
+                            Value::String(s) => (k, s),
+                            other => (k, other.to_string()),
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+        "csv" => csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<std::result::Result<_, _>>()?,
+        other => bail!("unknown snapshot format {other:?}"),
+    };
+    Ok(rows)
+}
+
+fn row_str<'a>(row: &'a Row, name: &str) -> Result<&'a str> {
+    row.get(name)
+        .map(String::as_str)
+        .with_context(|| format!("row without {name}"))
+}
+
+fn field<T: FromStr>(row: &Row, name: &str) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let text = row_str(row, name)?;
+    text.parse()
+        .with_context(|| format!("{name}: invalid value {text:?}"))
+}
@@ -0,0 +1,314 @@
+//! seka-export: writes the index and account snapshots as Parquet for offline analysis.
+//!
+//! Tables are Hive-partitioned by seka cycle under `--out`, one file per partition,
+//! so DuckDB or Spark can read them directly, e.g.
+//! `read_parquet('out/claims/*/*.parquet', hive_partitioning = true)`:
+//!
+//! - `events/cycle=N/part-0.parquet`: every indexed event, raw payload included
+//! - `instructions/cycle=N/part-0.parquet`: every indexed instruction; `accounts` is
+//!   space-separated base58
+//! - `claims/cycle=N/part-0.parquet`: decoded `KarmaClaimed` events
+//! - `ledgers/cycle=N/snapshot-<slot>.parquet`: every `PeerLedger` with its membership,
+//!   read from RPC at `<slot>`
+//!
+//! Events and instructions are placed in the cycle their block time falls in. Cycle
+//! partitions are rewritten whole on every run; `--from-cycle` skips older ones, which
+//! are closed and only change if history is backfilled. With `--every-secs` the export
+//! repeats, taking a ledger snapshot each time.
+
+mod table;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use sentinelkarma_indexer::store::{EventRow, InstructionRow, Store};
+use sentinelkarma_sdk::anchor_lang::{AccountDeserialize, Discriminator};
+use sentinelkarma_sdk::{decode_account, decode_event, fetch_account, seka, Versioned};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use table::Column;
+
+const EVENTS_SCHEMA: &str = "message event {
+    required binary signature (UTF8);
+    required int32 event_index;
+    required int64 slot;
+    optional int64 block_time;
+    required binary program (UTF8);
+    required binary name (UTF8);
+    required binary payload;
+}";
+
+const INSTRUCTIONS_SCHEMA: &str = "message instruction {
+    required binary signature (UTF8);
+    required int32 ix_index;
+    required int64 slot;
+    optional int64 block_time;
+    required binary program (UTF8);
+    required binary name (UTF8);
+    required binary data;
+    required binary accounts (UTF8);
+}";
+
+const CLAIMS_SCHEMA: &str = "message claim {
+    required binary owner (UTF8);
+    required int64 cycle_index;
+    required int32 delta_points;
+    required int64 new_points;
+    required binary signature (UTF8);
+    required int64 slot;
+    optional int64 block_time;
+}";
+
+const LEDGERS_SCHEMA: &str = "message ledger {
+    required int64 snapshot_slot;
+    required int64 snapshot_time;
+    required binary owner (UTF8);
+    required int64 points;
+    required int64 last_cycle_claimed;
+    required boolean member;
+    required boolean active;
+    optional int64 joined_at;
+}";
+
+#[derive(Parser)]
+#[command(about = "Export indexed events and account snapshots as Parquet, by cycle")]
+struct Cli {
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL", default_value = "sqlite:indexer.db")]
+    database_url: String,
+
+    /// For the cycle schedule and ledger snapshots
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Leave partitions of earlier cycles as they are
+    #[arg(long, default_value_t = 0)]
+    from_cycle: u64,
+
+    /// Export only the index, without a ledger snapshot
+    #[arg(long)]
+    no_snapshot: bool,
+
+    /// Repeat every this many seconds instead of exiting
+    #[arg(long)]
+    every_secs: Option<u64>,
+}
+
+/// The seka cycle schedule, from `GlobalConfig`.
+struct Schedule {
+    start_ts: i64,
+    cycle_secs: u64,
+}
+
+impl Schedule {
+    /// The cycle `time` falls in; `None` before the first.
+    fn cycle_at(&self, time: i64) -> Option<u64> {
+        (time >= self.start_ts).then(|| (time - self.start_ts) as u64 / self.cycle_secs.max(1))
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new(cli.rpc_url.clone());
+    loop {
+        let config: seka::GlobalConfig = fetch_account(&rpc, &seka::config_pda())?;
+        let schedule = Schedule {
+            start_ts: config.start_ts,
+            cycle_secs: config.cycle_secs,
+        };
+        let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+        export_index(store.as_mut(), &schedule, &cli.out, cli.from_cycle)?;
+        drop(store);
+        if !cli.no_snapshot {
+            snapshot_ledgers(&rpc, &schedule, &cli.out)?;
+        }
+        match cli.every_secs {
+            Some(secs) => std::thread::sleep(Duration::from_secs(secs)),
+            None => return Ok(()),
+        }
+    }
+}
+
+fn export_index(store: &mut dyn Store, schedule: &Schedule, out: &Path, from: u64) -> Result<()> {
+    let mut events = store.events("seka", None, 0)?;
+    events.extend(store.events("sentinel", None, 0)?);
+    let mut instructions = store.instructions("seka", None, 0)?;
+    instructions.extend(store.instructions("sentinel", None, 0)?);
+
+    let mut unplaced = 0;
+    let mut by_cycle = |time: Option<i64>| {
+        let cycle = time.and_then(|t| schedule.cycle_at(t));
+        unplaced += cycle.is_none() as usize;
+        cycle.filter(|c| *c >= from)
+    };
+    let mut event_parts: BTreeMap<u64, Vec<&EventRow>> = BTreeMap::new();
+    for row in &events {
+        if let Some(cycle) = by_cycle(row.block_time) {
+            event_parts.entry(cycle).or_default().push(row);
+        }
+    }
+    let mut instruction_parts: BTreeMap<u64, Vec<&InstructionRow>> = BTreeMap::new();
+    for row in &instructions {
+        if let Some(cycle) = by_cycle(row.block_time) {
+            instruction_parts.entry(cycle).or_default().push(row);
+        }
+    }
+    let mut claim_parts: BTreeMap<u64, Vec<(seka::KarmaClaimed, &EventRow)>> = BTreeMap::new();
+    for row in events
+        .iter()
+        .filter(|r| r.program == "seka" && r.name == "KarmaClaimed")
+    {
+        if let Some(claim) = decode_event::<seka::KarmaClaimed>(&row.payload) {
+            if claim.cycle_index >= from {
+                claim_parts
+                    .entry(claim.cycle_index)
+                    .or_default()
+                    .push((claim, row));
+            }
+        }
+    }
+
+    for (cycle, rows) in &event_parts {
+        let columns = vec![
+            strings(rows.iter().map(|r| r.signature.clone())),
+            Column::I32(rows.iter().map(|r| r.event_index as i32).collect()),
+            Column::I64(rows.iter().map(|r| r.slot as i64).collect()),
+            Column::OptI64(rows.iter().map(|r| r.block_time).collect()),
+            strings(rows.iter().map(|r| r.program.clone())),
+            strings(rows.iter().map(|r| r.name.clone())),
+            Column::Bytes(rows.iter().map(|r| r.payload.clone()).collect()),
+        ];
+        table::write(&part(out, "events", *cycle), EVENTS_SCHEMA, columns)?;
+    }
+    for (cycle, rows) in &instruction_parts {
+        let columns = vec![
+            strings(rows.iter().map(|r| r.signature.clone())),
+            Column::I32(rows.iter().map(|r| r.ix_index as i32).collect()),
+            Column::I64(rows.iter().map(|r| r.slot as i64).collect()),
+            Column::OptI64(rows.iter().map(|r| r.block_time).collect()),
+            strings(rows.iter().map(|r| r.program.clone())),
+            strings(rows.iter().map(|r| r.name.clone())),
+            Column::Bytes(rows.iter().map(|r| r.data.clone()).collect()),
+            strings(rows.iter().map(|r| r.accounts.join(" "))),
+        ];
+        table::write(
+            &part(out, "instructions", *cycle),
+            INSTRUCTIONS_SCHEMA,
+            columns,
+        )?;
+    }
+    for (cycle, rows) in &claim_parts {
+        let columns = vec![
+            strings(rows.iter().map(|(c, _)| c.owner.to_string())),
+            Column::I64(rows.iter().map(|(c, _)| c.cycle_index as i64).collect()),
+            Column::I32(rows.iter().map(|(c, _)| c.delta_points).collect()),
+            Column::I64(rows.iter().map(|(c, _)| c.new_points).collect()),
+            strings(rows.iter().map(|(_, r)| r.signature.clone())),
+            Column::I64(rows.iter().map(|(_, r)| r.slot as i64).collect()),
+            Column::OptI64(rows.iter().map(|(_, r)| r.block_time).collect()),
+        ];
+        table::write(&part(out, "claims", *cycle), CLAIMS_SCHEMA, columns)?;
+    }
+
+    println!(
+        "exported {} events, {} instructions and {} claims in cycles {from}..",
+        event_parts.values().map(Vec::len).sum::<usize>(),
+        instruction_parts.values().map(Vec::len).sum::<usize>(),
+        claim_parts.values().map(Vec::len).sum::<usize>(),
+    );
+    if unplaced > 0 {
+        println!("{unplaced} rows before the first cycle or without a block time were skipped");
+    }
+    Ok(())
+}
+
+fn snapshot_ledgers(rpc: &RpcClient, schedule: &Schedule, out: &Path) -> Result<()> {
+    let slot = rpc.get_slot()?;
+    let time = rpc.get_block_time(slot).unwrap_or_else(|_| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64)
+    });
+    let ledgers = program_accounts::<seka::PeerLedger>(rpc)?;
+    let members: HashMap<Pubkey, seka::Membership> = program_accounts::<seka::Membership>(rpc)?
+        .into_iter()
+        .map(|m| (m.owner, m))
+        .collect();
+
+    let n = ledgers.len();
+    let member = |l: &seka::PeerLedger| members.get(&l.owner);
+    let columns = vec![
+        Column::I64(vec![slot as i64; n]),
+        Column::I64(vec![time; n]),
+        strings(ledgers.iter().map(|l| l.owner.to_string())),
+        Column::I64(ledgers.iter().map(|l| l.points).collect()),
+        Column::I64(
+            ledgers
+                .iter()
+                .map(|l| l.last_cycle_claimed as i64)
+                .collect(),
+        ),
+        Column::Bool(ledgers.iter().map(|l| member(l).is_some()).collect()),
+        Column::Bool(
+            ledgers
+                .iter()
+                .map(|l| member(l).is_some_and(|m| m.active))
+                .collect(),
+        ),
+        Column::OptI64(
+            ledgers
+                .iter()
+                .map(|l| member(l).map(|m| m.joined_at))
+                .collect(),
+        ),
+    ];
+    let cycle = schedule.cycle_at(time).unwrap_or(0);
+    let path = out
+        .join("ledgers")
+        .join(format!("cycle={cycle}"))
+        .join(format!("snapshot-{slot}.parquet"));
+    table::write(&path, LEDGERS_SCHEMA, columns)?;
+    println!(
+        "snapshot of {n} ledgers at slot {slot} written to {}",
+        path.display()
+    );
+    Ok(())
+}
+
+fn program_accounts<T: AccountDeserialize + Discriminator + Versioned>(rpc: &RpcClient) -> Result<Vec<T>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            T::DISCRIMINATOR.to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    rpc.get_program_accounts_with_config(&seka::ID, config)?
+        .into_iter()
+        .map(|(address, account)| {
+            decode_account(&account.data).with_context(|| format!("decoding {address}"))
+        })
+        .collect()
+}
+
+fn part(out: &Path, table: &str, cycle: u64) -> PathBuf {
+    out.join(table)
+        .join(format!("cycle={cycle}"))
+        .join("part-0.parquet")
+}
+
+fn strings(values: impl Iterator<Item = String>) -> Column {
+    Column::from(values.collect::<Vec<_>>())
+}
@@ -0,0 +1,100 @@
+//! Writing column vectors to Parquet with the low-level file writer.
+
+use anyhow::{bail, Context, Result};
+use parquet::basic::Compression;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Arc;
+
+/// One column's values, in schema order.
+pub enum Column {
+    Bool(Vec<bool>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    /// An `optional int64`
+    OptI64(Vec<Option<i64>>),
+    /// A `binary`, `(UTF8)` or not
+    Bytes(Vec<Vec<u8>>),
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Bool(v) => v.len(),
+            Column::I32(v) => v.len(),
+            Column::I64(v) => v.len(),
+            Column::OptI64(v) => v.len(),
+            Column::Bytes(v) => v.len(),
+        }
+    }
+}
+
+impl From<Vec<String>> for Column {
+    fn from(values: Vec<String>) -> Self {
+        Column::Bytes(values.into_iter().map(String::into_bytes).collect())
+    }
+}
+
+/// Writes `columns` as one row group to `path`, via a temporary file so readers never
+/// see a partial file.
+pub fn write(path: &Path, schema: &str, columns: Vec<Column>) -> Result<usize> {
+    let rows = columns.first().map_or(0, Column::len);
+    if columns.iter().any(|c| c.len() != rows) {
+        bail!("columns of {} differ in length", path.display());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let tmp = path.with_extension("parquet.tmp");
+    let schema = Arc::new(parse_message_type(schema)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let file = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+    let mut writer = SerializedFileWriter::new(file, schema, properties)?;
+    let mut group = writer.next_row_group()?;
+    let mut columns = columns.into_iter();
+    while let Some(mut writer) = group.next_column()? {
+        let column = columns
+            .next()
+            .context("schema has more columns than were given")?;
+        match column {
+            Column::Bool(v) => {
+                writer.typed::<BoolType>().write_batch(&v, None, None)?;
+            }
+            Column::I32(v) => {
+                writer.typed::<Int32Type>().write_batch(&v, None, None)?;
+            }
+            Column::I64(v) => {
+                writer.typed::<Int64Type>().write_batch(&v, None, None)?;
+            }
+            Column::OptI64(v) => {
+                let levels: Vec<i16> = v.iter().map(|x| x.is_some() as i16).collect();
+                let values: Vec<i64> = v.into_iter().flatten().collect();
+                writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            Column::Bytes(v) => {
+                let values: Vec<ByteArray> = v.into_iter().map(ByteArray::from).collect();
+                writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, None, None)?;
+            }
+        }
+        writer.close()?;
+    }
+    if columns.next().is_some() {
+        bail!("more columns were given than the schema has");
+    }
+    group.close()?;
+    writer.close()?;
+    fs::rename(&tmp, path).with_context(|| format!("renaming {}", tmp.display()))?;
+    Ok(rows)
+}
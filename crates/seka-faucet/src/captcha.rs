@@ -0,0 +1,53 @@
+//! Captcha hook: the token a client sends with its drip is checked against a
+//! `siteverify`-style endpoint before anything is sent.
+//!
+//! hCaptcha, reCAPTCHA and Cloudflare Turnstile all take a form POST of `secret`,
+//! `response` and `remoteip` and answer `{"success": bool, ...}`, so any of them works
+//! by pointing `--captcha-verify-url` at it.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::net::IpAddr;
+
+pub struct Captcha {
+    http: reqwest::Client,
+    verify_url: String,
+    secret: String,
+}
+
+#[derive(Deserialize)]
+struct Verdict {
+    success: bool,
+}
+
+impl Captcha {
+    pub fn new(verify_url: String, secret: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            verify_url,
+            secret,
+        }
+    }
+
+    /// Whether the provider accepts `token` as solved from `ip`.
+    pub async fn verify(&self, token: &str, ip: IpAddr) -> Result<bool> {
+        let ip = ip.to_string();
+        let verdict: Verdict = self
+            .http
+            .post(&self.verify_url)
+            .form(&[
+                ("secret", self.secret.as_str()),
+                ("response", token),
+                ("remoteip", ip.as_str()),
+            ])
+            .send()
+            .await
+            .context("calling the captcha provider")?
+            .error_for_status()
+            .context("calling the captcha provider")?
+            .json()
+            .await
+            .context("decoding the captcha verdict")?;
+        Ok(verdict.success)
+    }
+}
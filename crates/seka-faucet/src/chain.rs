@@ -0,0 +1,175 @@
+//! The faucet's transactions: creating the recipient's ATAs and paying it SEKA and
+//! SENTINEL, signed and paid for by the faucet key.
+//!
+//! SEKA is minted with `issue_tokens` when the faucet is seka's issuer and otherwise
+//! transferred from the faucet's own SEKA account. SENTINEL's mint authority is the
+//! sentinel state, so SENTINEL always comes from the faucet's own account, which the
+//! sentinel authority tops up with `distribute_initial_supply`. When sentinel runs on
+//! the SEKA mint (`initialize_shared_mint`) there is no separate SENTINEL to hand out.
+
+use anyhow::{anyhow, bail, Context, Result};
+use sentinelkarma_sdk::client::Client;
+use sentinelkarma_sdk::sentinel::{self, State as SentinelState};
+use sentinelkarma_sdk::seka;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+use spl_token::instruction::TokenInstruction;
+
+/// A mint the faucet pays out of.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub token_program: Pubkey,
+    /// Paid per drip, in base units
+    pub amount: u64,
+    /// Minted with `issue_tokens` rather than transferred from the faucet's account
+    pub issued: bool,
+}
+
+impl Token {
+    pub fn ata(&self, wallet: &Pubkey) -> Pubkey {
+        sentinel::associated_token_address(wallet, &self.mint, &self.token_program)
+    }
+}
+
+/// What one drip pays out.
+#[derive(Clone, Debug)]
+pub struct Tokens {
+    pub seka: Token,
+    /// `None` when sentinel shares the SEKA mint
+    pub sentinel: Option<Token>,
+    pub lamports: u64,
+}
+
+impl Tokens {
+    /// Reads both programs' mints; `seka_whole` and `sentinel_whole` are per drip.
+    pub async fn load(
+        client: &Client,
+        faucet: &Pubkey,
+        seka_whole: u64,
+        sentinel_whole: u64,
+        lamports: u64,
+    ) -> Result<Self> {
+        let config: seka::GlobalConfig = client
+            .fetch_account(&seka::config_pda())
+            .await
+            .context("reading the seka config")?;
+        let seka = token(client, config.mint, seka_whole, config.issuer == *faucet).await?;
+        let state: SentinelState = client
+            .fetch_account(&sentinel::state_pda())
+            .await
+            .context("reading the sentinel state")?;
+        let sentinel = if state.shared_mint {
+            None
+        } else {
+            Some(token(client, state.sentinel_mint, sentinel_whole, false).await?)
+        };
+        Ok(Self {
+            seka,
+            sentinel,
+            lamports,
+        })
+    }
+
+    fn each(&self) -> impl Iterator<Item = &Token> {
+        std::iter::once(&self.seka).chain(&self.sentinel)
+    }
+
+    /// Instructions paying one drip to `wallet`, creating its ATAs if needed.
+    pub fn drip(&self, faucet: &Pubkey, wallet: &Pubkey) -> Vec<Instruction> {
+        let mut ixs = Vec::new();
+        if self.lamports > 0 {
+            ixs.push(system_instruction::transfer(faucet, wallet, self.lamports));
+        }
+        for token in self.each().filter(|t| t.amount > 0) {
+            let ata = token.ata(wallet);
+            ixs.push(sentinel::create_associated_token_account_idempotent(
+                faucet,
+                wallet,
+                &token.mint,
+                &token.token_program,
+            ));
+            ixs.push(if token.issued {
+                seka::issue_tokens(faucet, &token.mint, &ata, token.amount)
+            } else {
+                transfer_checked(token, &token.ata(faucet), &ata, faucet)
+            });
+        }
+        ixs
+    }
+
+    /// The faucet's balance of each token it transfers, in base units, and whether it
+    /// covers another drip.
+    pub async fn balances(&self, client: &Client, faucet: &Pubkey) -> Result<Vec<Balance>> {
+        let mut balances = Vec::new();
+        for token in self.each().filter(|t| !t.issued) {
+            let amount = match client
+                .rpc()
+                .get_token_account_balance(&token.ata(faucet))
+                .await
+            {
+                Ok(balance) => balance.amount.parse().unwrap_or(0),
+                // No account yet is an empty faucet, not an error
+                Err(_) => 0,
+            };
+            balances.push(Balance {
+                mint: token.mint,
+                amount,
+                enough: amount >= token.amount,
+            });
+        }
+        Ok(balances)
+    }
+}
+
+pub struct Balance {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub enough: bool,
+}
+
+async fn token(client: &Client, mint: Pubkey, whole: u64, issued: bool) -> Result<Token> {
+    let account = client
+        .rpc()
+        .get_account(&mint)
+        .await
+        .with_context(|| format!("fetching mint {mint}"))?;
+    // Token-2022 mints start with the same layout, extensions follow
+    let Some(base) = account.data.get(..spl_token::state::Mint::LEN) else {
+        bail!("{mint} is not a mint");
+    };
+    let decimals = spl_token::state::Mint::unpack_from_slice(base)
+        .with_context(|| format!("decoding mint {mint}"))?
+        .decimals;
+    let amount = whole
+        .checked_mul(10u64.pow(u32::from(decimals)))
+        .ok_or_else(|| anyhow!("{whole} whole tokens of {mint} overflow u64"))?;
+    Ok(Token {
+        mint,
+        decimals,
+        token_program: account.owner,
+        amount,
+        issued,
+    })
+}
+
+/// `TransferChecked` for either token program; spl-token's builder only takes its own.
+fn transfer_checked(token: &Token, from: &Pubkey, to: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: token.token_program,
+        accounts: vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new_readonly(token.mint, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: TokenInstruction::TransferChecked {
+            amount: token.amount,
+            decimals: token.decimals,
+        }
+        .pack(),
+    }
+}
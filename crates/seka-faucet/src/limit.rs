@@ -0,0 +1,58 @@
+//! Sliding-window limits on drips per client IP and per wallet.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// At most `max` drips per key in any `window`.
+pub struct Limiter<K> {
+    max: u32,
+    window: Duration,
+    hits: HashMap<K, VecDeque<Instant>>,
+    last_prune: Instant,
+}
+
+impl<K: Hash + Eq + Clone> Limiter<K> {
+    pub fn new(max: u32, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            hits: HashMap::new(),
+            last_prune: Instant::now(),
+        }
+    }
+
+    /// Records a drip for `key` at `now`, or returns how long until one is allowed.
+    pub fn acquire(&mut self, key: &K, now: Instant) -> Result<(), Duration> {
+        if now.duration_since(self.last_prune) >= self.window {
+            let window = self.window;
+            self.hits.retain(|_, hits| {
+                hits.retain(|at| now.duration_since(*at) < window);
+                !hits.is_empty()
+            });
+            self.last_prune = now;
+        }
+        let hits = self.hits.entry(key.clone()).or_default();
+        while hits
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= self.window)
+        {
+            hits.pop_front();
+        }
+        if hits.len() >= self.max as usize {
+            let oldest = hits.front().copied().unwrap_or(now);
+            return Err(self.window.saturating_sub(now.duration_since(oldest)));
+        }
+        hits.push_back(now);
+        Ok(())
+    }
+
+    /// Takes back the drip recorded at `at`, for a drip that was never sent.
+    pub fn release(&mut self, key: &K, at: Instant) {
+        if let Some(hits) = self.hits.get_mut(key) {
+            if let Some(i) = hits.iter().rposition(|hit| *hit == at) {
+                hits.remove(i);
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! seka-faucet: hands out small amounts of SEKA and SENTINEL on devnet so developers can
+//! try the join and claim flows without asking the team for tokens.
+//!
+//! Each drip creates the wallet's ATAs if needed and pays `--seka` and `--sentinel`
+//! whole tokens (plus `--sol`, if set) in one transaction from the faucet key; see
+//! [`chain`] for where the tokens come from. A client IP gets `--ip-limit` drips and a
+//! wallet `--wallet-limit` drips per `--window-secs`. With `--captcha-verify-url`, every
+//! drip also needs a solved captcha token. The faucet refuses to start against mainnet,
+//! or a public cluster it was not built for: build it with
+//! `--features sentinelkarma-sdk/devnet` to serve devnet.
+//!
+//! - `GET /status` returns the faucet's address, mints, amounts, limits and balances
+//! - `POST /drip` with `{"wallet": string, "captcha_token"?: string}` pays one drip and
+//!   returns its signature; `429` with `retry_after_secs` when a limit is hit
+
+mod captcha;
+mod chain;
+mod limit;
+
+use anyhow::{bail, Result};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use captcha::Captcha;
+use chain::Tokens;
+use clap::Parser;
+use limit::Limiter;
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::signer::SignerThread;
+use sentinelkarma_sdk::{Cluster, CLUSTER};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::native_token::sol_to_lamports;
+use solana_sdk::pubkey::Pubkey;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(about = "Hand out devnet SEKA and SENTINEL over HTTP")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// Faucet key paying fees, rent and tokens: a keypair file, `usb://ledger` or a
+    /// remote signer URL
+    #[arg(long, env = "FAUCET_KEYPAIR")]
+    keypair: String,
+
+    #[arg(long, env = "LISTEN", default_value = "127.0.0.1:8095")]
+    listen: SocketAddr,
+
+    /// Whole SEKA per drip
+    #[arg(long, default_value_t = 100)]
+    seka: u64,
+
+    /// Whole SENTINEL per drip
+    #[arg(long, default_value_t = 2_000)]
+    sentinel: u64,
+
+    /// SOL per drip, for fees and membership rent
+    #[arg(long, default_value_t = 0.0)]
+    sol: f64,
+
+    /// Drips per client IP per window
+    #[arg(long, default_value_t = 5)]
+    ip_limit: u32,
+
+    /// Drips per wallet per window
+    #[arg(long, default_value_t = 1)]
+    wallet_limit: u32,
+
+    #[arg(long, default_value_t = 86_400)]
+    window_secs: u64,
+
+    /// Take the client IP from `X-Forwarded-For`; only behind a proxy that sets it
+    #[arg(long)]
+    trust_proxy: bool,
+
+    /// `siteverify` endpoint of the captcha provider; drips need a token when set
+    #[arg(long, env = "CAPTCHA_VERIFY_URL", requires = "captcha_secret")]
+    captcha_verify_url: Option<String>,
+
+    #[arg(long, env = "CAPTCHA_SECRET", hide_env_values = true)]
+    captcha_secret: Option<String>,
+}
+
+struct AppState {
+    client: Client,
+    faucet: SignerThread,
+    tokens: Tokens,
+    captcha: Option<Captcha>,
+    trust_proxy: bool,
+    ip_limit: u32,
+    wallet_limit: u32,
+    window_secs: u64,
+    by_ip: Mutex<Limiter<IpAddr>>,
+    by_wallet: Mutex<Limiter<Pubkey>>,
+}
+
+enum ApiError {
+    BadRequest(String),
+    Forbidden(String),
+    TooManyRequests(Duration),
+    Unavailable(String),
+    Internal(anyhow::Error),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, body) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, json!({ "error": message })),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, json!({ "error": message })),
+            ApiError::TooManyRequests(retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                json!({
+                    "error": "drip limit reached",
+                    "retry_after_secs": retry_after.as_secs().max(1),
+                }),
+            ),
+            ApiError::Unavailable(message) => {
+                (StatusCode::SERVICE_UNAVAILABLE, json!({ "error": message }))
+            }
+            ApiError::Internal(e) => {
+                eprintln!("error: {e:#}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    json!({ "error": format!("{e:#}") }),
+                )
+            }
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError::Internal(e)
+    }
+}
+
+impl AppState {
+    /// The first `X-Forwarded-For` hop with `--trust-proxy`, else the peer address.
+    fn client_ip(&self, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+        let forwarded = self
+            .trust_proxy
+            .then(|| headers.get("x-forwarded-for"))
+            .flatten()
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .and_then(|ip| IpAddr::from_str(ip.trim()).ok());
+        forwarded.unwrap_or(peer.ip())
+    }
+}
+
+#[derive(Deserialize)]
+struct DripRequest {
+    wallet: String,
+    captcha_token: Option<String>,
+}
+
+async fn get_status(State(state): State<Arc<AppState>>) -> Result<Json<Value>, ApiError> {
+    let balances = state
+        .tokens
+        .balances(&state.client, &state.faucet.pubkey)
+        .await?;
+    let token = |t: &chain::Token| {
+        json!({
+            "mint": t.mint.to_string(),
+            "decimals": t.decimals,
+            "amount": t.amount.to_string(),
+            "minted": t.issued,
+        })
+    };
+    Ok(Json(json!({
+        "faucet": state.faucet.pubkey.to_string(),
+        "seka": token(&state.tokens.seka),
+        "sentinel": state.tokens.sentinel.as_ref().map(token),
+        "lamports": state.tokens.lamports.to_string(),
+        "captcha": state.captcha.is_some(),
+        "limits": {
+            "per_ip": state.ip_limit,
+            "per_wallet": state.wallet_limit,
+            "window_secs": state.window_secs,
+        },
+        "balances": balances
+            .iter()
+            .map(|b| json!({
+                "mint": b.mint.to_string(),
+                "amount": b.amount.to_string(),
+                "enough": b.enough,
+            }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+async fn drip(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<DripRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let wallet = Pubkey::from_str(&request.wallet)
+        .map_err(|_| ApiError::BadRequest(format!("invalid pubkey {:?}", request.wallet)))?;
+    if !wallet.is_on_curve() {
+        return Err(ApiError::BadRequest(format!(
+            "{wallet} is a program address, not a wallet"
+        )));
+    }
+    let ip = state.client_ip(&headers, peer);
+
+    // Limits first: a flood of bad captchas should not reach the provider
+    let now = Instant::now();
+    state
+        .by_ip
+        .lock()
+        .unwrap()
+        .acquire(&ip, now)
+        .map_err(ApiError::TooManyRequests)?;
+    let by_wallet = state.by_wallet.lock().unwrap().acquire(&wallet, now);
+    if let Err(retry_after) = by_wallet {
+        state.by_ip.lock().unwrap().release(&ip, now);
+        return Err(ApiError::TooManyRequests(retry_after));
+    }
+    // A rejected captcha still counts against the IP, but not against the wallet
+    if let Err(e) = check_captcha(&state, request.captcha_token.as_deref(), ip).await {
+        state.by_wallet.lock().unwrap().release(&wallet, now);
+        if matches!(e, ApiError::Internal(_)) {
+            state.by_ip.lock().unwrap().release(&ip, now);
+        }
+        return Err(e);
+    }
+
+    let result = send_drip(&state, &wallet, ip).await;
+    if result.is_err() {
+        state.by_ip.lock().unwrap().release(&ip, now);
+        state.by_wallet.lock().unwrap().release(&wallet, now);
+    }
+    result
+}
+
+async fn check_captcha(state: &AppState, token: Option<&str>, ip: IpAddr) -> Result<(), ApiError> {
+    let Some(captcha) = &state.captcha else {
+        return Ok(());
+    };
+    let token = token.ok_or_else(|| ApiError::Forbidden("captcha_token is required".into()))?;
+    if !captcha.verify(token, ip).await? {
+        return Err(ApiError::Forbidden("captcha rejected".into()));
+    }
+    Ok(())
+}
+
+async fn send_drip(state: &AppState, wallet: &Pubkey, ip: IpAddr) -> Result<Json<Value>, ApiError> {
+    let faucet = state.faucet.pubkey;
+    let balances = state.tokens.balances(&state.client, &faucet).await?;
+    if let Some(empty) = balances.iter().find(|b| !b.enough) {
+        return Err(ApiError::Unavailable(format!(
+            "the faucet is out of {}",
+            empty.mint
+        )));
+    }
+    let signature = state
+        .faucet
+        .send(state.tokens.drip(&faucet, wallet))
+        .await?;
+    println!("drip to {wallet} from {ip}: {signature}");
+    let ata = |t: &chain::Token| t.ata(wallet).to_string();
+    Ok(Json(json!({
+        "signature": signature.to_string(),
+        "wallet": wallet.to_string(),
+        "seka_ata": ata(&state.tokens.seka),
+        "sentinel_ata": state.tokens.sentinel.as_ref().map(ata),
+        "seka": state.tokens.seka.amount.to_string(),
+        "sentinel": state.tokens.sentinel.as_ref().map(|t| t.amount.to_string()),
+        "lamports": state.tokens.lamports.to_string(),
+    })))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = Client::new(cli.rpc_url.clone(), ClientConfig::default());
+    let genesis = client.rpc().get_genesis_hash().await?;
+    let cluster = Cluster::from_genesis_hash(&genesis.to_string());
+    if cluster == Cluster::Mainnet {
+        bail!(
+            "{} is mainnet; the faucet only runs on devnet and test validators",
+            cli.rpc_url
+        );
+    }
+    // A test validator's genesis is its own, so only a public cluster can be checked
+    if cluster != Cluster::Localnet && cluster != CLUSTER {
+        bail!(
+            "{} is {}, but the faucet was built with {}'s program ids",
+            cli.rpc_url,
+            cluster.name(),
+            CLUSTER.name()
+        );
+    }
+    let faucet = SignerThread::spawn("faucet", cli.keypair, cli.rpc_url)?;
+    let tokens = Tokens::load(
+        &client,
+        &faucet.pubkey,
+        cli.seka,
+        cli.sentinel,
+        sol_to_lamports(cli.sol),
+    )
+    .await?;
+    println!("faucet {}", faucet.pubkey);
+    let mode = if tokens.seka.issued {
+        "minted"
+    } else {
+        "transferred"
+    };
+    println!("  SEKA {} ({mode})", tokens.seka.mint);
+    match &tokens.sentinel {
+        Some(sentinel) => println!("  SENTINEL {} (transferred)", sentinel.mint),
+        None => println!("  sentinel shares the SEKA mint"),
+    }
+
+    let window = Duration::from_secs(cli.window_secs);
+    let state = Arc::new(AppState {
+        client,
+        faucet,
+        tokens,
+        captcha: cli
+            .captcha_verify_url
+            .zip(cli.captcha_secret)
+            .map(|(url, secret)| Captcha::new(url, secret)),
+        trust_proxy: cli.trust_proxy,
+        ip_limit: cli.ip_limit,
+        wallet_limit: cli.wallet_limit,
+        window_secs: cli.window_secs,
+        by_ip: Mutex::new(Limiter::new(cli.ip_limit, window)),
+        by_wallet: Mutex::new(Limiter::new(cli.wallet_limit, window)),
+    });
+    let app = Router::new()
+        .route("/status", get(get_status))
+        .route("/drip", post(drip))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(cli.listen).await?;
+    println!("listening on http://{}", cli.listen);
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,845 @@
+//! A query-only GraphQL executor.
+//!
+//! Supports what frontends send: named and anonymous queries, variables with defaults,
+//! aliases, arguments, named and inline fragments, `@skip`/`@include` and
+//! `__typename`. There are no mutations or subscriptions, and no introspection; the
+//! schema is served as SDL instead. Queries are checked against resolvers as they run,
+//! so an unknown field is a field error, with `null` in its place.
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value as Json};
+use std::collections::{HashMap, HashSet};
+
+/// Deepest selection allowed, counting the root field as 1.
+const MAX_DEPTH: usize = 12;
+
+/// Most fields one query may resolve, across every list it walks.
+const MAX_FIELDS: usize = 200_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub query: String,
+    #[serde(default)]
+    pub variables: Option<Map<String, Json>>,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+}
+
+/// A GraphQL object: a named type whose fields resolve lazily.
+pub trait Object {
+    fn typename(&self) -> &'static str;
+    fn resolve(&self, field: &str, args: &Args) -> Result<Output<'_>, String>;
+}
+
+/// What a field resolves to.
+pub enum Output<'a> {
+    Leaf(Json),
+    Object(Box<dyn Object + 'a>),
+    List(Vec<Output<'a>>),
+}
+
+impl<'a> Output<'a> {
+    pub fn object(object: impl Object + 'a) -> Self {
+        Output::Object(Box::new(object))
+    }
+
+    pub fn optional(object: Option<impl Object + 'a>) -> Self {
+        object.map_or(Output::Leaf(Json::Null), Output::object)
+    }
+
+    pub fn list<O: Object + 'a>(objects: impl IntoIterator<Item = O>) -> Self {
+        Output::List(objects.into_iter().map(Output::object).collect())
+    }
+}
+
+impl<T: Into<Json>> From<T> for Output<'_> {
+    fn from(value: T) -> Self {
+        Output::Leaf(value.into())
+    }
+}
+
+/// A field's arguments, variables substituted and enum values as strings.
+#[derive(Debug, Default)]
+pub struct Args(Map<String, Json>);
+
+impl Args {
+    /// `None` when absent or `null`.
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        self.0.get(name).filter(|v| !v.is_null())
+    }
+
+    pub fn string(&self, name: &str) -> Result<Option<&str>, String> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(Json::String(s)) => Ok(Some(s)),
+            Some(v) => Err(format!("argument {name:?} must be a string, got {v}")),
+        }
+    }
+
+    pub fn int(&self, name: &str) -> Result<Option<i64>, String> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(Json::Number(n)) if n.as_i64().is_some() => Ok(n.as_i64()),
+            Some(v) => Err(format!("argument {name:?} must be an integer, got {v}")),
+        }
+    }
+
+    /// A `BigInt`: an integer, or a decimal string for values past 2^53.
+    pub fn big(&self, name: &str) -> Result<Option<i64>, String> {
+        match self.get(name) {
+            Some(Json::String(s)) => s
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("argument {name:?} must be a decimal integer, got {s:?}")),
+            _ => self.int(name),
+        }
+    }
+
+    pub fn boolean(&self, name: &str) -> Result<Option<bool>, String> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(Json::Bool(b)) => Ok(Some(*b)),
+            Some(v) => Err(format!("argument {name:?} must be a boolean, got {v}")),
+        }
+    }
+}
+
+/// Runs `request` against `root`, returning the `{data, errors}` response body.
+pub fn execute(root: &dyn Object, request: &Request) -> Json {
+    let document = match Parser::new(&request.query).and_then(|mut p| p.document()) {
+        Ok(document) => document,
+        Err(e) => return json!({ "errors": [{ "message": e }] }),
+    };
+    let operation = match pick(&document, request.operation_name.as_deref()) {
+        Ok(operation) => operation,
+        Err(e) => return json!({ "errors": [{ "message": e }] }),
+    };
+    let variables = match variables(operation, request.variables.as_ref()) {
+        Ok(variables) => variables,
+        Err(e) => return json!({ "errors": [{ "message": e }] }),
+    };
+    let mut executor = Executor {
+        fragments: document
+            .fragments
+            .iter()
+            .map(|f| (f.name.as_str(), f))
+            .collect(),
+        variables,
+        errors: Vec::new(),
+        fields: 0,
+    };
+    let data = executor.object(root, &[&operation.selections], &mut Vec::new());
+    if executor.errors.is_empty() {
+        json!({ "data": data })
+    } else {
+        json!({ "data": data, "errors": executor.errors })
+    }
+}
+
+fn pick<'d>(document: &'d Document, name: Option<&str>) -> Result<&'d Operation, String> {
+    match name {
+        Some(name) => document
+            .operations
+            .iter()
+            .find(|op| op.name.as_deref() == Some(name))
+            .ok_or_else(|| format!("unknown operation {name:?}")),
+        None => match document.operations.as_slice() {
+            [operation] => Ok(operation),
+            [] => Err("document has no operations".into()),
+            _ => Err("document has several operations; set operationName".into()),
+        },
+    }
+}
+
+fn variables(
+    operation: &Operation,
+    given: Option<&Map<String, Json>>,
+) -> Result<Map<String, Json>, String> {
+    let mut variables = Map::new();
+    for definition in &operation.variables {
+        let value = match given.and_then(|g| g.get(&definition.name)) {
+            Some(value) => value.clone(),
+            None => match &definition.default {
+                Some(default) => default.to_json(&Map::new()),
+                None => Json::Null,
+            },
+        };
+        if definition.required && value.is_null() {
+            return Err(format!("variable ${} is required", definition.name));
+        }
+        variables.insert(definition.name.clone(), value);
+    }
+    Ok(variables)
+}
+
+struct Executor<'d> {
+    fragments: HashMap<&'d str, &'d Fragment>,
+    variables: Map<String, Json>,
+    errors: Vec<Json>,
+    fields: usize,
+}
+
+/// A path segment in an error: a response key or a list index.
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl<'d> Executor<'d> {
+    fn error(&mut self, path: &[Segment], message: String) {
+        let path: Vec<Json> = path
+            .iter()
+            .map(|s| match s {
+                Segment::Key(k) => Json::from(k.as_str()),
+                Segment::Index(i) => Json::from(*i),
+            })
+            .collect();
+        self.errors
+            .push(json!({ "message": message, "path": path }));
+    }
+
+    fn object(
+        &mut self,
+        object: &dyn Object,
+        selections: &[&'d [Selection]],
+        path: &mut Vec<Segment>,
+    ) -> Json {
+        let mut fields: Vec<(&'d str, Vec<&'d Field>)> = Vec::new();
+        let mut visited = HashSet::new();
+        for selections in selections {
+            if let Err(e) = self.collect(object.typename(), selections, &mut fields, &mut visited) {
+                self.error(path, e);
+                return Json::Null;
+            }
+        }
+
+        let mut result = Map::new();
+        for (key, group) in fields {
+            path.push(Segment::Key(key.to_owned()));
+            let value = self.field(object, &group, path);
+            path.pop();
+            result.insert(key.to_owned(), value);
+        }
+        Json::Object(result)
+    }
+
+    fn field(&mut self, object: &dyn Object, group: &[&'d Field], path: &mut Vec<Segment>) -> Json {
+        let field = group[0];
+        if field.name == "__typename" {
+            return object.typename().into();
+        }
+        self.fields += 1;
+        if self.fields > MAX_FIELDS {
+            self.error(
+                path,
+                format!("query resolves more than {MAX_FIELDS} fields"),
+            );
+            return Json::Null;
+        }
+        let depth = path.iter().filter(|s| matches!(s, Segment::Key(_))).count();
+        if depth > MAX_DEPTH {
+            self.error(path, format!("query is nested deeper than {MAX_DEPTH}"));
+            return Json::Null;
+        }
+        let args = Args(
+            field
+                .arguments
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_json(&self.variables)))
+                .collect(),
+        );
+        match object.resolve(&field.name, &args) {
+            Ok(output) => {
+                let selections: Vec<&'d [Selection]> =
+                    group.iter().map(|f| f.selections.as_slice()).collect();
+                self.complete(output, &field.name, &selections, path)
+            }
+            Err(e) => {
+                self.error(path, e);
+                Json::Null
+            }
+        }
+    }
+
+    fn complete(
+        &mut self,
+        output: Output<'_>,
+        name: &str,
+        selections: &[&'d [Selection]],
+        path: &mut Vec<Segment>,
+    ) -> Json {
+        let selected = selections.iter().any(|s| !s.is_empty());
+        match output {
+            Output::Leaf(Json::Null) => Json::Null,
+            Output::Leaf(value) if !selected => value,
+            Output::Leaf(_) => {
+                self.error(
+                    path,
+                    format!("field {name:?} is a scalar and takes no selection"),
+                );
+                Json::Null
+            }
+            Output::Object(_) if !selected => {
+                self.error(
+                    path,
+                    format!("field {name:?} needs a selection of subfields"),
+                );
+                Json::Null
+            }
+            Output::Object(object) => self.object(object.as_ref(), selections, path),
+            Output::List(items) => Json::Array(
+                items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        path.push(Segment::Index(i));
+                        let value = self.complete(item, name, selections, path);
+                        path.pop();
+                        value
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Flattens fragments that apply to `typename` into fields grouped by response key.
+    fn collect(
+        &self,
+        typename: &str,
+        selections: &'d [Selection],
+        fields: &mut Vec<(&'d str, Vec<&'d Field>)>,
+        visited: &mut HashSet<&'d str>,
+    ) -> Result<(), String> {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    if !self.included(&field.directives)? {
+                        continue;
+                    }
+                    let key = field.alias.as_deref().unwrap_or(&field.name);
+                    match fields.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, group)) if group[0].name != field.name => {
+                            return Err(format!(
+                                "response key {key:?} selects both {:?} and {:?}",
+                                group[0].name, field.name
+                            ));
+                        }
+                        Some((_, group)) => group.push(field),
+                        None => fields.push((key, vec![field])),
+                    }
+                }
+                Selection::Spread { name, directives } => {
+                    if !self.included(directives)? || !visited.insert(name) {
+                        continue;
+                    }
+                    let fragment = self
+                        .fragments
+                        .get(name.as_str())
+                        .ok_or_else(|| format!("unknown fragment {name:?}"))?;
+                    if fragment.on == typename {
+                        self.collect(typename, &fragment.selections, fields, visited)?;
+                    }
+                }
+                Selection::Inline {
+                    on,
+                    directives,
+                    selections,
+                } => {
+                    if !self.included(directives)? {
+                        continue;
+                    }
+                    if on.as_deref().is_none_or(|on| on == typename) {
+                        self.collect(typename, selections, fields, visited)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn included(&self, directives: &[Directive]) -> Result<bool, String> {
+        for directive in directives {
+            let condition = || {
+                let (_, value) = directive
+                    .arguments
+                    .iter()
+                    .find(|(name, _)| name == "if")
+                    .ok_or_else(|| format!("@{} needs an `if` argument", directive.name))?;
+                match value.to_json(&self.variables) {
+                    Json::Bool(b) => Ok(b),
+                    v => Err(format!(
+                        "@{}(if:) must be a boolean, got {v}",
+                        directive.name
+                    )),
+                }
+            };
+            match directive.name.as_str() {
+                "skip" if condition()? => return Ok(false),
+                "include" if !condition()? => return Ok(false),
+                "skip" | "include" => {}
+                name => return Err(format!("unknown directive @{name}")),
+            }
+        }
+        Ok(true)
+    }
+}
+
+struct Document {
+    operations: Vec<Operation>,
+    fragments: Vec<Fragment>,
+}
+
+struct Operation {
+    name: Option<String>,
+    variables: Vec<VariableDefinition>,
+    selections: Vec<Selection>,
+}
+
+struct VariableDefinition {
+    name: String,
+    /// The outer type is non-null
+    required: bool,
+    default: Option<Value>,
+}
+
+struct Fragment {
+    name: String,
+    on: String,
+    selections: Vec<Selection>,
+}
+
+enum Selection {
+    Field(Field),
+    Spread {
+        name: String,
+        directives: Vec<Directive>,
+    },
+    Inline {
+        on: Option<String>,
+        directives: Vec<Directive>,
+        selections: Vec<Selection>,
+    },
+}
+
+struct Field {
+    alias: Option<String>,
+    name: String,
+    arguments: Vec<(String, Value)>,
+    directives: Vec<Directive>,
+    selections: Vec<Selection>,
+}
+
+struct Directive {
+    name: String,
+    arguments: Vec<(String, Value)>,
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Variable(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn to_json(&self, variables: &Map<String, Json>) -> Json {
+        match self {
+            Value::Variable(name) => variables.get(name).cloned().unwrap_or(Json::Null),
+            Value::Int(i) => (*i).into(),
+            Value::Float(f) => (*f).into(),
+            Value::String(s) | Value::Enum(s) => s.as_str().into(),
+            Value::Boolean(b) => (*b).into(),
+            Value::Null => Json::Null,
+            Value::List(items) => items.iter().map(|v| v.to_json(variables)).collect(),
+            Value::Object(fields) => Json::Object(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_json(variables)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Punct(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+fn lex(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() || c == ',' || c == '\u{feff}' => {}
+            '#' => while chars.next_if(|&c| c != '\n' && c != '\r').is_some() {},
+            '!' | '$' | '&' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => {
+                tokens.push(Token::Punct(c))
+            }
+            '.' => {
+                if chars.next() != Some('.') || chars.next() != Some('.') {
+                    return Err("expected `...`".into());
+                }
+                tokens.push(Token::Spread);
+            }
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = String::from(c);
+                while let Some(c) = chars.next_if(|&c| c == '_' || c.is_ascii_alphanumeric()) {
+                    name.push(c);
+                }
+                tokens.push(Token::Name(name));
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::from(c);
+                let mut float = false;
+                while let Some(c) = chars
+                    .next_if(|&c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+                {
+                    float |= matches!(c, '.' | 'e' | 'E');
+                    number.push(c);
+                }
+                tokens.push(if float {
+                    Token::Float(
+                        number
+                            .parse()
+                            .map_err(|_| format!("invalid number {number}"))?,
+                    )
+                } else {
+                    Token::Int(
+                        number
+                            .parse()
+                            .map_err(|_| format!("invalid integer {number}"))?,
+                    )
+                });
+            }
+            '"' => tokens.push(Token::Str(string(&mut chars)?)),
+            c => return Err(format!("unexpected character {c:?}")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// A string after its opening quote; `"""` block strings are taken verbatim.
+fn string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut s = String::new();
+    if chars.next_if_eq(&'"').is_some() {
+        if chars.next_if_eq(&'"').is_none() {
+            return Ok(s);
+        }
+        loop {
+            match chars.next() {
+                Some('"') if chars.next_if_eq(&'"').is_some() => {
+                    if chars.next_if_eq(&'"').is_some() {
+                        return Ok(s);
+                    }
+                    s.push_str("\"\"");
+                }
+                Some(c) => s.push(c),
+                None => return Err("unterminated block string".into()),
+            }
+        }
+    }
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('"') => s.push('"'),
+                Some('\\') => s.push('\\'),
+                Some('/') => s.push('/'),
+                Some('b') => s.push('\u{8}'),
+                Some('f') => s.push('\u{c}'),
+                Some('n') => s.push('\n'),
+                Some('r') => s.push('\r'),
+                Some('t') => s.push('\t'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape \\u{hex}"))?;
+                    s.push(c);
+                }
+                c => return Err(format!("invalid escape {c:?}")),
+            },
+            Some('\n') | None => return Err("unterminated string".into()),
+            Some(c) => s.push(c),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, String> {
+        Ok(Self {
+            tokens: lex(source)?,
+            pos: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or("unexpected end of query")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn at(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn at_name(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(n)) if n == name)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let at = self.at(c);
+        if at {
+            self.pos += 1;
+        }
+        at
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            token => Err(format!("expected `{c}`, found {token:?}")),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Name(name) => Ok(name),
+            token => Err(format!("expected a name, found {token:?}")),
+        }
+    }
+
+    fn document(&mut self) -> Result<Document, String> {
+        let mut document = Document {
+            operations: Vec::new(),
+            fragments: Vec::new(),
+        };
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Punct('{') => document.operations.push(Operation {
+                    name: None,
+                    variables: Vec::new(),
+                    selections: self.selections()?,
+                }),
+                Token::Name(n) if n == "query" => {
+                    self.pos += 1;
+                    document.operations.push(self.operation()?);
+                }
+                Token::Name(n) if n == "fragment" => {
+                    self.pos += 1;
+                    document.fragments.push(self.fragment()?);
+                }
+                Token::Name(n) if n == "mutation" || n == "subscription" => {
+                    return Err(format!(
+                        "{n} operations are not supported; this API is read-only"
+                    ));
+                }
+                token => return Err(format!("unexpected {token:?}")),
+            }
+        }
+        Ok(document)
+    }
+
+    fn operation(&mut self) -> Result<Operation, String> {
+        let name = match self.peek() {
+            Some(Token::Name(_)) => Some(self.name()?),
+            _ => None,
+        };
+        let mut variables = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                self.expect('$')?;
+                let name = self.name()?;
+                self.expect(':')?;
+                let required = self.skip_type()?;
+                let default = if self.eat('=') {
+                    Some(self.value()?)
+                } else {
+                    None
+                };
+                self.directives()?;
+                variables.push(VariableDefinition {
+                    name,
+                    required,
+                    default,
+                });
+            }
+        }
+        self.directives()?;
+        Ok(Operation {
+            name,
+            variables,
+            selections: self.selections()?,
+        })
+    }
+
+    /// Skips a type reference, returning whether it is non-null.
+    fn skip_type(&mut self) -> Result<bool, String> {
+        if self.eat('[') {
+            self.skip_type()?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        Ok(self.eat('!'))
+    }
+
+    fn fragment(&mut self) -> Result<Fragment, String> {
+        let name = self.name()?;
+        if name == "on" {
+            return Err("a fragment cannot be named `on`".into());
+        }
+        if self.name()? != "on" {
+            return Err(format!("expected `on` after fragment {name}"));
+        }
+        let on = self.name()?;
+        self.directives()?;
+        Ok(Fragment {
+            name,
+            on,
+            selections: self.selections()?,
+        })
+    }
+
+    fn selections(&mut self) -> Result<Vec<Selection>, String> {
+        self.expect('{')?;
+        let mut selections = Vec::new();
+        while !self.eat('}') {
+            selections.push(self.selection()?);
+        }
+        if selections.is_empty() {
+            return Err("empty selection set".into());
+        }
+        Ok(selections)
+    }
+
+    fn selection(&mut self) -> Result<Selection, String> {
+        if self.peek() == Some(&Token::Spread) {
+            self.pos += 1;
+            if self.at_name("on") {
+                self.pos += 1;
+                let on = Some(self.name()?);
+                let directives = self.directives()?;
+                let selections = self.selections()?;
+                return Ok(Selection::Inline {
+                    on,
+                    directives,
+                    selections,
+                });
+            }
+            if let Some(Token::Name(_)) = self.peek() {
+                let name = self.name()?;
+                let directives = self.directives()?;
+                return Ok(Selection::Spread { name, directives });
+            }
+            let directives = self.directives()?;
+            let selections = self.selections()?;
+            return Ok(Selection::Inline {
+                on: None,
+                directives,
+                selections,
+            });
+        }
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.eat(':') {
+            alias = Some(name);
+            name = self.name()?;
+        }
+        let arguments = self.arguments()?;
+        let directives = self.directives()?;
+        let selections = if self.at('{') {
+            self.selections()?
+        } else {
+            Vec::new()
+        };
+        Ok(Selection::Field(Field {
+            alias,
+            name,
+            arguments,
+            directives,
+            selections,
+        }))
+    }
+
+    fn arguments(&mut self) -> Result<Vec<(String, Value)>, String> {
+        let mut arguments = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                let name = self.name()?;
+                self.expect(':')?;
+                arguments.push((name, self.value()?));
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn directives(&mut self) -> Result<Vec<Directive>, String> {
+        let mut directives = Vec::new();
+        while self.eat('@') {
+            let name = self.name()?;
+            let arguments = self.arguments()?;
+            directives.push(Directive { name, arguments });
+        }
+        Ok(directives)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        Ok(match self.next()? {
+            Token::Punct('$') => Value::Variable(self.name()?),
+            Token::Int(i) => Value::Int(i),
+            Token::Float(f) => Value::Float(f),
+            Token::Str(s) => Value::String(s),
+            Token::Name(n) => match n.as_str() {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                _ => Value::Enum(n),
+            },
+            Token::Punct('[') => {
+                let mut items = Vec::new();
+                while !self.eat(']') {
+                    items.push(self.value()?);
+                }
+                Value::List(items)
+            }
+            Token::Punct('{') => {
+                let mut fields = Vec::new();
+                while !self.eat('}') {
+                    let name = self.name()?;
+                    self.expect(':')?;
+                    fields.push((name, self.value()?));
+                }
+                Value::Object(fields)
+            }
+            token => return Err(format!("expected a value, found {token:?}")),
+        })
+    }
+}
@@ -0,0 +1,105 @@
+//! seka-graphql: a read-only GraphQL API over the indexer database.
+//!
+//! Peers, ledgers, cycles, claims, posts, likes and leaderboards are projected from
+//! the rows `sentinelkarma-indexer` writes, kept in memory and refreshed every
+//! `--refresh-secs`. Every `--rebuild-secs` the projection is rebuilt from scratch,
+//! which picks up rows backfilled behind the newest slot.
+//!
+//! - `POST /graphql` takes `{"query", "variables", "operationName"}`
+//! - `GET /graphql/schema.graphql` returns the schema as SDL
+
+mod graphql;
+mod schema;
+
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Parser;
+use sentinelkarma_indexer::projection::{self, Model};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Serve peers, ledgers, cycles, claims, posts and likes over GraphQL")]
+struct Cli {
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    #[arg(long, env = "LISTEN", default_value = "127.0.0.1:8092")]
+    listen: SocketAddr,
+
+    /// How often to read newly indexed rows
+    #[arg(long, default_value_t = 5)]
+    refresh_secs: u64,
+
+    /// How often to rebuild from every row, picking up backfills
+    #[arg(long, default_value_t = 3600)]
+    rebuild_secs: u64,
+}
+
+async fn graphql(
+    State(state): State<Arc<RwLock<Model>>>,
+    Json(request): Json<graphql::Request>,
+) -> Response {
+    let response = tokio::task::spawn_blocking(move || {
+        let model = state.read().unwrap();
+        graphql::execute(&schema::Query(&model), &request)
+    })
+    .await;
+    match response {
+        Ok(body) => Json(body).into_response(),
+        Err(e) => {
+            eprintln!("error: {e}");
+            let body = json!({ "errors": [{ "message": "internal error" }] });
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+        }
+    }
+}
+
+async fn sdl() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        schema::SDL,
+    )
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+    let model = Model::load(store.as_mut())?;
+    println!(
+        "loaded {} peers, {} cycles, {} claims, {} posts up to slot {}",
+        model.peers.len(),
+        model.cycles.len(),
+        model.claims.len(),
+        model.posts.len(),
+        model.slot
+    );
+    drop(store);
+
+    let state = Arc::new(RwLock::new(model));
+    {
+        let state = state.clone();
+        let every = Duration::from_secs(cli.refresh_secs.max(1));
+        let rebuild = Duration::from_secs(cli.rebuild_secs.max(1));
+        let database_url = cli.database_url.clone();
+        std::thread::spawn(move || projection::follow(state, &database_url, every, rebuild));
+    }
+
+    let app = Router::new()
+        .route("/graphql", post(graphql))
+        .route("/graphql/schema.graphql", get(sdl))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(cli.listen).await?;
+    println!("listening on http://{}/graphql", cli.listen);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
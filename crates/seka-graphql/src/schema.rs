@@ -0,0 +1,748 @@
+//! The schema: its SDL and the resolvers that answer it from a [`Model`].
+//!
+//! Pubkeys are base58 strings, and 64-bit integers are `BigInt`s serialized as decimal
+//! strings so JavaScript clients do not lose precision, as in seka-stream frames.
+
+use crate::graphql::{Args, Object, Output};
+use seka_reputation::{Params, Signals};
+use sentinelkarma_indexer::projection::{self as model, At, Model};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SDL: &str = r#""A 64-bit integer, serialized as a decimal string"
+scalar BigInt
+
+enum Order {
+  "Oldest first"
+  ASC
+  "Newest first"
+  DESC
+}
+
+enum PostStatus {
+  ACTIVE
+  REMOVED
+  CLOSED
+  DELETED
+}
+
+enum LeaderboardMetric {
+  "Current ledger points"
+  POINTS
+  "Points claimed from cycle roots; per cycle with `cycle`"
+  KARMA_CLAIMED
+  "Tokens minted by converting points"
+  TOKENS_MINTED
+  "Likes on the posts an account authored"
+  LIKES_RECEIVED
+  "Posts an account authored"
+  POSTS
+}
+
+"`first` defaults to 50 and may be at most 500; `after` takes a previous `endCursor`"
+type PageInfo {
+  hasNextPage: Boolean!
+  endCursor: String
+}
+
+type Query {
+  "Highest slot indexed"
+  indexedSlot: BigInt!
+  peer(owner: String!): Peer
+  peers(active: Boolean, first: Int, after: String): PeerConnection!
+  ledger(owner: String!): Ledger
+  ledgers(minPoints: BigInt, first: Int, after: String): LedgerConnection!
+  cycle(index: BigInt!): Cycle
+  cycles(from: BigInt, to: BigInt, order: Order = ASC, first: Int, after: String): CycleConnection!
+  claims(owner: String, cycle: BigInt, order: Order = ASC, first: Int, after: String): ClaimConnection!
+  post(address: String!): Post
+  posts(author: String, owner: String, status: PostStatus, order: Order = ASC, first: Int, after: String): PostConnection!
+  likes(liker: String, post: String, order: Order = ASC, first: Int, after: String): LikeConnection!
+  leaderboard(metric: LeaderboardMetric!, cycle: BigInt, first: Int, after: String): LeaderboardConnection!
+}
+
+"A seka network member"
+type Peer {
+  owner: String!
+  active: Boolean!
+  "The transaction that joined"
+  signature: String!
+  slot: BigInt!
+  blockTime: BigInt
+  ledger: Ledger
+  posts(status: PostStatus, order: Order = ASC, first: Int, after: String): PostConnection!
+  likes(order: Order = ASC, first: Int, after: String): LikeConnection!
+  "Now, or as of a unix time"
+  reputation(asOf: BigInt): Reputation!
+}
+
+"A score under seka-reputation's default weights; see that crate for the formula"
+type Reputation {
+  "0 to 100"
+  score: Float!
+  "The normalized signals, 0 to 1"
+  points: Float!
+  history: Float!
+  reports: Float!
+  age: Float!
+  "Multiplier for upheld reports against the peer's posts"
+  penalty: Float!
+  cyclesClaimed: BigInt!
+  reportsUpheld: Int!
+  reportsRejected: Int!
+  postsUpheldAgainst: Int!
+}
+
+type Ledger {
+  owner: String!
+  points: BigInt!
+  lastCycleClaimed: BigInt!
+  karmaClaimed: BigInt!
+  pointsConverted: BigInt!
+  tokensMinted: BigInt!
+  peer: Peer
+  claims(cycle: BigInt, order: Order = ASC, first: Int, after: String): ClaimConnection!
+}
+
+type Cycle {
+  index: BigInt!
+  "Hex"
+  merkleRoot: String!
+  totalPointsDeclared: Int!
+  claimCount: Int!
+  pointsClaimed: BigInt!
+  "The transaction that set the root"
+  signature: String!
+  slot: BigInt!
+  blockTime: BigInt
+  claims(owner: String, order: Order = ASC, first: Int, after: String): ClaimConnection!
+}
+
+type Claim {
+  "`signature:event_index`"
+  id: String!
+  owner: String!
+  cycleIndex: BigInt!
+  deltaPoints: Int!
+  newPoints: BigInt!
+  signature: String!
+  slot: BigInt!
+  blockTime: BigInt
+  cycle: Cycle
+  ledger: Ledger
+}
+
+type Post {
+  "The post PDA"
+  address: String!
+  asset: String!
+  author: String!
+  owner: String!
+  status: PostStatus!
+  likeCount: Int!
+  editCount: Int!
+  "The minting transaction"
+  signature: String!
+  slot: BigInt!
+  blockTime: BigInt
+  likes(order: Order = ASC, first: Int, after: String): LikeConnection!
+}
+
+type Like {
+  "`signature:ix_index`"
+  id: String!
+  liker: String!
+  postAddress: String!
+  "Null when the post was minted before indexing began"
+  post: Post
+  signature: String!
+  slot: BigInt!
+  blockTime: BigInt
+}
+
+type LeaderboardEntry {
+  "1-based"
+  rank: Int!
+  owner: String!
+  value: BigInt!
+  peer: Peer
+  ledger: Ledger
+}
+
+type PeerConnection { nodes: [Peer!]! pageInfo: PageInfo! totalCount: Int! }
+type LedgerConnection { nodes: [Ledger!]! pageInfo: PageInfo! totalCount: Int! }
+type CycleConnection { nodes: [Cycle!]! pageInfo: PageInfo! totalCount: Int! }
+type ClaimConnection { nodes: [Claim!]! pageInfo: PageInfo! totalCount: Int! }
+type PostConnection { nodes: [Post!]! pageInfo: PageInfo! totalCount: Int! }
+type LikeConnection { nodes: [Like!]! pageInfo: PageInfo! totalCount: Int! }
+type LeaderboardConnection { nodes: [LeaderboardEntry!]! pageInfo: PageInfo! totalCount: Int! }
+"#;
+
+const DEFAULT_PAGE: i64 = 50;
+const MAX_PAGE: i64 = 500;
+
+/// The root object.
+pub struct Query<'a>(pub &'a Model);
+
+impl Object for Query<'_> {
+    fn typename(&self) -> &'static str {
+        "Query"
+    }
+
+    fn resolve(&self, field: &str, args: &Args) -> Result<Output<'_>, String> {
+        let model = self.0;
+        match field {
+            "indexedSlot" => Ok(big(model.slot)),
+            "peer" => {
+                let owner = required(pubkey(args, "owner")?, "owner")?;
+                Ok(Output::optional(peer(model, &owner)))
+            }
+            "peers" => {
+                let active = args.boolean("active")?;
+                let peers = model
+                    .peers
+                    .values()
+                    .filter(|p| active.is_none_or(|a| p.active == a))
+                    .map(|peer| Peer { model, peer })
+                    .collect();
+                page("PeerConnection", peers, args, |p| p.peer.owner.to_string())
+            }
+            "ledger" => {
+                let owner = required(pubkey(args, "owner")?, "owner")?;
+                Ok(Output::optional(ledger(model, &owner)))
+            }
+            "ledgers" => {
+                let min = args.big("minPoints")?;
+                let ledgers = model
+                    .ledgers
+                    .iter()
+                    .filter(|(_, l)| min.is_none_or(|min| l.points >= min))
+                    .map(|(owner, ledger)| Ledger {
+                        model,
+                        owner: *owner,
+                        ledger,
+                    })
+                    .collect();
+                page("LedgerConnection", ledgers, args, |l| l.owner.to_string())
+            }
+            "cycle" => {
+                let index = required(args.big("index")?, "index")?;
+                Ok(Output::optional(cycle(model, index as u64)))
+            }
+            "cycles" => {
+                let from = args.big("from")?.unwrap_or(0) as u64;
+                let to = args.big("to")?.map_or(u64::MAX, |to| to as u64);
+                let mut cycles: Vec<_> = model
+                    .cycles
+                    .range(from..)
+                    .take_while(|(index, _)| **index <= to)
+                    .map(|(_, cycle)| Cycle { model, cycle })
+                    .collect();
+                if descending(args)? {
+                    cycles.reverse();
+                }
+                page("CycleConnection", cycles, args, |c| {
+                    c.cycle.index.to_string()
+                })
+            }
+            "claims" => claims(model, pubkey(args, "owner")?, args.big("cycle")?, args),
+            "post" => {
+                let address = required(pubkey(args, "address")?, "address")?;
+                Ok(Output::optional(post(model, &address)))
+            }
+            "posts" => posts(model, pubkey(args, "author")?, pubkey(args, "owner")?, args),
+            "likes" => likes(model, pubkey(args, "liker")?, pubkey(args, "post")?, args),
+            "leaderboard" => leaderboard(model, args),
+            _ => Err(unknown(field, self.typename())),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Peer<'a> {
+    model: &'a Model,
+    peer: &'a model::Peer,
+}
+
+impl Object for Peer<'_> {
+    fn typename(&self) -> &'static str {
+        "Peer"
+    }
+
+    fn resolve(&self, field: &str, args: &Args) -> Result<Output<'_>, String> {
+        let owner = self.peer.owner;
+        match field {
+            "owner" => Ok(owner.to_string().into()),
+            "active" => Ok(self.peer.active.into()),
+            "ledger" => Ok(Output::optional(ledger(self.model, &owner))),
+            "posts" => posts(self.model, Some(owner), None, args),
+            "likes" => likes(self.model, Some(owner), None, args),
+            "reputation" => {
+                let as_of = args.big("asOf")?.unwrap_or_else(now);
+                let params = Params::default();
+                let signals = Signals::of(self.model, &params, &owner, as_of);
+                let reputation = signals.score(&params, as_of);
+                Ok(Output::object(Reputation {
+                    signals,
+                    reputation,
+                }))
+            }
+            _ => at(&self.peer.joined, field).ok_or_else(|| unknown(field, self.typename())),
+        }
+    }
+}
+
+struct Reputation {
+    signals: Signals,
+    reputation: seka_reputation::Reputation,
+}
+
+impl Object for Reputation {
+    fn typename(&self) -> &'static str {
+        "Reputation"
+    }
+
+    fn resolve(&self, field: &str, _: &Args) -> Result<Output<'_>, String> {
+        let r = &self.reputation;
+        Ok(match field {
+            "score" => r.score.into(),
+            "points" => r.points.into(),
+            "history" => r.history.into(),
+            "reports" => r.reports.into(),
+            "age" => r.age.into(),
+            "penalty" => r.penalty.into(),
+            "cyclesClaimed" => big(self.signals.cycles_claimed),
+            "reportsUpheld" => self.signals.reports_upheld.into(),
+            "reportsRejected" => self.signals.reports_rejected.into(),
+            "postsUpheldAgainst" => self.signals.posts_upheld_against.into(),
+            _ => return Err(unknown(field, self.typename())),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Ledger<'a> {
+    model: &'a Model,
+    owner: Pubkey,
+    ledger: &'a model::Ledger,
+}
+
+impl Object for Ledger<'_> {
+    fn typename(&self) -> &'static str {
+        "Ledger"
+    }
+
+    fn resolve(&self, field: &str, args: &Args) -> Result<Output<'_>, String> {
+        let ledger = self.ledger;
+        Ok(match field {
+            "owner" => self.owner.to_string().into(),
+            "points" => big(ledger.points),
+            "lastCycleClaimed" => big(ledger.last_cycle_claimed),
+            "karmaClaimed" => big(ledger.karma_claimed),
+            "pointsConverted" => big(ledger.points_converted),
+            "tokensMinted" => big(ledger.tokens_minted),
+            "peer" => Output::optional(peer(self.model, &self.owner)),
+            "claims" => claims(self.model, Some(self.owner), args.big("cycle")?, args)?,
+            _ => return Err(unknown(field, self.typename())),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Cycle<'a> {
+    model: &'a Model,
+    cycle: &'a model::Cycle,
+}
+
+impl Object for Cycle<'_> {
+    fn typename(&self) -> &'static str {
+        "Cycle"
+    }
+
+    fn resolve(&self, field: &str, args: &Args) -> Result<Output<'_>, String> {
+        let cycle = self.cycle;
+        match field {
+            "index" => Ok(big(cycle.index)),
+            "merkleRoot" => Ok(hex::encode(cycle.merkle_root).into()),
+            "totalPointsDeclared" => Ok(cycle.total_points_declared.into()),
+            "claimCount" => Ok(cycle.claims.into()),
+            "pointsClaimed" => Ok(big(cycle.points_claimed)),
+            "claims" => {
+                let owner = pubkey(args, "owner")?;
+                claims(self.model, owner, Some(cycle.index as i64), args)
+            }
+            _ => at(&cycle.published, field).ok_or_else(|| unknown(field, self.typename())),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Claim<'a> {
+    model: &'a Model,
+    claim: &'a model::Claim,
+}
+
+impl Object for Claim<'_> {
+    fn typename(&self) -> &'static str {
+        "Claim"
+    }
+
+    fn resolve(&self, field: &str, _: &Args) -> Result<Output<'_>, String> {
+        let claim = self.claim;
+        match field {
+            "id" => Ok(claim.id.as_str().into()),
+            "owner" => Ok(claim.owner.to_string().into()),
+            "cycleIndex" => Ok(big(claim.cycle_index)),
+            "deltaPoints" => Ok(claim.delta_points.into()),
+            "newPoints" => Ok(big(claim.new_points)),
+            "cycle" => Ok(Output::optional(cycle(self.model, claim.cycle_index))),
+            "ledger" => Ok(Output::optional(ledger(self.model, &claim.owner))),
+            _ => at(&claim.at, field).ok_or_else(|| unknown(field, self.typename())),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Post<'a> {
+    model: &'a Model,
+    post: &'a model::Post,
+}
+
+impl Object for Post<'_> {
+    fn typename(&self) -> &'static str {
+        "Post"
+    }
+
+    fn resolve(&self, field: &str, args: &Args) -> Result<Output<'_>, String> {
+        let post = self.post;
+        match field {
+            "address" => Ok(post.address.to_string().into()),
+            "asset" => Ok(post.asset.to_string().into()),
+            "author" => Ok(post.author.to_string().into()),
+            "owner" => Ok(post.owner.to_string().into()),
+            "status" => Ok(status(post).into()),
+            "likeCount" => Ok(post.likes.into()),
+            "editCount" => Ok(post.edit_count.into()),
+            "likes" => likes(self.model, None, Some(post.address), args),
+            _ => at(&post.created, field).ok_or_else(|| unknown(field, self.typename())),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Like<'a> {
+    model: &'a Model,
+    like: &'a model::Like,
+}
+
+impl Object for Like<'_> {
+    fn typename(&self) -> &'static str {
+        "Like"
+    }
+
+    fn resolve(&self, field: &str, _: &Args) -> Result<Output<'_>, String> {
+        let like = self.like;
+        match field {
+            "id" => Ok(like.id.as_str().into()),
+            "liker" => Ok(like.liker.to_string().into()),
+            "postAddress" => Ok(like.post.to_string().into()),
+            "post" => Ok(Output::optional(post(self.model, &like.post))),
+            _ => at(&like.at, field).ok_or_else(|| unknown(field, self.typename())),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LeaderboardEntry<'a> {
+    model: &'a Model,
+    rank: usize,
+    owner: Pubkey,
+    value: i64,
+}
+
+impl Object for LeaderboardEntry<'_> {
+    fn typename(&self) -> &'static str {
+        "LeaderboardEntry"
+    }
+
+    fn resolve(&self, field: &str, _: &Args) -> Result<Output<'_>, String> {
+        Ok(match field {
+            "rank" => self.rank.into(),
+            "owner" => self.owner.to_string().into(),
+            "value" => big(self.value),
+            "peer" => Output::optional(peer(self.model, &self.owner)),
+            "ledger" => Output::optional(ledger(self.model, &self.owner)),
+            _ => return Err(unknown(field, self.typename())),
+        })
+    }
+}
+
+struct Connection<T> {
+    typename: &'static str,
+    nodes: Vec<T>,
+    total: usize,
+    end_cursor: Option<String>,
+    has_next_page: bool,
+}
+
+impl<T: Object + Clone> Object for Connection<T> {
+    fn typename(&self) -> &'static str {
+        self.typename
+    }
+
+    fn resolve(&self, field: &str, _: &Args) -> Result<Output<'_>, String> {
+        Ok(match field {
+            "nodes" => Output::list(self.nodes.iter().cloned()),
+            "totalCount" => self.total.into(),
+            "pageInfo" => Output::object(PageInfo {
+                has_next_page: self.has_next_page,
+                end_cursor: self.end_cursor.clone(),
+            }),
+            _ => return Err(unknown(field, self.typename())),
+        })
+    }
+}
+
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+impl Object for PageInfo {
+    fn typename(&self) -> &'static str {
+        "PageInfo"
+    }
+
+    fn resolve(&self, field: &str, _: &Args) -> Result<Output<'_>, String> {
+        Ok(match field {
+            "hasNextPage" => self.has_next_page.into(),
+            "endCursor" => self.end_cursor.clone().into(),
+            _ => return Err(unknown(field, self.typename())),
+        })
+    }
+}
+
+/// The page of `items` selected by `first` and `after`, `cursor` naming each item.
+fn page<'a, T: Object + Clone + 'a>(
+    typename: &'static str,
+    items: Vec<T>,
+    args: &Args,
+    cursor: impl Fn(&T) -> String,
+) -> Result<Output<'a>, String> {
+    let first = args.int("first")?.unwrap_or(DEFAULT_PAGE);
+    if !(0..=MAX_PAGE).contains(&first) {
+        return Err(format!("first must be between 0 and {MAX_PAGE}"));
+    }
+    let start = match args.string("after")? {
+        Some(after) => {
+            let position = items.iter().position(|item| cursor(item) == after);
+            position.ok_or_else(|| format!("unknown cursor {after:?}"))? + 1
+        }
+        None => 0,
+    };
+    let total = items.len();
+    let end = (start + first as usize).min(total);
+    let nodes = items[start..end].to_vec();
+    Ok(Output::object(Connection {
+        typename,
+        end_cursor: nodes.last().map(&cursor),
+        nodes,
+        total,
+        has_next_page: end < total,
+    }))
+}
+
+fn claims<'a>(
+    model: &'a Model,
+    owner: Option<Pubkey>,
+    cycle: Option<i64>,
+    args: &Args,
+) -> Result<Output<'a>, String> {
+    let mut claims: Vec<_> = model
+        .claims
+        .iter()
+        .filter(|c| owner.is_none_or(|o| c.owner == o))
+        .filter(|c| cycle.is_none_or(|i| c.cycle_index == i as u64))
+        .map(|claim| Claim { model, claim })
+        .collect();
+    if descending(args)? {
+        claims.reverse();
+    }
+    page("ClaimConnection", claims, args, |c| c.claim.id.clone())
+}
+
+fn posts<'a>(
+    model: &'a Model,
+    author: Option<Pubkey>,
+    owner: Option<Pubkey>,
+    args: &Args,
+) -> Result<Output<'a>, String> {
+    let wanted = args.string("status")?;
+    let mut posts: Vec<_> = model
+        .posts
+        .values()
+        .filter(|p| author.is_none_or(|a| p.author == a))
+        .filter(|p| owner.is_none_or(|o| p.owner == o))
+        .filter(|p| wanted.is_none_or(|s| status(p) == s))
+        .map(|post| Post { model, post })
+        .collect();
+    posts.sort_by_key(|p| (p.post.created.slot, p.post.address));
+    if descending(args)? {
+        posts.reverse();
+    }
+    page("PostConnection", posts, args, |p| {
+        p.post.address.to_string()
+    })
+}
+
+fn likes<'a>(
+    model: &'a Model,
+    liker: Option<Pubkey>,
+    post: Option<Pubkey>,
+    args: &Args,
+) -> Result<Output<'a>, String> {
+    let mut likes: Vec<_> = model
+        .likes
+        .iter()
+        .filter(|l| liker.is_none_or(|k| l.liker == k))
+        .filter(|l| post.is_none_or(|p| l.post == p))
+        .map(|like| Like { model, like })
+        .collect();
+    if descending(args)? {
+        likes.reverse();
+    }
+    page("LikeConnection", likes, args, |l| l.like.id.clone())
+}
+
+fn leaderboard<'a>(model: &'a Model, args: &Args) -> Result<Output<'a>, String> {
+    let metric = required(args.string("metric")?, "metric")?;
+    let cycle = args.big("cycle")?;
+    let mut totals: BTreeMap<Pubkey, i64> = BTreeMap::new();
+    match (metric, cycle) {
+        ("POINTS", None) => totals.extend(model.ledgers.iter().map(|(o, l)| (*o, l.points))),
+        ("KARMA_CLAIMED", None) => totals.extend(
+            model
+                .ledgers
+                .iter()
+                .map(|(o, l)| (*o, l.karma_claimed as i64)),
+        ),
+        ("KARMA_CLAIMED", Some(cycle)) => {
+            for claim in model
+                .claims
+                .iter()
+                .filter(|c| c.cycle_index == cycle as u64)
+            {
+                *totals.entry(claim.owner).or_default() += claim.delta_points.max(0) as i64;
+            }
+        }
+        ("TOKENS_MINTED", None) => totals.extend(
+            model
+                .ledgers
+                .iter()
+                .map(|(o, l)| (*o, l.tokens_minted as i64)),
+        ),
+        ("LIKES_RECEIVED", None) => {
+            for post in model.posts.values() {
+                *totals.entry(post.author).or_default() += post.likes as i64;
+            }
+        }
+        ("POSTS", None) => {
+            for post in model.posts.values() {
+                *totals.entry(post.author).or_default() += 1;
+            }
+        }
+        ("POINTS" | "TOKENS_MINTED" | "LIKES_RECEIVED" | "POSTS", Some(_)) => {
+            return Err(format!("cycle does not apply to {metric}"));
+        }
+        _ => return Err(format!("unknown leaderboard metric {metric:?}")),
+    }
+
+    let mut ranked: Vec<(Pubkey, i64)> = totals.into_iter().filter(|(_, v)| *v != 0).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let entries = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(i, (owner, value))| LeaderboardEntry {
+            model,
+            rank: i + 1,
+            owner,
+            value,
+        })
+        .collect();
+    page("LeaderboardConnection", entries, args, |e| {
+        e.rank.to_string()
+    })
+}
+
+fn peer<'a>(model: &'a Model, owner: &Pubkey) -> Option<Peer<'a>> {
+    model.peers.get(owner).map(|peer| Peer { model, peer })
+}
+
+fn ledger<'a>(model: &'a Model, owner: &Pubkey) -> Option<Ledger<'a>> {
+    model.ledgers.get(owner).map(|ledger| Ledger {
+        model,
+        owner: *owner,
+        ledger,
+    })
+}
+
+fn cycle(model: &Model, index: u64) -> Option<Cycle<'_>> {
+    model.cycles.get(&index).map(|cycle| Cycle { model, cycle })
+}
+
+fn post<'a>(model: &'a Model, address: &Pubkey) -> Option<Post<'a>> {
+    model.posts.get(address).map(|post| Post { model, post })
+}
+
+fn status(post: &model::Post) -> &'static str {
+    match post.status {
+        Some("removed") => "REMOVED",
+        Some("closed") => "CLOSED",
+        Some("deleted") => "DELETED",
+        _ => "ACTIVE",
+    }
+}
+
+/// The `signature`, `slot` and `blockTime` fields shared by recorded things.
+fn at<'a>(at: &At, field: &str) -> Option<Output<'a>> {
+    Some(match field {
+        "signature" => at.signature.clone().into(),
+        "slot" => big(at.slot),
+        "blockTime" => at.block_time.map(|t| t.to_string()).into(),
+        _ => return None,
+    })
+}
+
+fn big<'a>(value: impl ToString) -> Output<'a> {
+    value.to_string().into()
+}
+
+fn descending(args: &Args) -> Result<bool, String> {
+    match args.string("order")? {
+        None | Some("ASC") => Ok(false),
+        Some("DESC") => Ok(true),
+        Some(order) => Err(format!("unknown order {order:?}")),
+    }
+}
+
+fn pubkey(args: &Args, name: &str) -> Result<Option<Pubkey>, String> {
+    args.string(name)?
+        .map(|s| Pubkey::from_str(s).map_err(|_| format!("{name} is not a valid pubkey: {s:?}")))
+        .transpose()
+}
+
+fn required<T>(value: Option<T>, name: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("argument {name:?} is required"))
+}
+
+fn unknown(field: &str, typename: &str) -> String {
+    format!("cannot query field {field:?} on type {typename:?}")
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
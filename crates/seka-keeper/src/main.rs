@@ -0,0 +1,393 @@
+//! seka-keeper: publishes each closed seka cycle's Merkle root without an operator.
+//!
+//! Cycle `k` covers `[start_ts + k * cycle_secs, start_ts + (k + 1) * cycle_secs)` of
+//! cluster time. Once a cycle has been closed for `--grace-secs`, the keeper pulls its
+//! karma deltas from `--source` (see `source.rs`), builds the tree, checks it against
+//! the config caps, sends `set_cycle_root` as the governor and archives the result:
+//!
+//! - `<archive-dir>/<cycle>.json`: the dataset, so the archive directory doubles as
+//!   `seka-proof-server --data-dir`;
+//! - `<archive-dir>/<cycle>/root.json` and `proofs/<owner>.json`, as `seka-proofs build`
+//!   writes them.
+//!
+//! The archive is written to `.<cycle>.partial` before sending and moved into place once
+//! the root lands, so a published cycle never lacks its proofs; a keeper that dies in
+//! between finishes the move on its next run. A cycle's root can be set only once, so
+//! cycles already on-chain are skipped.
+//!
+//! `set_cycle_root` goes through the SDK's submission queue in `--queue-dir`, so a keeper
+//! restarted mid-send settles the transaction it signed before signing another. With
+//! `--nonce-account` it signs against a durable nonce, for governors on a Ledger or a
+//! remote signer whose approval may outlast a blockhash.
+//!
+//! Failures are retried every `--poll-secs` and reported once per distinct error through
+//! `--alert-webhook` and/or `--alert-command`, and again when the keeper recovers.
+
+mod source;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use seka_aggregation::Rules;
+use seka_merkle::{LeafFormat, MerkleTree};
+use seka_proofs::{parse_leaf_format, write_json, write_output, InputRow, RootFile};
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::queue::Queue;
+use sentinelkarma_sdk::seka::{self, CycleState, GlobalConfig};
+use sentinelkarma_sdk::signer;
+use serde_json::json;
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::sysvar;
+use source::{Source, Window};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Publish seka cycle roots on schedule and archive their proofs")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// `GlobalConfig.governor` keypair file, `usb://ledger[?key=0/0]` or a remote signer
+    /// `https://…?pubkey=<base58>`; also pays fees
+    #[arg(long, env = "GOVERNOR_KEYPAIR")]
+    governor: String,
+
+    /// `file:<dir>`, `http(s)://…/{cycle}` or `indexer:<database-url>`
+    #[arg(long, env = "SOURCE")]
+    source: String,
+
+    #[arg(long, env = "ARCHIVE_DIR")]
+    archive_dir: PathBuf,
+
+    /// Pending `set_cycle_root` transactions; defaults to `<archive-dir>/.queue`
+    #[arg(long, env = "QUEUE_DIR")]
+    queue_dir: Option<PathBuf>,
+
+    /// Durable nonce account to sign against, with the governor as authority (`solana
+    /// create-nonce-account <keypair> <sol> --nonce-authority <governor>`)
+    #[arg(long, env = "NONCE_ACCOUNT")]
+    nonce_account: Option<Pubkey>,
+
+    /// Scoring rules for the indexer source; see seka-aggregation's rules.example.json
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// Format to build roots in: `solana`, or `evm` for roots an EVM contract can verify too
+    #[arg(long, env = "LEAF_FORMAT", default_value = "solana", value_parser = parse_leaf_format)]
+    leaf_format: LeafFormat,
+
+    /// First cycle to publish; defaults to the most recently closed one
+    #[arg(long)]
+    first_cycle: Option<u64>,
+
+    /// Seconds to wait after a cycle ends before pulling its deltas
+    #[arg(long, default_value_t = 300)]
+    grace_secs: u64,
+
+    /// Longest sleep between checks, and the retry delay after a failure, in seconds
+    #[arg(long, default_value_t = 60)]
+    poll_secs: u64,
+
+    /// URL to POST `{"text", "status", "cycle_index"}` to on failure and recovery
+    #[arg(long, env = "ALERT_WEBHOOK")]
+    alert_webhook: Option<String>,
+
+    /// Shell command run on failure and recovery, with `SEKA_KEEPER_STATUS` and
+    /// `SEKA_KEEPER_MESSAGE` set
+    #[arg(long, env = "ALERT_COMMAND")]
+    alert_command: Option<String>,
+
+    /// Publish every due cycle and exit
+    #[arg(long)]
+    once: bool,
+}
+
+struct Keeper {
+    client: Client,
+    queue: Queue,
+    governor: Box<dyn Signer>,
+    source: Box<dyn Source>,
+    archive_dir: PathBuf,
+    leaf_format: LeafFormat,
+    grace_secs: i64,
+    /// Next cycle to publish; `None` until the first pass picks a default
+    next: Option<u64>,
+}
+
+struct Alerts {
+    webhook: Option<(String, reqwest::blocking::Client)>,
+    command: Option<String>,
+    /// Message of the failure currently alerted, so a stuck cycle alerts once
+    active: Option<String>,
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let governor = signer::resolve(&cli.governor)?;
+    let rules = match &cli.rules {
+        Some(path) => Rules::read(path)?,
+        None => Rules::default(),
+    };
+    fs::create_dir_all(&cli.archive_dir)
+        .with_context(|| format!("creating {}", cli.archive_dir.display()))?;
+    let client = Client::new(&cli.rpc_url, ClientConfig::default());
+    let queue_dir = cli.queue_dir.unwrap_or_else(|| cli.archive_dir.join(".queue"));
+    let mut queue = Queue::open(client.clone(), queue_dir)?;
+    if let Some(account) = cli.nonce_account {
+        queue = queue.with_nonce(account);
+    }
+    let mut keeper = Keeper {
+        client,
+        queue,
+        governor,
+        source: source::open(&cli.source, rules)?,
+        archive_dir: cli.archive_dir,
+        leaf_format: cli.leaf_format,
+        grace_secs: cli.grace_secs as i64,
+        next: cli.first_cycle,
+    };
+    let mut alerts = Alerts {
+        webhook: cli
+            .alert_webhook
+            .map(|url| (url, reqwest::blocking::Client::new())),
+        command: cli.alert_command,
+        active: None,
+    };
+    let poll = Duration::from_secs(cli.poll_secs.max(1));
+
+    loop {
+        match keeper.tick().await {
+            Ok(wait) => {
+                alerts.resolve(keeper.next);
+                if cli.once {
+                    return Ok(());
+                }
+                tokio::time::sleep(wait.min(poll)).await;
+            }
+            Err(e) => {
+                let message = format!("{e:#}");
+                eprintln!("error: {message}");
+                alerts.fail(keeper.next, message);
+                if cli.once {
+                    return Err(e);
+                }
+                tokio::time::sleep(poll).await;
+            }
+        }
+    }
+}
+
+impl Keeper {
+    /// Publishes every cycle that is due. Returns how long until the next one is.
+    async fn tick(&mut self) -> Result<Duration> {
+        let config: GlobalConfig = self.client.fetch_account(&seka::config_pda()).await?;
+        if config.governor != self.governor.pubkey() {
+            bail!(
+                "{} is not the seka governor ({})",
+                self.governor.pubkey(),
+                config.governor
+            );
+        }
+        if config.cycle_secs == 0 {
+            bail!("seka config has cycle_secs = 0");
+        }
+        let cycle_secs = config.cycle_secs as i64;
+        let now = self.chain_time().await?;
+
+        // Cycles 0..due have ended at least grace_secs ago
+        let elapsed = now - self.grace_secs - config.start_ts;
+        let due = if elapsed < 0 {
+            0
+        } else {
+            (elapsed / cycle_secs) as u64
+        };
+        let next = *self.next.get_or_insert(due.saturating_sub(1));
+
+        for cycle_index in next..due {
+            let window = Window::new(&config, cycle_index);
+            self.publish(&config, &window)
+                .await
+                .with_context(|| format!("cycle {cycle_index}"))?;
+            self.next = Some(cycle_index + 1);
+        }
+
+        let next_due = config.start_ts + (due as i64 + 1) * cycle_secs + self.grace_secs;
+        println!("cycle {due} is due in {}s", next_due - now);
+        Ok(Duration::from_secs((next_due - now).max(1) as u64))
+    }
+
+    async fn publish(&mut self, config: &GlobalConfig, window: &Window) -> Result<()> {
+        let cycle_index = window.cycle_index;
+        let address = seka::cycle_state_pda(cycle_index);
+        if self.exists(&address).await? {
+            let state: CycleState = self.client.fetch_account(&address).await?;
+            return self.recover(&state);
+        }
+
+        let source = &mut self.source;
+        let leaves = tokio::task::block_in_place(|| source.deltas(window))?;
+        if leaves.is_empty() {
+            println!("cycle {cycle_index}: no deltas; nothing to publish");
+            return Ok(());
+        }
+        if let Some(leaf) = leaves
+            .iter()
+            .find(|l| l.delta_points.unsigned_abs() > config.per_peer_cycle_cap.unsigned_abs())
+        {
+            bail!(
+                "{} has delta {} over the per-peer cap {}",
+                leaf.owner,
+                leaf.delta_points,
+                config.per_peer_cycle_cap
+            );
+        }
+        let dataset: Vec<InputRow> = leaves.iter().map(InputRow::from).collect();
+        let tree = MerkleTree::with_format(self.leaf_format, cycle_index, leaves)?;
+        let total = tree.total_points_declared()?;
+        if total > config.max_points_per_cycle {
+            bail!(
+                "declares {total} points, over max_points_per_cycle {}",
+                config.max_points_per_cycle
+            );
+        }
+
+        let partial = self.partial_dir(cycle_index);
+        if partial.exists() {
+            fs::remove_dir_all(&partial)?;
+        }
+        let root = write_output(&tree, &partial)?;
+        write_json(&partial.join("dataset.json"), &dataset)?;
+
+        let ix = seka::set_cycle_root(
+            &self.governor.pubkey(),
+            cycle_index,
+            tree.root(),
+            root.total_points_declared,
+            root.claims_bitmap_len,
+            root.leaf_count,
+            tree.format(),
+        );
+        let id = format!("set-cycle-root-{cycle_index}");
+        let signature = self.queue.submit(&id, &[ix], self.governor.as_ref(), &[]).await?;
+        println!(
+            "cycle {cycle_index}: root {} for {} leaves, {total} points: {signature}",
+            root.merkle_root, root.leaf_count
+        );
+        self.promote(cycle_index)
+    }
+
+    /// Finishes archiving a cycle found already on-chain.
+    fn recover(&self, state: &CycleState) -> Result<()> {
+        let cycle_index = state.cycle_index;
+        let partial = self.partial_dir(cycle_index);
+        if self.archive_dir.join(cycle_index.to_string()).is_dir() {
+            return Ok(());
+        }
+        if !partial.is_dir() {
+            eprintln!("warning: cycle {cycle_index} was published elsewhere and is not archived");
+            return Ok(());
+        }
+        let root_path = partial.join("root.json");
+        let root: RootFile = serde_json::from_slice(&fs::read(&root_path)?)
+            .with_context(|| format!("reading {}", root_path.display()))?;
+        if root.merkle_root != hex::encode(state.merkle_root) {
+            eprintln!(
+                "warning: cycle {cycle_index} was published with another root; discarding {}",
+                partial.display()
+            );
+            return Ok(fs::remove_dir_all(&partial)?);
+        }
+        println!("cycle {cycle_index}: root landed before the archive was moved; finishing");
+        self.promote(cycle_index)
+    }
+
+    fn promote(&self, cycle_index: u64) -> Result<()> {
+        let partial = self.partial_dir(cycle_index);
+        let dataset = self.archive_dir.join(format!("{cycle_index}.json"));
+        let dir = self.archive_dir.join(cycle_index.to_string());
+        // Already moved if an earlier run stopped between the two renames
+        if partial.join("dataset.json").exists() {
+            fs::rename(partial.join("dataset.json"), &dataset)
+                .with_context(|| format!("moving dataset to {}", dataset.display()))?;
+        }
+        fs::rename(&partial, &dir)
+            .with_context(|| format!("moving proofs to {}", dir.display()))?;
+        println!("cycle {cycle_index}: archived to {}", dir.display());
+        Ok(())
+    }
+
+    fn partial_dir(&self, cycle_index: u64) -> PathBuf {
+        self.archive_dir.join(format!(".{cycle_index}.partial"))
+    }
+
+    /// Cluster time, which is what the cycle schedule is measured in.
+    async fn chain_time(&self) -> Result<i64> {
+        let account = self.client.rpc().get_account(&sysvar::clock::ID).await?;
+        let clock: Clock =
+            solana_sdk::account::from_account(&account).context("decoding clock sysvar")?;
+        Ok(clock.unix_timestamp)
+    }
+
+    async fn exists(&self, address: &Pubkey) -> Result<bool> {
+        let account = self
+            .client
+            .rpc()
+            .get_account_with_commitment(address, self.client.config().commitment)
+            .await?;
+        Ok(account.value.is_some())
+    }
+}
+
+impl Alerts {
+    fn fail(&mut self, cycle_index: Option<u64>, message: String) {
+        if self.active.as_ref() == Some(&message) {
+            return;
+        }
+        self.send(
+            "failed",
+            cycle_index,
+            &format!("seka-keeper failed: {message}"),
+        );
+        self.active = Some(message);
+    }
+
+    fn resolve(&mut self, cycle_index: Option<u64>) {
+        if self.active.take().is_some() {
+            self.send("resolved", cycle_index, "seka-keeper recovered");
+        }
+    }
+
+    fn send(&self, status: &str, cycle_index: Option<u64>, text: &str) {
+        tokio::task::block_in_place(|| {
+            if let Some((url, client)) = &self.webhook {
+                let body = json!({ "text": text, "status": status, "cycle_index": cycle_index });
+                if let Err(e) = client
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .and_then(|r| r.error_for_status())
+                {
+                    eprintln!("alert webhook: {e}");
+                }
+            }
+            if let Some(command) = &self.command {
+                let result = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("SEKA_KEEPER_STATUS", status)
+                    .env("SEKA_KEEPER_MESSAGE", text)
+                    .status();
+                match result {
+                    Ok(exit) if exit.success() => {}
+                    Ok(exit) => eprintln!("alert command: {exit}"),
+                    Err(e) => eprintln!("alert command: {e}"),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,119 @@
+//! Where a cycle's karma deltas come from. `--source` picks one:
+//!
+//! - `file:<dir>` (or a bare directory): `<dir>/<cycle_index>.csv|json` in the
+//!   `seka-proofs build` input format, dropped there by an external aggregation job;
+//! - `http(s)://…`: a GET of the URL with `{cycle}`, `{start_ts}` and `{end_ts}`
+//!   substituted, answering a JSON array of `{"owner", "delta"}`;
+//! - `indexer:<database-url>`: the indexer's sentinel activity in the cycle's time
+//!   window, scored by `seka_aggregation` under `--rules`.
+//!
+//! A source that has nothing yet for a closed cycle must fail rather than return an
+//! empty list: an empty list means no activity, and the cycle is skipped for good. The
+//! indexer source cannot tell the two apart, so run the keeper with a `--grace-secs`
+//! longer than the indexer's lag.
+
+use anyhow::{bail, Context, Result};
+use seka_aggregation::{aggregate, Rules};
+use seka_merkle::Leaf;
+use seka_proofs::{read_input, to_leaves, InputRow};
+use sentinelkarma_indexer::store::Store;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub use seka_aggregation::Window;
+
+pub trait Source {
+    /// The cycle's leaves, in the order they should take in the tree.
+    fn deltas(&mut self, window: &Window) -> Result<Vec<Leaf>>;
+}
+
+/// `rules` applies to the indexer source only.
+pub fn open(spec: &str, rules: Rules) -> Result<Box<dyn Source>> {
+    if let Some(url) = spec.strip_prefix("indexer:") {
+        let store = sentinelkarma_indexer::open(url)?;
+        return Ok(Box::new(IndexerSource { store, rules }));
+    }
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        if !spec.contains("{cycle}") {
+            bail!("HTTP source {spec:?} has no {{cycle}} placeholder");
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()?;
+        return Ok(Box::new(HttpSource {
+            template: spec.to_string(),
+            client,
+        }));
+    }
+    let dir = PathBuf::from(spec.strip_prefix("file:").unwrap_or(spec));
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    Ok(Box::new(FileSource { dir }))
+}
+
+struct FileSource {
+    dir: PathBuf,
+}
+
+impl Source for FileSource {
+    fn deltas(&mut self, window: &Window) -> Result<Vec<Leaf>> {
+        let cycle = window.cycle_index;
+        let Some(path) = ["csv", "json"]
+            .iter()
+            .map(|ext| self.dir.join(format!("{cycle}.{ext}")))
+            .find(|path| path.is_file())
+        else {
+            bail!("no dataset for cycle {cycle} in {} yet", self.dir.display());
+        };
+        read_input(&path).with_context(|| format!("reading {}", path.display()))
+    }
+}
+
+struct HttpSource {
+    template: String,
+    client: reqwest::blocking::Client,
+}
+
+impl Source for HttpSource {
+    fn deltas(&mut self, window: &Window) -> Result<Vec<Leaf>> {
+        let url = self
+            .template
+            .replace("{cycle}", &window.cycle_index.to_string())
+            .replace("{start_ts}", &window.start_ts.to_string())
+            .replace("{end_ts}", &window.end_ts.to_string());
+        let rows: Vec<InputRow> = self
+            .client
+            .get(&url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .with_context(|| format!("fetching {url}"))?;
+        to_leaves(rows)
+    }
+}
+
+struct IndexerSource {
+    store: Box<dyn Store>,
+    rules: Rules,
+}
+
+impl Source for IndexerSource {
+    fn deltas(&mut self, window: &Window) -> Result<Vec<Leaf>> {
+        let aggregation = aggregate(self.store.as_mut(), window, &self.rules)?;
+        let stats = &aggregation.stats;
+        if stats.undated > 0 || stats.unattributed > 0 {
+            eprintln!(
+                "warning: cycle {}: {} undated and {} unattributed rows not counted",
+                window.cycle_index, stats.undated, stats.unattributed
+            );
+        }
+        if let Some(total) = stats.scaled_from {
+            println!(
+                "cycle {}: scaled {total} points down to the cycle cap",
+                window.cycle_index
+            );
+        }
+        Ok(aggregation.leaves)
+    }
+}
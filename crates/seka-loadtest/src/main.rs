@@ -0,0 +1,580 @@
+//! seka-loadtest: publishes a synthetic cycle on a local validator, fires `claim_karma`
+//! at it from many wallets and reports throughput, failures and compute units.
+//!
+//! The cycle has `--leaves` random owners, so every claim also creates a ledger, the
+//! worst case for rent and compute. `--wallets` payers are airdropped enough SOL for
+//! their share of the claims and send round-robin, without preflight, up to `--rate`
+//! transactions a second. Signatures are polled until they confirm or `--timeout-secs`
+//! after the last send; units are read from up to `--cu-sample` landed transactions.
+//! The report also gives the cycle account's size and rent, which is what the bitmap
+//! costs at this leaf count, for comparison with `seka-cost`'s receipt and sharded
+//! estimates.
+//!
+//! Localnet only: the governor's keypair publishes the cycle and payers are funded by
+//! airdrop.
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use seka_merkle::{Leaf, MerkleTree, Proof};
+use sentinelkarma_sdk::{decode_account, program_error, seka, signer};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_transaction_status::UiTransactionEncoding;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+
+/// `getSignatureStatuses` limit.
+const MAX_STATUSES_PER_CALL: usize = 256;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Blockhashes last ~60s; refreshing often keeps late sends from expiring.
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(1);
+/// Base fee per signature.
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Funding headroom per payer on top of its claims' rent and fees.
+const PAYER_HEADROOM: u64 = 10_000_000;
+
+#[derive(Parser)]
+#[command(about = "Load-test seka claims against a local validator")]
+struct Cli {
+    #[arg(long, env = "SEKA_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Governor keypair, or a `usb://` / `http(s)://` signer locator
+    #[arg(long, env = "SEKA_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    /// Leaves in the synthetic cycle
+    #[arg(long, default_value_t = 20_000)]
+    leaves: u32,
+
+    /// Claims to send, from leaf 0 up; defaults to every leaf
+    #[arg(long)]
+    claims: Option<u32>,
+
+    /// Payer wallets sending claims
+    #[arg(long, default_value_t = 64)]
+    wallets: u32,
+
+    /// Transactions per second to send at most; unlimited if absent
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// Sends in flight at once
+    #[arg(long, default_value_t = 256)]
+    concurrency: usize,
+
+    /// Cycle index to publish; defaults to the current unix time, which is unused
+    #[arg(long)]
+    cycle: Option<u64>,
+
+    /// Every leaf's delta; the cycle's positive deltas must fit `max_points_per_cycle`
+    #[arg(long, default_value_t = 0, allow_hyphen_values = true)]
+    delta: i32,
+
+    /// Seed for the leaf owners
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Landed claims to read compute units from
+    #[arg(long, default_value_t = 500)]
+    cu_sample: usize,
+
+    /// How long to wait for confirmations after the last send
+    #[arg(long, default_value_t = 60)]
+    timeout_secs: u64,
+
+    /// Write the report as JSON
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    cycle_index: u64,
+    leaves: u32,
+    wallets: u32,
+    proof_depth: usize,
+    /// Serialized size of one claim transaction
+    tx_bytes: usize,
+    /// `CycleState` account size and rent, i.e. the bitmap's cost at this leaf count
+    cycle_state_bytes: usize,
+    cycle_state_rent: u64,
+    /// Rent each first claim pays for the owner's ledger
+    ledger_rent: u64,
+    sent: u64,
+    /// Sends the RPC node rejected, by message
+    send_errors: BTreeMap<String, u64>,
+    landed: u64,
+    /// Landed transactions that failed, by program error
+    failed: BTreeMap<String, u64>,
+    /// Sent but not confirmed before the timeout
+    unconfirmed: u64,
+    failure_rate: f64,
+    /// Claims set in the on-chain bitmap afterwards; should equal `landed - failed`
+    bitmap_claimed: u32,
+    send_secs: f64,
+    send_tps: f64,
+    /// Successful claims per second, first send to last confirmation
+    landed_tps: f64,
+    /// Send to confirmation, at `POLL_INTERVAL` resolution
+    latency_ms: Option<Distribution>,
+    compute_units: Option<Distribution>,
+}
+
+#[derive(Serialize)]
+struct Distribution {
+    samples: usize,
+    min: u64,
+    p50: u64,
+    p90: u64,
+    p99: u64,
+    max: u64,
+    mean: f64,
+}
+
+impl Distribution {
+    fn of(mut values: Vec<u64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_unstable();
+        let at = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+        Some(Distribution {
+            samples: values.len(),
+            min: values[0],
+            p50: at(0.5),
+            p90: at(0.9),
+            p99: at(0.99),
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+        })
+    }
+}
+
+/// A claim handed to the RPC node.
+struct Sent {
+    signature: Signature,
+    at: Instant,
+}
+
+/// Where a sent claim ended up.
+#[derive(Default)]
+struct Outcomes {
+    /// Successful claims and their latency
+    ok: Vec<(Signature, Duration)>,
+    failed: BTreeMap<String, u64>,
+    unconfirmed: u64,
+    last_landed: Option<Instant>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let claims = cli.claims.unwrap_or(cli.leaves);
+    if claims == 0 || claims > cli.leaves {
+        bail!("--claims must be positive and at most --leaves");
+    }
+    if cli.wallets == 0 || cli.concurrency == 0 {
+        bail!("--wallets and --concurrency must be positive");
+    }
+    if cli.rate.is_some_and(|rate| rate <= 0.0) {
+        bail!("--rate must be positive");
+    }
+    let governor = signer::resolve(&cli.keypair)?;
+    let cycle_index = match cli.cycle {
+        Some(cycle_index) => cycle_index,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    let rpc = Arc::new(RpcClient::new_with_commitment(
+        cli.url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
+    let config: seka::GlobalConfig =
+        decode_account(&rpc.get_account_data(&seka::config_pda()).await?)?;
+    if governor.pubkey() != config.governor {
+        bail!(
+            "{} is not the governor {}",
+            governor.pubkey(),
+            config.governor
+        );
+    }
+    if cli.delta.unsigned_abs() > config.per_peer_cycle_cap.unsigned_abs() {
+        bail!(
+            "|--delta| is over per_peer_cycle_cap {}",
+            config.per_peer_cycle_cap
+        );
+    }
+
+    let mut rng = StdRng::seed_from_u64(cli.seed);
+    let leaves = (0..cli.leaves)
+        .map(|_| Leaf {
+            owner: Pubkey::new_from_array(rng.gen()),
+            delta_points: cli.delta,
+        })
+        .collect();
+    let tree = MerkleTree::new(cycle_index, leaves)?;
+    let total = tree.total_points_declared()?;
+    if total > config.max_points_per_cycle {
+        bail!(
+            "{total} points declared is over max_points_per_cycle {}; lower --delta or --leaves",
+            config.max_points_per_cycle
+        );
+    }
+    let cycle_state_bytes = seka::CycleState::space_for(tree.claims_bitmap_len());
+    if cycle_state_bytes > MAX_PERMITTED_DATA_INCREASE {
+        bail!(
+            "{} leaves need a {cycle_state_bytes}-byte cycle account; `init` allows {MAX_PERMITTED_DATA_INCREASE}",
+            cli.leaves
+        );
+    }
+    if rpc
+        .get_account_with_commitment(&seka::cycle_state_pda(cycle_index), rpc.commitment())
+        .await?
+        .value
+        .is_some()
+    {
+        bail!("cycle {cycle_index} already has a root; pass another --cycle");
+    }
+
+    let ix = seka::set_cycle_root(
+        &governor.pubkey(),
+        cycle_index,
+        tree.root(),
+        total,
+        tree.claims_bitmap_len(),
+        tree.leaf_count(),
+        tree.format(),
+    );
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&governor.pubkey()),
+        &[governor.as_ref()],
+        blockhash,
+    );
+    rpc.send_and_confirm_transaction(&tx)
+        .await
+        .context("publishing the cycle root")?;
+    let cycle_state_rent = rpc
+        .get_minimum_balance_for_rent_exemption(cycle_state_bytes)
+        .await?;
+    let ledger_rent = rpc
+        .get_minimum_balance_for_rent_exemption(seka::PeerLedger::SPACE)
+        .await?;
+    println!(
+        "cycle {cycle_index}: {} leaves, depth {}, {cycle_state_bytes}-byte cycle account",
+        cli.leaves,
+        tree.proof(0).map_or(0, |p| p.proof.len())
+    );
+
+    let payers: Vec<Arc<Keypair>> = (0..cli.wallets).map(|_| Arc::new(Keypair::new())).collect();
+    let per_payer = u64::from(claims.div_ceil(cli.wallets));
+    let funding = per_payer * (ledger_rent + LAMPORTS_PER_SIGNATURE) + PAYER_HEADROOM;
+    fund(&rpc, &payers, funding).await?;
+    println!(
+        "funded {} payers with {funding} lamports each",
+        payers.len()
+    );
+
+    let proofs: Vec<Proof> = (0..claims).filter_map(|i| tree.proof(i)).collect();
+    let tx_bytes = claim_tx(&proofs[0], &payers[0], Hash::default())
+        .message_data()
+        .len()
+        + 65;
+
+    let (sent_tx, sent_rx) = mpsc::unbounded_channel();
+    let poller = tokio::spawn(poll(
+        rpc.clone(),
+        sent_rx,
+        Duration::from_secs(cli.timeout_secs),
+    ));
+
+    let start = Instant::now();
+    let mut ticker = cli
+        .rate
+        .map(|rate| tokio::time::interval(Duration::from_secs_f64(1.0 / rate)));
+    let slots = Arc::new(Semaphore::new(cli.concurrency));
+    let mut sends = JoinSet::new();
+    let mut blockhash = rpc.get_latest_blockhash().await?;
+    let mut blockhash_at = Instant::now();
+    for (i, proof) in proofs.iter().enumerate() {
+        if let Some(ticker) = &mut ticker {
+            ticker.tick().await;
+        }
+        if blockhash_at.elapsed() >= BLOCKHASH_REFRESH {
+            blockhash = rpc.get_latest_blockhash().await?;
+            blockhash_at = Instant::now();
+        }
+        let tx = claim_tx(proof, &payers[i % payers.len()], blockhash);
+        let slot = slots.clone().acquire_owned().await?;
+        let rpc = rpc.clone();
+        let sent_tx = sent_tx.clone();
+        sends.spawn(async move {
+            let config = RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            };
+            let at = Instant::now();
+            let result = rpc.send_transaction_with_config(&tx, config).await;
+            drop(slot);
+            match result {
+                Ok(signature) => {
+                    let _ = sent_tx.send(Sent { signature, at });
+                    Ok(())
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        });
+    }
+    drop(sent_tx);
+    let mut sent = 0;
+    let mut send_errors = BTreeMap::new();
+    while let Some(result) = sends.join_next().await {
+        match result? {
+            Ok(()) => sent += 1,
+            Err(e) => *send_errors.entry(e).or_insert(0) += 1,
+        }
+    }
+    let send_secs = start.elapsed().as_secs_f64();
+    println!("sent {sent} claims in {send_secs:.1}s, waiting for confirmations");
+
+    let outcomes = poller.await??;
+    let landed_ok = outcomes.ok.len() as u64;
+    let landed = landed_ok + outcomes.failed.values().sum::<u64>();
+    let landed_secs = outcomes
+        .last_landed
+        .map_or(0.0, |at| at.duration_since(start).as_secs_f64());
+
+    let state: seka::CycleState = decode_account(
+        &rpc.get_account_data(&seka::cycle_state_pda(cycle_index))
+            .await?,
+    )?;
+    let bitmap_claimed = state.claims_bitmap.iter().map(|b| b.count_ones()).sum();
+
+    let report = Report {
+        cycle_index,
+        leaves: cli.leaves,
+        wallets: cli.wallets,
+        proof_depth: proofs[0].proof.len(),
+        tx_bytes,
+        cycle_state_bytes,
+        cycle_state_rent,
+        ledger_rent,
+        sent,
+        failure_rate: if sent == 0 {
+            0.0
+        } else {
+            1.0 - landed_ok as f64 / (sent + send_errors.values().sum::<u64>()) as f64
+        },
+        send_errors,
+        landed,
+        failed: outcomes.failed,
+        unconfirmed: outcomes.unconfirmed,
+        bitmap_claimed,
+        send_secs,
+        send_tps: sent as f64 / send_secs,
+        landed_tps: if landed_secs > 0.0 {
+            landed_ok as f64 / landed_secs
+        } else {
+            0.0
+        },
+        latency_ms: Distribution::of(
+            outcomes
+                .ok
+                .iter()
+                .map(|(_, latency)| latency.as_millis() as u64)
+                .collect(),
+        ),
+        compute_units: Distribution::of(compute_units(&rpc, &outcomes.ok, cli.cu_sample).await?),
+    };
+    print(&report);
+    if let Some(path) = &cli.out {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, json + "\n").with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn claim_tx(proof: &Proof, payer: &Keypair, blockhash: Hash) -> Transaction {
+    let ix = seka::claim_karma(
+        &payer.pubkey(),
+        proof.owner,
+        proof.cycle_index,
+        proof.delta_points,
+        proof.leaf_index,
+        proof.proof.clone(),
+    );
+    Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash)
+}
+
+/// Airdrops `lamports` to every payer and waits for the airdrops to confirm.
+async fn fund(rpc: &RpcClient, payers: &[Arc<Keypair>], lamports: u64) -> Result<()> {
+    let mut signatures = Vec::with_capacity(payers.len());
+    for payer in payers {
+        let signature = rpc
+            .request_airdrop(&payer.pubkey(), lamports)
+            .await
+            .context("requesting airdrop; is this a local validator?")?;
+        signatures.push(signature);
+    }
+    for signature in &signatures {
+        rpc.poll_for_signature(signature)
+            .await
+            .with_context(|| format!("confirming airdrop {signature}"))?;
+    }
+    Ok(())
+}
+
+/// Polls the signatures coming off `sent` until each confirms, or until `timeout` has
+/// passed since the last one came in.
+async fn poll(
+    rpc: Arc<RpcClient>,
+    mut sent: mpsc::UnboundedReceiver<Sent>,
+    timeout: Duration,
+) -> Result<Outcomes> {
+    let mut outcomes = Outcomes::default();
+    let mut pending: HashMap<Signature, Instant> = HashMap::new();
+    let mut open = true;
+    let mut last_sent = Instant::now();
+    loop {
+        while open {
+            match sent.try_recv() {
+                Ok(s) => {
+                    pending.insert(s.signature, s.at);
+                    last_sent = Instant::now();
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => open = false,
+            }
+        }
+        if !open && (pending.is_empty() || last_sent.elapsed() >= timeout) {
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let signatures: Vec<Signature> = pending.keys().copied().collect();
+        for chunk in signatures.chunks(MAX_STATUSES_PER_CALL) {
+            let statuses = rpc.get_signature_statuses(chunk).await?.value;
+            let now = Instant::now();
+            for (signature, status) in chunk.iter().zip(statuses) {
+                let Some(status) = status else { continue };
+                if !status.satisfies_commitment(rpc.commitment()) {
+                    continue;
+                }
+                let at = pending
+                    .remove(signature)
+                    .expect("polled signatures are pending");
+                outcomes.last_landed = Some(now);
+                match status.err {
+                    None => outcomes.ok.push((*signature, now - at)),
+                    Some(err) => *outcomes.failed.entry(error_name(&err)).or_insert(0) += 1,
+                }
+            }
+        }
+    }
+    outcomes.unconfirmed = pending.len() as u64;
+    Ok(outcomes)
+}
+
+/// The seka error name for program errors, else the transaction error.
+fn error_name(err: &TransactionError) -> String {
+    match program_error(err) {
+        Some(e) => e.as_str().to_string(),
+        None => err.to_string(),
+    }
+}
+
+/// Units consumed by up to `sample` of the successful claims, spread evenly across them.
+async fn compute_units(
+    rpc: &RpcClient,
+    ok: &[(Signature, Duration)],
+    sample: usize,
+) -> Result<Vec<u64>> {
+    if ok.is_empty() || sample == 0 {
+        return Ok(Vec::new());
+    }
+    let step = ok.len().div_ceil(sample);
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(rpc.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    let mut units = Vec::new();
+    for (signature, _) in ok.iter().step_by(step) {
+        let tx = rpc
+            .get_transaction_with_config(signature, config)
+            .await
+            .with_context(|| format!("fetching {signature}"))?;
+        let consumed = tx
+            .transaction
+            .meta
+            .and_then(|meta| Option::<u64>::from(meta.compute_units_consumed));
+        units.extend(consumed);
+    }
+    Ok(units)
+}
+
+fn print(report: &Report) {
+    println!();
+    println!(
+        "sent {} ({:.1} tps over {:.1}s), {} rejected by the node",
+        report.sent,
+        report.send_tps,
+        report.send_secs,
+        report.send_errors.values().sum::<u64>()
+    );
+    for (error, count) in &report.send_errors {
+        println!("  {count:>6}  {error}");
+    }
+    let failed: u64 = report.failed.values().sum();
+    println!(
+        "landed {} ({} ok, {failed} failed, {} unconfirmed); {:.1} successful claims/s",
+        report.landed,
+        report.landed - failed,
+        report.unconfirmed,
+        report.landed_tps
+    );
+    for (error, count) in &report.failed {
+        println!("  {count:>6}  {error}");
+    }
+    println!("failure rate {:.2}%", report.failure_rate * 100.0);
+    println!(
+        "bitmap: {} of {} leaves claimed on-chain",
+        report.bitmap_claimed, report.leaves
+    );
+    if let Some(latency) = &report.latency_ms {
+        println!(
+            "latency ms: p50 {} p90 {} p99 {} max {}",
+            latency.p50, latency.p90, latency.p99, latency.max
+        );
+    }
+    match &report.compute_units {
+        Some(cu) => println!(
+            "compute units ({} samples): min {} p50 {} p90 {} p99 {} max {} mean {:.0}",
+            cu.samples, cu.min, cu.p50, cu.p90, cu.p99, cu.max, cu.mean
+        ),
+        None => println!("compute units: no successful claims to sample"),
+    }
+    println!(
+        "tx {} bytes at proof depth {}; cycle account {} bytes, {} lamports rent; ledger rent {} lamports per new owner",
+        report.tx_bytes,
+        report.proof_depth,
+        report.cycle_state_bytes,
+        report.cycle_state_rent,
+        report.ledger_rent
+    );
+}
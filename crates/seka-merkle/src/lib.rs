@@ -0,0 +1,418 @@
+//! Off-chain Merkle tree builder for seka karma cycles.
+//!
+//! Leaves and proof folding come from `sentinelkarma_core::leaf`, the same code
+//! `claim_karma` in `programs/seka` verifies with, hashed here with keccak. A tree is
+//! built in one [`LeafFormat`]: [`LeafFormat::Evm`] trees also verify with OpenZeppelin's
+//! `MerkleProof`, so one dataset can be claimed on Solana and on an EVM contract.
+//!
+//! A node without a sibling on its level is paired with itself, so every proof of a
+//! tree has the same length.
+//!
+//! [`MultiProof`] covers several leaves of one cycle at once for batch claims: siblings
+//! shared between their paths, or computed from the batch itself, are sent once or not
+//! at all. [`compute_multi_root`] is the reference for the proposed on-chain verifier.
+
+pub use sentinelkarma_core::leaf::{claims_bitmap_len, Hash, LeafFormat};
+
+use sentinelkarma_core::leaf;
+use solana_program::keccak::hashv;
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+// ============================================================================
+// Leaves
+// ============================================================================
+
+/// Exact bytes hashed for a leaf by the on-chain `serialize_leaf`, in [`LeafFormat::Solana`].
+pub fn serialize_leaf(owner: &Pubkey, cycle_index: u64, delta_points: i32, leaf_index: u32) -> Vec<u8> {
+    leaf::serialize_leaf(&owner.to_bytes(), cycle_index, delta_points, leaf_index).to_vec()
+}
+
+pub fn leaf_hash(owner: &Pubkey, cycle_index: u64, delta_points: i32, leaf_index: u32) -> Hash {
+    format_leaf_hash(LeafFormat::Solana, owner, cycle_index, delta_points, leaf_index)
+}
+
+/// [`leaf_hash`] in `format`.
+pub fn format_leaf_hash(
+    format: LeafFormat,
+    owner: &Pubkey,
+    cycle_index: u64,
+    delta_points: i32,
+    leaf_index: u32,
+) -> Hash {
+    hashv(&[&format.serialize_leaf(&owner.to_bytes(), cycle_index, delta_points, leaf_index)]).0
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    hashv(&[left, right]).0
+}
+
+/// Parent of `left` and `right` in `format`: sorted first for [`LeafFormat::Evm`].
+fn hash_parent(format: LeafFormat, left: &Hash, right: &Hash) -> Hash {
+    match format {
+        LeafFormat::Evm if right < left => hash_pair(right, left),
+        _ => hash_pair(left, right),
+    }
+}
+
+/// Folds `proof` into `leaf` the way `compute_merkle_root` does on-chain.
+pub fn compute_root(leaf: Hash, proof: &[Hash], leaf_index: u32) -> Hash {
+    leaf::compute_root(leaf, proof, leaf_index, hash_pair)
+}
+
+// ============================================================================
+// Tree
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leaf {
+    pub owner: Pubkey,
+    pub delta_points: i32,
+}
+
+/// Everything `claim_karma` takes for one leaf, and the format its tree is built in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub format: LeafFormat,
+    pub owner: Pubkey,
+    pub cycle_index: u64,
+    pub delta_points: i32,
+    pub leaf_index: u32,
+    pub proof: Vec<Hash>,
+}
+
+impl Proof {
+    pub fn verify(&self, root: &Hash) -> bool {
+        let leaf = format_leaf_hash(
+            self.format,
+            &self.owner,
+            self.cycle_index,
+            self.delta_points,
+            self.leaf_index,
+        );
+        self.format.compute_root(leaf, &self.proof, self.leaf_index, hash_pair) == *root
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A cycle root must commit to at least one leaf
+    Empty,
+    /// Leaf indices are u32 on-chain
+    TooManyLeaves,
+    /// An owner may only appear once per cycle
+    DuplicateOwner(Pubkey),
+    /// Sum of positive deltas does not fit `total_points_declared`
+    Overflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "no leaves"),
+            Error::TooManyLeaves => write!(f, "more than u32::MAX leaves"),
+            Error::DuplicateOwner(owner) => write!(f, "owner {owner} appears more than once"),
+            Error::Overflow => write!(f, "total points overflow u32"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Keccak tree for one cycle; leaf `i` has `leaf_index == i`.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    format: LeafFormat,
+    cycle_index: u64,
+    leaves: Vec<Leaf>,
+    // levels[0] are the leaf hashes, the last level holds only the root
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// Tree in [`LeafFormat::Solana`].
+    pub fn new(cycle_index: u64, leaves: Vec<Leaf>) -> Result<Self, Error> {
+        Self::with_format(LeafFormat::Solana, cycle_index, leaves)
+    }
+
+    pub fn with_format(format: LeafFormat, cycle_index: u64, leaves: Vec<Leaf>) -> Result<Self, Error> {
+        if leaves.is_empty() {
+            return Err(Error::Empty);
+        }
+        if u32::try_from(leaves.len()).is_err() {
+            return Err(Error::TooManyLeaves);
+        }
+        let mut seen = std::collections::HashSet::with_capacity(leaves.len());
+        for leaf in &leaves {
+            if !seen.insert(leaf.owner) {
+                return Err(Error::DuplicateOwner(leaf.owner));
+            }
+        }
+
+        let mut level: Vec<Hash> = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, l)| format_leaf_hash(format, &l.owner, cycle_index, l.delta_points, i as u32))
+            .collect();
+        let mut levels = Vec::new();
+        while level.len() > 1 {
+            let next = level
+                .chunks(2)
+                .map(|pair| hash_parent(format, &pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            levels.push(level);
+            level = next;
+        }
+        levels.push(level);
+
+        Ok(Self { format, cycle_index, leaves, levels })
+    }
+
+    /// `leaf_format` argument for `set_cycle_root`.
+    pub fn format(&self) -> LeafFormat {
+        self.format
+    }
+
+    pub fn cycle_index(&self) -> u64 {
+        self.cycle_index
+    }
+
+    pub fn leaves(&self) -> &[Leaf] {
+        &self.leaves
+    }
+
+    pub fn root(&self) -> Hash {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// `claims_bitmap_len` argument for `set_cycle_root`.
+    pub fn claims_bitmap_len(&self) -> u32 {
+        claims_bitmap_len(self.leaves.len() as u32)
+    }
+
+    /// `leaf_count` argument for `set_cycle_root`.
+    pub fn leaf_count(&self) -> u32 {
+        self.leaves.len() as u32
+    }
+
+    /// `total_points_declared` argument for `set_cycle_root`: the positive deltas, which
+    /// are what the cycle can add to ledgers.
+    pub fn total_points_declared(&self) -> Result<u32, Error> {
+        self.leaves.iter().try_fold(0u32, |acc, l| {
+            let points = u32::try_from(l.delta_points.max(0)).map_err(|_| Error::Overflow)?;
+            acc.checked_add(points).ok_or(Error::Overflow)
+        })
+    }
+
+    pub fn proof(&self, leaf_index: u32) -> Option<Proof> {
+        let leaf = self.leaves.get(leaf_index as usize)?;
+        let mut idx = leaf_index as usize;
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = level.get(idx ^ 1).unwrap_or(&level[idx]);
+            proof.push(*sibling);
+            idx >>= 1;
+        }
+        Some(Proof {
+            format: self.format,
+            owner: leaf.owner,
+            cycle_index: self.cycle_index,
+            delta_points: leaf.delta_points,
+            leaf_index,
+            proof,
+        })
+    }
+
+    pub fn proof_for(&self, owner: &Pubkey) -> Option<Proof> {
+        let leaf_index = self.leaves.iter().position(|l| l.owner == *owner)?;
+        self.proof(leaf_index as u32)
+    }
+
+    pub fn proofs(&self) -> impl Iterator<Item = Proof> + '_ {
+        (0..self.leaves.len() as u32).filter_map(|i| self.proof(i))
+    }
+
+    /// One proof for all of `leaf_indices`, in any order; duplicates are ignored. `None` if
+    /// one is out of range or none is given.
+    pub fn multiproof(&self, leaf_indices: &[u32]) -> Option<MultiProof> {
+        let mut indices = leaf_indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.is_empty() || indices.iter().any(|&i| i as usize >= self.leaves.len()) {
+            return None;
+        }
+        let leaves = indices.iter().map(|&i| (i, self.leaves[i as usize])).collect();
+
+        let mut proof = Vec::new();
+        let mut flags = Flags::default();
+        let mut known: Vec<usize> = indices.iter().map(|&i| i as usize).collect();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let idx = known[i];
+                if idx & 1 == 0 && known.get(i + 1) == Some(&(idx + 1)) {
+                    flags.push(true);
+                    i += 2;
+                } else if idx & 1 == 0 && idx + 1 == level.len() {
+                    flags.push(true);
+                    i += 1;
+                } else {
+                    flags.push(false);
+                    proof.push(level[idx ^ 1]);
+                    i += 1;
+                }
+                parents.push(idx >> 1);
+            }
+            known = parents;
+        }
+
+        Some(MultiProof {
+            format: self.format,
+            cycle_index: self.cycle_index,
+            depth: (self.levels.len() - 1) as u8,
+            leaves,
+            proof,
+            flags: flags.bytes,
+        })
+    }
+
+    /// [`MerkleTree::multiproof`] for the leaves of `owners`; `None` if one is not in the tree.
+    pub fn multiproof_for(&self, owners: &[Pubkey]) -> Option<MultiProof> {
+        let indices = owners
+            .iter()
+            .map(|o| self.leaves.iter().position(|l| l.owner == *o).map(|i| i as u32))
+            .collect::<Option<Vec<_>>>()?;
+        self.multiproof(&indices)
+    }
+}
+
+// ============================================================================
+// Multiproofs
+// ============================================================================
+
+/// Everything a batch claim takes for several leaves of one cycle.
+///
+/// The verifier rebuilds the tree level by level from the known nodes, leftmost first,
+/// starting with the leaves. Each parent it computes takes one bit of `flags`, least
+/// significant bit of the first byte first:
+/// - set: the sibling is the next known node (`index ^ 1`), or, for a left node with
+///   no such neighbour, the last node of its level, which is paired with itself
+/// - clear: the sibling is the next hash of `proof`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof {
+    pub format: LeafFormat,
+    pub cycle_index: u64,
+    /// Levels above the leaves, the length of a single proof of the same tree
+    pub depth: u8,
+    /// `(leaf_index, leaf)`, strictly ascending by index
+    pub leaves: Vec<(u32, Leaf)>,
+    /// Siblings the batch cannot compute, in the order the verifier consumes them
+    pub proof: Vec<Hash>,
+    pub flags: Vec<u8>,
+}
+
+impl MultiProof {
+    pub fn verify(&self, root: &Hash) -> bool {
+        let leaves: Vec<(u32, Hash)> = self
+            .leaves
+            .iter()
+            .map(|(i, l)| {
+                let leaf = format_leaf_hash(self.format, &l.owner, self.cycle_index, l.delta_points, *i);
+                (*i, leaf)
+            })
+            .collect();
+        compute_multi_root(self.format, &leaves, &self.proof, &self.flags, self.depth) == Some(*root)
+    }
+
+    /// Borsh-encoded instruction arguments of a batch claim: cycle, depth, the leaves with
+    /// their indices, `proof` and `flags`.
+    pub fn encoded_len(&self) -> usize {
+        8 + 1 + 4 + self.leaves.len() * (32 + 4 + 4) + 4 + self.proof.len() * 32 + 4 + self.flags.len()
+    }
+
+    /// [`Proof::encoded_len`] summed over separate proofs of the same leaves.
+    pub fn separate_len(&self) -> usize {
+        self.leaves.len() * Proof::encoded_len_for(self.depth as usize)
+    }
+}
+
+impl Proof {
+    /// Borsh-encoded `claim_karma` arguments.
+    pub fn encoded_len(&self) -> usize {
+        Self::encoded_len_for(self.proof.len())
+    }
+
+    fn encoded_len_for(depth: usize) -> usize {
+        32 + 8 + 4 + 4 + 4 + depth * 32
+    }
+}
+
+#[derive(Default)]
+struct Flags {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Flags {
+    fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            self.bytes[self.len / 8] |= 1 << (self.len % 8);
+        }
+        self.len += 1;
+    }
+}
+
+/// Root of a `format` tree of `depth` levels given `leaves` as `(leaf_index, hash)`, strictly
+/// ascending. `None` if the proof is malformed: unordered or out-of-range leaves, a set
+/// flag on a right node without its left neighbour, or `proof` or `flags` with hashes or
+/// bits left over or missing.
+pub fn compute_multi_root(
+    format: LeafFormat,
+    leaves: &[(u32, Hash)],
+    proof: &[Hash],
+    flags: &[u8],
+    depth: u8,
+) -> Option<Hash> {
+    if leaves.is_empty() || depth > 32 || leaves.windows(2).any(|w| w[0].0 >= w[1].0) {
+        return None;
+    }
+    if depth < 32 && leaves.iter().any(|(i, _)| *i >> depth != 0) {
+        return None;
+    }
+    let mut known: Vec<(u32, Hash)> = leaves.to_vec();
+    let mut proof = proof.iter();
+    let mut bit = 0usize;
+    for _ in 0..depth {
+        let mut parents = Vec::with_capacity(known.len());
+        let mut i = 0;
+        while i < known.len() {
+            let (idx, hash) = known[i];
+            let flag = (flags.get(bit / 8)? >> (bit % 8)) & 1 == 1;
+            bit += 1;
+            let parent = match known.get(i + 1) {
+                Some((next, right)) if flag && idx & 1 == 0 && *next == idx + 1 => {
+                    i += 1;
+                    hash_parent(format, &hash, right)
+                }
+                _ if flag && idx & 1 == 0 => hash_pair(&hash, &hash),
+                _ if flag => return None,
+                _ if idx & 1 == 1 => hash_parent(format, proof.next()?, &hash),
+                _ => hash_parent(format, &hash, proof.next()?),
+            };
+            parents.push((idx >> 1, parent));
+            i += 1;
+        }
+        known = parents;
+    }
+    let unused_bits = flags.len() * 8 - bit;
+    let padding_clear =
+        unused_bits == 0 || (unused_bits < 8 && flags[flags.len() - 1] >> (8 - unused_bits) == 0);
+    match known[..] {
+        [(0, root)] if proof.next().is_none() && padding_clear => Some(root),
+        _ => None,
+    }
+}
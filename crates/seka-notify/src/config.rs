@@ -0,0 +1,96 @@
+//! Notifier configuration: named sinks and, per event type, which sinks hear about it
+//! and above what threshold. See notify.example.json.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// By name
+    pub sinks: BTreeMap<String, Sink>,
+    pub notifications: Vec<Notification>,
+    /// Decimals of the minted token, for amounts in messages
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    /// Transaction link, `{signature}` replaced, e.g.
+    /// `https://explorer.solana.com/tx/{signature}?cluster=devnet`
+    pub explorer: Option<String>,
+}
+
+fn default_decimals() -> u8 {
+    6
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Sink {
+    /// Discord channel webhook
+    Discord { url: String },
+    /// Slack incoming webhook
+    Slack { url: String },
+    /// Any URL, POSTed `{"event", "text", "signature", "slot", "data"}`
+    Webhook { url: String },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Notification {
+    /// `CycleRootSet`: a seka cycle's root is published and claims open
+    CycleRoot { sinks: Vec<String> },
+    /// `PointsConverted` minting at least `min_tokens` base units
+    LargeConversion { min_tokens: u64, sinks: Vec<String> },
+    /// seka `Joined`
+    MemberJoined { sinks: Vec<String> },
+    /// sentinel `CycleFinalized`, with the owners of the cycle's `top_earners` most
+    /// liked posts
+    CycleFinalized {
+        #[serde(default = "default_top_earners")]
+        top_earners: usize,
+        sinks: Vec<String>,
+    },
+}
+
+fn default_top_earners() -> usize {
+    5
+}
+
+impl Notification {
+    pub fn sinks(&self) -> &[String] {
+        match self {
+            Notification::CycleRoot { sinks }
+            | Notification::LargeConversion { sinks, .. }
+            | Notification::MemberJoined { sinks }
+            | Notification::CycleFinalized { sinks, .. } => sinks,
+        }
+    }
+
+    /// The event it is sent for, as named in stream frames.
+    pub fn event(&self) -> &'static str {
+        match self {
+            Notification::CycleRoot { .. } => "CycleRootSet",
+            Notification::LargeConversion { .. } => "PointsConverted",
+            Notification::MemberJoined { .. } => "Joined",
+            Notification::CycleFinalized { .. } => "CycleFinalized",
+        }
+    }
+}
+
+impl Config {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let config: Self =
+            serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        for notification in &config.notifications {
+            for sink in notification.sinks() {
+                if !config.sinks.contains_key(sink) {
+                    bail!("{}: unknown sink {sink:?}", notification.event());
+                }
+            }
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,280 @@
+//! seka-notify: posts seka and sentinel milestones to Discord, Slack and webhooks.
+//!
+//! Subscribes to seka-stream's WebSocket and, per `notifications` entry in `--config`
+//! (see notify.example.json), formats matching frames and sends them to the entry's
+//! sinks:
+//!
+//! - `cycle_root`: a seka cycle root is published
+//! - `large_conversion`: a point conversion minting at least `min_tokens` base units
+//! - `member_joined`: a new seka member
+//! - `cycle_finalized`: a sentinel cycle is finalized, with the owners of its most
+//!   liked posts, read from the cycle's `TopPosts`
+//!
+//! Frames missed while disconnected or lagging are not replayed. A failed send is
+//! logged and dropped.
+
+mod config;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use config::{Config, Notification, Sink};
+use futures_util::StreamExt;
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::sentinel;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Discord rejects longer message content.
+const DISCORD_MAX_CHARS: usize = 2000;
+
+#[derive(Parser)]
+#[command(about = "Send seka and sentinel milestones to Discord, Slack and webhooks")]
+struct Cli {
+    /// seka-stream WebSocket endpoint
+    #[arg(long, env = "STREAM_URL", default_value = "ws://127.0.0.1:8090/ws")]
+    stream_url: String,
+
+    /// For cycle top posts
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// Sinks and notifications; see notify.example.json
+    #[arg(long, env = "NOTIFY_CONFIG")]
+    config: PathBuf,
+}
+
+/// A seka-stream frame; control frames carry only `type` and their own fields.
+#[derive(Debug, Deserialize)]
+struct Frame {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    program: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    signature: String,
+    #[serde(default)]
+    slot: u64,
+    #[serde(default)]
+    data: Value,
+    missed: Option<u64>,
+}
+
+struct Notifier {
+    config: Config,
+    client: Client,
+    http: reqwest::Client,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::read(&cli.config)?;
+    let notifier = Notifier {
+        config,
+        client: Client::new(&cli.rpc_url, ClientConfig::default()),
+        http: reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?,
+    };
+    let url = format!("{}?topics=claims,cycles,members", cli.stream_url);
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match notifier.listen(&url).await {
+            Ok(()) => {
+                eprintln!("stream closed, reconnecting");
+                backoff = Duration::from_secs(1);
+            }
+            Err(e) => eprintln!("stream: {e:#}; retrying in {}s", backoff.as_secs()),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(Duration::from_secs(60));
+    }
+}
+
+impl Notifier {
+    /// Handles frames from `url` until the connection closes.
+    async fn listen(&self, url: &str) -> Result<()> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .with_context(|| format!("connecting to {url}"))?;
+        println!("connected to {url}");
+        while let Some(message) = socket.next().await {
+            let Message::Text(text) = message? else {
+                continue;
+            };
+            let frame: Frame = match serde_json::from_str(&text) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    eprintln!("unreadable frame: {e}");
+                    continue;
+                }
+            };
+            match frame.kind.as_str() {
+                "event" => self.handle(&frame).await,
+                "lagged" => eprintln!("missed {} frames", frame.missed.unwrap_or(0)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle(&self, frame: &Frame) {
+        for notification in &self.config.notifications {
+            if notification.event() != frame.name {
+                continue;
+            }
+            let text = match self.format(notification, frame).await {
+                Ok(Some(text)) => text,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("{}: {e:#}", frame.name);
+                    continue;
+                }
+            };
+            println!("{}: {}", frame.name, text.lines().next().unwrap_or(""));
+            for name in notification.sinks() {
+                self.send(name, frame, &text).await;
+            }
+        }
+    }
+
+    /// The message for `frame`, or `None` if it is under the notification's threshold.
+    async fn format(&self, notification: &Notification, frame: &Frame) -> Result<Option<String>> {
+        let data = &frame.data;
+        let mut text = match notification {
+            Notification::CycleRoot { .. } if frame.program == "seka" => format!(
+                "Seka cycle {} root published: {} points declared, claims are open",
+                field(data, "cycle_index"),
+                field(data, "total_points_declared"),
+            ),
+            Notification::LargeConversion { min_tokens, .. } if frame.program == "seka" => {
+                let tokens = number(data, "tokens_minted").unwrap_or(0);
+                if tokens < u128::from(*min_tokens) {
+                    return Ok(None);
+                }
+                format!(
+                    "`{}` converted {} points into {} tokens",
+                    field(data, "owner"),
+                    field(data, "points_spent"),
+                    self.amount(tokens),
+                )
+            }
+            Notification::MemberJoined { .. } if frame.program == "seka" => {
+                format!("New seka member: `{}`", field(data, "member"))
+            }
+            Notification::CycleFinalized { top_earners, .. } if frame.program == "sentinel" => {
+                let cycle = number(data, "cycle_index").unwrap_or(0) as u64;
+                let mut text = format!(
+                    "Sentinel cycle {cycle} finalized: {} tokens to {} peers, the most ({}) to `{}`",
+                    self.amount(number(data, "total_minted").unwrap_or(0)),
+                    field(data, "peers_rewarded"),
+                    self.amount(number(data, "top_reward").unwrap_or(0)),
+                    field(data, "top_recipient"),
+                );
+                let earners = self.top_earners(cycle, *top_earners).await?;
+                if !earners.is_empty() {
+                    text.push_str("\nMost liked this cycle:");
+                    for (i, (owner, likes)) in earners.iter().enumerate() {
+                        text.push_str(&format!("\n{}. `{owner}`: {likes} likes", i + 1));
+                    }
+                }
+                text
+            }
+            _ => return Ok(None),
+        };
+        if let Some(explorer) = &self.config.explorer {
+            text.push('\n');
+            text.push_str(&explorer.replace("{signature}", &frame.signature));
+        }
+        Ok(Some(text))
+    }
+
+    /// Owners of the cycle's top posts by total likes, most first.
+    async fn top_earners(&self, cycle: u64, n: usize) -> Result<Vec<(Pubkey, u64)>> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        let address = sentinel::top_posts_pda(cycle);
+        let top: sentinel::TopPosts = match self.client.fetch_zero_copy(&address).await {
+            Ok(top) => top,
+            // No post was liked during the cycle
+            Err(_) => return Ok(Vec::new()),
+        };
+        let mut likes: HashMap<Pubkey, u64> = HashMap::new();
+        for entry in &top.entries[..(top.len as usize).min(top.entries.len())] {
+            *likes.entry(entry.owner).or_default() += entry.likes;
+        }
+        let mut earners: Vec<(Pubkey, u64)> = likes.into_iter().collect();
+        earners.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        earners.truncate(n);
+        Ok(earners)
+    }
+
+    /// `base` units as a decimal token amount.
+    fn amount(&self, base: u128) -> String {
+        let scale = 10u128.pow(self.config.decimals.into());
+        let fraction = base % scale;
+        if fraction == 0 {
+            return (base / scale).to_string();
+        }
+        let fraction = format!("{fraction:0width$}", width = self.config.decimals as usize);
+        format!("{}.{}", base / scale, fraction.trim_end_matches('0'))
+    }
+
+    async fn send(&self, name: &str, frame: &Frame, text: &str) {
+        let (url, body) = match &self.config.sinks[name] {
+            Sink::Discord { url } => {
+                let content: String = text.chars().take(DISCORD_MAX_CHARS).collect();
+                // Addresses are never meant as mentions
+                let body = json!({ "content": content, "allowed_mentions": { "parse": [] } });
+                (url, body)
+            }
+            Sink::Slack { url } => (url, json!({ "text": text })),
+            Sink::Webhook { url } => (
+                url,
+                json!({
+                    "event": frame.name,
+                    "text": text,
+                    "signature": frame.signature,
+                    "slot": frame.slot,
+                    "data": frame.data,
+                }),
+            ),
+        };
+        let result = self
+            .http
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = result {
+            eprintln!("{name}: {e}");
+        }
+    }
+}
+
+/// `name` of frame data as display text.
+fn field<'a>(data: &'a Value, name: &str) -> std::borrow::Cow<'a, str> {
+    match &data[name] {
+        Value::String(s) => s.into(),
+        Value::Null => "?".into(),
+        other => other.to_string().into(),
+    }
+}
+
+/// `name` of frame data as a number, whether written as one or as a decimal string.
+fn number(data: &Value, name: &str) -> Option<u128> {
+    match &data[name] {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
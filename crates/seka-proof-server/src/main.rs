@@ -0,0 +1,216 @@
+//! seka-proof-server: serves `claim_karma` proofs so wallets need not ship the tree.
+//!
+//! `--data-dir` holds one published dataset per cycle, `<cycle_index>.csv` or
+//! `<cycle_index>.json`, in the `seka-proofs build` input format. A cycle's tree is built
+//! on first request and kept in memory. With `--rpc-url`, the built root is checked
+//! against the on-chain `CycleState` before anything is served, so a stale or edited
+//! dataset cannot hand out proofs that would fail on-chain, and the tree is built in the
+//! leaf format of the cycle's root; without it, every tree is in the Solana format.
+//!
+//! - `GET /cycles/{idx}/root` returns root.json
+//! - `GET /cycles/{idx}/proof/{owner}` returns proofs/<owner>.json
+//! - `GET /owed/{owner}` returns the owner's unclaimed leaves across every dataset, with
+//!   proofs; with `--rpc-url` also its ledger points, convertible SEKA and membership
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use clap::Parser;
+use seka_merkle::{LeafFormat, MerkleTree};
+use seka_proofs::{
+    dataset_cycles, dataset_path, owed, parse_pubkey, read_input, ProofFile, RootFile,
+};
+use sentinelkarma_sdk::{decode_account, seka};
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Parser)]
+#[command(about = "Serve seka cycle roots and claim proofs over HTTP")]
+struct Cli {
+    /// Directory of `<cycle_index>.csv|json` datasets
+    #[arg(long, env = "DATA_DIR")]
+    data_dir: PathBuf,
+
+    #[arg(long, env = "LISTEN", default_value = "127.0.0.1:8091")]
+    listen: SocketAddr,
+
+    /// Check each cycle's root against the chain before serving it
+    #[arg(long, env = "RPC_URL")]
+    rpc_url: Option<String>,
+}
+
+struct Cycle {
+    tree: MerkleTree,
+    root: RootFile,
+}
+
+struct AppState {
+    data_dir: PathBuf,
+    rpc_url: Option<String>,
+    /// Built cycles; the lock also keeps a cycle from being built twice concurrently
+    cycles: Mutex<HashMap<u64, Arc<Cycle>>>,
+}
+
+enum ApiError {
+    BadRequest(String),
+    NotFound(String),
+    Internal(anyhow::Error),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::Internal(e) => {
+                eprintln!("error: {e:#}");
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+            }
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError::Internal(e)
+    }
+}
+
+impl AppState {
+    async fn cycle(&self, cycle_index: u64) -> Result<Arc<Cycle>, ApiError> {
+        let mut cycles = self.cycles.lock().await;
+        if let Some(cycle) = cycles.get(&cycle_index) {
+            return Ok(cycle.clone());
+        }
+        let Some(input) = dataset_path(&self.data_dir, cycle_index) else {
+            return Err(ApiError::NotFound(format!("no dataset for cycle {cycle_index}")));
+        };
+
+        let rpc_url = self.rpc_url.clone();
+        let cycle = tokio::task::spawn_blocking(move || load(cycle_index, input, rpc_url))
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))??;
+        let cycle = Arc::new(cycle);
+        cycles.insert(cycle_index, cycle.clone());
+        Ok(cycle)
+    }
+}
+
+/// A cycle whose root is not on-chain yet is `NotFound`, not an error: its dataset is
+/// staged ahead of `set_cycle_root`.
+fn load(cycle_index: u64, input: PathBuf, rpc_url: Option<String>) -> Result<Cycle, ApiError> {
+    let leaves = read_input(&input).with_context(|| format!("reading {}", input.display()))?;
+    let state = match rpc_url {
+        Some(url) => {
+            let rpc = RpcClient::new(url);
+            let account = rpc
+                .get_account_with_commitment(&seka::cycle_state_pda(cycle_index), rpc.commitment())
+                .with_context(|| format!("fetching cycle {cycle_index} state"))?
+                .value
+                .ok_or_else(|| {
+                    ApiError::NotFound(format!("cycle {cycle_index} has no root on-chain"))
+                })?;
+            Some(decode_account::<seka::CycleState>(&account.data)?)
+        }
+        None => None,
+    };
+    let format = match &state {
+        Some(state) => state.leaf_format().ok_or_else(|| {
+            anyhow!("cycle {cycle_index} has unknown leaf format {}", state.leaf_format)
+        })?,
+        None => LeafFormat::Solana,
+    };
+    let tree = MerkleTree::with_format(format, cycle_index, leaves).map_err(anyhow::Error::from)?;
+    let root = RootFile::new(&tree)?;
+
+    if let Some(state) = state {
+        if state.merkle_root != tree.root() {
+            return Err(anyhow!(
+                "dataset root {} does not match on-chain root {} for cycle {cycle_index}",
+                root.merkle_root,
+                hex::encode(state.merkle_root)
+            )
+            .into());
+        }
+    }
+    println!("cycle {cycle_index}: {} leaves, root {}", root.leaf_count, root.merkle_root);
+    Ok(Cycle { tree, root })
+}
+
+async fn get_root(
+    State(state): State<Arc<AppState>>,
+    Path(cycle_index): Path<u64>,
+) -> Result<Json<RootFile>, ApiError> {
+    let cycle = state.cycle(cycle_index).await?;
+    Ok(Json(cycle.root.clone()))
+}
+
+async fn get_proof(
+    State(state): State<Arc<AppState>>,
+    Path((cycle_index, owner)): Path<(u64, String)>,
+) -> Result<Json<ProofFile>, ApiError> {
+    let owner = parse_pubkey(&owner).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let cycle = state.cycle(cycle_index).await?;
+    let proof = cycle
+        .tree
+        .proof_for(&owner)
+        .ok_or_else(|| ApiError::NotFound(format!("{owner} has no leaf in cycle {cycle_index}")))?;
+    Ok(Json(ProofFile::from(&proof)))
+}
+
+async fn get_owed(
+    State(state): State<Arc<AppState>>,
+    Path(owner): Path<String>,
+) -> Result<Json<owed::Report>, ApiError> {
+    let owner = parse_pubkey(&owner).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let mut cycles = Vec::new();
+    for cycle_index in dataset_cycles(&state.data_dir)? {
+        match state.cycle(cycle_index).await {
+            Ok(cycle) => cycles.push(cycle),
+            // Staged ahead of its root, so nothing in it can be claimed yet
+            Err(ApiError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    // Claims are read fresh on every request; only the trees are cached
+    let rpc_url = state.rpc_url.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let rpc = rpc_url.map(RpcClient::new);
+        owed::report(cycles.iter().map(|c| &c.tree), &owner, rpc.as_ref())
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))??;
+    Ok(Json(report))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if !cli.data_dir.is_dir() {
+        bail!("{} is not a directory", cli.data_dir.display());
+    }
+    let state = Arc::new(AppState {
+        data_dir: cli.data_dir,
+        rpc_url: cli.rpc_url,
+        cycles: Mutex::new(HashMap::new()),
+    });
+    let app = Router::new()
+        .route("/cycles/{idx}/root", get(get_root))
+        .route("/cycles/{idx}/proof/{owner}", get(get_proof))
+        .route("/owed/{owner}", get(get_owed))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(cli.listen).await?;
+    println!("listening on http://{}", cli.listen);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,358 @@
+//! File formats shared by the `seka-proofs` CLI and the proof server.
+//!
+//! Input is CSV with an `owner,delta` header or a JSON array of `{"owner", "delta"}`,
+//! picked by file extension. Leaf indices follow input order.
+//!
+//! [`validate`] checks a dataset against the root about to be published for it, and
+//! [`owed`] reports what one wallet can still claim across published cycles.
+
+pub mod owed;
+
+use anyhow::{bail, Context, Result};
+use seka_merkle::{claims_bitmap_len, Hash, Leaf, LeafFormat, MerkleTree, Proof};
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// ============================================================================
+// File formats
+// ============================================================================
+
+#[derive(Serialize, Deserialize)]
+pub struct InputRow {
+    pub owner: String,
+    pub delta: i32,
+}
+
+impl From<&Leaf> for InputRow {
+    fn from(leaf: &Leaf) -> Self {
+        Self {
+            owner: leaf.owner.to_string(),
+            delta: leaf.delta_points,
+        }
+    }
+}
+
+/// `set_cycle_root` arguments.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RootFile {
+    pub cycle_index: u64,
+    pub merkle_root: String,
+    pub total_points_declared: u32,
+    pub claims_bitmap_len: u32,
+    pub leaf_count: u32,
+    /// `solana` or `evm`; files from before formats are `solana`
+    #[serde(default = "solana_format")]
+    pub leaf_format: String,
+}
+
+impl RootFile {
+    pub fn new(tree: &MerkleTree) -> Result<Self> {
+        Ok(Self {
+            cycle_index: tree.cycle_index(),
+            merkle_root: hex::encode(tree.root()),
+            total_points_declared: tree.total_points_declared()?,
+            claims_bitmap_len: tree.claims_bitmap_len(),
+            leaf_count: tree.leaves().len() as u32,
+            leaf_format: tree.format().name().to_owned(),
+        })
+    }
+}
+
+/// `claim_karma` arguments for one owner.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProofFile {
+    pub owner: String,
+    pub cycle_index: u64,
+    pub delta_points: i32,
+    pub leaf_index: u32,
+    pub proof: Vec<String>,
+    #[serde(default = "solana_format")]
+    pub leaf_format: String,
+}
+
+impl From<&Proof> for ProofFile {
+    fn from(p: &Proof) -> Self {
+        Self {
+            owner: p.owner.to_string(),
+            cycle_index: p.cycle_index,
+            delta_points: p.delta_points,
+            leaf_index: p.leaf_index,
+            proof: p.proof.iter().map(hex::encode).collect(),
+            leaf_format: p.format.name().to_owned(),
+        }
+    }
+}
+
+impl TryFrom<ProofFile> for Proof {
+    type Error = anyhow::Error;
+
+    fn try_from(f: ProofFile) -> Result<Self> {
+        Ok(Self {
+            format: parse_leaf_format(&f.leaf_format)?,
+            owner: parse_pubkey(&f.owner)?,
+            cycle_index: f.cycle_index,
+            delta_points: f.delta_points,
+            leaf_index: f.leaf_index,
+            proof: f.proof.iter().map(|h| parse_hash(h)).collect::<Result<_>>()?,
+        })
+    }
+}
+
+fn solana_format() -> String {
+    LeafFormat::Solana.name().to_owned()
+}
+
+pub fn parse_leaf_format(s: &str) -> Result<LeafFormat> {
+    LeafFormat::from_name(s.trim())
+        .with_context(|| format!("unknown leaf format {s:?}, expected solana or evm"))
+}
+
+pub fn parse_pubkey(s: &str) -> Result<Pubkey> {
+    Pubkey::from_str(s.trim()).with_context(|| format!("invalid owner pubkey {s:?}"))
+}
+
+pub fn parse_hash(s: &str) -> Result<Hash> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x"))
+        .with_context(|| format!("invalid hex {s:?}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("hash {s:?} is not 32 bytes"))
+}
+
+pub fn read_input(path: &Path) -> Result<Vec<Leaf>> {
+    let rows: Vec<InputRow> = match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<std::result::Result<_, _>>()?,
+        Some("json") => serde_json::from_slice(&fs::read(path)?)?,
+        _ => bail!("input must be a .csv or .json file"),
+    };
+    to_leaves(rows)
+}
+
+pub fn to_leaves(rows: Vec<InputRow>) -> Result<Vec<Leaf>> {
+    rows.into_iter()
+        .map(|r| {
+            Ok(Leaf {
+                owner: parse_pubkey(&r.owner)?,
+                delta_points: r.delta,
+            })
+        })
+        .collect()
+}
+
+/// Cycle indices with a dataset in `data_dir`, ascending.
+pub fn dataset_cycles(data_dir: &Path) -> Result<Vec<u64>> {
+    let mut cycles = Vec::new();
+    for entry in
+        fs::read_dir(data_dir).with_context(|| format!("reading {}", data_dir.display()))?
+    {
+        let path = entry?.path();
+        if !path.extension().is_some_and(|e| e == "csv" || e == "json") {
+            continue;
+        }
+        if let Some(index) = path.file_stem().and_then(|s| s.to_str()?.parse().ok()) {
+            cycles.push(index);
+        }
+    }
+    cycles.sort_unstable();
+    cycles.dedup();
+    Ok(cycles)
+}
+
+/// The dataset for `cycle_index` in a directory of `<cycle_index>.csv|json` files,
+/// preferring CSV.
+pub fn dataset_path(data_dir: &Path, cycle_index: u64) -> Option<PathBuf> {
+    ["csv", "json"]
+        .iter()
+        .map(|ext| data_dir.join(format!("{cycle_index}.{ext}")))
+        .find(|path| path.is_file())
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(value)? + "\n")
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// Writes `<out_dir>/root.json` and `<out_dir>/proofs/<owner>.json`.
+pub fn write_output(tree: &MerkleTree, out_dir: &Path) -> Result<RootFile> {
+    let proofs_dir = out_dir.join("proofs");
+    fs::create_dir_all(&proofs_dir)?;
+    let root = RootFile::new(tree)?;
+    write_json(&out_dir.join("root.json"), &root)?;
+    for proof in tree.proofs() {
+        write_json(
+            &proofs_dir.join(format!("{}.json", proof.owner)),
+            &ProofFile::from(&proof),
+        )?;
+    }
+    Ok(root)
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+/// Problems reported per check before the rest are summarized.
+const MAX_LISTED: usize = 10;
+
+/// The `GlobalConfig` limits a cycle is published under.
+#[derive(Clone, Copy, Debug)]
+pub struct Caps {
+    pub max_points_per_cycle: u32,
+    pub per_peer_cycle_cap: i32,
+}
+
+/// Everything that would make `root` a bad root for `leaves`: a root or total that does
+/// not match the tree, caps exceeded, a bitmap too short for every leaf to claim, and,
+/// when `proofs` are given, leaf indices that are not dense and unique or proofs that do
+/// not verify. Empty when the root is safe to publish.
+pub fn validate(
+    leaves: &[Leaf],
+    root: &RootFile,
+    proofs: &[ProofFile],
+    caps: Caps,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let count = leaves.len() as u32;
+
+    let over_cap: Vec<String> = leaves
+        .iter()
+        .filter(|l| l.delta_points.unsigned_abs() > caps.per_peer_cycle_cap.unsigned_abs())
+        .map(|l| {
+            format!(
+                "{} has delta {} over the per-peer cap {}",
+                l.owner, l.delta_points, caps.per_peer_cycle_cap
+            )
+        })
+        .collect();
+    push_listed(&mut problems, over_cap);
+
+    let positive: u64 = leaves.iter().map(|l| l.delta_points.max(0) as u64).sum();
+    if positive > u64::from(caps.max_points_per_cycle) {
+        problems.push(format!(
+            "positive deltas sum to {positive}, over max_points_per_cycle {}",
+            caps.max_points_per_cycle
+        ));
+    }
+    if u64::from(root.total_points_declared) != positive {
+        problems.push(format!(
+            "total_points_declared is {}, the positive deltas sum to {positive}",
+            root.total_points_declared
+        ));
+    }
+    if root.claims_bitmap_len < claims_bitmap_len(count) {
+        problems.push(format!(
+            "claims_bitmap_len {} leaves leaf indices past {} unclaimable; {count} leaves need {}",
+            root.claims_bitmap_len,
+            root.claims_bitmap_len * 8,
+            claims_bitmap_len(count)
+        ));
+    }
+    if root.leaf_count != count {
+        problems.push(format!(
+            "root file lists {} leaves, the dataset has {count}",
+            root.leaf_count
+        ));
+    }
+
+    let expected = match parse_hash(&root.merkle_root) {
+        Ok(hash) => hash,
+        Err(e) => {
+            problems.push(format!("merkle_root: {e:#}"));
+            return problems;
+        }
+    };
+    let format = match parse_leaf_format(&root.leaf_format) {
+        Ok(format) => format,
+        Err(e) => {
+            problems.push(format!("leaf_format: {e:#}"));
+            return problems;
+        }
+    };
+    let tree = match MerkleTree::with_format(format, root.cycle_index, leaves.to_vec()) {
+        Ok(tree) => tree,
+        Err(e) => {
+            problems.push(format!("dataset: {e}"));
+            return problems;
+        }
+    };
+    if tree.root() != expected {
+        problems.push(format!(
+            "merkle_root {} does not match the dataset's root {} for cycle {}",
+            root.merkle_root,
+            hex::encode(tree.root()),
+            root.cycle_index
+        ));
+    }
+
+    if !proofs.is_empty() {
+        problems.extend(validate_proofs(&tree, &expected, proofs));
+    }
+    problems
+}
+
+/// Leaf indices of `proofs` must be exactly `0..leaf_count`, each naming its dataset
+/// leaf and verifying against `root`.
+fn validate_proofs(tree: &MerkleTree, root: &Hash, proofs: &[ProofFile]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    let mut bad = Vec::new();
+    for file in proofs {
+        if !seen.insert(file.leaf_index) {
+            problems.push(format!(
+                "leaf index {} appears in more than one proof",
+                file.leaf_index
+            ));
+            continue;
+        }
+        let Some(leaf) = tree.leaves().get(file.leaf_index as usize) else {
+            bad.push(format!(
+                "{}: leaf index {} is past the last leaf",
+                file.owner, file.leaf_index
+            ));
+            continue;
+        };
+        let proof = match Proof::try_from(file.clone()) {
+            Ok(proof) => proof,
+            Err(e) => {
+                bad.push(format!("{}: {e:#}", file.owner));
+                continue;
+            }
+        };
+        if proof.owner != leaf.owner || proof.delta_points != leaf.delta_points {
+            bad.push(format!(
+                "leaf {} is {} with delta {} in the proofs, {} with delta {} in the dataset",
+                file.leaf_index, proof.owner, proof.delta_points, leaf.owner, leaf.delta_points
+            ));
+        } else if proof.format != tree.format() {
+            bad.push(format!(
+                "{}: proof is in the {} format, the root in {}",
+                file.owner,
+                proof.format.name(),
+                tree.format().name()
+            ));
+        } else if proof.cycle_index != tree.cycle_index() || !proof.verify(root) {
+            bad.push(format!("{}: proof does not verify against the root", file.owner));
+        }
+    }
+    push_listed(&mut problems, bad);
+    let missing: Vec<String> = (0..tree.leaves().len() as u32)
+        .filter(|i| !seen.contains(i))
+        .map(|i| format!("no proof for leaf index {i}"))
+        .collect();
+    push_listed(&mut problems, missing);
+    problems
+}
+
+fn push_listed(problems: &mut Vec<String>, found: Vec<String>) {
+    let extra = found.len().saturating_sub(MAX_LISTED);
+    problems.extend(found.into_iter().take(MAX_LISTED));
+    if extra > 0 {
+        problems.push(format!("... and {extra} more like the above"));
+    }
+}
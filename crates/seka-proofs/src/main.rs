@@ -0,0 +1,242 @@
+//! seka-proofs: turns a cycle's (owner, delta) list into the arguments of
+//! `set_cycle_root` and one `claim_karma` proof file per owner, and checks both before
+//! they are published. `owed` answers the support question of what a wallet can still
+//! claim across every published cycle.
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use seka_merkle::{LeafFormat, MerkleTree, Proof};
+use seka_proofs::{
+    owed, parse_hash, parse_leaf_format, parse_pubkey, read_input, validate, write_output, Caps,
+    ProofFile, RootFile,
+};
+use solana_client::rpc_client::RpcClient;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "seka-proofs", about = "Build and verify seka cycle Merkle proofs")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the root and write root.json plus proofs/<owner>.json
+    Build {
+        #[arg(long)]
+        cycle: u64,
+        /// CSV or JSON file of (owner, delta) pairs
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long, default_value = "proofs-out")]
+        out_dir: PathBuf,
+        /// `solana`, or `evm` for a root an EVM contract can verify too
+        #[arg(long, default_value = "solana", value_parser = parse_leaf_format)]
+        leaf_format: LeafFormat,
+    },
+    /// Check a proof file against a root
+    Verify {
+        #[arg(long)]
+        proof: PathBuf,
+        /// Hex root; defaults to the root.json next to the proofs directory
+        #[arg(long)]
+        root: Option<String>,
+        #[arg(long, conflicts_with = "root")]
+        root_file: Option<PathBuf>,
+    },
+    /// Check a dataset against the root about to be published for it
+    Validate {
+        /// CSV or JSON file of (owner, delta) pairs, in leaf order
+        #[arg(long)]
+        input: PathBuf,
+        /// root.json with the proposed `set_cycle_root` arguments
+        #[arg(long)]
+        root_file: PathBuf,
+        /// Also check the proofs in this directory; defaults to `proofs/` next to
+        /// root.json when it exists
+        #[arg(long)]
+        proofs_dir: Option<PathBuf>,
+        /// `GlobalConfig.max_points_per_cycle`; `seka-admin config` prints the live one
+        #[arg(long, default_value_t = 10_000)]
+        max_points_per_cycle: u32,
+        /// `GlobalConfig.per_peer_cycle_cap`
+        #[arg(long, default_value_t = 100)]
+        per_peer_cycle_cap: i32,
+    },
+    /// Report a wallet's unclaimed leaves with proofs, its convertible points and its
+    /// membership
+    Owed {
+        #[arg(long)]
+        owner: String,
+        /// Directory of `<cycle_index>.csv|json` datasets, as served by seka-proof-server
+        #[arg(long, env = "DATA_DIR")]
+        data_dir: PathBuf,
+        /// Read claims, ledger and membership from the chain; without it every leaf in the
+        /// datasets is listed as possibly unclaimed
+        #[arg(long, env = "RPC_URL")]
+        rpc_url: Option<String>,
+        /// Print the full report, proofs included, as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+fn build(cycle: u64, input: &Path, out_dir: &Path, leaf_format: LeafFormat) -> Result<()> {
+    let leaves = read_input(input).with_context(|| format!("reading {}", input.display()))?;
+    let tree = MerkleTree::with_format(leaf_format, cycle, leaves)?;
+
+    let root = write_output(&tree, out_dir)?;
+    let proofs_dir = out_dir.join("proofs");
+
+    println!("cycle_index:           {}", root.cycle_index);
+    println!("merkle_root:           {}", root.merkle_root);
+    println!("total_points_declared: {}", root.total_points_declared);
+    println!("claims_bitmap_len:     {}", root.claims_bitmap_len);
+    println!("leaf_format:           {}", root.leaf_format);
+    println!("proofs:                {} in {}", root.leaf_count, proofs_dir.display());
+    Ok(())
+}
+
+fn verify(proof_path: &Path, root: Option<String>, root_file: Option<PathBuf>) -> Result<()> {
+    let file: ProofFile = serde_json::from_slice(&fs::read(proof_path)?)
+        .with_context(|| format!("reading {}", proof_path.display()))?;
+    let proof = Proof::try_from(file)?;
+
+    let root = match (root, root_file) {
+        (Some(hex_root), _) => parse_hash(&hex_root)?,
+        (None, root_file) => {
+            // build writes <out>/root.json and <out>/proofs/<owner>.json
+            let path = match root_file {
+                Some(p) => p,
+                None => proof_path
+                    .parent()
+                    .and_then(Path::parent)
+                    .map(|dir| dir.join("root.json"))
+                    .context("pass --root or --root-file")?,
+            };
+            let file: RootFile = serde_json::from_slice(&fs::read(&path)?)
+                .with_context(|| format!("reading {}", path.display()))?;
+            if file.cycle_index != proof.cycle_index {
+                bail!(
+                    "proof is for cycle {}, root is for cycle {}",
+                    proof.cycle_index,
+                    file.cycle_index
+                );
+            }
+            parse_hash(&file.merkle_root)?
+        }
+    };
+
+    if !proof.verify(&root) {
+        bail!("proof for {} does not match root {}", proof.owner, hex::encode(root));
+    }
+    println!("ok: {} leaf {} verifies against {}", proof.owner, proof.leaf_index, hex::encode(root));
+    Ok(())
+}
+
+fn validate_dataset(
+    input: &Path,
+    root_file: &Path,
+    proofs_dir: Option<PathBuf>,
+    caps: Caps,
+) -> Result<()> {
+    let leaves = read_input(input).with_context(|| format!("reading {}", input.display()))?;
+    let root: RootFile = serde_json::from_slice(&fs::read(root_file)?)
+        .with_context(|| format!("reading {}", root_file.display()))?;
+
+    let proofs_dir = proofs_dir.or_else(|| {
+        let dir = root_file.parent()?.join("proofs");
+        dir.is_dir().then_some(dir)
+    });
+    let mut proofs = Vec::new();
+    if let Some(dir) = &proofs_dir {
+        for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                let file: ProofFile = serde_json::from_slice(&fs::read(&path)?)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                proofs.push(file);
+            }
+        }
+    }
+
+    let problems = validate(&leaves, &root, &proofs, caps);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("error: {problem}");
+        }
+        bail!("cycle {} root {} must not be published", root.cycle_index, root.merkle_root);
+    }
+    println!("ok: cycle {} root {} matches {} leaves", root.cycle_index, root.merkle_root, leaves.len());
+    println!("    {} of {} points declared", root.total_points_declared, caps.max_points_per_cycle);
+    if let Some(dir) = proofs_dir {
+        println!("    {} proofs in {} verify", proofs.len(), dir.display());
+    }
+    Ok(())
+}
+
+fn owed_report(owner: &str, data_dir: &Path, rpc_url: Option<String>, json: bool) -> Result<()> {
+    let owner = parse_pubkey(owner)?;
+    let rpc = rpc_url.map(RpcClient::new);
+    let report = owed::from_dir(data_dir, &owner, rpc.as_ref())?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("owner:             {}", report.owner);
+    for leaf in &report.unclaimed {
+        let status = if leaf.claimed.is_none() { " (claim status unknown)" } else { "" };
+        println!(
+            "  cycle {:>6}: delta {:>+5} at leaf {}{status}",
+            leaf.proof.cycle_index, leaf.proof.delta_points, leaf.proof.leaf_index
+        );
+    }
+    println!("unclaimed:         {} leaves, {} points", report.unclaimed.len(), report.unclaimed_points);
+    if !report.claimed_cycles.is_empty() {
+        println!("claimed cycles:    {:?}", report.claimed_cycles);
+    }
+    if !report.unpublished_cycles.is_empty() {
+        println!("not yet published: {:?}", report.unpublished_cycles);
+    }
+    if let Some(chain) = &report.chain {
+        println!("ledger points:     {}", chain.points);
+        println!(
+            "convertible:       {} SEKA now, {} after claiming (1 SEKA per {} points)",
+            chain.convertible_tokens, chain.convertible_after_claims, chain.conversion_ratio
+        );
+        println!("membership:        {}", chain.membership.as_str());
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Build {
+            cycle,
+            input,
+            out_dir,
+            leaf_format,
+        } => build(cycle, &input, &out_dir, leaf_format),
+        Command::Verify { proof, root, root_file } => verify(&proof, root, root_file),
+        Command::Validate {
+            input,
+            root_file,
+            proofs_dir,
+            max_points_per_cycle,
+            per_peer_cycle_cap,
+        } => {
+            let caps = Caps { max_points_per_cycle, per_peer_cycle_cap };
+            validate_dataset(&input, &root_file, proofs_dir, caps)
+        }
+        Command::Owed { owner, data_dir, rpc_url, json } => {
+            owed_report(&owner, &data_dir, rpc_url, json)
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! "What am I owed": for one wallet, its unclaimed leaves across the published cycle
+//! datasets, its ledger points and what they convert to, and its membership.
+//!
+//! Datasets are read from a directory laid out as the proof server expects (see
+//! [`dataset_path`]). Without an RPC client only the datasets are read, so every leaf is
+//! listed with `claimed` unknown, the chain-side fields are absent and every tree is
+//! built in the Solana leaf format; with one, each is built in its cycle's format.
+
+use crate::{dataset_cycles, dataset_path, read_input, ProofFile};
+use anyhow::{Context, Result};
+use seka_merkle::{LeafFormat, MerkleTree};
+use sentinelkarma_sdk::anchor_lang::AccountDeserialize;
+use sentinelkarma_sdk::{decode_account, seka, Versioned};
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use std::path::Path;
+
+/// `getMultipleAccounts` limit.
+const MAX_ACCOUNTS_PER_CALL: usize = 100;
+
+#[derive(Clone, Serialize)]
+pub struct Report {
+    pub owner: String,
+    /// Leaves the owner can still claim, oldest cycle first, each with its proof
+    pub unclaimed: Vec<OwedLeaf>,
+    /// Cycles where the owner's leaf is already claimed
+    pub claimed_cycles: Vec<u64>,
+    /// Datasets with a leaf for the owner whose root is not on-chain yet
+    pub unpublished_cycles: Vec<u64>,
+    /// Sum of the unclaimed deltas
+    pub unclaimed_points: i64,
+    /// Present when the chain was read
+    pub chain: Option<ChainStatus>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct OwedLeaf {
+    /// Unknown (`null`) when the chain was not read
+    pub claimed: Option<bool>,
+    #[serde(flatten)]
+    pub proof: ProofFile,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ChainStatus {
+    /// `PeerLedger.points`; 0 when the ledger does not exist yet
+    pub points: i64,
+    pub last_cycle_claimed: Option<u64>,
+    pub conversion_ratio: u32,
+    /// Whole SEKA `convert_points_to_tokens` would mint now
+    pub convertible_tokens: u64,
+    /// Whole SEKA after every unclaimed leaf is claimed, in cycle order, with negative
+    /// deltas clamping the ledger at zero as the program does
+    pub convertible_after_claims: u64,
+    pub membership: MembershipStatus,
+    pub joined_at: Option<i64>,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipStatus {
+    Active,
+    Inactive,
+    None,
+}
+
+impl MembershipStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MembershipStatus::Active => "active",
+            MembershipStatus::Inactive => "inactive",
+            MembershipStatus::None => "none",
+        }
+    }
+}
+
+/// Builds every dataset in `data_dir` and reports on `owner`.
+pub fn from_dir(data_dir: &Path, owner: &Pubkey, rpc: Option<&RpcClient>) -> Result<Report> {
+    let cycles = dataset_cycles(data_dir)?;
+    let formats = match rpc {
+        Some(rpc) => {
+            let addresses: Vec<Pubkey> = cycles.iter().map(|&c| seka::cycle_state_pda(c)).collect();
+            fetch_many::<seka::CycleState>(rpc, &addresses)?
+                .into_iter()
+                .map(|state| match state {
+                    Some(state) => state.leaf_format().with_context(|| {
+                        format!("cycle {} has unknown leaf format {}", state.cycle_index, state.leaf_format)
+                    }),
+                    None => Ok(LeafFormat::Solana),
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+        None => vec![LeafFormat::Solana; cycles.len()],
+    };
+    let mut trees = Vec::new();
+    for (cycle_index, format) in cycles.into_iter().zip(formats) {
+        let Some(path) = dataset_path(data_dir, cycle_index) else {
+            continue;
+        };
+        let leaves = read_input(&path).with_context(|| format!("reading {}", path.display()))?;
+        trees.push(MerkleTree::with_format(format, cycle_index, leaves)?);
+    }
+    report(trees.iter(), owner, rpc)
+}
+
+/// Reports on `owner` over already built cycle trees.
+pub fn report<'a>(
+    trees: impl IntoIterator<Item = &'a MerkleTree>,
+    owner: &Pubkey,
+    rpc: Option<&RpcClient>,
+) -> Result<Report> {
+    let mut proofs: Vec<ProofFile> = trees
+        .into_iter()
+        .filter_map(|tree| tree.proof_for(owner))
+        .map(|proof| ProofFile::from(&proof))
+        .collect();
+    proofs.sort_by_key(|p| p.cycle_index);
+
+    let mut report = Report {
+        owner: owner.to_string(),
+        unclaimed: Vec::new(),
+        claimed_cycles: Vec::new(),
+        unpublished_cycles: Vec::new(),
+        unclaimed_points: 0,
+        chain: None,
+    };
+    let Some(rpc) = rpc else {
+        for proof in proofs {
+            report.unclaimed_points += i64::from(proof.delta_points);
+            report.unclaimed.push(OwedLeaf {
+                claimed: None,
+                proof,
+            });
+        }
+        return Ok(report);
+    };
+
+    let addresses: Vec<Pubkey> = proofs
+        .iter()
+        .map(|p| seka::cycle_state_pda(p.cycle_index))
+        .collect();
+    let states = fetch_many::<seka::CycleState>(rpc, &addresses)?;
+    for (proof, state) in proofs.into_iter().zip(states) {
+        match state {
+            None => report.unpublished_cycles.push(proof.cycle_index),
+            Some(state) if state.is_claimed(proof.leaf_index) => {
+                report.claimed_cycles.push(proof.cycle_index)
+            }
+            Some(_) => {
+                report.unclaimed_points += i64::from(proof.delta_points);
+                report.unclaimed.push(OwedLeaf {
+                    claimed: Some(false),
+                    proof,
+                });
+            }
+        }
+    }
+
+    let config: seka::GlobalConfig = sentinelkarma_sdk::fetch_account(rpc, &seka::config_pda())?;
+    let mut accounts = rpc
+        .get_multiple_accounts(&[seka::peer_pda(owner), seka::membership_pda(owner)])
+        .context("fetching ledger and membership")?
+        .into_iter();
+    let ledger = accounts
+        .next()
+        .flatten()
+        .map(|a| decode_account::<seka::PeerLedger>(&a.data))
+        .transpose()?;
+    let membership = accounts
+        .next()
+        .flatten()
+        .map(|a| decode_account::<seka::Membership>(&a.data))
+        .transpose()?;
+
+    let points = ledger.as_ref().map_or(0, |l| l.points);
+    let after_claims = report.unclaimed.iter().fold(points, |acc, leaf| {
+        acc.saturating_add(i64::from(leaf.proof.delta_points))
+            .max(0)
+    });
+    let ratio = i64::from(config.conversion_ratio.max(1));
+    let convertible = |points: i64| (points.max(0) / ratio) as u64;
+    report.chain = Some(ChainStatus {
+        points,
+        last_cycle_claimed: ledger.as_ref().map(|l| l.last_cycle_claimed),
+        conversion_ratio: config.conversion_ratio,
+        convertible_tokens: convertible(points),
+        convertible_after_claims: convertible(after_claims),
+        membership: match &membership {
+            Some(m) if m.active => MembershipStatus::Active,
+            Some(_) => MembershipStatus::Inactive,
+            None => MembershipStatus::None,
+        },
+        joined_at: membership.map(|m| m.joined_at),
+    });
+    Ok(report)
+}
+
+/// Accounts at `addresses`, `None` where there is none.
+fn fetch_many<T: AccountDeserialize + Versioned>(
+    rpc: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<T>>> {
+    let mut decoded = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_ACCOUNTS_PER_CALL) {
+        for account in rpc
+            .get_multiple_accounts(chunk)
+            .context("fetching cycle states")?
+        {
+            decoded.push(account.map(|a| decode_account(&a.data)).transpose()?);
+        }
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,244 @@
+//! Peer reputation: a 0–100 score from indexed on-chain signals.
+//!
+//! Four signals, each normalized to `[0, 1]`, are averaged under [`Params`]' weights
+//! and scaled by a penalty for upheld reports:
+//!
+//! - points: `ln(1 + max(points, 0)) / ln(1 + points_ref)`, capped at 1, where `points`
+//!   is the balance after the member's last claim
+//! - history: cycles with a positive claim among the last `history_cycles` published,
+//!   over `history_cycles`
+//! - reports: the member's accuracy as a reporter, `(1 + upheld) / (2 + upheld +
+//!   rejected)`; 0.5 for members who never reported
+//! - age: time since joining seka over `age_full_days`, capped at 1; 0 if not a member
+//!
+//! ```text
+//! score = 100 * upheld_penalty^posts_upheld_against
+//!             * (w_points * points + w_history * history + w_reports * reports + w_age * age)
+//!             / (w_points + w_history + w_reports + w_age)
+//! ```
+//!
+//! where `posts_upheld_against` counts upheld reports against posts the member
+//! authored. Signals are taken as of a timestamp, from rows with a block time at or
+//! before it, so a score for a past time does not move as the index grows. Ledger
+//! changes other than claims (conversions, credits) are not replayed.
+
+use anyhow::{Context, Result};
+use sentinelkarma_indexer::projection::{At, Model};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Weights and scales of the formula. Missing fields take the defaults.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Params {
+    pub points_weight: f64,
+    pub history_weight: f64,
+    pub reports_weight: f64,
+    pub age_weight: f64,
+    /// Points at which the points signal reaches 1
+    pub points_ref: u64,
+    /// Recent cycles the history signal looks at
+    pub history_cycles: u64,
+    /// Membership age at which the age signal reaches 1
+    pub age_full_days: u64,
+    /// Factor applied per upheld report against the member's posts
+    pub upheld_penalty: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            points_weight: 0.35,
+            history_weight: 0.25,
+            reports_weight: 0.15,
+            age_weight: 0.25,
+            points_ref: 1_000,
+            history_cycles: 12,
+            age_full_days: 180,
+            upheld_penalty: 0.75,
+        }
+    }
+}
+
+impl Params {
+    pub fn read(path: &Path) -> Result<Self> {
+        serde_json::from_slice(&std::fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))
+    }
+}
+
+/// One member's inputs to the formula.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Signals {
+    pub points: i64,
+    /// Of the last `history_cycles`
+    pub cycles_claimed: u64,
+    /// Reports filed by the member that were upheld
+    pub reports_upheld: u32,
+    /// Reports filed by the member that were rejected
+    pub reports_rejected: u32,
+    /// Upheld reports against posts the member authored
+    pub posts_upheld_against: u32,
+    pub joined_at: Option<i64>,
+}
+
+/// A score and the normalized signals it came from.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Reputation {
+    /// 0–100, two decimals
+    pub score: f64,
+    pub points: f64,
+    pub history: f64,
+    pub reports: f64,
+    pub age: f64,
+    /// Multiplier from upheld reports, 1 without any
+    pub penalty: f64,
+}
+
+fn dated(at: &At, as_of: i64) -> bool {
+    at.block_time.is_some_and(|t| t <= as_of)
+}
+
+impl Signals {
+    /// Every account with a membership, a claim or a report outcome as of `as_of`.
+    pub fn collect(model: &Model, params: &Params, as_of: i64) -> BTreeMap<Pubkey, Self> {
+        let recent = recent_cycles(model, params, as_of);
+        let mut signals: BTreeMap<Pubkey, Self> = BTreeMap::new();
+        for peer in model.peers.values().filter(|p| dated(&p.joined, as_of)) {
+            signals.entry(peer.owner).or_default().joined_at = peer.joined.block_time;
+        }
+        let mut claimed: BTreeMap<Pubkey, BTreeSet<u64>> = BTreeMap::new();
+        for claim in model.claims.iter().filter(|c| dated(&c.at, as_of)) {
+            signals.entry(claim.owner).or_default().points = claim.new_points;
+            if claim.delta_points > 0 && recent.contains(&claim.cycle_index) {
+                claimed
+                    .entry(claim.owner)
+                    .or_default()
+                    .insert(claim.cycle_index);
+            }
+        }
+        for (owner, cycles) in claimed {
+            signals.entry(owner).or_default().cycles_claimed = cycles.len() as u64;
+        }
+        for report in model.reports.iter().filter(|r| dated(&r.at, as_of)) {
+            let reporter = signals.entry(report.reporter).or_default();
+            if report.upheld {
+                reporter.reports_upheld += 1;
+            } else {
+                reporter.reports_rejected += 1;
+            }
+            if let (true, Some(post)) = (report.upheld, model.posts.get(&report.post)) {
+                signals.entry(post.author).or_default().posts_upheld_against += 1;
+            }
+        }
+        signals
+    }
+
+    /// `owner`'s signals as of `as_of`; all zero for an unknown account.
+    pub fn of(model: &Model, params: &Params, owner: &Pubkey, as_of: i64) -> Self {
+        let recent = recent_cycles(model, params, as_of);
+        let mut signals = Self {
+            joined_at: model
+                .peers
+                .get(owner)
+                .filter(|p| dated(&p.joined, as_of))
+                .and_then(|p| p.joined.block_time),
+            ..Self::default()
+        };
+        let mut claimed = BTreeSet::new();
+        for claim in model
+            .claims
+            .iter()
+            .filter(|c| c.owner == *owner && dated(&c.at, as_of))
+        {
+            signals.points = claim.new_points;
+            if claim.delta_points > 0 && recent.contains(&claim.cycle_index) {
+                claimed.insert(claim.cycle_index);
+            }
+        }
+        signals.cycles_claimed = claimed.len() as u64;
+        for report in model.reports.iter().filter(|r| dated(&r.at, as_of)) {
+            if report.reporter == *owner {
+                if report.upheld {
+                    signals.reports_upheld += 1;
+                } else {
+                    signals.reports_rejected += 1;
+                }
+            }
+            let authored = model
+                .posts
+                .get(&report.post)
+                .is_some_and(|p| p.author == *owner);
+            if report.upheld && authored {
+                signals.posts_upheld_against += 1;
+            }
+        }
+        signals
+    }
+
+    pub fn score(&self, params: &Params, as_of: i64) -> Reputation {
+        let scale = (params.points_ref.max(1) as f64).ln_1p();
+        let points = ((self.points.max(0) as f64).ln_1p() / scale).min(1.0);
+        let history = if params.history_cycles == 0 {
+            0.0
+        } else {
+            (self.cycles_claimed as f64 / params.history_cycles as f64).min(1.0)
+        };
+        let reports = f64::from(1 + self.reports_upheld)
+            / f64::from(2 + self.reports_upheld + self.reports_rejected);
+        let full = (params.age_full_days * 86_400) as f64;
+        let age = match self.joined_at {
+            Some(joined) if full > 0.0 => ((as_of - joined).max(0) as f64 / full).min(1.0),
+            Some(_) => 1.0,
+            None => 0.0,
+        };
+        let penalty = params
+            .upheld_penalty
+            .clamp(0.0, 1.0)
+            .powi(self.posts_upheld_against as i32);
+
+        let weights = params.points_weight
+            + params.history_weight
+            + params.reports_weight
+            + params.age_weight;
+        let weighted = params.points_weight * points
+            + params.history_weight * history
+            + params.reports_weight * reports
+            + params.age_weight * age;
+        let score = if weights > 0.0 {
+            100.0 * penalty * weighted / weights
+        } else {
+            0.0
+        };
+        Reputation {
+            score: (score.clamp(0.0, 100.0) * 100.0).round() / 100.0,
+            points,
+            history,
+            reports,
+            age,
+            penalty,
+        }
+    }
+}
+
+/// Scores of every account [`Signals::collect`] finds.
+pub fn score_all(model: &Model, params: &Params, as_of: i64) -> BTreeMap<Pubkey, Reputation> {
+    Signals::collect(model, params, as_of)
+        .into_iter()
+        .map(|(owner, signals)| (owner, signals.score(params, as_of)))
+        .collect()
+}
+
+/// The last `history_cycles` cycles whose root was published by `as_of`.
+fn recent_cycles(model: &Model, params: &Params, as_of: i64) -> BTreeSet<u64> {
+    model
+        .cycles
+        .values()
+        .filter(|c| dated(&c.published, as_of))
+        .map(|c| c.index)
+        .rev()
+        .take(params.history_cycles as usize)
+        .collect()
+}
@@ -0,0 +1,171 @@
+//! sentinel instructions for the scenario steps, on an SPL Token sentinel mint with no
+//! optional accounts.
+
+use sentinelkarma_sdk::seka::ASSOCIATED_TOKEN_PROGRAM_ID;
+use sentinelkarma_sdk::sentinel::{
+    self, accounts, instruction, ContentAddress, ContentScheme, ParamsUpdate,
+};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{system_program, sysvar};
+
+pub fn ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    sentinel::associated_token_address(wallet, mint, &spl_token::ID)
+}
+
+pub fn create_ata(payer: &Pubkey, wallet: &Pubkey, mint: &Pubkey) -> Instruction {
+    sentinel::create_associated_token_account_idempotent(payer, wallet, mint, &spl_token::ID)
+}
+
+pub fn update_params(authority: &Pubkey, params: ParamsUpdate) -> Instruction {
+    sentinel::instruction(
+        accounts::UpdateParams {
+            authority: *authority,
+            state: sentinel::state_pda(),
+        },
+        instruction::UpdateParams { params },
+    )
+}
+
+/// `distribute_initial_supply` of `amount` to the token account `recipient`.
+pub fn distribute(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::DistributeInitialSupply {
+            authority: *authority,
+            state: sentinel::state_pda(),
+            treasury_vault: treasury,
+            treasury_sentinel_ata: ata(&treasury, mint),
+            recipient: *recipient,
+            sentinel_mint: *mint,
+            token_program: spl_token::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::DistributeInitialSupply {
+            amount,
+            reason_code: 0,
+        },
+    )
+}
+
+pub fn join(user: &Pubkey, mint: &Pubkey) -> Instruction {
+    let treasury = sentinel::treasury_vault_pda();
+    sentinel::instruction(
+        accounts::JoinNetwork {
+            user: *user,
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            user_sentinel_ata: ata(user, mint),
+            sentinel_mint: *mint,
+            treasury_vault: treasury,
+            treasury_sentinel_ata: ata(&treasury, mint),
+            invite: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::JoinNetwork {},
+    )
+}
+
+/// `user`'s post number `index` (`AuthorIndex.posts_created`), of an existing 0-decimal
+/// mint whose authority is `user`. The content is a placeholder; nothing fetches it.
+pub fn mint_nft(user: &Pubkey, nft_mint: &Pubkey, mint: &Pubkey, index: u64) -> Instruction {
+    let post = sentinel::post_pda(nft_mint);
+    let mut cid = vec![0x01, 0x55, 0x12, 32];
+    cid.extend_from_slice(nft_mint.as_ref());
+    sentinel::instruction(
+        accounts::MintNft {
+            user: *user,
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            nft_mint: *nft_mint,
+            user_nft_ata: ata(user, nft_mint),
+            post,
+            author_index: sentinel::author_index_pda(user),
+            user_post: sentinel::user_post_pda(user, index),
+            sentinel_mint: *mint,
+            user_sentinel_ata: ata(user, mint),
+            bond_escrow: sentinel::bond_escrow_pda(&post),
+            post_commit: None,
+            storage_provider: None,
+            instructions: None,
+            tag_counter: None,
+            token_program: spl_token::ID,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MintNft {
+            hash: nft_mint.to_bytes(),
+            content: ContentAddress {
+                scheme: ContentScheme::Ipfs,
+                addr: cid,
+            },
+            soulbound: false,
+            royalty_bps: 0,
+            tag: [0; sentinel::TAG_LEN],
+        },
+    )
+}
+
+/// `like_nft` of an untagged post.
+pub fn like(liker: &Pubkey, post: &Pubkey, author: &Pubkey) -> Instruction {
+    sentinel::instruction(
+        accounts::LikeNft {
+            liker: *liker,
+            state: sentinel::state_pda(),
+            like: sentinel::like_pda(liker, post),
+            post: *post,
+            like_pair: sentinel::like_pair_pda(liker, author),
+            top_posts: None,
+            liked_peer: sentinel::peer_pda(author),
+            liker_peer: sentinel::peer_pda(liker),
+            tag_counter: None,
+            tag_karma: None,
+            system_program: system_program::ID,
+        },
+        instruction::LikeNft {},
+    )
+}
+
+pub fn crank_finalize(payer: &Pubkey, cycle_index: u64) -> Instruction {
+    sentinel::instruction(
+        accounts::CrankFinalizeCycle {
+            payer: *payer,
+            state: sentinel::state_pda(),
+            top_posts: None,
+            cycle_record: sentinel::cycle_record_pda(cycle_index),
+            system_program: system_program::ID,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::CrankFinalizeCycle {},
+    )
+}
+
+/// `crank_reset_karma` of `user`, whose karma was earned in `karma_cycle`.
+pub fn crank_reset(user: &Pubkey, karma_cycle: u64, mint: &Pubkey) -> Instruction {
+    sentinel::instruction(
+        accounts::CrankResetKarma {
+            state: sentinel::state_pda(),
+            peer: sentinel::peer_pda(user),
+            karma_history: sentinel::karma_history_pda(user),
+            cycle_record: Some(sentinel::cycle_record_pda(karma_cycle)),
+            sentinel_mint: *mint,
+            peer_sentinel_ata: ata(user, mint),
+            token_program: spl_token::ID,
+            seka_config: None,
+            seka_mint_authority: None,
+            seka_program: None,
+        },
+        instruction::CrankResetKarma {},
+    )
+}
@@ -0,0 +1,133 @@
+//! seka-scenario: runs multi-actor sentinel flows described in YAML against a local
+//! validator and reports which steps passed.
+//!
+//! A scenario names its actors, what they are funded with and the sentinel params to
+//! run under, then lists steps: join, post, like, finalize the cycle, settle rewards,
+//! and assertions on karma, likes, rewards and the finalized cycle. See [`scenario`]
+//! for the format and `scenarios/` for examples.
+//!
+//! Localnet only: `--keypair` must be the sentinel authority, which funds the actors
+//! from the genesis allocation and sets the scenario's params for the length of the run.
+
+mod ix;
+mod runner;
+mod scenario;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use runner::{Env, Report, Status};
+use scenario::Scenario;
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::signer;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Run YAML sentinel scenarios against a local validator")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Sentinel authority keypair, or a `usb://` / `http(s)://` signer locator
+    #[arg(
+        long,
+        env = "SENTINEL_KEYPAIR",
+        default_value = "~/.config/solana/id.json"
+    )]
+    keypair: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run scenarios in order; exits nonzero if any fails
+    Run {
+        files: Vec<PathBuf>,
+
+        /// Write the reports as JSON
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+    /// Parse and validate scenarios without running them
+    Check { files: Vec<PathBuf> },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Check { files } => {
+            for path in &files {
+                let scenario = Scenario::read(path)?;
+                println!(
+                    "{}: {} ({} actors, {} steps)",
+                    path.display(),
+                    scenario.name,
+                    scenario.actor_names().len(),
+                    scenario.steps.len()
+                );
+            }
+        }
+        Command::Run { files, report } => {
+            let scenarios = files
+                .iter()
+                .map(|path| Scenario::read(path))
+                .collect::<Result<Vec<_>>>()?;
+            let client = Client::new(cli.url, ClientConfig::default());
+            let env = Env::connect(client, signer::resolve(&cli.keypair)?).await?;
+
+            let mut reports = Vec::with_capacity(scenarios.len());
+            for scenario in &scenarios {
+                println!("scenario {}", scenario.name);
+                if !scenario.description.is_empty() {
+                    println!("  {}", scenario.description.trim());
+                }
+                let report = runner::run(&env, scenario).await;
+                print_report(&report);
+                reports.push(report);
+            }
+            let failed = reports.iter().filter(|r| !r.passed).count();
+            println!(
+                "{} scenarios, {} passed, {failed} failed",
+                reports.len(),
+                reports.len() - failed
+            );
+            if let Some(path) = report {
+                let json = serde_json::to_string_pretty(&reports)?;
+                std::fs::write(&path, json + "\n")
+                    .with_context(|| format!("writing {}", path.display()))?;
+            }
+            if failed > 0 {
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_report(report: &Report) {
+    for step in &report.steps {
+        let mut detail = step.detail.iter();
+        match detail.next() {
+            Some(first) => println!(
+                "  {:<4} {:>2} {}: {first}",
+                step.status, step.step, step.action
+            ),
+            None => println!("  {:<4} {:>2} {}", step.status, step.step, step.action),
+        }
+        for line in detail {
+            println!("            {line}");
+        }
+    }
+    let verdict = if report.passed { "passed" } else { "FAILED" };
+    let skipped = report
+        .steps
+        .iter()
+        .filter(|s| s.status == Status::Skipped)
+        .count();
+    println!(
+        "  {verdict} in {:.1}s, {skipped} steps skipped",
+        report.secs
+    );
+}
@@ -0,0 +1,657 @@
+//! Runs a [`Scenario`] against a live sentinel and records what each step did.
+//!
+//! Actors are fresh keypairs, funded by the sentinel authority with SOL and SENTINEL
+//! from the genesis allocation, so scenarios do not interfere with each other's peers.
+//! They do share the program's state: the cycle and its karma are whatever the cluster
+//! is at, which is why cycle assertions read the cycle the scenario itself finalized.
+//!
+//! A step that fails stops the scenario and the remaining steps are skipped; a failed
+//! assertion does not, as assertions change nothing.
+
+use crate::ix;
+use crate::scenario::{
+    Action, Assertion, CycleField, LikeSpec, Params, Pattern, Scenario, Step, Targets,
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use sentinelkarma_sdk::client::Client;
+use sentinelkarma_sdk::sentinel::{self, CycleRecord, ParamsUpdate, PeerState, Post, State};
+use serde::Serialize;
+use solana_sdk::clock::Clock;
+use solana_sdk::native_token::sol_to_lamports;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::{system_instruction, sysvar};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The cluster a scenario runs on and the sentinel authority that funds its actors.
+pub struct Env {
+    pub client: Client,
+    pub authority: Box<dyn Signer>,
+    pub mint: Pubkey,
+    pub decimals: u8,
+}
+
+impl Env {
+    pub async fn connect(client: Client, authority: Box<dyn Signer>) -> Result<Self> {
+        let state: State = client
+            .fetch_account(&sentinel::state_pda())
+            .await
+            .context("reading the sentinel state; is sentinel initialized?")?;
+        ensure!(
+            state.authority == authority.pubkey(),
+            "{} is not the sentinel authority {}",
+            authority.pubkey(),
+            state.authority
+        );
+        ensure!(
+            !state.shared_mint,
+            "scenarios need sentinel's own mint; this sentinel shares the SEKA mint"
+        );
+        let mint = client.rpc().get_account(&state.sentinel_mint).await?;
+        ensure!(
+            mint.owner == spl_token::ID,
+            "scenarios need an SPL Token sentinel mint, as `seka-admin bootstrap` creates"
+        );
+        let decimals = spl_token::state::Mint::unpack(&mint.data)?.decimals;
+        Ok(Self {
+            client,
+            authority,
+            mint: state.sentinel_mint,
+            decimals,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub name: String,
+    pub passed: bool,
+    pub secs: f64,
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Serialize)]
+pub struct StepReport {
+    /// 0 is the setup, steps count from 1
+    pub step: usize,
+    pub action: String,
+    pub status: Status,
+    /// Failures for failed steps, else what the step did
+    pub detail: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Status::Passed => "ok",
+            Status::Failed => "FAIL",
+            Status::Skipped => "skip",
+        })
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Join(targets) => write!(f, "join {targets}"),
+            Action::Post(targets) => write!(f, "post {targets}"),
+            Action::Like(LikeSpec::Pattern(Pattern::Ring)) => write!(f, "like ring"),
+            Action::Like(LikeSpec::Pattern(Pattern::All)) => write!(f, "like all"),
+            Action::Like(LikeSpec::Pair { from, to }) => write!(f, "like {from} -> {to}"),
+            Action::Finalize(_) => write!(f, "finalize"),
+            Action::Settle(targets) => write!(f, "settle {targets}"),
+            Action::Sleep(secs) => write!(f, "sleep {secs}s"),
+            Action::Assert(assertions) => write!(f, "assert ({})", assertions.len()),
+        }
+    }
+}
+
+impl fmt::Display for Targets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Targets::One(name) => f.write_str(name),
+            Targets::Many(names) => write!(f, "[{}]", names.join(", ")),
+        }
+    }
+}
+
+struct Run<'a> {
+    env: &'a Env,
+    scenario: &'a Scenario,
+    actors: HashMap<String, Keypair>,
+    /// Latest post of each actor that has posted
+    posts: HashMap<String, Pubkey>,
+    posts_created: HashMap<String, u64>,
+    /// SENTINEL each actor's last settle paid
+    rewards: HashMap<String, u64>,
+    finalized: Option<u64>,
+}
+
+pub async fn run(env: &Env, scenario: &Scenario) -> Report {
+    let start = Instant::now();
+    let mut run = Run {
+        env,
+        scenario,
+        actors: scenario
+            .actor_names()
+            .into_iter()
+            .map(|name| (name, Keypair::new()))
+            .collect(),
+        posts: HashMap::new(),
+        posts_created: HashMap::new(),
+        rewards: HashMap::new(),
+        finalized: None,
+    };
+    let mut steps = Vec::with_capacity(scenario.steps.len() + 1);
+
+    let setup = async {
+        run.fund().await?;
+        run.apply_params(&scenario.params).await
+    };
+    let restore = match setup.await {
+        Ok(restore) => {
+            steps.push(StepReport {
+                step: 0,
+                action: "setup".into(),
+                status: Status::Passed,
+                detail: vec![format!("{} actors funded", run.actors.len())],
+            });
+            restore
+        }
+        Err(e) => {
+            steps.push(StepReport {
+                step: 0,
+                action: "setup".into(),
+                status: Status::Failed,
+                detail: vec![format!("{e:#}")],
+            });
+            return finish(scenario, start, steps);
+        }
+    };
+
+    let mut stopped = false;
+    for (i, step) in scenario.steps.iter().enumerate() {
+        let action = step.action.to_string();
+        if stopped {
+            steps.push(StepReport {
+                step: i + 1,
+                action,
+                status: Status::Skipped,
+                detail: Vec::new(),
+            });
+            continue;
+        }
+        let (status, detail) = run.step(step).await;
+        if status == Status::Failed && !matches!(step.action, Action::Assert(_)) {
+            stopped = true;
+        }
+        steps.push(StepReport {
+            step: i + 1,
+            action,
+            status,
+            detail,
+        });
+    }
+
+    if let Some(restore) = restore {
+        if let Err(e) = run
+            .send_as_authority(vec![ix::update_params(&env.authority.pubkey(), restore)])
+            .await
+        {
+            eprintln!("warning: restoring sentinel params: {e:#}");
+        }
+    }
+    finish(scenario, start, steps)
+}
+
+fn finish(scenario: &Scenario, start: Instant, steps: Vec<StepReport>) -> Report {
+    Report {
+        name: scenario.name.clone(),
+        passed: steps.iter().all(|s| s.status == Status::Passed),
+        secs: start.elapsed().as_secs_f64(),
+        steps,
+    }
+}
+
+impl Run<'_> {
+    fn actor(&self, name: &str) -> Result<&Keypair> {
+        self.actors
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown actor {name:?}"))
+    }
+
+    async fn send_as_authority(
+        &self,
+        ixs: Vec<solana_sdk::instruction::Instruction>,
+    ) -> Result<()> {
+        self.env
+            .client
+            .send(&ixs, self.env.authority.as_ref(), &[])
+            .await?;
+        Ok(())
+    }
+
+    async fn send_as(
+        &self,
+        actor: &Keypair,
+        ixs: &[solana_sdk::instruction::Instruction],
+        signers: &[&dyn Signer],
+    ) -> Result<()> {
+        self.env.client.send(ixs, actor, signers).await?;
+        Ok(())
+    }
+
+    /// SOL, an ATA and SENTINEL for every actor.
+    async fn fund(&self) -> Result<()> {
+        let authority = self.env.authority.pubkey();
+        let lamports = sol_to_lamports(self.scenario.fund.sol);
+        let amount = self.scenario.fund.sentinel * 10u64.pow(u32::from(self.env.decimals));
+        for name in self.scenario.actor_names() {
+            let wallet = self.actor(&name)?.pubkey();
+            let mut ixs = vec![ix::create_ata(&authority, &wallet, &self.env.mint)];
+            if lamports > 0 {
+                ixs.insert(
+                    0,
+                    system_instruction::transfer(&authority, &wallet, lamports),
+                );
+            }
+            if amount > 0 {
+                let ata = ix::ata(&wallet, &self.env.mint);
+                ixs.push(ix::distribute(&authority, &self.env.mint, &ata, amount));
+            }
+            self.send_as_authority(ixs)
+                .await
+                .with_context(|| format!("funding {name}"))?;
+        }
+        Ok(())
+    }
+
+    /// Applies the scenario's params and returns the update that undoes them.
+    async fn apply_params(&self, params: &Params) -> Result<Option<ParamsUpdate>> {
+        if params.is_empty() {
+            return Ok(None);
+        }
+        let state = self.state().await?;
+        let update = ParamsUpdate {
+            cycle_seconds: params.cycle_seconds,
+            cycle_reward_total: params.cycle_reward_total,
+            max_likes_per_cycle: params.max_likes_per_cycle,
+            max_likes_per_pair: params.max_likes_per_pair,
+            like_reward_window: params.like_reward_window,
+            post_bond: params.post_bond,
+            crank_finalize: params.crank_finalize,
+            ..ParamsUpdate::default()
+        };
+        let restore = ParamsUpdate {
+            cycle_seconds: params.cycle_seconds.map(|_| state.cycle_seconds),
+            cycle_reward_total: params.cycle_reward_total.map(|_| state.cycle_reward_total),
+            max_likes_per_cycle: params
+                .max_likes_per_cycle
+                .map(|_| state.max_likes_per_cycle),
+            max_likes_per_pair: params.max_likes_per_pair.map(|_| state.max_likes_per_pair),
+            like_reward_window: params.like_reward_window.map(|_| state.like_reward_window),
+            post_bond: params.post_bond.map(|_| state.post_bond),
+            crank_finalize: params.crank_finalize.map(|_| state.crank_finalize),
+            ..ParamsUpdate::default()
+        };
+        self.send_as_authority(vec![ix::update_params(
+            &self.env.authority.pubkey(),
+            update,
+        )])
+        .await
+        .context("applying params")?;
+        Ok(Some(restore))
+    }
+
+    async fn step(&mut self, step: &Step) -> (Status, Vec<String>) {
+        if let Action::Assert(assertions) = &step.action {
+            return match self.check(assertions).await {
+                Ok(failures) if failures.is_empty() => (Status::Passed, Vec::new()),
+                Ok(failures) => (Status::Failed, failures),
+                Err(e) => (Status::Failed, vec![format!("{e:#}")]),
+            };
+        }
+        let result = self.act(&step.action).await;
+        match (&step.expect_error, result) {
+            (None, Ok(detail)) => (Status::Passed, detail),
+            (None, Err(e)) => (Status::Failed, vec![format!("{e:#}")]),
+            (Some(name), Ok(_)) => (Status::Failed, vec![format!("succeeded, expected {name}")]),
+            (Some(name), Err(e)) => {
+                let message = format!("{e:#}");
+                // Anchor logs `Error Code: <name>. Error Number: ...`
+                if message.contains(&format!("Error Code: {name}.")) {
+                    (
+                        Status::Passed,
+                        vec![format!("failed with {name}, as expected")],
+                    )
+                } else {
+                    (
+                        Status::Failed,
+                        vec![format!("expected {name}, failed with: {message}")],
+                    )
+                }
+            }
+        }
+    }
+
+    async fn act(&mut self, action: &Action) -> Result<Vec<String>> {
+        match action {
+            Action::Join(targets) => {
+                let names = self.scenario.resolve(targets)?;
+                for name in &names {
+                    let actor = self.actor(name)?;
+                    self.send_as(actor, &[ix::join(&actor.pubkey(), &self.env.mint)], &[])
+                        .await
+                        .with_context(|| format!("{name} joining"))?;
+                }
+                Ok(vec![format!("{} joined", names.len())])
+            }
+            Action::Post(targets) => {
+                let names = self.scenario.resolve(targets)?;
+                for name in &names {
+                    self.post(name)
+                        .await
+                        .with_context(|| format!("{name} posting"))?;
+                }
+                Ok(vec![format!("{} posts", names.len())])
+            }
+            Action::Like(spec) => {
+                let pairs = self.like_pairs(spec)?;
+                for (from, to) in &pairs {
+                    let post = *self
+                        .posts
+                        .get(to)
+                        .ok_or_else(|| anyhow!("{to} has not posted"))?;
+                    let liker = self.actor(from)?;
+                    let author = self.actor(to)?.pubkey();
+                    self.send_as(liker, &[ix::like(&liker.pubkey(), &post, &author)], &[])
+                        .await
+                        .with_context(|| format!("{from} liking {to}'s post"))?;
+                }
+                Ok(vec![format!("{} likes", pairs.len())])
+            }
+            Action::Finalize(finalize) => {
+                let cycle_index = self
+                    .finalize(Duration::from_secs(finalize.timeout_secs))
+                    .await?;
+                self.finalized = Some(cycle_index);
+                Ok(vec![format!("cycle {cycle_index} finalized")])
+            }
+            Action::Settle(targets) => {
+                let names = self.scenario.resolve(targets)?;
+                let mut paid = 0;
+                for name in &names {
+                    let reward = self
+                        .settle(name)
+                        .await
+                        .with_context(|| format!("settling {name}"))?;
+                    paid += reward;
+                    self.rewards.insert(name.clone(), reward);
+                }
+                Ok(vec![format!("{} settled, {paid} paid", names.len())])
+            }
+            Action::Sleep(secs) => {
+                tokio::time::sleep(Duration::from_secs(*secs)).await;
+                Ok(Vec::new())
+            }
+            Action::Assert(_) => unreachable!("assertions are checked, not acted"),
+        }
+    }
+
+    async fn post(&mut self, name: &str) -> Result<()> {
+        let actor = self.actor(name)?;
+        let user = actor.pubkey();
+        let nft_mint = Keypair::new();
+        let rent = self
+            .env
+            .client
+            .rpc()
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+            .await?;
+        let index = self.posts_created.get(name).copied().unwrap_or(0);
+        let ixs = [
+            system_instruction::create_account(
+                &user,
+                &nft_mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(
+                &spl_token::ID,
+                &nft_mint.pubkey(),
+                &user,
+                None,
+                0,
+            )?,
+            ix::mint_nft(&user, &nft_mint.pubkey(), &self.env.mint, index),
+        ];
+        self.send_as(actor, &ixs, &[&nft_mint]).await?;
+        self.posts
+            .insert(name.to_string(), sentinel::post_pda(&nft_mint.pubkey()));
+        self.posts_created.insert(name.to_string(), index + 1);
+        Ok(())
+    }
+
+    fn like_pairs(&self, spec: &LikeSpec) -> Result<Vec<(String, String)>> {
+        let names = self.scenario.actor_names();
+        Ok(match spec {
+            LikeSpec::Pattern(Pattern::Ring) => {
+                ensure!(names.len() > 1, "a ring needs at least two actors");
+                (0..names.len())
+                    .map(|i| (names[i].clone(), names[(i + 1) % names.len()].clone()))
+                    .collect()
+            }
+            LikeSpec::Pattern(Pattern::All) => names
+                .iter()
+                .flat_map(|from| {
+                    names
+                        .iter()
+                        .filter(move |to| *to != from)
+                        .map(move |to| (from.clone(), to.clone()))
+                })
+                .collect(),
+            LikeSpec::Pair { from, to } => self
+                .scenario
+                .resolve(from)?
+                .into_iter()
+                .map(|from| (from, to.clone()))
+                .collect(),
+        })
+    }
+
+    /// Waits for the current cycle to end and cranks it; returns the finalized index.
+    async fn finalize(&self, timeout: Duration) -> Result<u64> {
+        let deadline = Instant::now() + timeout;
+        let cycle_index = self.state().await?.cycle_index;
+        let payer = self.env.authority.pubkey();
+        loop {
+            let state = self.state().await?;
+            if state.cycle_index > cycle_index {
+                return Ok(cycle_index);
+            }
+            let due = state.cycle_start_ts + state.cycle_seconds;
+            let now = self.chain_time().await?;
+            if now >= due {
+                // A no-op if someone else cranked first; the next read sees the new cycle
+                self.send_as_authority(vec![ix::crank_finalize(&payer, cycle_index)])
+                    .await?;
+                continue;
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "cycle {cycle_index} ends in {}s, past the {}s timeout; lower params.cycle_seconds",
+                    due - now,
+                    timeout.as_secs()
+                );
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// `crank_reset_karma` for `name`; returns the SENTINEL it paid.
+    async fn settle(&self, name: &str) -> Result<u64> {
+        let user = self.actor(name)?.pubkey();
+        let peer: PeerState = self
+            .env
+            .client
+            .fetch_zero_copy(&sentinel::peer_pda(&user))
+            .await?;
+        let ata = ix::ata(&user, &self.env.mint);
+        let before = self.balance(&ata).await?;
+        self.send_as_authority(vec![ix::crank_reset(
+            &user,
+            peer.karma_cycle,
+            &self.env.mint,
+        )])
+        .await?;
+        Ok(self.balance(&ata).await?.saturating_sub(before))
+    }
+
+    async fn check(&self, assertions: &[Assertion]) -> Result<Vec<String>> {
+        let mut failures = Vec::new();
+        for assertion in assertions {
+            match assertion {
+                Assertion::Karma(a) | Assertion::KarmaEarned(a) => {
+                    let earned = matches!(assertion, Assertion::KarmaEarned(_));
+                    let what = if earned { "karma_earned" } else { "karma" };
+                    for name in self.scenario.resolve(&a.actor)? {
+                        let user = self.actor(&name)?.pubkey();
+                        let peer: PeerState = self
+                            .env
+                            .client
+                            .fetch_zero_copy(&sentinel::peer_pda(&user))
+                            .await
+                            .with_context(|| format!("{name} has not joined"))?;
+                        let actual = if earned {
+                            peer.karma_earned
+                        } else {
+                            peer.karma
+                        };
+                        if let Some(why) = a.compare.check(actual) {
+                            failures.push(format!("{what} of {name}: {why}"));
+                        }
+                    }
+                }
+                Assertion::Likes(a) => {
+                    for name in self.scenario.resolve(&a.actor)? {
+                        let Some(address) = self.posts.get(&name) else {
+                            failures.push(format!("likes of {name}: has not posted"));
+                            continue;
+                        };
+                        let post: Post = self.env.client.fetch_zero_copy(address).await?;
+                        if let Some(why) = a.compare.check(post.likes) {
+                            failures.push(format!("likes of {name}'s post: {why}"));
+                        }
+                    }
+                }
+                Assertion::Reward(a) => {
+                    for name in self.scenario.resolve(&a.actor)? {
+                        match self.rewards.get(&name) {
+                            None => failures.push(format!("reward of {name}: not settled")),
+                            Some(reward) => {
+                                if let Some(why) = a.compare.check(*reward) {
+                                    failures.push(format!("reward of {name}: {why}"));
+                                }
+                            }
+                        }
+                    }
+                }
+                Assertion::RewardsEqual(targets) => {
+                    let names = self.scenario.resolve(targets)?;
+                    let rewards: Vec<(String, Option<u64>)> = names
+                        .into_iter()
+                        .map(|name| {
+                            let reward = self.rewards.get(&name).copied();
+                            (name, reward)
+                        })
+                        .collect();
+                    let first = rewards[0].1.filter(|r| *r > 0);
+                    if first.is_none() || rewards.iter().any(|(_, r)| *r != first) {
+                        let listed: Vec<String> = rewards
+                            .iter()
+                            .map(|(name, r)| match r {
+                                Some(r) => format!("{name} {r}"),
+                                None => format!("{name} unsettled"),
+                            })
+                            .collect();
+                        failures.push(format!(
+                            "rewards not equal and positive: {}",
+                            listed.join(", ")
+                        ));
+                    }
+                }
+                Assertion::TotalPeers(compare) => {
+                    let state = self.state().await?;
+                    if let Some(why) = compare.check(state.total_peers) {
+                        failures.push(format!("total_peers: {why}"));
+                    }
+                }
+                Assertion::Cycle(c) => {
+                    let Some(cycle_index) = self.finalized else {
+                        failures.push("cycle: no cycle finalized yet".into());
+                        continue;
+                    };
+                    let record: CycleRecord = self
+                        .env
+                        .client
+                        .fetch_account(&sentinel::cycle_record_pda(cycle_index))
+                        .await?;
+                    let (field, actual) = match c.field {
+                        CycleField::TotalKarma => (
+                            "total_karma",
+                            u64::try_from(record.total_karma).unwrap_or(u64::MAX),
+                        ),
+                        CycleField::TotalMinted => ("total_minted", record.total_minted),
+                        CycleField::PeersRewarded => {
+                            ("peers_rewarded", u64::from(record.peers_rewarded))
+                        }
+                    };
+                    if let Some(why) = c.compare.check(actual) {
+                        failures.push(format!("cycle {cycle_index} {field}: {why}"));
+                    }
+                }
+            }
+        }
+        Ok(failures)
+    }
+
+    async fn state(&self) -> Result<State> {
+        self.env.client.fetch_account(&sentinel::state_pda()).await
+    }
+
+    async fn balance(&self, ata: &Pubkey) -> Result<u64> {
+        let balance = self
+            .env
+            .client
+            .rpc()
+            .get_token_account_balance(ata)
+            .await
+            .with_context(|| format!("reading {ata}"))?;
+        Ok(balance.amount.parse()?)
+    }
+
+    /// The cluster's unix time, which is what `crank_finalize_cycle` compares against.
+    async fn chain_time(&self) -> Result<i64> {
+        let account = self
+            .env
+            .client
+            .rpc()
+            .get_account(&sysvar::clock::ID)
+            .await?;
+        let clock: Clock = solana_sdk::account::from_account(&account)
+            .ok_or_else(|| anyhow!("decoding the clock sysvar"))?;
+        Ok(clock.unix_timestamp)
+    }
+}
@@ -0,0 +1,329 @@
+//! The YAML scenario format.
+//!
+//! ```yaml
+//! name: ring of likes
+//! actors: 10                 # peer0..peer9, or a list of names
+//! fund: { sol: 1.0, sentinel: 2000 }
+//! params: { cycle_seconds: 20 }
+//! steps:
+//!   - join: all
+//!   - post: all
+//!   - like: ring             # each actor likes the next one's latest post
+//!   - like: { from: peer0, to: peer0 }
+//!     expect_error: CannotLikeSelf
+//!   - finalize: {}
+//!   - settle: all
+//!   - assert:
+//!       - karma_earned: { actor: all, equals: 1 }
+//!       - rewards_equal: all
+//! ```
+//!
+//! Wherever actors are named, `all` means every actor and a list names several.
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub actors: Actors,
+    #[serde(default)]
+    pub fund: Fund,
+    /// Sentinel `update_params` applied before the steps and undone after them
+    #[serde(default)]
+    pub params: Params,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Actors {
+    Count(u32),
+    Names(Vec<String>),
+}
+
+/// What each actor starts with.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fund {
+    #[serde(default = "default_sol")]
+    pub sol: f64,
+    /// Whole SENTINEL from the genesis allocation
+    #[serde(default = "default_sentinel")]
+    pub sentinel: u64,
+}
+
+fn default_sol() -> f64 {
+    1.0
+}
+
+fn default_sentinel() -> u64 {
+    2_000
+}
+
+impl Default for Fund {
+    fn default() -> Self {
+        Self {
+            sol: default_sol(),
+            sentinel: default_sentinel(),
+        }
+    }
+}
+
+/// The sentinel parameters a scenario may set; see `ParamsUpdate`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Params {
+    pub cycle_seconds: Option<i64>,
+    pub cycle_reward_total: Option<u64>,
+    pub max_likes_per_cycle: Option<u32>,
+    pub max_likes_per_pair: Option<u32>,
+    pub like_reward_window: Option<u64>,
+    pub post_bond: Option<u64>,
+    /// Needed by `finalize` steps unless the network already allows the keyless crank
+    pub crank_finalize: Option<bool>,
+}
+
+impl Params {
+    pub fn is_empty(&self) -> bool {
+        self.cycle_seconds.is_none()
+            && self.cycle_reward_total.is_none()
+            && self.max_likes_per_cycle.is_none()
+            && self.max_likes_per_pair.is_none()
+            && self.like_reward_window.is_none()
+            && self.post_bond.is_none()
+            && self.crank_finalize.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Step {
+    #[serde(flatten)]
+    pub action: Action,
+    /// Sentinel error name the step must fail with, e.g. `CannotLikeSelf`
+    pub expect_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// `join_network`
+    Join(Targets),
+    /// `mint_nft` of a fresh 0-decimal mint; becomes the actor's latest post
+    Post(Targets),
+    /// `like_nft` of latest posts
+    Like(LikeSpec),
+    /// Waits out the current sentinel cycle, then `crank_finalize_cycle`
+    Finalize(Finalize),
+    /// `crank_reset_karma`, paying each actor's reward for the cycle its karma is from
+    Settle(Targets),
+    /// Seconds
+    Sleep(u64),
+    Assert(Vec<Assertion>),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Targets {
+    /// An actor name, or `all`
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum LikeSpec {
+    Pattern(Pattern),
+    Pair { from: Targets, to: String },
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// Actor `i` likes actor `i + 1`'s post, the last likes the first's
+    Ring,
+    /// Every actor likes every other actor's post
+    All,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Finalize {
+    /// Longest to wait for the cycle to end
+    #[serde(default = "default_finalize_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_finalize_timeout() -> u64 {
+    300
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Assertion {
+    /// `PeerState.karma`, karma not yet settled
+    Karma(ActorCompare),
+    /// `PeerState.karma_earned`, karma over the actor's lifetime
+    KarmaEarned(ActorCompare),
+    /// Likes on the actor's latest post
+    Likes(ActorCompare),
+    /// SENTINEL base units the actor's last `settle` paid
+    Reward(ActorCompare),
+    /// Every named actor's last `settle` paid the same, and more than nothing
+    RewardsEqual(Targets),
+    /// `State.total_peers`
+    TotalPeers(Compare),
+    /// A field of the last cycle this scenario finalized
+    Cycle(CycleCompare),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ActorCompare {
+    pub actor: Targets,
+    #[serde(flatten)]
+    pub compare: Compare,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CycleCompare {
+    pub field: CycleField,
+    #[serde(flatten)]
+    pub compare: Compare,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleField {
+    TotalKarma,
+    TotalMinted,
+    PeersRewarded,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Compare {
+    pub equals: Option<u64>,
+    pub at_least: Option<u64>,
+    pub at_most: Option<u64>,
+}
+
+impl Compare {
+    /// Why `actual` fails the comparison; `None` if it passes.
+    pub fn check(&self, actual: u64) -> Option<String> {
+        if self.equals.is_some_and(|v| actual != v) {
+            return Some(format!("{actual}, expected {}", self.equals.unwrap()));
+        }
+        if self.at_least.is_some_and(|v| actual < v) {
+            return Some(format!(
+                "{actual}, expected at least {}",
+                self.at_least.unwrap()
+            ));
+        }
+        if self.at_most.is_some_and(|v| actual > v) {
+            return Some(format!(
+                "{actual}, expected at most {}",
+                self.at_most.unwrap()
+            ));
+        }
+        None
+    }
+
+    fn is_empty(&self) -> bool {
+        self.equals.is_none() && self.at_least.is_none() && self.at_most.is_none()
+    }
+}
+
+impl Scenario {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let scenario: Scenario =
+            serde_yaml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        scenario
+            .validate()
+            .with_context(|| format!("checking {}", path.display()))?;
+        Ok(scenario)
+    }
+
+    pub fn actor_names(&self) -> Vec<String> {
+        match &self.actors {
+            Actors::Count(n) => (0..*n).map(|i| format!("peer{i}")).collect(),
+            Actors::Names(names) => names.clone(),
+        }
+    }
+
+    /// Names `targets` stands for, in actor order.
+    pub fn resolve(&self, targets: &Targets) -> Result<Vec<String>> {
+        let names = self.actor_names();
+        let wanted = match targets {
+            Targets::One(name) if name == "all" => return Ok(names),
+            Targets::One(name) => vec![name.clone()],
+            Targets::Many(list) => list.clone(),
+        };
+        for name in &wanted {
+            ensure!(names.contains(name), "unknown actor {name:?}");
+        }
+        Ok(wanted)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let names = self.actor_names();
+        ensure!(!names.is_empty(), "a scenario needs at least one actor");
+        let mut seen = HashSet::new();
+        for name in &names {
+            ensure!(name != "all", "`all` is reserved and cannot name an actor");
+            ensure!(seen.insert(name), "actor {name:?} is listed twice");
+        }
+        ensure!(!self.steps.is_empty(), "a scenario needs at least one step");
+        ensure!(self.fund.sol >= 0.0, "fund.sol cannot be negative");
+        for (i, step) in self.steps.iter().enumerate() {
+            self.validate_step(step)
+                .with_context(|| format!("step {}", i + 1))?;
+        }
+        Ok(())
+    }
+
+    fn validate_step(&self, step: &Step) -> Result<()> {
+        match &step.action {
+            Action::Join(targets) | Action::Post(targets) | Action::Settle(targets) => {
+                self.resolve(targets)?;
+            }
+            Action::Like(LikeSpec::Pair { from, to }) => {
+                self.resolve(from)?;
+                self.resolve(&Targets::One(to.clone()))?;
+            }
+            Action::Like(LikeSpec::Pattern(_)) | Action::Finalize(_) | Action::Sleep(_) => {}
+            Action::Assert(assertions) => {
+                if step.expect_error.is_some() {
+                    bail!("assertions cannot expect an error");
+                }
+                for assertion in assertions {
+                    let compare = match assertion {
+                        Assertion::Karma(a)
+                        | Assertion::KarmaEarned(a)
+                        | Assertion::Likes(a)
+                        | Assertion::Reward(a) => {
+                            self.resolve(&a.actor)?;
+                            &a.compare
+                        }
+                        Assertion::RewardsEqual(targets) => {
+                            self.resolve(targets)?;
+                            continue;
+                        }
+                        Assertion::TotalPeers(compare) => compare,
+                        Assertion::Cycle(c) => &c.compare,
+                    };
+                    ensure!(
+                        !compare.is_empty(),
+                        "an assertion needs equals, at_least or at_most"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,471 @@
+//! Monte Carlo model of seka's token flows, for weighing `update_config` proposals
+//! before they are voted on.
+//!
+//! A [`Scenario`] describes the network: how fast peers join and leave, how karma is
+//! spread across them each cycle, and how eagerly they convert points to SEKA. It is
+//! run against a [`Config`] (the program defaults, or the live `GlobalConfig`) and
+//! against the same config with a [`Proposal`] applied. Both runs share the scenario's
+//! seed, so differences in the projections come from the parameters rather than noise.
+//!
+//! Each cycle follows the program's rules: joins pay `join_cost_tokens` into the
+//! treasury, deltas are clamped to `per_peer_cycle_cap` and scaled down to
+//! `max_points_per_cycle` the way `seka-aggregation` does, claims apply them to
+//! ledgers, and conversions mint whole tokens at `conversion_ratio` points each.
+
+use anyhow::{ensure, Context, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Pareto, Poisson};
+use sentinelkarma_sdk::seka::{GlobalConfig, UpdateParams};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// The `GlobalConfig` fields that move tokens.
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub cycle_secs: u64,
+    pub max_points_per_cycle: u32,
+    pub per_peer_cycle_cap: i32,
+    /// Points per whole SEKA
+    pub conversion_ratio: u32,
+    /// Base units
+    pub join_cost_tokens: u64,
+    pub decimals: u8,
+}
+
+impl Default for Config {
+    /// What `initialize` sets.
+    fn default() -> Self {
+        Self {
+            cycle_secs: 259_200,
+            max_points_per_cycle: 10_000,
+            per_peer_cycle_cap: 100,
+            conversion_ratio: 100,
+            join_cost_tokens: 10 * 10u64.pow(6),
+            decimals: 6,
+        }
+    }
+}
+
+impl From<&GlobalConfig> for Config {
+    fn from(config: &GlobalConfig) -> Self {
+        Self {
+            cycle_secs: config.cycle_secs,
+            max_points_per_cycle: config.max_points_per_cycle,
+            per_peer_cycle_cap: config.per_peer_cycle_cap,
+            conversion_ratio: config.conversion_ratio,
+            join_cost_tokens: config.join_cost_tokens,
+            decimals: config.decimals,
+        }
+    }
+}
+
+impl Config {
+    /// The config after `update_config` with `proposal`.
+    pub fn apply(mut self, proposal: &Proposal) -> Self {
+        if let Some(v) = proposal.cycle_secs {
+            self.cycle_secs = v;
+        }
+        if let Some(v) = proposal.max_points_per_cycle {
+            self.max_points_per_cycle = v;
+        }
+        if let Some(v) = proposal.per_peer_cycle_cap {
+            self.per_peer_cycle_cap = v;
+        }
+        if let Some(v) = proposal.conversion_ratio {
+            self.conversion_ratio = v;
+        }
+        if let Some(v) = proposal.join_cost_tokens {
+            self.join_cost_tokens = v;
+        }
+        self
+    }
+
+    fn one_token(&self) -> u64 {
+        10u64.pow(u32::from(self.decimals))
+    }
+}
+
+/// An `UpdateParams` under evaluation, as JSON. Omitted fields stay unchanged, and
+/// `join_cost_tokens` is in base units, exactly as the instruction takes them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Proposal {
+    /// Label in reports; the file name when empty
+    pub name: String,
+    pub cycle_secs: Option<u64>,
+    pub max_points_per_cycle: Option<u32>,
+    pub per_peer_cycle_cap: Option<i32>,
+    pub conversion_ratio: Option<u32>,
+    pub join_cost_tokens: Option<u64>,
+}
+
+impl Proposal {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut proposal: Self = serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))?;
+        if proposal.name.is_empty() {
+            proposal.name = path.file_stem().map_or_else(
+                || path.display().to_string(),
+                |s| s.to_string_lossy().into(),
+            );
+        }
+        Ok(proposal)
+    }
+}
+
+impl From<&Proposal> for UpdateParams {
+    fn from(proposal: &Proposal) -> Self {
+        Self {
+            cycle_secs: proposal.cycle_secs,
+            max_points_per_cycle: proposal.max_points_per_cycle,
+            per_peer_cycle_cap: proposal.per_peer_cycle_cap,
+            conversion_ratio: proposal.conversion_ratio,
+            join_cost_tokens: proposal.join_cost_tokens,
+            ..Self::default()
+        }
+    }
+}
+
+/// How the network behaves. Missing fields take the defaults, so a scenario file only
+/// lists what it changes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub seed: u64,
+    /// Simulated time; the number of cycles follows from `cycle_secs`
+    pub days: u64,
+    /// Members at the start
+    pub initial_members: u64,
+    /// Minted by `initialize`, in whole tokens
+    pub airdrop_whole_tokens: u64,
+    /// Mean new members per day (Poisson)
+    pub joins_per_day: f64,
+    /// Chance an active member leaves in a cycle
+    pub churn_per_cycle: f64,
+    /// Chance an active member earns karma in a cycle
+    pub active_share: f64,
+    /// Smallest karma an earning member collects in a cycle
+    pub karma_scale: f64,
+    /// Pareto shape of karma per earning member; lower is more skewed
+    pub karma_shape: f64,
+    /// Chance an active member is penalized in a cycle instead of earning
+    pub penalized_share: f64,
+    /// Points a penalty takes, before the per-peer cap
+    pub penalty_points: u64,
+    /// Chance a member with convertible points converts in a cycle
+    pub convert_share: f64,
+    /// Fraction of their convertible tokens a converting member takes
+    pub convert_fraction: f64,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            days: 365,
+            initial_members: 100,
+            airdrop_whole_tokens: 10_000,
+            joins_per_day: 5.0,
+            churn_per_cycle: 0.01,
+            active_share: 0.6,
+            karma_scale: 5.0,
+            karma_shape: 1.5,
+            penalized_share: 0.02,
+            penalty_points: 20,
+            convert_share: 0.3,
+            convert_fraction: 1.0,
+        }
+    }
+}
+
+impl Scenario {
+    pub fn read(path: &Path) -> Result<Self> {
+        let scenario: Self = serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (name, p) in [
+            ("churn_per_cycle", self.churn_per_cycle),
+            ("active_share", self.active_share),
+            ("penalized_share", self.penalized_share),
+            ("convert_share", self.convert_share),
+            ("convert_fraction", self.convert_fraction),
+        ] {
+            ensure!((0.0..=1.0).contains(&p), "{name} must be within 0..=1");
+        }
+        ensure!(
+            self.active_share + self.penalized_share <= 1.0,
+            "active_share + penalized_share must not exceed 1"
+        );
+        ensure!(
+            self.joins_per_day >= 0.0,
+            "joins_per_day must not be negative"
+        );
+        ensure!(
+            self.karma_scale > 0.0 && self.karma_shape > 0.0,
+            "karma_scale and karma_shape must be positive"
+        );
+        Ok(())
+    }
+}
+
+/// One simulated cycle, after its claims and conversions. Token amounts are base units.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CycleReport {
+    /// The projection this cycle belongs to
+    pub variant: String,
+    pub cycle: u64,
+    /// Elapsed time at the end of the cycle
+    pub day: f64,
+    pub members: u64,
+    pub joins: u64,
+    pub churned: u64,
+    /// Positive deltas claimed this cycle
+    pub points_earned: u64,
+    /// Negative deltas claimed this cycle, as a positive number
+    pub points_penalized: u64,
+    /// Peers clamped to `per_peer_cycle_cap`
+    pub capped: u64,
+    /// Positive total before scaling to `max_points_per_cycle`, when it was over
+    pub scaled_from: Option<u64>,
+    pub minted: u64,
+    pub join_fees: u64,
+    pub supply: u64,
+    pub treasury: u64,
+    /// Positive ledger balances not yet converted
+    pub points_outstanding: u64,
+}
+
+/// The cycles of one run, in order.
+#[derive(Clone, Debug)]
+pub struct Projection {
+    pub name: String,
+    pub config: Config,
+    pub cycles: Vec<CycleReport>,
+}
+
+#[derive(Clone, Copy)]
+struct Member {
+    points: i64,
+    active: bool,
+}
+
+/// Runs `scenario` under `config`.
+pub fn simulate(name: &str, config: &Config, scenario: &Scenario) -> Result<Projection> {
+    scenario.validate()?;
+    ensure!(config.cycle_secs > 0, "cycle_secs must be positive");
+    ensure!(
+        config.conversion_ratio > 0,
+        "conversion_ratio must be positive"
+    );
+
+    let mut rng = StdRng::seed_from_u64(scenario.seed);
+    let cycle_days = config.cycle_secs as f64 / SECS_PER_DAY;
+    let cycles = (scenario.days as f64 / cycle_days).floor() as u64;
+    let joins = Poisson::new(scenario.joins_per_day * cycle_days).ok();
+    let karma =
+        Pareto::new(scenario.karma_scale, scenario.karma_shape).context("karma distribution")?;
+    let ratio = i64::from(config.conversion_ratio);
+
+    let mut members = vec![
+        Member {
+            points: 0,
+            active: true,
+        };
+        scenario.initial_members as usize
+    ];
+    let mut supply = scenario.airdrop_whole_tokens * config.one_token();
+    let mut treasury = 0u64;
+    let mut reports = Vec::with_capacity(cycles as usize);
+
+    for cycle in 0..cycles {
+        let mut report = CycleReport {
+            variant: name.to_string(),
+            cycle,
+            day: (cycle + 1) as f64 * cycle_days,
+            ..CycleReport::default()
+        };
+
+        // Joiners are assumed to hold the join cost already; it moves into the treasury
+        report.joins = joins.map_or(0, |d| d.sample(&mut rng) as u64);
+        report.join_fees = report.joins * config.join_cost_tokens;
+        treasury += report.join_fees;
+        members.extend((0..report.joins).map(|_| Member {
+            points: 0,
+            active: true,
+        }));
+
+        for member in members.iter_mut().filter(|m| m.active) {
+            if rng.gen_bool(scenario.churn_per_cycle) {
+                member.active = false;
+                report.churned += 1;
+            }
+        }
+
+        let mut deltas: Vec<(usize, i64)> = Vec::new();
+        for (i, _) in members.iter().enumerate().filter(|(_, m)| m.active) {
+            let roll: f64 = rng.gen();
+            if roll < scenario.penalized_share {
+                deltas.push((i, -(scenario.penalty_points as i64)));
+            } else if roll < scenario.penalized_share + scenario.active_share {
+                deltas.push((i, karma.sample(&mut rng).floor() as i64));
+            }
+        }
+        apply_caps(&mut deltas, config, &mut report);
+        for &(i, delta) in &deltas {
+            // As claim_karma: a penalty can leave a ledger below zero
+            members[i].points += delta;
+            if delta > 0 {
+                report.points_earned += delta as u64;
+            } else {
+                report.points_penalized += delta.unsigned_abs();
+            }
+        }
+
+        // Conversions are open to former members too; the program doesn't check
+        for member in &mut members {
+            let convertible = member.points / ratio;
+            if convertible <= 0 || !rng.gen_bool(scenario.convert_share) {
+                continue;
+            }
+            let tokens = ((convertible as f64 * scenario.convert_fraction).floor() as i64).max(1);
+            member.points -= tokens * ratio;
+            report.minted += tokens as u64 * config.one_token();
+        }
+        supply += report.minted;
+
+        report.members = members.iter().filter(|m| m.active).count() as u64;
+        report.supply = supply;
+        report.treasury = treasury;
+        report.points_outstanding = members.iter().map(|m| m.points.max(0) as u64).sum();
+        reports.push(report);
+    }
+
+    Ok(Projection {
+        name: name.to_string(),
+        config: *config,
+        cycles: reports,
+    })
+}
+
+/// The caps `seka-aggregation` applies before building a cycle's tree: clamp each delta
+/// to the per-peer cap, then scale positive deltas down to `max_points_per_cycle`.
+fn apply_caps(deltas: &mut [(usize, i64)], config: &Config, report: &mut CycleReport) {
+    let cap = i64::from(config.per_peer_cycle_cap.max(0));
+    for (_, delta) in deltas.iter_mut() {
+        if delta.abs() > cap {
+            report.capped += 1;
+        }
+        *delta = (*delta).clamp(-cap, cap);
+    }
+
+    let total: u64 = deltas.iter().map(|&(_, d)| d.max(0) as u64).sum();
+    let max = u64::from(config.max_points_per_cycle);
+    if total > max {
+        report.scaled_from = Some(total);
+        for (_, delta) in deltas.iter_mut().filter(|(_, d)| *d > 0) {
+            *delta = (*delta as u128 * max as u128 / total as u128) as i64;
+        }
+    }
+}
+
+/// Totals over a projection. Token amounts are base units.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub cycles: u64,
+    pub members: u64,
+    pub supply: u64,
+    pub minted: u64,
+    pub treasury: u64,
+    pub points_outstanding: u64,
+    /// Cycles whose claims were scaled down to `max_points_per_cycle`
+    pub scaled_cycles: u64,
+    /// Peer-cycles clamped to `per_peer_cycle_cap`
+    pub capped: u64,
+}
+
+impl Projection {
+    pub fn summary(&self) -> Summary {
+        let last = self.cycles.last().cloned().unwrap_or_default();
+        Summary {
+            cycles: self.cycles.len() as u64,
+            members: last.members,
+            supply: last.supply,
+            minted: self.cycles.iter().map(|c| c.minted).sum(),
+            treasury: last.treasury,
+            points_outstanding: last.points_outstanding,
+            scaled_cycles: self
+                .cycles
+                .iter()
+                .filter(|c| c.scaled_from.is_some())
+                .count() as u64,
+            capped: self.cycles.iter().map(|c| c.capped).sum(),
+        }
+    }
+
+    /// Whole tokens, for display.
+    pub fn tokens(&self, base_units: u64) -> f64 {
+        base_units as f64 / self.config.one_token() as f64
+    }
+}
+
+/// Side-by-side totals of `projections`, each against the first.
+pub struct Comparison<'a>(pub &'a [Projection]);
+
+impl fmt::Display for Comparison<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(base) = self.0.first() else {
+            return Ok(());
+        };
+        let base_summary = base.summary();
+        let change = |value: f64, base: f64| {
+            if base == 0.0 {
+                String::new()
+            } else {
+                format!("{:+.1}%", (value / base - 1.0) * 100.0)
+            }
+        };
+        writeln!(
+            f,
+            "{:<20} {:>7} {:>8} {:>16} {:>9} {:>16} {:>9} {:>12} {:>7} {:>7}",
+            "variant",
+            "cycles",
+            "members",
+            "supply",
+            "change",
+            "treasury",
+            "change",
+            "points",
+            "scaled",
+            "capped"
+        )?;
+        for projection in self.0 {
+            let s = projection.summary();
+            let supply = projection.tokens(s.supply);
+            let treasury = projection.tokens(s.treasury);
+            writeln!(
+                f,
+                "{:<20} {:>7} {:>8} {:>16.2} {:>9} {:>16.2} {:>9} {:>12} {:>7} {:>7}",
+                projection.name,
+                s.cycles,
+                s.members,
+                supply,
+                change(supply, base.tokens(base_summary.supply)),
+                treasury,
+                change(treasury, base.tokens(base_summary.treasury)),
+                s.points_outstanding,
+                s.scaled_cycles,
+                s.capped,
+            )?;
+        }
+        Ok(())
+    }
+}
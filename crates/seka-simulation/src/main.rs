@@ -0,0 +1,85 @@
+//! seka-simulate: projects supply and treasury under the current config and under each
+//! proposed `UpdateParams`, with the same simulated network for all of them.
+
+use anyhow::Result;
+use clap::Parser;
+use seka_simulation::{simulate, Comparison, Config, Proposal, Scenario};
+use sentinelkarma_sdk::{fetch_account, seka};
+use solana_client::rpc_client::RpcClient;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Project seka supply and treasury under proposed config changes")]
+struct Cli {
+    /// Start from the live GlobalConfig at this RPC instead of the program defaults
+    #[arg(long, env = "RPC_URL")]
+    rpc_url: Option<String>,
+
+    /// JSON network model; see scenario.example.json
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+
+    /// JSON UpdateParams to evaluate; repeat to compare several
+    #[arg(long = "proposal")]
+    proposals: Vec<PathBuf>,
+
+    /// Overrides the scenario's seed
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Per-cycle rows of every run
+    #[arg(long)]
+    csv: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut scenario = match &cli.scenario {
+        Some(path) => Scenario::read(path)?,
+        None => Scenario::default(),
+    };
+    if let Some(seed) = cli.seed {
+        scenario.seed = seed;
+    }
+    let (base_name, config) = match &cli.rpc_url {
+        Some(url) => {
+            let rpc = RpcClient::new(url.clone());
+            let config: seka::GlobalConfig = fetch_account(&rpc, &seka::config_pda())?;
+            ("current", Config::from(&config))
+        }
+        None => ("defaults", Config::default()),
+    };
+
+    let mut projections = vec![simulate(base_name, &config, &scenario)?];
+    for path in &cli.proposals {
+        let proposal = Proposal::read(path)?;
+        println!(
+            "{}: {:?}",
+            proposal.name,
+            seka::UpdateParams::from(&proposal)
+        );
+        projections.push(simulate(
+            &proposal.name,
+            &config.apply(&proposal),
+            &scenario,
+        )?);
+    }
+
+    println!(
+        "{} days, seed {}; supply and treasury in whole SEKA",
+        scenario.days, scenario.seed
+    );
+    print!("{}", Comparison(&projections));
+
+    if let Some(path) = &cli.csv {
+        let mut out = csv::Writer::from_path(path)?;
+        for projection in &projections {
+            for cycle in &projection.cycles {
+                out.serialize(cycle)?;
+            }
+        }
+        out.flush()?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
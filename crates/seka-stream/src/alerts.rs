@@ -0,0 +1,309 @@
+//! Alert rules over the frame stream, sent to webhooks and PagerDuty.
+//!
+//! Rules are read from `--alerts <file>` (see `alerts.example.json`) and checked against
+//! every frame, whether or not any client is subscribed:
+//!
+//! - `conversion_rate`: more than `max_per_hour` `PointsConverted` events, or more than
+//!   `max_tokens_per_hour` tokens minted, in the last hour. Alerts once when the limit
+//!   is crossed and again when the rate is back under it.
+//! - `claim_near_cap`: a `KarmaClaimed` whose `delta_points` reaches `fraction` of the
+//!   config's `per_peer_cycle_cap`.
+//! - `root_off_schedule`: a `CycleRootSet` before its cycle has ended, or more than
+//!   `late_secs` after. The program does not check either.
+//!
+//! Caps and the schedule come from the seka `GlobalConfig`, re-read every ten minutes.
+
+use crate::frame::Frame;
+use anyhow::{Context, Result};
+use sentinelkarma_sdk::{decode_account, seka};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+const CONFIG_REFRESH: Duration = Duration::from_secs(600);
+const PAGERDUTY_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const HOUR: i64 = 3600;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Error,
+    Critical,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Rule {
+    ConversionRate {
+        max_per_hour: Option<usize>,
+        max_tokens_per_hour: Option<u64>,
+        #[serde(default)]
+        severity: Severity,
+    },
+    ClaimNearCap {
+        fraction: f64,
+        #[serde(default)]
+        severity: Severity,
+    },
+    RootOffSchedule {
+        late_secs: i64,
+        #[serde(default)]
+        severity: Severity,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    pub rules: Vec<Rule>,
+}
+
+impl Rules {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+}
+
+/// A fired rule. `key` identifies the incident, so a resolve matches its trigger and
+/// PagerDuty folds repeats together.
+#[derive(Debug)]
+pub struct Alert {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub key: String,
+    pub resolved: bool,
+    pub text: String,
+    pub frame: Option<Arc<Frame>>,
+}
+
+/// Where alerts go; both are optional and a failed send is only logged.
+pub struct Sinks {
+    pub webhook: Option<String>,
+    pub pagerduty_routing_key: Option<String>,
+    pub http: reqwest::Client,
+}
+
+/// The schedule and caps the rules check against.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub start_ts: i64,
+    pub cycle_secs: u64,
+    pub per_peer_cycle_cap: i32,
+}
+
+impl From<&seka::GlobalConfig> for Limits {
+    fn from(config: &seka::GlobalConfig) -> Self {
+        Self {
+            start_ts: config.start_ts,
+            cycle_secs: config.cycle_secs,
+            per_peer_cycle_cap: config.per_peer_cycle_cap,
+        }
+    }
+}
+
+/// Rule state: the last hour of conversions and whether the rate alert is open.
+pub struct Engine {
+    rules: Vec<Rule>,
+    /// `(block_time, tokens_minted)`, oldest first
+    conversions: VecDeque<(i64, u64)>,
+    rate_alert: bool,
+}
+
+impl Engine {
+    pub fn new(rules: Rules) -> Self {
+        Self { rules: rules.rules, conversions: VecDeque::new(), rate_alert: false }
+    }
+
+    pub fn check(&mut self, frame: &Arc<Frame>, limits: &Limits) -> Vec<Alert> {
+        if frame.kind != "event" || frame.program != "seka" {
+            return Vec::new();
+        }
+        let now = frame.block_time.unwrap_or_else(unix_now);
+        if frame.name == "PointsConverted" {
+            let tokens = number(&frame.data, "tokens_minted").unwrap_or(0);
+            self.conversions.push_back((now, tokens as u64));
+        }
+        while self.conversions.front().is_some_and(|&(time, _)| time <= now - HOUR) {
+            self.conversions.pop_front();
+        }
+
+        let mut alerts = Vec::new();
+        for rule in &self.rules {
+            match *rule {
+                Rule::ConversionRate { max_per_hour, max_tokens_per_hour, severity } => {
+                    let count = self.conversions.len();
+                    let tokens: u64 = self.conversions.iter().map(|&(_, t)| t).sum();
+                    let over = max_per_hour.is_some_and(|max| count > max)
+                        || max_tokens_per_hour.is_some_and(|max| tokens > max);
+                    if over == self.rate_alert {
+                        continue;
+                    }
+                    self.rate_alert = over;
+                    let text = if over {
+                        format!("{count} conversions minting {tokens} base units in the last hour")
+                    } else {
+                        format!("conversion rate back to normal: {count} in the last hour")
+                    };
+                    alerts.push(Alert {
+                        rule: "conversion_rate",
+                        severity,
+                        key: "seka-conversion-rate".into(),
+                        resolved: !over,
+                        text,
+                        frame: over.then(|| frame.clone()),
+                    });
+                }
+                Rule::ClaimNearCap { fraction, severity } if frame.name == "KarmaClaimed" => {
+                    let Some(delta) = number(&frame.data, "delta_points") else { continue };
+                    let threshold = fraction * f64::from(limits.per_peer_cycle_cap);
+                    if (delta as f64) < threshold {
+                        continue;
+                    }
+                    let owner = frame.data["owner"].as_str().unwrap_or("?");
+                    let cycle = number(&frame.data, "cycle_index").unwrap_or(-1);
+                    alerts.push(Alert {
+                        rule: "claim_near_cap",
+                        severity,
+                        key: format!("seka-claim-{}", frame.signature),
+                        resolved: false,
+                        text: format!(
+                            "{owner} claimed {delta} points in cycle {cycle}; the per-peer cap is {}",
+                            limits.per_peer_cycle_cap
+                        ),
+                        frame: Some(frame.clone()),
+                    });
+                }
+                Rule::RootOffSchedule { late_secs, severity } if frame.name == "CycleRootSet" => {
+                    let Some(cycle) = number(&frame.data, "cycle_index") else { continue };
+                    let end = limits.start_ts + (cycle as i64 + 1) * limits.cycle_secs as i64;
+                    let text = if now < end {
+                        format!("root of cycle {cycle} published {}s before the cycle ends", end - now)
+                    } else if now > end + late_secs {
+                        format!("root of cycle {cycle} published {}s after the cycle ended", now - end)
+                    } else {
+                        continue;
+                    };
+                    alerts.push(Alert {
+                        rule: "root_off_schedule",
+                        severity,
+                        key: format!("seka-root-{cycle}"),
+                        resolved: false,
+                        text,
+                        frame: Some(frame.clone()),
+                    });
+                }
+                Rule::ClaimNearCap { .. } | Rule::RootOffSchedule { .. } => {}
+            }
+        }
+        alerts
+    }
+}
+
+/// Checks every frame on `rx` against `engine`, sending alerts to `sinks`, until the
+/// channel closes.
+pub async fn run(
+    rpc: Arc<RpcClient>,
+    mut engine: Engine,
+    sinks: Sinks,
+    mut rx: broadcast::Receiver<Arc<Frame>>,
+) {
+    let mut limits = None;
+    let mut fetched = Instant::now();
+    loop {
+        let frame = match rx.recv().await {
+            Ok(frame) => frame,
+            Err(RecvError::Lagged(missed)) => {
+                eprintln!("alerts: skipped {missed} frames");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if limits.is_none() || fetched.elapsed() > CONFIG_REFRESH {
+            match fetch_limits(&rpc).await {
+                Ok(fresh) => {
+                    limits = Some(fresh);
+                    fetched = Instant::now();
+                }
+                Err(e) => eprintln!("alerts: {e:#}"),
+            }
+        }
+        // Without the config there is nothing to check caps and schedule against
+        let Some(limits) = &limits else { continue };
+        for alert in engine.check(&frame, limits) {
+            println!("alert: [{}] {}", alert.rule, alert.text);
+            sinks.send(&alert).await;
+        }
+    }
+}
+
+async fn fetch_limits(rpc: &RpcClient) -> Result<Limits> {
+    let address = seka::config_pda();
+    let data = rpc
+        .get_account_data(&address)
+        .await
+        .with_context(|| format!("fetching seka config {address}"))?;
+    Ok(Limits::from(&decode_account::<seka::GlobalConfig>(&data)?))
+}
+
+impl Sinks {
+    async fn send(&self, alert: &Alert) {
+        let status = if alert.resolved { "resolved" } else { "triggered" };
+        let details = json!({
+            "rule": alert.rule,
+            "signature": alert.frame.as_ref().map(|f| &f.signature),
+            "slot": alert.frame.as_ref().map(|f| f.slot),
+            "data": alert.frame.as_ref().map(|f| &f.data),
+        });
+        if let Some(url) = &self.webhook {
+            let mut body = json!({ "text": alert.text, "status": status, "severity": alert.severity });
+            if let (Value::Object(body), Value::Object(details)) = (&mut body, details.clone()) {
+                body.extend(details);
+            }
+            self.post("webhook", url, &body).await;
+        }
+        if let Some(routing_key) = &self.pagerduty_routing_key {
+            let body = json!({
+                "routing_key": routing_key,
+                "event_action": if alert.resolved { "resolve" } else { "trigger" },
+                "dedup_key": alert.key,
+                "payload": {
+                    "summary": alert.text,
+                    "source": "seka-stream",
+                    "severity": alert.severity,
+                    "custom_details": details,
+                },
+            });
+            self.post("pagerduty", PAGERDUTY_URL, &body).await;
+        }
+    }
+
+    async fn post(&self, sink: &str, url: &str, body: &Value) {
+        let result = self.http.post(url).json(body).send().await.and_then(|r| r.error_for_status());
+        if let Err(e) = result {
+            eprintln!("alerts: {sink}: {e}");
+        }
+    }
+}
+
+/// `field` of frame data as a number, whether written as one or as a decimal string.
+fn number(data: &Value, field: &str) -> Option<i128> {
+    match &data[field] {
+        Value::Number(n) => n.as_i64().map(i128::from),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
@@ -0,0 +1,288 @@
+//! JSON frames and the mapping from program events to topics.
+//!
+//! Every frame carries `"v": SCHEMA_VERSION`. Bump it on any breaking change to the
+//! frame envelope or to a `data` object; adding fields is not breaking.
+//!
+//! In `data`, pubkeys are base58, byte arrays are hex, and 64/128-bit integers are
+//! decimal strings so JavaScript clients do not lose precision.
+
+use sentinelkarma_indexer::store::{EventRow, InstructionRow};
+use sentinelkarma_sdk::{decode_event, seka, sentinel};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topic {
+    /// Karma, tag, top-post, achievement and vesting claims; point conversions
+    Claims,
+    /// Cycle roots published and cycles finalized
+    Cycles,
+    /// Post creation, edits, moderation, marketplace, comments
+    Posts,
+    /// Post and comment likes
+    Likes,
+    /// Seka memberships joined and deactivated
+    Members,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 5] =
+        [Topic::Claims, Topic::Cycles, Topic::Posts, Topic::Likes, Topic::Members];
+}
+
+/// A decoded event or instruction published on one topic.
+#[derive(Clone, Debug, Serialize)]
+pub struct Frame {
+    pub v: u32,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub topic: Topic,
+    pub program: String,
+    pub name: String,
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub data: Value,
+}
+
+/// Messages clients send: `{"op": "subscribe", "topics": ["claims", "posts"]}`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum ClientMessage {
+    Subscribe { topics: Vec<Topic> },
+    Unsubscribe { topics: Vec<Topic> },
+}
+
+/// Control frames the server sends besides [`Frame`]s.
+pub fn control(kind: &str, body: Value) -> String {
+    let mut frame = json!({ "v": SCHEMA_VERSION, "type": kind });
+    if let (Value::Object(frame), Value::Object(body)) = (&mut frame, body) {
+        frame.extend(body);
+    }
+    frame.to_string()
+}
+
+pub fn event_frame(row: &EventRow) -> Option<Frame> {
+    let (topic, data) = match row.program.as_str() {
+        "seka" => seka_event(&row.name, &row.payload)?,
+        "sentinel" => sentinel_event(&row.name, &row.payload)?,
+        _ => return None,
+    };
+    Some(Frame {
+        v: SCHEMA_VERSION,
+        kind: "event",
+        topic,
+        program: row.program.clone(),
+        name: row.name.clone(),
+        signature: row.signature.clone(),
+        slot: row.slot,
+        block_time: row.block_time,
+        data,
+    })
+}
+
+/// Post mints and post likes emit no event, so they are published from the instruction.
+pub fn instruction_frame(row: &InstructionRow) -> Option<Frame> {
+    if row.program != "sentinel" {
+        return None;
+    }
+    let account = |i: usize| row.accounts.get(i).cloned();
+    let (topic, data) = match row.name.as_str() {
+        // `user, state, peer, nft_mint | asset, ..` in both contexts
+        "mint_nft" | "reveal_post" | "mint_core_post" => {
+            let asset = account(3)?;
+            let post = sentinel::post_pda(&Pubkey::from_str(&asset).ok()?);
+            (Topic::Posts, json!({ "author": account(0)?, "asset": asset, "post": post.to_string() }))
+        }
+        // `liker, state, like, post, ..`
+        "like_nft" => (Topic::Likes, json!({ "liker": account(0)?, "post": account(3)? })),
+        _ => return None,
+    };
+    Some(Frame {
+        v: SCHEMA_VERSION,
+        kind: "instruction",
+        topic,
+        program: row.program.clone(),
+        name: row.name.clone(),
+        signature: row.signature.clone(),
+        slot: row.slot,
+        block_time: row.block_time,
+        data,
+    })
+}
+
+// ============================================================================
+// Event data
+// ============================================================================
+
+trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+impl ToJson for Pubkey {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl<const N: usize> ToJson for [u8; N] {
+    fn to_json(&self) -> Value {
+        Value::String(hex::encode(self))
+    }
+}
+
+macro_rules! json_number {
+    ($($ty:ty),*) => {$(
+        impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                json!(self)
+            }
+        }
+    )*};
+}
+
+macro_rules! json_string {
+    ($($ty:ty),*) => {$(
+        impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                Value::String(self.to_string())
+            }
+        }
+    )*};
+}
+
+json_number!(u8, u16, u32, i32);
+json_string!(u64, i64, u128);
+
+/// Tag bytes as the zero-padded name they were created from.
+fn tag_name(tag: &[u8; sentinel::TAG_LEN]) -> Value {
+    let end = tag.iter().position(|&b| b == 0).unwrap_or(tag.len());
+    Value::String(String::from_utf8_lossy(&tag[..end]).into_owned())
+}
+
+/// Decodes `payload` as `$ty` and builds `{ field: value, .. }`; a field may name a
+/// converter with `field => fn`.
+macro_rules! fields {
+    ($payload:expr, $ty:ty { $($field:ident $(=> $conv:expr)?),* $(,)? }) => {
+        decode_event::<$ty>($payload).map(|ev| {
+            let mut data = serde_json::Map::new();
+            $(data.insert(stringify!($field).into(), fields!(@value ev.$field $(, $conv)?));)*
+            Value::Object(data)
+        })
+    };
+    (@value $value:expr) => { $value.to_json() };
+    (@value $value:expr, $conv:expr) => { $conv(&$value) };
+}
+
+fn seka_event(name: &str, payload: &[u8]) -> Option<(Topic, Value)> {
+    use seka::{CycleRootSet, Joined, KarmaClaimed, MembershipDeactivated, PointsConverted};
+    Some(match name {
+        "KarmaClaimed" => (
+            Topic::Claims,
+            fields!(payload, KarmaClaimed { owner, cycle_index, delta_points, new_points })?,
+        ),
+        "PointsConverted" => (
+            Topic::Claims,
+            fields!(payload, PointsConverted { owner, tokens_minted, points_spent })?,
+        ),
+        "CycleRootSet" => (
+            Topic::Cycles,
+            fields!(payload, CycleRootSet { cycle_index, merkle_root, total_points_declared })?,
+        ),
+        "Joined" => (Topic::Members, fields!(payload, Joined { member })?),
+        "MembershipDeactivated" => (
+            Topic::Members,
+            fields!(payload, MembershipDeactivated { member, actor })?,
+        ),
+        _ => return None,
+    })
+}
+
+fn sentinel_event(name: &str, payload: &[u8]) -> Option<(Topic, Value)> {
+    use sentinel::{
+        AchievementClaimed, CommentCreated, CommentLiked, CycleFinalized, ListingCancelled,
+        PostClosed, PostDeleted, PostListed, PostOwnerSynced, PostRemoved, PostSold,
+        PostUpdated, TagRewardClaimed, TopPostBonusClaimed, VestedSupplyClaimed,
+    };
+    Some(match name {
+        "TagRewardClaimed" => (
+            Topic::Claims,
+            fields!(payload, TagRewardClaimed { tag => tag_name, user, cycle_index, karma, reward })?,
+        ),
+        "TopPostBonusClaimed" => (
+            Topic::Claims,
+            fields!(payload, TopPostBonusClaimed { cycle_index, post, owner, amount })?,
+        ),
+        "AchievementClaimed" => (
+            Topic::Claims,
+            fields!(payload, AchievementClaimed { id, owner, progress, karma_bonus })?,
+        ),
+        "VestedSupplyClaimed" => (
+            Topic::Claims,
+            fields!(payload, VestedSupplyClaimed { beneficiary, amount, total_claimed })?,
+        ),
+        "CycleFinalized" => (
+            Topic::Cycles,
+            fields!(payload, CycleFinalized {
+                cycle_index,
+                total_karma,
+                total_minted,
+                peers_rewarded,
+                top_recipient,
+                top_reward,
+            })?,
+        ),
+        "PostUpdated" => (
+            Topic::Posts,
+            fields!(payload, PostUpdated { post, previous_hash, hash, edit_count })?,
+        ),
+        "PostRemoved" => (Topic::Posts, fields!(payload, PostRemoved { post, moderator })?),
+        "PostClosed" => (Topic::Posts, fields!(payload, PostClosed { post, closed_by })?),
+        "PostDeleted" => (
+            Topic::Posts,
+            fields!(payload, PostDeleted { post, owner, karma_removed })?,
+        ),
+        "PostOwnerSynced" => (
+            Topic::Posts,
+            fields!(payload, PostOwnerSynced { post, previous_owner, owner })?,
+        ),
+        "PostListed" => (Topic::Posts, fields!(payload, PostListed { post, seller, price })?),
+        "ListingCancelled" => (
+            Topic::Posts,
+            fields!(payload, ListingCancelled { post, seller })?,
+        ),
+        "PostSold" => (
+            Topic::Posts,
+            fields!(payload, PostSold {
+                post,
+                seller,
+                buyer,
+                creator,
+                price,
+                seller_proceeds,
+                royalty,
+                treasury_fee,
+            })?,
+        ),
+        "CommentCreated" => (
+            Topic::Posts,
+            fields!(payload, CommentCreated { comment, post, parent, author, hash })?,
+        ),
+        "CommentLiked" => (
+            Topic::Likes,
+            fields!(payload, CommentLiked { comment, liker, author, karma })?,
+        ),
+        _ => return None,
+    })
+}
@@ -0,0 +1,81 @@
+//! `seka-stream`: realtime seka and sentinel events over WebSocket, by topic, with
+//! optional alert rules over the same stream (see `alerts.rs`).
+
+mod alerts;
+mod frame;
+mod server;
+mod source;
+
+use anyhow::Result;
+use clap::Parser;
+use sentinelkarma_indexer::Program;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+#[derive(Parser)]
+#[command(about = "Re-broadcast decoded seka and sentinel events to WebSocket subscribers")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// RPC pubsub endpoint; derived from --rpc-url when omitted
+    #[arg(long, env = "WS_URL")]
+    ws_url: Option<String>,
+
+    #[arg(long, env = "LISTEN", default_value = "127.0.0.1:8090")]
+    listen: SocketAddr,
+
+    /// Frames buffered per client before it is reported as lagged
+    #[arg(long, default_value_t = 1024)]
+    buffer: usize,
+
+    /// Alert rules to check every frame against; see alerts.example.json
+    #[arg(long, env = "ALERTS")]
+    alerts: Option<PathBuf>,
+
+    /// URL to POST `{"text", "status", "severity", "rule", ..}` to when a rule fires
+    #[arg(long, env = "ALERT_WEBHOOK", requires = "alerts")]
+    alert_webhook: Option<String>,
+
+    /// PagerDuty Events v2 routing key to trigger incidents with
+    #[arg(long, env = "PAGERDUTY_ROUTING_KEY", requires = "alerts")]
+    pagerduty_routing_key: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let ws_url = cli.ws_url.unwrap_or_else(|| ws_url_for(&cli.rpc_url));
+    let rpc = Arc::new(RpcClient::new_with_commitment(cli.rpc_url, CommitmentConfig::confirmed()));
+
+    let (tx, _) = broadcast::channel(cli.buffer);
+    if let Some(path) = &cli.alerts {
+        let engine = alerts::Engine::new(alerts::Rules::read(path)?);
+        let sinks = alerts::Sinks {
+            webhook: cli.alert_webhook,
+            pagerduty_routing_key: cli.pagerduty_routing_key,
+            http: reqwest::Client::new(),
+        };
+        tokio::spawn(alerts::run(rpc.clone(), engine, sinks, tx.subscribe()));
+    }
+    for program in Program::ALL {
+        tokio::spawn(source::run(ws_url.clone(), rpc.clone(), program, tx.clone()));
+    }
+
+    let listener = tokio::net::TcpListener::bind(cli.listen).await?;
+    println!("listening on ws://{}/ws", cli.listen);
+    axum::serve(listener, server::router(tx)).await?;
+    Ok(())
+}
+
+/// `http(s)://host:8899` -> `ws(s)://host:8900`, the validator's default pubsub port.
+fn ws_url_for(rpc_url: &str) -> String {
+    let url = rpc_url
+        .replacen("https://", "wss://", 1)
+        .replacen("http://", "ws://", 1);
+    url.replace(":8899", ":8900")
+}
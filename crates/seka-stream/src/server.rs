@@ -0,0 +1,88 @@
+//! WebSocket endpoint: `GET /ws[?topics=claims,posts]`.
+//!
+//! On connect the server sends `{"type": "hello", "topics": [..]}` listing every topic,
+//! then only frames of the subscribed topics. Clients change their set with
+//! `{"op": "subscribe" | "unsubscribe", "topics": [..]}`, answered by
+//! `{"type": "subscribed", "topics": [..]}` with the resulting set. A client too slow
+//! to keep up gets `{"type": "lagged", "missed": n}` and continues from the newest frame.
+
+use crate::frame::{control, ClientMessage, Frame, Topic};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Deserialize)]
+struct Params {
+    topics: Option<String>,
+}
+
+pub fn router(tx: broadcast::Sender<Arc<Frame>>) -> Router {
+    Router::new()
+        .route("/ws", get(upgrade))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(tx)
+}
+
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    Query(params): Query<Params>,
+    State(tx): State<broadcast::Sender<Arc<Frame>>>,
+) -> Response {
+    let topics: HashSet<Topic> = params
+        .topics
+        .iter()
+        .flat_map(|list| list.split(','))
+        .filter_map(|name| serde_json::from_value(json!(name.trim())).ok())
+        .collect();
+    ws.on_upgrade(move |socket| session(socket, tx.subscribe(), topics))
+}
+
+async fn session(mut socket: WebSocket, mut rx: broadcast::Receiver<Arc<Frame>>, mut topics: HashSet<Topic>) {
+    let hello = control("hello", json!({ "topics": Topic::ALL, "subscribed": sorted(&topics) }));
+    if socket.send(Message::Text(hello.into())).await.is_err() {
+        return;
+    }
+    loop {
+        let reply = tokio::select! {
+            frame = rx.recv() => match frame {
+                Ok(frame) if topics.contains(&frame.topic) => {
+                    serde_json::to_string(&*frame).expect("frames serialize")
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => control("lagged", json!({ "missed": missed })),
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(ClientMessage::Subscribe { topics: add }) => {
+                        topics.extend(add);
+                        control("subscribed", json!({ "topics": sorted(&topics) }))
+                    }
+                    Ok(ClientMessage::Unsubscribe { topics: remove }) => {
+                        topics.retain(|topic| !remove.contains(topic));
+                        control("subscribed", json!({ "topics": sorted(&topics) }))
+                    }
+                    Err(e) => control("error", json!({ "message": e.to_string() })),
+                },
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                // Pings are answered by axum; binary frames are ignored
+                Some(Ok(_)) => continue,
+            },
+        };
+        if socket.send(Message::Text(reply.into())).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn sorted(topics: &HashSet<Topic>) -> Vec<Topic> {
+    Topic::ALL.into_iter().filter(|topic| topics.contains(topic)).collect()
+}
@@ -0,0 +1,94 @@
+//! Follows both programs over `logsSubscribe` and decodes each landed transaction.
+//!
+//! Logs alone only carry `emit!` events, so every notification is followed by a
+//! `getTransaction` to also see sentinel's `emit_cpi!` events and instruction data.
+
+use crate::frame::{event_frame, instruction_frame, Frame};
+use anyhow::Result;
+use futures_util::StreamExt;
+use sentinelkarma_indexer::ingest::rows_for;
+use sentinelkarma_indexer::Program;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+const FETCH_ATTEMPTS: usize = 10;
+const FETCH_DELAY: Duration = Duration::from_millis(400);
+
+/// Publishes frames of `program` to `tx` forever, reconnecting on errors.
+pub async fn run(ws_url: String, rpc: Arc<RpcClient>, program: Program, tx: broadcast::Sender<Arc<Frame>>) {
+    loop {
+        if let Err(e) = follow(&ws_url, &rpc, program, &tx).await {
+            eprintln!("{}: {e:#}; reconnecting", program.name());
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn follow(
+    ws_url: &str,
+    rpc: &RpcClient,
+    program: Program,
+    tx: &broadcast::Sender<Arc<Frame>>,
+) -> Result<()> {
+    let pubsub = PubsubClient::new(ws_url).await?;
+    let (mut notifications, _unsubscribe) = pubsub
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![program.id().to_string()]),
+            RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) },
+        )
+        .await?;
+    println!("{}: subscribed", program.name());
+
+    while let Some(notification) = notifications.next().await {
+        let logs = notification.value;
+        if logs.err.is_some() {
+            continue;
+        }
+        for frame in frames(rpc, program, &logs.signature).await? {
+            // No receivers is fine; frames are not buffered for future clients
+            let _ = tx.send(Arc::new(frame));
+        }
+    }
+    anyhow::bail!("subscription closed")
+}
+
+async fn frames(rpc: &RpcClient, program: Program, signature: &str) -> Result<Vec<Frame>> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let parsed = Signature::from_str(signature)?;
+
+    // The notification can race the node's transaction status index
+    let mut attempt = 0;
+    let tx = loop {
+        match rpc.get_transaction_with_config(&parsed, config).await {
+            Ok(tx) => break tx,
+            Err(_) if attempt + 1 < FETCH_ATTEMPTS => {
+                attempt += 1;
+                tokio::time::sleep(FETCH_DELAY).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let rows = rows_for(program, signature, &tx);
+    Ok(rows
+        .instructions
+        .iter()
+        .filter_map(instruction_frame)
+        .chain(rows.events.iter().filter_map(event_frame))
+        .collect())
+}
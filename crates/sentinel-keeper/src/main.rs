@@ -0,0 +1,393 @@
+//! sentinel-keeper: closes each sentinel cycle when it is due and settles every active
+//! peer's karma, which otherwise needs an operator every `cycle_seconds`.
+//!
+//! `finalize_cycle` / `crank_finalize_cycle` close the cycle in one call (the first call
+//! advances `cycle_index`, and rewards are shares of that call's total), so they are
+//! never split. The per-peer work that follows is what gets chunked:
+//!
+//! - `crank` mode (default, keyless): `crank_finalize_cycle`, then `crank_reset_karma`
+//!   per peer, which pays the peer's share and resets its karma. Cycles only close this
+//!   way once the authority has set `crank_finalize`.
+//! - `authority` mode: `finalize_cycle` with every peer's karma and reward ATA in a single
+//!   transaction (small networks only; about a dozen peers fit), then `reset_karma` per
+//!   peer. Needs the single `State.authority`, not a committee.
+//!
+//! Batches of `--batch` peers are sent per transaction; a batch that fails (too large,
+//! over the compute limit, or one bad peer) is halved until the failing peer is
+//! isolated, reported and skipped.
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use sentinelkarma_core::seka::seeds::{CONFIG_SEED, MINT_AUTHORITY_SEED};
+use sentinelkarma_sdk::anchor_lang::Discriminator;
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::decode_zero_copy;
+use sentinelkarma_sdk::sentinel::{self, accounts, instruction, PeerState, State};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::{system_program, sysvar};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Crank,
+    Authority,
+}
+
+#[derive(Parser)]
+#[command(about = "Finalize sentinel cycles and settle peer karma on schedule")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// Fee payer; also pays for cycle records and missing reward ATAs
+    #[arg(long, env = "KEEPER_KEYPAIR")]
+    keypair: PathBuf,
+
+    #[arg(long, value_enum, default_value = "crank")]
+    mode: Mode,
+
+    /// `State.authority` keypair for `authority` mode; defaults to --keypair
+    #[arg(long)]
+    authority: Option<PathBuf>,
+
+    /// Peers settled per transaction
+    #[arg(long, default_value_t = 6)]
+    batch: usize,
+
+    /// Longest sleep between checks, in seconds
+    #[arg(long, default_value_t = 30)]
+    poll_secs: u64,
+
+    /// Settle pending peers, close the cycle if it is due, settle again, and exit
+    #[arg(long)]
+    once: bool,
+}
+
+struct Keeper {
+    client: Client,
+    payer: Keypair,
+    authority: Option<Keypair>,
+    mode: Mode,
+    batch: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Peer {
+    address: Pubkey,
+    state: PeerState,
+}
+
+/// Accounts shared by every settlement instruction of one pass.
+struct MintAccounts {
+    mint: Pubkey,
+    token_program: Pubkey,
+    /// `(config, mint_authority, program)` in shared-mint mode
+    seka: Option<(Pubkey, Pubkey, Pubkey)>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let read = |path: &PathBuf| {
+        read_keypair_file(path).map_err(|e| anyhow::anyhow!("reading {}: {e}", path.display()))
+    };
+    let payer = read(&cli.keypair)?;
+    let authority = match (cli.mode, &cli.authority) {
+        (Mode::Authority, Some(path)) => Some(read(path)?),
+        (Mode::Authority, None) => Some(read(&cli.keypair)?),
+        (Mode::Crank, _) => None,
+    };
+    let keeper = Keeper {
+        client: Client::new(&cli.rpc_url, ClientConfig::default()),
+        payer,
+        authority,
+        mode: cli.mode,
+        batch: cli.batch.max(1),
+    };
+    let poll = Duration::from_secs(cli.poll_secs.max(1));
+
+    // Peers left unsettled by an earlier run go first
+    let mut settled_cycle = None;
+    loop {
+        match keeper.tick(&mut settled_cycle).await {
+            // Closed a cycle; settle it right away
+            Ok(None) => continue,
+            Ok(Some(_)) if cli.once => return Ok(()),
+            Ok(Some(wait)) => tokio::time::sleep(wait.min(poll)).await,
+            Err(e) if cli.once => return Err(e),
+            Err(e) => {
+                eprintln!("error: {e:#}");
+                tokio::time::sleep(poll).await;
+            }
+        }
+    }
+}
+
+impl Keeper {
+    /// One pass: settles peers of a newly closed cycle and closes the current one if it
+    /// is due. Returns `None` after closing a cycle, else how long to wait.
+    async fn tick(&self, settled_cycle: &mut Option<u64>) -> Result<Option<Duration>> {
+        let state: State = self.client.fetch_account(&sentinel::state_pda()).await?;
+        if *settled_cycle != Some(state.cycle_index) {
+            self.settle(&state).await?;
+            *settled_cycle = Some(state.cycle_index);
+        }
+
+        let due = state.cycle_start_ts + state.cycle_seconds;
+        let now = self.chain_time().await?;
+        if state.paused {
+            println!("program is paused; waiting");
+        } else if self.mode == Mode::Crank && !state.crank_finalize {
+            println!("crank_finalize is off; waiting for the authority to turn it on");
+        } else if now >= due {
+            self.finalize(&state).await?;
+            return Ok(None);
+        } else {
+            println!("cycle {} ends in {}s", state.cycle_index, due - now);
+        }
+        Ok(Some(Duration::from_secs((due - now).max(1) as u64)))
+    }
+
+    /// Cluster time, which is what the program compares `cycle_start_ts` against.
+    async fn chain_time(&self) -> Result<i64> {
+        let account = self.client.rpc().get_account(&sysvar::clock::ID).await?;
+        let clock: Clock = solana_sdk::account::from_account(&account).context("decoding clock sysvar")?;
+        Ok(clock.unix_timestamp)
+    }
+
+    async fn exists(&self, address: &Pubkey) -> Result<bool> {
+        let account = self
+            .client
+            .rpc()
+            .get_account_with_commitment(address, self.client.config().commitment)
+            .await?;
+        Ok(account.value.is_some())
+    }
+
+    async fn mint_accounts(&self, state: &State) -> Result<MintAccounts> {
+        let mint = self.client.rpc().get_account(&state.sentinel_mint).await?;
+        let seka = state.shared_mint.then(|| {
+            let program = state.seka_program;
+            let config = Pubkey::find_program_address(&[CONFIG_SEED], &program).0;
+            let mint_authority = Pubkey::find_program_address(&[MINT_AUTHORITY_SEED], &program).0;
+            (config, mint_authority, program)
+        });
+        Ok(MintAccounts { mint: state.sentinel_mint, token_program: mint.owner, seka })
+    }
+
+    /// Active peers, from every `PeerState` the program owns.
+    async fn peers(&self) -> Result<Vec<Peer>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                0,
+                PeerState::DISCRIMINATOR.to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.client.config().commitment),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = self
+            .client
+            .rpc()
+            .get_program_accounts_with_config(&sentinel::ID, config)
+            .await?;
+        let mut peers = Vec::with_capacity(accounts.len());
+        for (address, account) in accounts {
+            // Pre-migration peers are shorter than the current layout
+            match decode_zero_copy::<PeerState>(&account.data) {
+                Ok(state) if state.active != 0 => peers.push(Peer { address, state }),
+                Ok(_) => {}
+                Err(e) => eprintln!("skipping peer {address}: {e:#}; run migrate_accounts"),
+            }
+        }
+        Ok(peers)
+    }
+
+    // ------------------------------------------------------------------------
+    // Cycle close
+    // ------------------------------------------------------------------------
+
+    async fn finalize(&self, state: &State) -> Result<()> {
+        let cycle = state.cycle_index;
+        let top_posts = sentinel::top_posts_pda(cycle);
+        let top_posts = self.exists(&top_posts).await?.then_some(top_posts);
+
+        let ix = match self.mode {
+            Mode::Crank => sentinel::instruction(
+                accounts::CrankFinalizeCycle {
+                    payer: self.payer.pubkey(),
+                    state: sentinel::state_pda(),
+                    top_posts,
+                    cycle_record: sentinel::cycle_record_pda(cycle),
+                    system_program: system_program::ID,
+                    event_authority: sentinel::event_authority_pda(),
+                    program: sentinel::ID,
+                },
+                instruction::CrankFinalizeCycle {},
+            ),
+            Mode::Authority => self.finalize_with_authority(state, top_posts).await?,
+        };
+        let signature = self.send(&[ix]).await.with_context(|| format!("finalizing cycle {cycle}"))?;
+        println!("cycle {cycle} finalized: {signature}");
+        Ok(())
+    }
+
+    async fn finalize_with_authority(&self, state: &State, top_posts: Option<Pubkey>) -> Result<Instruction> {
+        let authority = self.authority.as_ref().expect("authority mode has an authority");
+        if state.threshold != 0 {
+            bail!("state is governed by a committee; use --mode crank or a governance proposal");
+        }
+        let mint = self.mint_accounts(state).await?;
+        let earners: Vec<Peer> = self
+            .peers()
+            .await?
+            .into_iter()
+            .filter(|p| p.state.karma > 0 && p.state.karma_cycle == state.cycle_index)
+            .collect();
+
+        let mut ix = sentinel::instruction(
+            accounts::FinalizeCycle {
+                authority: authority.pubkey(),
+                payer: self.payer.pubkey(),
+                state: sentinel::state_pda(),
+                sentinel_mint: mint.mint,
+                top_posts,
+                cycle_record: sentinel::cycle_record_pda(state.cycle_index),
+                token_program: mint.token_program,
+                system_program: system_program::ID,
+                seka_config: mint.seka.map(|s| s.0),
+                seka_mint_authority: mint.seka.map(|s| s.1),
+                seka_program: mint.seka.map(|s| s.2),
+                event_authority: sentinel::event_authority_pda(),
+                program: sentinel::ID,
+            },
+            instruction::FinalizeCycle {
+                peers: earners.iter().map(|p| p.state.user).collect(),
+                karmas: earners.iter().map(|p| p.state.karma).collect(),
+            },
+        );
+        // Reward ATAs must exist; finalize_cycle mints into them without creating them
+        for peer in &earners {
+            let ata = sentinel::associated_token_address(&peer.state.user, &mint.mint, &mint.token_program);
+            if !self.exists(&ata).await? {
+                bail!("{} has no reward account {ata}; create it or use --mode crank", peer.state.user);
+            }
+            ix.accounts.push(AccountMeta::new(ata, false));
+        }
+        println!("finalizing cycle {} for {} earning peers", state.cycle_index, earners.len());
+        Ok(ix)
+    }
+
+    // ------------------------------------------------------------------------
+    // Per-peer settlement
+    // ------------------------------------------------------------------------
+
+    /// Settles every active peer whose karma belongs to a closed cycle.
+    async fn settle(&self, state: &State) -> Result<()> {
+        let pending: Vec<Peer> = self
+            .peers()
+            .await?
+            .into_iter()
+            .filter(|p| p.state.karma_cycle < state.cycle_index)
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        println!("settling {} peers", pending.len());
+        let mint = self.mint_accounts(state).await?;
+
+        let mut settled = 0;
+        let mut failed = Vec::new();
+        let mut work: Vec<Vec<Peer>> = pending.chunks(self.batch).rev().map(<[Peer]>::to_vec).collect();
+        while let Some(chunk) = work.pop() {
+            let mut ixs = Vec::new();
+            for peer in &chunk {
+                ixs.extend(self.settle_ixs(peer, &mint).await?);
+            }
+            match self.send(&ixs).await {
+                Ok(_) => settled += chunk.len(),
+                Err(e) if chunk.len() == 1 => {
+                    eprintln!("settling {}: {e:#}", chunk[0].state.user);
+                    failed.push(chunk[0].state.user);
+                }
+                Err(_) => {
+                    let (a, b) = chunk.split_at(chunk.len() / 2);
+                    work.push(b.to_vec());
+                    work.push(a.to_vec());
+                }
+            }
+        }
+        println!("settled {settled} peers, {} failed", failed.len());
+        Ok(())
+    }
+
+    async fn settle_ixs(&self, peer: &Peer, mint: &MintAccounts) -> Result<Vec<Instruction>> {
+        let user = peer.state.user;
+        let karma_history = sentinel::karma_history_pda(&user);
+        match self.mode {
+            Mode::Authority => {
+                let authority = self.authority.as_ref().expect("authority mode has an authority");
+                Ok(vec![sentinel::instruction(
+                    accounts::ResetKarma {
+                        authority: authority.pubkey(),
+                        state: sentinel::state_pda(),
+                        peer: peer.address,
+                        karma_history,
+                    },
+                    instruction::ResetKarma {},
+                )])
+            }
+            Mode::Crank => {
+                let mut ixs = Vec::new();
+                let ata = sentinel::associated_token_address(&user, &mint.mint, &mint.token_program);
+                if !self.exists(&ata).await? {
+                    ixs.push(sentinel::create_associated_token_account_idempotent(
+                        &self.payer.pubkey(),
+                        &user,
+                        &mint.mint,
+                        &mint.token_program,
+                    ));
+                }
+                // Peers that never earned in a crank-closed cycle may predate cycle records
+                let record = sentinel::cycle_record_pda(peer.state.karma_cycle);
+                let cycle_record = self.exists(&record).await?.then_some(record);
+                ixs.push(sentinel::instruction(
+                    accounts::CrankResetKarma {
+                        state: sentinel::state_pda(),
+                        peer: peer.address,
+                        karma_history,
+                        cycle_record,
+                        sentinel_mint: mint.mint,
+                        peer_sentinel_ata: ata,
+                        token_program: mint.token_program,
+                        seka_config: mint.seka.map(|s| s.0),
+                        seka_mint_authority: mint.seka.map(|s| s.1),
+                        seka_program: mint.seka.map(|s| s.2),
+                    },
+                    instruction::CrankResetKarma {},
+                ));
+                Ok(ixs)
+            }
+        }
+    }
+
+    async fn send(&self, ixs: &[Instruction]) -> Result<solana_sdk::signature::Signature> {
+        let mut signers: Vec<&dyn Signer> = Vec::new();
+        if let Some(authority) = &self.authority {
+            if authority.pubkey() != self.payer.pubkey() {
+                signers.push(authority);
+            }
+        }
+        self.client.send(ixs, &self.payer, &signers).await
+    }
+}
@@ -0,0 +1,63 @@
+//! The moderator's `resolve_report` and `remove_post` instructions. `resolve_report`
+//! reads the report and sentinel state first for the accounts it has to pass.
+
+use anyhow::{Context, Result};
+use sentinelkarma_sdk::client::Client;
+use sentinelkarma_sdk::sentinel::{self, accounts, instruction, State};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+/// `resolve_report` for `report`, as it stands on-chain now.
+pub async fn resolve_report(
+    client: &Client,
+    moderator: Pubkey,
+    report: Pubkey,
+    uphold: bool,
+) -> Result<Instruction> {
+    let state: State = client.fetch_account(&sentinel::state_pda()).await?;
+    let record: sentinel::Report = client
+        .fetch_account(&report)
+        .await
+        .context("the report is not open")?;
+    let post: sentinel::Post = client.fetch_zero_copy(&record.post).await?;
+    let mint = state.sentinel_mint;
+    let token_program = client.rpc().get_account(&mint).await?.owner;
+    let ata = |wallet: &Pubkey| sentinel::associated_token_address(wallet, &mint, &token_program);
+    let treasury_vault = sentinel::treasury_vault_pda();
+    Ok(sentinel::instruction(
+        accounts::ResolveReport {
+            moderator,
+            state: sentinel::state_pda(),
+            post: record.post,
+            report,
+            report_escrow: sentinel::report_bond_pda(&report),
+            reporter: record.reporter,
+            reporter_sentinel_ata: ata(&record.reporter),
+            // Only read when upholding a report against a post whose bond is still locked
+            bond_escrow: (uphold && post.bond_escrow_open != 0)
+                .then(|| sentinel::bond_escrow_pda(&record.post)),
+            post_owner: post.owner,
+            treasury_vault,
+            treasury_sentinel_ata: ata(&treasury_vault),
+            sentinel_mint: mint,
+            token_program,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::ResolveReport { uphold },
+    ))
+}
+
+/// `remove_post`: takes `post` down without a report.
+pub fn remove_post(moderator: Pubkey, post: Pubkey) -> Instruction {
+    sentinel::instruction(
+        accounts::RemovePost {
+            moderator,
+            state: sentinel::state_pda(),
+            post,
+            event_authority: sentinel::event_authority_pda(),
+            program: sentinel::ID,
+        },
+        instruction::RemovePost {},
+    )
+}
@@ -0,0 +1,126 @@
+//! Fetching reported posts' content from IPFS and Arweave gateways.
+//!
+//! Content is downloaded once per post into `<data-dir>/content/<post>` and served to
+//! reviewers from there, so a gateway outage or an unpinned CID does not lose what was
+//! reviewed. The SHA-256 of the bytes is compared with the post's on-chain `hash`.
+
+use anyhow::{bail, Context, Result};
+use sentinelkarma_sdk::client::Client;
+use sentinelkarma_sdk::sentinel::{self, ContentLocation};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::path::{Path, PathBuf};
+
+pub struct Gateways {
+    /// Serves `/ipfs/<cid>`
+    pub ipfs: String,
+    /// Serves `/<transaction id>`
+    pub arweave: String,
+}
+
+/// What is known of one post's content.
+#[derive(Clone, Debug)]
+pub enum Content {
+    /// Stored before content addresses existed, or otherwise not fetchable
+    Unavailable(String),
+    Failed {
+        url: String,
+        error: String,
+    },
+    Fetched {
+        url: String,
+        path: PathBuf,
+        size: usize,
+        content_type: Option<String>,
+        sha256: [u8; 32],
+        matches_post_hash: bool,
+    },
+}
+
+impl Content {
+    pub fn to_json(&self) -> Value {
+        match self {
+            Content::Unavailable(reason) => json!({ "status": "unavailable", "reason": reason }),
+            Content::Failed { url, error } => {
+                json!({ "status": "failed", "url": url, "error": error })
+            }
+            Content::Fetched {
+                url,
+                size,
+                content_type,
+                sha256,
+                matches_post_hash,
+                ..
+            } => json!({
+                "status": "fetched",
+                "url": url,
+                "size": size,
+                "content_type": content_type,
+                "sha256": hex::encode(sha256),
+                "matches_post_hash": matches_post_hash,
+            }),
+        }
+    }
+}
+
+/// Looks up `post`'s content address on-chain and downloads it.
+pub async fn fetch(
+    client: &Client,
+    http: &reqwest::Client,
+    gateways: &Gateways,
+    data_dir: &Path,
+    max_bytes: usize,
+    post: &Pubkey,
+) -> Result<Content> {
+    let account: sentinel::Post = client.fetch_zero_copy(post).await?;
+    let Some(location) = ContentLocation::of(&account) else {
+        return Ok(Content::Unavailable("post predates content addresses".into()));
+    };
+    let url = location.url(&gateways.ipfs, &gateways.arweave);
+
+    let (bytes, content_type) = match download(http, &url, max_bytes).await {
+        Ok(downloaded) => downloaded,
+        Err(e) => {
+            return Ok(Content::Failed {
+                url,
+                error: format!("{e:#}"),
+            })
+        }
+    };
+    let dir = data_dir.join("content");
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(post.to_string());
+    tokio::fs::write(&path, &bytes)
+        .await
+        .with_context(|| format!("writing {}", path.display()))?;
+    let sha256 = solana_sdk::hash::hash(&bytes).to_bytes();
+    Ok(Content::Fetched {
+        url,
+        path,
+        size: bytes.len(),
+        content_type,
+        sha256,
+        matches_post_hash: sha256 == account.hash,
+    })
+}
+
+async fn download(
+    http: &reqwest::Client,
+    url: &str,
+    max_bytes: usize,
+) -> Result<(Vec<u8>, Option<String>)> {
+    let mut response = http.get(url).send().await?.error_for_status()?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > max_bytes {
+            bail!("larger than {max_bytes} bytes");
+        }
+    }
+    Ok((bytes, content_type))
+}
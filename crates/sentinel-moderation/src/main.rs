@@ -0,0 +1,458 @@
+//! sentinel-moderation: the off-chain side of sentinel's report and takedown flow.
+//!
+//! Reports are read from the indexer database as they are filed. For each reported
+//! post the content is fetched from IPFS or Arweave by its on-chain address, kept
+//! under `--data-dir`, and checked against the post's hash. Reviewers work through an
+//! HTTP API; their decisions are sent as `resolve_report` / `remove_post` signed by
+//! `State.moderator` and appended to `<data-dir>/decisions.jsonl`.
+//!
+//! Every route needs `Authorization: Bearer <MODERATION_API_TOKEN>`.
+//!
+//! - `GET /reports[?status=open|resolved|all]`: reports with their content status
+//! - `GET /reports/{report}`
+//! - `GET /reports/{report}/content`: the reported post's content, as fetched
+//! - `POST /reports/{report}/resolve` with `{"uphold": bool, "note"?: string}`
+//! - `POST /posts/{post}/takedown` with `{"note"?: string}`
+
+mod chain;
+mod content;
+mod queue;
+
+use anyhow::{Context, Result};
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Parser;
+use content::{Content, Gateways};
+use queue::Queue;
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::sentinel::{self, State as SentinelState};
+use sentinelkarma_sdk::signer::SignerThread;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+#[derive(Parser)]
+#[command(about = "Review sentinel reports and send moderation decisions")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// `State.moderator`: a keypair file, `usb://ledger` or a remote signer URL
+    #[arg(long, env = "MODERATOR_KEYPAIR")]
+    moderator: String,
+
+    /// Bearer token reviewers authenticate with
+    #[arg(long, env = "MODERATION_API_TOKEN", hide_env_values = true)]
+    api_token: String,
+
+    /// Fetched content and the decision log
+    #[arg(long, env = "DATA_DIR", default_value = "moderation")]
+    data_dir: PathBuf,
+
+    #[arg(long, env = "IPFS_GATEWAY", default_value = "https://ipfs.io")]
+    ipfs_gateway: String,
+
+    #[arg(long, env = "ARWEAVE_GATEWAY", default_value = "https://arweave.net")]
+    arweave_gateway: String,
+
+    /// Content larger than this is not downloaded
+    #[arg(long, default_value_t = 20 << 20)]
+    max_content_bytes: usize,
+
+    #[arg(long, env = "LISTEN", default_value = "127.0.0.1:8094")]
+    listen: SocketAddr,
+
+    /// How often to read newly filed reports
+    #[arg(long, default_value_t = 10)]
+    poll_secs: u64,
+}
+
+struct AppState {
+    client: Client,
+    http: reqwest::Client,
+    moderator: SignerThread,
+    api_token: String,
+    data_dir: PathBuf,
+    gateways: Gateways,
+    max_content_bytes: usize,
+    queue: Arc<RwLock<Queue>>,
+    /// By post; a failed fetch is retried on the next pass
+    content: Mutex<HashMap<Pubkey, Content>>,
+}
+
+enum ApiError {
+    Unauthorized,
+    BadRequest(String),
+    NotFound(String),
+    Internal(anyhow::Error),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".to_string()),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::Internal(e) => {
+                eprintln!("error: {e:#}");
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+            }
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError::Internal(e)
+    }
+}
+
+impl AppState {
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        match token {
+            Some(token) if constant_time_eq(token.as_bytes(), self.api_token.as_bytes()) => Ok(()),
+            _ => Err(ApiError::Unauthorized),
+        }
+    }
+
+    async fn report_json(&self, report: &queue::Report, removed: bool) -> Value {
+        let mut value = report.to_json(removed);
+        let content = self
+            .content
+            .lock()
+            .await
+            .get(&report.post)
+            .map(Content::to_json);
+        value["content"] = content.unwrap_or_else(|| json!({ "status": "pending" }));
+        value
+    }
+
+    /// Downloads content for open reports that have none yet, or whose fetch failed.
+    async fn fetch_pending(&self) {
+        let posts: Vec<Pubkey> = {
+            let queue = self.queue.read().unwrap();
+            queue.open().iter().map(|r| r.post).collect()
+        };
+        for post in posts {
+            let done = matches!(
+                self.content.lock().await.get(&post),
+                Some(Content::Fetched { .. } | Content::Unavailable(_))
+            );
+            if done {
+                continue;
+            }
+            let fetched = content::fetch(
+                &self.client,
+                &self.http,
+                &self.gateways,
+                &self.data_dir,
+                self.max_content_bytes,
+                &post,
+            )
+            .await;
+            match fetched {
+                Ok(content) => {
+                    self.content.lock().await.insert(post, content);
+                }
+                Err(e) => eprintln!("post {post}: {e:#}"),
+            }
+        }
+    }
+
+    /// Appends a sent decision to the decision log.
+    fn record(&self, decision: Value) -> Result<()> {
+        let path = self.data_dir.join("decisions.jsonl");
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        writeln!(file, "{decision}")?;
+        Ok(())
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn parse_pubkey(s: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(s).map_err(|_| ApiError::BadRequest(format!("invalid pubkey {s:?}")))
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    status: Option<String>,
+}
+
+async fn list_reports(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Value>, ApiError> {
+    state.authorize(&headers)?;
+    let wanted: fn(&queue::Report) -> bool = match query.status.as_deref() {
+        None | Some("open") => queue::Report::is_open,
+        Some("resolved") => |r| !r.is_open(),
+        Some("all") => |_| true,
+        Some(status) => {
+            return Err(ApiError::BadRequest(format!("unknown status {status:?}")));
+        }
+    };
+    let reports: Vec<(queue::Report, bool)> = {
+        let queue = state.queue.read().unwrap();
+        let mut reports: Vec<_> = queue.reports.values().filter(|r| wanted(r)).collect();
+        reports.sort_by_key(|r| r.filed.slot);
+        reports
+            .into_iter()
+            .map(|r| (r.clone(), queue.removed_posts.contains(&r.post)))
+            .collect()
+    };
+    let mut out = Vec::with_capacity(reports.len());
+    for (report, removed) in &reports {
+        out.push(state.report_json(report, *removed).await);
+    }
+    Ok(Json(json!({ "reports": out })))
+}
+
+fn find(state: &AppState, report: &str) -> Result<(queue::Report, bool), ApiError> {
+    let address = parse_pubkey(report)?;
+    let queue = state.queue.read().unwrap();
+    let report = queue
+        .reports
+        .get(&address)
+        .ok_or_else(|| ApiError::NotFound(format!("no report {address}")))?;
+    Ok((report.clone(), queue.removed_posts.contains(&report.post)))
+}
+
+async fn get_report(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(report): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    state.authorize(&headers)?;
+    let (report, removed) = find(&state, &report)?;
+    Ok(Json(state.report_json(&report, removed).await))
+}
+
+async fn get_content(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(report): Path<String>,
+) -> Result<Response, ApiError> {
+    state.authorize(&headers)?;
+    let (report, _) = find(&state, &report)?;
+    let content = state.content.lock().await.get(&report.post).cloned();
+    let Some(Content::Fetched {
+        path, content_type, ..
+    }) = content
+    else {
+        return Err(ApiError::NotFound(format!(
+            "content of {} is not fetched",
+            report.post
+        )));
+    };
+    let bytes = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("reading {}", path.display()))?;
+    let content_type = content_type.unwrap_or_else(|| "application/octet-stream".into());
+    // Reported content is untrusted: never let a browser sniff or render it inline
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".into()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", report.post),
+            ),
+            (header::CONTENT_SECURITY_POLICY, "sandbox".into()),
+        ],
+        Body::from(bytes),
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+struct Resolve {
+    uphold: bool,
+    note: Option<String>,
+}
+
+async fn resolve_report(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(report): Path<String>,
+    Json(body): Json<Resolve>,
+) -> Result<Json<Value>, ApiError> {
+    state.authorize(&headers)?;
+    let (report, _) = find(&state, &report)?;
+    if !report.is_open() {
+        return Err(ApiError::BadRequest(format!(
+            "report {} is resolved",
+            report.address
+        )));
+    }
+    let ix = chain::resolve_report(
+        &state.client,
+        state.moderator.pubkey,
+        report.address,
+        body.uphold,
+    )
+    .await?;
+    let signature = state.moderator.send(vec![ix]).await?;
+    let decision = json!({
+        "time": now(),
+        "action": "resolve_report",
+        "report": report.address.to_string(),
+        "post": report.post.to_string(),
+        "uphold": body.uphold,
+        "note": body.note,
+        "signature": signature.to_string(),
+    });
+    state.record(decision)?;
+    println!(
+        "report {} {}: {signature}",
+        report.address,
+        if body.uphold { "upheld" } else { "dismissed" }
+    );
+    Ok(Json(json!({ "signature": signature.to_string() })))
+}
+
+#[derive(Deserialize)]
+struct Takedown {
+    note: Option<String>,
+}
+
+async fn takedown_post(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(post): Path<String>,
+    Json(body): Json<Takedown>,
+) -> Result<Json<Value>, ApiError> {
+    state.authorize(&headers)?;
+    let post = parse_pubkey(&post)?;
+    let signature = state
+        .moderator
+        .send(vec![chain::remove_post(state.moderator.pubkey, post)])
+        .await?;
+    state.record(json!({
+        "time": now(),
+        "action": "remove_post",
+        "post": post.to_string(),
+        "note": body.note,
+        "signature": signature.to_string(),
+    }))?;
+    println!("post {post} removed: {signature}");
+    Ok(Json(json!({ "signature": signature.to_string() })))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    std::fs::create_dir_all(&cli.data_dir)
+        .with_context(|| format!("creating {}", cli.data_dir.display()))?;
+    let client = Client::new(&cli.rpc_url, ClientConfig::default());
+    let moderator = SignerThread::spawn("moderator", cli.moderator.clone(), cli.rpc_url.clone())?;
+    let sentinel_state: SentinelState = client.fetch_account(&sentinel::state_pda()).await?;
+    if sentinel_state.moderator != moderator.pubkey {
+        eprintln!(
+            "warning: {} is not the sentinel moderator ({}); decisions will fail",
+            moderator.pubkey, sentinel_state.moderator
+        );
+    }
+
+    let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+    let mut queue = Queue::default();
+    queue.refresh(store.as_mut())?;
+    println!("{} open reports", queue.open().len());
+    drop(store);
+    let queue = Arc::new(RwLock::new(queue));
+
+    let poll = Duration::from_secs(cli.poll_secs.max(1));
+    {
+        // Stores are blocking and not `Send`: poll from a thread of its own
+        let queue = queue.clone();
+        let database_url = cli.database_url.clone();
+        std::thread::spawn(move || {
+            let mut store = None;
+            loop {
+                std::thread::sleep(poll);
+                let refreshed = match &mut store {
+                    Some(store) => Ok(store),
+                    None => sentinelkarma_indexer::open(&database_url).map(|s| store.insert(s)),
+                }
+                .and_then(|store| queue.write().unwrap().refresh(store.as_mut()));
+                if let Err(e) = refreshed {
+                    eprintln!("reading reports failed: {e:#}");
+                    store = None;
+                }
+            }
+        });
+    }
+
+    let state = Arc::new(AppState {
+        client,
+        http: reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()?,
+        moderator,
+        api_token: cli.api_token,
+        data_dir: cli.data_dir,
+        gateways: Gateways {
+            ipfs: cli.ipfs_gateway,
+            arweave: cli.arweave_gateway,
+        },
+        max_content_bytes: cli.max_content_bytes,
+        queue,
+        content: Mutex::new(HashMap::new()),
+    });
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            loop {
+                state.fetch_pending().await;
+                tokio::time::sleep(poll).await;
+            }
+        });
+    }
+
+    let app = Router::new()
+        .route("/reports", get(list_reports))
+        .route("/reports/{report}", get(get_report))
+        .route("/reports/{report}/content", get(get_content))
+        .route("/reports/{report}/resolve", post(resolve_report))
+        .route("/posts/{post}/takedown", post(takedown_post))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(cli.listen).await?;
+    println!("listening on http://{}", cli.listen);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
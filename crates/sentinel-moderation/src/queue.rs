@@ -0,0 +1,152 @@
+//! The report queue, replayed from indexed `ReportFiled`, `ReportResolved` and
+//! `PostRemoved` events.
+
+use anyhow::Result;
+use sentinelkarma_indexer::store::{EventRow, Store};
+use sentinelkarma_sdk::decode_event;
+use sentinelkarma_sdk::sentinel::{PostRemoved, ReportFiled, ReportResolved};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashSet};
+
+const EVENTS: &[&str] = &["ReportFiled", "ReportResolved", "PostRemoved"];
+
+#[derive(Clone, Debug)]
+pub struct Filed {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Resolution {
+    pub upheld: bool,
+    pub bounty: u64,
+    pub forfeited: u64,
+    pub signature: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub address: Pubkey,
+    pub post: Pubkey,
+    pub reporter: Pubkey,
+    pub bond: u64,
+    pub reason_hash: [u8; 32],
+    pub filed: Filed,
+    pub resolution: Option<Resolution>,
+}
+
+impl Report {
+    pub fn is_open(&self) -> bool {
+        self.resolution.is_none()
+    }
+
+    pub fn to_json(&self, post_removed: bool) -> Value {
+        json!({
+            "report": self.address.to_string(),
+            "post": self.post.to_string(),
+            "reporter": self.reporter.to_string(),
+            "bond": self.bond.to_string(),
+            "reason_hash": hex::encode(self.reason_hash),
+            "filed": {
+                "signature": self.filed.signature,
+                "slot": self.filed.slot.to_string(),
+                "block_time": self.filed.block_time.map(|t| t.to_string()),
+            },
+            "post_removed": post_removed,
+            "resolution": self.resolution.as_ref().map(|r| json!({
+                "upheld": r.upheld,
+                "bounty": r.bounty.to_string(),
+                "forfeited": r.forfeited.to_string(),
+                "signature": r.signature,
+            })),
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct Queue {
+    /// By report address. A report PDA is reused when the same reporter reports the
+    /// same post again after resolution; the newest filing replaces the old one.
+    pub reports: BTreeMap<Pubkey, Report>,
+    pub removed_posts: HashSet<Pubkey>,
+    /// Highest slot applied
+    slot: u64,
+    /// `(signature, event_index)` of rows at `slot`
+    applied: HashSet<(String, u32)>,
+}
+
+impl Queue {
+    /// Reads events indexed since the last refresh.
+    pub fn refresh(&mut self, store: &mut dyn Store) -> Result<()> {
+        let mut rows = Vec::new();
+        for name in EVENTS {
+            rows.extend(store.events("sentinel", Some(name), self.slot)?);
+        }
+        rows.sort_by(|a, b| {
+            (a.slot, &a.signature, a.event_index).cmp(&(b.slot, &b.signature, b.event_index))
+        });
+        for row in rows {
+            let key = (row.signature.clone(), row.event_index);
+            if row.slot == self.slot && self.applied.contains(&key) {
+                continue;
+            }
+            if row.slot > self.slot {
+                self.slot = row.slot;
+                self.applied.clear();
+            }
+            self.applied.insert(key);
+            self.apply(&row);
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, row: &EventRow) {
+        match row.name.as_str() {
+            "ReportFiled" => {
+                if let Some(e) = decode_event::<ReportFiled>(&row.payload) {
+                    let report = Report {
+                        address: e.report,
+                        post: e.post,
+                        reporter: e.reporter,
+                        bond: e.bond,
+                        reason_hash: e.reason_hash,
+                        filed: Filed {
+                            signature: row.signature.clone(),
+                            slot: row.slot,
+                            block_time: row.block_time,
+                        },
+                        resolution: None,
+                    };
+                    self.reports.insert(e.report, report);
+                }
+            }
+            "ReportResolved" => {
+                if let Some(e) = decode_event::<ReportResolved>(&row.payload) {
+                    if let Some(report) = self.reports.get_mut(&e.report) {
+                        report.resolution = Some(Resolution {
+                            upheld: e.upheld,
+                            bounty: e.bounty,
+                            forfeited: e.forfeited,
+                            signature: row.signature.clone(),
+                        });
+                    }
+                }
+            }
+            "PostRemoved" => {
+                if let Some(e) = decode_event::<PostRemoved>(&row.payload) {
+                    self.removed_posts.insert(e.post);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Reports still awaiting a decision, oldest first.
+    pub fn open(&self) -> Vec<&Report> {
+        let mut open: Vec<&Report> = self.reports.values().filter(|r| r.is_open()).collect();
+        open.sort_by_key(|r| r.filed.slot);
+        open
+    }
+}
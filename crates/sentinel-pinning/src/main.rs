@@ -0,0 +1,364 @@
+//! sentinel-pinning: pins every minted post's content and flags posts whose content
+//! cannot be found.
+//!
+//! Sentinel emits no event on mint, so new posts come from the indexed `mint_nft`,
+//! `mint_core_post` and `reveal_post` instructions, through the indexer projection.
+//! For each post the content address is read from the `Post` account, and again after
+//! every `PostUpdated`:
+//!
+//! - IPFS CIDs are submitted to every service in `--services` and polled until one of
+//!   them reports `pinned`. Without services, the CID is only probed on the gateway.
+//! - Arweave is permanent storage; the transaction is probed on the gateway.
+//! - Legacy posts carry an opaque `db_addr` and are left alone.
+//!
+//! A post not pinned or reachable after `--max-attempts` checks, or rejected by every
+//! service, is flagged: it is listed in `<data-dir>/unreachable.json`, the format
+//! seka-aggregation's `excluded_posts` rule reads, and keeps being checked so it is
+//! unflagged once its content turns up. `<data-dir>/pins.json` holds the state of
+//! every post.
+
+mod psa;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use psa::{PinStatus, Pinner, Service};
+use sentinelkarma_indexer::projection::{self, Model};
+use sentinelkarma_sdk::client::{Client, ClientConfig};
+use sentinelkarma_sdk::sentinel::{self, ContentLocation};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command(about = "Pin minted posts' content and flag posts whose content is unreachable")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// postgres://… or sqlite:<path>, as given to sentinelkarma-indexer
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// JSON list of pinning services; see services.example.json
+    #[arg(long)]
+    services: Option<PathBuf>,
+
+    /// `pins.json` and `unreachable.json`
+    #[arg(long, env = "DATA_DIR", default_value = "pinning")]
+    data_dir: PathBuf,
+
+    #[arg(long, env = "IPFS_GATEWAY", default_value = "https://ipfs.io")]
+    ipfs_gateway: String,
+
+    #[arg(long, env = "ARWEAVE_GATEWAY", default_value = "https://arweave.net")]
+    arweave_gateway: String,
+
+    /// Failed checks before a post is flagged
+    #[arg(long, default_value_t = 10)]
+    max_attempts: u32,
+
+    /// Seconds between checks of a post not pinned yet
+    #[arg(long, default_value_t = 60)]
+    retry_secs: u64,
+
+    /// How often to look for new posts
+    #[arg(long, default_value_t = 10)]
+    poll_secs: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pending,
+    /// Pinned by at least one service
+    Pinned,
+    /// Fetched from the gateway; final for Arweave
+    Reachable,
+    Unreachable,
+    /// No content address to pin
+    Legacy,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Pin {
+    /// `ipfs` or `arweave`
+    scheme: Option<String>,
+    /// CID or Arweave transaction id
+    id: Option<String>,
+    /// `edit_count` the address was read at
+    edit_count: u32,
+    status: Status,
+    /// Checks since the address was read that found nothing
+    attempts: u32,
+    checked_at: i64,
+    /// By service name
+    requests: BTreeMap<String, PinStatus>,
+    last_error: Option<String>,
+}
+
+impl Pin {
+    fn new(location: Option<&ContentLocation>, edit_count: u32) -> Self {
+        let (scheme, id) = match location {
+            Some(ContentLocation::Ipfs(cid)) => (Some("ipfs"), Some(cid.clone())),
+            Some(ContentLocation::Arweave(id)) => (Some("arweave"), Some(id.clone())),
+            None => (None, None),
+        };
+        Self {
+            scheme: scheme.map(str::to_owned),
+            id,
+            edit_count,
+            status: if location.is_some() {
+                Status::Pending
+            } else {
+                Status::Legacy
+            },
+            attempts: 0,
+            checked_at: 0,
+            requests: BTreeMap::new(),
+            last_error: None,
+        }
+    }
+
+    fn location(&self) -> Option<ContentLocation> {
+        match (self.scheme.as_deref(), &self.id) {
+            (Some("ipfs"), Some(cid)) => Some(ContentLocation::Ipfs(cid.clone())),
+            (Some("arweave"), Some(id)) => Some(ContentLocation::Arweave(id.clone())),
+            _ => None,
+        }
+    }
+
+    /// Nothing left to check.
+    fn settled(&self) -> bool {
+        match self.status {
+            Status::Pinned | Status::Legacy => true,
+            Status::Reachable => self.scheme.as_deref() == Some("arweave"),
+            Status::Pending | Status::Unreachable => false,
+        }
+    }
+}
+
+struct Worker {
+    client: Client,
+    http: reqwest::Client,
+    pinners: Vec<Pinner>,
+    ipfs_gateway: String,
+    arweave_gateway: String,
+    max_attempts: u32,
+    retry_secs: i64,
+    /// By post
+    pins: BTreeMap<Pubkey, Pin>,
+}
+
+impl Worker {
+    /// Brings every live post in `posts` (address, edit count) up to date.
+    async fn pass(&mut self, posts: Vec<(Pubkey, u32)>) {
+        for (post, edit_count) in posts {
+            if let Err(e) = self.check(post, edit_count).await {
+                eprintln!("post {post}: {e:#}");
+            }
+        }
+    }
+
+    async fn check(&mut self, post: Pubkey, edit_count: u32) -> Result<()> {
+        let current = self
+            .pins
+            .get(&post)
+            .is_some_and(|p| p.edit_count == edit_count);
+        if !current {
+            let account: sentinel::Post = self.client.fetch_zero_copy(&post).await?;
+            let location = ContentLocation::of(&account);
+            self.pins
+                .insert(post, Pin::new(location.as_ref(), edit_count));
+        }
+        let now = now();
+        let pin = &self.pins[&post];
+        if pin.settled() || now - pin.checked_at < self.retry_secs {
+            return Ok(());
+        }
+        let Some(location) = pin.location() else {
+            return Ok(());
+        };
+        let mut pin = pin.clone();
+        pin.checked_at = now;
+        pin.last_error = None;
+
+        let found = match &location {
+            ContentLocation::Ipfs(cid) if !self.pinners.is_empty() => {
+                self.pin_ipfs(&post, cid, &mut pin).await;
+                if pin.requests.values().any(PinStatus::pinned) {
+                    Some(Status::Pinned)
+                } else {
+                    None
+                }
+            }
+            _ => self
+                .probe(&location, &mut pin)
+                .await
+                .then_some(Status::Reachable),
+        };
+        let rejected = !pin.requests.is_empty()
+            && pin.requests.len() == self.pinners.len()
+            && pin.requests.values().all(PinStatus::failed);
+        let previous = pin.status;
+        match found {
+            Some(status) => {
+                pin.status = status;
+                pin.attempts = 0;
+            }
+            None => {
+                pin.attempts += 1;
+                if rejected || pin.attempts >= self.max_attempts {
+                    pin.status = Status::Unreachable;
+                }
+            }
+        }
+        if pin.status != previous {
+            println!("post {post}: {:?} -> {:?}", previous, pin.status);
+        }
+        self.pins.insert(post, pin);
+        Ok(())
+    }
+
+    /// Submits `cid` to the services without a live request, and polls the others.
+    async fn pin_ipfs(&self, post: &Pubkey, cid: &str, pin: &mut Pin) {
+        for pinner in &self.pinners {
+            let result = match pin.requests.get(&pinner.name) {
+                Some(request) if request.pinned() => continue,
+                Some(request) if !request.failed() => {
+                    pinner.status(&self.http, &request.requestid).await
+                }
+                // Failed requests are submitted again on the next check
+                _ => pinner.pin(&self.http, cid, &post.to_string()).await,
+            };
+            match result {
+                Ok(status) => {
+                    pin.requests.insert(pinner.name.clone(), status);
+                }
+                Err(e) => pin.last_error = Some(format!("{}: {e:#}", pinner.name)),
+            }
+        }
+    }
+
+    /// Whether the gateway serves the content; only its first byte is requested.
+    async fn probe(&self, location: &ContentLocation, pin: &mut Pin) -> bool {
+        let url = location.url(&self.ipfs_gateway, &self.arweave_gateway);
+        let result = self
+            .http
+            .get(&url)
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                pin.last_error = Some(format!("{url}: {e}"));
+                false
+            }
+        }
+    }
+
+    fn save(&self, data_dir: &Path) -> Result<()> {
+        let pins: BTreeMap<String, &Pin> =
+            self.pins.iter().map(|(k, v)| (k.to_string(), v)).collect();
+        write_json(&data_dir.join("pins.json"), &pins)?;
+        let unreachable: Vec<String> = self
+            .pins
+            .iter()
+            .filter(|(_, p)| p.status == Status::Unreachable)
+            .map(|(k, _)| k.to_string())
+            .collect();
+        write_json(&data_dir.join("unreachable.json"), &unreachable)
+    }
+}
+
+fn load(data_dir: &Path) -> Result<BTreeMap<Pubkey, Pin>> {
+    let path = data_dir.join("pins.json");
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let pins: BTreeMap<String, Pin> = serde_json::from_slice(&std::fs::read(&path)?)
+        .with_context(|| format!("reading {}", path.display()))?;
+    pins.into_iter()
+        .map(|(k, v)| Ok((k.parse().with_context(|| format!("post {k}"))?, v)))
+        .collect()
+}
+
+/// Written to a temporary file and renamed, so readers never see a partial file.
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(value)?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    std::fs::create_dir_all(&cli.data_dir)
+        .with_context(|| format!("creating {}", cli.data_dir.display()))?;
+    let services = match &cli.services {
+        Some(path) => Service::read_all(path)?,
+        None => Vec::new(),
+    };
+    let pinners = services
+        .iter()
+        .map(Pinner::new)
+        .collect::<Result<Vec<_>>>()?;
+    if pinners.is_empty() {
+        println!(
+            "no pinning services: IPFS content is only probed on {}",
+            cli.ipfs_gateway
+        );
+    }
+
+    let mut store = sentinelkarma_indexer::open(&cli.database_url)?;
+    let model = Arc::new(RwLock::new(Model::load(store.as_mut())?));
+    drop(store);
+    let poll = Duration::from_secs(cli.poll_secs.max(1));
+    {
+        let model = model.clone();
+        let database_url = cli.database_url.clone();
+        std::thread::spawn(move || {
+            projection::follow(model, &database_url, poll, Duration::from_secs(3600))
+        });
+    }
+
+    let mut worker = Worker {
+        client: Client::new(&cli.rpc_url, ClientConfig::default()),
+        http: reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?,
+        pinners,
+        ipfs_gateway: cli.ipfs_gateway,
+        arweave_gateway: cli.arweave_gateway,
+        max_attempts: cli.max_attempts.max(1),
+        retry_secs: cli.retry_secs as i64,
+        pins: load(&cli.data_dir)?,
+    };
+    loop {
+        // Removed, closed and deleted posts keep their last state but are not checked
+        let posts: Vec<(Pubkey, u32)> = {
+            let model = model.read().unwrap();
+            model
+                .posts
+                .values()
+                .filter(|p| p.status.is_none())
+                .map(|p| (p.address, p.edit_count))
+                .collect()
+        };
+        worker.pass(posts).await;
+        worker.save(&cli.data_dir)?;
+        tokio::time::sleep(poll).await;
+    }
+}
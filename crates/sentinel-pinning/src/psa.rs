@@ -0,0 +1,91 @@
+//! Client for the IPFS Pinning Service API (`POST /pins`, `GET /pins/{requestid}`),
+//! as Pinata, Filebase and other providers serve it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+/// One configured pinning service; see services.example.json.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Service {
+    pub name: String,
+    /// API root, without the trailing `/pins`
+    pub endpoint: String,
+    /// Environment variable holding the access token
+    pub token_env: String,
+}
+
+impl Service {
+    pub fn read_all(path: &Path) -> Result<Vec<Self>> {
+        serde_json::from_slice(&std::fs::read(path)?)
+            .with_context(|| format!("reading {}", path.display()))
+    }
+}
+
+/// A service with its token resolved.
+pub struct Pinner {
+    pub name: String,
+    endpoint: String,
+    token: String,
+}
+
+/// `queued`, `pinning`, `pinned` or `failed`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PinStatus {
+    pub requestid: String,
+    pub status: String,
+}
+
+impl PinStatus {
+    pub fn pinned(&self) -> bool {
+        self.status == "pinned"
+    }
+
+    pub fn failed(&self) -> bool {
+        self.status == "failed"
+    }
+}
+
+impl Pinner {
+    pub fn new(service: &Service) -> Result<Self> {
+        let token = std::env::var(&service.token_env).with_context(|| {
+            format!("{} for pinning service {}", service.token_env, service.name)
+        })?;
+        Ok(Self {
+            name: service.name.clone(),
+            endpoint: service.endpoint.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+
+    /// Asks the service to pin `cid`; `name` is shown in the provider's dashboard.
+    pub async fn pin(&self, http: &reqwest::Client, cid: &str, name: &str) -> Result<PinStatus> {
+        let response = http
+            .post(format!("{}/pins", self.endpoint))
+            .bearer_auth(&self.token)
+            .json(&json!({ "cid": cid, "name": name }))
+            .send()
+            .await?;
+        Self::decode(response).await
+    }
+
+    pub async fn status(&self, http: &reqwest::Client, requestid: &str) -> Result<PinStatus> {
+        let response = http
+            .get(format!("{}/pins/{requestid}", self.endpoint))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        Self::decode(response).await
+    }
+
+    async fn decode(response: reqwest::Response) -> Result<PinStatus> {
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("{status}: {body}");
+        }
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,166 @@
+//! `sync-idls`: rebuilds `idls/*.json` from the program sources.
+//!
+//! Does what `anchor idl build` does without the Anchor CLI: runs the program's
+//! `__anchor_private_print_idl` tests with the `idl-build` feature and assembles the
+//! pieces they print. Builds go to `target/idl-build`, as the feature and flags would
+//! otherwise invalidate the regular build.
+
+use anchor_lang_idl::types::{Idl, IdlConst, IdlErrorCode, IdlEvent, IdlTypeDef};
+use anyhow::{bail, ensure, Context, Result};
+use clap::Parser;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Program name and its crate, relative to the repository root.
+const PROGRAMS: [(&str, &str); 2] = [
+    ("seka", "programs/seka"),
+    ("sentinel", "sentinel/programs/sentinel"),
+];
+
+#[derive(Parser)]
+#[command(about = "Rebuild the IDLs the generated clients are declared from")]
+struct Cli {
+    /// Fail if an IDL differs from the program instead of rewriting it
+    #[arg(long)]
+    check: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let root = crate_dir.join("../..").canonicalize()?;
+    let target_dir = root.join("target/idl-build");
+
+    let mut stale = Vec::new();
+    for (name, path) in PROGRAMS {
+        let idl = build(&root.join(path), &target_dir)
+            .with_context(|| format!("building the {name} IDL"))?;
+        let json = serde_json::to_string_pretty(&idl)? + "\n";
+        let out = crate_dir.join("idls").join(format!("{name}.json"));
+        let current = fs::read_to_string(&out).unwrap_or_default();
+        if current == json {
+            println!("{name}: up to date");
+        } else if cli.check {
+            stale.push(name);
+        } else {
+            fs::write(&out, json).with_context(|| format!("writing {}", out.display()))?;
+            println!("{name}: updated {}", out.display());
+        }
+    }
+    if !stale.is_empty() {
+        bail!("stale IDLs: {}; run sync-idls", stale.join(", "));
+    }
+    Ok(())
+}
+
+fn build(program: &Path, target_dir: &PathBuf) -> Result<Idl> {
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args([
+            "test",
+            "__anchor_private_print_idl",
+            "--features",
+            "idl-build",
+        ])
+        .arg("--target-dir")
+        .arg(target_dir)
+        .args(["--", "--show-output", "--quiet"])
+        // Seka's seeds call helpers the resolver cannot follow, so PDAs are left out
+        .env("ANCHOR_IDL_BUILD_RESOLUTION", "FALSE")
+        .env("ANCHOR_IDL_BUILD_SKIP_LINT", "TRUE")
+        .env("ANCHOR_IDL_BUILD_NO_DOCS", "FALSE")
+        .env("ANCHOR_IDL_BUILD_PROGRAM_PATH", program)
+        .env("RUSTFLAGS", "-A warnings")
+        .current_dir(program)
+        .stderr(Stdio::inherit())
+        .output()?;
+    ensure!(
+        output.status.success(),
+        "cargo test failed: {}",
+        output.status
+    );
+    parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Assembles the `--- IDL begin <part> ---` blocks the IDL tests print.
+fn parse(stdout: &str) -> Result<Idl> {
+    #[derive(Deserialize)]
+    struct EventPrint {
+        event: IdlEvent,
+        types: Vec<IdlTypeDef>,
+    }
+
+    let mut address = None;
+    let mut constants: Vec<IdlConst> = Vec::new();
+    let mut events = Vec::new();
+    let mut errors: Vec<IdlErrorCode> = Vec::new();
+    let mut types = BTreeMap::new();
+    let mut program: Option<Idl> = None;
+
+    let mut lines = stdout.lines();
+    while let Some(line) = lines.next() {
+        let Some(part) = line
+            .strip_prefix("--- IDL begin ")
+            .and_then(|rest| rest.strip_suffix(" ---"))
+        else {
+            continue;
+        };
+        if part == "address" {
+            let line = lines.next().context("IDL address missing")?;
+            address = Some(line.replace(|c: char| !c.is_alphanumeric(), ""));
+            continue;
+        }
+        let end = format!("--- IDL end {part} ---");
+        let body = lines
+            .by_ref()
+            .take_while(|line| *line != end)
+            .collect::<Vec<_>>()
+            .join("\n");
+        match part {
+            "const" => constants.push(serde_json::from_str(&body)?),
+            "event" => {
+                let print: EventPrint = serde_json::from_str(&body)?;
+                events.push(print.event);
+                types.extend(print.types.into_iter().map(|ty| (ty.name.clone(), ty)));
+            }
+            "errors" => errors = serde_json::from_str(&body)?,
+            "program" => program = Some(serde_json::from_str(&body)?),
+            _ => bail!("unknown IDL part {part:?}"),
+        }
+    }
+
+    let mut idl = program.context("no IDL printed; does the crate have `idl-build`?")?;
+    idl.address = address.context("IDL address missing")?;
+    idl.constants = constants;
+    idl.events = events;
+    idl.errors = errors;
+    types.extend(idl.types.drain(..).map(|ty| (ty.name.clone(), ty)));
+    idl.types = types.into_values().collect();
+    idl.accounts.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.constants.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.events.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.instructions.sort_by(|a, b| a.name.cmp(&b.name));
+    shorten_paths(idl)
+}
+
+/// Type names are printed as module paths (`seka::CycleState`), which `declare_program!`
+/// rejects; each becomes its last segment unless two paths share it.
+fn shorten_paths(idl: Idl) -> Result<Idl> {
+    let mut json = serde_json::to_string(&idl)?;
+    let path = Regex::new(r#""(?:\w+::)+(\w+)""#)?;
+    let paths: Vec<(String, String)> = path
+        .captures_iter(&json)
+        .map(|c| (c[0].to_owned(), c[1].to_owned()))
+        .collect();
+    for (full, name) in paths {
+        let replaced = json.replace(&full, &format!("\"{name}\""));
+        let conflict = Regex::new(&format!(r#""(?:\w+::)+{name}""#))?;
+        if !conflict.is_match(&replaced) {
+            json = replaced;
+        }
+    }
+    Ok(serde_json::from_str(&json)?)
+}
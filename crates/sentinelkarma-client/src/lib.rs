@@ -0,0 +1,49 @@
+//! Clients for the seka and sentinel programs generated by Anchor's `declare_program!`
+//! from `idls/seka.json` and `idls/sentinel.json`.
+//!
+//! Each module has the program's `accounts`, `events` and `types`, instruction builders
+//! under `client::{accounts, args}` and CPI helpers under `cpi`, all derived from the
+//! IDL at compile time. The IDLs are built from the program sources by
+//! `cargo run -p sentinelkarma-client --bin sync-idls`; with `--check` it fails when a
+//! program has changed since, so a stale client is caught before it ships.
+//!
+//! The IDLs are built without PDA resolution (seka's seeds call helper functions the IDL
+//! builder cannot follow), so every account is passed explicitly; `sentinelkarma-sdk`
+//! has the PDA helpers.
+//!
+//! ```
+//! use anchor_lang::prelude::*;
+//! use anchor_lang::solana_program::instruction::Instruction;
+//! use anchor_lang::{InstructionData, ToAccountMetas};
+//! use sentinelkarma_client::seka;
+//!
+//! fn set_cycle_root(governor: Pubkey, config: Pubkey, cycle_state: Pubkey) -> Instruction {
+//!     let accounts = seka::client::accounts::SetCycleRoot {
+//!         signer: governor,
+//!         system_program: anchor_lang::system_program::ID,
+//!         config,
+//!         cycle_state,
+//!     };
+//!     let args = seka::client::args::SetCycleRoot {
+//!         cycle_index: 7,
+//!         merkle_root: [0; 32],
+//!         total_points_declared: 100,
+//!         claims_bitmap_len: 1,
+//!         leaf_count: 8,
+//!         leaf_format: 0,
+//!     };
+//!     Instruction {
+//!         program_id: seka::ID,
+//!         accounts: accounts.to_account_metas(None),
+//!         data: args.data(),
+//!     }
+//! }
+//! ```
+
+// Generated CPI helpers take one parameter per instruction argument
+#![allow(clippy::too_many_arguments)]
+
+use anchor_lang::prelude::*;
+
+declare_program!(seka);
+declare_program!(sentinel);
@@ -0,0 +1,78 @@
+//! Picks the cluster from the `localnet`, `devnet` and `mainnet` features and writes its
+//! program ids to `$OUT_DIR/program_ids.rs`, which `cluster` includes.
+//!
+//! A localnet build uses the ids the repo deploys locally. Devnet and mainnet builds take
+//! theirs from `SEKA_PROGRAM_ID` and `SENTINEL_PROGRAM_ID` and refuse the localnet ones,
+//! so a placeholder id never ends up in a program meant for a real cluster.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const LOCALNET_SEKA_ID: &str = "SEKA111111111111111111111111111111111111111";
+const LOCALNET_SENTINEL_ID: &str = "7e5HppSuDGkqSjgKNfC62saPoJR5LBkYMuQHkv59eDY7";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SEKA_PROGRAM_ID");
+    println!("cargo:rerun-if-env-changed=SENTINEL_PROGRAM_ID");
+    println!("cargo::rustc-check-cfg=cfg(target_os, values(\"solana\"))");
+
+    let enabled: Vec<&str> = ["localnet", "devnet", "mainnet"]
+        .into_iter()
+        .filter(|c| env::var_os(format!("CARGO_FEATURE_{}", c.to_uppercase())).is_some())
+        .collect();
+    let cluster = match enabled[..] {
+        [] => "localnet",
+        [cluster] => cluster,
+        _ => panic!(
+            "sentinelkarma-core: enable one cluster feature, not {}",
+            enabled.join(", ")
+        ),
+    };
+    let seka = program_id("SEKA_PROGRAM_ID", cluster, LOCALNET_SEKA_ID);
+    let sentinel = program_id("SENTINEL_PROGRAM_ID", cluster, LOCALNET_SENTINEL_ID);
+
+    let variant = match cluster {
+        "devnet" => "Devnet",
+        "mainnet" => "Mainnet",
+        _ => "Localnet",
+    };
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("program_ids.rs");
+    fs::write(
+        out,
+        format!(
+            r#"/// The cluster this build targets.
+pub const CLUSTER: Cluster = Cluster::{variant};
+/// seka's program id on [`CLUSTER`], base58.
+pub const SEKA_PROGRAM_ID: &str = "{seka}";
+/// sentinel's program id on [`CLUSTER`], base58.
+pub const SENTINEL_PROGRAM_ID: &str = "{sentinel}";
+
+/// Invokes `$m!("<seka program id>")`, as `seka_id!(declare_id)` or
+/// `seka_id!(solana_sdk::pubkey)`: `declare_id!` and `pubkey!` take only literals.
+#[macro_export]
+macro_rules! seka_id {{
+    ($($m:ident)::+) => {{ $($m)::+! {{ "{seka}" }} }};
+}}
+
+/// Invokes `$m!("<sentinel program id>")`, as [`seka_id!`] does for seka.
+#[macro_export]
+macro_rules! sentinel_id {{
+    ($($m:ident)::+) => {{ $($m)::+! {{ "{sentinel}" }} }};
+}}
+"#
+        ),
+    )
+    .unwrap();
+}
+
+fn program_id(var: &str, cluster: &str, localnet: &str) -> String {
+    match env::var(var) {
+        Ok(id) if cluster != "localnet" && id == localnet => {
+            panic!("{var} is the localnet id {id}; set the {cluster} program id")
+        }
+        Ok(id) => id,
+        Err(_) if cluster == "localnet" => localnet.to_owned(),
+        Err(_) => panic!("{var} must be set to the {cluster} program id for a {cluster} build"),
+    }
+}
@@ -0,0 +1,62 @@
+//! The cluster a build targets, its program ids and the defaults that differ by cluster.
+//!
+//! One of the `localnet` (the default), `devnet` and `mainnet` features picks the
+//! cluster. The programs and the SDK forward theirs here, so a build agrees on one
+//! cluster throughout. Devnet and mainnet builds take the program ids from the
+//! `SEKA_PROGRAM_ID` and `SENTINEL_PROGRAM_ID` variables at build time.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cluster {
+    Localnet,
+    Devnet,
+    Mainnet,
+}
+
+impl Cluster {
+    pub const ALL: &'static [Cluster] = &[Cluster::Localnet, Cluster::Devnet, Cluster::Mainnet];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Cluster::Localnet => "localnet",
+            Cluster::Devnet => "devnet",
+            Cluster::Mainnet => "mainnet",
+        }
+    }
+
+    /// Genesis hash, base58; `None` for localnet, whose genesis is per validator.
+    pub const fn genesis_hash(self) -> Option<&'static str> {
+        match self {
+            Cluster::Localnet => None,
+            Cluster::Devnet => Some("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+            Cluster::Mainnet => Some("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+        }
+    }
+
+    /// The public cluster with genesis hash `hash`; anything else is taken for a localnet.
+    pub fn from_genesis_hash(hash: &str) -> Self {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.genesis_hash() == Some(hash))
+            .unwrap_or(Cluster::Localnet)
+    }
+
+    /// seka's initial `cycle_secs`. Localnet cycles are short so claim flows finish in
+    /// minutes; devnet runs mainnet's.
+    pub const fn seka_cycle_secs(self) -> u64 {
+        match self {
+            Cluster::Localnet => 600,
+            Cluster::Devnet | Cluster::Mainnet => 259_200, // 3 days
+        }
+    }
+
+    /// sentinel's initial `cycle_seconds`, on the same terms as [`Self::seka_cycle_secs`].
+    pub const fn sentinel_cycle_seconds(self) -> i64 {
+        match self {
+            Cluster::Localnet => 5 * 60,
+            Cluster::Devnet | Cluster::Mainnet => 2 * 60 * 60,
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/program_ids.rs"));
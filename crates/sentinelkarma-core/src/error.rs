@@ -0,0 +1,286 @@
+//! Errors of both programs, numbered in one space.
+//!
+//! Every code is `ERROR_CODE_OFFSET` (Anchor's 6000) plus a discriminant, and each
+//! subsystem owns a block of 100, so a code alone says which program and subsystem
+//! raised it:
+//!
+//! | Codes     | Subsystem                 | Program  |
+//! |-----------|---------------------------|----------|
+//! | 6000–6099 | claims, points and tokens | seka     |
+//! | 6100–6199 | general                   | sentinel |
+//! | 6200–6299 | membership                | sentinel |
+//! | 6300–6399 | posts                     | sentinel |
+//! | 6400–6499 | likes                     | sentinel |
+//! | 6500–6599 | cycles and rewards        | sentinel |
+//! | 6600–6699 | tokens and treasury       | sentinel |
+//! | 6700–6799 | marketplace               | sentinel |
+//! | 6800–6899 | identity                  | sentinel |
+//!
+//! Codes never move once released: a new error takes the next free code of its block,
+//! a removed one leaves a gap. [`KarmaError::from_code`] maps any custom program error
+//! to its variant.
+
+use core::fmt;
+use core::ops::Range;
+
+/// Anchor's first custom error code.
+pub const ERROR_CODE_OFFSET: u32 = 6000;
+
+/// Declares seka's errors as `$name` in the calling crate: `anchor` makes it an
+/// `#[error_code]`, `plain` a plain enum, as [`SekaError`].
+#[macro_export]
+macro_rules! seka_error {
+    ($mode:ident $name:ident) => {
+        $crate::error_enum!($mode $name {
+            Unauthorized = 0 => "Unauthorized",
+            CycleAlreadyInitialized = 1 => "Cycle already initialized",
+            InvalidMerkleProof = 2 => "Invalid Merkle proof",
+            ClaimAlreadyProcessed = 3 => "Claim already processed",
+            DeltaExceedsPerPeerCap = 4 => "Delta exceeds per-peer cap",
+            TotalPointsExceedsCycleCap = 5 => "Total points exceed cycle cap",
+            InsufficientPointsToConvert = 6 => "Insufficient points to convert",
+            InsufficientTokenBalance = 7 => "Insufficient token balance",
+            MathOverflow = 8 => "Math overflow",
+            InvalidCycle = 9 => "Invalid cycle",
+            WrongLedgerOwner = 10 => "Wrong ledger owner",
+            WrongMembershipOwner = 11 => "Wrong membership owner",
+            CycleStillClaimable = 12 => "Cycle still has claimable leaves",
+            InvalidAccount = 13 => "Invalid account data",
+            InvalidLeafFormat = 14 => "Invalid leaf format",
+            CycleAlreadyClosed = 15 => "Cycle was closed and cannot be published again",
+            InvalidLeafCount = 16 => "Leaf count does not fit the claims bitmap",
+        });
+    };
+}
+
+/// Declares sentinel's errors as `$name` in the calling crate: `anchor` makes it an
+/// `#[error_code]`, `plain` a plain enum, as [`SentinelError`].
+#[macro_export]
+macro_rules! sentinel_error {
+    ($mode:ident $name:ident) => {
+        $crate::error_enum!($mode $name {
+            // General
+            Overflow = 100 => "Math overflow",
+            InvalidInput = 101 => "Invalid input vectors",
+            MissingAccount = 102 => "Missing remaining account for reward ATA",
+            InvalidAccount = 103 => "Invalid account data",
+            Unauthorized = 104 => "Unauthorized",
+            InvalidAmount = 105 => "Amount must be greater than zero",
+            Paused = 106 => "Program is paused",
+            // Membership
+            NotPeer = 200 => "User is not an active peer",
+            InviteRequired = 201 => "An invite is required to join",
+            InviteQuotaExceeded = 202 => "Invite quota exhausted",
+            SolJoinDisabled = 203 => "Joining with SOL is disabled",
+            // Posts
+            InvalidNftMint = 300 => "Invalid NFT mint (must be 0 decimals with user as authority)",
+            PostRemoved = 301 => "Post has been removed by moderation",
+            PostStillActive = 302 => "Post NFT still exists and post was not removed",
+            BondOutstanding = 303 => "Post bond escrow must be refunded or slashed first",
+            CommitmentMismatch = 304 => "Revealed hash and salt do not match the commitment",
+            RevealTooEarly = 305 => "Reveal must wait a few slots after the commit",
+            InvalidAttestation = 306 => "Missing or invalid storage provider attestation",
+            InvalidContentAddress = 307 => "Content address is malformed for its scheme",
+            InvalidTag = 308 => "Tag must be lowercase letters, digits, '_' or '-', zero-padded",
+            CoreAssetsDisabled = 309 => "Metaplex Core posts are not enabled",
+            // Likes
+            CannotLikeSelf = 400 => "Cannot like your own post",
+            LikeBudgetExceeded = 401 => "Per-cycle like budget exhausted",
+            PairLikeLimitExceeded = 402 => "Like limit between this pair reached for the cycle",
+            // Cycles and rewards
+            CycleNotEnded = 500 => "Cycle not ended yet",
+            BonusAlreadyClaimed = 501 => "Top post bonus already claimed",
+            KarmaUnsettled = 502 => "Peer karma from a closed cycle has not been settled",
+            SekaSyncDisabled = 503 => "Syncing karma to seka is disabled",
+            NothingToSync = 504 => "No unsynced karma",
+            CrankFinalizeDisabled = 505 => "Keyless cycle finalization is disabled",
+            // Tokens and treasury
+            UnsupportedMintExtension = 600 => "Mint carries an unsupported Token-2022 extension",
+            InvalidFeeAuthority = 601 => "Transfer fee authorities must be the state PDA",
+            NothingToClaim = 602 => "Nothing has vested since the last claim",
+            GenesisExhausted = 603 => "Amount exceeds the undistributed initial supply",
+            GenesisReserved = 604 => "Treasury balance is reserved for the undistributed initial supply",
+            // Marketplace
+            PostSoulbound = 700 => "Soulbound posts cannot be sold",
+            PriceChanged = 701 => "Listing price is above the buyer's limit",
+            // Identity
+            InvalidUsername = 800 => "Username must be 3-32 lowercase letters, digits or underscores",
+            UsernameHeld = 801 => "Peer already holds a username",
+            AchievementNotMet = 802 => "Peer has not reached the achievement threshold",
+        });
+    };
+}
+
+/// Expands an error list into an enum plus [`ERROR_CODE_OFFSET`]-based conversions.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! error_enum {
+    (anchor $name:ident { $($variant:ident = $id:tt => $msg:tt,)* }) => {
+        #[anchor_lang::error_code]
+        pub enum $name {
+            $(#[msg($msg)] $variant = $id,)*
+        }
+        $crate::error_enum!(@impl $name { $($variant = $id => $msg,)* });
+    };
+    (plain $name:ident { $($variant:ident = $id:tt => $msg:tt,)* }) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[repr(u32)]
+        pub enum $name {
+            $(#[doc = $msg] $variant = $id,)*
+        }
+        $crate::error_enum!(@impl $name { $($variant = $id => $msg,)* });
+    };
+    (@impl $name:ident { $($variant:ident = $id:tt => $msg:tt,)* }) => {
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            /// The custom program error code.
+            pub const fn code(self) -> u32 {
+                $crate::error::ERROR_CODE_OFFSET + self as u32
+            }
+
+            pub fn from_code(code: u32) -> Option<Self> {
+                Self::ALL.iter().copied().find(|e| e.code() == code)
+            }
+
+            /// The variant name, which Anchor logs as `Error Code: <name>`.
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                }
+            }
+
+            pub const fn message(self) -> &'static str {
+                match self {
+                    $($name::$variant => $msg,)*
+                }
+            }
+        }
+    };
+}
+
+seka_error!(plain SekaError);
+sentinel_error!(plain SentinelError);
+
+impl fmt::Display for SekaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Display for SentinelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Where an error code belongs; see the table in the module docs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    Seka,
+    General,
+    Membership,
+    Posts,
+    Likes,
+    Cycles,
+    Tokens,
+    Marketplace,
+    Identity,
+}
+
+impl Subsystem {
+    pub const ALL: &'static [Subsystem] = &[
+        Subsystem::Seka,
+        Subsystem::General,
+        Subsystem::Membership,
+        Subsystem::Posts,
+        Subsystem::Likes,
+        Subsystem::Cycles,
+        Subsystem::Tokens,
+        Subsystem::Marketplace,
+        Subsystem::Identity,
+    ];
+
+    /// Codes reserved for the subsystem.
+    pub const fn range(self) -> Range<u32> {
+        let start = ERROR_CODE_OFFSET + 100 * self as u32;
+        start..start + 100
+    }
+
+    /// The subsystem whose block `code` is in, used or not.
+    pub fn of(code: u32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|s| s.range().contains(&code))
+    }
+
+    pub const fn program(self) -> &'static str {
+        match self {
+            Subsystem::Seka => "seka",
+            _ => "sentinel",
+        }
+    }
+}
+
+/// Any custom error either program raises.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KarmaError {
+    Seka(SekaError),
+    Sentinel(SentinelError),
+}
+
+impl KarmaError {
+    /// The error with custom program error `code`; `None` for codes no program uses,
+    /// such as Anchor's own below 6000.
+    pub fn from_code(code: u32) -> Option<Self> {
+        match Subsystem::of(code)? {
+            Subsystem::Seka => SekaError::from_code(code).map(KarmaError::Seka),
+            _ => SentinelError::from_code(code).map(KarmaError::Sentinel),
+        }
+    }
+
+    pub const fn code(self) -> u32 {
+        match self {
+            KarmaError::Seka(e) => e.code(),
+            KarmaError::Sentinel(e) => e.code(),
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            KarmaError::Seka(e) => e.as_str(),
+            KarmaError::Sentinel(e) => e.as_str(),
+        }
+    }
+
+    pub const fn message(self) -> &'static str {
+        match self {
+            KarmaError::Seka(e) => e.message(),
+            KarmaError::Sentinel(e) => e.message(),
+        }
+    }
+
+    pub fn subsystem(self) -> Subsystem {
+        // Every declared code lies in a block
+        Subsystem::of(self.code()).unwrap_or(Subsystem::General)
+    }
+}
+
+impl From<SekaError> for KarmaError {
+    fn from(e: SekaError) -> Self {
+        KarmaError::Seka(e)
+    }
+}
+
+impl From<SentinelError> for KarmaError {
+    fn from(e: SentinelError) -> Self {
+        KarmaError::Sentinel(e)
+    }
+}
+
+impl fmt::Display for KarmaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.as_str(), self.code(), self.message())
+    }
+}
@@ -0,0 +1,183 @@
+//! The seka claim leaf and its Merkle proofs.
+//!
+//! Each cycle's root is built in one of two [`LeafFormat`]s. [`LeafFormat::Solana`]:
+//!
+//! - leaf = keccak(owner || cycle_index (u64 LE) || delta (i32 LE) || leaf_index (u32 LE))
+//! - parents are keccak(left || right), with the side taken from the bits of `leaf_index`
+//!   (bit set = we are the right child), not from sorting the pair
+//!
+//! [`LeafFormat::Evm`], which an EVM contract verifies with OpenZeppelin's `MerkleProof`:
+//!
+//! - leaf = keccak(abi.encodePacked(bytes32 owner, uint64 cycle_index, int32 delta,
+//!   uint32 leaf_index)), the same fields big-endian
+//! - parents are keccak of the sorted pair, smaller hash first
+//!
+//! Leaves are 48 bytes and parents hash 64, so no leaf can pass for a parent in either
+//! format. Hashing is left to the caller, so the same code runs with the `keccak`
+//! syscall on-chain and a software keccak off-chain. With the `solana` feature, a
+//! program built for Solana also gets [`compute_root_keccak`], which calls the syscall
+//! itself.
+
+pub type Hash = [u8; 32];
+
+/// Bytes of a serialized leaf.
+pub const LEAF_LEN: usize = 32 + 8 + 4 + 4;
+
+/// How a cycle's leaves and parents are hashed, stored in its cycle state as a `u8`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum LeafFormat {
+    #[default]
+    Solana = 0,
+    Evm = 1,
+}
+
+impl LeafFormat {
+    pub const ALL: &'static [LeafFormat] = &[LeafFormat::Solana, LeafFormat::Evm];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            LeafFormat::Solana => "solana",
+            LeafFormat::Evm => "evm",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|f| f.name() == name)
+    }
+
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(LeafFormat::Solana),
+            1 => Some(LeafFormat::Evm),
+            _ => None,
+        }
+    }
+
+    /// Exact bytes hashed for a leaf in this format.
+    pub fn serialize_leaf(
+        self,
+        owner: &[u8; 32],
+        cycle_index: u64,
+        delta_points: i32,
+        leaf_index: u32,
+    ) -> [u8; LEAF_LEN] {
+        match self {
+            LeafFormat::Solana => serialize_leaf(owner, cycle_index, delta_points, leaf_index),
+            LeafFormat::Evm => {
+                let mut v = [0u8; LEAF_LEN];
+                v[..32].copy_from_slice(owner);
+                v[32..40].copy_from_slice(&cycle_index.to_be_bytes());
+                v[40..44].copy_from_slice(&delta_points.to_be_bytes());
+                v[44..48].copy_from_slice(&leaf_index.to_be_bytes());
+                v
+            }
+        }
+    }
+
+    /// Folds `proof` into `leaf` in this format; [`LeafFormat::Evm`] ignores `leaf_index`,
+    /// which its leaves carry anyway.
+    pub fn compute_root(
+        self,
+        leaf: Hash,
+        proof: &[Hash],
+        leaf_index: u32,
+        hash_pair: impl Fn(&Hash, &Hash) -> Hash,
+    ) -> Hash {
+        match self {
+            LeafFormat::Solana => compute_root(leaf, proof, leaf_index, hash_pair),
+            LeafFormat::Evm => proof.iter().fold(leaf, |hash, sibling| {
+                if *sibling < hash {
+                    hash_pair(sibling, &hash)
+                } else {
+                    hash_pair(&hash, sibling)
+                }
+            }),
+        }
+    }
+}
+
+/// Exact bytes hashed for a leaf in [`LeafFormat::Solana`].
+pub fn serialize_leaf(
+    owner: &[u8; 32],
+    cycle_index: u64,
+    delta_points: i32,
+    leaf_index: u32,
+) -> [u8; LEAF_LEN] {
+    let mut v = [0u8; LEAF_LEN];
+    v[..32].copy_from_slice(owner);
+    v[32..40].copy_from_slice(&cycle_index.to_le_bytes());
+    v[40..44].copy_from_slice(&delta_points.to_le_bytes());
+    v[44..48].copy_from_slice(&leaf_index.to_le_bytes());
+    v
+}
+
+/// Folds `proof` into `leaf` in [`LeafFormat::Solana`], `hash_pair(left, right)`
+/// hashing each parent.
+pub fn compute_root(
+    leaf: Hash,
+    proof: &[Hash],
+    leaf_index: u32,
+    hash_pair: impl Fn(&Hash, &Hash) -> Hash,
+) -> Hash {
+    let mut idx = leaf_index;
+    let mut hash = leaf;
+    for sibling in proof {
+        hash = if idx & 1 == 1 {
+            hash_pair(sibling, &hash)
+        } else {
+            hash_pair(&hash, sibling)
+        };
+        idx >>= 1;
+    }
+    hash
+}
+
+/// [`LeafFormat::compute_root`] hashing with the `sol_keccak256` syscall: one call per
+/// level on two 32-byte slices held on the stack, with nothing allocated in between.
+#[cfg(all(feature = "solana", target_os = "solana"))]
+pub fn compute_root_keccak(
+    format: LeafFormat,
+    leaf: Hash,
+    proof: &[Hash],
+    leaf_index: u32,
+) -> Hash {
+    extern "C" {
+        fn sol_keccak256(vals: *const u8, val_len: u64, hash_result: *mut u8) -> u64;
+    }
+    format.compute_root(leaf, proof, leaf_index, |left, right| {
+        let pair: [&[u8]; 2] = [left, right];
+        let mut parent = [0u8; 32];
+        // SAFETY: `pair` is the array of slices the syscall reads, and `parent` has room
+        // for the 32 bytes it writes.
+        unsafe { sol_keccak256(pair.as_ptr() as *const u8, 2, parent.as_mut_ptr()) };
+        parent
+    })
+}
+
+// ============================================================================
+// Claims bitmap
+// ============================================================================
+
+/// Bytes of claim bitmap needed for `leaf_count` leaves (one bit per leaf index).
+pub fn claims_bitmap_len(leaf_count: u32) -> u32 {
+    leaf_count.div_ceil(8)
+}
+
+/// Whether leaf `index` is marked claimed; indices past the bitmap are not.
+pub fn is_claimed(bitmap: &[u8], index: u32) -> bool {
+    bitmap
+        .get((index / 8) as usize)
+        .is_some_and(|byte| byte & (1u8 << (index % 8)) != 0)
+}
+
+/// Marks leaf `index` claimed; `false` if the bitmap has no bit for it.
+pub fn set_claimed(bitmap: &mut [u8], index: u32) -> bool {
+    match bitmap.get_mut((index / 8) as usize) {
+        Some(byte) => {
+            *byte |= 1u8 << (index % 8);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,26 @@
+//! Formats shared by the SentinelKarma programs and every off-chain tool.
+//!
+//! - [`leaf`] is the seka claim leaf and proof folding, in the Solana and EVM formats,
+//!   which `claim_karma` verifies and `seka-merkle` builds trees from
+//! - [`seka`] and [`sentinel`] hold each program's PDA seeds and the constants off-chain
+//!   tools need to agree with; [`seka::events`] are seka's events
+//! - [`cluster`] is the cluster a build targets, with its program ids and defaults
+//! - [`error`] numbers every error of both programs in one space, by subsystem
+//! - [`version`] is how account layouts change without breaking accounts on chain
+//!
+//! The crate is `no_std` and has no dependencies, so the programs and any client can
+//! take it as is. Events and errors are declared by macros: the programs and the SDK
+//! expand them with `anchor` into `#[event]`s and `#[error_code]`s of their own, which
+//! is what Anchor's IDL build needs, and this crate expands them into plain types.
+
+#![no_std]
+
+pub mod cluster;
+pub mod error;
+pub mod leaf;
+pub mod seka;
+pub mod sentinel;
+pub mod version;
+
+/// An account address, as the 32 bytes of a `Pubkey`.
+pub type Address = [u8; 32];
@@ -0,0 +1,169 @@
+//! seka: PDA seeds, config constants and events.
+
+use crate::cluster::CLUSTER;
+
+/// Decimals of the SEKA mint `initialize` creates.
+pub const DEFAULT_DECIMALS: u8 = 6;
+pub const DEFAULT_CYCLE_SECS: u64 = CLUSTER.seka_cycle_secs();
+pub const DEFAULT_MAX_POINTS_PER_CYCLE: u32 = 10_000;
+pub const DEFAULT_PER_PEER_CYCLE_CAP: i32 = 100;
+pub const DEFAULT_CONVERSION_RATIO: u32 = 100; // KP per 1 SEKA
+/// Cycles after which an unclaimed leaf is forfeit and the cycle's rent can be reclaimed
+pub const CLAIM_WINDOW_CYCLES: u64 = 30; // ~90 days at mainnet's cycle length
+
+pub mod seeds {
+    pub const CONFIG_SEED: &[u8] = b"config";
+    pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+    pub const TREASURY_SEED: &[u8] = b"treasury";
+    /// Followed by the cycle index, u64 LE
+    pub const CYCLE_SEED: &[u8] = b"cycle";
+    /// `PeerLedger`, followed by the owner
+    pub const PEER_SEED: &[u8] = b"peer";
+    /// `Membership`, followed by the member
+    pub const MEMBER_SEED: &[u8] = b"member";
+    pub const GOVERNOR_TRANSFER_SEED: &[u8] = b"governor_transfer";
+}
+
+pub mod events {
+    use crate::Address;
+
+    /// Every seka event, in declaration order.
+    pub const EVENT_NAMES: &[&str] = &[
+        "Initialized",
+        "ConfigUpdated",
+        "CycleRootSet",
+        "KarmaClaimed",
+        "PointsCredited",
+        "TokensIssued",
+        "PointsConverted",
+        "Joined",
+        "MembershipDeactivated",
+        "GovernorNominated",
+        "GovernorAccepted",
+        "CycleClosed",
+        "AccountMigrated",
+    ];
+
+    crate::seka_events!(plain);
+}
+
+/// Declares seka's events in the calling crate: `anchor` makes them `#[event]`s over
+/// `Pubkey` (with `anchor_lang::prelude::*` in scope), `plain` plain structs over
+/// [`Address`](crate::Address), as in [`events`].
+#[macro_export]
+macro_rules! seka_events {
+    (anchor) => {
+        $crate::seka_events!(@declare [#[anchor_lang::event]] Pubkey);
+    };
+    (plain) => {
+        $crate::seka_events!(@declare [] Address);
+    };
+    (@declare [$(#[$attr:meta])*] $address:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Initialized {
+            pub governor: $address,
+            pub mint: $address,
+            pub treasury: $address,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct ConfigUpdated {
+            pub cycle_secs: u64,
+            pub max_points_per_cycle: u32,
+            pub per_peer_cycle_cap: i32,
+            pub conversion_ratio: u32,
+            pub join_cost_tokens: u64,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct CycleRootSet {
+            pub cycle_index: u64,
+            pub merkle_root: [u8; 32],
+            pub total_points_declared: u32,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct KarmaClaimed {
+            pub owner: $address,
+            pub cycle_index: u64,
+            pub delta_points: i32,
+            pub new_points: i64,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct PointsCredited {
+            pub owner: $address,
+            pub issuer: $address,
+            pub points: u64,
+            pub new_points: i64,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct TokensIssued {
+            pub issuer: $address,
+            pub recipient: $address,
+            pub amount: u64,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct PointsConverted {
+            pub owner: $address,
+            pub tokens_minted: u64,
+            pub points_spent: u64,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Joined {
+            pub member: $address,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct MembershipDeactivated {
+            pub member: $address,
+            /// Governor, or the member themselves for `deactivate_self`
+            pub actor: $address,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct GovernorNominated {
+            pub current: $address,
+            /// `Pubkey::default()` when a nomination is cancelled
+            pub pending: $address,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct GovernorAccepted {
+            pub previous: $address,
+            pub governor: $address,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct CycleClosed {
+            pub cycle_index: u64,
+            /// Rent returned to the governor
+            pub lamports: u64,
+            /// Closed because every leaf was claimed rather than because the window ended
+            pub fully_claimed: bool,
+        }
+
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct AccountMigrated {
+            pub account: $address,
+            pub from_version: u8,
+            pub to_version: u8,
+        }
+    };
+}
@@ -0,0 +1,49 @@
+//! sentinel: PDA seeds and the constants its account layouts and defaults depend on.
+//!
+//! sentinel's events stay in the program crate: the SDK re-exports that crate whole, so
+//! there is no second copy to drift.
+
+use crate::cluster::CLUSTER;
+
+/// Decimals of the SENTINEL mint `initialize` creates.
+pub const SENTINEL_DECIMALS: u8 = 9; // standard SPL decimals
+pub const CYCLE_SECONDS: i64 = CLUSTER.sentinel_cycle_seconds();
+pub const CYCLE_REWARD_TOTAL: u64 = 1_000 * 10u64.pow(SENTINEL_DECIMALS as u32);
+pub const DEFAULT_MAX_LIKES_PER_CYCLE: u32 = 50;
+pub const DEFAULT_MAX_LIKES_PER_PAIR: u32 = 5;
+pub const DEFAULT_LIKE_REWARD_WINDOW: u64 = 1; // cycles, counting the post's own
+/// Bytes of a post tag, zero-padded
+pub const TAG_LEN: usize = 16;
+pub const TOP_POSTS_LEN: usize = 16;
+pub const KARMA_HISTORY_LEN: usize = 32; // most recent settled cycles kept per peer
+pub const MAX_CONTENT_ADDR_LEN: usize = 64; // binary CIDv1 with up to a 60-byte multihash
+
+pub mod seeds {
+    pub const STATE_SEED: &[u8] = b"state";
+    pub const TREASURY_VAULT_SEED: &[u8] = b"treasury";
+    pub const PEER_SEED: &[u8] = b"peer";
+    pub const POST_SEED: &[u8] = b"post";
+    pub const LIKE_SEED: &[u8] = b"like";
+    pub const LIKE_PAIR_SEED: &[u8] = b"like_pair";
+    pub const BOND_SEED: &[u8] = b"bond";
+    pub const INVITE_SEED: &[u8] = b"invite";
+    pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
+    pub const POST_INDEX_SEED: &[u8] = b"post_index";
+    pub const USER_POST_SEED: &[u8] = b"user_post";
+    pub const AUTHOR_INDEX_SEED: &[u8] = b"author_index";
+    pub const TOP_POSTS_SEED: &[u8] = b"top_posts";
+    pub const CYCLE_RECORD_SEED: &[u8] = b"cycle_record";
+    pub const VESTING_SEED: &[u8] = b"vesting";
+    pub const LISTING_SEED: &[u8] = b"listing";
+    pub const USERNAME_SEED: &[u8] = b"username";
+    pub const ACHIEVEMENT_SEED: &[u8] = b"achievement";
+    pub const BADGE_SEED: &[u8] = b"badge";
+    pub const COMMENT_SEED: &[u8] = b"comment";
+    pub const KARMA_HISTORY_SEED: &[u8] = b"karma_history";
+    pub const POST_COMMIT_SEED: &[u8] = b"post_commit";
+    pub const STORAGE_PROVIDER_SEED: &[u8] = b"storage_provider";
+    pub const REPORT_SEED: &[u8] = b"report";
+    pub const REPORT_BOND_SEED: &[u8] = b"report_bond";
+    pub const TAG_SEED: &[u8] = b"tag";
+    pub const TAG_KARMA_SEED: &[u8] = b"tag_karma";
+}
@@ -0,0 +1,43 @@
+//! Layout versions of program accounts.
+//!
+//! Every account of both programs ends in a version byte and reserved bytes. A new field
+//! takes reserved bytes, or is appended when none are left, and the layout version goes
+//! up. Layouts only grow at the end, so an account written by an older layout reads as
+//! the current one with its missing tail zeroed: that is what each program's
+//! `migrate_accounts` leaves on chain after growing it, and what the SDK's decoders do
+//! off chain. [`Versioned::migrate`] then fills in whatever zero does not stand for.
+
+pub trait Versioned {
+    /// Layout version new accounts are written with. Zero initialization writes version
+    /// 0; past it, whatever creates the account has to.
+    const VERSION: u8;
+    /// Bytes of the current layout, discriminator included; for an account with a
+    /// variable-length field, those of its fixed part.
+    const SPACE: usize;
+
+    fn version(&self) -> u8;
+
+    /// Brings an account read from an older layout, its missing tail zeroed, up to
+    /// [`Self::VERSION`].
+    fn migrate(&mut self);
+}
+
+/// Implements [`Versioned`] for an account with a `version` field whose older layouts
+/// differ only by a zeroed tail, so migrating one just stamps `$version`.
+#[macro_export]
+macro_rules! versioned {
+    ($account:ty, $version:expr, $space:expr) => {
+        impl $crate::version::Versioned for $account {
+            const VERSION: u8 = $version;
+            const SPACE: usize = $space;
+
+            fn version(&self) -> u8 {
+                self.version
+            }
+
+            fn migrate(&mut self) {
+                self.version = Self::VERSION;
+            }
+        }
+    };
+}
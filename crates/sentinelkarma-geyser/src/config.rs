@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Plugin config file, passed to the validator with `--geyser-plugin-config`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Read by the validator to locate this library
+    #[allow(dead_code)]
+    pub libpath: String,
+    /// Program ids to follow; defaults to seka and sentinel. Set for other deployments.
+    #[serde(default)]
+    pub programs: Option<Vec<String>>,
+    /// Forward updates of accounts owned by the programs
+    #[serde(default = "yes")]
+    pub accounts: bool,
+    /// Also forward the snapshot accounts notified at startup
+    #[serde(default)]
+    pub startup_accounts: bool,
+    /// Forward transactions that reference the programs
+    #[serde(default = "yes")]
+    pub transactions: bool,
+    /// Forward failed transactions too (they still pay fees and move no state)
+    #[serde(default = "yes")]
+    pub include_failed: bool,
+    /// Records buffered for the sink before validator callbacks block
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+    pub sink: SinkConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// Appends newline-delimited JSON to a file
+    File { path: String },
+    /// Streams newline-delimited JSON to a TCP collector, reconnecting as needed
+    Tcp { address: String },
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn default_queue_capacity() -> usize {
+    100_000
+}
+
+impl Config {
+    pub fn program_ids(&self) -> Result<Vec<Pubkey>, String> {
+        match &self.programs {
+            None => Ok(vec![
+                sentinelkarma_sdk::seka::ID,
+                sentinelkarma_sdk::sentinel::ID,
+            ]),
+            Some(ids) => ids
+                .iter()
+                .map(|id| Pubkey::from_str(id).map_err(|e| format!("program id {id}: {e}")))
+                .collect(),
+        }
+    }
+}
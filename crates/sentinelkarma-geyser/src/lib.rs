@@ -0,0 +1,319 @@
+//! Geyser plugin that forwards seka and sentinel state straight from a validator.
+//!
+//! RPC websocket subscriptions drop notifications under load; this plugin sees every
+//! account write and transaction as the bank processes it. It emits newline-delimited
+//! JSON records to the configured sink (see `config.example.json`):
+//!
+//! - `{"type": "account", "slot", "pubkey", "owner", "lamports", "executable",
+//!   "rentEpoch", "data" (base64), "writeVersion", "txnSignature", "isStartup"}` for
+//!   accounts owned by the followed programs;
+//! - `{"type": "transaction", "signature", "index", ..}` for non-vote transactions
+//!   referencing them, the rest being exactly a base64 `getTransaction` response, so
+//!   `sentinelkarma_indexer::ingest::rows_for` decodes it unchanged;
+//! - `{"type": "slot", "slot", "parent", "status"}` for every slot status change.
+//!
+//! Records are produced at `processed` level. Consumers must hold them until the slot
+//! reports `confirmed` (or `rooted`) and discard slots of abandoned forks.
+//!
+//! Validator callbacks only serialize and enqueue; a worker thread owns the sink. When
+//! the queue is full the callbacks block rather than drop records.
+//!
+//! The plugin must be built with the same Rust toolchain and Solana version as the
+//! validator that loads it.
+
+mod config;
+mod sink;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use config::Config;
+use serde_json::json;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, ReplicaTransactionInfoVersions,
+    Result as PluginResult, SlotStatus,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::SanitizedTransaction;
+use solana_transaction_status::{
+    ConfirmedTransactionWithStatusMeta, TransactionStatusMeta, TransactionWithStatusMeta,
+    UiTransactionEncoding, VersionedTransactionWithStatusMeta,
+};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::thread::JoinHandle;
+
+#[derive(Default)]
+pub struct Plugin {
+    inner: Option<Inner>,
+}
+
+struct Inner {
+    programs: Vec<Pubkey>,
+    accounts: bool,
+    startup_accounts: bool,
+    transactions: bool,
+    include_failed: bool,
+    tx: SyncSender<Vec<u8>>,
+    worker: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin").finish_non_exhaustive()
+    }
+}
+
+fn config_error(msg: impl ToString) -> GeyserPluginError {
+    GeyserPluginError::ConfigFileReadError {
+        msg: msg.to_string(),
+    }
+}
+
+fn custom(e: impl std::error::Error + Send + Sync + 'static) -> GeyserPluginError {
+    GeyserPluginError::Custom(Box::new(e))
+}
+
+impl Inner {
+    fn send(&self, mut record: Vec<u8>) -> PluginResult<()> {
+        record.push(b'\n');
+        self.tx
+            .send(record)
+            .map_err(|_| custom(std::io::Error::other("sink worker stopped")))
+    }
+
+    fn follows(&self, key: &Pubkey) -> bool {
+        self.programs.contains(key)
+    }
+}
+
+impl GeyserPlugin for Plugin {
+    fn name(&self) -> &'static str {
+        "sentinelkarma-geyser"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> PluginResult<()> {
+        let text = std::fs::read_to_string(config_file)?;
+        let config: Config = serde_json::from_str(&text).map_err(config_error)?;
+        let programs = config.program_ids().map_err(config_error)?;
+        let mut sink = sink::open(&config.sink)?;
+
+        let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(config.queue_capacity);
+        let worker = std::thread::Builder::new()
+            .name("skGeyserSink".into())
+            .spawn(move || drain(&rx, sink.as_mut()))?;
+
+        log::info!(
+            "following {} program(s), sink {:?}",
+            programs.len(),
+            config.sink
+        );
+        self.inner = Some(Inner {
+            programs,
+            accounts: config.accounts,
+            startup_accounts: config.startup_accounts,
+            transactions: config.transactions,
+            include_failed: config.include_failed,
+            tx,
+            worker,
+        });
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            // Closing the channel lets the worker flush what is queued and exit
+            drop(inner.tx);
+            let _ = inner.worker.join();
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> PluginResult<()> {
+        let Some(inner) = &self.inner else {
+            return Ok(());
+        };
+        if is_startup && !inner.startup_accounts {
+            return Ok(());
+        }
+        let (pubkey, owner, lamports, executable, rent_epoch, data, write_version, txn) =
+            match account {
+                ReplicaAccountInfoVersions::V0_0_1(a) => (
+                    a.pubkey,
+                    a.owner,
+                    a.lamports,
+                    a.executable,
+                    a.rent_epoch,
+                    a.data,
+                    a.write_version,
+                    None,
+                ),
+                ReplicaAccountInfoVersions::V0_0_2(a) => (
+                    a.pubkey,
+                    a.owner,
+                    a.lamports,
+                    a.executable,
+                    a.rent_epoch,
+                    a.data,
+                    a.write_version,
+                    a.txn_signature.copied(),
+                ),
+                ReplicaAccountInfoVersions::V0_0_3(a) => (
+                    a.pubkey,
+                    a.owner,
+                    a.lamports,
+                    a.executable,
+                    a.rent_epoch,
+                    a.data,
+                    a.write_version,
+                    a.txn.map(|t| *t.signature()),
+                ),
+            };
+        let (Ok(pubkey), Ok(owner)) = (Pubkey::try_from(pubkey), Pubkey::try_from(owner)) else {
+            return Ok(());
+        };
+        if !inner.follows(&owner) {
+            return Ok(());
+        }
+        let record = json!({
+            "type": "account",
+            "slot": slot,
+            "pubkey": pubkey.to_string(),
+            "owner": owner.to_string(),
+            "lamports": lamports,
+            "executable": executable,
+            "rentEpoch": rent_epoch,
+            "data": STANDARD.encode(data),
+            "writeVersion": write_version,
+            "txnSignature": txn.map(|s: Signature| s.to_string()),
+            "isStartup": is_startup,
+        });
+        inner.send(serde_json::to_vec(&record).map_err(custom)?)
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    ) -> PluginResult<()> {
+        let Some(inner) = &self.inner else {
+            return Ok(());
+        };
+        let record = json!({
+            "type": "slot",
+            "slot": slot,
+            "parent": parent,
+            "status": status.as_str(),
+        });
+        inner.send(serde_json::to_vec(&record).map_err(custom)?)
+    }
+
+    fn notify_transaction(
+        &self,
+        transaction: ReplicaTransactionInfoVersions,
+        slot: u64,
+    ) -> PluginResult<()> {
+        let Some(inner) = &self.inner else {
+            return Ok(());
+        };
+        let (signature, is_vote, tx, meta, index) = match transaction {
+            ReplicaTransactionInfoVersions::V0_0_1(t) => (
+                t.signature,
+                t.is_vote,
+                t.transaction,
+                t.transaction_status_meta,
+                None,
+            ),
+            ReplicaTransactionInfoVersions::V0_0_2(t) => (
+                t.signature,
+                t.is_vote,
+                t.transaction,
+                t.transaction_status_meta,
+                Some(t.index),
+            ),
+        };
+        if is_vote || (meta.status.is_err() && !inner.include_failed) {
+            return Ok(());
+        }
+        if !tx
+            .message()
+            .account_keys()
+            .iter()
+            .any(|key| inner.follows(key))
+        {
+            return Ok(());
+        }
+        let mut record = encode_transaction(slot, tx, meta)?;
+        record["type"] = json!("transaction");
+        record["signature"] = json!(signature.to_string());
+        record["index"] = json!(index);
+        inner.send(serde_json::to_vec(&record).map_err(custom)?)
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.inner.as_ref().is_some_and(|inner| inner.accounts)
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.inner.as_ref().is_some_and(|inner| inner.transactions)
+    }
+}
+
+/// The transaction as `getTransaction` returns it with base64 encoding.
+fn encode_transaction(
+    slot: u64,
+    tx: &SanitizedTransaction,
+    meta: &TransactionStatusMeta,
+) -> PluginResult<serde_json::Value> {
+    let confirmed = ConfirmedTransactionWithStatusMeta {
+        slot,
+        tx_with_meta: TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+            transaction: tx.to_versioned_transaction(),
+            meta: meta.clone(),
+        }),
+        block_time: None,
+    };
+    let encoded = confirmed
+        .encode(UiTransactionEncoding::Base64, Some(0))
+        .map_err(|e| GeyserPluginError::TransactionUpdateError { msg: e.to_string() })?;
+    serde_json::to_value(encoded).map_err(custom)
+}
+
+fn drain(rx: &Receiver<Vec<u8>>, sink: &mut dyn sink::Sink) {
+    loop {
+        let record = match rx.try_recv() {
+            Ok(record) => record,
+            Err(TryRecvError::Empty) => {
+                if let Err(e) = sink.flush() {
+                    log::error!("flushing sink: {e}");
+                }
+                match rx.recv() {
+                    Ok(record) => record,
+                    Err(_) => break,
+                }
+            }
+            Err(TryRecvError::Disconnected) => break,
+        };
+        if let Err(e) = sink.write(&record) {
+            // A sink that cannot write loses data; stop loudly rather than skip records
+            log::error!("writing to sink: {e}; dropping the plugin's queue");
+            break;
+        }
+    }
+    if let Err(e) = sink.flush() {
+        log::error!("flushing sink: {e}");
+    }
+}
+
+/// # Safety
+///
+/// Called by the validator's plugin manager, which takes ownership of the plugin.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    Box::into_raw(Box::<Plugin>::default())
+}
@@ -0,0 +1,89 @@
+//! Record sinks. They run on the plugin's worker thread, never on validator threads.
+
+use crate::config::SinkConfig;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+pub trait Sink: Send {
+    /// Writes one newline-terminated record. Must not drop it: block or fail instead.
+    fn write(&mut self, line: &[u8]) -> io::Result<()>;
+
+    /// Called whenever the queue drains.
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+pub fn open(config: &SinkConfig) -> io::Result<Box<dyn Sink>> {
+    Ok(match config {
+        SinkConfig::File { path } => Box::new(FileSink::open(path)?),
+        SinkConfig::Tcp { address } => Box::new(TcpSink {
+            address: address.clone(),
+            stream: None,
+        }),
+    })
+}
+
+struct FileSink {
+    out: BufWriter<File>,
+}
+
+impl FileSink {
+    fn open(path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            out: BufWriter::with_capacity(1 << 20, file),
+        })
+    }
+}
+
+impl Sink for FileSink {
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        self.out.write_all(line)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Unbuffered, so a dropped connection loses at most what the kernel had not yet sent;
+/// there are no acknowledgements. Use the file sink where even that is unacceptable.
+struct TcpSink {
+    address: String,
+    stream: Option<TcpStream>,
+}
+
+impl Sink for TcpSink {
+    /// Retries until the collector accepts the record; the queue absorbs the backlog.
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        loop {
+            let stream = match &mut self.stream {
+                Some(stream) => stream,
+                None => match TcpStream::connect(&self.address) {
+                    Ok(stream) => self.stream.insert(stream),
+                    Err(e) => {
+                        log::warn!("connecting to {}: {e}", self.address);
+                        thread::sleep(RECONNECT_DELAY);
+                        continue;
+                    }
+                },
+            };
+            match stream.write_all(line) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    log::warn!("writing to {}: {e}", self.address);
+                    self.stream = None;
+                    thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
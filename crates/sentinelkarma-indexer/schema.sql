@@ -0,0 +1,36 @@
+-- Shared by the Postgres and SQLite stores; keep it to types and syntax both accept.
+
+-- One row per successful top-level instruction of either program
+CREATE TABLE IF NOT EXISTS instructions (
+    signature  TEXT    NOT NULL,
+    ix_index   INTEGER NOT NULL,
+    slot       BIGINT  NOT NULL,
+    block_time BIGINT,
+    program    TEXT    NOT NULL, -- 'seka' | 'sentinel'
+    name       TEXT    NOT NULL, -- snake_case instruction name, or 'unknown'
+    data       BYTEA   NOT NULL, -- full instruction data, discriminator included
+    accounts   TEXT    NOT NULL, -- space-separated base58 account keys, in order
+    PRIMARY KEY (signature, ix_index)
+);
+CREATE INDEX IF NOT EXISTS instructions_name_slot ON instructions (program, name, slot);
+
+-- One row per decoded event (`emit!` logs and `emit_cpi!` inner instructions)
+CREATE TABLE IF NOT EXISTS events (
+    signature   TEXT    NOT NULL,
+    event_index INTEGER NOT NULL, -- order within the transaction
+    slot        BIGINT  NOT NULL,
+    block_time  BIGINT,
+    program     TEXT    NOT NULL,
+    name        TEXT    NOT NULL, -- event struct name
+    payload     BYTEA   NOT NULL, -- discriminator + borsh body
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS events_name_slot ON events (program, name, slot);
+
+-- Newest signature processed per program ('seka', 'sentinel'), and oldest signature
+-- backfilled ('seka:backfill', 'sentinel:backfill')
+CREATE TABLE IF NOT EXISTS cursors (
+    program        TEXT   PRIMARY KEY,
+    last_signature TEXT   NOT NULL,
+    last_slot      BIGINT NOT NULL
+);
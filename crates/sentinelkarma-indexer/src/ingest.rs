@@ -0,0 +1,265 @@
+//! Pulls confirmed transactions of one program from RPC and turns them into rows.
+
+use crate::store::{EventRow, InstructionRow, Store, TransactionRows};
+use anyhow::{Context, Result};
+use sentinelkarma_sdk::{cpi_event_data, log_event_data, seka, sentinel};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInstruction, UiTransactionEncoding,
+};
+use std::str::FromStr;
+
+const PAGE_LIMIT: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Program {
+    Seka,
+    Sentinel,
+}
+
+impl Program {
+    pub const ALL: [Program; 2] = [Program::Seka, Program::Sentinel];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Program::Seka => "seka",
+            Program::Sentinel => "sentinel",
+        }
+    }
+
+    pub fn id(self) -> Pubkey {
+        match self {
+            Program::Seka => seka::ID,
+            Program::Sentinel => sentinel::ID,
+        }
+    }
+
+    fn instruction_name(self, data: &[u8]) -> Option<&'static str> {
+        match self {
+            Program::Seka => seka::instruction_name(data),
+            Program::Sentinel => sentinel::instruction_name(data),
+        }
+    }
+
+    fn event_name(self, data: &[u8]) -> Option<&'static str> {
+        match self {
+            Program::Seka => seka::event_name(data),
+            Program::Sentinel => sentinel::event_name(data),
+        }
+    }
+}
+
+/// Indexes every transaction of `program` newer than the stored cursor; returns how
+/// many were processed.
+pub fn catch_up(rpc: &RpcClient, store: &mut dyn Store, program: Program) -> Result<usize> {
+    let until = store
+        .cursor(program.name())?
+        .map(|s| Signature::from_str(&s))
+        .transpose()
+        .context("stored cursor is not a signature")?;
+
+    // Newest first from RPC; collect back to the cursor, then replay oldest first
+    let mut pending = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc.get_signatures_for_address_with_config(
+            &program.id(),
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(PAGE_LIMIT),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let done = page.len() < PAGE_LIMIT;
+        before = page
+            .last()
+            .map(|s| Signature::from_str(&s.signature))
+            .transpose()?;
+        pending.extend(page);
+        if done {
+            break;
+        }
+    }
+
+    let count = pending.len();
+    for entry in pending.into_iter().rev() {
+        index(rpc, store, program, program.name(), &entry)?;
+    }
+    Ok(count)
+}
+
+/// Cursor key of `program`'s backfill: the oldest signature indexed so far.
+pub fn backfill_cursor(program: Program) -> String {
+    format!("{}:backfill", program.name())
+}
+
+/// Indexes `program`'s history backwards from the oldest transaction backfilled so far
+/// (or from the newest, on the first run) until genesis, writing as it goes so an
+/// interrupted backfill resumes where it stopped. Transactions `catch_up` already
+/// indexed are fetched again and ignored on insert. Calls `progress` with the running
+/// count and the slot reached after each page; returns the total.
+///
+/// Only reaches genesis on an RPC node that keeps full transaction history.
+pub fn backfill(
+    rpc: &RpcClient,
+    store: &mut dyn Store,
+    program: Program,
+    mut progress: impl FnMut(usize, u64),
+) -> Result<usize> {
+    let key = backfill_cursor(program);
+    let mut before = store
+        .cursor(&key)?
+        .map(|s| Signature::from_str(&s))
+        .transpose()
+        .context("stored backfill cursor is not a signature")?;
+
+    let mut count = 0;
+    loop {
+        let page = rpc.get_signatures_for_address_with_config(
+            &program.id(),
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(PAGE_LIMIT),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let Some(last) = page.last() else {
+            return Ok(count);
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+        let last_slot = last.slot;
+
+        // Newest first, so the cursor always holds the oldest signature done
+        for entry in &page {
+            index(rpc, store, program, &key, entry)?;
+        }
+        count += page.len();
+        progress(count, last_slot);
+        if page.len() < PAGE_LIMIT {
+            return Ok(count);
+        }
+    }
+}
+
+/// Fetches and stores one transaction, moving cursor `key` to it.
+fn index(
+    rpc: &RpcClient,
+    store: &mut dyn Store,
+    program: Program,
+    key: &str,
+    entry: &RpcConfirmedTransactionStatusWithSignature,
+) -> Result<()> {
+    // Failed transactions have no effects; just move the cursor past them
+    let rows = if entry.err.is_some() {
+        TransactionRows::default()
+    } else {
+        let signature = Signature::from_str(&entry.signature)?;
+        let tx = rpc.get_transaction_with_config(&signature, tx_config())?;
+        rows_for(program, &entry.signature, &tx)
+    };
+    store.write(key, &entry.signature, entry.slot, &rows)
+}
+
+fn tx_config() -> RpcTransactionConfig {
+    RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    }
+}
+
+/// Top-level instructions and events of `program` in one successful transaction.
+pub fn rows_for(
+    program: Program,
+    signature: &str,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+) -> TransactionRows {
+    let mut rows = TransactionRows::default();
+    let Some(decoded) = tx.transaction.transaction.decode() else {
+        return rows;
+    };
+    let Some(meta) = &tx.transaction.meta else {
+        return rows;
+    };
+    let message = &decoded.message;
+
+    // Lookup-table addresses follow the static keys, writable before readonly
+    let mut keys: Vec<Pubkey> = message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for key in loaded.writable.iter().chain(&loaded.readonly) {
+            keys.extend(Pubkey::from_str(key).ok());
+        }
+    }
+    let program_id = program.id();
+    let slot = tx.slot;
+    let block_time = tx.block_time;
+
+    for (ix_index, ix) in message.instructions().iter().enumerate() {
+        if keys.get(ix.program_id_index as usize) != Some(&program_id) {
+            continue;
+        }
+        rows.instructions.push(InstructionRow {
+            signature: signature.to_string(),
+            ix_index: ix_index as u32,
+            slot,
+            block_time,
+            program: program.name().to_string(),
+            name: program
+                .instruction_name(&ix.data)
+                .unwrap_or("unknown")
+                .to_string(),
+            data: ix.data.clone(),
+            accounts: ix
+                .accounts
+                .iter()
+                .filter_map(|&i| keys.get(i as usize))
+                .map(Pubkey::to_string)
+                .collect(),
+        });
+    }
+
+    let payloads: Vec<Vec<u8>> = match program {
+        Program::Seka => match &meta.log_messages {
+            OptionSerializer::Some(logs) => log_event_data(logs),
+            _ => Vec::new(),
+        },
+        Program::Sentinel => match &meta.inner_instructions {
+            OptionSerializer::Some(inner) => inner
+                .iter()
+                .flat_map(|set| set.instructions.iter())
+                .filter_map(|ix| match ix {
+                    UiInstruction::Compiled(ix) => Some(ix),
+                    UiInstruction::Parsed(_) => None,
+                })
+                .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&program_id))
+                .filter_map(|ix| bs58::decode(&ix.data).into_vec().ok())
+                .filter_map(|data| cpi_event_data(&data).map(<[u8]>::to_vec))
+                .collect(),
+            _ => Vec::new(),
+        },
+    };
+    for payload in payloads {
+        // seka logs can carry data lines from other programs; keep only known events
+        let Some(name) = program.event_name(&payload) else {
+            continue;
+        };
+        rows.events.push(EventRow {
+            signature: signature.to_string(),
+            event_index: rows.events.len() as u32,
+            slot,
+            block_time,
+            program: program.name().to_string(),
+            name: name.to_string(),
+            payload,
+        });
+    }
+    rows
+}
@@ -0,0 +1,14 @@
+//! Indexes seka and sentinel instructions and events into SQL.
+//!
+//! Both programs are followed through `getSignaturesForAddress` from a per-program
+//! cursor; `backfill` walks the same history backwards to genesis for an index started
+//! late. Rows land in Postgres, or in an embedded SQLite file with the same
+//! [`schema`](store::SCHEMA) for small operators and tests. [`projection`] folds the
+//! rows into the peers, ledgers, cycles, claims, posts and likes that read APIs serve.
+
+pub mod ingest;
+pub mod projection;
+pub mod store;
+
+pub use ingest::{backfill, catch_up, Program};
+pub use store::{open, PgStore, SqliteStore, Store};
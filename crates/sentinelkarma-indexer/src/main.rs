@@ -0,0 +1,59 @@
+use anyhow::Result;
+use clap::Parser;
+use sentinelkarma_indexer::{backfill, catch_up, open, Program};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Index seka and sentinel transactions into Postgres or SQLite")]
+struct Cli {
+    #[arg(long, env = "RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+
+    /// postgres://… or sqlite:<path> (a bare *.db / *.sqlite path also works)
+    #[arg(long, env = "DATABASE_URL", default_value = "sqlite:indexer.db")]
+    database_url: String,
+
+    /// Seconds between polls; 0 to catch up once and exit
+    #[arg(long, default_value_t = 5)]
+    poll_secs: u64,
+
+    /// First index both programs' full history back to genesis (needs an archival RPC
+    /// node); resumes an interrupted backfill
+    #[arg(long)]
+    backfill: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.rpc_url, CommitmentConfig::confirmed());
+    let mut store = open(&cli.database_url)?;
+
+    if cli.backfill {
+        for program in Program::ALL {
+            let n = backfill(&rpc, store.as_mut(), program, |n, slot| {
+                println!(
+                    "{}: backfilled {n} transactions, down to slot {slot}",
+                    program.name()
+                )
+            })?;
+            println!("{}: backfill done ({n} transactions)", program.name());
+        }
+    }
+
+    loop {
+        for program in Program::ALL {
+            match catch_up(&rpc, store.as_mut(), program) {
+                Ok(0) => {}
+                Ok(n) => println!("{}: indexed {n} transactions", program.name()),
+                Err(e) if cli.poll_secs > 0 => eprintln!("{}: {e:#}", program.name()),
+                Err(e) => return Err(e),
+            }
+        }
+        if cli.poll_secs == 0 {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(cli.poll_secs));
+    }
+}
@@ -0,0 +1,390 @@
+//! An in-memory projection of the index that read APIs answer from.
+//!
+//! Rows are applied in `(slot, signature, index)` order, as `seka-audit` replays them.
+//! Refreshing reads only rows at or after the last slot applied; rows at that slot
+//! that were already applied are skipped. Rows backfilled behind it are picked up
+//! by a full rebuild; [`follow`] does both on a schedule.
+
+use crate::store::{EventRow, InstructionRow, Store};
+use anyhow::Result;
+use sentinelkarma_sdk::{decode_event, seka, sentinel};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Where and when something was recorded.
+#[derive(Clone, Debug)]
+pub struct At {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Peer {
+    pub owner: Pubkey,
+    pub active: bool,
+    pub joined: At,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Ledger {
+    pub points: i64,
+    pub last_cycle_claimed: u64,
+    /// Sum of positive claimed deltas
+    pub karma_claimed: u64,
+    pub points_converted: u64,
+    pub tokens_minted: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Cycle {
+    pub index: u64,
+    pub merkle_root: [u8; 32],
+    pub total_points_declared: u32,
+    pub published: At,
+    pub claims: u32,
+    pub points_claimed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Claim {
+    /// `signature:event_index`
+    pub id: String,
+    pub owner: Pubkey,
+    pub cycle_index: u64,
+    pub delta_points: i32,
+    pub new_points: i64,
+    pub at: At,
+}
+
+#[derive(Clone, Debug)]
+pub struct Post {
+    pub address: Pubkey,
+    pub asset: Pubkey,
+    pub author: Pubkey,
+    pub owner: Pubkey,
+    pub created: At,
+    pub likes: u32,
+    pub edit_count: u32,
+    /// `removed`, `closed` or `deleted`
+    pub status: Option<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Like {
+    /// `signature:ix_index`
+    pub id: String,
+    pub liker: Pubkey,
+    pub post: Pubkey,
+    pub at: At,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReportOutcome {
+    pub report: Pubkey,
+    pub post: Pubkey,
+    pub reporter: Pubkey,
+    pub upheld: bool,
+    pub at: At,
+}
+
+#[derive(Default)]
+pub struct Model {
+    pub peers: BTreeMap<Pubkey, Peer>,
+    pub ledgers: BTreeMap<Pubkey, Ledger>,
+    pub cycles: BTreeMap<u64, Cycle>,
+    /// Oldest first
+    pub claims: Vec<Claim>,
+    pub posts: BTreeMap<Pubkey, Post>,
+    /// Oldest first
+    pub likes: Vec<Like>,
+    /// Resolved reports, oldest first
+    pub reports: Vec<ReportOutcome>,
+    /// Highest slot applied
+    pub slot: u64,
+    /// Rows at `slot` already applied
+    applied: HashSet<(String, u32, u8)>,
+}
+
+enum Row<'a> {
+    Instruction(&'a InstructionRow),
+    Event(&'a EventRow),
+}
+
+impl Row<'_> {
+    fn key(&self) -> (u64, &str, u32, u8) {
+        match self {
+            Row::Instruction(r) => (r.slot, &r.signature, r.ix_index, 0),
+            Row::Event(r) => (r.slot, &r.signature, r.event_index, 1),
+        }
+    }
+}
+
+/// Rows read by [`Model::fetch`], for [`Model::apply`].
+pub struct Batch {
+    events: Vec<EventRow>,
+    instructions: Vec<InstructionRow>,
+}
+
+impl Model {
+    pub fn load(store: &mut dyn Store) -> Result<Self> {
+        let mut model = Self::default();
+        model.apply(Self::fetch(store, 0)?);
+        Ok(model)
+    }
+
+    /// Reads rows at or after `from_slot`; pass the model's `slot` to catch up.
+    pub fn fetch(store: &mut dyn Store, from_slot: u64) -> Result<Batch> {
+        let mut events = store.events("seka", None, from_slot)?;
+        events.extend(store.events("sentinel", None, from_slot)?);
+        let mut instructions = Vec::new();
+        for name in ["mint_nft", "reveal_post", "mint_core_post", "like_nft"] {
+            instructions.extend(store.instructions("sentinel", Some(name), from_slot)?);
+        }
+        Ok(Batch {
+            events,
+            instructions,
+        })
+    }
+
+    /// Applies the rows of `batch` not applied yet; returns how many.
+    pub fn apply(&mut self, batch: Batch) -> usize {
+        let mut rows: Vec<Row> = batch
+            .instructions
+            .iter()
+            .map(Row::Instruction)
+            .chain(batch.events.iter().map(Row::Event))
+            .collect();
+        rows.sort_by(|a, b| a.key().cmp(&b.key()));
+        let mut applied = 0;
+        for row in rows {
+            let (slot, signature, index, kind) = row.key();
+            if slot < self.slot {
+                continue;
+            }
+            let key = (signature.to_owned(), index, kind);
+            if slot == self.slot && self.applied.contains(&key) {
+                continue;
+            }
+            if slot > self.slot {
+                self.slot = slot;
+                self.applied.clear();
+            }
+            self.applied.insert(key);
+            match row {
+                Row::Instruction(r) => self.apply_instruction(r),
+                Row::Event(r) => self.apply_event(r),
+            }
+            applied += 1;
+        }
+        applied
+    }
+
+    fn apply_instruction(&mut self, row: &InstructionRow) {
+        let account = |i: usize| row.accounts.get(i).and_then(|a| Pubkey::from_str(a).ok());
+        let at = at(&row.signature, row.slot, row.block_time);
+        match row.name.as_str() {
+            // `user, state, peer, nft_mint | asset, ..`, as seka-stream reads them
+            "mint_nft" | "reveal_post" | "mint_core_post" => {
+                let (Some(author), Some(asset)) = (account(0), account(3)) else {
+                    return;
+                };
+                let address = sentinel::post_pda(&asset);
+                self.posts.entry(address).or_insert(Post {
+                    address,
+                    asset,
+                    author,
+                    owner: author,
+                    created: at,
+                    likes: 0,
+                    edit_count: 0,
+                    status: None,
+                });
+            }
+            // `liker, state, like, post, ..`
+            "like_nft" => {
+                let (Some(liker), Some(post)) = (account(0), account(3)) else {
+                    return;
+                };
+                if let Some(post) = self.posts.get_mut(&post) {
+                    post.likes += 1;
+                }
+                let id = format!("{}:{}", row.signature, row.ix_index);
+                self.likes.push(Like {
+                    id,
+                    liker,
+                    post,
+                    at,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_event(&mut self, row: &EventRow) {
+        let payload = &row.payload;
+        let at = at(&row.signature, row.slot, row.block_time);
+        match (row.program.as_str(), row.name.as_str()) {
+            ("seka", "CycleRootSet") => {
+                if let Some(e) = decode_event::<seka::CycleRootSet>(payload) {
+                    let cycle = self.cycles.entry(e.cycle_index).or_insert(Cycle {
+                        index: e.cycle_index,
+                        merkle_root: e.merkle_root,
+                        total_points_declared: e.total_points_declared,
+                        published: at.clone(),
+                        claims: 0,
+                        points_claimed: 0,
+                    });
+                    cycle.published = at;
+                }
+            }
+            ("seka", "KarmaClaimed") => {
+                if let Some(e) = decode_event::<seka::KarmaClaimed>(payload) {
+                    let ledger = self.ledgers.entry(e.owner).or_default();
+                    ledger.points = e.new_points;
+                    ledger.last_cycle_claimed = ledger.last_cycle_claimed.max(e.cycle_index);
+                    ledger.karma_claimed += e.delta_points.max(0) as u64;
+                    if let Some(cycle) = self.cycles.get_mut(&e.cycle_index) {
+                        cycle.claims += 1;
+                        cycle.points_claimed += e.delta_points.max(0) as u64;
+                    }
+                    self.claims.push(Claim {
+                        id: format!("{}:{}", row.signature, row.event_index),
+                        owner: e.owner,
+                        cycle_index: e.cycle_index,
+                        delta_points: e.delta_points,
+                        new_points: e.new_points,
+                        at,
+                    });
+                }
+            }
+            ("seka", "PointsCredited") => {
+                if let Some(e) = decode_event::<seka::PointsCredited>(payload) {
+                    self.ledgers.entry(e.owner).or_default().points = e.new_points;
+                }
+            }
+            ("seka", "PointsConverted") => {
+                if let Some(e) = decode_event::<seka::PointsConverted>(payload) {
+                    let ledger = self.ledgers.entry(e.owner).or_default();
+                    ledger.points = ledger.points.saturating_sub(e.points_spent as i64);
+                    ledger.points_converted += e.points_spent;
+                    ledger.tokens_minted += e.tokens_minted;
+                }
+            }
+            ("seka", "Joined") => {
+                if let Some(e) = decode_event::<seka::Joined>(payload) {
+                    let peer = Peer {
+                        owner: e.member,
+                        active: true,
+                        joined: at,
+                    };
+                    self.peers.insert(e.member, peer);
+                }
+            }
+            ("seka", "MembershipDeactivated") => {
+                if let Some(e) = decode_event::<seka::MembershipDeactivated>(payload) {
+                    if let Some(peer) = self.peers.get_mut(&e.member) {
+                        peer.active = false;
+                    }
+                }
+            }
+            ("sentinel", "PostUpdated") => {
+                if let Some(e) = decode_event::<sentinel::PostUpdated>(payload) {
+                    if let Some(post) = self.posts.get_mut(&e.post) {
+                        post.edit_count = e.edit_count;
+                    }
+                }
+            }
+            ("sentinel", "ReportResolved") => {
+                if let Some(e) = decode_event::<sentinel::ReportResolved>(payload) {
+                    self.reports.push(ReportOutcome {
+                        report: e.report,
+                        post: e.post,
+                        reporter: e.reporter,
+                        upheld: e.upheld,
+                        at,
+                    });
+                }
+            }
+            ("sentinel", "PostRemoved") => {
+                if let Some(e) = decode_event::<sentinel::PostRemoved>(payload) {
+                    self.set_status(&e.post, "removed");
+                }
+            }
+            ("sentinel", "PostClosed") => {
+                if let Some(e) = decode_event::<sentinel::PostClosed>(payload) {
+                    self.set_status(&e.post, "closed");
+                }
+            }
+            ("sentinel", "PostDeleted") => {
+                if let Some(e) = decode_event::<sentinel::PostDeleted>(payload) {
+                    self.set_status(&e.post, "deleted");
+                }
+            }
+            ("sentinel", "PostOwnerSynced") => {
+                if let Some(e) = decode_event::<sentinel::PostOwnerSynced>(payload) {
+                    if let Some(post) = self.posts.get_mut(&e.post) {
+                        post.owner = e.owner;
+                    }
+                }
+            }
+            ("sentinel", "PostSold") => {
+                if let Some(e) = decode_event::<sentinel::PostSold>(payload) {
+                    if let Some(post) = self.posts.get_mut(&e.post) {
+                        post.owner = e.buyer;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_status(&mut self, post: &Pubkey, status: &'static str) {
+        if let Some(post) = self.posts.get_mut(post) {
+            post.status = Some(status);
+        }
+    }
+}
+
+fn at(signature: &str, slot: u64, block_time: Option<i64>) -> At {
+    At {
+        signature: signature.to_owned(),
+        slot,
+        block_time,
+    }
+}
+
+/// Keeps `model` current: new rows every `every`, a full rebuild every `rebuild`.
+///
+/// Blocks forever, so run it on its own thread; the store's clients are blocking. A
+/// failed refresh is logged and retried on a fresh connection at the next tick.
+pub fn follow(model: Arc<RwLock<Model>>, database_url: &str, every: Duration, rebuild: Duration) {
+    let mut store: Option<Box<dyn Store>> = None;
+    let mut rebuilt = Instant::now();
+    loop {
+        std::thread::sleep(every);
+        let result = (|| -> Result<()> {
+            let store = match &mut store {
+                Some(store) => store,
+                None => store.insert(crate::open(database_url)?),
+            };
+            if rebuilt.elapsed() >= rebuild {
+                let fresh = Model::load(store.as_mut())?;
+                *model.write().unwrap() = fresh;
+                rebuilt = Instant::now();
+                return Ok(());
+            }
+            let slot = model.read().unwrap().slot;
+            let batch = Model::fetch(store.as_mut(), slot)?;
+            model.write().unwrap().apply(batch);
+            Ok(())
+        })();
+        if let Err(e) = result {
+            eprintln!("refresh failed: {e:#}");
+            store = None;
+        }
+    }
+}
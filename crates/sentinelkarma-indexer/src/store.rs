@@ -0,0 +1,340 @@
+//! Storage backends. Both run `schema.sql` unchanged and share every statement:
+//! SQLite binds `$1, $2, ..` positionally just like Postgres.
+
+use anyhow::{bail, Context, Result};
+
+pub const SCHEMA: &str = include_str!("../schema.sql");
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstructionRow {
+    pub signature: String,
+    pub ix_index: u32,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub program: String,
+    pub name: String,
+    pub data: Vec<u8>,
+    pub accounts: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventRow {
+    pub signature: String,
+    pub event_index: u32,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub program: String,
+    pub name: String,
+    pub payload: Vec<u8>,
+}
+
+/// Everything indexed from one transaction, written atomically with the cursor.
+#[derive(Default, Debug)]
+pub struct TransactionRows {
+    pub instructions: Vec<InstructionRow>,
+    pub events: Vec<EventRow>,
+}
+
+pub trait Store {
+    fn cursor(&mut self, program: &str) -> Result<Option<String>>;
+
+    /// Inserts `rows` (idempotently) and advances `program`'s cursor to `signature`.
+    fn write(
+        &mut self,
+        program: &str,
+        signature: &str,
+        slot: u64,
+        rows: &TransactionRows,
+    ) -> Result<()>;
+
+    /// Events at or after `from_slot`, oldest first; `name = None` for all.
+    fn events(
+        &mut self,
+        program: &str,
+        name: Option<&str>,
+        from_slot: u64,
+    ) -> Result<Vec<EventRow>>;
+
+    /// Instructions at or after `from_slot`, oldest first; `name = None` for all.
+    fn instructions(
+        &mut self,
+        program: &str,
+        name: Option<&str>,
+        from_slot: u64,
+    ) -> Result<Vec<InstructionRow>>;
+}
+
+/// `postgres://…` / `postgresql://…` for Postgres, `sqlite:<path>` or a bare `*.db` /
+/// `*.sqlite` path for an embedded SQLite file.
+pub fn open(url: &str) -> Result<Box<dyn Store>> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        return Ok(Box::new(PgStore::connect(url)?));
+    }
+    let path = url
+        .strip_prefix("sqlite:")
+        .or_else(|| (url.ends_with(".db") || url.ends_with(".sqlite")).then_some(url));
+    match path {
+        Some(path) => Ok(Box::new(SqliteStore::open(path)?)),
+        None => bail!("unsupported database url {url:?}"),
+    }
+}
+
+const INSERT_INSTRUCTION: &str = "INSERT INTO instructions \
+     (signature, ix_index, slot, block_time, program, name, data, accounts) \
+     VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT DO NOTHING";
+const INSERT_EVENT: &str = "INSERT INTO events \
+     (signature, event_index, slot, block_time, program, name, payload) \
+     VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT DO NOTHING";
+const UPSERT_CURSOR: &str = "INSERT INTO cursors (program, last_signature, last_slot) \
+     VALUES ($1, $2, $3) ON CONFLICT (program) DO UPDATE \
+     SET last_signature = excluded.last_signature, last_slot = excluded.last_slot";
+const SELECT_CURSOR: &str = "SELECT last_signature FROM cursors WHERE program = $1";
+const SELECT_EVENTS: &str =
+    "SELECT signature, event_index, slot, block_time, program, name, payload \
+     FROM events WHERE program = $1 AND ($2 IS NULL OR name = $2) AND slot >= $3 \
+     ORDER BY slot, signature, event_index";
+const SELECT_INSTRUCTIONS: &str =
+    "SELECT signature, ix_index, slot, block_time, program, name, data, accounts \
+     FROM instructions WHERE program = $1 AND ($2 IS NULL OR name = $2) AND slot >= $3 \
+     ORDER BY slot, signature, ix_index";
+
+fn split_accounts(accounts: &str) -> Vec<String> {
+    accounts.split_whitespace().map(str::to_string).collect()
+}
+
+// ============================================================================
+// Postgres
+// ============================================================================
+
+pub struct PgStore {
+    client: postgres::Client,
+}
+
+impl PgStore {
+    pub fn connect(url: &str) -> Result<Self> {
+        let mut client =
+            postgres::Client::connect(url, postgres::NoTls).context("connecting to Postgres")?;
+        client.batch_execute(SCHEMA)?;
+        Ok(Self { client })
+    }
+}
+
+impl Store for PgStore {
+    fn cursor(&mut self, program: &str) -> Result<Option<String>> {
+        Ok(self
+            .client
+            .query_opt(SELECT_CURSOR, &[&program])?
+            .map(|row| row.get(0)))
+    }
+
+    fn write(
+        &mut self,
+        program: &str,
+        signature: &str,
+        slot: u64,
+        rows: &TransactionRows,
+    ) -> Result<()> {
+        let mut tx = self.client.transaction()?;
+        for ix in &rows.instructions {
+            tx.execute(
+                INSERT_INSTRUCTION,
+                &[
+                    &ix.signature,
+                    &(ix.ix_index as i32),
+                    &(ix.slot as i64),
+                    &ix.block_time,
+                    &ix.program,
+                    &ix.name,
+                    &ix.data,
+                    &ix.accounts.join(" "),
+                ],
+            )?;
+        }
+        for ev in &rows.events {
+            tx.execute(
+                INSERT_EVENT,
+                &[
+                    &ev.signature,
+                    &(ev.event_index as i32),
+                    &(ev.slot as i64),
+                    &ev.block_time,
+                    &ev.program,
+                    &ev.name,
+                    &ev.payload,
+                ],
+            )?;
+        }
+        tx.execute(UPSERT_CURSOR, &[&program, &signature, &(slot as i64)])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn events(
+        &mut self,
+        program: &str,
+        name: Option<&str>,
+        from_slot: u64,
+    ) -> Result<Vec<EventRow>> {
+        let rows = self
+            .client
+            .query(SELECT_EVENTS, &[&program, &name, &(from_slot as i64)])?;
+        Ok(rows
+            .iter()
+            .map(|row| EventRow {
+                signature: row.get(0),
+                event_index: row.get::<_, i32>(1) as u32,
+                slot: row.get::<_, i64>(2) as u64,
+                block_time: row.get(3),
+                program: row.get(4),
+                name: row.get(5),
+                payload: row.get(6),
+            })
+            .collect())
+    }
+
+    fn instructions(
+        &mut self,
+        program: &str,
+        name: Option<&str>,
+        from_slot: u64,
+    ) -> Result<Vec<InstructionRow>> {
+        let rows = self
+            .client
+            .query(SELECT_INSTRUCTIONS, &[&program, &name, &(from_slot as i64)])?;
+        Ok(rows
+            .iter()
+            .map(|row| InstructionRow {
+                signature: row.get(0),
+                ix_index: row.get::<_, i32>(1) as u32,
+                slot: row.get::<_, i64>(2) as u64,
+                block_time: row.get(3),
+                program: row.get(4),
+                name: row.get(5),
+                data: row.get(6),
+                accounts: split_accounts(row.get(7)),
+            })
+            .collect())
+    }
+}
+
+// ============================================================================
+// SQLite
+// ============================================================================
+
+/// Single-file store for small operators and integration tests.
+pub struct SqliteStore {
+    conn: rusqlite::Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = rusqlite::Connection::open(path).with_context(|| format!("opening {path}"))?;
+        conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        let conn = rusqlite::Connection::open_in_memory()?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+}
+
+impl Store for SqliteStore {
+    fn cursor(&mut self, program: &str) -> Result<Option<String>> {
+        use rusqlite::OptionalExtension;
+        Ok(self
+            .conn
+            .query_row(SELECT_CURSOR, [program], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn write(
+        &mut self,
+        program: &str,
+        signature: &str,
+        slot: u64,
+        rows: &TransactionRows,
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for ix in &rows.instructions {
+            tx.execute(
+                INSERT_INSTRUCTION,
+                rusqlite::params![
+                    ix.signature,
+                    ix.ix_index,
+                    ix.slot as i64,
+                    ix.block_time,
+                    ix.program,
+                    ix.name,
+                    ix.data,
+                    ix.accounts.join(" "),
+                ],
+            )?;
+        }
+        for ev in &rows.events {
+            tx.execute(
+                INSERT_EVENT,
+                rusqlite::params![
+                    ev.signature,
+                    ev.event_index,
+                    ev.slot as i64,
+                    ev.block_time,
+                    ev.program,
+                    ev.name,
+                    ev.payload,
+                ],
+            )?;
+        }
+        tx.execute(
+            UPSERT_CURSOR,
+            rusqlite::params![program, signature, slot as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn events(
+        &mut self,
+        program: &str,
+        name: Option<&str>,
+        from_slot: u64,
+    ) -> Result<Vec<EventRow>> {
+        let mut stmt = self.conn.prepare(SELECT_EVENTS)?;
+        let rows = stmt.query_map(rusqlite::params![program, name, from_slot as i64], |row| {
+            Ok(EventRow {
+                signature: row.get(0)?,
+                event_index: row.get(1)?,
+                slot: row.get::<_, i64>(2)? as u64,
+                block_time: row.get(3)?,
+                program: row.get(4)?,
+                name: row.get(5)?,
+                payload: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn instructions(
+        &mut self,
+        program: &str,
+        name: Option<&str>,
+        from_slot: u64,
+    ) -> Result<Vec<InstructionRow>> {
+        let mut stmt = self.conn.prepare(SELECT_INSTRUCTIONS)?;
+        let rows = stmt.query_map(rusqlite::params![program, name, from_slot as i64], |row| {
+            Ok(InstructionRow {
+                signature: row.get(0)?,
+                ix_index: row.get(1)?,
+                slot: row.get::<_, i64>(2)? as u64,
+                block_time: row.get(3)?,
+                program: row.get(4)?,
+                name: row.get(5)?,
+                data: row.get(6)?,
+                accounts: split_accounts(&row.get::<_, String>(7)?),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
@@ -0,0 +1,439 @@
+//! Async RPC client for keepers and backends (`client` feature).
+//!
+//! `Client::send` prepends compute-budget instructions, signs against a fresh blockhash,
+//! rebroadcasts until confirmation, and re-signs with a new blockhash only after checking
+//! that none of the earlier signatures landed, so a retry never executes twice.
+//!
+//! With lookup tables, `send_with_tables` sends v0 transactions; `create_lookup_table`
+//! and `extend_lookup_table` maintain tables for [`crate::compose`] batches.
+
+use crate::compose::{unsigned, Chunk, Composer};
+use crate::{decode_account, decode_zero_copy, Versioned};
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use anyhow::{anyhow, bail, Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(500);
+pub(crate) const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+/// Addresses per `extend_lookup_table` transaction; 30 fit, this leaves room for fees
+const EXTEND_CHUNK: usize = 20;
+
+#[derive(Clone, Copy, Debug)]
+pub enum PriorityFee {
+    None,
+    /// Micro-lamports per compute unit
+    Fixed(u64),
+    /// Percentile of recent fees paid on the transaction's writable accounts, capped
+    Recent { percentile: u8, max: u64 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ComputeUnits {
+    /// Leave the runtime default
+    Default,
+    Fixed(u32),
+    /// Simulate and add `margin_bps` on top of the units consumed
+    Simulate { margin_bps: u16 },
+}
+
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    pub commitment: CommitmentConfig,
+    pub compute_units: ComputeUnits,
+    pub priority_fee: PriorityFee,
+    /// Simulate before the first send and fail early on program errors
+    pub preflight: bool,
+    /// Blockhashes to try before giving up
+    pub max_attempts: usize,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            compute_units: ComputeUnits::Simulate { margin_bps: 1_000 },
+            priority_fee: PriorityFee::Recent { percentile: 75, max: 1_000_000 },
+            preflight: true,
+            max_attempts: 5,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Client {
+    rpc: Arc<RpcClient>,
+    config: ClientConfig,
+}
+
+impl Client {
+    pub fn new(url: impl ToString, config: ClientConfig) -> Self {
+        let rpc = RpcClient::new_with_commitment(url.to_string(), config.commitment);
+        Self { rpc: Arc::new(rpc), config }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    pub async fn fetch_account<T: AccountDeserialize + Versioned>(&self, address: &Pubkey) -> Result<T> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .await
+            .with_context(|| format!("fetching {address}"))?;
+        decode_account(&data)
+    }
+
+    pub async fn fetch_zero_copy<T: ZeroCopy + Versioned>(&self, address: &Pubkey) -> Result<T> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .await
+            .with_context(|| format!("fetching {address}"))?;
+        decode_zero_copy(&data)
+    }
+
+    /// Sends `ixs` paid by `payer` and co-signed by `signers`; returns the signature that
+    /// landed.
+    pub async fn send(
+        &self,
+        ixs: &[Instruction],
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+    ) -> Result<Signature> {
+        self.send_with_tables(ixs, payer, signers, &[]).await
+    }
+
+    /// [`Client::send`] as a v0 transaction resolving accounts through `tables`; a legacy
+    /// transaction when `tables` is empty.
+    pub async fn send_with_tables(
+        &self,
+        ixs: &[Instruction],
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Signature> {
+        let mut all_signers: Vec<&dyn Signer> = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let ixs = self.with_compute_budget(ixs, &payer.pubkey(), tables).await?;
+
+        let mut sent: Vec<Signature> = Vec::new();
+        for attempt in 0..self.config.max_attempts {
+            // A transaction from an expired attempt may still have landed late
+            if let Some(signature) = self.find_landed(&sent).await? {
+                return Ok(signature);
+            }
+
+            let (blockhash, last_valid_height) = self
+                .rpc
+                .get_latest_blockhash_with_commitment(self.config.commitment)
+                .await?;
+            let tx = sign(&ixs, &payer.pubkey(), &all_signers, tables, blockhash)?;
+            let signature = tx.signatures[0];
+            sent.push(signature);
+
+            if attempt == 0 && self.config.preflight {
+                self.preflight(&tx).await?;
+            }
+            if self.broadcast_until_final(&tx, last_valid_height).await? {
+                return Ok(signature);
+            }
+        }
+
+        // Last look before reporting failure
+        if let Some(signature) = self.find_landed(&sent).await? {
+            return Ok(signature);
+        }
+        bail!("transaction not confirmed after {} blockhashes", self.config.max_attempts)
+    }
+
+    /// Packs `chunks` with `composer` and sends the batches in order; stops at the first
+    /// failure, after which the batches already sent have landed.
+    pub async fn send_chunks(
+        &self,
+        composer: &Composer,
+        chunks: Vec<Chunk>,
+        payer: &dyn Signer,
+    ) -> Result<Vec<Signature>> {
+        let batches = composer.pack(chunks)?;
+        let mut signatures = Vec::with_capacity(batches.len());
+        for (i, batch) in batches.iter().enumerate() {
+            let signature = self
+                .send_with_tables(batch, payer, &[], composer.tables())
+                .await
+                .with_context(|| format!("batch {} of {}", i + 1, batches.len()))?;
+            signatures.push(signature);
+        }
+        Ok(signatures)
+    }
+
+    pub async fn fetch_lookup_table(&self, address: &Pubkey) -> Result<AddressLookupTableAccount> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .await
+            .with_context(|| format!("fetching lookup table {address}"))?;
+        let table = AddressLookupTable::deserialize(&data)
+            .map_err(|e| anyhow!("decoding lookup table {address}: {e}"))?;
+        Ok(AddressLookupTableAccount { key: *address, addresses: table.addresses.to_vec() })
+    }
+
+    /// Creates a lookup table with `authority` as authority and payer, holding
+    /// `addresses`, and returns it once usable.
+    pub async fn create_lookup_table(
+        &self,
+        authority: &dyn Signer,
+        addresses: &[Pubkey],
+    ) -> Result<AddressLookupTableAccount> {
+        // The recent slot must still be in the SlotHashes sysvar when the create lands
+        let slot = self.rpc.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+        let (create, table) = create_lookup_table(authority.pubkey(), authority.pubkey(), slot);
+        self.send(&[create], authority, &[]).await.context("creating lookup table")?;
+        self.extend_lookup_table(&table, authority, addresses).await
+    }
+
+    /// Appends the `addresses` `table` does not hold yet, then waits out the one-slot
+    /// warm-up after which transactions can use them.
+    pub async fn extend_lookup_table(
+        &self,
+        table: &Pubkey,
+        authority: &dyn Signer,
+        addresses: &[Pubkey],
+    ) -> Result<AddressLookupTableAccount> {
+        let current = self.fetch_lookup_table(table).await?;
+        let mut missing: Vec<Pubkey> = Vec::new();
+        for address in addresses {
+            if !current.addresses.contains(address) && !missing.contains(address) {
+                missing.push(*address);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(current);
+        }
+        for page in missing.chunks(EXTEND_CHUNK) {
+            let ix = extend_lookup_table(*table, authority.pubkey(), Some(authority.pubkey()), page.to_vec());
+            self.send(&[ix], authority, &[]).await.context("extending lookup table")?;
+        }
+
+        let data = self.rpc.get_account_data(table).await?;
+        let extended_at = AddressLookupTable::deserialize(&data)
+            .map_err(|e| anyhow!("decoding lookup table {table}: {e}"))?
+            .meta
+            .last_extended_slot;
+        while self.rpc.get_slot().await? <= extended_at {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        self.fetch_lookup_table(table).await
+    }
+
+    /// Rebroadcasts `tx` until it confirms (`true`) or its blockhash expires (`false`).
+    async fn broadcast_until_final(&self, tx: &VersionedTransaction, last_valid_height: u64) -> Result<bool> {
+        let signature = tx.signatures[0];
+        let mut last_broadcast: Option<Instant> = None;
+        loop {
+            if last_broadcast.is_none_or(|t| t.elapsed() >= REBROADCAST_INTERVAL) {
+                // Send errors are transient here; the status poll decides the outcome
+                let _ = self.rpc.send_transaction_with_config(tx, send_config()).await;
+                last_broadcast = Some(Instant::now());
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let status = self.rpc.get_signature_statuses(&[signature]).await?.value[0].clone();
+            match status {
+                Some(status) => {
+                    if let Some(err) = status.err {
+                        bail!("transaction {signature} failed: {err}");
+                    }
+                    if status.satisfies_commitment(self.config.commitment) {
+                        return Ok(true);
+                    }
+                    // Landed but not yet at our commitment: keep waiting, never re-sign
+                }
+                None => {
+                    if self.rpc.get_block_height().await? > last_valid_height {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+    }
+
+    /// First of `signatures` the cluster has seen, waited on until it reaches our commitment.
+    async fn find_landed(&self, signatures: &[Signature]) -> Result<Option<Signature>> {
+        if signatures.is_empty() {
+            return Ok(None);
+        }
+        let statuses = self.rpc.get_signature_statuses(signatures).await?.value;
+        let Some(signature) = signatures
+            .iter()
+            .zip(statuses)
+            .find_map(|(signature, status)| status.map(|_| *signature))
+        else {
+            return Ok(None);
+        };
+        loop {
+            let status = self.rpc.get_signature_statuses(&[signature]).await?.value[0].clone();
+            match status {
+                Some(status) if status.err.is_some() => {
+                    bail!("transaction {signature} failed: {}", status.err.unwrap());
+                }
+                Some(status) if status.satisfies_commitment(self.config.commitment) => {
+                    return Ok(Some(signature));
+                }
+                // Dropped by a fork; safe to re-sign
+                None => return Ok(None),
+                Some(_) => tokio::time::sleep(POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    pub(crate) async fn preflight(&self, tx: &VersionedTransaction) -> Result<()> {
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(tx, simulate_config(self.config.commitment))
+            .await?
+            .value;
+        if let Some(err) = result.err {
+            let logs = result.logs.unwrap_or_default().join("\n  ");
+            bail!("preflight failed: {err}\n  {logs}");
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn with_compute_budget(
+        &self,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Vec<Instruction>> {
+        let mut budget = Vec::new();
+        match self.config.compute_units {
+            ComputeUnits::Default => {}
+            ComputeUnits::Fixed(units) => {
+                budget.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+            }
+            ComputeUnits::Simulate { margin_bps } => {
+                let units = self.simulate_units(ixs, payer, tables).await?;
+                let units = units.saturating_mul(10_000 + margin_bps as u64) / 10_000;
+                let units = units.min(MAX_COMPUTE_UNITS as u64) as u32;
+                budget.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+            }
+        }
+        let price = match self.config.priority_fee {
+            PriorityFee::None => 0,
+            PriorityFee::Fixed(price) => price,
+            PriorityFee::Recent { percentile, max } => {
+                self.recent_fee(ixs, percentile).await?.min(max)
+            }
+        };
+        if price > 0 {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        budget.extend_from_slice(ixs);
+        Ok(budget)
+    }
+
+    async fn simulate_units(
+        &self,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<u64> {
+        // Unsigned, with the maximum limit so the estimate is not clipped
+        let mut probe = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNITS)];
+        probe.extend_from_slice(ixs);
+        let tx = unsigned(&probe, payer, tables)?;
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(&tx, simulate_config(self.config.commitment))
+            .await?
+            .value;
+        if let Some(err) = result.err {
+            let logs = result.logs.unwrap_or_default().join("\n  ");
+            bail!("simulation failed: {err}\n  {logs}");
+        }
+        result
+            .units_consumed
+            .ok_or_else(|| anyhow!("RPC did not report compute units"))
+    }
+
+    async fn recent_fee(&self, ixs: &[Instruction], percentile: u8) -> Result<u64> {
+        let mut writable: Vec<Pubkey> = ixs
+            .iter()
+            .flat_map(|ix| ix.accounts.iter())
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey)
+            .collect();
+        writable.sort();
+        writable.dedup();
+        let mut fees: Vec<u64> = self
+            .rpc
+            .get_recent_prioritization_fees(&writable)
+            .await?
+            .into_iter()
+            .map(|f| f.prioritization_fee)
+            .collect();
+        if fees.is_empty() {
+            return Ok(0);
+        }
+        fees.sort_unstable();
+        let idx = (fees.len() - 1) * percentile.min(100) as usize / 100;
+        Ok(fees[idx])
+    }
+}
+
+/// Legacy without `tables`, v0 with them.
+pub(crate) fn sign(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    if tables.is_empty() {
+        // `try_sign`, as a remote signer or a Ledger can fail where a keypair cannot
+        let mut tx = Transaction::new_with_payer(ixs, Some(payer));
+        tx.try_sign(signers, blockhash)?;
+        return Ok(tx.into());
+    }
+    let message = v0::Message::try_compile(payer, ixs, tables, blockhash)
+        .map_err(|e| anyhow!("compiling v0 message: {e}"))?;
+    Ok(VersionedTransaction::try_new(VersionedMessage::V0(message), signers)?)
+}
+
+pub(crate) fn send_config() -> solana_client::rpc_config::RpcSendTransactionConfig {
+    solana_client::rpc_config::RpcSendTransactionConfig {
+        // Preflight already ran once; rebroadcasts must not be rejected by a stale view
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..Default::default()
+    }
+}
+
+fn simulate_config(commitment: CommitmentConfig) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(commitment),
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
@@ -0,0 +1,176 @@
+//! Packing many instruction chunks into as few transactions as fit.
+//!
+//! A [`Chunk`] is instructions that must land together (one claim, one finalize page)
+//! with an estimate of the compute units they use. [`Composer::pack`] fills each
+//! transaction with whole chunks, in order, until the next one would exceed the packet
+//! size, the account-lock limit or the compute budget. With lookup tables the batches
+//! are compiled as v0 messages, which is what makes more than a handful of claims fit:
+//! each account found in a table costs one byte instead of 32.
+//!
+//! [`recurring_accounts`] picks the accounts worth putting in a table; the `client`
+//! feature creates and extends tables and sends the batches.
+//!
+//! ```
+//! # use solana_sdk::pubkey::Pubkey;
+//! use sentinelkarma_sdk::compose::{recurring_accounts, Chunk, Composer};
+//! use sentinelkarma_sdk::seka;
+//!
+//! let payer = Pubkey::new_unique();
+//! let chunks: Vec<Chunk> = (0..20)
+//!     .map(|i| Chunk {
+//!         instructions: vec![seka::claim_karma(
+//!             &payer,
+//!             Pubkey::new_unique(),
+//!             7,
+//!             10,
+//!             i,
+//!             vec![[0; 32]; 12],
+//!         )],
+//!         compute_units: 40_000,
+//!     })
+//!     .collect();
+//! assert!(recurring_accounts(&chunks, &payer).contains(&seka::cycle_state_pda(7)));
+//! let batches = Composer::new(payer, Vec::new()).pack(chunks).unwrap();
+//! assert!(batches.len() > 1);
+//! ```
+
+use anyhow::{anyhow, bail, Result};
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::{HashMap, HashSet};
+
+/// Accounts one transaction may lock, lookups included.
+pub const MAX_ACCOUNT_LOCKS: usize = 64;
+
+/// Units a batch may be estimated at; below the 1.4M cap, leaving room for the margin
+/// `Client` adds on top of simulated usage.
+pub const DEFAULT_COMPUTE_UNIT_BUDGET: u32 = 1_200_000;
+
+/// Instructions that must go out in the same transaction.
+#[derive(Clone, Debug)]
+pub struct Chunk {
+    pub instructions: Vec<Instruction>,
+    pub compute_units: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Composer {
+    payer: Pubkey,
+    tables: Vec<AddressLookupTableAccount>,
+    compute_unit_budget: u32,
+}
+
+impl Composer {
+    pub fn new(payer: Pubkey, tables: Vec<AddressLookupTableAccount>) -> Self {
+        Self { payer, tables, compute_unit_budget: DEFAULT_COMPUTE_UNIT_BUDGET }
+    }
+
+    pub fn compute_unit_budget(mut self, units: u32) -> Self {
+        self.compute_unit_budget = units;
+        self
+    }
+
+    pub fn tables(&self) -> &[AddressLookupTableAccount] {
+        &self.tables
+    }
+
+    /// Whole chunks, in order, grouped into transactions. Fails if a single chunk does
+    /// not fit one transaction on its own.
+    pub fn pack(&self, chunks: impl IntoIterator<Item = Chunk>) -> Result<Vec<Vec<Instruction>>> {
+        let mut batches = Vec::new();
+        let mut current: Vec<Instruction> = Vec::new();
+        let mut units = 0u32;
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let total = units.saturating_add(chunk.compute_units);
+            if !current.is_empty() && total <= self.compute_unit_budget {
+                let mut candidate = current.clone();
+                candidate.extend_from_slice(&chunk.instructions);
+                if self.fits(&candidate)? {
+                    current = candidate;
+                    units = total;
+                    continue;
+                }
+            }
+            if chunk.compute_units > self.compute_unit_budget || !self.fits(&chunk.instructions)? {
+                bail!("chunk {i} does not fit in one transaction");
+            }
+            if !current.is_empty() {
+                batches.push(std::mem::take(&mut current));
+            }
+            current = chunk.instructions;
+            units = chunk.compute_units;
+        }
+        if !current.is_empty() {
+            batches.push(current);
+        }
+        Ok(batches)
+    }
+
+    /// Whether `ixs`, with the compute-budget instructions `Client` prepends, fit one
+    /// transaction's size and account locks.
+    pub fn fits(&self, ixs: &[Instruction]) -> Result<bool> {
+        let mut all = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(0),
+            ComputeBudgetInstruction::set_compute_unit_price(0),
+        ];
+        all.extend_from_slice(ixs);
+        let tx = unsigned(&all, &self.payer, &self.tables)?;
+        let locks = tx.message.static_account_keys().len()
+            + tx.message.address_table_lookups().map_or(0, |lookups| {
+                lookups
+                    .iter()
+                    .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
+                    .sum()
+            });
+        let size = bincode::serialized_size(&tx)? as usize;
+        Ok(size <= PACKET_DATA_SIZE && locks <= MAX_ACCOUNT_LOCKS)
+    }
+}
+
+/// `ixs` as a transaction with placeholder signatures: legacy without tables, v0 with.
+pub(crate) fn unsigned(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    tables: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let message = if tables.is_empty() {
+        VersionedMessage::Legacy(Message::new(ixs, Some(payer)))
+    } else {
+        let message = v0::Message::try_compile(payer, ixs, tables, Hash::default())
+            .map_err(|e| anyhow!("compiling v0 message: {e}"))?;
+        VersionedMessage::V0(message)
+    };
+    let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+    Ok(VersionedTransaction { signatures, message })
+}
+
+/// Accounts used by more than one chunk, most used first: the ones a lookup table saves
+/// bytes on. Signers and invoked programs are left out, as lookups cannot provide them.
+pub fn recurring_accounts(chunks: &[Chunk], payer: &Pubkey) -> Vec<Pubkey> {
+    let programs: HashSet<Pubkey> =
+        chunks.iter().flat_map(|c| &c.instructions).map(|ix| ix.program_id).collect();
+    let mut uses: HashMap<Pubkey, usize> = HashMap::new();
+    for chunk in chunks {
+        let accounts: HashSet<Pubkey> = chunk
+            .instructions
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| !meta.is_signer && meta.pubkey != *payer)
+            .map(|meta| meta.pubkey)
+            .filter(|key| !programs.contains(key))
+            .collect();
+        for key in accounts {
+            *uses.entry(key).or_default() += 1;
+        }
+    }
+    let mut recurring: Vec<(Pubkey, usize)> = uses.into_iter().filter(|&(_, n)| n > 1).collect();
+    recurring.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    recurring.into_iter().map(|(key, _)| key).collect()
+}
@@ -0,0 +1,151 @@
+//! Localnet fixtures (`fixtures` feature): the accounts `seka-admin bootstrap` created,
+//! so examples and integration tests can run against a bootstrapped validator without
+//! repeating its setup.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! let fixtures = sentinelkarma_sdk::fixtures::Fixtures::read("fixtures.json".as_ref())?;
+//! let alice = fixtures.members[0].keypair()?;
+//! let claim = &fixtures.cycle.claims[0];
+//! let ix = sentinelkarma_sdk::seka::claim_karma(
+//!     &fixtures.payer,
+//!     claim.owner,
+//!     fixtures.cycle.cycle_index,
+//!     claim.delta_points,
+//!     claim.leaf_index,
+//!     claim.proof()?,
+//! );
+//! # let _ = (alice, ix);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use std::path::Path;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fixtures {
+    pub rpc_url: String,
+    /// Paid for the setup; governor of seka and authority of sentinel
+    #[serde(with = "b58")]
+    pub payer: Pubkey,
+    pub seka: Seka,
+    pub sentinel: Sentinel,
+    pub members: Vec<Member>,
+    /// A published root with one leaf per member, none claimed
+    pub cycle: Cycle,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Seka {
+    #[serde(with = "b58")]
+    pub program_id: Pubkey,
+    #[serde(with = "b58")]
+    pub config: Pubkey,
+    #[serde(with = "b58")]
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// The treasury PDA's associated token account, which receives join costs
+    #[serde(with = "b58")]
+    pub treasury_ata: Pubkey,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sentinel {
+    #[serde(with = "b58")]
+    pub program_id: Pubkey,
+    #[serde(with = "b58")]
+    pub state: Pubkey,
+    /// SPL Token mint
+    #[serde(with = "b58")]
+    pub mint: Pubkey,
+    pub decimals: u8,
+    #[serde(with = "b58")]
+    pub treasury_ata: Pubkey,
+}
+
+/// A funded wallet that has joined both networks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Member {
+    #[serde(with = "b58")]
+    pub pubkey: Pubkey,
+    /// Keypair bytes in the `solana-keygen` file layout; localnet only
+    pub secret_key: Vec<u8>,
+    #[serde(with = "b58")]
+    pub seka_ata: Pubkey,
+    #[serde(with = "b58")]
+    pub sentinel_ata: Pubkey,
+}
+
+impl Member {
+    pub fn keypair(&self) -> Result<Keypair> {
+        Keypair::from_bytes(&self.secret_key)
+            .map_err(|e| anyhow!("member {} keypair: {e}", self.pubkey))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cycle {
+    pub cycle_index: u64,
+    /// Hex
+    pub merkle_root: String,
+    pub total_points_declared: u32,
+    pub claims_bitmap_len: u32,
+    pub claims: Vec<Claim>,
+}
+
+/// `claim_karma` arguments for one leaf.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claim {
+    #[serde(with = "b58")]
+    pub owner: Pubkey,
+    pub delta_points: i32,
+    pub leaf_index: u32,
+    /// Hex sibling hashes, leaf to root
+    pub proof: Vec<String>,
+}
+
+impl Claim {
+    pub fn proof(&self) -> Result<Vec<[u8; 32]>> {
+        self.proof
+            .iter()
+            .map(|node| {
+                hex::decode(node)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| anyhow!("proof node {node:?} is not 32 hex bytes"))
+            })
+            .collect()
+    }
+}
+
+impl Fixtures {
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_slice(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// Pubkeys as base58 strings rather than byte arrays.
+mod b58 {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,143 @@
+//! Rust SDK for the SentinelKarma programs.
+//!
+//! - [`sentinel`] re-exports the program crate built with `no-entrypoint`, so account
+//!   layouts, `accounts::*` / `instruction::*` builders and events are the program's own
+//!   types, plus PDA helpers.
+//! - [`seka`] is hand-written (the program crate is not a dependency) and covers every
+//!   seka instruction, account and event.
+//!
+//! Decoding and fetching work the same for both through Anchor's traits. The `client`
+//! feature adds an async [`client::Client`] for sending transactions, [`compose`] packs
+//! many claims or finalize chunks into batches, [`simulate`] explains would-be claims and
+//! conversions, `queue` sends persisted operations exactly once, `fixtures` reads the
+//! localnet layout that `seka-admin bootstrap` writes, and `signer` resolves keypair
+//! files, Ledgers and remote signing services. [`program_error`] names the error of a
+//! failed transaction in either program.
+//!
+//! Every decoder reads accounts written by any layout version of their type as the
+//! current one; see [`Versioned`].
+//!
+//! The `localnet` (default), `devnet` and `mainnet` features pick the [`CLUSTER`] whose
+//! program ids [`seka::ID`] and [`sentinel::ID`] are.
+
+#[cfg(feature = "client")]
+pub mod client;
+pub mod compose;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "queue")]
+pub mod queue;
+pub mod seka;
+pub mod sentinel;
+#[cfg(feature = "signer")]
+pub mod signer;
+pub mod simulate;
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, ZeroCopy};
+use anyhow::{anyhow, ensure, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+pub use anchor_lang;
+pub use sentinelkarma_core::cluster::{Cluster, CLUSTER};
+pub use sentinelkarma_core::error::{KarmaError, SekaError, SentinelError, Subsystem};
+pub use sentinelkarma_core::version::Versioned;
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+/// Decodes a Borsh account (`Account<T>` on-chain), checking its discriminator.
+///
+/// An account still in an older layout decodes as `migrate_accounts` would leave it:
+/// layouts only grow at the end, so the tail it lacks reads as zero before
+/// [`Versioned::migrate`] runs.
+pub fn decode_account<T: AccountDeserialize + Versioned>(data: &[u8]) -> Result<T> {
+    let mut padded = Vec::with_capacity(data.len() + T::SPACE);
+    padded.extend_from_slice(data);
+    padded.resize(data.len() + T::SPACE, 0);
+    let mut account =
+        T::try_deserialize(&mut &padded[..]).map_err(|e| anyhow!("decoding account: {e}"))?;
+    account.migrate();
+    Ok(account)
+}
+
+/// Decodes a zero-copy account (`AccountLoader<T>` on-chain), checking its discriminator;
+/// older layouts decode as with [`decode_account`].
+pub fn decode_zero_copy<T: ZeroCopy + Versioned>(data: &[u8]) -> Result<T> {
+    ensure!(data.len() >= 8, "account data too short");
+    ensure!(data[..8] == T::DISCRIMINATOR, "account discriminator mismatch");
+    let mut bytes = vec![0u8; std::mem::size_of::<T>()];
+    let len = bytes.len().min(data.len() - 8);
+    bytes[..len].copy_from_slice(&data[8..8 + len]);
+    let mut account: T = bytemuck::pod_read_unaligned(&bytes);
+    account.migrate();
+    Ok(account)
+}
+
+pub fn fetch_account<T: AccountDeserialize + Versioned>(client: &RpcClient, address: &Pubkey) -> Result<T> {
+    let data = client
+        .get_account_data(address)
+        .with_context(|| format!("fetching {address}"))?;
+    decode_account(&data)
+}
+
+pub fn fetch_zero_copy<T: ZeroCopy + Versioned>(client: &RpcClient, address: &Pubkey) -> Result<T> {
+    let data = client
+        .get_account_data(address)
+        .with_context(|| format!("fetching {address}"))?;
+    decode_zero_copy(&data)
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Payloads of `Program data:` lines, i.e. events sent with `emit!`.
+pub fn log_event_data(logs: &[String]) -> Vec<Vec<u8>> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|b64| STANDARD.decode(b64).ok())
+        .collect()
+}
+
+/// Payload of a self-CPI event instruction (`emit_cpi!`), given the inner
+/// instruction's data; `None` for any other instruction.
+pub fn cpi_event_data(ix_data: &[u8]) -> Option<&[u8]> {
+    ix_data.strip_prefix(&anchor_lang::event::EVENT_IX_TAG_LE[..])
+}
+
+/// Name whose `sha256("<namespace>:<name>")[..8]` prefixes `data`, out of `names`.
+fn lookup_name(namespace: &str, names: &[&'static str], data: &[u8]) -> Option<&'static str> {
+    let tag = data.get(..8)?;
+    names.iter().copied().find(|name| {
+        solana_sdk::hash::hash(format!("{namespace}:{name}").as_bytes()).to_bytes()[..8] == *tag
+    })
+}
+
+/// Decodes `data` as `E` if its discriminator matches.
+pub fn decode_event<E: Discriminator + AnchorDeserialize>(data: &[u8]) -> Option<E> {
+    let body = data.strip_prefix(&E::DISCRIMINATOR[..])?;
+    E::deserialize(&mut &body[..]).ok()
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// The seka or sentinel error a transaction failed with; `None` for any other failure.
+///
+/// Codes of both programs are disjoint, so this needs no program id, but a custom
+/// error from another Anchor program in the same range would be misread.
+pub fn program_error(err: &TransactionError) -> Option<KarmaError> {
+    match err {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            KarmaError::from_code(*code)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,405 @@
+//! Persistent, exactly-once submission of operations (`queue` feature).
+//!
+//! An operation is a caller-chosen id (`set-cycle-root-42`) and its instructions, kept
+//! as `<dir>/<id>.json`. Before a signed transaction is broadcast it is written to the
+//! operation's file along with what bounds its lifetime, so a process that restarts at
+//! any point settles the transaction it already sent before it signs another:
+//!
+//! - it landed: the operation is done and is never sent again;
+//! - it landed with an error: nothing executed, so it is signed again on the next try;
+//! - it can no longer land: its blockhash has expired or, with a durable nonce, the
+//!   nonce has moved on. Only then is the operation signed again.
+//!
+//! Blockhash transactions expire about a minute after signing, so a crash costs little.
+//! With [`Queue::with_nonce`] transactions use a durable nonce instead and stay valid
+//! until the nonce advances, which suits admin transactions approved on a Ledger or a
+//! remote signer; the nonce authority must be the payer. One nonce account serves one
+//! queue, as each transaction holds it until it lands or fails.
+
+use crate::client::{self, Client, POLL_INTERVAL, REBROADCAST_INTERVAL};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_client::nonce_utils::{data_from_account, nonblocking::get_account_with_commitment};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::nonce::State as NonceState;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::TransactionStatus;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Instant;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: String,
+    /// bincode of the `Vec<Instruction>`, base64
+    instructions: String,
+    pub state: State,
+    /// Transactions signed for this operation so far
+    pub attempts: u32,
+    /// Why the last attempt failed, if it did
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum State {
+    /// Not sent, or the last transaction can no longer land
+    Pending,
+    /// Broadcast; not yet known to have landed
+    Sent {
+        signature: String,
+        /// bincode of the signed transaction, base64, for rebroadcasting after a restart
+        transaction: String,
+        expiry: Expiry,
+    },
+    Done { signature: String },
+}
+
+/// What ends a sent transaction's chance of landing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Expiry {
+    Blockhash { last_valid_block_height: u64 },
+    /// The nonce account's value when the transaction was signed
+    Nonce { account: String, nonce: String },
+}
+
+impl Operation {
+    pub fn instructions(&self) -> Result<Vec<Instruction>> {
+        let bytes = STANDARD.decode(&self.instructions)?;
+        bincode::deserialize(&bytes).with_context(|| format!("decoding operation {}", self.id))
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done { .. })
+    }
+}
+
+enum Settled {
+    Landed(Signature),
+    Failed(Signature, TransactionError),
+    Expired,
+}
+
+pub struct Queue {
+    client: Client,
+    dir: PathBuf,
+    nonce: Option<Pubkey>,
+}
+
+impl Queue {
+    /// Opens the queue kept in `dir`, creating the directory if needed.
+    pub fn open(client: Client, dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        Ok(Self { client, dir, nonce: None })
+    }
+
+    /// Signs every transaction against the durable nonce in `account`.
+    pub fn with_nonce(mut self, account: Pubkey) -> Self {
+        self.nonce = Some(account);
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Records `ixs` under `id` unless it is already queued. Queuing the same id again
+    /// with different instructions is an error, as the first may already have executed.
+    pub fn enqueue(&self, id: &str, ixs: &[Instruction]) -> Result<Operation> {
+        let instructions = STANDARD.encode(bincode::serialize(ixs)?);
+        if let Some(op) = self.get(id)? {
+            ensure!(
+                op.instructions == instructions,
+                "operation {id} is already queued with different instructions"
+            );
+            return Ok(op);
+        }
+        let op = Operation {
+            id: id.to_owned(),
+            instructions,
+            state: State::Pending,
+            attempts: 0,
+            last_error: None,
+        };
+        self.save(&op)?;
+        Ok(op)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Operation>> {
+        let path = self.path(id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let op = serde_json::from_slice(&fs::read(&path)?)
+            .with_context(|| format!("reading {}", path.display()))?;
+        Ok(Some(op))
+    }
+
+    /// Operations not yet done, by id.
+    pub fn pending(&self) -> Result<Vec<Operation>> {
+        let mut ops = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let op: Operation = serde_json::from_slice(&fs::read(&path)?)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                if !op.is_done() {
+                    ops.push(op);
+                }
+            }
+        }
+        ops.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(ops)
+    }
+
+    /// [`Queue::enqueue`], then [`Queue::process`].
+    pub async fn submit(
+        &self,
+        id: &str,
+        ixs: &[Instruction],
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+    ) -> Result<Signature> {
+        self.enqueue(id, ixs)?;
+        self.process(id, payer, signers).await
+    }
+
+    /// Settles `id`'s last transaction and, until the operation lands, signs and sends
+    /// new ones, up to the client's `max_attempts` in this call. Returns the signature
+    /// that executed it; at once if it already has.
+    pub async fn process(
+        &self,
+        id: &str,
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+    ) -> Result<Signature> {
+        let mut op = self.get(id)?.ok_or_else(|| anyhow!("no queued operation {id}"))?;
+        let max_attempts = self.client.config().max_attempts;
+        let mut signed = 0;
+        loop {
+            match &op.state {
+                State::Done { signature } => return parse_signature(signature),
+                State::Sent { signature, transaction, expiry } => {
+                    let signature = parse_signature(signature)?;
+                    let tx: VersionedTransaction =
+                        bincode::deserialize(&STANDARD.decode(transaction)?)?;
+                    match self.settle(&signature, &tx, expiry).await? {
+                        Settled::Landed(signature) => {
+                            op.state = State::Done { signature: signature.to_string() };
+                            op.last_error = None;
+                            self.save(&op)?;
+                            return Ok(signature);
+                        }
+                        Settled::Failed(signature, err) => {
+                            // A failed transaction executes nothing; sending again is safe
+                            op.state = State::Pending;
+                            op.last_error = Some(format!("{signature}: {err}"));
+                            self.save(&op)?;
+                            bail!("operation {id}: transaction {signature} failed: {err}");
+                        }
+                        Settled::Expired => {
+                            op.state = State::Pending;
+                            self.save(&op)?;
+                        }
+                    }
+                }
+                State::Pending => {
+                    if signed == max_attempts {
+                        bail!("operation {id} not confirmed after {max_attempts} transactions");
+                    }
+                    let ixs = op.instructions()?;
+                    let (tx, expiry) = self.sign(&ixs, payer, signers).await?;
+                    if signed == 0 && self.client.config().preflight {
+                        if let Err(e) = self.client.preflight(&tx).await {
+                            op.last_error = Some(format!("{e:#}"));
+                            self.save(&op)?;
+                            return Err(e.context(format!("operation {id}")));
+                        }
+                    }
+                    signed += 1;
+                    op.attempts += 1;
+                    // Recorded before the first broadcast, so a restart finds it
+                    op.state = State::Sent {
+                        signature: tx.signatures[0].to_string(),
+                        transaction: STANDARD.encode(bincode::serialize(&tx)?),
+                        expiry,
+                    };
+                    self.save(&op)?;
+                }
+            }
+        }
+    }
+
+    /// Processes every pending operation in id order; stops at the first failure.
+    pub async fn drain(
+        &self,
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+    ) -> Result<Vec<Signature>> {
+        let mut signatures = Vec::new();
+        for op in self.pending()? {
+            signatures.push(self.process(&op.id, payer, signers).await?);
+        }
+        Ok(signatures)
+    }
+
+    async fn sign(
+        &self,
+        ixs: &[Instruction],
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+    ) -> Result<(VersionedTransaction, Expiry)> {
+        let mut all_signers: Vec<&dyn Signer> = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let payer_key = payer.pubkey();
+        let Some(account) = self.nonce else {
+            let ixs = self.client.with_compute_budget(ixs, &payer_key, &[]).await?;
+            let (blockhash, last_valid_block_height) = self
+                .client
+                .rpc()
+                .get_latest_blockhash_with_commitment(self.client.config().commitment)
+                .await?;
+            let tx = client::sign(&ixs, &payer_key, &all_signers, &[], blockhash)?;
+            return Ok((tx, Expiry::Blockhash { last_valid_block_height }));
+        };
+
+        let nonce = self.nonce_value(&account, Some(&payer_key)).await?;
+        let advance = system_instruction::advance_nonce_account(&account, &payer_key);
+        let mut with_advance = vec![advance.clone()];
+        with_advance.extend_from_slice(ixs);
+        // The advance must be the first instruction, ahead of the compute-budget ones
+        let mut ixs = self.client.with_compute_budget(&with_advance, &payer_key, &[]).await?;
+        let position = ixs.iter().position(|ix| *ix == advance).unwrap_or_default();
+        let advance = ixs.remove(position);
+        ixs.insert(0, advance);
+        let tx = client::sign(&ixs, &payer_key, &all_signers, &[], nonce)?;
+        let expiry = Expiry::Nonce { account: account.to_string(), nonce: nonce.to_string() };
+        Ok((tx, expiry))
+    }
+
+    /// Rebroadcasts `tx` until it lands, fails, or can no longer land.
+    async fn settle(
+        &self,
+        signature: &Signature,
+        tx: &VersionedTransaction,
+        expiry: &Expiry,
+    ) -> Result<Settled> {
+        let commitment = self.client.config().commitment;
+        let rpc = self.client.rpc();
+        let mut last_broadcast: Option<Instant> = None;
+        loop {
+            // With history, as a transaction sent before a long outage may be old
+            let status = self.status(signature).await?;
+            match status {
+                Some(status) => {
+                    if let Some(err) = status.err {
+                        return Ok(Settled::Failed(*signature, err));
+                    }
+                    if status.satisfies_commitment(commitment) {
+                        return Ok(Settled::Landed(*signature));
+                    }
+                    // Landed but not yet at our commitment: wait, never re-sign
+                }
+                None => {
+                    let expired = match expiry {
+                        Expiry::Blockhash { last_valid_block_height } => {
+                            let height = rpc.get_block_height_with_commitment(commitment).await?;
+                            height > *last_valid_block_height
+                        }
+                        Expiry::Nonce { account, nonce } => {
+                            let account = Pubkey::from_str(account)?;
+                            self.nonce_value(&account, None).await?.to_string() != *nonce
+                        }
+                    };
+                    if expired {
+                        // It may have landed between the status check and the expiry check
+                        if self.status(signature).await?.is_none() {
+                            return Ok(Settled::Expired);
+                        }
+                        continue;
+                    }
+                    if last_broadcast.is_none_or(|t| t.elapsed() >= REBROADCAST_INTERVAL) {
+                        // Send errors are transient here; the status poll decides the outcome
+                        let _ = rpc.send_transaction_with_config(tx, client::send_config()).await;
+                        last_broadcast = Some(Instant::now());
+                    }
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn status(&self, signature: &Signature) -> Result<Option<TransactionStatus>> {
+        let statuses = self.client.rpc().get_signature_statuses_with_history(&[*signature]).await?;
+        Ok(statuses.value.into_iter().next().flatten())
+    }
+
+    /// Current value of the nonce in `account`, checking its authority if given.
+    async fn nonce_value(&self, account: &Pubkey, authority: Option<&Pubkey>) -> Result<Hash> {
+        let commitment = self.client.config().commitment;
+        let data = get_account_with_commitment(self.client.rpc(), account, commitment)
+            .await
+            .and_then(|a| data_from_account(&a))
+            .map_err(|e| anyhow!("reading nonce account {account}: {e}"))?;
+        if let Some(authority) = authority {
+            ensure!(
+                data.authority == *authority,
+                "nonce account {account} has authority {}, not the payer {authority}",
+                data.authority
+            );
+        }
+        Ok(data.blockhash())
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        ensure!(
+            !id.is_empty()
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                && !id.starts_with('.'),
+            "operation id {id:?} must be letters, digits, '-', '_' and '.'"
+        );
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+
+    /// Writes through a temporary file, so a crash never leaves half an operation.
+    fn save(&self, op: &Operation) -> Result<()> {
+        let path = self.path(&op.id)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(op)? + "\n")
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// Creates `nonce_account` as a durable nonce whose authority is `payer`, for
+/// [`Queue::with_nonce`].
+pub async fn create_nonce_account(
+    client: &Client,
+    payer: &dyn Signer,
+    nonce_account: &dyn Signer,
+) -> Result<Signature> {
+    let lamports = client
+        .rpc()
+        .get_minimum_balance_for_rent_exemption(NonceState::size())
+        .await?;
+    let ixs = system_instruction::create_nonce_account(
+        &payer.pubkey(),
+        &nonce_account.pubkey(),
+        &payer.pubkey(),
+        lamports,
+    );
+    client.send(&ixs, payer, &[nonce_account]).await.context("creating nonce account")
+}
+
+fn parse_signature(s: &str) -> Result<Signature> {
+    Signature::from_str(s).map_err(|e| anyhow!("invalid signature {s:?}: {e}"))
+}
@@ -0,0 +1,690 @@
+//! seka program: PDAs, instruction builders, accounts and events.
+//!
+//! Account order follows the program's `#[derive(Accounts)]` structs and instruction
+//! data is Anchor's `sha256("global:<name>")[..8] || borsh(args)`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::Discriminator;
+use sentinelkarma_core::seka::seeds::*;
+use solana_sdk::pubkey;
+use std::fmt;
+
+pub const ID: Pubkey = sentinelkarma_core::seka_id!(pubkey);
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+pub use sentinelkarma_core::leaf::LeafFormat;
+pub use sentinelkarma_core::seka::events::EVENT_NAMES;
+pub use sentinelkarma_core::seka::CLAIM_WINDOW_CYCLES;
+
+// ============================================================================
+// PDAs
+// ============================================================================
+
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], &ID).0
+}
+
+pub fn mint_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[MINT_AUTHORITY_SEED], &ID).0
+}
+
+pub fn treasury_pda() -> Pubkey {
+    Pubkey::find_program_address(&[TREASURY_SEED], &ID).0
+}
+
+pub fn cycle_state_pda(cycle_index: u64) -> Pubkey {
+    Pubkey::find_program_address(&[CYCLE_SEED, &cycle_index.to_le_bytes()], &ID).0
+}
+
+/// `PeerLedger` of `owner`.
+pub fn peer_pda(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PEER_SEED, owner.as_ref()], &ID).0
+}
+
+pub fn membership_pda(member: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[MEMBER_SEED, member.as_ref()], &ID).0
+}
+
+/// `GovernorTransfer`, present while a governor handover is pending.
+pub fn governor_transfer_pda() -> Pubkey {
+    Pubkey::find_program_address(&[GOVERNOR_TRANSFER_SEED], &ID).0
+}
+
+/// Classic SPL Token associated account; seka does not use Token-2022.
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+macro_rules! seka_account {
+    ($name:ident, $discriminator:expr) => {
+        impl Discriminator for $name {
+            // sha256("account:<name>")[..8]
+            const DISCRIMINATOR: [u8; 8] = $discriminator;
+        }
+
+        impl AccountDeserialize for $name {
+            fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+                if buf.len() < 8 || buf[..8] != Self::DISCRIMINATOR {
+                    return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+                }
+                Self::try_deserialize_unchecked(buf)
+            }
+
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+                // Anything past the layout is left unread
+                let mut data = &buf[8..];
+                Self::deserialize(&mut data)
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+
+        impl Owner for $name {
+            fn owner() -> Pubkey {
+                ID
+            }
+        }
+    };
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct GlobalConfig {
+    pub bump: u8,
+    pub governor: Pubkey,
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub mint_authority: Pubkey,
+    pub cycle_secs: u64,
+    pub max_points_per_cycle: u32,
+    pub per_peer_cycle_cap: i32,
+    pub conversion_ratio: u32,
+    pub join_cost_tokens: u64,
+    pub start_ts: i64,
+    pub airdrop_done: bool,
+    pub decimals: u8,
+    pub issuer: Pubkey,
+    pub version: u8,
+    pub first_open_cycle: u64,
+    pub _reserved: [u8; 7],
+}
+seka_account!(GlobalConfig, [149, 8, 156, 202, 160, 252, 176, 217]);
+sentinelkarma_core::versioned!(GlobalConfig, 1, GlobalConfig::SPACE);
+
+impl GlobalConfig {
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 32 + 32 + 32 + 8 + 4 + 4 + 4 + 8 + 8 + 1 + 1 + 32 + 1 + 8 + 7;
+
+    /// Cycle index at unix time `now`, as `close_cycle` computes it.
+    pub fn current_cycle(&self, now: i64) -> u64 {
+        (now.saturating_sub(self.start_ts).max(0) as u64) / self.cycle_secs.max(1)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CycleState {
+    pub bump: u8,
+    pub cycle_index: u64,
+    pub merkle_root: [u8; 32],
+    pub total_points_declared: u32,
+    pub claims_bitmap: Vec<u8>,
+    pub version: u8,
+    pub leaf_format: u8,
+    pub leaf_count: u32,
+    pub _reserved: [u8; 10],
+}
+seka_account!(CycleState, [16, 20, 107, 197, 115, 234, 217, 60]);
+sentinelkarma_core::versioned!(CycleState, 2, CycleState::space_for(0));
+
+impl CycleState {
+    /// Account size `set_cycle_root` allocates, as the program computes it.
+    pub const fn space_for(bitmap_len: u32) -> usize {
+        8 /*disc*/ + 1 + 8 + 32 + 4 + 4 /*vec prefix*/ + bitmap_len as usize + 1 + 1 + 4 + 10
+    }
+
+    /// Format the root was built in; `None` for a byte this SDK does not know.
+    pub fn leaf_format(&self) -> Option<LeafFormat> {
+        LeafFormat::from_u8(self.leaf_format)
+    }
+
+    pub fn is_claimed(&self, leaf_index: u32) -> bool {
+        self.claims_bitmap
+            .get((leaf_index / 8) as usize)
+            .is_some_and(|byte| byte & (1 << (leaf_index % 8)) != 0)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PeerLedger {
+    pub bump: u8,
+    pub owner: Pubkey,
+    pub points: i64,
+    pub last_cycle_claimed: u64,
+    pub version: u8,
+    pub _reserved: [u8; 15],
+}
+seka_account!(PeerLedger, [228, 24, 64, 98, 46, 43, 205, 132]);
+sentinelkarma_core::versioned!(PeerLedger, 0, PeerLedger::SPACE);
+
+impl PeerLedger {
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 8 + 8 + 1 + 15;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Membership {
+    pub bump: u8,
+    pub owner: Pubkey,
+    pub joined_at: i64,
+    pub active: bool,
+    pub version: u8,
+    pub _reserved: [u8; 15],
+}
+seka_account!(Membership, [231, 141, 180, 98, 109, 168, 175, 166]);
+sentinelkarma_core::versioned!(Membership, 0, Membership::SPACE);
+
+impl Membership {
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 8 + 1 + 1 + 15;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct GovernorTransfer {
+    pub bump: u8,
+    pub pending: Pubkey,
+    pub version: u8,
+    pub _reserved: [u8; 15],
+}
+seka_account!(GovernorTransfer, [203, 231, 148, 218, 212, 131, 86, 85]);
+sentinelkarma_core::versioned!(GovernorTransfer, 0, GovernorTransfer::SPACE);
+
+impl GovernorTransfer {
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 1 + 15;
+}
+
+// ============================================================================
+// Instructions
+// ============================================================================
+
+fn data<T: AnchorSerialize>(name: &str, args: &T) -> Vec<u8> {
+    let mut v = hash(format!("global:{name}").as_bytes()).to_bytes()[..8].to_vec();
+    args.serialize(&mut v).expect("borsh into Vec cannot fail");
+    v
+}
+
+/// Arguments of an indexed instruction, e.g. `ClaimKarmaArgs` for `claim_karma` data;
+/// the caller checks the discriminator (see [`instruction_name`]).
+pub fn decode_args<T: AnchorDeserialize>(data: &[u8]) -> Option<T> {
+    T::deserialize(&mut data.get(8..)?).ok()
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InitializeArgs {
+    pub governor: Pubkey,
+    pub airdrop_recipient: Pubkey,
+    pub start_ts: i64,
+    pub decimals: u8,
+    pub airdrop_whole_tokens: u64,
+}
+
+/// `mint` is a fresh keypair that must co-sign; `airdrop_recipient_ata` is the recipient's
+/// associated token account of that mint, which the instruction creates.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    airdrop_recipient_ata: &Pubkey,
+    governor: Pubkey,
+    airdrop_recipient: Pubkey,
+    start_ts: i64,
+    decimals: u8,
+    airdrop_whole_tokens: u64,
+) -> Instruction {
+    let treasury = treasury_pda();
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new(*mint, true),
+            AccountMeta::new_readonly(mint_authority_pda(), false),
+            AccountMeta::new_readonly(treasury, false),
+            AccountMeta::new(associated_token_address(&treasury, mint), false),
+            AccountMeta::new(*airdrop_recipient_ata, false),
+            AccountMeta::new_readonly(airdrop_recipient, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: data(
+            "initialize",
+            &InitializeArgs {
+                governor,
+                airdrop_recipient,
+                start_ts,
+                decimals,
+                airdrop_whole_tokens,
+            },
+        ),
+    }
+}
+
+/// Mirrors the program's `UpdateParams`; `None` leaves a field unchanged.
+#[derive(AnchorSerialize, Clone, Default, Debug)]
+pub struct UpdateParams {
+    pub cycle_secs: Option<u64>,
+    pub max_points_per_cycle: Option<u32>,
+    pub per_peer_cycle_cap: Option<i32>,
+    pub conversion_ratio: Option<u32>,
+    pub join_cost_tokens: Option<u64>,
+    pub treasury_owner: Option<Pubkey>,
+    pub issuer: Option<Pubkey>,
+}
+
+pub fn update_config(governor: &Pubkey, params: UpdateParams) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new_readonly(*governor, true),
+        ],
+        data: data("update_config", &params),
+    }
+}
+
+#[derive(AnchorSerialize)]
+struct SetCycleRootArgs {
+    cycle_index: u64,
+    merkle_root: [u8; 32],
+    total_points_declared: u32,
+    claims_bitmap_len: u32,
+    leaf_count: u32,
+    leaf_format: u8,
+}
+
+/// The governor also pays for the new cycle account.
+pub fn set_cycle_root(
+    governor: &Pubkey,
+    cycle_index: u64,
+    merkle_root: [u8; 32],
+    total_points_declared: u32,
+    claims_bitmap_len: u32,
+    leaf_count: u32,
+    leaf_format: LeafFormat,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*governor, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new(cycle_state_pda(cycle_index), false),
+        ],
+        data: data(
+            "set_cycle_root",
+            &SetCycleRootArgs {
+                cycle_index,
+                merkle_root,
+                total_points_declared,
+                claims_bitmap_len,
+                leaf_count,
+                leaf_format: leaf_format as u8,
+            },
+        ),
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ClaimKarmaArgs {
+    pub owner: Pubkey,
+    pub cycle_index: u64,
+    pub delta_points: i32,
+    pub leaf_index: u32,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Anyone may submit a claim; `payer` funds the ledger on first claim.
+pub fn claim_karma(
+    payer: &Pubkey,
+    owner: Pubkey,
+    cycle_index: u64,
+    delta_points: i32,
+    leaf_index: u32,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new(cycle_state_pda(cycle_index), false),
+            AccountMeta::new(peer_pda(&owner), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            "claim_karma",
+            &ClaimKarmaArgs {
+                owner,
+                cycle_index,
+                delta_points,
+                leaf_index,
+                proof,
+            },
+        ),
+    }
+}
+
+pub fn credit_points(issuer: &Pubkey, payer: &Pubkey, owner: Pubkey, points: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(*issuer, true),
+            AccountMeta::new(peer_pda(&owner), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data("credit_points", &(owner, points)),
+    }
+}
+
+/// `amount` is in base units.
+pub fn issue_tokens(issuer: &Pubkey, mint: &Pubkey, recipient_ata: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new_readonly(*issuer, true),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(mint_authority_pda(), false),
+            AccountMeta::new(*recipient_ata, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: data("issue_tokens", &amount),
+    }
+}
+
+/// `tokens_to_mint_whole` is in whole SEKA, not base units.
+pub fn convert_points_to_tokens(
+    owner: Pubkey,
+    mint: &Pubkey,
+    recipient_ata: &Pubkey,
+    tokens_to_mint_whole: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new(peer_pda(&owner), false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(mint_authority_pda(), false),
+            AccountMeta::new(*recipient_ata, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: data("convert_points_to_tokens", &(owner, tokens_to_mint_whole)),
+    }
+}
+
+/// `payer` pays the join cost from its ATA of `mint` and the membership rent.
+pub fn join_network(payer: &Pubkey, mint: &Pubkey, member: Pubkey) -> Instruction {
+    let treasury = treasury_pda();
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new_readonly(treasury, false),
+            AccountMeta::new(associated_token_address(&treasury, mint), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(payer, mint), false),
+            AccountMeta::new(membership_pda(&member), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data("join_network", &member),
+    }
+}
+
+pub fn deactivate_membership(governor: &Pubkey, member: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new_readonly(*governor, true),
+            AccountMeta::new(membership_pda(&member), false),
+        ],
+        data: data("deactivate_membership", &member),
+    }
+}
+
+pub fn deactivate_self(owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(membership_pda(owner), false),
+        ],
+        data: data("deactivate_self", &()),
+    }
+}
+
+/// The governor also pays for the transfer account on first nomination;
+/// `Pubkey::default()` cancels a pending handover.
+pub fn nominate_governor(governor: &Pubkey, new_governor: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(config_pda(), false),
+            AccountMeta::new(*governor, true),
+            AccountMeta::new(governor_transfer_pda(), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data("nominate_governor", &new_governor),
+    }
+}
+
+/// Signed by the nominated key, which receives the transfer account's rent.
+pub fn accept_governor(new_governor: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new(*new_governor, true),
+            AccountMeta::new(governor_transfer_pda(), false),
+        ],
+        data: data("accept_governor", &()),
+    }
+}
+
+/// The governor receives the cycle account's rent. Closing also stops `cycle_index` and
+/// every earlier cycle from being published again.
+pub fn close_cycle(governor: &Pubkey, cycle_index: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(config_pda(), false),
+            AccountMeta::new(*governor, true),
+            AccountMeta::new(cycle_state_pda(cycle_index), false),
+        ],
+        data: data("close_cycle", &cycle_index),
+    }
+}
+
+/// `migrate_accounts`: grows the config and `accounts` to their current size and brings
+/// them up to their current layout version, the governor paying the rent.
+pub fn migrate_accounts(governor: &Pubkey, accounts: &[Pubkey]) -> Instruction {
+    let mut metas = vec![
+        AccountMeta::new(config_pda(), false),
+        AccountMeta::new(*governor, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    metas.extend(accounts.iter().map(|a| AccountMeta::new(*a, false)));
+    Instruction {
+        program_id: ID,
+        accounts: metas,
+        data: data("migrate_accounts", &()),
+    }
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+sentinelkarma_core::seka_events!(anchor);
+
+pub const INSTRUCTION_NAMES: &[&str] = &[
+    "initialize",
+    "update_config",
+    "set_cycle_root",
+    "claim_karma",
+    "credit_points",
+    "issue_tokens",
+    "convert_points_to_tokens",
+    "join_network",
+    "deactivate_membership",
+    "deactivate_self",
+    "nominate_governor",
+    "accept_governor",
+    "close_cycle",
+    "migrate_accounts",
+];
+
+/// Name of the seka instruction `data` belongs to.
+pub fn instruction_name(data: &[u8]) -> Option<&'static str> {
+    crate::lookup_name("global", INSTRUCTION_NAMES, data)
+}
+
+/// Name of the seka event `data` (discriminator included) encodes.
+pub fn event_name(data: &[u8]) -> Option<&'static str> {
+    crate::lookup_name("event", EVENT_NAMES, data)
+}
+
+#[derive(Debug)]
+pub enum SekaEvent {
+    Initialized(Initialized),
+    ConfigUpdated(ConfigUpdated),
+    CycleRootSet(CycleRootSet),
+    KarmaClaimed(KarmaClaimed),
+    PointsCredited(PointsCredited),
+    TokensIssued(TokensIssued),
+    PointsConverted(PointsConverted),
+    Joined(Joined),
+    MembershipDeactivated(MembershipDeactivated),
+    GovernorNominated(GovernorNominated),
+    GovernorAccepted(GovernorAccepted),
+    CycleClosed(CycleClosed),
+    AccountMigrated(AccountMigrated),
+}
+
+impl SekaEvent {
+    /// Decodes one `emit!` payload; `None` if it is not a seka event.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        use crate::decode_event;
+        decode_event(data)
+            .map(Self::Initialized)
+            .or_else(|| decode_event(data).map(Self::ConfigUpdated))
+            .or_else(|| decode_event(data).map(Self::CycleRootSet))
+            .or_else(|| decode_event(data).map(Self::KarmaClaimed))
+            .or_else(|| decode_event(data).map(Self::PointsCredited))
+            .or_else(|| decode_event(data).map(Self::TokensIssued))
+            .or_else(|| decode_event(data).map(Self::PointsConverted))
+            .or_else(|| decode_event(data).map(Self::Joined))
+            .or_else(|| decode_event(data).map(Self::MembershipDeactivated))
+            .or_else(|| decode_event(data).map(Self::GovernorNominated))
+            .or_else(|| decode_event(data).map(Self::GovernorAccepted))
+            .or_else(|| decode_event(data).map(Self::CycleClosed))
+            .or_else(|| decode_event(data).map(Self::AccountMigrated))
+    }
+
+    /// All seka events in a transaction's log messages.
+    pub fn from_logs(logs: &[String]) -> Vec<Self> {
+        crate::log_event_data(logs)
+            .iter()
+            .filter_map(|data| Self::decode(data))
+            .collect()
+    }
+}
+
+impl fmt::Display for SekaEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SekaEvent::Initialized(e) => write!(
+                f,
+                "Initialized: governor {} mint {} treasury {}",
+                e.governor, e.mint, e.treasury
+            ),
+            SekaEvent::ConfigUpdated(e) => write!(
+                f,
+                "ConfigUpdated: cycle_secs {} max_points_per_cycle {} per_peer_cycle_cap {} \
+                 conversion_ratio {} join_cost_tokens {}",
+                e.cycle_secs,
+                e.max_points_per_cycle,
+                e.per_peer_cycle_cap,
+                e.conversion_ratio,
+                e.join_cost_tokens
+            ),
+            SekaEvent::CycleRootSet(e) => write!(
+                f,
+                "CycleRootSet: cycle {} root {} total_points_declared {}",
+                e.cycle_index,
+                hex::encode(e.merkle_root),
+                e.total_points_declared
+            ),
+            SekaEvent::KarmaClaimed(e) => write!(
+                f,
+                "KarmaClaimed: {} cycle {} delta {} -> {} points",
+                e.owner, e.cycle_index, e.delta_points, e.new_points
+            ),
+            SekaEvent::PointsCredited(e) => write!(
+                f,
+                "PointsCredited: {} +{} by {} -> {} points",
+                e.owner, e.points, e.issuer, e.new_points
+            ),
+            SekaEvent::TokensIssued(e) => write!(
+                f,
+                "TokensIssued: {} base units to {} by {}",
+                e.amount, e.recipient, e.issuer
+            ),
+            SekaEvent::PointsConverted(e) => write!(
+                f,
+                "PointsConverted: {} spent {} points for {} SEKA",
+                e.owner, e.points_spent, e.tokens_minted
+            ),
+            SekaEvent::Joined(e) => write!(f, "Joined: {}", e.member),
+            SekaEvent::MembershipDeactivated(e) => write!(
+                f,
+                "MembershipDeactivated: member {} by {}",
+                e.member, e.actor
+            ),
+            SekaEvent::GovernorNominated(e) => write!(
+                f,
+                "GovernorNominated: {} nominated {}",
+                e.current, e.pending
+            ),
+            SekaEvent::GovernorAccepted(e) => write!(
+                f,
+                "GovernorAccepted: {} replaces {}",
+                e.governor, e.previous
+            ),
+            SekaEvent::CycleClosed(e) => write!(
+                f,
+                "CycleClosed: cycle {} returned {} lamports{}",
+                e.cycle_index,
+                e.lamports,
+                if e.fully_claimed { " (fully claimed)" } else { "" }
+            ),
+            SekaEvent::AccountMigrated(e) => write!(
+                f,
+                "AccountMigrated: {} v{} -> v{}",
+                e.account, e.from_version, e.to_version
+            ),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! sentinel program: the program crate's own types plus PDA helpers.
+//!
+//! Instructions are built from the generated pairs, e.g.
+//! `sentinel::instruction(sentinel::accounts::LikeNft { .. }, sentinel::instruction::LikeNft { .. })`.
+//! Contexts marked `#[event_cpi]` also take `event_authority: event_authority_pda()` and
+//! `program: ID`.
+
+pub use sentinel_program::*;
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use solana_sdk::pubkey::Pubkey;
+
+/// Builds a sentinel instruction from its generated accounts and data structs.
+pub fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+// ============================================================================
+// Global PDAs
+// ============================================================================
+
+pub fn state_pda() -> Pubkey {
+    pda(&[STATE_SEED])
+}
+
+pub fn treasury_vault_pda() -> Pubkey {
+    pda(&[TREASURY_VAULT_SEED])
+}
+
+pub fn sol_vault_pda() -> Pubkey {
+    pda(&[SOL_VAULT_SEED])
+}
+
+pub fn vesting_pda() -> Pubkey {
+    pda(&[VESTING_SEED])
+}
+
+/// Signer of `emit_cpi!` self-invocations.
+pub fn event_authority_pda() -> Pubkey {
+    pda(&[b"__event_authority"])
+}
+
+// ============================================================================
+// Per-cycle PDAs
+// ============================================================================
+
+pub fn top_posts_pda(cycle_index: u64) -> Pubkey {
+    pda(&[TOP_POSTS_SEED, &cycle_index.to_le_bytes()])
+}
+
+pub fn cycle_record_pda(cycle_index: u64) -> Pubkey {
+    pda(&[CYCLE_RECORD_SEED, &cycle_index.to_le_bytes()])
+}
+
+// ============================================================================
+// Per-user PDAs
+// ============================================================================
+
+/// `PeerState` of `user`.
+pub fn peer_pda(user: &Pubkey) -> Pubkey {
+    pda(&[PEER_SEED, user.as_ref()])
+}
+
+pub fn invite_pda(invitee: &Pubkey) -> Pubkey {
+    pda(&[INVITE_SEED, invitee.as_ref()])
+}
+
+pub fn author_index_pda(user: &Pubkey) -> Pubkey {
+    pda(&[AUTHOR_INDEX_SEED, user.as_ref()])
+}
+
+/// `n`-th post created by `user`.
+pub fn user_post_pda(user: &Pubkey, n: u64) -> Pubkey {
+    pda(&[USER_POST_SEED, user.as_ref(), &n.to_le_bytes()])
+}
+
+pub fn karma_history_pda(user: &Pubkey) -> Pubkey {
+    pda(&[KARMA_HISTORY_SEED, user.as_ref()])
+}
+
+/// `name` must already be normalized (lowercase).
+pub fn username_pda(name: &str) -> Pubkey {
+    pda(&[USERNAME_SEED, name.as_bytes()])
+}
+
+pub fn post_commit_pda(user: &Pubkey, commitment: &[u8; 32]) -> Pubkey {
+    pda(&[POST_COMMIT_SEED, user.as_ref(), commitment])
+}
+
+pub fn like_pair_pda(liker: &Pubkey, author: &Pubkey) -> Pubkey {
+    pda(&[LIKE_PAIR_SEED, liker.as_ref(), author.as_ref()])
+}
+
+/// Like of `liker` on a post or comment account.
+pub fn like_pda(liker: &Pubkey, target: &Pubkey) -> Pubkey {
+    pda(&[LIKE_SEED, liker.as_ref(), target.as_ref()])
+}
+
+// ============================================================================
+// Post PDAs
+// ============================================================================
+
+/// Post of an SPL mint or Core asset.
+pub fn post_pda(asset: &Pubkey) -> Pubkey {
+    pda(&[POST_SEED, asset.as_ref()])
+}
+
+pub fn post_index_pda(post_id: u64) -> Pubkey {
+    pda(&[POST_INDEX_SEED, &post_id.to_le_bytes()])
+}
+
+pub fn bond_escrow_pda(post: &Pubkey) -> Pubkey {
+    pda(&[BOND_SEED, post.as_ref()])
+}
+
+pub fn listing_pda(post: &Pubkey) -> Pubkey {
+    pda(&[LISTING_SEED, post.as_ref()])
+}
+
+pub fn comment_pda(post: &Pubkey, index: u64) -> Pubkey {
+    pda(&[COMMENT_SEED, post.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn report_pda(post: &Pubkey, reporter: &Pubkey) -> Pubkey {
+    pda(&[REPORT_SEED, post.as_ref(), reporter.as_ref()])
+}
+
+pub fn report_bond_pda(report: &Pubkey) -> Pubkey {
+    pda(&[REPORT_BOND_SEED, report.as_ref()])
+}
+
+// ============================================================================
+// Registry PDAs
+// ============================================================================
+
+pub fn achievement_pda(id: u32) -> Pubkey {
+    pda(&[ACHIEVEMENT_SEED, &id.to_le_bytes()])
+}
+
+pub fn badge_pda(achievement: &Pubkey, user: &Pubkey) -> Pubkey {
+    pda(&[BADGE_SEED, achievement.as_ref(), user.as_ref()])
+}
+
+pub fn storage_provider_pda(provider: &Pubkey) -> Pubkey {
+    pda(&[STORAGE_PROVIDER_SEED, provider.as_ref()])
+}
+
+pub fn tag_pda(tag: &[u8; TAG_LEN]) -> Pubkey {
+    pda(&[TAG_SEED, tag])
+}
+
+pub fn tag_karma_pda(tag: &[u8; TAG_LEN], user: &Pubkey) -> Pubkey {
+    pda(&[TAG_KARMA_SEED, tag, user.as_ref()])
+}
+
+/// Zero-padded tag bytes for `name`; `None` if it is too long.
+pub fn tag_bytes(name: &str) -> Option<[u8; TAG_LEN]> {
+    let mut tag = [0u8; TAG_LEN];
+    tag.get_mut(..name.len())?.copy_from_slice(name.as_bytes());
+    Some(tag)
+}
+
+// ============================================================================
+// Token accounts
+// ============================================================================
+
+/// Associated token account of `wallet`; `token_program` is SPL Token or Token-2022,
+/// whichever owns the sentinel mint.
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &crate::seka::ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// `CreateIdempotent`: creates the associated token account unless it already exists.
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: crate::seka::ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(wallet, mint, token_program), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![1],
+    }
+}
+
+// ============================================================================
+// Content addresses
+// ============================================================================
+
+/// Where a post's content lives, in the form gateways and pinning services take.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContentLocation {
+    /// CIDv1 string, multibase base32 (`b…`)
+    Ipfs(String),
+    /// Arweave transaction id, base64url
+    Arweave(String),
+}
+
+impl ContentLocation {
+    /// `post`'s content address; `None` for legacy posts, whose `db_addr` is opaque.
+    pub fn of(post: &Post) -> Option<Self> {
+        let addr = &post.content_addr[..(post.content_len as usize).min(post.content_addr.len())];
+        match post.content_scheme {
+            s if s == ContentScheme::Ipfs as u8 => {
+                Some(Self::Ipfs(format!("b{}", base32_lower(addr))))
+            }
+            s if s == ContentScheme::Arweave as u8 => {
+                use base64::Engine;
+                let id = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(addr);
+                Some(Self::Arweave(id))
+            }
+            _ => None,
+        }
+    }
+
+    /// The content's URL under an IPFS (`/ipfs/<cid>`) or Arweave (`/<id>`) gateway.
+    pub fn url(&self, ipfs_gateway: &str, arweave_gateway: &str) -> String {
+        match self {
+            Self::Ipfs(cid) => format!("{}/ipfs/{cid}", ipfs_gateway.trim_end_matches('/')),
+            Self::Arweave(id) => format!("{}/{id}", arweave_gateway.trim_end_matches('/')),
+        }
+    }
+}
+
+/// RFC 4648 base32, lowercase and unpadded.
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+// ============================================================================
+// Names
+// ============================================================================
+
+pub const INSTRUCTION_NAMES: &[&str] = &[
+    "initialize",
+    "initialize_shared_mint",
+    "claim_vested_supply",
+    "distribute_initial_supply",
+    "update_params",
+    "migrate_accounts",
+    "set_committee",
+    "nominate_authority",
+    "accept_authority",
+    "pause",
+    "unpause",
+    "treasury_withdraw",
+    "burn_from_treasury",
+    "set_transfer_fee",
+    "harvest_transfer_fees",
+    "add_storage_provider",
+    "remove_storage_provider",
+    "create_invite",
+    "join_network",
+    "join_network_with_sol",
+    "withdraw_sol",
+    "leave_network",
+    "register_username",
+    "release_username",
+    "create_achievement",
+    "claim_achievement",
+    "mint_nft",
+    "mint_core_post",
+    "commit_post",
+    "reveal_post",
+    "cancel_post_commit",
+    "create_post_index",
+    "update_post",
+    "like_nft",
+    "create_comment",
+    "like_comment",
+    "init_tag",
+    "set_tag_budget",
+    "claim_tag_reward",
+    "init_karma_history",
+    "init_top_posts",
+    "claim_top_post_bonus",
+    "remove_post",
+    "close_post",
+    "refund_bond",
+    "slash_bond",
+    "file_report",
+    "resolve_report",
+    "delete_post",
+    "close_like",
+    "sync_post_owner",
+    "list_post_nft",
+    "cancel_listing",
+    "buy_post_nft",
+    "finalize_cycle",
+    "reset_karma",
+    "crank_finalize_cycle",
+    "crank_reset_karma",
+    "sync_karma_to_seka",
+];
+
+/// Every sentinel event is sent with `emit_cpi!`, i.e. as self-CPI instruction data.
+pub const EVENT_NAMES: &[&str] = &[
+    "PeerJoined",
+    "InviteCreated",
+    "PeerJoinedWithSol",
+    "PeerLeft",
+    "SolWithdrawn",
+    "UsernameRegistered",
+    "UsernameReleased",
+    "AchievementCreated",
+    "AchievementClaimed",
+    "CommentCreated",
+    "CommentLiked",
+    "TagBudgetSet",
+    "TagRewardClaimed",
+    "ReportFiled",
+    "ReportResolved",
+    "PostRemoved",
+    "PostClosed",
+    "PostDeleted",
+    "PostOwnerSynced",
+    "PostListed",
+    "ListingCancelled",
+    "PostSold",
+    "PostUpdated",
+    "BondRefunded",
+    "BondSlashed",
+    "AccountMigrated",
+    "StorageProviderAdded",
+    "StorageProviderRemoved",
+    "CommitteeUpdated",
+    "AuthorityNominated",
+    "AuthorityAccepted",
+    "PauseChanged",
+    "TopPostBonusClaimed",
+    "CycleFinalized",
+    "TreasuryWithdrawn",
+    "TreasuryBurned",
+    "VestedSupplyClaimed",
+    "GenesisDistributed",
+    "KarmaSyncedToSeka",
+    "TransferFeeUpdated",
+    "TransferFeesHarvested",
+];
+
+/// Name of the sentinel instruction `data` belongs to.
+pub fn instruction_name(data: &[u8]) -> Option<&'static str> {
+    crate::lookup_name("global", INSTRUCTION_NAMES, data)
+}
+
+/// Name of the sentinel event `data` (discriminator included, CPI tag stripped) encodes.
+pub fn event_name(data: &[u8]) -> Option<&'static str> {
+    crate::lookup_name("event", EVENT_NAMES, data)
+}
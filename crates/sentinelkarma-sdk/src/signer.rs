@@ -0,0 +1,236 @@
+//! Signers for CLIs that should not hold the governor or treasury key on disk.
+//!
+//! [`resolve`] turns a `--keypair`-style argument into a [`Signer`]:
+//!
+//! - a keypair file (`~/` is expanded);
+//! - `usb://ledger[?key=<account>/<change>]`, a Ledger, with the `ledger` feature;
+//! - `http(s)://…?pubkey=<base58>`, a [`RemoteSigner`]: an HSM, KMS or signing service
+//!   that holds the key and signs whatever message it is sent.
+//!
+//! A remote signer is sent `POST <url>` with `{"pubkey", "message"}`, the message being
+//! the base64 serialized transaction message, and answers `{"signature"}` in base58.
+//! `SEKA_REMOTE_SIGNER_TOKEN`, if set, goes along as a bearer token. The pubkey is
+//! part of the locator so that building, simulating and printing transactions never
+//! reaches the service; only signing does, and the signature is checked before use.
+
+#[cfg(feature = "client")]
+use crate::client::{Client, ClientConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::json;
+use solana_remote_wallet::locator::Locator;
+use solana_remote_wallet::remote_keypair::generate_remote_keypair;
+use solana_remote_wallet::remote_wallet::maybe_wallet_manager;
+use solana_sdk::derivation_path::DerivationPath;
+#[cfg(feature = "client")]
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signature, Signer};
+use solana_sdk::signer::SignerError;
+use std::str::FromStr;
+#[cfg(feature = "client")]
+use std::sync::mpsc;
+use std::time::Duration;
+#[cfg(feature = "client")]
+use tokio::sync::oneshot;
+
+pub const REMOTE_SIGNER_TOKEN_ENV: &str = "SEKA_REMOTE_SIGNER_TOKEN";
+
+/// Long enough for a signing service that asks a human to approve.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Resolves a keypair file, `usb://` or `http(s)://` locator into a signer.
+pub fn resolve(locator: &str) -> Result<Box<dyn Signer>> {
+    if locator.starts_with("usb://") {
+        return ledger(locator);
+    }
+    if locator.starts_with("https://") || locator.starts_with("http://") {
+        return Ok(Box::new(RemoteSigner::from_locator(locator)?));
+    }
+    let path = expand_home(locator);
+    let keypair = read_keypair_file(&path).map_err(|e| anyhow!("reading keypair {path}: {e}"))?;
+    Ok(Box::new(keypair))
+}
+
+/// `path` with a leading `~/` replaced by `$HOME`.
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+fn ledger(locator: &str) -> Result<Box<dyn Signer>> {
+    let (locator, key) = match locator.split_once("?key=") {
+        Some((locator, key)) => (locator, Some(key)),
+        None => (locator, None),
+    };
+    let derivation_path = match key {
+        Some(key) => DerivationPath::from_key_str(key)
+            .map_err(|e| anyhow!("invalid derivation path {key:?}: {e}"))?,
+        None => DerivationPath::default(),
+    };
+    let locator = Locator::new_from_path(locator).context("invalid usb:// locator")?;
+    let wallet_manager = maybe_wallet_manager()
+        .map_err(|e| anyhow!("hardware wallet unavailable (built without `ledger`?): {e}"))?
+        .ok_or_else(|| anyhow!("no hardware wallet found"))?;
+    let keypair =
+        generate_remote_keypair(locator, derivation_path, &wallet_manager, true, "signer")
+            .map_err(|e| anyhow!("opening ledger: {e}"))?;
+    Ok(Box::new(keypair))
+}
+
+/// A key held by a signing service; see the module docs for the protocol.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    url: String,
+    pubkey: Pubkey,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+impl RemoteSigner {
+    pub fn new(url: impl Into<String>, pubkey: Pubkey, token: Option<String>) -> Self {
+        Self {
+            url: url.into(),
+            pubkey,
+            token,
+        }
+    }
+
+    /// `http(s)://…?pubkey=<base58>`, with the token from `SEKA_REMOTE_SIGNER_TOKEN`.
+    pub fn from_locator(locator: &str) -> Result<Self> {
+        let (_, query) = locator
+            .split_once('?')
+            .ok_or_else(|| anyhow!("remote signer {locator} needs ?pubkey=<base58>"))?;
+        let Some(pubkey) = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("pubkey="))
+        else {
+            bail!("remote signer {locator} needs ?pubkey=<base58>");
+        };
+        let pubkey = Pubkey::from_str(pubkey)
+            .map_err(|e| anyhow!("invalid remote signer pubkey {pubkey:?}: {e}"))?;
+        Ok(Self::new(
+            locator,
+            pubkey,
+            std::env::var(REMOTE_SIGNER_TOKEN_ENV).ok(),
+        ))
+    }
+
+    fn request(&self, message: &[u8]) -> Result<Signature> {
+        let body = json!({
+            "pubkey": self.pubkey.to_string(),
+            "message": STANDARD.encode(message),
+        });
+        // reqwest's blocking client may not be created or dropped on an async runtime
+        // thread, and signing is called from async code (the SDK `Client`, keepers)
+        let response: SignResponse = std::thread::scope(|scope| {
+            scope
+                .spawn(|| -> Result<SignResponse> {
+                    let http = reqwest::blocking::Client::builder()
+                        .timeout(REMOTE_TIMEOUT)
+                        .build()?;
+                    let mut request = http.post(&self.url).json(&body);
+                    if let Some(token) = &self.token {
+                        request = request.bearer_auth(token);
+                    }
+                    Ok(request.send()?.error_for_status()?.json()?)
+                })
+                .join()
+                .map_err(|_| anyhow!("remote signer request panicked"))?
+        })
+        .with_context(|| format!("remote signer {}", self.url))?;
+        let signature = Signature::from_str(&response.signature)
+            .map_err(|e| anyhow!("remote signer returned an invalid signature: {e}"))?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            bail!(
+                "remote signer returned a signature that is not {}'s",
+                self.pubkey
+            );
+        }
+        Ok(signature)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.request(message)
+            .map_err(|e| SignerError::Custom(format!("{e:#}")))
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "client")]
+type Job = (Vec<Instruction>, oneshot::Sender<Result<Signature>>);
+
+/// A [`resolve`]d signer sending transactions through a [`Client`] as their fee payer,
+/// for services that sign from async handlers.
+///
+/// Hardware and remote signers cannot be shared between threads, so the signer lives on
+/// its own thread and transactions are queued to it one at a time.
+#[cfg(feature = "client")]
+pub struct SignerThread {
+    pub pubkey: Pubkey,
+    jobs: mpsc::Sender<Job>,
+    /// Whose key this is, for errors
+    role: &'static str,
+}
+
+#[cfg(feature = "client")]
+impl SignerThread {
+    /// Resolves `locator` on the signing thread; `role` names the key in errors.
+    pub fn spawn(role: &'static str, locator: String, rpc_url: String) -> Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let (ready, started) = mpsc::channel();
+        std::thread::spawn(move || {
+            let signer = match resolve(&locator) {
+                Ok(signer) => signer,
+                Err(e) => {
+                    let _ = ready.send(Err(e));
+                    return;
+                }
+            };
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready.send(Err(e.into()));
+                    return;
+                }
+            };
+            let _ = ready.send(Ok(signer.pubkey()));
+            let client = Client::new(rpc_url, ClientConfig::default());
+            for (ixs, reply) in queue {
+                let result = runtime.block_on(client.send(&ixs, signer.as_ref(), &[]));
+                let _ = reply.send(result);
+            }
+        });
+        let pubkey = started
+            .recv()
+            .map_err(|_| anyhow!("{role} signer thread exited"))??;
+        Ok(Self { pubkey, jobs, role })
+    }
+
+    pub async fn send(&self, ixs: Vec<Instruction>) -> Result<Signature> {
+        let exited = || anyhow!("{} signer thread exited", self.role);
+        let (reply, result) = oneshot::channel();
+        self.jobs.send((ixs, reply)).map_err(|_| exited())?;
+        result.await.map_err(|_| exited())?
+    }
+}
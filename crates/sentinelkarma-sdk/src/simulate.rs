@@ -0,0 +1,289 @@
+//! Dry runs of `claim_karma` and `convert_points_to_tokens` through `simulateTransaction`.
+//!
+//! A rejected claim only says `custom program error: 0x1772` on its own. [`claim`] and
+//! [`convert`] read the Anchor error from the simulation logs and explain it in terms of
+//! the arguments given (the proof does not match the root, the leaf is already claimed,
+//! the delta is over the cap, ...). On success they report the state the transaction
+//! would leave: the ledger's points and, for conversions, the recipient's balance.
+
+use crate::seka::{self, CycleState, GlobalConfig, PeerLedger, SekaEvent};
+use crate::{decode_account, fetch_account};
+use anyhow::{Context, Result};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::fmt;
+
+/// Offset of `amount` in an SPL token account.
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// Why the transaction would fail; `None` if it would succeed
+    pub failure: Option<Failure>,
+    pub events: Vec<SekaEvent>,
+    /// State the transaction would leave, as `(what, before, after)`
+    pub changes: Vec<(String, String, String)>,
+}
+
+#[derive(Debug)]
+pub struct Failure {
+    /// Anchor error name, e.g. `InvalidMerkleProof`, or the raw transaction error
+    pub error: String,
+    pub code: Option<u32>,
+    /// What it means for the arguments given
+    pub explanation: String,
+}
+
+impl Outcome {
+    pub fn ok(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.failure {
+            Some(failure) => {
+                write!(f, "would fail: {}", failure.error)?;
+                if let Some(code) = failure.code {
+                    write!(f, " ({code})")?;
+                }
+                writeln!(f)?;
+                writeln!(f, "  {}", failure.explanation)?;
+            }
+            None => writeln!(f, "would succeed")?,
+        }
+        for (what, before, after) in &self.changes {
+            writeln!(f, "  {what}: {before} -> {after}")?;
+        }
+        for event in &self.events {
+            writeln!(f, "  event: {event}")?;
+        }
+        if let Some(units) = self.units_consumed {
+            writeln!(f, "  compute units: {units}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Simulates `claim_karma` for one leaf, paid by `payer`.
+pub fn claim(
+    client: &RpcClient,
+    payer: &Pubkey,
+    owner: Pubkey,
+    cycle_index: u64,
+    delta_points: i32,
+    leaf_index: u32,
+    proof: Vec<[u8; 32]>,
+) -> Result<Outcome> {
+    let config: GlobalConfig = fetch_account(client, &seka::config_pda())?;
+    let ledger_address = seka::peer_pda(&owner);
+    let before = points(client, &ledger_address)?;
+    let bitmap_len = fetch_account::<CycleState>(client, &seka::cycle_state_pda(cycle_index))
+        .ok()
+        .map(|state| state.claims_bitmap.len());
+    let ix = seka::claim_karma(payer, owner, cycle_index, delta_points, leaf_index, proof);
+
+    let explain = |error: &str| -> String {
+        match error {
+            // The program reports a leaf past the bitmap with the same error
+            "InvalidMerkleProof" if bitmap_len.is_some_and(|len| leaf_index as usize / 8 >= len) => {
+                format!(
+                    "the proof verifies, but leaf {leaf_index} is past the end of cycle \
+                     {cycle_index}'s claims bitmap; the root was published with too short a \
+                     claims_bitmap_len"
+                )
+            }
+            "InvalidMerkleProof" => format!(
+                "the proof does not lead to cycle {cycle_index}'s root from ({owner}, delta \
+                 {delta_points}, leaf {leaf_index}); the delta, leaf index or proof is not \
+                 the one published"
+            ),
+            "ClaimAlreadyProcessed" => {
+                format!("leaf {leaf_index} of cycle {cycle_index} has already been claimed")
+            }
+            "DeltaExceedsPerPeerCap" => format!(
+                "|{delta_points}| is over per_peer_cycle_cap {}",
+                config.per_peer_cycle_cap
+            ),
+            "InvalidCycle" => format!("the cycle state at that address is not cycle {cycle_index}"),
+            "AccountNotInitialized" => {
+                format!("no root has been published for cycle {cycle_index}")
+            }
+            "WrongLedgerOwner" => format!("{ledger_address} belongs to another owner"),
+            "MathOverflow" => "the ledger's points would overflow".into(),
+            _ => generic(error),
+        }
+    };
+    let (mut outcome, post) = run(client, payer, &[ix], &[ledger_address], explain)?;
+    if outcome.ok() {
+        let before = before.map_or("none".into(), |p| p.to_string());
+        outcome.changes = vec![
+            ("ledger points".into(), before, points_after(&post, 0)),
+            (
+                format!("cycle {cycle_index} leaf {leaf_index}"),
+                "unclaimed".into(),
+                "claimed".into(),
+            ),
+        ];
+    }
+    Ok(outcome)
+}
+
+/// Simulates converting `tokens_whole` SEKA worth of `owner`'s points into
+/// `recipient_ata`, with `owner` paying the fee.
+pub fn convert(
+    client: &RpcClient,
+    owner: Pubkey,
+    recipient_ata: &Pubkey,
+    tokens_whole: u64,
+) -> Result<Outcome> {
+    let config: GlobalConfig = fetch_account(client, &seka::config_pda())?;
+    let ledger_address = seka::peer_pda(&owner);
+    let before = points(client, &ledger_address)?;
+    let balance_before = token_amount(client, recipient_ata)?;
+    let ix = seka::convert_points_to_tokens(owner, &config.mint, recipient_ata, tokens_whole);
+
+    let needed = u128::from(tokens_whole) * u128::from(config.conversion_ratio);
+    let explain = |error: &str| -> String {
+        match error {
+            "InsufficientPointsToConvert" => format!(
+                "{tokens_whole} SEKA needs {needed} points at ratio {}; the ledger has {}",
+                config.conversion_ratio,
+                before.map_or("none".into(), |p| p.to_string())
+            ),
+            "AccountNotInitialized" => format!("{owner} has no ledger; it must claim karma first"),
+            "WrongLedgerOwner" => format!("{ledger_address} belongs to another owner"),
+            "MathOverflow" => format!("{tokens_whole} SEKA overflows in base units"),
+            _ if balance_before.is_none() => {
+                format!("{recipient_ata} is not a token account; create the ATA first")
+            }
+            _ => generic(error),
+        }
+    };
+    let accounts = [ledger_address, *recipient_ata];
+    let (mut outcome, post) = run(client, &owner, &[ix], &accounts, explain)?;
+    if outcome.ok() {
+        let balance_after = post.get(1).and_then(|data| amount(data.as_deref()?));
+        let base = |amount: Option<u64>| {
+            amount.map_or("none".into(), |a| format_base(a, config.decimals))
+        };
+        let points_before = before.map_or("none".into(), |p| p.to_string());
+        outcome.changes = vec![
+            ("ledger points".into(), points_before, points_after(&post, 0)),
+            (format!("{recipient_ata} SEKA"), base(balance_before), base(balance_after)),
+        ];
+    }
+    Ok(outcome)
+}
+
+// ============================================================================
+// Plumbing
+// ============================================================================
+
+/// Simulates `ixs` and returns the outcome with the post-state of `accounts`, in order.
+fn run(
+    client: &RpcClient,
+    payer: &Pubkey,
+    ixs: &[Instruction],
+    accounts: &[Pubkey],
+    explain: impl Fn(&str) -> String,
+) -> Result<(Outcome, Vec<Option<Vec<u8>>>)> {
+    // Unsigned, so neither the payer nor the owner needs to be a local keypair
+    let tx = Transaction::new_unsigned(Message::new(ixs, Some(payer)));
+    let result = client
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(client.commitment()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: accounts.iter().map(Pubkey::to_string).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .context("simulating transaction")?
+        .value;
+
+    let logs = result.logs.unwrap_or_default();
+    let failure = result.err.map(|err| {
+        let (error, code) = match anchor_error(&logs) {
+            Some((name, code)) => (name, Some(code)),
+            None => (err.to_string(), None),
+        };
+        let explanation = explain(&error);
+        Failure { error, code, explanation }
+    });
+    let post = result
+        .accounts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|account| account.and_then(|a| a.data.decode()))
+        .collect();
+    let outcome = Outcome {
+        events: SekaEvent::from_logs(&logs),
+        logs,
+        units_consumed: result.units_consumed,
+        failure,
+        changes: Vec::new(),
+    };
+    Ok((outcome, post))
+}
+
+/// Name and number of the Anchor error in `logs`, from the line Anchor writes as
+/// `AnchorError ... Error Code: <name>. Error Number: <n>. Error Message: ...`.
+fn anchor_error(logs: &[String]) -> Option<(String, u32)> {
+    logs.iter().find_map(|line| {
+        let rest = line.split_once("Error Code: ")?.1;
+        let (name, rest) = rest.split_once(". Error Number: ")?;
+        let (number, _) = rest.split_once('.')?;
+        Some((name.to_owned(), number.parse().ok()?))
+    })
+}
+
+fn generic(error: &str) -> String {
+    format!("the program rejected the transaction with {error}; see the logs")
+}
+
+/// Current points of the ledger at `address`; `None` if it does not exist yet.
+fn points(client: &RpcClient, address: &Pubkey) -> Result<Option<i64>> {
+    let account = client
+        .get_account_with_commitment(address, client.commitment())?
+        .value;
+    account
+        .map(|a| decode_account::<PeerLedger>(&a.data).map(|ledger| ledger.points))
+        .transpose()
+}
+
+fn points_after(post: &[Option<Vec<u8>>], i: usize) -> String {
+    post.get(i)
+        .and_then(|data| decode_account::<PeerLedger>(data.as_deref()?).ok())
+        .map_or("unknown".into(), |ledger| ledger.points.to_string())
+}
+
+fn token_amount(client: &RpcClient, address: &Pubkey) -> Result<Option<u64>> {
+    let account = client
+        .get_account_with_commitment(address, client.commitment())?
+        .value;
+    Ok(account.and_then(|a| amount(&a.data)))
+}
+
+fn amount(data: &[u8]) -> Option<u64> {
+    let bytes = data.get(TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn format_base(amount: u64, decimals: u8) -> String {
+    let one = 10u64.pow(u32::from(decimals));
+    format!("{}.{:0width$}", amount / one, amount % one, width = decimals as usize)
+}
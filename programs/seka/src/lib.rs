@@ -1,15 +1,18 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::keccak::hashv;
+use anchor_lang::{system_program, Discriminator};
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
 
-declare_id!("SEKA111111111111111111111111111111111111111");
+sentinelkarma_core::seka_id!(declare_id);
 
-const DEFAULT_DECIMALS: u8 = 6;
-const DEFAULT_CYCLE_SECS: u64 = 259_200; // 3 days
-const DEFAULT_MAX_POINTS_PER_CYCLE: u32 = 10_000;
-const DEFAULT_PER_PEER_CYCLE_CAP: i32 = 100;
-const DEFAULT_CONVERSION_RATIO: u32 = 100; // KP per 1 SEKA
+use sentinelkarma_core::leaf::{self, LeafFormat};
+use sentinelkarma_core::seka::seeds::*;
+use sentinelkarma_core::seka::{
+    CLAIM_WINDOW_CYCLES, DEFAULT_CONVERSION_RATIO, DEFAULT_CYCLE_SECS, DEFAULT_DECIMALS,
+    DEFAULT_MAX_POINTS_PER_CYCLE, DEFAULT_PER_PEER_CYCLE_CAP,
+};
+use sentinelkarma_core::version::Versioned;
 
 #[program]
 pub mod seka {
@@ -24,7 +27,7 @@ pub mod seka {
         airdrop_whole_tokens: u64, // e.g., 10_000 for 10k SEKA
     ) -> Result<()> {
         let cfg = &mut ctx.accounts.config;
-        cfg.bump = *ctx.bumps.get("config").unwrap();
+        cfg.bump = ctx.bumps.config;
         cfg.governor = governor;
         cfg.treasury = ctx.accounts.treasury_pda.key();
         cfg.mint = ctx.accounts.mint.key();
@@ -37,15 +40,21 @@ pub mod seka {
         cfg.start_ts = start_ts;
         cfg.airdrop_done = false;
         cfg.decimals = decimals;
-
-        // Create the recipient ATA if not exists (optional best-effort)
-        // Expect recipient_ata to be provided or created externally.
+        cfg.issuer = Pubkey::default();
+        cfg.version = GlobalConfig::VERSION;
+        cfg.first_open_cycle = 0;
+
+        require_keys_eq!(
+            ctx.accounts.airdrop_recipient_wallet.key(),
+            airdrop_recipient,
+            ErrorCode::Unauthorized
+        );
         let base_units = airdrop_whole_tokens
             .checked_mul(pow10(decimals as u32))
             .ok_or(ErrorCode::MathOverflow)?;
 
         // Mint airdrop to recipient ATA using mint_authority PDA.
-        let seeds: &[&[u8]] = &[b"mint_authority", &[*ctx.bumps.get("mint_authority_pda").unwrap()]];
+        let seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority_pda]];
         let signer_seeds: &[&[&[u8]]] = &[seeds];
         let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -79,6 +88,7 @@ pub mod seka {
         if let Some(v) = params.treasury_owner {
             cfg.treasury = v;
         }
+        if let Some(v) = params.issuer { cfg.issuer = v; }
         emit!(ConfigUpdated {
             cycle_secs: cfg.cycle_secs,
             max_points_per_cycle: cfg.max_points_per_cycle,
@@ -95,17 +105,29 @@ pub mod seka {
         merkle_root: [u8; 32],
         total_points_declared: u32,
         claims_bitmap_len: u32,
+        leaf_count: u32,
+        leaf_format: u8,
     ) -> Result<()> {
         let cfg = &ctx.accounts.config;
         require_keys_eq!(ctx.accounts.signer.key(), cfg.governor, ErrorCode::Unauthorized);
+        // A closed cycle's claims bitmap is gone, so publishing it again would reopen its leaves
+        require!(cycle_index >= cfg.first_open_cycle, ErrorCode::CycleAlreadyClosed);
         require!(total_points_declared <= cfg.max_points_per_cycle, ErrorCode::TotalPointsExceedsCycleCap);
+        require!(
+            leaf_count > 0 && leaf::claims_bitmap_len(leaf_count) <= claims_bitmap_len,
+            ErrorCode::InvalidLeafCount
+        );
+        require!(LeafFormat::from_u8(leaf_format).is_some(), ErrorCode::InvalidLeafFormat);
 
         let state = &mut ctx.accounts.cycle_state;
-        state.bump = *ctx.bumps.get("cycle_state").unwrap();
+        state.bump = ctx.bumps.cycle_state;
         state.cycle_index = cycle_index;
         state.merkle_root = merkle_root;
         state.total_points_declared = total_points_declared;
         state.claims_bitmap = vec![0u8; claims_bitmap_len as usize];
+        state.version = CycleState::VERSION;
+        state.leaf_format = leaf_format;
+        state.leaf_count = leaf_count;
 
         emit!(CycleRootSet { cycle_index, merkle_root, total_points_declared });
         Ok(())
@@ -122,22 +144,23 @@ pub mod seka {
         let cfg = &ctx.accounts.config;
         let state = &mut ctx.accounts.cycle_state;
         require!(state.cycle_index == cycle_index, ErrorCode::InvalidCycle);
-        require!(delta_points.abs() as i32 <= cfg.per_peer_cycle_cap, ErrorCode::DeltaExceedsPerPeerCap);
+        require!(delta_points.abs() <= cfg.per_peer_cycle_cap, ErrorCode::DeltaExceedsPerPeerCap);
 
-        // Reconstruct leaf and verify Merkle proof using positional path from leaf_index bits.
-        let leaf_bytes = serialize_leaf(&owner, cycle_index, delta_points, leaf_index);
+        // Reconstruct leaf and verify Merkle proof in the format the cycle's root was built in.
+        let format = LeafFormat::from_u8(state.leaf_format).ok_or(ErrorCode::InvalidLeafFormat)?;
+        let leaf_bytes = format.serialize_leaf(&owner.to_bytes(), cycle_index, delta_points, leaf_index);
         let leaf_hash = keccak_hash(&leaf_bytes);
-        let computed_root = compute_merkle_root(leaf_hash, &proof, leaf_index);
+        let computed_root = compute_merkle_root(format, leaf_hash, &proof, leaf_index);
         require!(computed_root == state.merkle_root, ErrorCode::InvalidMerkleProof);
 
         // Check and set claim bit
-        require!(!is_claimed(&state.claims_bitmap, leaf_index), ErrorCode::ClaimAlreadyProcessed);
+        require!(!leaf::is_claimed(&state.claims_bitmap, leaf_index), ErrorCode::ClaimAlreadyProcessed);
         set_claimed(&mut state.claims_bitmap, leaf_index)?;
 
         // Upsert PeerLedger and apply delta with clamp to >= 0
         let ledger = &mut ctx.accounts.ledger;
         if ledger.owner == Pubkey::default() {
-            ledger.bump = *ctx.bumps.get("ledger").unwrap();
+            ledger.bump = ctx.bumps.ledger;
             ledger.owner = owner;
             ledger.points = 0;
             ledger.last_cycle_claimed = 0;
@@ -158,6 +181,52 @@ pub mod seka {
         Ok(())
     }
 
+    /// Issuer interface: lets the configured issuer (e.g. the sentinel state PDA, signing via
+    /// CPI) credit points straight into a ledger without a Merkle cycle.
+    pub fn credit_points(ctx: Context<CreditPoints>, owner: Pubkey, points: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config;
+        require_keys_eq!(ctx.accounts.issuer.key(), cfg.issuer, ErrorCode::Unauthorized);
+
+        let ledger = &mut ctx.accounts.ledger;
+        if ledger.owner == Pubkey::default() {
+            ledger.bump = ctx.bumps.ledger;
+            ledger.owner = owner;
+            ledger.points = 0;
+            ledger.last_cycle_claimed = 0;
+        } else {
+            require_keys_eq!(ledger.owner, owner, ErrorCode::WrongLedgerOwner);
+        }
+
+        let delta = i64::try_from(points).map_err(|_| ErrorCode::MathOverflow)?;
+        ledger.points = ledger.points.checked_add(delta).ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(PointsCredited { owner, issuer: cfg.issuer, points, new_points: ledger.points });
+        Ok(())
+    }
+
+    /// Issuer interface: lets the configured issuer mint SEKA (base units) to a token account,
+    /// e.g. sentinel paying its cycle rewards in SEKA when running in shared-mint mode.
+    pub fn issue_tokens(ctx: Context<IssueTokens>, amount: u64) -> Result<()> {
+        let cfg = &ctx.accounts.config;
+        require_keys_eq!(ctx.accounts.issuer.key(), cfg.issuer, ErrorCode::Unauthorized);
+
+        let seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority_pda]];
+        let signer_seeds: &[&[&[u8]]] = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.recipient_ata.to_account_info(),
+                authority: ctx.accounts.mint_authority_pda.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(cpi_ctx, amount)?;
+
+        emit!(TokensIssued { issuer: cfg.issuer, recipient: ctx.accounts.recipient_ata.key(), amount });
+        Ok(())
+    }
+
     pub fn convert_points_to_tokens(
         ctx: Context<ConvertPointsToTokens>,
         owner: Pubkey,
@@ -170,7 +239,7 @@ pub mod seka {
         let needed_points = (tokens_to_mint_whole as i64)
             .checked_mul(cfg.conversion_ratio as i64)
             .ok_or(ErrorCode::MathOverflow)?;
-        let convertible_tokens = (ledger.points as i64) / (cfg.conversion_ratio as i64);
+        let convertible_tokens = ledger.points / (cfg.conversion_ratio as i64);
         require!(tokens_to_mint_whole as i64 <= convertible_tokens, ErrorCode::InsufficientPointsToConvert);
 
         ledger.points = ledger
@@ -182,7 +251,7 @@ pub mod seka {
             .checked_mul(pow10(cfg.decimals as u32))
             .ok_or(ErrorCode::MathOverflow)?;
 
-        let seeds: &[&[u8]] = &[b"mint_authority", &[*ctx.bumps.get("mint_authority_pda").unwrap()]];
+        let seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, &[ctx.bumps.mint_authority_pda]];
         let signer_seeds: &[&[&[u8]]] = &[seeds];
         let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -215,7 +284,7 @@ pub mod seka {
         // Activate membership
         let m = &mut ctx.accounts.membership;
         if m.owner == Pubkey::default() {
-            m.bump = *ctx.bumps.get("membership").unwrap();
+            m.bump = ctx.bumps.membership;
             m.owner = member;
         } else {
             require_keys_eq!(m.owner, member, ErrorCode::WrongMembershipOwner);
@@ -233,6 +302,120 @@ pub mod seka {
         let m = &mut ctx.accounts.membership;
         require_keys_eq!(m.owner, member, ErrorCode::WrongMembershipOwner);
         m.active = false;
+
+        emit!(MembershipDeactivated { member, actor: ctx.accounts.signer.key() });
+        Ok(())
+    }
+
+    /// Member-initiated counterpart of `deactivate_membership`; no governor needed.
+    pub fn deactivate_self(ctx: Context<DeactivateSelf>) -> Result<()> {
+        let member = ctx.accounts.owner.key();
+        let m = &mut ctx.accounts.membership;
+        require_keys_eq!(m.owner, member, ErrorCode::WrongMembershipOwner);
+        m.active = false;
+
+        emit!(MembershipDeactivated { member, actor: member });
+        Ok(())
+    }
+
+    /// First step of a governor handover: the governor names its successor, who then
+    /// signs `accept_governor`. Nominating again replaces the pending key; nominating
+    /// `Pubkey::default()` cancels.
+    pub fn nominate_governor(ctx: Context<NominateGovernor>, new_governor: Pubkey) -> Result<()> {
+        let cfg = &ctx.accounts.config;
+        require_keys_eq!(ctx.accounts.signer.key(), cfg.governor, ErrorCode::Unauthorized);
+        let transfer = &mut ctx.accounts.transfer;
+        transfer.bump = ctx.bumps.transfer;
+        transfer.pending = new_governor;
+
+        emit!(GovernorNominated { current: cfg.governor, pending: new_governor });
+        Ok(())
+    }
+
+    /// Second step: the nominated key becomes governor and the transfer account is closed
+    /// to it.
+    pub fn accept_governor(ctx: Context<AcceptGovernor>) -> Result<()> {
+        let cfg = &mut ctx.accounts.config;
+        let previous = cfg.governor;
+        cfg.governor = ctx.accounts.pending_governor.key();
+
+        emit!(GovernorAccepted { previous, governor: cfg.governor });
+        Ok(())
+    }
+
+    /// Closes a cycle's state to the governor once nothing more can be claimed from it:
+    /// it is `CLAIM_WINDOW_CYCLES` old, or every one of the leaves it was published with
+    /// is claimed. Cycles up to this one can no longer be published, or their leaves
+    /// could be claimed twice.
+    pub fn close_cycle(ctx: Context<CloseCycle>, cycle_index: u64) -> Result<()> {
+        let cfg = &mut ctx.accounts.config;
+        require_keys_eq!(ctx.accounts.signer.key(), cfg.governor, ErrorCode::Unauthorized);
+        let state = &ctx.accounts.cycle_state;
+        require!(state.cycle_index == cycle_index, ErrorCode::InvalidCycle);
+
+        let now = Clock::get()?.unix_timestamp;
+        let current_cycle = (now.saturating_sub(cfg.start_ts).max(0) as u64) / cfg.cycle_secs.max(1);
+        let expired = cycle_index.saturating_add(CLAIM_WINDOW_CYCLES) <= current_cycle;
+        let fully_claimed =
+            state.leaf_count > 0 && (0..state.leaf_count).all(|i| leaf::is_claimed(&state.claims_bitmap, i));
+        require!(expired || fully_claimed, ErrorCode::CycleStillClaimable);
+        cfg.first_open_cycle = cfg.first_open_cycle.max(cycle_index.saturating_add(1));
+
+        emit!(CycleClosed {
+            cycle_index,
+            lamports: state.to_account_info().lamports(),
+            fully_claimed,
+        });
+        Ok(())
+    }
+
+    /// Grows the config and any seka accounts passed as remaining accounts to their
+    /// current size and brings them up to their current layout version; the governor
+    /// pays the rent.
+    ///
+    /// The config is taken unchecked because an older, shorter layout cannot be
+    /// deserialized until it has grown; the governor check runs on the migrated copy.
+    pub fn migrate_accounts<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateAccounts<'info>>,
+    ) -> Result<()> {
+        let payer = ctx.accounts.signer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        let config_info = ctx.accounts.config.to_account_info();
+        require_keys_eq!(*config_info.owner, crate::ID, ErrorCode::InvalidAccount);
+        let config = migrate_account::<GlobalConfig>(&config_info, GlobalConfig::SPACE, &payer, &system_program)?;
+        require_keys_eq!(ctx.accounts.signer.key(), config.governor, ErrorCode::Unauthorized);
+
+        for info in ctx.remaining_accounts {
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidAccount);
+            let (discriminator, bitmap_len) = {
+                let data = info.try_borrow_data()?;
+                let discriminator: [u8; 8] = data
+                    .get(..8)
+                    .and_then(|d| d.try_into().ok())
+                    .ok_or(ErrorCode::InvalidAccount)?;
+                let bitmap_len = data
+                    .get(CycleState::BITMAP_LEN_OFFSET..CycleState::BITMAP_LEN_OFFSET + 4)
+                    .and_then(|d| d.try_into().ok())
+                    .map(u32::from_le_bytes);
+                (discriminator, bitmap_len)
+            };
+
+            if discriminator == GlobalConfig::DISCRIMINATOR {
+                migrate_account::<GlobalConfig>(info, GlobalConfig::SPACE, &payer, &system_program)?;
+            } else if discriminator == CycleState::DISCRIMINATOR {
+                let space = CycleState::space_for(bitmap_len.ok_or(ErrorCode::InvalidAccount)?);
+                migrate_account::<CycleState>(info, space, &payer, &system_program)?;
+            } else if discriminator == PeerLedger::DISCRIMINATOR {
+                migrate_account::<PeerLedger>(info, PeerLedger::SPACE, &payer, &system_program)?;
+            } else if discriminator == Membership::DISCRIMINATOR {
+                migrate_account::<Membership>(info, Membership::SPACE, &payer, &system_program)?;
+            } else if discriminator == GovernorTransfer::DISCRIMINATOR {
+                migrate_account::<GovernorTransfer>(info, GovernorTransfer::SPACE, &payer, &system_program)?;
+            } else {
+                return err!(ErrorCode::InvalidAccount);
+            }
+        }
         Ok(())
     }
 }
@@ -243,7 +426,7 @@ pub struct Initialize<'info> {
         init,
         payer = payer,
         space = GlobalConfig::SPACE,
-        seeds = [b"config"],
+        seeds = [CONFIG_SEED],
         bump,
     )]
     pub config: Account<'info, GlobalConfig>,
@@ -259,12 +442,12 @@ pub struct Initialize<'info> {
 
     /// PDA used as the mint authority
     /// CHECK: PDA signer via seeds
-    #[account(seeds = [b"mint_authority"], bump)]
+    #[account(seeds = [MINT_AUTHORITY_SEED], bump)]
     pub mint_authority_pda: UncheckedAccount<'info>,
 
     /// PDA that owns the treasury ATA
     /// CHECK: PDA only
-    #[account(seeds = [b"treasury"], bump)]
+    #[account(seeds = [TREASURY_SEED], bump)]
     pub treasury_pda: UncheckedAccount<'info>,
 
     #[account(
@@ -275,10 +458,18 @@ pub struct Initialize<'info> {
     )]
     pub treasury_ata: Account<'info, TokenAccount>,
 
-    /// Recipient ATA for initial airdrop; must be associated to the provided recipient
-    #[account(mut)]
+    /// Recipient ATA for initial airdrop; created here, as the mint is new
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = airdrop_recipient_wallet,
+    )]
     pub airdrop_recipient_ata: Account<'info, TokenAccount>,
 
+    /// CHECK: only the airdrop ATA's authority; must match the `airdrop_recipient` argument
+    pub airdrop_recipient_wallet: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -295,42 +486,66 @@ pub struct UpdateParams {
     pub conversion_ratio: Option<u32>,
     pub join_cost_tokens: Option<u64>, // base units
     pub treasury_owner: Option<Pubkey>,
+    pub issuer: Option<Pubkey>,
 }
 
 #[derive(Accounts)]
 pub struct UpdateConfig<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
     pub config: Account<'info, GlobalConfig>,
     pub signer: Signer<'info>,
 }
 
 #[derive(Accounts)]
+#[instruction(cycle_index: u64, merkle_root: [u8; 32], total_points_declared: u32, claims_bitmap_len: u32, leaf_count: u32, leaf_format: u8)]
 pub struct SetCycleRoot<'info> {
+    #[account(mut)]
     pub signer: Signer<'info>,
     pub system_program: Program<'info, System>,
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
     pub config: Account<'info, GlobalConfig>,
     #[account(
         init,
         payer = signer,
-        space = CycleState::space_for(0), // will reallocate below using vec of desired len
-        seeds = [b"cycle", cycle_index_le(&cycle_index)],
+        space = CycleState::space_for(claims_bitmap_len),
+        seeds = [CYCLE_SEED, cycle_index_le(&cycle_index).as_ref()],
         bump,
     )]
     pub cycle_state: Account<'info, CycleState>,
 }
 
 #[derive(Accounts)]
+#[instruction(owner: Pubkey)]
 pub struct ClaimKarma<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
     pub config: Account<'info, GlobalConfig>,
-    #[account(mut, seeds = [b"cycle", cycle_index_le(&cycle_state.cycle_index)], bump = cycle_state.bump)]
+    #[account(mut, seeds = [CYCLE_SEED, cycle_index_le(&cycle_state.cycle_index).as_ref()], bump = cycle_state.bump)]
     pub cycle_state: Account<'info, CycleState>,
     #[account(
         init_if_needed,
         payer = payer,
         space = PeerLedger::SPACE,
-        seeds = [b"peer", owner.as_ref()],
+        seeds = [PEER_SEED, owner.as_ref()],
+        bump,
+    )]
+    pub ledger: Account<'info, PeerLedger>,
+    /// Payer for rent if ledger is created
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct CreditPoints<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+    pub issuer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PeerLedger::SPACE,
+        seeds = [PEER_SEED, owner.as_ref()],
         bump,
     )]
     pub ledger: Account<'info, PeerLedger>,
@@ -341,15 +556,31 @@ pub struct ClaimKarma<'info> {
 }
 
 #[derive(Accounts)]
+pub struct IssueTokens<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+    pub issuer: Signer<'info>,
+    #[account(mut, address = config.mint)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: PDA signer for mint
+    #[account(seeds = [MINT_AUTHORITY_SEED], bump)]
+    pub mint_authority_pda: UncheckedAccount<'info>,
+    #[account(mut, constraint = recipient_ata.mint == config.mint)]
+    pub recipient_ata: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
 pub struct ConvertPointsToTokens<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
     pub config: Account<'info, GlobalConfig>,
-    #[account(mut, seeds = [b"peer", owner.as_ref()], bump = ledger.bump)]
+    #[account(mut, seeds = [PEER_SEED, owner.as_ref()], bump = ledger.bump)]
     pub ledger: Account<'info, PeerLedger>,
     #[account(mut)]
     pub mint: Account<'info, Mint>,
     /// CHECK: PDA signer for mint
-    #[account(seeds = [b"mint_authority"], bump)]
+    #[account(seeds = [MINT_AUTHORITY_SEED], bump)]
     pub mint_authority_pda: UncheckedAccount<'info>,
     #[account(mut)]
     pub recipient_ata: Account<'info, TokenAccount>,
@@ -357,11 +588,12 @@ pub struct ConvertPointsToTokens<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(member: Pubkey)]
 pub struct JoinNetwork<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
     pub config: Account<'info, GlobalConfig>,
     /// CHECK: treasury owner PDA
-    #[account(seeds = [b"treasury"], bump)]
+    #[account(seeds = [TREASURY_SEED], bump)]
     pub treasury_pda: UncheckedAccount<'info>,
     #[account(mut)]
     pub treasury_ata: Account<'info, TokenAccount>,
@@ -373,7 +605,7 @@ pub struct JoinNetwork<'info> {
         init_if_needed,
         payer = payer,
         space = Membership::SPACE,
-        seeds = [b"member", member.as_ref()],
+        seeds = [MEMBER_SEED, member.as_ref()],
         bump,
     )]
     pub membership: Account<'info, Membership>,
@@ -382,14 +614,82 @@ pub struct JoinNetwork<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(member: Pubkey)]
 pub struct DeactivateMembership<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
     pub config: Account<'info, GlobalConfig>,
     pub signer: Signer<'info>,
-    #[account(mut, seeds = [b"member", member.as_ref()], bump = membership.bump)]
+    #[account(mut, seeds = [MEMBER_SEED, member.as_ref()], bump = membership.bump)]
+    pub membership: Account<'info, Membership>,
+}
+
+#[derive(Accounts)]
+pub struct DeactivateSelf<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [MEMBER_SEED, owner.key().as_ref()], bump = membership.bump)]
     pub membership: Account<'info, Membership>,
 }
 
+#[derive(Accounts)]
+pub struct NominateGovernor<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+    /// Governor; pays for the transfer account on first nomination
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = GovernorTransfer::SPACE,
+        seeds = [GOVERNOR_TRANSFER_SEED],
+        bump,
+    )]
+    pub transfer: Account<'info, GovernorTransfer>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptGovernor<'info> {
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+    #[account(mut, address = transfer.pending @ ErrorCode::Unauthorized)]
+    pub pending_governor: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [GOVERNOR_TRANSFER_SEED],
+        bump = transfer.bump,
+        close = pending_governor,
+    )]
+    pub transfer: Account<'info, GovernorTransfer>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCycle<'info> {
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+    /// Governor; receives the cycle's rent
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [CYCLE_SEED, cycle_index_le(&cycle_state.cycle_index).as_ref()],
+        bump = cycle_state.bump,
+        close = signer,
+    )]
+    pub cycle_state: Account<'info, CycleState>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccounts<'info> {
+    /// CHECK: deserialized after growing; see `migrate_accounts`
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// Governor; pays for any growth
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 pub struct GlobalConfig {
     pub bump: u8,
@@ -405,9 +705,15 @@ pub struct GlobalConfig {
     pub start_ts: i64,
     pub airdrop_done: bool,
     pub decimals: u8,
+    pub issuer: Pubkey,
+    pub version: u8,
+    /// Lowest cycle `set_cycle_root` still takes, one past the last closed cycle; since
+    /// version 1, so cycles closed before the upgrade are not covered
+    pub first_open_cycle: u64,
+    pub _reserved: [u8; 7],
 }
 impl GlobalConfig {
-    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 32 + 32 + 32 + 8 + 4 + 4 + 4 + 8 + 8 + 1 + 1 + 16; // pad
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 32 + 32 + 32 + 8 + 4 + 4 + 4 + 8 + 8 + 1 + 1 + 32 + 1 + 8 + 7;
 }
 
 #[account]
@@ -417,10 +723,20 @@ pub struct CycleState {
     pub merkle_root: [u8; 32],
     pub total_points_declared: u32,
     pub claims_bitmap: Vec<u8>,
+    pub version: u8,
+    /// `LeafFormat` `merkle_root` was built in; 0 (Solana) on accounts from before version 1
+    pub leaf_format: u8,
+    /// Leaves `merkle_root` commits to; 0 on accounts from before version 2, which then
+    /// close only once expired
+    pub leaf_count: u32,
+    pub _reserved: [u8; 10],
 }
 impl CycleState {
-    pub fn space_for(bitmap_len: u32) -> usize {
-        8 /*disc*/ + 1 + 8 + 32 + 4 + 4 /*vec prefix*/ + bitmap_len as usize + 16
+    /// Offset of the bitmap's length prefix, discriminator included.
+    const BITMAP_LEN_OFFSET: usize = 8 + 1 + 8 + 32 + 4;
+
+    pub const fn space_for(bitmap_len: u32) -> usize {
+        8 /*disc*/ + 1 + 8 + 32 + 4 + 4 /*vec prefix*/ + bitmap_len as usize + 1 + 1 + 4 + 10
     }
 }
 
@@ -430,9 +746,11 @@ pub struct PeerLedger {
     pub owner: Pubkey,
     pub points: i64,
     pub last_cycle_claimed: u64,
+    pub version: u8,
+    pub _reserved: [u8; 15],
 }
 impl PeerLedger {
-    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 8 + 8 + 16;
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 8 + 8 + 1 + 15;
 }
 
 #[account]
@@ -441,112 +759,242 @@ pub struct Membership {
     pub owner: Pubkey,
     pub joined_at: i64,
     pub active: bool,
+    pub version: u8,
+    pub _reserved: [u8; 15],
 }
 impl Membership {
-    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 8 + 1 + 16;
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 8 + 1 + 1 + 15;
 }
 
-#[event]
-pub struct Initialized {
-    pub governor: Pubkey,
-    pub mint: Pubkey,
-    pub treasury: Pubkey,
+/// A governor handover in progress; exists from `nominate_governor` until
+/// `accept_governor`. Kept out of `GlobalConfig` so its layout does not change.
+#[account]
+pub struct GovernorTransfer {
+    pub bump: u8,
+    pub pending: Pubkey,
+    pub version: u8,
+    pub _reserved: [u8; 15],
 }
-
-#[event]
-pub struct ConfigUpdated {
-    pub cycle_secs: u64,
-    pub max_points_per_cycle: u32,
-    pub per_peer_cycle_cap: i32,
-    pub conversion_ratio: u32,
-    pub join_cost_tokens: u64,
+impl GovernorTransfer {
+    pub const SPACE: usize = 8 /*disc*/ + 1 + 32 + 1 + 15;
 }
 
-#[event]
-pub struct CycleRootSet {
-    pub cycle_index: u64,
-    pub merkle_root: [u8; 32],
-    pub total_points_declared: u32,
+// Accounts were created with 16 bytes of zero padding, now `version` and `_reserved`,
+// so those from before versioning read as version 0 without growing.
+sentinelkarma_core::versioned!(GlobalConfig, 1, GlobalConfig::SPACE);
+sentinelkarma_core::versioned!(CycleState, 2, CycleState::space_for(0));
+sentinelkarma_core::versioned!(PeerLedger, 0, PeerLedger::SPACE);
+sentinelkarma_core::versioned!(Membership, 0, Membership::SPACE);
+sentinelkarma_core::versioned!(GovernorTransfer, 0, GovernorTransfer::SPACE);
+
+sentinelkarma_core::seka_events!(anchor);
+
+sentinelkarma_core::seka_error!(anchor ErrorCode);
+
+/// Grows a seka account to at least `len` bytes and rewrites it at its current layout
+/// version, emitting `AccountMigrated` if either changed anything.
+fn migrate_account<'info, T>(
+    info: &AccountInfo<'info>,
+    len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<T>
+where
+    T: AccountSerialize + AccountDeserialize + Versioned,
+{
+    let grew = grow_account(info, len, payer, system_program)?;
+    let mut account = T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = account.version();
+    account.migrate();
+    account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    if grew || from_version < T::VERSION {
+        emit!(AccountMigrated { account: info.key(), from_version, to_version: T::VERSION });
+    }
+    Ok(account)
 }
 
-#[event]
-pub struct KarmaClaimed {
-    pub owner: Pubkey,
-    pub cycle_index: u64,
-    pub delta_points: i32,
-    pub new_points: i64,
+/// Reallocates an account to at least `len` bytes, topping up rent from `payer`; new
+/// bytes are zeroed. Returns whether it grew.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    if info.data_len() >= len {
+        return Ok(false);
+    }
+    let shortfall = Rent::get()?.minimum_balance(len).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer { from: payer.clone(), to: info.clone() },
+        );
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+    info.realloc(len, true)?;
+    Ok(true)
 }
 
-#[event]
-pub struct PointsConverted {
-    pub owner: Pubkey,
-    pub tokens_minted: u64,
-    pub points_spent: u64,
+fn cycle_index_le(idx: &u64) -> [u8; 8] { idx.to_le_bytes() }
+
+fn pow10(p: u32) -> u64 { 10u64.pow(p) }
+
+fn keccak_hash(data: &[u8]) -> [u8; 32] { hashv(&[data]).0 }
+
+#[cfg(target_os = "solana")]
+fn compute_merkle_root(format: LeafFormat, leaf: [u8; 32], proof: &[[u8; 32]], leaf_index: u32) -> [u8; 32] {
+    leaf::compute_root_keccak(format, leaf, proof, leaf_index)
 }
 
-#[event]
-pub struct Joined {
-    pub member: Pubkey,
+#[cfg(not(target_os = "solana"))]
+fn compute_merkle_root(format: LeafFormat, leaf: [u8; 32], proof: &[[u8; 32]], leaf_index: u32) -> [u8; 32] {
+    format.compute_root(leaf, proof, leaf_index, |left, right| hashv(&[left, right]).0)
 }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Unauthorized")] Unauthorized,
-    #[msg("Cycle already initialized")] CycleAlreadyInitialized,
-    #[msg("Invalid Merkle proof")] InvalidMerkleProof,
-    #[msg("Claim already processed")] ClaimAlreadyProcessed,
-    #[msg("Delta exceeds per-peer cap")] DeltaExceedsPerPeerCap,
-    #[msg("Total points exceed cycle cap")] TotalPointsExceedsCycleCap,
-    #[msg("Insufficient points to convert")] InsufficientPointsToConvert,
-    #[msg("Insufficient token balance")] InsufficientTokenBalance,
-    #[msg("Math overflow")] MathOverflow,
-    #[msg("Invalid cycle")] InvalidCycle,
-    #[msg("Wrong ledger owner")] WrongLedgerOwner,
-    #[msg("Wrong membership owner")] WrongMembershipOwner,
+fn set_claimed(bitmap: &mut [u8], index: u32) -> Result<()> {
+    require!(leaf::set_claimed(bitmap, index), ErrorCode::InvalidMerkleProof);
+    Ok(())
 }
 
-fn cycle_index_le(idx: &u64) -> [u8; 8] { idx.to_le_bytes() }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use seka_merkle::{Leaf, MerkleTree};
+    use sentinelkarma_core::leaf::{is_claimed, serialize_leaf};
+    use std::collections::BTreeSet;
 
-fn pow10(p: u32) -> u64 { 10u64.pow(p) }
+    fn pubkey() -> impl Strategy<Value = Pubkey> {
+        any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+    }
 
-fn serialize_leaf(owner: &Pubkey, cycle_index: u64, delta_points: i32, leaf_index: u32) -> Vec<u8> {
-    let mut v = Vec::with_capacity(32 + 8 + 4 + 4);
-    v.extend_from_slice(owner.as_ref());
-    v.extend_from_slice(&cycle_index.to_le_bytes());
-    v.extend_from_slice(&delta_points.to_le_bytes());
-    v.extend_from_slice(&leaf_index.to_le_bytes());
-    v
-}
+    proptest! {
+        /// The on-chain leaf bytes are exactly the ones the off-chain builder hashes.
+        #[test]
+        fn serialize_leaf_matches_merkle_crate(
+            owner in pubkey(),
+            cycle_index in any::<u64>(),
+            delta_points in any::<i32>(),
+            leaf_index in any::<u32>(),
+        ) {
+            let bytes = serialize_leaf(&owner.to_bytes(), cycle_index, delta_points, leaf_index);
+            prop_assert_eq!(
+                &bytes[..],
+                &seka_merkle::serialize_leaf(&owner, cycle_index, delta_points, leaf_index)[..]
+            );
+            prop_assert_eq!(
+                keccak_hash(&bytes),
+                seka_merkle::leaf_hash(&owner, cycle_index, delta_points, leaf_index)
+            );
+        }
 
-fn keccak_hash(data: &[u8]) -> [u8; 32] { hashv(&[data]).0 }
+        /// Any leaf, path and index fold to the same root on both sides.
+        #[test]
+        fn compute_merkle_root_matches_merkle_crate(
+            leaf in any::<[u8; 32]>(),
+            proof in prop::collection::vec(any::<[u8; 32]>(), 0..40),
+            leaf_index in any::<u32>(),
+        ) {
+            prop_assert_eq!(
+                compute_merkle_root(LeafFormat::Solana, leaf, &proof, leaf_index),
+                seka_merkle::compute_root(leaf, &proof, leaf_index)
+            );
+        }
 
-fn compute_merkle_root(mut leaf: [u8; 32], proof: &Vec<[u8; 32]>, leaf_index: u32) -> [u8; 32] {
-    let mut idx = leaf_index;
-    let mut hash = leaf;
-    for sibling in proof.iter() {
-        let (left, right) = if idx & 1 == 1 {
-            (sibling, &hash)
-        } else {
-            (&hash, sibling)
-        };
-        let combined = [left.as_slice(), right.as_slice()].concat();
-        hash = keccak_hash(&combined);
-        idx >>= 1;
-    }
-    hash
-}
+        /// Every proof of an off-chain tree verifies on-chain, and only for its own leaf.
+        #[test]
+        fn tree_proofs_verify_on_chain(
+            deltas in prop::collection::vec(any::<i32>(), 1..80),
+            cycle_index in any::<u64>(),
+        ) {
+            let leaves = deltas
+                .iter()
+                .enumerate()
+                .map(|(i, &delta_points)| {
+                    let mut owner = [0u8; 32];
+                    owner[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                    Leaf { owner: Pubkey::new_from_array(owner), delta_points }
+                })
+                .collect();
+            let tree = MerkleTree::new(cycle_index, leaves).unwrap();
+            for proof in tree.proofs() {
+                let leaf = keccak_hash(&serialize_leaf(
+                    &proof.owner.to_bytes(),
+                    cycle_index,
+                    proof.delta_points,
+                    proof.leaf_index,
+                ));
+                prop_assert_eq!(
+                    compute_merkle_root(LeafFormat::Solana, leaf, &proof.proof, proof.leaf_index),
+                    tree.root()
+                );
+                // A self-paired node makes the path alone ambiguous; the leaf index is
+                // committed in the leaf hash, so a claim at another index still fails
+                let moved = proof.leaf_index ^ 1;
+                let moved_leaf = keccak_hash(&serialize_leaf(
+                    &proof.owner.to_bytes(),
+                    cycle_index,
+                    proof.delta_points,
+                    moved,
+                ));
+                prop_assert_ne!(
+                    compute_merkle_root(LeafFormat::Solana, moved_leaf, &proof.proof, moved),
+                    tree.root()
+                );
+            }
+        }
 
-fn is_claimed(bitmap: &Vec<u8>, index: u32) -> bool {
-    let byte_index = (index / 8) as usize;
-    let bit_index = (index % 8) as u8;
-    if byte_index >= bitmap.len() { return false; }
-    (bitmap[byte_index] & (1u8 << bit_index)) != 0
-}
+        /// Every proof of an off-chain EVM-format tree verifies on-chain in that format.
+        #[test]
+        fn evm_tree_proofs_verify_on_chain(
+            deltas in prop::collection::vec(any::<i32>(), 1..80),
+            cycle_index in any::<u64>(),
+        ) {
+            let leaves = deltas
+                .iter()
+                .enumerate()
+                .map(|(i, &delta_points)| {
+                    let mut owner = [0u8; 32];
+                    owner[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                    Leaf { owner: Pubkey::new_from_array(owner), delta_points }
+                })
+                .collect();
+            let tree = MerkleTree::with_format(LeafFormat::Evm, cycle_index, leaves).unwrap();
+            for proof in tree.proofs() {
+                let bytes = LeafFormat::Evm.serialize_leaf(
+                    &proof.owner.to_bytes(),
+                    cycle_index,
+                    proof.delta_points,
+                    proof.leaf_index,
+                );
+                let leaf = keccak_hash(&bytes);
+                prop_assert_eq!(
+                    compute_merkle_root(LeafFormat::Evm, leaf, &proof.proof, proof.leaf_index),
+                    tree.root()
+                );
+            }
+        }
 
-fn set_claimed(bitmap: &mut Vec<u8>, index: u32) -> Result<()> {
-    let byte_index = (index / 8) as usize;
-    let bit_index = (index % 8) as u8;
-    require!(byte_index < bitmap.len(), ErrorCode::InvalidMerkleProof);
-    bitmap[byte_index] |= 1u8 << bit_index;
-    Ok(())
+        /// The bitmap agrees with a set of claimed indices, and never panics out of range.
+        #[test]
+        fn claims_bitmap_matches_set_model(
+            len in 0..64usize,
+            indices in prop::collection::vec(any::<u32>(), 0..200),
+            probes in prop::collection::vec(any::<u32>(), 0..50),
+        ) {
+            let mut bitmap = vec![0u8; len];
+            let mut claimed = BTreeSet::new();
+            for index in indices.iter().map(|i| i % (len as u32 * 8 + 16)).chain(indices.iter().copied()) {
+                let in_range = (index as usize) < len * 8;
+                prop_assert_eq!(set_claimed(&mut bitmap, index).is_ok(), in_range);
+                if in_range {
+                    claimed.insert(index);
+                }
+            }
+            for index in (0..len as u32 * 8 + 16).chain(probes) {
+                prop_assert_eq!(is_claimed(&bitmap, index), claimed.contains(&index));
+            }
+        }
+    }
 }
@@ -1,24 +1,83 @@
 use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, ZeroCopy};
+use anchor_lang::system_program;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
 use anchor_lang::solana_program::program_option::COption;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, MintTo, SetAuthority};
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::token_interface::{
+    self, Burn, CloseAccount, FreezeAccount, HarvestWithheldTokensToMint, Mint, MintTo,
+    SetAuthority, ThawAccount, TokenAccount, TokenInterface, TransferChecked,
+    TransferFeeSetTransferFee, WithdrawWithheldTokensFromMint,
+};
+use anchor_spl::token_2022::{
+    self,
+    spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+            StateWithExtensions,
+        },
+    },
+};
 use anchor_spl::associated_token::AssociatedToken;
 
 // Bump seed constants
-const STATE_SEED: &[u8] = b"state";
-const TREASURY_VAULT_SEED: &[u8] = b"treasury";
-const PEER_SEED: &[u8] = b"peer";
-const POST_SEED: &[u8] = b"post";
-const LIKE_SEED: &[u8] = b"like";
+pub use sentinelkarma_core::sentinel::seeds::*;
 
 // Config constants
-const SENTINEL_DECIMALS: u8 = 9; // standard SPL decimals
+pub use sentinelkarma_core::sentinel::TAG_LEN;
+use sentinelkarma_core::sentinel::{
+    CYCLE_REWARD_TOTAL, CYCLE_SECONDS, DEFAULT_LIKE_REWARD_WINDOW, DEFAULT_MAX_LIKES_PER_CYCLE,
+    DEFAULT_MAX_LIKES_PER_PAIR, KARMA_HISTORY_LEN, MAX_CONTENT_ADDR_LEN, SENTINEL_DECIMALS,
+    TOP_POSTS_LEN,
+};
+use sentinelkarma_core::version::Versioned;
 const JOIN_COST: u64 = 1_000 * 10u64.pow(SENTINEL_DECIMALS as u32);
-const CYCLE_REWARD_TOTAL: u64 = 1_000 * 10u64.pow(SENTINEL_DECIMALS as u32);
+const DEFAULT_JOIN_COST_STEP_SIZE: u64 = 100; // peers per price step
+const MAX_COMMITTEE: usize = 7;
 const MAX_PEER_REWARD_PCT: u64 = 10; // 10%
-const CYCLE_SECONDS: i64 = 2 * 60 * 60; // 2 hours
 const INITIAL_MINT_SUPPLY: u64 = 100_000 * 10u64.pow(SENTINEL_DECIMALS as u32);
+const DEFAULT_JOIN_BURN_BPS: u16 = 0;
+const DEFAULT_LEAVE_REFUND_BPS: u16 = 0;
+const MAX_HASH_HISTORY: usize = 4;
+const ARWEAVE_TX_ID_LEN: usize = 32;
+// Metaplex Core wire format: instruction and enum variant indices
+const CORE_CREATE_V1: u8 = 0;
+const CORE_UPDATE_PLUGIN_V1: u8 = 6;
+const CORE_BURN_V1: u8 = 12;
+const CORE_KEY_ASSET_V1: u8 = 1;
+const CORE_PLUGIN_FREEZE_DELEGATE: u8 = 1;
+const CORE_PLUGIN_TRANSFER_DELEGATE: u8 = 3;
+const CORE_AUTHORITY_ADDRESS: u8 = 3;
+const DEFAULT_POST_BOND: u64 = 0;
+const DEFAULT_INVITES_PER_PEER: u32 = 3;
+const BPS_DENOMINATOR: u64 = 10_000;
+const REVEAL_DELAY_SLOTS: u64 = 2; // commit must land before the reveal is broadcast
+// Account layout versions; bump when a layout changes and extend `migrate_accounts`
+const STATE_VERSION: u8 = 6;
+const PEER_VERSION: u8 = 1;
+const POST_VERSION: u8 = 7;
+const MAX_ROYALTY_BPS: u16 = 1_000; // 10%
+const DEFAULT_MARKET_FEE_BPS: u16 = 0;
+const MIN_USERNAME_LEN: usize = 3;
+const MAX_USERNAME_LEN: usize = 32; // also the max seed length
+const DEFAULT_USERNAME_FEE: u64 = 0;
+const DEFAULT_COMMENT_LIKE_BPS: u16 = 2_500; // a comment like is worth a quarter of a post like
+const DEFAULT_REPORT_BOND: u64 = 0;
+const DEFAULT_REPORT_BOUNTY_BPS: u16 = 5_000; // reporter's share of an upheld report's slashed bond
+const DEFAULT_REPORT_BOUNTY: u64 = 0; // flat treasury bounty on top
+const ALLOWED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
 
-declare_id!("7e5HppSuDGkqSjgKNfC62saPoJR5LBkYMuQHkv59eDY7");
+sentinelkarma_core::sentinel_id!(declare_id);
 
 #[program]
 pub mod sentinel {
@@ -26,24 +85,58 @@ pub mod sentinel {
 
     pub fn initialize(
         ctx: Context<Initialize>,
+        genesis: Genesis,
     ) -> Result<()> {
         let state = &mut ctx.accounts.state;
-        state.authority = ctx.accounts.authority.key();
-        state.sentinel_mint = ctx.accounts.sentinel_mint.key();
-        state.treasury_vault = ctx.accounts.treasury_vault.key();
-        state.cycle_start_ts = Clock::get()?.unix_timestamp;
-        state.cycle_index = 0;
+        state.init(
+            ctx.accounts.authority.key(),
+            ctx.bumps.state,
+            ctx.accounts.sentinel_mint.key(),
+            ctx.accounts.treasury_vault.key(),
+        )?;
+
+        check_mint_extensions(&ctx.accounts.sentinel_mint.to_account_info(), &state.key())?;
+
+        let genesis_ata = match genesis {
+            // Lock the initial supply in the vesting PDA, released to the authority over time
+            Genesis::Vested { cliff_seconds, duration_seconds } => {
+                require!(
+                    duration_seconds > 0 && (0..=duration_seconds).contains(&cliff_seconds),
+                    SentinelError::InvalidInput
+                );
+                let (Some(vesting), Some(vesting_ata), Some(bump)) = (
+                    ctx.accounts.vesting.as_mut(),
+                    ctx.accounts.vesting_sentinel_ata.as_ref(),
+                    ctx.bumps.vesting,
+                ) else {
+                    return err!(SentinelError::MissingAccount);
+                };
+                let now = Clock::get()?.unix_timestamp;
+                vesting.beneficiary = ctx.accounts.authority.key();
+                vesting.total = INITIAL_MINT_SUPPLY;
+                vesting.claimed = 0;
+                vesting.start_ts = now;
+                vesting.cliff_ts = now.checked_add(cliff_seconds).ok_or(SentinelError::Overflow)?;
+                vesting.end_ts = now.checked_add(duration_seconds).ok_or(SentinelError::Overflow)?;
+                vesting.bump = bump;
+                vesting_ata.to_account_info()
+            }
+            // Park it in the treasury; every payout goes through distribute_initial_supply
+            Genesis::Treasury => {
+                ctx.accounts.state.genesis_remaining = INITIAL_MINT_SUPPLY;
+                ctx.accounts.treasury_sentinel_ata.to_account_info()
+            }
+        };
 
-        // Mint initial supply to authority's ATA
         let cpi_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             MintTo {
                 mint: ctx.accounts.sentinel_mint.to_account_info(),
-                to: ctx.accounts.authority_sentinel_ata.to_account_info(),
+                to: genesis_ata,
                 authority: ctx.accounts.authority.to_account_info(),
             },
         );
-        token::mint_to(cpi_ctx, INITIAL_MINT_SUPPLY)?;
+        token_interface::mint_to(cpi_ctx, INITIAL_MINT_SUPPLY)?;
 
         // hand off mint authority to state PDA for future rewards
         let cpi_ctx_set = CpiContext::new(
@@ -53,512 +146,6501 @@ pub mod sentinel {
                 current_authority: ctx.accounts.authority.to_account_info(),
             },
         );
-        token::set_authority(
+        token_interface::set_authority(
             cpi_ctx_set,
-            anchor_spl::token::spl_token::instruction::AuthorityType::MintTokens,
+            token_interface::spl_token_2022::instruction::AuthorityType::MintTokens,
             Some(ctx.accounts.state.key()),
         )?;
 
         Ok(())
     }
 
-    pub fn join_network(ctx: Context<JoinNetwork>) -> Result<()> {
-        // Transfer JOIN_COST from user to treasury vault
-        let cpi_ctx = CpiContext::new(
+    /// Initializes sentinel on top of the existing SEKA mint instead of its own token.
+    ///
+    /// Join fees are paid in SEKA and rewards are minted through seka's `issue_tokens`
+    /// issuer interface, so seka must list the state PDA as its issuer. There is no
+    /// genesis allocation; SEKA's supply is seka's concern.
+    pub fn initialize_shared_mint(ctx: Context<InitializeSharedMint>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.init(
+            ctx.accounts.authority.key(),
+            ctx.bumps.state,
+            ctx.accounts.sentinel_mint.key(),
+            ctx.accounts.treasury_vault.key(),
+        )?;
+        state.shared_mint = true;
+        state.seka_program = ctx.accounts.seka_program.key();
+
+        check_mint_extensions(&ctx.accounts.sentinel_mint.to_account_info(), &state.key())?;
+
+        Ok(())
+    }
+
+    pub fn claim_vested_supply(ctx: Context<ClaimVestedSupply>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vesting = &ctx.accounts.vesting;
+        let amount = vesting
+            .vested_amount(now)
+            .checked_sub(vesting.claimed)
+            .ok_or(SentinelError::Overflow)?;
+        require!(amount > 0, SentinelError::NothingToClaim);
+
+        let signer_seeds: &[&[u8]] = &[VESTING_SEED, &[vesting.bump]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_sentinel_ata.to_account_info(),
-                to: ctx.accounts.treasury_sentinel_ata.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.vesting_sentinel_ata.to_account_info(),
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                to: ctx.accounts.beneficiary_sentinel_ata.to_account_info(),
+                authority: ctx.accounts.vesting.to_account_info(),
             },
+            signer,
         );
-        token::transfer(cpi_ctx, JOIN_COST)?;
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.sentinel_mint.decimals)?;
 
-        // Activate peer state
-        let peer = &mut ctx.accounts.peer;
-        peer.user = ctx.accounts.user.key();
-        peer.active = true;
-        peer.karma = 0;
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.claimed = vesting
+            .claimed
+            .checked_add(amount)
+            .ok_or(SentinelError::Overflow)?;
+
+        emit_cpi!(VestedSupplyClaimed {
+            beneficiary: vesting.beneficiary,
+            amount,
+            total_claimed: vesting.claimed,
+        });
 
         Ok(())
     }
 
-    pub fn mint_nft(ctx: Context<MintNft>, hash: [u8; 32], db_addr: Pubkey) -> Result<()> {
-        require!(ctx.accounts.peer.active, SentinelError::NotPeer);
+    pub fn distribute_initial_supply(
+        ctx: Context<DistributeInitialSupply>,
+        amount: u64,
+        reason_code: u16,
+    ) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(amount > 0, SentinelError::InvalidAmount);
+        // Only the genesis allocation can leave this way, never join fees or slashed bonds
+        let remaining = ctx.accounts.state
+            .genesis_remaining
+            .checked_sub(amount)
+            .ok_or(SentinelError::GenesisExhausted)?;
 
-        // Mint the NFT (1 token of a new mint with 0 decimals) to user
-        let cpi_ctx = CpiContext::new(
+        let signer_seeds: &[&[u8]] = &[TREASURY_VAULT_SEED, &[ctx.bumps.treasury_vault]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            MintTo {
-                mint: ctx.accounts.nft_mint.to_account_info(),
-                to: ctx.accounts.user_nft_ata.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                to: ctx.accounts.recipient.to_account_info(),
+                authority: ctx.accounts.treasury_vault.to_account_info(),
             },
+            signer,
         );
-        token::mint_to(cpi_ctx, 1)?;
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.sentinel_mint.decimals)?;
 
-        // Create post metadata
-        let post = &mut ctx.accounts.post;
-        post.owner = ctx.accounts.user.key();
-        post.nft_mint = ctx.accounts.nft_mint.key();
-        post.hash = hash;
-        post.db_addr = db_addr;
-        post.likes = 0;
-        post.cycle_index = ctx.accounts.state.cycle_index;
+        ctx.accounts.state.genesis_remaining = remaining;
+
+        emit_cpi!(GenesisDistributed {
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            reason_code,
+            remaining,
+        });
 
         Ok(())
     }
 
-    pub fn like_nft(ctx: Context<LikeNft>) -> Result<()> {
-        // prevent double-like via PDA uniqueness and ensure liker is an active peer
-        let post = &mut ctx.accounts.post;
-        let liked_peer = &mut ctx.accounts.liked_peer;
-        let liker_peer = &ctx.accounts.liker_peer;
-        
-        require!(liked_peer.active, SentinelError::NotPeer);
-        require!(liker_peer.active, SentinelError::NotPeer);
-        
-        // Prevent self-liking
-        require!(ctx.accounts.liker.key() != post.owner, SentinelError::CannotLikeSelf);
+    pub fn update_params(ctx: Context<UpdateParams>, params: ParamsUpdate) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        let state = &mut ctx.accounts.state;
+        if let Some(v) = params.max_likes_per_cycle { state.max_likes_per_cycle = v; }
+        if let Some(v) = params.max_likes_per_pair { state.max_likes_per_pair = v; }
+        if let Some(v) = params.join_burn_bps { state.join_burn_bps = v; }
+        if let Some(v) = params.leave_refund_bps { state.leave_refund_bps = v; }
+        if let Some(v) = params.moderator { state.moderator = v; }
+        if let Some(v) = params.post_bond { state.post_bond = v; }
+        if let Some(v) = params.invite_only { state.invite_only = v; }
+        if let Some(v) = params.invites_per_peer { state.invites_per_peer = v; }
+        if let Some(v) = params.join_price_lamports { state.join_price_lamports = v; }
+        if let Some(v) = params.join_cost_base { state.join_cost_base = v; }
+        if let Some(v) = params.join_cost_step { state.join_cost_step = v; }
+        if let Some(v) = params.top_post_bonus { state.top_post_bonus = v; }
+        if let Some(v) = params.like_reward_window { state.like_reward_window = v; }
+        if let Some(v) = params.seka_program { state.seka_program = v; }
+        if let Some(v) = params.seka_points_per_karma { state.seka_points_per_karma = v; }
+        if let Some(v) = params.cycle_reward_total { state.cycle_reward_total = v; }
+        if let Some(v) = params.username_fee { state.username_fee = v; }
+        if let Some(v) = params.require_storage_attestation { state.require_storage_attestation = v; }
+        if let Some(v) = params.market_fee_bps {
+            // Leaves room for the largest creator royalty
+            require!(v as u64 + MAX_ROYALTY_BPS as u64 <= BPS_DENOMINATOR, SentinelError::InvalidInput);
+            state.market_fee_bps = v;
+        }
+        if let Some(v) = params.report_bond { state.report_bond = v; }
+        if let Some(v) = params.report_bounty { state.report_bounty = v; }
+        if let Some(v) = params.core_program { state.core_program = v; }
+        if let Some(v) = params.crank_finalize { state.crank_finalize = v; }
+        if let Some(v) = params.report_bounty_bps {
+            require!(v as u64 <= BPS_DENOMINATOR, SentinelError::InvalidInput);
+            state.report_bounty_bps = v;
+        }
+        if let Some(v) = params.comment_like_bps {
+            require!(v as u64 <= BPS_DENOMINATOR, SentinelError::InvalidInput);
+            state.comment_like_bps = v;
+        }
+        if let Some(v) = params.cycle_seconds {
+            require!(v > 0, SentinelError::InvalidInput);
+            state.cycle_seconds = v;
+        }
+        if let Some(v) = params.join_cost_step_size {
+            require!(v > 0, SentinelError::InvalidInput);
+            state.join_cost_step_size = v;
+        }
+        // Burned fees never reach the treasury, so refunds must come out of the kept share
+        require!(
+            state.join_burn_bps as u64 + state.leave_refund_bps as u64 <= BPS_DENOMINATOR,
+            SentinelError::InvalidInput
+        );
+        Ok(())
+    }
 
-        post.likes = post
-            .likes
-            .checked_add(1)
-            .ok_or(SentinelError::Overflow)?;
-        liked_peer.karma = liked_peer
-            .karma
-            .checked_add(1)
-            .ok_or(SentinelError::Overflow)?;
+    /// Reallocates `State` and any sentinel accounts passed after the committee co-signers
+    /// to their current size and brings them up to their current layout version.
+    ///
+    /// The state is taken unchecked because an old, shorter layout cannot be deserialized
+    /// until it has grown; the admin check runs on the migrated copy before anything is
+    /// written back.
+    pub fn migrate_accounts<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateAccounts<'info>>,
+    ) -> Result<()> {
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
 
-        let like = &mut ctx.accounts.like;
-        like.liker = ctx.accounts.liker.key();
-        like.post = ctx.accounts.post.key();
+        let state_info = ctx.accounts.state.to_account_info();
+        require!(*state_info.owner == crate::ID, SentinelError::InvalidAccount);
+        let grew = grow_account(&state_info, State::SPACE, &payer, &system_program)?;
+        let mut state = State::try_deserialize(&mut &state_info.try_borrow_data()?[..])?;
+        let cosigners = require_admin(&state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+
+        let from_version = state.version;
+        state.migrate();
+        // The original layout had no bump, and everything state signs or derives needs it
+        state.bump = ctx.bumps.state;
+        state.try_serialize(&mut &mut state_info.try_borrow_mut_data()?[..])?;
+        if grew || from_version < STATE_VERSION {
+            emit_cpi!(AccountMigrated {
+                account: state_info.key(),
+                from_version,
+                to_version: STATE_VERSION,
+            });
+        }
+
+        for info in &ctx.remaining_accounts[cosigners..] {
+            require!(*info.owner == crate::ID, SentinelError::InvalidAccount);
+            let (from_version, to_version, grew) = migrate_any(info, &payer, &system_program)?;
+            if grew || from_version < to_version {
+                emit_cpi!(AccountMigrated {
+                    account: info.key(),
+                    from_version,
+                    to_version,
+                });
+            }
+        }
 
         Ok(())
     }
 
-    pub fn finalize_cycle<'info>(
-        ctx: Context<'_, '_, '_, 'info, FinalizeCycle<'info>>, 
-        peers: Vec<Pubkey>, 
-        karmas: Vec<u64>
+    pub fn set_committee(
+        ctx: Context<SetCommittee>,
+        members: Vec<Pubkey>,
+        threshold: u8,
     ) -> Result<()> {
-        let now = Clock::get()?.unix_timestamp;
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(members.len() <= MAX_COMMITTEE, SentinelError::InvalidInput);
+        require!(threshold as usize <= members.len(), SentinelError::InvalidInput);
+        for (i, m) in members.iter().enumerate() {
+            require!(!members[..i].contains(m), SentinelError::InvalidInput);
+        }
+
+        let state = &mut ctx.accounts.state;
+        state.committee = members;
+        state.threshold = threshold;
+
+        emit_cpi!(CommitteeUpdated {
+            members: state.committee.clone(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    pub fn nominate_authority(ctx: Context<NominateAuthority>, new_authority: Pubkey) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+
+        let state = &mut ctx.accounts.state;
+        state.pending_authority = new_authority;
+
+        emit_cpi!(AuthorityNominated {
+            current: state.authority,
+            pending: new_authority,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let previous = state.authority;
+        state.authority = ctx.accounts.pending_authority.key();
+        state.pending_authority = Pubkey::default();
+
+        emit_cpi!(AuthorityAccepted {
+            previous,
+            authority: state.authority,
+        });
+
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        ctx.accounts.state.paused = true;
+        emit_cpi!(PauseChanged { paused: true });
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        ctx.accounts.state.paused = false;
+        emit_cpi!(PauseChanged { paused: false });
+        Ok(())
+    }
+
+    pub fn treasury_withdraw(ctx: Context<TreasuryWithdraw>, amount: u64) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(amount > 0, SentinelError::InvalidAmount);
         require!(
-            now - ctx.accounts.state.cycle_start_ts >= CYCLE_SECONDS, 
-            SentinelError::CycleNotEnded
+            ctx.accounts.treasury_sentinel_ata.amount.saturating_sub(amount)
+                >= ctx.accounts.state.genesis_remaining,
+            SentinelError::GenesisReserved
         );
-        require!(peers.len() == karmas.len(), SentinelError::InvalidInput);
+
+        let signer_seeds: &[&[u8]] = &[TREASURY_VAULT_SEED, &[ctx.bumps.treasury_vault]];
+        let signer = &[signer_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.treasury_vault.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.sentinel_mint.decimals)?;
+
+        emit_cpi!(TreasuryWithdrawn {
+            authority: ctx.accounts.authority.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn burn_from_treasury(ctx: Context<BurnFromTreasury>, amount: u64) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(amount > 0, SentinelError::InvalidAmount);
         require!(
-            peers.len() == ctx.remaining_accounts.len(), 
-            SentinelError::InvalidInput
+            ctx.accounts.treasury_sentinel_ata.amount.saturating_sub(amount)
+                >= ctx.accounts.state.genesis_remaining,
+            SentinelError::GenesisReserved
         );
 
-        // Compute total karma
-        let total_karma: u128 = karmas.iter().map(|k| *k as u128).sum();
+        let signer_seeds: &[&[u8]] = &[TREASURY_VAULT_SEED, &[ctx.bumps.treasury_vault]];
+        let signer = &[signer_seeds];
 
-        // Edge case: no karma -> nothing to distribute, just advance cycle
-        if total_karma == 0 {
-            ctx.accounts.state.cycle_start_ts = now;
-            ctx.accounts.state.cycle_index = ctx.accounts.state
-                .cycle_index
-                .checked_add(1)
-                .ok_or(SentinelError::Overflow)?;
-            return Ok(());
-        }
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                from: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                authority: ctx.accounts.treasury_vault.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::burn(cpi_ctx, amount)?;
+
+        emit_cpi!(TreasuryBurned {
+            authority: ctx.accounts.authority.key(),
+            amount,
+        });
 
-        // Prepare signer seeds for state PDA mint authority
-        let (_, state_bump) = Pubkey::find_program_address(&[STATE_SEED], &crate::ID);
-        let signer_seeds: &[&[u8]] = &[STATE_SEED, &[state_bump]];
+        Ok(())
+    }
+
+    pub fn set_transfer_fee(
+        ctx: Context<SetTransferFee>,
+        basis_points: u16,
+        maximum_fee: u64,
+    ) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(basis_points as u64 <= BPS_DENOMINATOR, SentinelError::InvalidInput);
+
+        let signer_seeds: &[&[u8]] = &[STATE_SEED, &[ctx.accounts.state.bump]];
         let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferFeeSetTransferFee {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_fee_set(cpi_ctx, basis_points, maximum_fee)?;
 
-        for (i, peer_pubkey) in peers.iter().enumerate() {
-            let karma = karmas[i] as u128;
-            if karma == 0 { 
-                continue; 
-            }
-            
-            // Proportional share
-            let mut reward: u128 = (karma * CYCLE_REWARD_TOTAL as u128) / total_karma;
-            
-            // Cap at 10%
-            let cap: u128 = (CYCLE_REWARD_TOTAL as u128 * MAX_PEER_REWARD_PCT as u128) / 100u128;
-            if reward > cap { 
-                reward = cap; 
-            }
-            let reward_u64: u64 = reward as u64;
+        emit_cpi!(TransferFeeUpdated {
+            basis_points,
+            maximum_fee,
+        });
 
-            // Get peer's ATA from remaining_accounts
-            let ata_info = ctx.remaining_accounts
-                .get(i)
-                .ok_or(SentinelError::MissingAccount)?;
+        Ok(())
+    }
 
-            // Validate ATA is the canonical associated token address
-            let expected_ata = anchor_spl::associated_token::get_associated_token_address(
-                peer_pubkey, 
-                &ctx.accounts.sentinel_mint.key()
-            );
-            require!(
-                *ata_info.key == expected_ata, 
-                SentinelError::InvalidAccount
-            );
+    /// Permissionless: sweeps fees withheld in the token accounts passed as
+    /// `remaining_accounts` into the mint, then withdraws everything withheld on the mint
+    /// into the treasury vault ATA.
+    pub fn harvest_transfer_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, HarvestTransferFees<'info>>,
+    ) -> Result<()> {
+        let before = ctx.accounts.treasury_sentinel_ata.amount;
 
-            // Mint reward to peer's ATA
+        if !ctx.remaining_accounts.is_empty() {
             let cpi_ctx = CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                MintTo {
+                HarvestWithheldTokensToMint {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
                     mint: ctx.accounts.sentinel_mint.to_account_info(),
-                    to: ata_info.clone(),
-                    authority: ctx.accounts.state.to_account_info(),
                 },
             );
-            token::mint_to(cpi_ctx.with_signer(signer), reward_u64)?;
+            token_interface::harvest_withheld_tokens_to_mint(
+                cpi_ctx,
+                ctx.remaining_accounts.to_vec(),
+            )?;
         }
 
-        // Advance cycle
-        ctx.accounts.state.cycle_start_ts = now;
-        ctx.accounts.state.cycle_index = ctx.accounts.state
-            .cycle_index
-            .checked_add(1)
-            .ok_or(SentinelError::Overflow)?;
+        let signer_seeds: &[&[u8]] = &[STATE_SEED, &[ctx.accounts.state.bump]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            WithdrawWithheldTokensFromMint {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                destination: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::withdraw_withheld_tokens_from_mint(cpi_ctx)?;
 
-        Ok(())
-    }
+        ctx.accounts.treasury_sentinel_ata.reload()?;
+        let amount = ctx.accounts.treasury_sentinel_ata.amount.saturating_sub(before);
+
+        emit_cpi!(TransferFeesHarvested {
+            amount,
+            sources: ctx.remaining_accounts.len() as u32,
+        });
 
-    pub fn reset_karma(ctx: Context<ResetKarma>) -> Result<()> {
-        let peer = &mut ctx.accounts.peer;
-        peer.karma = 0;
         Ok(())
     }
-}
 
-// ============================================================================
-// Account Contexts
-// ============================================================================
+    pub fn add_storage_provider(ctx: Context<AddStorageProvider>, provider: Pubkey) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        let storage_provider = &mut ctx.accounts.storage_provider;
+        storage_provider.provider = provider;
+        storage_provider.registered_ts = Clock::get()?.unix_timestamp;
+        storage_provider.bump = ctx.bumps.storage_provider;
 
-    #[account(
-        init,
-        payer = authority,
-        seeds = [STATE_SEED],
-        bump,
-        space = 8 + State::SIZE,
-    )]
-    pub state: Account<'info, State>,
+        emit_cpi!(StorageProviderAdded { provider });
 
-    #[account(
-        init,
-        payer = authority,
-        mint::decimals = SENTINEL_DECIMALS,
-        mint::authority = authority,
-        mint::freeze_authority = state,
-    )]
-    pub sentinel_mint: Account<'info, Mint>,
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = authority,
-        seeds = [TREASURY_VAULT_SEED],
-        bump,
-        space = 8 + TreasuryVault::SIZE,
-    )]
-    pub treasury_vault: Account<'info, TreasuryVault>,
+    pub fn remove_storage_provider(ctx: Context<RemoveStorageProvider>) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
 
-    #[account(
-        init,
-        payer = authority,
-        associated_token::mint = sentinel_mint,
-        associated_token::authority = authority,
-    )]
-    pub authority_sentinel_ata: Account<'info, TokenAccount>,
+        emit_cpi!(StorageProviderRemoved {
+            provider: ctx.accounts.storage_provider.provider,
+        });
 
-    #[account(
-        init,
-        payer = authority,
-        associated_token::mint = sentinel_mint,
-        associated_token::authority = treasury_vault,
-    )]
-    pub treasury_sentinel_ata: Account<'info, TokenAccount>,
+        Ok(())
+    }
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    pub fn create_invite(ctx: Context<CreateInvite>, invitee: Pubkey) -> Result<()> {
+        let mut inviter_peer = ctx.accounts.inviter_peer.load_mut()?;
+        require!(inviter_peer.active != 0, SentinelError::NotPeer);
+        require!(
+            inviter_peer.invites_issued < ctx.accounts.state.invites_per_peer,
+            SentinelError::InviteQuotaExceeded
+        );
+        inviter_peer.invites_issued = inviter_peer
+            .invites_issued
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
 
-#[derive(Accounts)]
-pub struct JoinNetwork<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        let invite = &mut ctx.accounts.invite;
+        invite.inviter = ctx.accounts.inviter.key();
+        invite.invitee = invitee;
 
-    #[account(
-        seeds = [STATE_SEED],
-        bump,
-    )]
-    pub state: Account<'info, State>,
+        emit_cpi!(InviteCreated {
+            inviter: invite.inviter,
+            invitee,
+        });
 
-    #[account(
-        init,
-        payer = user,
-        seeds = [PEER_SEED, user.key().as_ref()],
-        bump,
-        space = 8 + PeerState::SIZE,
-    )]
-    pub peer: Account<'info, PeerState>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = user_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
-        constraint = user_sentinel_ata.owner == user.key() @ SentinelError::InvalidAccount,
-    )]
-    pub user_sentinel_ata: Account<'info, TokenAccount>,
+    pub fn join_network(ctx: Context<JoinNetwork>) -> Result<()> {
+        // In invite-only mode a one-time invite must be consumed (closed below by Anchor)
+        if ctx.accounts.state.invite_only {
+            require!(ctx.accounts.invite.is_some(), SentinelError::InviteRequired);
+        }
 
-    #[account(
-        seeds = [TREASURY_VAULT_SEED],
-        bump,
-    )]
-    pub treasury_vault: Account<'info, TreasuryVault>,
+        // Split the current join cost into a burned share and the treasury share
+        let join_cost = ctx.accounts.state.join_cost()?;
+        let burned = (join_cost as u128 * ctx.accounts.state.join_burn_bps as u128
+            / BPS_DENOMINATOR as u128) as u64;
+        let to_treasury = join_cost
+            .checked_sub(burned)
+            .ok_or(SentinelError::Overflow)?;
 
-    #[account(
-        mut,
-        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
-        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+        if burned > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.sentinel_mint.to_account_info(),
+                    from: ctx.accounts.user_sentinel_ata.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            );
+            token_interface::burn(cpi_ctx, burned)?;
+        }
+
+        // Transfer the remainder from user to treasury vault
+        if to_treasury > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_sentinel_ata.to_account_info(),
+                    mint: ctx.accounts.sentinel_mint.to_account_info(),
+                    to: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            );
+            token_interface::transfer_checked(cpi_ctx, to_treasury, ctx.accounts.sentinel_mint.decimals)?;
+        }
+
+        // Activate peer state
+        let mut peer = ctx.accounts.peer.load_init()?;
+        activate_peer(&mut peer, ctx.accounts.user.key(), ctx.accounts.state.cycle_index, join_cost, false);
+        ctx.accounts.state.total_peers = ctx.accounts.state
+            .total_peers
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        emit_cpi!(PeerJoined {
+            user: peer.user,
+            join_cost,
+            burned,
+            to_treasury,
+        });
+
+        Ok(())
+    }
+
+    pub fn join_network_with_sol(ctx: Context<JoinNetworkWithSol>) -> Result<()> {
+        if ctx.accounts.state.invite_only {
+            require!(ctx.accounts.invite.is_some(), SentinelError::InviteRequired);
+        }
+
+        // A zero price means SOL joining is disabled
+        let price = ctx.accounts.state.join_price_lamports;
+        require!(price > 0, SentinelError::SolJoinDisabled);
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.user.to_account_info(),
+                to: ctx.accounts.sol_vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_ctx, price)?;
+
+        let mut peer = ctx.accounts.peer.load_init()?;
+        activate_peer(&mut peer, ctx.accounts.user.key(), ctx.accounts.state.cycle_index, price, true);
+        ctx.accounts.state.total_peers = ctx.accounts.state
+            .total_peers
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        emit_cpi!(PeerJoinedWithSol {
+            user: peer.user,
+            lamports: price,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_sol(ctx: Context<WithdrawSol>, lamports: u64) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(lamports > 0, SentinelError::InvalidAmount);
+
+        let signer_seeds: &[&[u8]] = &[SOL_VAULT_SEED, &[ctx.bumps.sol_vault]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.sol_vault.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+            },
+            signer,
+        );
+        system_program::transfer(cpi_ctx, lamports)?;
+
+        emit_cpi!(SolWithdrawn {
+            authority: ctx.accounts.authority.key(),
+            destination: ctx.accounts.destination.key(),
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    pub fn leave_network(ctx: Context<LeaveNetwork>) -> Result<()> {
+        // Refunds are paid in whatever the peer joined with
+        let (join_paid, in_sol) = {
+            let peer = ctx.accounts.peer.load()?;
+            // A closed peer could not be matched back to its name
+            require!(peer.has_username == 0, SentinelError::UsernameHeld);
+            (peer.join_paid, peer.joined_with_sol != 0)
+        };
+        let refund = (join_paid as u128 * ctx.accounts.state.leave_refund_bps as u128
+            / BPS_DENOMINATOR as u128) as u64;
+
+        if refund > 0 && in_sol {
+            let signer_seeds: &[&[u8]] = &[SOL_VAULT_SEED, &[ctx.bumps.sol_vault]];
+            let signer = &[signer_seeds];
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.user.to_account_info(),
+                },
+                signer,
+            );
+            system_program::transfer(cpi_ctx, refund)?;
+        } else if refund > 0 {
+            require!(
+                ctx.accounts.treasury_sentinel_ata.amount.saturating_sub(refund)
+                    >= ctx.accounts.state.genesis_remaining,
+                SentinelError::GenesisReserved
+            );
+            let signer_seeds: &[&[u8]] = &[TREASURY_VAULT_SEED, &[ctx.bumps.treasury_vault]];
+            let signer = &[signer_seeds];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                    mint: ctx.accounts.sentinel_mint.to_account_info(),
+                    to: ctx.accounts.user_sentinel_ata.to_account_info(),
+                    authority: ctx.accounts.treasury_vault.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(cpi_ctx, refund, ctx.accounts.sentinel_mint.decimals)?;
+        }
+
+        // Deactivate; the account itself is closed to the user on exit
+        let mut peer = ctx.accounts.peer.load_mut()?;
+        peer.active = 0;
+        ctx.accounts.state.total_peers = ctx.accounts.state.total_peers.saturating_sub(1);
+
+        emit_cpi!(PeerLeft {
+            user: peer.user,
+            refund,
+            in_sol,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_username(ctx: Context<RegisterUsername>, name: String) -> Result<()> {
+        require!(is_normalized_username(&name), SentinelError::InvalidUsername);
+        {
+            let mut peer = ctx.accounts.peer.load_mut()?;
+            require!(peer.active != 0, SentinelError::NotPeer);
+            require!(peer.has_username == 0, SentinelError::UsernameHeld);
+            peer.has_username = 1;
+        }
+
+        let fee = ctx.accounts.state.username_fee;
+        if fee > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_sentinel_ata.to_account_info(),
+                    mint: ctx.accounts.sentinel_mint.to_account_info(),
+                    to: ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            );
+            token_interface::transfer_checked(cpi_ctx, fee, ctx.accounts.sentinel_mint.decimals)?;
+        }
+
+        let username = &mut ctx.accounts.username;
+        username.owner = ctx.accounts.user.key();
+        username.name = name;
+        username.registered_ts = Clock::get()?.unix_timestamp;
+        username.bump = ctx.bumps.username;
+
+        emit_cpi!(UsernameRegistered {
+            owner: username.owner,
+            name: username.name.clone(),
+            fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn release_username(ctx: Context<ReleaseUsername>) -> Result<()> {
+        ctx.accounts.peer.load_mut()?.has_username = 0;
+
+        emit_cpi!(UsernameReleased {
+            owner: ctx.accounts.username.owner,
+            name: ctx.accounts.username.name.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn create_achievement(
+        ctx: Context<CreateAchievement>,
+        id: u32,
+        metric: AchievementMetric,
+        threshold: u64,
+        karma_bonus: u64,
+    ) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        require!(threshold > 0, SentinelError::InvalidInput);
+
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.id = id;
+        achievement.metric = metric;
+        achievement.threshold = threshold;
+        achievement.karma_bonus = karma_bonus;
+        achievement.claims = 0;
+        achievement.bump = ctx.bumps.achievement;
+
+        emit_cpi!(AchievementCreated {
+            id,
+            metric,
+            threshold,
+            karma_bonus,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_achievement(ctx: Context<ClaimAchievement>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let achievement = &mut ctx.accounts.achievement;
+        let mut peer = ctx.accounts.peer.load_mut()?;
+        require!(peer.active != 0, SentinelError::NotPeer);
+
+        let progress = match achievement.metric {
+            AchievementMetric::KarmaEarned => peer.karma_earned,
+            AchievementMetric::LikesGiven => peer.likes_given,
+            AchievementMetric::PostsCreated => ctx
+                .accounts
+                .author_index
+                .as_ref()
+                .map_or(0, |index| index.posts_created),
+        };
+        require!(progress >= achievement.threshold, SentinelError::AchievementNotMet);
+
+        // The bonus lands in the current cycle like any other karma
+        let karma_bonus = achievement.karma_bonus;
+        if karma_bonus > 0 {
+            credit_karma(&mut peer, state, karma_bonus)?;
+        }
+
+        achievement.claims = achievement
+            .claims
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        // The badge PDA is what makes the claim one-shot
+        let badge = &mut ctx.accounts.badge;
+        badge.owner = peer.user;
+        badge.achievement = achievement.key();
+        badge.achievement_id = achievement.id;
+        badge.claimed_ts = Clock::get()?.unix_timestamp;
+        badge.bump = ctx.bumps.badge;
+
+        emit_cpi!(AchievementClaimed {
+            id: achievement.id,
+            owner: peer.user,
+            progress,
+            karma_bonus,
+        });
+
+        Ok(())
+    }
+
+    pub fn mint_nft(
+        ctx: Context<MintNft>,
+        hash: [u8; 32],
+        content: ContentAddress,
+        soulbound: bool,
+        royalty_bps: u16,
+        tag: [u8; TAG_LEN],
+    ) -> Result<()> {
+        // A pending commitment is consumed through `reveal_post` only
+        require!(ctx.accounts.post_commit.is_none(), SentinelError::InvalidAccount);
+        let committed_ts = Clock::get()?.unix_timestamp;
+        mint_post(
+            ctx,
+            NewPost { hash, content, soulbound, royalty_bps, tag, committed_ts },
+        )
+    }
+
+    /// Mints a post as a single-account Metaplex Core asset instead of an SPL mint + ATA.
+    /// Enabled once governance sets `core_program`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint_core_post(
+        ctx: Context<MintCorePost>,
+        hash: [u8; 32],
+        content: ContentAddress,
+        soulbound: bool,
+        royalty_bps: u16,
+        tag: [u8; TAG_LEN],
+        name: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(ctx.accounts.peer.load()?.active != 0, SentinelError::NotPeer);
+        require!(
+            ctx.accounts.state.core_program != Pubkey::default(),
+            SentinelError::CoreAssetsDisabled
+        );
+        let committed_ts = Clock::get()?.unix_timestamp;
+        let new_post = NewPost { hash, content, soulbound, royalty_bps, tag, committed_ts };
+        check_new_post(
+            &ctx.accounts.state,
+            &new_post,
+            ctx.accounts.tag_counter.as_mut(),
+            ctx.accounts.storage_provider.as_ref(),
+            ctx.accounts.instructions.as_ref(),
+        )?;
+
+        create_core_asset(&ctx, name, uri, soulbound)?;
+
+        // Lock the posting bond in the per-post escrow
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sentinel_mint,
+            ctx.accounts.user_sentinel_ata.to_account_info(),
+            ctx.accounts.bond_escrow.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            &[],
+            ctx.accounts.state.post_bond,
+        )?;
+
+        init_post(
+            &mut *ctx.accounts.post.load_init()?,
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.author_index,
+            ctx.accounts.user.key(),
+            ctx.accounts.asset.key(),
+            PostAsset::CoreAsset,
+            &new_post,
+        )?;
+        ctx.accounts.user_post.post = ctx.accounts.post.key();
+
+        Ok(())
+    }
+
+    /// First half of the commit-reveal posting flow: records `sha256(hash || salt)` so the
+    /// content hash stays private until it is revealed.
+    pub fn commit_post(ctx: Context<CommitPost>, commitment: [u8; 32]) -> Result<()> {
+        require!(ctx.accounts.peer.load()?.active != 0, SentinelError::NotPeer);
+
+        let clock = Clock::get()?;
+        let post_commit = &mut ctx.accounts.post_commit;
+        post_commit.owner = ctx.accounts.user.key();
+        post_commit.commitment = commitment;
+        post_commit.slot = clock.slot;
+        post_commit.ts = clock.unix_timestamp;
+        post_commit.bump = ctx.bumps.post_commit;
+        Ok(())
+    }
+
+    /// Mints a post from an earlier commitment. The post's `committed_ts` is the commit
+    /// time, so a copy minted by whoever saw the reveal cannot claim to be earlier.
+    pub fn reveal_post(
+        ctx: Context<MintNft>,
+        hash: [u8; 32],
+        salt: [u8; 32],
+        content: ContentAddress,
+        soulbound: bool,
+        royalty_bps: u16,
+        tag: [u8; TAG_LEN],
+    ) -> Result<()> {
+        let post_commit = ctx
+            .accounts
+            .post_commit
+            .as_ref()
+            .ok_or(SentinelError::MissingAccount)?;
+        require!(
+            hashv(&[&hash, &salt]).to_bytes() == post_commit.commitment,
+            SentinelError::CommitmentMismatch
+        );
+        require!(
+            Clock::get()?.slot >= post_commit.slot.saturating_add(REVEAL_DELAY_SLOTS),
+            SentinelError::RevealTooEarly
+        );
+        let committed_ts = post_commit.ts;
+        mint_post(
+            ctx,
+            NewPost { hash, content, soulbound, royalty_bps, tag, committed_ts },
+        )
+    }
+
+    pub fn cancel_post_commit(_ctx: Context<CancelPostCommit>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn create_post_index(ctx: Context<CreatePostIndex>, post_id: u64) -> Result<()> {
+        require!(
+            ctx.accounts.post.load()?.post_id == post_id,
+            SentinelError::InvalidInput
+        );
+        ctx.accounts.post_index.post = ctx.accounts.post.key();
+        Ok(())
+    }
+
+    pub fn update_post(
+        ctx: Context<UpdatePost>,
+        hash: [u8; 32],
+        content: ContentAddress,
+    ) -> Result<()> {
+        content.validate()?;
+        let mut post = ctx.accounts.post.load_mut()?;
+        require!(post.removed == 0, SentinelError::PostRemoved);
+
+        // Keep only the most recent previous hashes
+        let previous_hash = post.hash;
+        let len = post.hash_history_len as usize;
+        if len == MAX_HASH_HISTORY {
+            post.hash_history.copy_within(1.., 0);
+            post.hash_history[MAX_HASH_HISTORY - 1] = previous_hash;
+        } else {
+            post.hash_history[len] = previous_hash;
+            post.hash_history_len += 1;
+        }
+
+        post.hash = hash;
+        post.set_content(&content);
+        post.edit_count = post
+            .edit_count
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        emit_cpi!(PostUpdated {
+            post: ctx.accounts.post.key(),
+            previous_hash,
+            hash,
+            content,
+            edit_count: post.edit_count,
+        });
+
+        Ok(())
+    }
+
+    pub fn like_nft(ctx: Context<LikeNft>) -> Result<()> {
+        // prevent double-like via PDA uniqueness and ensure liker is an active peer
+        let mut post = ctx.accounts.post.load_mut()?;
+        // Prevent self-liking; checked first, as the liker's peer is then also the liked
+        // one and cannot be loaded twice
+        require!(ctx.accounts.liker.key() != post.owner, SentinelError::CannotLikeSelf);
+        let mut liked_peer = ctx.accounts.liked_peer.load_mut()?;
+        let mut liker_peer = ctx.accounts.liker_peer.load_mut()?;
+        let state = &mut ctx.accounts.state;
+        
+        require!(liked_peer.active != 0, SentinelError::NotPeer);
+        require!(liker_peer.active != 0, SentinelError::NotPeer);
+        require!(post.removed == 0, SentinelError::PostRemoved);
+
+        spend_like(
+            &mut liker_peer,
+            &mut ctx.accounts.like_pair,
+            ctx.accounts.liker.key(),
+            post.owner,
+            state,
+        )?;
+
+        post.likes = post
+            .likes
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        // Only likes inside the post's reward window earn karma, so old posts can't be farmed
+        let counted = state.cycle_index < post.cycle_index.saturating_add(state.like_reward_window);
+        if counted {
+            // Likes on a tag with its own budget earn into that tag's pool, not the general one
+            let mut pooled = false;
+            if post.tag != [0; TAG_LEN] {
+                let tag_counter = ctx
+                    .accounts
+                    .tag_counter
+                    .as_mut()
+                    .ok_or(SentinelError::MissingAccount)?;
+                tag_counter.roll(state.cycle_index);
+                pooled = tag_counter.reward_budget > 0;
+                if pooled {
+                    let tag_karma = ctx
+                        .accounts
+                        .tag_karma
+                        .as_mut()
+                        .ok_or(SentinelError::MissingAccount)?;
+                    if tag_karma.user == Pubkey::default() {
+                        tag_karma.tag = post.tag;
+                        tag_karma.user = post.owner;
+                        tag_karma.cycle_index = state.cycle_index;
+                        tag_karma.bump = ctx.bumps.tag_karma.ok_or(SentinelError::MissingAccount)?;
+                    } else if tag_karma.cycle_index != state.cycle_index {
+                        require!(tag_karma.karma == 0, SentinelError::KarmaUnsettled);
+                        tag_karma.cycle_index = state.cycle_index;
+                    }
+                    tag_karma.karma = tag_karma
+                        .karma
+                        .checked_add(1)
+                        .ok_or(SentinelError::Overflow)?;
+                    tag_counter.cycle_karma = tag_counter
+                        .cycle_karma
+                        .checked_add(1)
+                        .ok_or(SentinelError::Overflow)?;
+                    post.pool_likes = post
+                        .pool_likes
+                        .checked_add(1)
+                        .ok_or(SentinelError::Overflow)?;
+                }
+            }
+            if !pooled {
+                credit_karma(&mut liked_peer, state, 1)?;
+            }
+            liked_peer.karma_earned = liked_peer
+                .karma_earned
+                .checked_add(1)
+                .ok_or(SentinelError::Overflow)?;
+        }
+
+        // Opportunistically keep the cycle's trending list current
+        if let Some(top_posts) = &ctx.accounts.top_posts {
+            top_posts.load_mut()?.record(ctx.accounts.post.key(), post.owner, post.likes);
+        }
+
+        let like = &mut ctx.accounts.like;
+        like.liker = ctx.accounts.liker.key();
+        like.post = ctx.accounts.post.key();
+        like.ts = Clock::get()?.unix_timestamp;
+        like.cycle_index = state.cycle_index;
+        like.counted = counted;
+        like.target = LikeTarget::Post;
+
+        Ok(())
+    }
+
+    pub fn create_comment(ctx: Context<CreateComment>, hash: [u8; 32]) -> Result<()> {
+        require!(ctx.accounts.peer.load()?.active != 0, SentinelError::NotPeer);
+
+        let mut post = ctx.accounts.post.load_mut()?;
+        require!(post.removed == 0, SentinelError::PostRemoved);
+        let index = post.comment_count;
+        post.comment_count = index.checked_add(1).ok_or(SentinelError::Overflow)?;
+
+        let parent = ctx
+            .accounts
+            .parent_comment
+            .as_ref()
+            .map_or(Pubkey::default(), |c| c.key());
+
+        let comment = &mut ctx.accounts.comment;
+        comment.post = ctx.accounts.post.key();
+        comment.parent = parent;
+        comment.author = ctx.accounts.author.key();
+        comment.hash = hash;
+        comment.index = index;
+        comment.likes = 0;
+        comment.counted_likes = 0;
+        comment.cycle_index = ctx.accounts.state.cycle_index;
+        comment.created_ts = Clock::get()?.unix_timestamp;
+        comment.bump = ctx.bumps.comment;
+
+        emit_cpi!(CommentCreated {
+            comment: comment.key(),
+            post: comment.post,
+            parent,
+            author: comment.author,
+            hash,
+        });
+
+        Ok(())
+    }
+
+    pub fn like_comment(ctx: Context<LikeComment>) -> Result<()> {
+        // Before the peers load, as liking your own comment would load one peer twice
+        require!(
+            ctx.accounts.liker.key() != ctx.accounts.comment.author,
+            SentinelError::CannotLikeSelf
+        );
+        let mut liked_peer = ctx.accounts.liked_peer.load_mut()?;
+        let mut liker_peer = ctx.accounts.liker_peer.load_mut()?;
+        let state = &mut ctx.accounts.state;
+        let comment = &mut ctx.accounts.comment;
+
+        require!(liked_peer.active != 0, SentinelError::NotPeer);
+        require!(ctx.accounts.post.load()?.removed == 0, SentinelError::PostRemoved);
+
+        spend_like(
+            &mut liker_peer,
+            &mut ctx.accounts.like_pair,
+            ctx.accounts.liker.key(),
+            comment.author,
+            state,
+        )?;
+
+        comment.likes = comment
+            .likes
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        // Comment likes are worth a fraction of a post like; whole karma is paid out
+        // as the comment's counted likes cross each boundary
+        let counted = state.cycle_index < comment.cycle_index.saturating_add(state.like_reward_window);
+        let mut karma = 0;
+        if counted {
+            let bps = state.comment_like_bps as u128;
+            let before = comment.counted_likes as u128 * bps / BPS_DENOMINATOR as u128;
+            comment.counted_likes = comment
+                .counted_likes
+                .checked_add(1)
+                .ok_or(SentinelError::Overflow)?;
+            let after = comment.counted_likes as u128 * bps / BPS_DENOMINATOR as u128;
+            karma = (after - before) as u64;
+            if karma > 0 {
+                credit_karma(&mut liked_peer, state, karma)?;
+                liked_peer.karma_earned = liked_peer
+                    .karma_earned
+                    .checked_add(karma)
+                    .ok_or(SentinelError::Overflow)?;
+            }
+        }
+
+        let like = &mut ctx.accounts.like;
+        like.liker = ctx.accounts.liker.key();
+        like.post = comment.key();
+        like.ts = Clock::get()?.unix_timestamp;
+        like.cycle_index = state.cycle_index;
+        like.counted = counted;
+        like.target = LikeTarget::Comment;
+
+        emit_cpi!(CommentLiked {
+            comment: comment.key(),
+            liker: like.liker,
+            author: comment.author,
+            karma,
+        });
+
+        Ok(())
+    }
+
+    /// Creates the post counter for a tag; anyone may open a new topic.
+    pub fn init_tag(ctx: Context<InitTag>, tag: [u8; TAG_LEN]) -> Result<()> {
+        require!(tag != [0; TAG_LEN] && is_valid_tag(&tag), SentinelError::InvalidTag);
+        let tag_counter = &mut ctx.accounts.tag_counter;
+        tag_counter.tag = tag;
+        tag_counter.posts = 0;
+        tag_counter.cycle_index = ctx.accounts.state.cycle_index;
+        tag_counter.bump = ctx.bumps.tag_counter;
+        Ok(())
+    }
+
+    pub fn set_tag_budget(ctx: Context<SetTagBudget>, reward_budget: u64) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+
+        // The budget applies from the current cycle on; a closed cycle keeps its snapshot
+        let tag_counter = &mut ctx.accounts.tag_counter;
+        tag_counter.roll(ctx.accounts.state.cycle_index);
+        tag_counter.reward_budget = reward_budget;
+
+        emit_cpi!(TagBudgetSet {
+            tag: tag_counter.tag,
+            reward_budget,
+        });
+
+        Ok(())
+    }
+
+    /// Keyless payout of a peer's share of a tag pool for the tag's last closed cycle:
+    /// `karma * budget / total`, capped like the general pool. Karma from an older cycle
+    /// than the tag's last closed one has expired and is just cleared.
+    pub fn claim_tag_reward(ctx: Context<ClaimTagReward>) -> Result<()> {
+        let state = &ctx.accounts.state;
+        let tag_counter = &mut ctx.accounts.tag_counter;
+        let tag_karma = &mut ctx.accounts.tag_karma;
+        require!(
+            tag_karma.karma > 0 && tag_karma.cycle_index < state.cycle_index,
+            SentinelError::NothingToClaim
+        );
+
+        tag_counter.roll(state.cycle_index);
+        let karma = tag_karma.karma as u128;
+        let mut reward: u64 = 0;
+        if tag_karma.cycle_index == tag_counter.closed_cycle_index && tag_counter.closed_karma > 0 {
+            reward = reward_share(
+                karma,
+                tag_counter.closed_karma as u128,
+                tag_counter.closed_budget,
+            );
+
+            mint_reward(
+                state,
+                ctx.accounts.sentinel_mint.to_account_info(),
+                ctx.accounts.peer_sentinel_ata.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                SekaMinter::from_accounts(
+                    &ctx.accounts.seka_config,
+                    &ctx.accounts.seka_mint_authority,
+                    &ctx.accounts.seka_program,
+                ),
+                reward,
+            )?;
+            tag_counter.closed_minted = tag_counter
+                .closed_minted
+                .checked_add(reward)
+                .ok_or(SentinelError::Overflow)?;
+        }
+
+        emit_cpi!(TagRewardClaimed {
+            tag: tag_counter.tag,
+            user: tag_karma.user,
+            cycle_index: tag_karma.cycle_index,
+            karma: tag_karma.karma,
+            reward,
+        });
+
+        tag_karma.karma = 0;
+        tag_karma.cycle_index = state.cycle_index;
+
+        Ok(())
+    }
+
+    pub fn init_karma_history(ctx: Context<InitKarmaHistory>) -> Result<()> {
+        let mut history = ctx.accounts.karma_history.load_init()?;
+        history.user = ctx.accounts.peer.load()?.user;
+        Ok(())
+    }
+
+    pub fn init_top_posts(ctx: Context<InitTopPosts>) -> Result<()> {
+        let mut top_posts = ctx.accounts.top_posts.load_init()?;
+        top_posts.cycle_index = ctx.accounts.state.cycle_index;
+        Ok(())
+    }
+
+    pub fn claim_top_post_bonus(ctx: Context<ClaimTopPostBonus>, entry: u8) -> Result<()> {
+        let mut top_posts = ctx.accounts.top_posts.load_mut()?;
+        require!(top_posts.finalized != 0, SentinelError::CycleNotEnded);
+        let i = entry as usize;
+        require!(i < top_posts.len as usize, SentinelError::InvalidInput);
+        require!(
+            top_posts.entries[i].owner == ctx.accounts.owner.key(),
+            SentinelError::Unauthorized
+        );
+        require!(
+            top_posts.claimed_mask & (1u16 << i) == 0,
+            SentinelError::BonusAlreadyClaimed
+        );
+        top_posts.claimed_mask |= 1u16 << i;
+
+        let amount = top_posts.bonus;
+        mint_reward(
+            &ctx.accounts.state,
+            ctx.accounts.sentinel_mint.to_account_info(),
+            ctx.accounts.owner_sentinel_ata.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            SekaMinter::from_accounts(
+                &ctx.accounts.seka_config,
+                &ctx.accounts.seka_mint_authority,
+                &ctx.accounts.seka_program,
+            ),
+            amount,
+        )?;
+
+        emit_cpi!(TopPostBonusClaimed {
+            cycle_index: top_posts.cycle_index,
+            post: top_posts.entries[i].post,
+            owner: top_posts.entries[i].owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_post(ctx: Context<RemovePost>) -> Result<()> {
+        ctx.accounts.post.load_mut()?.removed = 1;
+
+        emit_cpi!(PostRemoved {
+            post: ctx.accounts.post.key(),
+            moderator: ctx.accounts.moderator.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn close_post(ctx: Context<ClosePost>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let post = ctx.accounts.post.load()?;
+        require!(
+            signer == post.owner || signer == ctx.accounts.state.moderator,
+            SentinelError::Unauthorized
+        );
+        // Only posts that no longer back a live NFT can be reclaimed
+        let burned = if post.asset_kind == PostAsset::CoreAsset as u8 {
+            let asset = ctx.accounts.asset.as_ref().ok_or(SentinelError::MissingAccount)?;
+            // Core leaves a burned asset as a single `Uninitialized` key byte
+            asset.try_borrow_data()?.first() != Some(&CORE_KEY_ASSET_V1)
+        } else {
+            let nft_mint = ctx.accounts.nft_mint.as_ref().ok_or(SentinelError::MissingAccount)?;
+            nft_mint.supply == 0
+        };
+        require!(post.removed != 0 || burned, SentinelError::PostStillActive);
+        require!(post.bond_escrow_open == 0, SentinelError::BondOutstanding);
+
+        emit_cpi!(PostClosed {
+            post: ctx.accounts.post.key(),
+            closed_by: signer,
+        });
+
+        Ok(())
+    }
+
+    pub fn refund_bond(ctx: Context<SettleBond>) -> Result<()> {
+        {
+            let post = ctx.accounts.post.load()?;
+            require!(post.owner == ctx.accounts.signer.key(), SentinelError::Unauthorized);
+            require!(post.removed == 0, SentinelError::PostRemoved);
+            require!(
+                post.cycle_index < ctx.accounts.state.cycle_index,
+                SentinelError::CycleNotEnded
+            );
+            require!(
+                ctx.accounts.destination.owner == post.owner,
+                SentinelError::InvalidAccount
+            );
+        }
+
+        settle_bond(&ctx)?;
+
+        let mut post = ctx.accounts.post.load_mut()?;
+        emit_cpi!(BondRefunded {
+            post: ctx.accounts.post.key(),
+            owner: post.owner,
+            amount: post.bond,
+        });
+        post.bond = 0;
+        post.bond_escrow_open = 0;
+
+        Ok(())
+    }
+
+    pub fn slash_bond(ctx: Context<SettleBond>) -> Result<()> {
+        require!(
+            ctx.accounts.signer.key() == ctx.accounts.state.moderator,
+            SentinelError::Unauthorized
+        );
+        require!(ctx.accounts.post.load()?.removed != 0, SentinelError::PostStillActive);
+        require!(
+            ctx.accounts.destination.owner == ctx.accounts.state.treasury_vault,
+            SentinelError::InvalidAccount
+        );
+
+        settle_bond(&ctx)?;
+
+        let mut post = ctx.accounts.post.load_mut()?;
+        emit_cpi!(BondSlashed {
+            post: ctx.accounts.post.key(),
+            owner: post.owner,
+            amount: post.bond,
+        });
+        post.bond = 0;
+        post.bond_escrow_open = 0;
+
+        Ok(())
+    }
+
+    pub fn file_report(ctx: Context<FileReport>, reason_hash: [u8; 32]) -> Result<()> {
+        require!(ctx.accounts.peer.load()?.active != 0, SentinelError::NotPeer);
+        {
+            let post = ctx.accounts.post.load()?;
+            require!(post.removed == 0, SentinelError::PostRemoved);
+            require!(post.owner != ctx.accounts.reporter.key(), SentinelError::InvalidInput);
+        }
+
+        let bond = ctx.accounts.state.report_bond;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sentinel_mint,
+            ctx.accounts.reporter_sentinel_ata.to_account_info(),
+            ctx.accounts.report_escrow.to_account_info(),
+            ctx.accounts.reporter.to_account_info(),
+            &[],
+            bond,
+        )?;
+
+        let report = &mut ctx.accounts.report;
+        report.reporter = ctx.accounts.reporter.key();
+        report.post = ctx.accounts.post.key();
+        report.bond = bond;
+        report.reason_hash = reason_hash;
+        report.created_ts = Clock::get()?.unix_timestamp;
+        report.bump = ctx.bumps.report;
+
+        emit_cpi!(ReportFiled {
+            report: report.key(),
+            post: report.post,
+            reporter: report.reporter,
+            bond,
+            reason_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Moderator verdict on a report.
+    ///
+    /// Upheld: the post is removed, its bond is slashed with `report_bounty_bps` going to the
+    /// reporter and the rest to the treasury, the reporter gets the flat `report_bounty` from
+    /// the treasury and their own bond back. Dismissed: the reporter's bond goes to the treasury.
+    pub fn resolve_report(ctx: Context<ResolveReport>, uphold: bool) -> Result<()> {
+        let state = &ctx.accounts.state;
+        let report_key = ctx.accounts.report.key();
+        let post_key = ctx.accounts.post.key();
+        let reporter = ctx.accounts.report.reporter;
+        let report_bond = ctx.accounts.report_escrow.amount;
+
+        let report_seeds: &[&[u8]] = &[
+            REPORT_SEED,
+            post_key.as_ref(),
+            reporter.as_ref(),
+            &[ctx.accounts.report.bump],
+        ];
+        let report_signer = &[report_seeds];
+
+        let mut bounty: u64 = 0;
+        let mut forfeited: u64 = 0;
+        if uphold {
+            let (nft_mint, bond_open) = {
+                let mut post = ctx.accounts.post.load_mut()?;
+                post.removed = 1;
+                (post.nft_mint, post.bond_escrow_open != 0)
+            };
+
+            // An earlier upheld report may already have slashed the bond
+            if bond_open {
+                let bond_escrow = ctx
+                    .accounts
+                    .bond_escrow
+                    .as_ref()
+                    .ok_or(SentinelError::MissingAccount)?;
+                let post_seeds: &[&[u8]] = &[POST_SEED, nft_mint.as_ref(), &[ctx.bumps.post]];
+                let post_signer = &[post_seeds];
+
+                let slashed = bond_escrow.amount;
+                let share = (slashed as u128 * state.report_bounty_bps as u128
+                    / BPS_DENOMINATOR as u128) as u64;
+                transfer_tokens(
+                    &ctx.accounts.token_program,
+                    &ctx.accounts.sentinel_mint,
+                    bond_escrow.to_account_info(),
+                    ctx.accounts.reporter_sentinel_ata.to_account_info(),
+                    ctx.accounts.post.to_account_info(),
+                    post_signer,
+                    share,
+                )?;
+                transfer_tokens(
+                    &ctx.accounts.token_program,
+                    &ctx.accounts.sentinel_mint,
+                    bond_escrow.to_account_info(),
+                    ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                    ctx.accounts.post.to_account_info(),
+                    post_signer,
+                    slashed - share,
+                )?;
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    CloseAccount {
+                        account: bond_escrow.to_account_info(),
+                        destination: ctx.accounts.post_owner.to_account_info(),
+                        authority: ctx.accounts.post.to_account_info(),
+                    },
+                    post_signer,
+                );
+                token_interface::close_account(cpi_ctx)?;
+
+                let mut post = ctx.accounts.post.load_mut()?;
+                emit_cpi!(BondSlashed {
+                    post: post_key,
+                    owner: post.owner,
+                    amount: slashed,
+                });
+                post.bond = 0;
+                post.bond_escrow_open = 0;
+                bounty = share;
+            }
+
+            let flat = state.report_bounty;
+            if flat > 0 {
+                ctx.accounts.treasury_sentinel_ata.reload()?;
+                require!(
+                    ctx.accounts.treasury_sentinel_ata.amount.saturating_sub(flat)
+                        >= state.genesis_remaining,
+                    SentinelError::GenesisReserved
+                );
+                let treasury_seeds: &[&[u8]] = &[TREASURY_VAULT_SEED, &[ctx.bumps.treasury_vault]];
+                transfer_tokens(
+                    &ctx.accounts.token_program,
+                    &ctx.accounts.sentinel_mint,
+                    ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                    ctx.accounts.reporter_sentinel_ata.to_account_info(),
+                    ctx.accounts.treasury_vault.to_account_info(),
+                    &[treasury_seeds],
+                    flat,
+                )?;
+                bounty = bounty.checked_add(flat).ok_or(SentinelError::Overflow)?;
+            }
+
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                &ctx.accounts.sentinel_mint,
+                ctx.accounts.report_escrow.to_account_info(),
+                ctx.accounts.reporter_sentinel_ata.to_account_info(),
+                ctx.accounts.report.to_account_info(),
+                report_signer,
+                report_bond,
+            )?;
+        } else {
+            transfer_tokens(
+                &ctx.accounts.token_program,
+                &ctx.accounts.sentinel_mint,
+                ctx.accounts.report_escrow.to_account_info(),
+                ctx.accounts.treasury_sentinel_ata.to_account_info(),
+                ctx.accounts.report.to_account_info(),
+                report_signer,
+                report_bond,
+            )?;
+            forfeited = report_bond;
+        }
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.report_escrow.to_account_info(),
+                destination: ctx.accounts.reporter.to_account_info(),
+                authority: ctx.accounts.report.to_account_info(),
+            },
+            report_signer,
+        );
+        token_interface::close_account(cpi_ctx)?;
+
+        emit_cpi!(ReportResolved {
+            report: report_key,
+            post: post_key,
+            reporter,
+            upheld: uphold,
+            bounty,
+            forfeited,
+        });
+
+        Ok(())
+    }
+
+    pub fn delete_post(ctx: Context<DeletePost>) -> Result<()> {
+        let (soulbound, bond_escrow_open, asset_kind) = {
+            let post = ctx.accounts.post.load()?;
+            (post.soulbound != 0, post.bond_escrow_open != 0, post.asset_kind)
+        };
+        // Settling the bond first keeps spammers from clawing it back before moderation
+        require!(!bond_escrow_open, SentinelError::BondOutstanding);
+
+        if asset_kind == PostAsset::CoreAsset as u8 {
+            burn_core_asset(&ctx, soulbound)?;
+        } else {
+            burn_spl_nft(&ctx, soulbound)?;
+        }
+
+        // Karma earned this cycle has not been paid out yet, so take it back; once the post
+        // has changed hands the deleting owner did not earn all of it, so leave it be
+        let post = ctx.accounts.post.load()?;
+        let mut karma_removed = 0;
+        if post.cycle_index == ctx.accounts.state.cycle_index && post.owner == post.creator {
+            let mut peer = ctx.accounts.peer.load_mut()?;
+            // Likes routed into a tag pool never reached the general karma
+            karma_removed = (post.likes - post.pool_likes).min(peer.karma);
+            peer.karma -= karma_removed;
+            peer.karma_earned = peer.karma_earned.saturating_sub(karma_removed);
+            ctx.accounts.state.cycle_karma = ctx.accounts.state.cycle_karma.saturating_sub(karma_removed);
+        }
+
+        emit_cpi!(PostDeleted {
+            post: ctx.accounts.post.key(),
+            owner: post.owner,
+            karma_removed,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_like(ctx: Context<CloseLike>) -> Result<()> {
+        // A closed post can no longer influence rewards; otherwise wait for its reward window
+        // to be finalized so the like can't be re-cast for extra karma
+        let post_info = &ctx.accounts.post;
+        if !post_info.data_is_empty() && *post_info.owner == crate::ID {
+            let data = post_info.try_borrow_data()?;
+            let cycle_index = match ctx.accounts.like.target {
+                LikeTarget::Post => {
+                    require!(
+                        data.len() >= 8 + Post::SIZE && data[..8] == Post::DISCRIMINATOR,
+                        SentinelError::InvalidAccount
+                    );
+                    bytemuck::from_bytes::<Post>(&data[8..8 + Post::SIZE]).cycle_index
+                }
+                LikeTarget::Comment => Comment::try_deserialize(&mut &data[..])?.cycle_index,
+            };
+            let state = &ctx.accounts.state;
+            require!(
+                cycle_index.saturating_add(state.like_reward_window.max(1)) <= state.cycle_index,
+                SentinelError::CycleNotEnded
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Lets whoever now holds the post NFT take over `post.owner`, e.g. after a transfer
+    /// outside the built-in marketplace. Future karma for the post goes to the new owner.
+    pub fn sync_post_owner(ctx: Context<SyncPostOwner>) -> Result<()> {
+        let mut post = ctx.accounts.post.load_mut()?;
+        require!(post.bond_escrow_open == 0, SentinelError::BondOutstanding);
+        let previous_owner = post.owner;
+        post.owner = ctx.accounts.holder.key();
+
+        emit_cpi!(PostOwnerSynced {
+            post: ctx.accounts.post.key(),
+            previous_owner,
+            owner: post.owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn list_post_nft(ctx: Context<ListPostNft>, price: u64) -> Result<()> {
+        require!(price > 0, SentinelError::InvalidAmount);
+        {
+            let post = ctx.accounts.post.load()?;
+            require!(post.removed == 0, SentinelError::PostRemoved);
+            require!(post.soulbound == 0, SentinelError::PostSoulbound);
+            // The bond belongs to the seller and would otherwise follow the post to the buyer
+            require!(post.bond_escrow_open == 0, SentinelError::BondOutstanding);
+        }
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.seller_nft_ata.to_account_info(),
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                to: ctx.accounts.escrow_nft_ata.to_account_info(),
+                authority: ctx.accounts.seller.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(cpi_ctx, 1, 0)?;
+
+        let listing = &mut ctx.accounts.listing;
+        listing.seller = ctx.accounts.seller.key();
+        listing.post = ctx.accounts.post.key();
+        listing.nft_mint = ctx.accounts.nft_mint.key();
+        listing.price = price;
+        listing.bump = ctx.bumps.listing;
+
+        emit_cpi!(PostListed {
+            post: listing.post,
+            seller: listing.seller,
+            price,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+        let post_key = ctx.accounts.listing.post;
+        let signer_seeds: &[&[u8]] = &[LISTING_SEED, post_key.as_ref(), &[ctx.accounts.listing.bump]];
+        let signer = &[signer_seeds];
+        release_listing_escrow(
+            &ctx.accounts.listing,
+            &ctx.accounts.escrow_nft_ata,
+            &ctx.accounts.nft_mint,
+            ctx.accounts.seller_nft_ata.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            &ctx.accounts.token_program,
+            signer,
+        )?;
+
+        emit_cpi!(ListingCancelled {
+            post: post_key,
+            seller: ctx.accounts.seller.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn buy_post_nft(ctx: Context<BuyPostNft>, max_price: u64) -> Result<()> {
+        let price = ctx.accounts.listing.price;
+        // Guards against the seller repricing between quote and purchase
+        require!(price <= max_price, SentinelError::PriceChanged);
+
+        // Split the price: creator royalty and treasury fee first, the rest to the seller
+        let (creator, royalty_bps) = {
+            let post = ctx.accounts.post.load()?;
+            (post.creator, post.royalty_bps)
+        };
+        let royalty = (price as u128 * royalty_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let treasury_fee = (price as u128 * ctx.accounts.state.market_fee_bps as u128
+            / BPS_DENOMINATOR as u128) as u64;
+        let seller_proceeds = price
+            .checked_sub(royalty)
+            .and_then(|v| v.checked_sub(treasury_fee))
+            .ok_or(SentinelError::Overflow)?;
+
+        for (to, amount) in [
+            (ctx.accounts.seller_sentinel_ata.to_account_info(), seller_proceeds),
+            (ctx.accounts.creator_sentinel_ata.to_account_info(), royalty),
+            (ctx.accounts.treasury_sentinel_ata.to_account_info(), treasury_fee),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.buyer_sentinel_ata.to_account_info(),
+                    mint: ctx.accounts.sentinel_mint.to_account_info(),
+                    to,
+                    authority: ctx.accounts.buyer.to_account_info(),
+                },
+            );
+            token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.sentinel_mint.decimals)?;
+        }
+
+        let post_key = ctx.accounts.listing.post;
+        let signer_seeds: &[&[u8]] = &[LISTING_SEED, post_key.as_ref(), &[ctx.accounts.listing.bump]];
+        let signer = &[signer_seeds];
+        release_listing_escrow(
+            &ctx.accounts.listing,
+            &ctx.accounts.escrow_nft_ata,
+            &ctx.accounts.nft_mint,
+            ctx.accounts.buyer_nft_ata.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            &ctx.accounts.token_program,
+            signer,
+        )?;
+
+        // Sync the post to its new holder
+        let mut post = ctx.accounts.post.load_mut()?;
+        post.owner = ctx.accounts.buyer.key();
+
+        emit_cpi!(PostSold {
+            post: post_key,
+            seller: ctx.accounts.seller.key(),
+            buyer: post.owner,
+            creator,
+            price,
+            seller_proceeds,
+            royalty,
+            treasury_fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn finalize_cycle<'info>(
+        ctx: Context<'_, '_, '_, 'info, FinalizeCycle<'info>>, 
+        peers: Vec<Pubkey>, 
+        karmas: Vec<u64>
+    ) -> Result<()> {
+        // Committee co-signers lead the remaining accounts, reward ATAs follow
+        let cosigners = require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        let reward_atas = &ctx.remaining_accounts[cosigners..];
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now - ctx.accounts.state.cycle_start_ts >= ctx.accounts.state.cycle_seconds, 
+            SentinelError::CycleNotEnded
+        );
+        require!(peers.len() == karmas.len(), SentinelError::InvalidInput);
+        require!(
+            peers.len() == reward_atas.len(), 
+            SentinelError::InvalidInput
+        );
+
+        // Compute total karma
+        let total_karma: u128 = karmas.iter().map(|k| *k as u128).sum();
+
+        // Unlock the optional top-post bonus for the cycle being closed
+        if let Some(top_posts) = &ctx.accounts.top_posts {
+            let mut top_posts = top_posts.load_mut()?;
+            require!(
+                top_posts.cycle_index == ctx.accounts.state.cycle_index,
+                SentinelError::InvalidAccount
+            );
+            top_posts.bonus = ctx.accounts.state.top_post_bonus;
+            top_posts.finalized = 1;
+        }
+
+        let mut total_minted: u64 = 0;
+        let mut peers_rewarded: u32 = 0;
+        let mut top_recipient = Pubkey::default();
+        let mut top_reward: u64 = 0;
+
+        // Zero-karma entries are skipped below, so an all-zero cycle mints nothing
+        let reward_total = ctx.accounts.state.cycle_reward_total;
+
+        for (i, peer_pubkey) in peers.iter().enumerate() {
+            let karma = karmas[i] as u128;
+            if karma == 0 { 
+                continue; 
+            }
+            
+            let reward_u64 = reward_share(karma, total_karma, reward_total);
+
+            // Get peer's ATA from remaining_accounts
+            let ata_info = reward_atas
+                .get(i)
+                .ok_or(SentinelError::MissingAccount)?;
+
+            // Validate ATA is the canonical associated token address
+            let expected_ata = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+                peer_pubkey, 
+                &ctx.accounts.sentinel_mint.key(),
+                &ctx.accounts.token_program.key(),
+            );
+            require!(
+                *ata_info.key == expected_ata, 
+                SentinelError::InvalidAccount
+            );
+
+            // Mint reward to peer's ATA
+            mint_reward(
+                &ctx.accounts.state,
+                ctx.accounts.sentinel_mint.to_account_info(),
+                ata_info.clone(),
+                ctx.accounts.token_program.to_account_info(),
+                SekaMinter::from_accounts(
+                    &ctx.accounts.seka_config,
+                    &ctx.accounts.seka_mint_authority,
+                    &ctx.accounts.seka_program,
+                ),
+                reward_u64,
+            )?;
+
+            total_minted = total_minted
+                .checked_add(reward_u64)
+                .ok_or(SentinelError::Overflow)?;
+            peers_rewarded += 1;
+            if reward_u64 > top_reward {
+                top_reward = reward_u64;
+                top_recipient = *peer_pubkey;
+            }
+        }
+
+        // Persist the cycle summary
+        let record = &mut ctx.accounts.cycle_record;
+        record.cycle_index = ctx.accounts.state.cycle_index;
+        record.start_ts = ctx.accounts.state.cycle_start_ts;
+        record.end_ts = now;
+        record.total_karma = total_karma;
+        record.total_minted = total_minted;
+        record.peers_rewarded = peers_rewarded;
+        record.top_recipient = top_recipient;
+        record.top_reward = top_reward;
+        record.onchain_rewards = false;
+
+        emit_cpi!(CycleFinalized {
+            cycle_index: record.cycle_index,
+            total_karma,
+            total_minted,
+            peers_rewarded,
+            top_recipient,
+            top_reward,
+        });
+
+        // Advance cycle
+        ctx.accounts.state.cycle_start_ts = now;
+        ctx.accounts.state.cycle_karma = 0;
+        ctx.accounts.state.cycle_index = ctx.accounts.state
+            .cycle_index
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        Ok(())
+    }
+
+    pub fn reset_karma(ctx: Context<ResetKarma>) -> Result<()> {
+        require_admin(&ctx.accounts.state, &ctx.accounts.authority, ctx.remaining_accounts)?;
+        let mut peer = ctx.accounts.peer.load_mut()?;
+        record_karma_history(&ctx.accounts.karma_history, peer.karma_cycle, peer.karma)?;
+        peer.karma = 0;
+        peer.karma_cycle = ctx.accounts.state.cycle_index;
+        peer.karma_synced = 0;
+        Ok(())
+    }
+
+    /// Keyless cycle close for automation threads, once the authority has turned on
+    /// `crank_finalize`; until then cycles close only through the admin `finalize_cycle`.
+    ///
+    /// A no-op until the cycle has run `cycle_seconds`; then it records the on-chain karma
+    /// total and advances the cycle. Rewards are paid per peer by `crank_reset_karma`.
+    /// Returns the unix time of the next cycle end, when the thread should fire again.
+    pub fn crank_finalize_cycle(ctx: Context<CrankFinalizeCycle>) -> Result<i64> {
+        let now = Clock::get()?.unix_timestamp;
+        let state = &mut ctx.accounts.state;
+        let due_ts = state.cycle_start_ts + state.cycle_seconds;
+        if now < due_ts {
+            return Ok(due_ts);
+        }
+
+        if let Some(top_posts) = &ctx.accounts.top_posts {
+            let mut top_posts = top_posts.load_mut()?;
+            require!(
+                top_posts.cycle_index == state.cycle_index,
+                SentinelError::InvalidAccount
+            );
+            top_posts.bonus = state.top_post_bonus;
+            top_posts.finalized = 1;
+        }
+
+        let record = &mut ctx.accounts.cycle_record;
+        record.cycle_index = state.cycle_index;
+        record.start_ts = state.cycle_start_ts;
+        record.end_ts = now;
+        record.total_karma = state.cycle_karma as u128;
+        record.total_minted = 0;
+        record.peers_rewarded = 0;
+        record.top_recipient = Pubkey::default();
+        record.top_reward = 0;
+        record.onchain_rewards = true;
+
+        emit_cpi!(CycleFinalized {
+            cycle_index: record.cycle_index,
+            total_karma: record.total_karma,
+            total_minted: 0,
+            peers_rewarded: 0,
+            top_recipient: Pubkey::default(),
+            top_reward: 0,
+        });
+
+        state.cycle_start_ts = now;
+        state.cycle_karma = 0;
+        state.cycle_index = state
+            .cycle_index
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+
+        Ok(now + state.cycle_seconds)
+    }
+
+    /// Keyless per-peer settlement for automation threads.
+    ///
+    /// Once the peer's karma cycle has closed, pays its share out of the cycle record (when
+    /// the cycle was closed by `crank_finalize_cycle`) and resets the karma. Calling it
+    /// again before the next cycle closes does nothing. Returns the next cycle end.
+    pub fn crank_reset_karma(ctx: Context<CrankResetKarma>) -> Result<i64> {
+        let state = &ctx.accounts.state;
+        let next_ts = state.cycle_start_ts + state.cycle_seconds;
+        let mut peer = ctx.accounts.peer.load_mut()?;
+        if peer.karma_cycle >= state.cycle_index {
+            return Ok(next_ts);
+        }
+
+        let record = ctx
+            .accounts
+            .cycle_record
+            .as_mut()
+            .ok_or(SentinelError::MissingAccount)?;
+        let karma = peer.karma as u128;
+        if record.onchain_rewards && karma > 0 && record.total_karma > 0 {
+            let reward = reward_share(karma, record.total_karma, state.cycle_reward_total);
+
+            mint_reward(
+                state,
+                ctx.accounts.sentinel_mint.to_account_info(),
+                ctx.accounts.peer_sentinel_ata.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                SekaMinter::from_accounts(
+                    &ctx.accounts.seka_config,
+                    &ctx.accounts.seka_mint_authority,
+                    &ctx.accounts.seka_program,
+                ),
+                reward,
+            )?;
+
+            record.total_minted = record
+                .total_minted
+                .checked_add(reward)
+                .ok_or(SentinelError::Overflow)?;
+            record.peers_rewarded += 1;
+            if reward > record.top_reward {
+                record.top_reward = reward;
+                record.top_recipient = peer.user;
+            }
+        }
+
+        record_karma_history(&ctx.accounts.karma_history, peer.karma_cycle, peer.karma)?;
+        peer.karma = 0;
+        peer.karma_cycle = state.cycle_index;
+        peer.karma_synced = 0;
+
+        Ok(next_ts)
+    }
+
+    /// Credits the peer's karma not yet synced this karma cycle to its seka `PeerLedger`,
+    /// at `seka_points_per_karma` points per karma, via seka's `credit_points` issuer interface.
+    pub fn sync_karma_to_seka(ctx: Context<SyncKarmaToSeka>) -> Result<()> {
+        let ratio = ctx.accounts.state.seka_points_per_karma;
+        require!(ratio > 0, SentinelError::SekaSyncDisabled);
+
+        let (user, karma) = {
+            let mut peer = ctx.accounts.peer.load_mut()?;
+            let karma = peer.karma.saturating_sub(peer.karma_synced);
+            peer.karma_synced = peer.karma_synced.max(peer.karma);
+            (peer.user, karma)
+        };
+        require!(karma > 0, SentinelError::NothingToSync);
+        let points = karma.checked_mul(ratio).ok_or(SentinelError::Overflow)?;
+
+        credit_seka_points(&ctx, user, points)?;
+
+        emit_cpi!(KarmaSyncedToSeka {
+            user,
+            karma,
+            points,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Authorizes an admin instruction.
+///
+/// With no committee configured (`threshold == 0`) the signer must be `state.authority`,
+/// which may itself be a Squads/Realms PDA signing via CPI. Otherwise `threshold` distinct
+/// committee members must sign: `authority` plus any leading signer accounts in
+/// `remaining`. Returns how many leading remaining accounts were consumed as co-signers.
+fn require_admin(state: &State, authority: &Signer, remaining: &[AccountInfo]) -> Result<usize> {
+    if state.threshold == 0 {
+        require_keys_eq!(authority.key(), state.authority, SentinelError::Unauthorized);
+        return Ok(0);
+    }
+
+    let mut approvals: Vec<Pubkey> = Vec::with_capacity(MAX_COMMITTEE);
+    if state.committee.contains(authority.key) {
+        approvals.push(authority.key());
+    }
+    let mut consumed = 0;
+    for info in remaining.iter() {
+        if !info.is_signer || !state.committee.contains(info.key) {
+            break;
+        }
+        if !approvals.contains(info.key) {
+            approvals.push(*info.key);
+        }
+        consumed += 1;
+    }
+    require!(
+        approvals.len() >= state.threshold as usize,
+        SentinelError::Unauthorized
+    );
+    Ok(consumed)
+}
+
+fn activate_peer(
+    peer: &mut PeerState,
+    user: Pubkey,
+    cycle_index: u64,
+    join_paid: u64,
+    joined_with_sol: bool,
+) {
+    peer.user = user;
+    peer.active = 1;
+    peer.karma = 0;
+    peer.likes_cast = 0;
+    peer.likes_cycle = cycle_index;
+    peer.invites_issued = 0;
+    peer.join_paid = join_paid;
+    peer.joined_with_sol = joined_with_sol as u8;
+    peer.karma_cycle = cycle_index;
+    peer.karma_synced = 0;
+    peer.version = PEER_VERSION;
+}
+
+/// Tags are `[a-z0-9_-]` left-aligned and zero-padded, so one topic has one spelling.
+fn is_valid_tag(tag: &[u8; TAG_LEN]) -> bool {
+    let len = tag.iter().position(|b| *b == 0).unwrap_or(TAG_LEN);
+    tag[..len]
+        .iter()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'_' || *b == b'-')
+        && tag[len..].iter().all(|b| *b == 0)
+}
+
+/// Names are stored exactly as they are seeded: 3-32 chars of `[a-z0-9_]`.
+/// Clients lowercase before submitting, so `Alice` and `alice` cannot both exist.
+fn is_normalized_username(name: &str) -> bool {
+    (MIN_USERNAME_LEN..=MAX_USERNAME_LEN).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Charges one like against the liker's per-cycle budget and the per-(liker, author) cap.
+fn spend_like(
+    liker_peer: &mut PeerState,
+    pair: &mut LikePair,
+    liker: Pubkey,
+    author: Pubkey,
+    state: &State,
+) -> Result<()> {
+    // Per-cycle like budget, reset lazily on the first like of a new cycle
+    if liker_peer.likes_cycle != state.cycle_index {
+        liker_peer.likes_cycle = state.cycle_index;
+        liker_peer.likes_cast = 0;
+    }
+    require!(
+        liker_peer.likes_cast < state.max_likes_per_cycle,
+        SentinelError::LikeBudgetExceeded
+    );
+    liker_peer.likes_cast = liker_peer
+        .likes_cast
+        .checked_add(1)
+        .ok_or(SentinelError::Overflow)?;
+    liker_peer.likes_given = liker_peer
+        .likes_given
+        .checked_add(1)
+        .ok_or(SentinelError::Overflow)?;
+
+    // Per-(liker, author) cap to break reciprocal like rings
+    if pair.liker == Pubkey::default() {
+        pair.liker = liker;
+        pair.author = author;
+        pair.cycle_index = state.cycle_index;
+        pair.count = 0;
+    } else if pair.cycle_index != state.cycle_index {
+        pair.cycle_index = state.cycle_index;
+        pair.count = 0;
+    }
+    require!(
+        pair.count < state.max_likes_per_pair,
+        SentinelError::PairLikeLimitExceeded
+    );
+    pair.count = pair
+        .count
+        .checked_add(1)
+        .ok_or(SentinelError::Overflow)?;
+    Ok(())
+}
+
+/// Adds current-cycle karma to a peer and to the cycle total.
+fn credit_karma(peer: &mut PeerState, state: &mut State, amount: u64) -> Result<()> {
+    // Karma from a closed cycle must be paid out or reset before new karma lands on it
+    if peer.karma_cycle != state.cycle_index {
+        require!(peer.karma == 0, SentinelError::KarmaUnsettled);
+        peer.karma_cycle = state.cycle_index;
+        peer.karma_synced = 0;
+    }
+    peer.karma = peer
+        .karma
+        .checked_add(amount)
+        .ok_or(SentinelError::Overflow)?;
+    state.cycle_karma = state
+        .cycle_karma
+        .checked_add(amount)
+        .ok_or(SentinelError::Overflow)?;
+    Ok(())
+}
+
+/// Appends a settled cycle to the peer's history if the peer has created one.
+///
+/// `history` is seed-checked by the caller, so a crank cannot skip recording.
+fn record_karma_history(history: &AccountInfo, cycle_index: u64, karma: u64) -> Result<()> {
+    if karma == 0 || history.data_is_empty() {
+        return Ok(());
+    }
+    require!(*history.owner == crate::ID, SentinelError::InvalidAccount);
+    let mut data = history.try_borrow_mut_data()?;
+    require!(
+        data.len() >= 8 + KarmaHistory::SIZE && data[..8] == KarmaHistory::DISCRIMINATOR,
+        SentinelError::InvalidAccount
+    );
+    bytemuck::from_bytes_mut::<KarmaHistory>(&mut data[8..8 + KarmaHistory::SIZE])
+        .push(cycle_index, karma);
+    Ok(())
+}
+
+/// `transfer_checked` of the SENTINEL mint; a zero amount is a no-op.
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+            from,
+            mint: mint.to_account_info(),
+            to,
+            authority,
+        },
+        signer,
+    );
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
+/// Migrates any sentinel account but `State`, found by its discriminator. Returns the
+/// layout version it was at, the current one, and whether it grew.
+fn migrate_any<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(u8, u8, bool)> {
+    let discriminator: [u8; 8] = info
+        .try_borrow_data()?
+        .get(..8)
+        .and_then(|d| d.try_into().ok())
+        .ok_or(SentinelError::InvalidAccount)?;
+
+    macro_rules! dispatch {
+        (zero_copy: $($z:ty),*; borsh: $($b:ty),* $(,)?) => {
+            $(if discriminator == <$z>::DISCRIMINATOR {
+                return migrate_zero_copy::<$z>(info, payer, system_program);
+            })*
+            $(if discriminator == <$b>::DISCRIMINATOR {
+                return migrate_account::<$b>(info, payer, system_program);
+            })*
+        };
+    }
+    dispatch!(
+        zero_copy: PeerState, Post, KarmaHistory, TopPosts;
+        borsh: Vesting, TreasuryVault, PostIndex, AuthorIndex, UserPost, CycleRecord, Like,
+            TagCounter, TagKarma, Report, StorageProvider, PostCommit, Comment, Listing,
+            Username, Achievement, Badge, Invite, LikePair,
+    );
+    err!(SentinelError::InvalidAccount)
+}
+
+/// Grows a Borsh account to its current size and rewrites it at its current layout
+/// version; returns as `migrate_any` does.
+fn migrate_account<'info, T>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(u8, u8, bool)>
+where
+    T: AccountSerialize + AccountDeserialize + Versioned,
+{
+    let grew = grow_account(info, T::SPACE, payer, system_program)?;
+    let mut account = T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = account.version();
+    account.migrate();
+    account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok((from_version, T::VERSION, grew))
+}
+
+/// `migrate_account` for zero-copy accounts, migrated in place.
+fn migrate_zero_copy<'info, T>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(u8, u8, bool)>
+where
+    T: ZeroCopy + Versioned + BorshLegacy,
+{
+    // Growing keeps bytes where they are, so a Borsh-era account is read out first
+    let legacy = match T::LEGACY_LEN {
+        Some(len) if info.data_len() == len => Some(info.try_borrow_data()?[8..].to_vec()),
+        _ => None,
+    };
+    let grew = grow_account(info, T::SPACE, payer, system_program)?;
+    let mut data = info.try_borrow_mut_data()?;
+    let account = bytemuck::from_bytes_mut::<T>(&mut data[8..T::SPACE]);
+    if let Some(legacy) = legacy {
+        *account = T::zeroed();
+        account.read_legacy(&legacy)?;
+    }
+    let from_version = account.version();
+    account.migrate();
+    Ok((from_version, T::VERSION, grew))
+}
+
+/// Zero-copy accounts that started out as Borsh accounts, whose fields sat at other
+/// offsets than they do now.
+trait BorshLegacy: Sized {
+    /// Account length, discriminator included, of the Borsh layout; `None` for accounts
+    /// that were zero-copy from the start.
+    const LEGACY_LEN: Option<usize> = None;
+
+    /// Fills a zeroed account from `legacy`, the Borsh layout without its discriminator.
+    fn read_legacy(&mut self, _legacy: &[u8]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `PeerState` as first deployed.
+#[derive(AnchorDeserialize, AnchorSerialize)]
+struct LegacyPeerState {
+    user: Pubkey,
+    active: bool,
+    karma: u64,
+}
+
+impl BorshLegacy for PeerState {
+    const LEGACY_LEN: Option<usize> = Some(8 + 32 + 1 + 8);
+
+    fn read_legacy(&mut self, legacy: &[u8]) -> Result<()> {
+        let old = LegacyPeerState::deserialize(&mut &legacy[..])?;
+        self.user = old.user;
+        self.active = old.active as u8;
+        self.karma = old.karma;
+        Ok(())
+    }
+}
+
+/// `Post` as first deployed.
+#[derive(AnchorDeserialize, AnchorSerialize)]
+struct LegacyPost {
+    owner: Pubkey,
+    nft_mint: Pubkey,
+    hash: [u8; 32],
+    db_addr: Pubkey,
+    likes: u64,
+    cycle_index: u64,
+}
+
+impl BorshLegacy for Post {
+    const LEGACY_LEN: Option<usize> = Some(8 + 32 + 32 + 32 + 32 + 8 + 8);
+
+    /// `migrate` then moves `db_addr` into the content address.
+    fn read_legacy(&mut self, legacy: &[u8]) -> Result<()> {
+        let old = LegacyPost::deserialize(&mut &legacy[..])?;
+        self.owner = old.owner;
+        self.nft_mint = old.nft_mint;
+        self.hash = old.hash;
+        self.legacy_db_addr = old.db_addr.to_bytes();
+        self.likes = old.likes;
+        self.cycle_index = old.cycle_index;
+        // Royalties go to the creator, who for a post this old is its owner
+        self.creator = old.owner;
+        Ok(())
+    }
+}
+
+impl BorshLegacy for KarmaHistory {}
+impl BorshLegacy for TopPosts {}
+
+/// Reallocates a program account to at least `len` bytes, topping up rent from `payer`.
+/// New bytes are zeroed. Returns whether it grew.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    if info.data_len() >= len {
+        return Ok(false);
+    }
+    let shortfall = Rent::get()?.minimum_balance(len).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: payer.clone(),
+                to: info.clone(),
+            },
+        );
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+    info.realloc(len, true)?;
+    Ok(true)
+}
+
+/// Arguments shared by every way of minting a post.
+struct NewPost {
+    hash: [u8; 32],
+    content: ContentAddress,
+    soulbound: bool,
+    royalty_bps: u16,
+    tag: [u8; TAG_LEN],
+    committed_ts: i64,
+}
+
+/// Checks common to SPL and Core posts; counts the post towards its tag.
+fn check_new_post(
+    state: &State,
+    new_post: &NewPost,
+    tag_counter: Option<&mut Account<TagCounter>>,
+    storage_provider: Option<&Account<StorageProvider>>,
+    instructions: Option<&UncheckedAccount>,
+) -> Result<()> {
+    require!(new_post.royalty_bps <= MAX_ROYALTY_BPS, SentinelError::InvalidInput);
+    new_post.content.validate()?;
+
+    // Tagged posts count towards their topic; an all-zero tag means untagged
+    if new_post.tag != [0; TAG_LEN] {
+        let tag_counter = tag_counter.ok_or(SentinelError::MissingAccount)?;
+        require!(tag_counter.tag == new_post.tag, SentinelError::InvalidTag);
+        tag_counter.posts = tag_counter
+            .posts
+            .checked_add(1)
+            .ok_or(SentinelError::Overflow)?;
+    }
+
+    if state.require_storage_attestation {
+        let provider = storage_provider.ok_or(SentinelError::MissingAccount)?;
+        let ix_sysvar = instructions.ok_or(SentinelError::MissingAccount)?;
+        verify_storage_attestation(ix_sysvar, &provider.provider, &new_post.hash, &new_post.content)?;
+    }
+    Ok(())
+}
+
+/// Fills a freshly created post and assigns its global and per-author ids.
+fn init_post(
+    post: &mut Post,
+    state: &mut State,
+    author_index: &mut AuthorIndex,
+    owner: Pubkey,
+    asset: Pubkey,
+    asset_kind: PostAsset,
+    new_post: &NewPost,
+) -> Result<()> {
+    // zero-initialized fields (likes, history, flags) start cleared
+    post.owner = owner;
+    post.nft_mint = asset;
+    post.asset_kind = asset_kind as u8;
+    post.hash = new_post.hash;
+    post.set_content(&new_post.content);
+    post.cycle_index = state.cycle_index;
+    post.soulbound = new_post.soulbound as u8;
+    post.bond = state.post_bond;
+    post.bond_escrow_open = 1;
+    post.creator = owner;
+    post.royalty_bps = new_post.royalty_bps;
+    post.version = POST_VERSION;
+    post.committed_ts = new_post.committed_ts;
+    post.tag = new_post.tag;
+    post.post_id = state.post_count;
+    state.post_count = state
+        .post_count
+        .checked_add(1)
+        .ok_or(SentinelError::Overflow)?;
+
+    // Per-author index: (owner, n) -> post. The counter outlives the peer account so
+    // leaving and rejoining never reuses an index.
+    author_index.owner = owner;
+    post.author_index = author_index.posts_created;
+    author_index.posts_created = author_index
+        .posts_created
+        .checked_add(1)
+        .ok_or(SentinelError::Overflow)?;
+    Ok(())
+}
+
+/// Shared body of `mint_nft` and `reveal_post`.
+fn mint_post(ctx: Context<MintNft>, new_post: NewPost) -> Result<()> {
+    require!(ctx.accounts.peer.load()?.active != 0, SentinelError::NotPeer);
+    check_new_post(
+        &ctx.accounts.state,
+        &new_post,
+        ctx.accounts.tag_counter.as_mut(),
+        ctx.accounts.storage_provider.as_ref(),
+        ctx.accounts.instructions.as_ref(),
+    )?;
+
+    // Mint the NFT (1 token of a new mint with 0 decimals) to user
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        MintTo {
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.user_nft_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        },
+    );
+    token_interface::mint_to(cpi_ctx, 1)?;
+
+    // Lock the posting bond in the per-post escrow
+    transfer_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.sentinel_mint,
+        ctx.accounts.user_sentinel_ata.to_account_info(),
+        ctx.accounts.bond_escrow.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+        &[],
+        ctx.accounts.state.post_bond,
+    )?;
+
+    // Soulbound posts are frozen in the creator's ATA using the state freeze authority
+    if new_post.soulbound {
+        require!(
+            ctx.accounts.nft_mint.freeze_authority == COption::Some(ctx.accounts.state.key()),
+            SentinelError::InvalidNftMint
+        );
+        let signer_seeds: &[&[u8]] = &[STATE_SEED, &[ctx.accounts.state.bump]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: ctx.accounts.user_nft_ata.to_account_info(),
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::freeze_account(cpi_ctx)?;
+    }
+
+    init_post(
+        &mut *ctx.accounts.post.load_init()?,
+        &mut ctx.accounts.state,
+        &mut ctx.accounts.author_index,
+        ctx.accounts.user.key(),
+        ctx.accounts.nft_mint.key(),
+        PostAsset::SplMint,
+        &new_post,
+    )?;
+    ctx.accounts.user_post.post = ctx.accounts.post.key();
+
+    Ok(())
+}
+
+/// Creates a Metaplex Core asset owned by `owner` with the state PDA as update authority
+/// and as freeze / transfer delegate, so moderation works without a token account.
+/// Hand-encoded `CreateV1`, as mpl-core is not a dependency.
+fn create_core_asset<'info>(
+    ctx: &Context<MintCorePost<'info>>,
+    name: String,
+    uri: String,
+    frozen: bool,
+) -> Result<()> {
+    let state = ctx.accounts.state.key();
+    let core_program = ctx.accounts.core_program.key();
+
+    let mut data = vec![CORE_CREATE_V1];
+    data.push(0); // DataState::AccountState
+    name.serialize(&mut data)?;
+    uri.serialize(&mut data)?;
+    // Some(vec![FreezeDelegate { frozen }, TransferDelegate {}]), both delegated to state
+    data.push(1);
+    2u32.serialize(&mut data)?;
+    for plugin in [vec![CORE_PLUGIN_FREEZE_DELEGATE, frozen as u8], vec![CORE_PLUGIN_TRANSFER_DELEGATE]] {
+        data.extend_from_slice(&plugin);
+        data.extend_from_slice(&[1, CORE_AUTHORITY_ADDRESS]);
+        data.extend_from_slice(state.as_ref());
+    }
+
+    // Unused optional accounts are passed as the Core program id
+    let ix = Instruction {
+        program_id: core_program,
+        accounts: vec![
+            AccountMeta::new(ctx.accounts.asset.key(), true),
+            AccountMeta::new_readonly(core_program, false),
+            AccountMeta::new_readonly(ctx.accounts.user.key(), true),
+            AccountMeta::new(ctx.accounts.user.key(), true),
+            AccountMeta::new_readonly(ctx.accounts.user.key(), false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            AccountMeta::new_readonly(core_program, false),
+        ],
+        data,
+    };
+    invoke(
+        &ix,
+        &[
+            ctx.accounts.asset.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.core_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Burns an SPL post's NFT from the user's ATA, thawing a soulbound one first.
+fn burn_spl_nft(ctx: &Context<DeletePost>, soulbound: bool) -> Result<()> {
+    let missing = || error!(SentinelError::MissingAccount);
+    let nft_mint = ctx.accounts.nft_mint.as_ref().ok_or_else(missing)?;
+    let user_nft_ata = ctx.accounts.user_nft_ata.as_ref().ok_or_else(missing)?;
+    let token_program = ctx.accounts.token_program.as_ref().ok_or_else(missing)?;
+
+    // Frozen soulbound NFTs must be thawed before they can be burned
+    if soulbound {
+        let signer_seeds: &[&[u8]] = &[STATE_SEED, &[ctx.accounts.state.bump]];
+        let signer = &[signer_seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            ThawAccount {
+                account: user_nft_ata.to_account_info(),
+                mint: nft_mint.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::thaw_account(cpi_ctx)?;
+    }
+
+    let cpi_ctx = CpiContext::new(
+        token_program.to_account_info(),
+        Burn {
+            mint: nft_mint.to_account_info(),
+            from: user_nft_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        },
+    );
+    token_interface::burn(cpi_ctx, 1)
+}
+
+/// Burns a Core post's asset as its owner, first thawing a soulbound one through the
+/// state's freeze delegate. Hand-encoded `UpdatePluginV1` and `BurnV1`, like `CreateV1`.
+fn burn_core_asset(ctx: &Context<DeletePost>, soulbound: bool) -> Result<()> {
+    let missing = || error!(SentinelError::MissingAccount);
+    let asset = ctx.accounts.asset.as_ref().ok_or_else(missing)?;
+    let core_program = ctx.accounts.core_program.as_ref().ok_or_else(missing)?;
+    let system_program = ctx.accounts.system_program.as_ref().ok_or_else(missing)?;
+    let user = ctx.accounts.user.key();
+    let core = core_program.key();
+
+    // Unused optional accounts (collection, log wrapper) are passed as the Core program id
+    if soulbound {
+        let state = ctx.accounts.state.key();
+        // UpdatePluginV1 { plugin: FreezeDelegate { frozen: false } }
+        let ix = Instruction {
+            program_id: core,
+            accounts: vec![
+                AccountMeta::new(asset.key(), false),
+                AccountMeta::new_readonly(core, false),
+                AccountMeta::new(user, true),
+                AccountMeta::new_readonly(state, true),
+                AccountMeta::new_readonly(system_program.key(), false),
+                AccountMeta::new_readonly(core, false),
+            ],
+            data: vec![CORE_UPDATE_PLUGIN_V1, CORE_PLUGIN_FREEZE_DELEGATE, 0],
+        };
+        let signer_seeds: &[&[u8]] = &[STATE_SEED, &[ctx.accounts.state.bump]];
+        invoke_signed(
+            &ix,
+            &[
+                asset.to_account_info(),
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.state.to_account_info(),
+                system_program.to_account_info(),
+                core_program.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
+    }
+
+    // BurnV1 { compression_proof: None }; the asset's rent goes back to the user
+    let ix = Instruction {
+        program_id: core,
+        accounts: vec![
+            AccountMeta::new(asset.key(), false),
+            AccountMeta::new_readonly(core, false),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(user, true),
+            AccountMeta::new_readonly(system_program.key(), false),
+            AccountMeta::new_readonly(core, false),
+        ],
+        data: vec![CORE_BURN_V1, 0],
+    };
+    invoke(
+        &ix,
+        &[
+            asset.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            system_program.to_account_info(),
+            core_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Checks that the instruction right before this one is an ed25519 signature check by
+/// `provider` over `hash || scheme || addr`.
+fn verify_storage_attestation(
+    ix_sysvar: &AccountInfo,
+    provider: &Pubkey,
+    hash: &[u8; 32],
+    content: &ContentAddress,
+) -> Result<()> {
+    let current = load_current_index_checked(ix_sysvar)?;
+    require!(current > 0, SentinelError::InvalidAttestation);
+    let ix = load_instruction_at_checked(current as usize - 1, ix_sysvar)?;
+    require!(ix.program_id == ed25519_program::ID, SentinelError::InvalidAttestation);
+
+    // Ed25519 layout: [count u8, padding u8, offsets (7 x u16)], then the referenced data.
+    // Every offset must point into this same instruction (index u16::MAX).
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, SentinelError::InvalidAttestation);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let pubkey_offset = read_u16(6) as usize;
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    require!(
+        read_u16(4) == u16::MAX && read_u16(8) == u16::MAX && read_u16(14) == u16::MAX,
+        SentinelError::InvalidAttestation
+    );
+
+    let signer = data
+        .get(pubkey_offset..pubkey_offset + 32)
+        .ok_or(SentinelError::InvalidAttestation)?;
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(SentinelError::InvalidAttestation)?;
+    require!(signer == provider.as_ref(), SentinelError::InvalidAttestation);
+    require!(
+        message.len() == 32 + 1 + content.addr.len()
+            && message[..32] == hash[..]
+            && message[32] == content.scheme as u8
+            && message[33..] == content.addr[..],
+        SentinelError::InvalidAttestation
+    );
+    Ok(())
+}
+
+/// Rejects Token-2022 mint extensions outside `ALLOWED_MINT_EXTENSIONS`.
+///
+/// Extensions such as a permanent delegate, transfer hook or default-frozen accounts would
+/// let whoever created the mint move or lock balances the program relies on. A transfer
+/// fee is only accepted when both its config and withdraw authorities are `fee_authority`,
+/// so withheld fees can only ever be harvested into the treasury.
+fn check_mint_extensions(mint: &AccountInfo, fee_authority: &Pubkey) -> Result<()> {
+    if mint.owner != &token_2022::ID {
+        return Ok(());
+    }
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in mint_state.get_extension_types()? {
+        require!(
+            ALLOWED_MINT_EXTENSIONS.contains(&extension),
+            SentinelError::UnsupportedMintExtension
+        );
+    }
+    if let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() {
+        let config_authority: Option<Pubkey> = fee_config.transfer_fee_config_authority.into();
+        let withdraw_authority: Option<Pubkey> = fee_config.withdraw_withheld_authority.into();
+        require!(
+            config_authority == Some(*fee_authority) && withdraw_authority == Some(*fee_authority),
+            SentinelError::InvalidFeeAuthority
+        );
+    }
+    Ok(())
+}
+
+/// CPI into seka's `credit_points` with the state PDA signing as the configured issuer.
+fn credit_seka_points(ctx: &Context<SyncKarmaToSeka>, owner: Pubkey, points: u64) -> Result<()> {
+    let mut data = hash(b"global:credit_points").to_bytes()[..8].to_vec();
+    owner.serialize(&mut data)?;
+    points.serialize(&mut data)?;
+
+    let ix = Instruction {
+        program_id: ctx.accounts.seka_program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(ctx.accounts.seka_config.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.state.key(), true),
+            AccountMeta::new(ctx.accounts.seka_ledger.key(), false),
+            AccountMeta::new(ctx.accounts.payer.key(), true),
+            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+        ],
+        data,
+    };
+    let signer_seeds: &[&[u8]] = &[STATE_SEED, &[ctx.accounts.state.bump]];
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.seka_config.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            ctx.accounts.seka_ledger.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.seka_program.to_account_info(),
+        ],
+        &[signer_seeds],
+    )?;
+    Ok(())
+}
+
+/// seka accounts needed to mint rewards in shared-mint mode.
+pub struct SekaMinter<'info> {
+    pub config: AccountInfo<'info>,
+    pub mint_authority: AccountInfo<'info>,
+    pub program: AccountInfo<'info>,
+}
+
+impl<'info> SekaMinter<'info> {
+    fn from_accounts(
+        config: &Option<UncheckedAccount<'info>>,
+        mint_authority: &Option<UncheckedAccount<'info>>,
+        program: &Option<UncheckedAccount<'info>>,
+    ) -> Option<Self> {
+        Some(Self {
+            config: config.as_ref()?.to_account_info(),
+            mint_authority: mint_authority.as_ref()?.to_account_info(),
+            program: program.as_ref()?.to_account_info(),
+        })
+    }
+}
+
+/// A peer's cut of a reward pool: `karma * reward_total / total_karma`, capped at
+/// `MAX_PEER_REWARD_PCT` of the pool. Callers skip zero karma, so `total_karma > 0`.
+fn reward_share(karma: u128, total_karma: u128, reward_total: u64) -> u64 {
+    let reward_total = reward_total as u128;
+    let cap = reward_total * MAX_PEER_REWARD_PCT as u128 / 100;
+    // The cap keeps it within `reward_total`, so it fits in a u64
+    (karma * reward_total / total_karma).min(cap) as u64
+}
+
+/// Mint `amount` reward tokens to `to`.
+///
+/// Sentinel's own mint is minted with the state PDA as mint authority. In shared-mint mode
+/// the mint belongs to seka, so the state PDA asks seka's `issue_tokens` to mint instead.
+fn mint_reward<'info>(
+    state: &Account<'info, State>,
+    mint: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    seka: Option<SekaMinter<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let signer_seeds: &[&[u8]] = &[STATE_SEED, &[state.bump]];
+    let signer = &[signer_seeds];
+    if !state.shared_mint {
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program,
+            MintTo {
+                mint,
+                to,
+                authority: state.to_account_info(),
+            },
+            signer,
+        );
+        return token_interface::mint_to(cpi_ctx, amount);
+    }
+
+    let seka = seka.ok_or(SentinelError::MissingAccount)?;
+    require!(seka.program.key() == state.seka_program, SentinelError::InvalidAccount);
+
+    let mut data = hash(b"global:issue_tokens").to_bytes()[..8].to_vec();
+    amount.serialize(&mut data)?;
+    let ix = Instruction {
+        program_id: state.seka_program,
+        accounts: vec![
+            AccountMeta::new_readonly(seka.config.key(), false),
+            AccountMeta::new_readonly(state.key(), true),
+            AccountMeta::new(mint.key(), false),
+            AccountMeta::new_readonly(seka.mint_authority.key(), false),
+            AccountMeta::new(to.key(), false),
+            AccountMeta::new_readonly(token_program.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            seka.config,
+            state.to_account_info(),
+            mint,
+            seka.mint_authority,
+            to,
+            token_program,
+            seka.program,
+        ],
+        signer,
+    )?;
+    Ok(())
+}
+
+/// Send the escrowed post NFT to `to` and close the escrow ATA to the seller.
+fn release_listing_escrow<'info>(
+    listing: &Account<'info, Listing>,
+    escrow_nft_ata: &InterfaceAccount<'info, TokenAccount>,
+    nft_mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    seller: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: escrow_nft_ata.to_account_info(),
+            mint: nft_mint.to_account_info(),
+            to,
+            authority: listing.to_account_info(),
+        },
+        signer,
+    );
+    token_interface::transfer_checked(cpi_ctx, 1, 0)?;
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow_nft_ata.to_account_info(),
+            destination: seller,
+            authority: listing.to_account_info(),
+        },
+        signer,
+    );
+    token_interface::close_account(cpi_ctx)
+}
+
+/// Move the whole escrowed bond to `destination` and close the escrow to the post owner.
+fn settle_bond(ctx: &Context<SettleBond>) -> Result<()> {
+    let nft_mint = ctx.accounts.post.load()?.nft_mint;
+    let signer_seeds: &[&[u8]] = &[POST_SEED, nft_mint.as_ref(), &[ctx.bumps.post]];
+    let signer = &[signer_seeds];
+
+    let amount = ctx.accounts.bond_escrow.amount;
+    if amount > 0 {
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.bond_escrow.to_account_info(),
+                mint: ctx.accounts.sentinel_mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.post.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.sentinel_mint.decimals)?;
+    }
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.bond_escrow.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.post.to_account_info(),
+        },
+        signer,
+    );
+    token_interface::close_account(cpi_ctx)
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [STATE_SEED],
+        bump,
+        space = 8 + State::SIZE,
+    )]
+    pub state: Account<'info, State>,
+
+    /// Created beforehand under the Token or Token-2022 program; Token-2022 deployments
+    /// may add metadata and transfer-fee extensions when creating it.
+    #[account(
+        mut,
+        mint::decimals = SENTINEL_DECIMALS,
+        mint::authority = authority,
+        mint::freeze_authority = state,
+        mint::token_program = token_program,
+        constraint = sentinel_mint.supply == 0 @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+        space = 8 + TreasuryVault::SIZE,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [VESTING_SEED],
+        bump,
+        space = 8 + Vesting::SIZE,
+    )]
+    pub vesting: Option<Account<'info, Vesting>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = sentinel_mint,
+        associated_token::authority = vesting,
+    )]
+    pub vesting_sentinel_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = sentinel_mint,
+        associated_token::authority = treasury_vault,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSharedMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [STATE_SEED],
+        bump,
+        space = 8 + State::SIZE,
+    )]
+    pub state: Account<'info, State>,
+
+    /// The SEKA mint, whose mint authority stays with seka
+    #[account(
+        mint::decimals = SENTINEL_DECIMALS,
+        mint::token_program = token_program,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+        space = 8 + TreasuryVault::SIZE,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = sentinel_mint,
+        associated_token::authority = treasury_vault,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: the seka program that owns the mint authority
+    #[account(executable)]
+    pub seka_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimVestedSupply<'info> {
+    pub beneficiary: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [VESTING_SEED],
+        bump = vesting.bump,
+        constraint = vesting.beneficiary == beneficiary.key() @ SentinelError::Unauthorized,
+    )]
+    pub vesting: Account<'info, Vesting>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = sentinel_mint,
+        associated_token::authority = vesting,
+    )]
+    pub vesting_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = beneficiary_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = beneficiary_sentinel_ata.owner == beneficiary.key() @ SentinelError::InvalidAccount,
+    )]
+    pub beneficiary_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DistributeInitialSupply<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// What `Post::nft_mint` refers to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PostAsset {
+    /// SPL / Token-2022 mint with the NFT held in an ATA
+    SplMint = 0,
+    /// Metaplex Core asset account; marketplace and owner sync are SPL-only for now,
+    /// deleting burns it through Core
+    CoreAsset = 1,
+}
+
+/// Addressing scheme of a post's off-chain content.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ContentScheme {
+    /// 32-byte `db_addr` carried over from pre-v3 posts; not accepted for new content
+    Legacy = 0,
+    /// Binary CIDv1
+    Ipfs = 1,
+    /// Raw 32-byte Arweave transaction id
+    Arweave = 2,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ContentAddress {
+    pub scheme: ContentScheme,
+    pub addr: Vec<u8>,
+}
+
+impl ContentAddress {
+    pub fn validate(&self) -> Result<()> {
+        let addr = &self.addr;
+        let valid = match self.scheme {
+            ContentScheme::Legacy => false,
+            // <version 0x01><codec><multihash code><digest len><digest>, all single-byte varints
+            ContentScheme::Ipfs => {
+                addr.len() > 4
+                    && addr.len() <= MAX_CONTENT_ADDR_LEN
+                    && addr[0] == 0x01
+                    && addr[1] < 0x80
+                    && addr[2] < 0x80
+                    && addr[3] as usize == addr.len() - 4
+            }
+            ContentScheme::Arweave => addr.len() == ARWEAVE_TX_ID_LEN,
+        };
+        require!(valid, SentinelError::InvalidContentAddress);
+        Ok(())
+    }
+}
+
+/// What a `Like` account points at; `Like::post` holds the target's address.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LikeTarget {
+    Post,
+    Comment,
+}
+
+/// Lifetime counter an achievement is measured against.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AchievementMetric {
+    KarmaEarned,
+    PostsCreated,
+    LikesGiven,
+}
+
+/// Where the initial supply is minted at `initialize`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Genesis {
+    /// Locked in the vesting PDA and released to the authority over time.
+    Vested { cliff_seconds: i64, duration_seconds: i64 },
+    /// Held by the treasury and paid out through `distribute_initial_supply`.
+    Treasury,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ParamsUpdate {
+    pub max_likes_per_cycle: Option<u32>,
+    pub max_likes_per_pair: Option<u32>,
+    pub join_burn_bps: Option<u16>,
+    pub leave_refund_bps: Option<u16>,
+    pub moderator: Option<Pubkey>,
+    pub post_bond: Option<u64>,
+    pub invite_only: Option<bool>,
+    pub invites_per_peer: Option<u32>,
+    pub join_price_lamports: Option<u64>,
+    pub join_cost_base: Option<u64>,
+    pub join_cost_step: Option<u64>,
+    pub join_cost_step_size: Option<u64>,
+    pub top_post_bonus: Option<u64>,
+    pub like_reward_window: Option<u64>,
+    pub seka_program: Option<Pubkey>,
+    pub seka_points_per_karma: Option<u64>,
+    pub cycle_seconds: Option<i64>,
+    pub cycle_reward_total: Option<u64>,
+    pub market_fee_bps: Option<u16>,
+    pub username_fee: Option<u64>,
+    pub comment_like_bps: Option<u16>,
+    pub require_storage_attestation: Option<bool>,
+    pub report_bond: Option<u64>,
+    pub report_bounty_bps: Option<u16>,
+    pub report_bounty: Option<u64>,
+    pub core_program: Option<Pubkey>,
+    pub crank_finalize: Option<bool>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateAccounts<'info> {
+    /// May be a governance PDA signing through a proposal, so it pays for nothing
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may still have an older layout; owner and contents checked in the handler
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump,
+    )]
+    pub state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetCommittee<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct NominateAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        address = state.pending_authority @ SentinelError::Unauthorized
+    )]
+    pub pending_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TreasuryWithdraw<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BurnFromTreasury<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTransferFee<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(address = token_2022::ID @ SentinelError::InvalidAccount)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct HarvestTransferFees<'info> {
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = token_2022::ID @ SentinelError::InvalidAccount)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(provider: Pubkey)]
+pub struct AddStorageProvider<'info> {
+    /// May be a governance PDA signing through a proposal, so it pays for nothing
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [STORAGE_PROVIDER_SEED, provider.as_ref()],
+        bump,
+        space = 8 + StorageProvider::SIZE,
+    )]
+    pub storage_provider: Account<'info, StorageProvider>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveStorageProvider<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: receives the registry rent
+    #[account(mut)]
+    pub rent_recipient: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [STORAGE_PROVIDER_SEED, storage_provider.provider.as_ref()],
+        bump = storage_provider.bump,
+        close = rent_recipient,
+    )]
+    pub storage_provider: Account<'info, StorageProvider>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(invitee: Pubkey)]
+pub struct CreateInvite<'info> {
+    #[account(mut)]
+    pub inviter: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, inviter.key().as_ref()],
+        bump,
+        constraint = inviter_peer.load()?.user == inviter.key() @ SentinelError::InvalidAccount,
+    )]
+    pub inviter_peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        init,
+        payer = inviter,
+        seeds = [INVITE_SEED, invitee.as_ref()],
+        bump,
+        space = 8 + Invite::SIZE,
+    )]
+    pub invite: Account<'info, Invite>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct JoinNetwork<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        space = 8 + PeerState::SIZE,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        constraint = user_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = user_sentinel_ata.owner == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub user_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [INVITE_SEED, user.key().as_ref()],
+        bump,
+        constraint = invite.invitee == user.key() @ SentinelError::InvalidAccount,
+        close = user,
+    )]
+    pub invite: Option<Account<'info, Invite>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct JoinNetworkWithSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        space = 8 + PeerState::SIZE,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [INVITE_SEED, user.key().as_ref()],
+        bump,
+        constraint = invite.invitee == user.key() @ SentinelError::InvalidAccount,
+        close = user,
+    )]
+    pub invite: Option<Account<'info, Invite>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawSol<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// CHECK: any lamport destination chosen by the authority
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LeaveNetwork<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+        close = user,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        constraint = user_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = user_sentinel_ata.owner == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub user_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterUsername<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [USERNAME_SEED, name.as_bytes()],
+        bump,
+        space = 8 + Username::SIZE,
+    )]
+    pub username: Account<'info, Username>,
+
+    #[account(
+        mut,
+        constraint = user_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = user_sentinel_ata.owner == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub user_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseUsername<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        seeds = [USERNAME_SEED, username.name.as_bytes()],
+        bump = username.bump,
+        constraint = username.owner == user.key() @ SentinelError::Unauthorized,
+        close = user,
+    )]
+    pub username: Account<'info, Username>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(id: u32)]
+pub struct CreateAchievement<'info> {
+    /// May be a governance PDA signing through a proposal, so it pays for nothing
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [ACHIEVEMENT_SEED, id.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Achievement::SIZE,
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimAchievement<'info> {
+    /// Anyone may crank a claim; the badge always goes to the peer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [ACHIEVEMENT_SEED, achievement.id.to_le_bytes().as_ref()],
+        bump = achievement.bump,
+    )]
+    pub achievement: Account<'info, Achievement>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    /// Only needed for `PostsCreated`; a peer that never posted has none
+    #[account(
+        seeds = [AUTHOR_INDEX_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub author_index: Option<Account<'info, AuthorIndex>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [BADGE_SEED, achievement.key().as_ref(), peer.load()?.user.as_ref()],
+        bump,
+        space = 8 + Badge::SIZE,
+    )]
+    pub badge: Account<'info, Badge>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintNft<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        constraint = nft_mint.decimals == 0 @ SentinelError::InvalidNftMint,
+        constraint = nft_mint.mint_authority == COption::Some(user.key()) @ SentinelError::InvalidNftMint,
+    )]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = nft_mint,
+        associated_token::authority = user,
+    )]
+    pub user_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [POST_SEED, nft_mint.key().as_ref()],
+        bump,
+        space = 8 + Post::SIZE,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [AUTHOR_INDEX_SEED, user.key().as_ref()],
+        bump,
+        space = 8 + AuthorIndex::SIZE,
+    )]
+    pub author_index: Account<'info, AuthorIndex>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [
+            USER_POST_SEED,
+            user.key().as_ref(),
+            author_index.posts_created.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = 8 + UserPost::SIZE,
+    )]
+    pub user_post: Account<'info, UserPost>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = user_sentinel_ata.owner == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub user_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [BOND_SEED, post.key().as_ref()],
+        bump,
+        token::mint = sentinel_mint,
+        token::authority = post,
+    )]
+    pub bond_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// Only for `reveal_post`; consumed by the reveal
+    #[account(
+        mut,
+        seeds = [POST_COMMIT_SEED, user.key().as_ref(), post_commit.commitment.as_ref()],
+        bump = post_commit.bump,
+        close = user,
+    )]
+    pub post_commit: Option<Account<'info, PostCommit>>,
+
+    /// Registered provider attesting to `(hash, content)`; required while
+    /// `state.require_storage_attestation` is set
+    #[account(
+        seeds = [STORAGE_PROVIDER_SEED, storage_provider.provider.as_ref()],
+        bump = storage_provider.bump,
+    )]
+    pub storage_provider: Option<Account<'info, StorageProvider>>,
+
+    /// CHECK: instructions sysvar, read for the provider's ed25519 signature check
+    #[account(
+        address = instructions_sysvar::ID @ SentinelError::InvalidAccount,
+    )]
+    pub instructions: Option<UncheckedAccount<'info>>,
+
+    /// Counter of the post's tag; required for tagged posts
+    #[account(
+        mut,
+        seeds = [TAG_SEED, tag_counter.tag.as_ref()],
+        bump = tag_counter.bump,
+    )]
+    pub tag_counter: Option<Account<'info, TagCounter>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MintCorePost<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    /// Fresh keypair for the Core asset; created by the Core program
+    #[account(mut)]
+    pub asset: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [POST_SEED, asset.key().as_ref()],
+        bump,
+        space = 8 + Post::SIZE,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [AUTHOR_INDEX_SEED, user.key().as_ref()],
+        bump,
+        space = 8 + AuthorIndex::SIZE,
+    )]
+    pub author_index: Account<'info, AuthorIndex>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [
+            USER_POST_SEED,
+            user.key().as_ref(),
+            author_index.posts_created.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = 8 + UserPost::SIZE,
+    )]
+    pub user_post: Account<'info, UserPost>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = user_sentinel_ata.owner == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub user_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [BOND_SEED, post.key().as_ref()],
+        bump,
+        token::mint = sentinel_mint,
+        token::authority = post,
+    )]
+    pub bond_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [STORAGE_PROVIDER_SEED, storage_provider.provider.as_ref()],
+        bump = storage_provider.bump,
+    )]
+    pub storage_provider: Option<Account<'info, StorageProvider>>,
+
+    /// CHECK: instructions sysvar, read for the provider's ed25519 signature check
+    #[account(
+        address = instructions_sysvar::ID @ SentinelError::InvalidAccount,
+    )]
+    pub instructions: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        seeds = [TAG_SEED, tag_counter.tag.as_ref()],
+        bump = tag_counter.bump,
+    )]
+    pub tag_counter: Option<Account<'info, TagCounter>>,
+
+    /// CHECK: Metaplex Core program; checked against `state.core_program`
+    #[account(
+        executable,
+        address = state.core_program @ SentinelError::InvalidAccount,
+    )]
+    pub core_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32])]
+pub struct CommitPost<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [POST_COMMIT_SEED, user.key().as_ref(), commitment.as_ref()],
+        bump,
+        space = 8 + PostCommit::SIZE,
+    )]
+    pub post_commit: Account<'info, PostCommit>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPostCommit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POST_COMMIT_SEED, user.key().as_ref(), post_commit.commitment.as_ref()],
+        bump = post_commit.bump,
+        close = user,
+    )]
+    pub post_commit: Account<'info, PostCommit>,
+}
+
+#[derive(Accounts)]
+#[instruction(post_id: u64)]
+pub struct CreatePostIndex<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [POST_INDEX_SEED, post_id.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + PostIndex::SIZE,
+    )]
+    pub post_index: Account<'info, PostIndex>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleBond<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        mut,
+        seeds = [BOND_SEED, post.key().as_ref()],
+        bump,
+    )]
+    pub bond_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: receives the escrow rent, must be the post owner
+    #[account(
+        mut,
+        address = post.load()?.owner @ SentinelError::InvalidAccount,
+    )]
+    pub owner: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdatePost<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+        constraint = post.load()?.owner == user.key() @ SentinelError::Unauthorized,
+    )]
+    pub post: AccountLoader<'info, Post>,
+}
+
+#[derive(Accounts)]
+pub struct LikeNft<'info> {
+    #[account(mut)]
+    pub liker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = liker,
+        seeds = [LIKE_SEED, liker.key().as_ref(), post.key().as_ref()],
+        bump,
+        space = 8 + Like::SIZE,
+    )]
+    pub like: Account<'info, Like>,
+
+    #[account(mut)]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        init_if_needed,
+        payer = liker,
+        seeds = [LIKE_PAIR_SEED, liker.key().as_ref(), post.load()?.owner.as_ref()],
+        bump,
+        space = 8 + LikePair::SIZE,
+    )]
+    pub like_pair: Account<'info, LikePair>,
+
+    #[account(
+        mut,
+        seeds = [TOP_POSTS_SEED, state.cycle_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub top_posts: Option<AccountLoader<'info, TopPosts>>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, post.load()?.owner.as_ref()],
+        bump,
+        constraint = liked_peer.load()?.user == post.load()?.owner @ SentinelError::InvalidAccount,
+    )]
+    pub liked_peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, liker.key().as_ref()],
+        bump,
+        constraint = liker_peer.load()?.user == liker.key() @ SentinelError::InvalidAccount,
+        constraint = liker_peer.load()?.active != 0 @ SentinelError::NotPeer,
+    )]
+    pub liker_peer: AccountLoader<'info, PeerState>,
+
+    /// Counter of the post's tag; required for tagged posts
+    #[account(
+        mut,
+        seeds = [TAG_SEED, post.load()?.tag.as_ref()],
+        bump = tag_counter.bump,
+    )]
+    pub tag_counter: Option<Account<'info, TagCounter>>,
+
+    /// Author's karma in the tag pool; required while the tag has a reward budget
+    #[account(
+        init_if_needed,
+        payer = liker,
+        seeds = [TAG_KARMA_SEED, post.load()?.tag.as_ref(), post.load()?.owner.as_ref()],
+        bump,
+        space = 8 + TagKarma::SIZE,
+    )]
+    pub tag_karma: Option<Account<'info, TagKarma>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateComment<'info> {
+    #[account(mut)]
+    pub author: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [PEER_SEED, author.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == author.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(mut)]
+    pub post: AccountLoader<'info, Post>,
+
+    /// Set when replying to another comment on the same post
+    #[account(
+        constraint = parent_comment.post == post.key() @ SentinelError::InvalidAccount,
+    )]
+    pub parent_comment: Option<Account<'info, Comment>>,
+
+    #[account(
+        init,
+        payer = author,
+        seeds = [COMMENT_SEED, post.key().as_ref(), post.load()?.comment_count.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Comment::SIZE,
+    )]
+    pub comment: Account<'info, Comment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LikeComment<'info> {
+    #[account(mut)]
+    pub liker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = liker,
+        seeds = [LIKE_SEED, liker.key().as_ref(), comment.key().as_ref()],
+        bump,
+        space = 8 + Like::SIZE,
+    )]
+    pub like: Account<'info, Like>,
+
+    #[account(
+        mut,
+        seeds = [COMMENT_SEED, comment.post.as_ref(), comment.index.to_le_bytes().as_ref()],
+        bump = comment.bump,
+    )]
+    pub comment: Account<'info, Comment>,
+
+    #[account(
+        address = comment.post @ SentinelError::InvalidAccount,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        init_if_needed,
+        payer = liker,
+        seeds = [LIKE_PAIR_SEED, liker.key().as_ref(), comment.author.as_ref()],
+        bump,
+        space = 8 + LikePair::SIZE,
+    )]
+    pub like_pair: Account<'info, LikePair>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, comment.author.as_ref()],
+        bump,
+        constraint = liked_peer.load()?.user == comment.author @ SentinelError::InvalidAccount,
+    )]
+    pub liked_peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, liker.key().as_ref()],
+        bump,
+        constraint = liker_peer.load()?.user == liker.key() @ SentinelError::InvalidAccount,
+        constraint = liker_peer.load()?.active != 0 @ SentinelError::NotPeer,
+    )]
+    pub liker_peer: AccountLoader<'info, PeerState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTagBudget<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [TAG_SEED, tag_counter.tag.as_ref()],
+        bump = tag_counter.bump,
+    )]
+    pub tag_counter: Account<'info, TagCounter>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimTagReward<'info> {
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [TAG_SEED, tag_counter.tag.as_ref()],
+        bump = tag_counter.bump,
+    )]
+    pub tag_counter: Account<'info, TagCounter>,
+
+    #[account(
+        mut,
+        seeds = [TAG_KARMA_SEED, tag_counter.tag.as_ref(), tag_karma.user.as_ref()],
+        bump = tag_karma.bump,
+    )]
+    pub tag_karma: Account<'info, TagKarma>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = peer_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = peer_sentinel_ata.owner == tag_karma.user @ SentinelError::InvalidAccount,
+    )]
+    pub peer_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: seka `GlobalConfig`, shared-mint mode only; verified by seka
+    pub seka_config: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka mint authority PDA, shared-mint mode only; verified by seka
+    pub seka_mint_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka program, shared-mint mode only; checked against `state.seka_program`
+    pub seka_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(tag: [u8; TAG_LEN])]
+pub struct InitTag<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [TAG_SEED, tag.as_ref()],
+        bump,
+        space = 8 + TagCounter::SIZE,
+    )]
+    pub tag_counter: Account<'info, TagCounter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitKarmaHistory<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [PEER_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [KARMA_HISTORY_SEED, peer.load()?.user.as_ref()],
+        bump,
+        space = 8 + KarmaHistory::SIZE,
+    )]
+    pub karma_history: AccountLoader<'info, KarmaHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitTopPosts<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [TOP_POSTS_SEED, state.cycle_index.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + TopPosts::SIZE,
+    )]
+    pub top_posts: AccountLoader<'info, TopPosts>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimTopPostBonus<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [TOP_POSTS_SEED, top_posts.load()?.cycle_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub top_posts: AccountLoader<'info, TopPosts>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = owner_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = owner_sentinel_ata.owner == owner.key() @ SentinelError::InvalidAccount,
+    )]
+    pub owner_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: seka `GlobalConfig`, shared-mint mode only; verified by seka
+    pub seka_config: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka mint authority PDA, shared-mint mode only; verified by seka
+    pub seka_mint_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka program, shared-mint mode only; checked against `state.seka_program`
+    pub seka_program: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemovePost<'info> {
+    #[account(
+        address = state.moderator @ SentinelError::Unauthorized
+    )]
+    pub moderator: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+    )]
+    pub post: AccountLoader<'info, Post>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClosePost<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+        close = owner,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    /// SPL posts: the NFT mint
+    #[account(
+        constraint = nft_mint.key() == post.load()?.nft_mint @ SentinelError::InvalidAccount,
+    )]
+    pub nft_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: rent destination, must be the post owner
+    #[account(
+        mut,
+        address = post.load()?.owner @ SentinelError::InvalidAccount,
+    )]
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: Core posts: the asset, only read for whether it was burned
+    #[account(
+        address = post.load()?.nft_mint @ SentinelError::InvalidAccount,
+    )]
+    pub asset: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FileReport<'info> {
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [PEER_SEED, reporter.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == reporter.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        init,
+        payer = reporter,
+        seeds = [REPORT_SEED, post.key().as_ref(), reporter.key().as_ref()],
+        bump,
+        space = 8 + Report::SIZE,
+    )]
+    pub report: Account<'info, Report>,
+
+    #[account(
+        init,
+        payer = reporter,
+        seeds = [REPORT_BOND_SEED, report.key().as_ref()],
+        bump,
+        token::mint = sentinel_mint,
+        token::authority = report,
+    )]
+    pub report_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = reporter_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = reporter_sentinel_ata.owner == reporter.key() @ SentinelError::InvalidAccount,
+    )]
+    pub reporter_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveReport<'info> {
+    #[account(
+        address = state.moderator @ SentinelError::Unauthorized
+    )]
+    pub moderator: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        mut,
+        seeds = [REPORT_SEED, post.key().as_ref(), report.reporter.as_ref()],
+        bump = report.bump,
+        close = reporter,
+    )]
+    pub report: Account<'info, Report>,
+
+    #[account(
+        mut,
+        seeds = [REPORT_BOND_SEED, report.key().as_ref()],
+        bump,
+    )]
+    pub report_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: receives the report rents, must be the reporter
+    #[account(
+        mut,
+        address = report.reporter @ SentinelError::InvalidAccount,
+    )]
+    pub reporter: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = reporter_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = reporter_sentinel_ata.owner == report.reporter @ SentinelError::InvalidAccount,
+    )]
+    pub reporter_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Post bond escrow; required when upholding while the bond is still locked
+    #[account(
+        mut,
+        seeds = [BOND_SEED, post.key().as_ref()],
+        bump,
+    )]
+    pub bond_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: receives the bond escrow rent, must be the post owner
+    #[account(
+        mut,
+        address = post.load()?.owner @ SentinelError::InvalidAccount,
+    )]
+    pub post_owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DeletePost<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, user.key().as_ref()],
+        bump,
+        constraint = peer.load()?.user == user.key() @ SentinelError::InvalidAccount,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+        constraint = post.load()?.owner == user.key() @ SentinelError::Unauthorized,
+        close = user,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    /// SPL posts: the NFT mint
+    #[account(
+        mut,
+        constraint = nft_mint.key() == post.load()?.nft_mint @ SentinelError::InvalidAccount,
+    )]
+    pub nft_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// SPL posts: the user's account holding the NFT
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = user,
+    )]
+    pub user_nft_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// SPL posts
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Core posts: the asset; Core checks that `user` owns it
+    #[account(
+        mut,
+        address = post.load()?.nft_mint @ SentinelError::InvalidAccount,
+    )]
+    pub asset: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Metaplex Core program; checked against `state.core_program`
+    #[account(
+        executable,
+        address = state.core_program @ SentinelError::InvalidAccount,
+    )]
+    pub core_program: Option<UncheckedAccount<'info>>,
+
+    /// Core posts
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseLike<'info> {
+    #[account(mut)]
+    pub liker: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [LIKE_SEED, liker.key().as_ref(), like.post.as_ref()],
+        bump,
+        constraint = like.liker == liker.key() @ SentinelError::Unauthorized,
+        close = liker,
+    )]
+    pub like: Account<'info, Like>,
+
+    /// CHECK: the liked post or comment; a post may already be closed
+    #[account(
+        address = like.post @ SentinelError::InvalidAccount,
+    )]
+    pub post: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncPostOwner<'info> {
+    /// The new holder claims the post; escrow PDAs holding a listed NFT can't sign
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POST_SEED, post.load()?.nft_mint.as_ref()],
+        bump,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        constraint = holder_nft_ata.mint == post.load()?.nft_mint @ SentinelError::InvalidAccount,
+        constraint = holder_nft_ata.owner == holder.key() @ SentinelError::InvalidAccount,
+        constraint = holder_nft_ata.amount == 1 @ SentinelError::InvalidAccount,
+    )]
+    pub holder_nft_ata: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ListPostNft<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        seeds = [POST_SEED, nft_mint.key().as_ref()],
+        bump,
+        constraint = post.load()?.owner == seller.key() @ SentinelError::Unauthorized,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = seller,
+    )]
+    pub seller_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = seller,
+        seeds = [LISTING_SEED, post.key().as_ref()],
+        bump,
+        space = 8 + Listing::SIZE,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = nft_mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [LISTING_SEED, listing.post.as_ref()],
+        bump = listing.bump,
+        constraint = listing.seller == seller.key() @ SentinelError::Unauthorized,
+        close = seller,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        address = listing.nft_mint @ SentinelError::InvalidAccount,
+    )]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = seller,
+    )]
+    pub seller_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BuyPostNft<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    /// CHECK: receives the listing and escrow rent; must be the listing seller
+    #[account(
+        mut,
+        address = listing.seller @ SentinelError::InvalidAccount,
+    )]
+    pub seller: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [LISTING_SEED, listing.post.as_ref()],
+        bump = listing.bump,
+        close = seller,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        address = listing.post @ SentinelError::InvalidAccount,
+    )]
+    pub post: AccountLoader<'info, Post>,
+
+    #[account(
+        address = listing.nft_mint @ SentinelError::InvalidAccount,
+    )]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = buyer_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = buyer_sentinel_ata.owner == buyer.key() @ SentinelError::InvalidAccount,
+    )]
+    pub buyer_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = seller_sentinel_ata.owner == listing.seller @ SentinelError::InvalidAccount,
+    )]
+    pub seller_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = creator_sentinel_ata.owner == post.load()?.creator @ SentinelError::InvalidAccount,
+    )]
+    pub creator_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [TREASURY_VAULT_SEED],
+        bump,
+    )]
+    pub treasury_vault: Account<'info, TreasuryVault>,
+
+    #[account(
+        mut,
+        constraint = treasury_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = treasury_sentinel_ata.owner == treasury_vault.key() @ SentinelError::InvalidAccount,
+    )]
+    pub treasury_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeCycle<'info> {
+    /// May be a governance PDA signing through a proposal, so it pays for nothing
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut, 
+        seeds = [STATE_SEED], 
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [TOP_POSTS_SEED, state.cycle_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub top_posts: Option<AccountLoader<'info, TopPosts>>,
+
+    /// `init_if_needed` because the crank may have allocated it while the cycle ran
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [CYCLE_RECORD_SEED, state.cycle_index.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + CycleRecord::SIZE,
+    )]
+    pub cycle_record: Account<'info, CycleRecord>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: seka `GlobalConfig`, shared-mint mode only; verified by seka
+    pub seka_config: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka mint authority PDA, shared-mint mode only; verified by seka
+    pub seka_mint_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka program, shared-mint mode only; checked against `state.seka_program`
+    pub seka_program: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CrankFinalizeCycle<'info> {
+    /// Any fee payer; the crank needs no authority
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_SEED],
+        bump = state.bump,
+        constraint = !state.paused @ SentinelError::Paused,
+        constraint = state.crank_finalize @ SentinelError::CrankFinalizeDisabled,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [TOP_POSTS_SEED, state.cycle_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub top_posts: Option<AccountLoader<'info, TopPosts>>,
+
+    /// Allocated on the first call of the cycle and only written once it is due
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [CYCLE_RECORD_SEED, state.cycle_index.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + CycleRecord::SIZE,
+    )]
+    pub cycle_record: Account<'info, CycleRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncKarmaToSeka<'info> {
+    /// Pays for the seka ledger if it does not exist yet
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    /// CHECK: seka `GlobalConfig`; seeds and issuer are verified by seka
+    pub seka_config: UncheckedAccount<'info>,
+
+    /// CHECK: seka `PeerLedger` of the peer; seeds are verified by seka
+    #[account(mut)]
+    pub seka_ledger: UncheckedAccount<'info>,
+
+    /// CHECK: the configured seka program
+    #[account(
+        executable,
+        address = state.seka_program @ SentinelError::InvalidAccount,
+    )]
+    pub seka_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrankResetKarma<'info> {
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    /// CHECK: the peer's `KarmaHistory`; recorded into when it exists
+    #[account(
+        mut,
+        seeds = [KARMA_HISTORY_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub karma_history: UncheckedAccount<'info>,
+
+    /// Record of the peer's karma cycle; may be omitted while that cycle is still open
+    #[account(
+        mut,
+        seeds = [CYCLE_RECORD_SEED, peer.load()?.karma_cycle.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub cycle_record: Option<Account<'info, CycleRecord>>,
+
+    #[account(
+        mut,
+        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
+    )]
+    pub sentinel_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = peer_sentinel_ata.mint == state.sentinel_mint @ SentinelError::InvalidAccount,
+        constraint = peer_sentinel_ata.owner == peer.load()?.user @ SentinelError::InvalidAccount,
+    )]
+    pub peer_sentinel_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: seka `GlobalConfig`, shared-mint mode only; verified by seka
+    pub seka_config: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka mint authority PDA, shared-mint mode only; verified by seka
+    pub seka_mint_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: seka program, shared-mint mode only; checked against `state.seka_program`
+    pub seka_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ResetKarma<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [STATE_SEED],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, State>,
+
+    #[account(
+        mut,
+        seeds = [PEER_SEED, peer.load()?.user.as_ref()],
+        bump,
+    )]
+    pub peer: AccountLoader<'info, PeerState>,
+
+    /// CHECK: the peer's `KarmaHistory`; recorded into when it exists
+    #[account(
+        mut,
+        seeds = [KARMA_HISTORY_SEED, peer.load()?.user.as_ref()],
+        bump,
     )]
-    pub treasury_sentinel_ata: Account<'info, TokenAccount>,
+    pub karma_history: UncheckedAccount<'info>,
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[account]
+pub struct State {
+    pub authority: Pubkey,          // 32
+    pub sentinel_mint: Pubkey,      // 32
+    pub treasury_vault: Pubkey,     // 32
+    pub cycle_start_ts: i64,        // 8
+    pub cycle_index: u64,           // 8
+    pub max_likes_per_cycle: u32,   // 4
+    pub max_likes_per_pair: u32,    // 4
+    pub join_burn_bps: u16,         // 2
+    pub leave_refund_bps: u16,      // 2
+    pub moderator: Pubkey,          // 32
+    pub post_bond: u64,             // 8
+    pub invite_only: bool,          // 1
+    pub invites_per_peer: u32,      // 4
+    pub join_price_lamports: u64,   // 8
+    pub total_peers: u64,           // 8
+    pub join_cost_base: u64,        // 8
+    pub join_cost_step: u64,        // 8
+    pub join_cost_step_size: u64,   // 8
+    pub committee: Vec<Pubkey>,     // 4 + 32 * MAX_COMMITTEE
+    pub threshold: u8,              // 1
+    pub pending_authority: Pubkey,  // 32
+    pub paused: bool,               // 1
+    pub bump: u8,                   // 1
+    pub post_count: u64,            // 8
+    pub top_post_bonus: u64,        // 8
+    pub like_reward_window: u64,    // 8
+    pub genesis_remaining: u64,     // 8
+    pub cycle_karma: u64,           // 8
+    pub seka_program: Pubkey,       // 32
+    pub seka_points_per_karma: u64, // 8
+    pub shared_mint: bool,          // 1
+    pub cycle_seconds: i64,         // 8
+    pub cycle_reward_total: u64,    // 8
+    pub market_fee_bps: u16,        // 2
+    pub username_fee: u64,          // 8
+    pub comment_like_bps: u16,      // 2
+    pub version: u8,                // 1
+    pub require_storage_attestation: bool, // 1
+    pub report_bond: u64,           // 8
+    pub report_bounty_bps: u16,     // 2
+    pub report_bounty: u64,         // 8
+    pub core_program: Pubkey,       // 32, Metaplex Core; default disables Core posts
+    pub crank_finalize: bool,       // 1, allows the keyless `crank_finalize_cycle`
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl State {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8 + 4 + 4 + 2 + 2 + 32 + 8 + 1 + 4 + 8 + 8 + 8 + 8 + 8
+        + (4 + 32 * MAX_COMMITTEE)
+        + 1
+        + 32
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 32
+        + 8
+        + 1
+        + 8
+        + 8
+        + 2
+        + 8
+        + 2
+        + 1
+        + 1
+        + 8
+        + 2
+        + 8
+        + 32
+        + 1
+        + 15;
+
+    /// Fills in a fresh state with default parameters.
+    pub fn init(
+        &mut self,
+        authority: Pubkey,
+        bump: u8,
+        sentinel_mint: Pubkey,
+        treasury_vault: Pubkey,
+    ) -> Result<()> {
+        self.authority = authority;
+        self.bump = bump;
+        self.sentinel_mint = sentinel_mint;
+        self.treasury_vault = treasury_vault;
+        self.cycle_start_ts = Clock::get()?.unix_timestamp;
+        self.cycle_index = 0;
+        self.max_likes_per_cycle = DEFAULT_MAX_LIKES_PER_CYCLE;
+        self.max_likes_per_pair = DEFAULT_MAX_LIKES_PER_PAIR;
+        self.join_burn_bps = DEFAULT_JOIN_BURN_BPS;
+        self.leave_refund_bps = DEFAULT_LEAVE_REFUND_BPS;
+        self.moderator = authority;
+        self.post_bond = DEFAULT_POST_BOND;
+        self.invite_only = false;
+        self.invites_per_peer = DEFAULT_INVITES_PER_PEER;
+        self.join_price_lamports = 0;
+        self.total_peers = 0;
+        self.join_cost_base = JOIN_COST;
+        self.join_cost_step = 0;
+        self.join_cost_step_size = DEFAULT_JOIN_COST_STEP_SIZE;
+        self.committee = Vec::new();
+        self.threshold = 0;
+        self.pending_authority = Pubkey::default();
+        self.paused = false;
+        self.post_count = 0;
+        self.top_post_bonus = 0;
+        self.like_reward_window = DEFAULT_LIKE_REWARD_WINDOW;
+        self.genesis_remaining = 0;
+        self.cycle_karma = 0;
+        self.seka_program = Pubkey::default();
+        self.seka_points_per_karma = 0;
+        self.shared_mint = false;
+        self.cycle_seconds = CYCLE_SECONDS;
+        self.cycle_reward_total = CYCLE_REWARD_TOTAL;
+        self.market_fee_bps = DEFAULT_MARKET_FEE_BPS;
+        self.username_fee = DEFAULT_USERNAME_FEE;
+        self.comment_like_bps = DEFAULT_COMMENT_LIKE_BPS;
+        self.version = STATE_VERSION;
+        self.require_storage_attestation = false;
+        self.report_bond = DEFAULT_REPORT_BOND;
+        self.report_bounty_bps = DEFAULT_REPORT_BOUNTY_BPS;
+        self.report_bounty = DEFAULT_REPORT_BOUNTY;
+        self.core_program = Pubkey::default();
+        self.crank_finalize = false;
+        Ok(())
+    }
+
+    /// Token join cost: `base + step * (total_peers / step_size)`.
+    pub fn join_cost(&self) -> Result<u64> {
+        let steps = self.total_peers / self.join_cost_step_size.max(1);
+        self.join_cost_step
+            .checked_mul(steps)
+            .and_then(|v| v.checked_add(self.join_cost_base))
+            .ok_or(SentinelError::Overflow.into())
+    }
+}
+
+impl Versioned for State {
+    const VERSION: u8 = STATE_VERSION;
+    const SPACE: usize = 8 + State::SIZE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Fields appended before versioning existed read back as zero, so the ones where
+    /// zero is not a usable setting get their defaults.
+    fn migrate(&mut self) {
+        if self.version < 1 {
+            if self.cycle_seconds == 0 {
+                self.cycle_seconds = CYCLE_SECONDS;
+            }
+            if self.cycle_reward_total == 0 {
+                self.cycle_reward_total = CYCLE_REWARD_TOTAL;
+            }
+            if self.join_cost_step_size == 0 {
+                self.join_cost_step_size = DEFAULT_JOIN_COST_STEP_SIZE;
+            }
+            if self.like_reward_window == 0 {
+                self.like_reward_window = DEFAULT_LIKE_REWARD_WINDOW;
+            }
+            if self.max_likes_per_cycle == 0 {
+                self.max_likes_per_cycle = DEFAULT_MAX_LIKES_PER_CYCLE;
+            }
+            if self.max_likes_per_pair == 0 {
+                self.max_likes_per_pair = DEFAULT_MAX_LIKES_PER_PAIR;
+            }
+            if self.invites_per_peer == 0 {
+                self.invites_per_peer = DEFAULT_INVITES_PER_PEER;
+            }
+            if self.join_cost_base == 0 {
+                self.join_cost_base = JOIN_COST;
+            }
+            if self.comment_like_bps == 0 {
+                self.comment_like_bps = DEFAULT_COMMENT_LIKE_BPS;
+            }
+            if self.moderator == Pubkey::default() {
+                self.moderator = self.authority;
+            }
+        }
+        // v2 appended `require_storage_attestation`, where zero (off) is the default
+        if self.version < 3 {
+            self.report_bond = DEFAULT_REPORT_BOND;
+            self.report_bounty_bps = DEFAULT_REPORT_BOUNTY_BPS;
+            self.report_bounty = DEFAULT_REPORT_BOUNTY;
+        }
+        // v4 appended `core_program`, where zero (disabled) is the default, v5 `_reserved`
+        // and v6 took `crank_finalize` (off) from it
+        self.version = STATE_VERSION;
+    }
+}
+
+#[account]
+pub struct Vesting {
+    pub beneficiary: Pubkey, // 32
+    pub total: u64,          // 8
+    pub claimed: u64,        // 8
+    pub start_ts: i64,       // 8
+    pub cliff_ts: i64,       // 8
+    pub end_ts: i64,         // 8
+    pub bump: u8,            // 1
+    pub version: u8,         // 1
+    pub _reserved: [u8; 15], // 15
+}
+
+impl Vesting {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 15;
+
+    /// Amount released by `now`: nothing before the cliff, then linear from start to end.
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        if now < self.cliff_ts {
+            return 0;
+        }
+        if now >= self.end_ts {
+            return self.total;
+        }
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        (self.total as u128 * elapsed / duration) as u64
+    }
+}
+
+#[account]
+pub struct TreasuryVault {
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl TreasuryVault {
+    pub const SIZE: usize = 1 + 15;
+}
+
+#[account(zero_copy)]
+pub struct PeerState {
+    pub user: Pubkey,               // 32
+    pub karma: u64,                 // 8
+    pub likes_cycle: u64,           // 8
+    pub join_paid: u64,             // 8
+    pub likes_cast: u32,            // 4
+    pub invites_issued: u32,        // 4
+    pub active: u8,                 // 1
+    pub joined_with_sol: u8,        // 1
+    pub has_username: u8,           // 1
+    pub version: u8,                // 1
+    pub _padding: [u8; 4],          // 4
+    pub karma_cycle: u64,           // 8
+    pub karma_synced: u64,          // 8
+    pub karma_earned: u64,          // 8
+    pub likes_given: u64,           // 8
+    pub _reserved: [u8; 32],        // 32
+}
+
+impl PeerState {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 4 + 4 + 1 + 1 + 1 + 1 + 4 + 8 + 8 + 8 + 8 + 32;
+}
+
+const _: () = assert!(PeerState::SIZE == std::mem::size_of::<PeerState>());
+
+#[account(zero_copy)]
+pub struct Post {
+    pub owner: Pubkey,              // 32
+    pub nft_mint: Pubkey,           // 32
+    pub hash: [u8; 32],             // 32
+    pub legacy_db_addr: [u8; 32],   // 32, pre-v3 content pointer; zero on new posts
+    pub likes: u64,                 // 8
+    pub cycle_index: u64,           // 8
+    pub bond: u64,                  // 8
+    pub hash_history: [[u8; 32]; MAX_HASH_HISTORY], // 32 * MAX_HASH_HISTORY
+    pub edit_count: u32,            // 4
+    pub hash_history_len: u8,       // 1
+    pub removed: u8,                // 1
+    pub soulbound: u8,              // 1
+    pub bond_escrow_open: u8,       // 1
+    pub post_id: u64,               // 8
+    pub author_index: u64,          // 8
+    pub creator: Pubkey,            // 32
+    pub royalty_bps: u16,           // 2
+    pub version: u8,                // 1
+    pub asset_kind: u8,             // 1, `PostAsset`
+    pub _padding: [u8; 4],          // 4
+    pub comment_count: u64,         // 8
+    pub committed_ts: i64,          // 8, earliest proof of authorship
+    pub content_scheme: u8,         // 1, `ContentScheme`
+    pub content_len: u8,            // 1
+    pub _content_padding: [u8; 6],  // 6
+    pub content_addr: [u8; MAX_CONTENT_ADDR_LEN], // MAX_CONTENT_ADDR_LEN
+    pub tag: [u8; TAG_LEN],         // TAG_LEN, all zero when untagged
+    pub pool_likes: u64,            // 8, counted likes paid from the tag pool
+    pub _reserved: [u8; 16],        // 16
+}
+
+impl Post {
+    pub const SIZE: usize = 32 + 32 + 32 + 32 + 8 + 8 + 8 + 32 * MAX_HASH_HISTORY + 4 + 1 + 1 + 1 + 1
+        + 8 + 8 + 32 + 2 + 1 + 1 + 4 + 8 + 8 + 1 + 1 + 6 + MAX_CONTENT_ADDR_LEN + TAG_LEN
+        + 8 + 16;
+
+    pub fn set_content(&mut self, content: &ContentAddress) {
+        self.content_scheme = content.scheme as u8;
+        self.content_len = content.addr.len() as u8;
+        self.content_addr = [0; MAX_CONTENT_ADDR_LEN];
+        self.content_addr[..content.addr.len()].copy_from_slice(&content.addr);
+    }
+}
+
+impl Versioned for Post {
+    const VERSION: u8 = POST_VERSION;
+    const SPACE: usize = 8 + Post::SIZE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn migrate(&mut self) {
+        // v3 moved the 32-byte `db_addr` into the scheme-tagged content address
+        if self.version < 3 && self.content_len == 0 && self.legacy_db_addr != [0; 32] {
+            self.content_scheme = ContentScheme::Legacy as u8;
+            self.content_len = 32;
+            self.content_addr[..32].copy_from_slice(&self.legacy_db_addr);
+            self.legacy_db_addr = [0; 32];
+        }
+        // v4 appended `tag`, v5 `pool_likes`, v6 took `asset_kind` from padding and v7
+        // appended `_reserved`; zero is right for all of them
+        self.version = POST_VERSION;
+    }
+}
+
+const _: () = assert!(Post::SIZE == std::mem::size_of::<Post>());
+
+#[account]
+pub struct PostIndex {
+    pub post: Pubkey,               // 32
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl PostIndex {
+    pub const SIZE: usize = 32 + 1 + 15;
+}
+
+#[account]
+pub struct AuthorIndex {
+    pub owner: Pubkey,              // 32
+    pub posts_created: u64,         // 8
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl AuthorIndex {
+    pub const SIZE: usize = 32 + 8 + 1 + 15;
+}
+
+#[account]
+pub struct UserPost {
+    pub post: Pubkey,               // 32
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl UserPost {
+    pub const SIZE: usize = 32 + 1 + 15;
+}
+
+#[zero_copy]
+pub struct TopEntry {
+    pub post: Pubkey,               // 32
+    pub owner: Pubkey,              // 32
+    pub likes: u64,                 // 8
+}
+
+#[zero_copy]
+pub struct KarmaEntry {
+    pub cycle_index: u64,           // 8
+    pub karma: u64,                 // 8
+}
+
+/// Ring buffer of a peer's karma in its most recent settled cycles.
+#[account(zero_copy)]
+pub struct KarmaHistory {
+    pub user: Pubkey,               // 32
+    pub head: u32,                  // 4, next slot to write
+    pub len: u32,                   // 4
+    pub entries: [KarmaEntry; KARMA_HISTORY_LEN], // 16 * KARMA_HISTORY_LEN
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl KarmaHistory {
+    pub const SIZE: usize = 32 + 4 + 4 + 16 * KARMA_HISTORY_LEN + 1 + 15;
+
+    pub fn push(&mut self, cycle_index: u64, karma: u64) {
+        self.entries[self.head as usize] = KarmaEntry { cycle_index, karma };
+        self.head = (self.head + 1) % KARMA_HISTORY_LEN as u32;
+        if (self.len as usize) < KARMA_HISTORY_LEN {
+            self.len += 1;
+        }
+    }
+}
+
+const _: () = assert!(KarmaHistory::SIZE == std::mem::size_of::<KarmaHistory>());
+
+#[account(zero_copy)]
+pub struct TopPosts {
+    pub cycle_index: u64,           // 8
+    pub bonus: u64,                 // 8
+    pub entries: [TopEntry; TOP_POSTS_LEN], // 72 * TOP_POSTS_LEN
+    pub claimed_mask: u16,          // 2
+    pub len: u8,                    // 1
+    pub finalized: u8,              // 1
+    pub _padding: [u8; 4],          // 4
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl TopPosts {
+    pub const SIZE: usize = 8 + 8 + 72 * TOP_POSTS_LEN + 2 + 1 + 1 + 4 + 1 + 15;
+
+    /// Upsert `post` and keep the `TOP_POSTS_LEN` most-liked entries (unordered).
+    pub fn record(&mut self, post: Pubkey, owner: Pubkey, likes: u64) {
+        if self.finalized != 0 {
+            return;
+        }
+        let len = self.len as usize;
+        if let Some(e) = self.entries[..len].iter_mut().find(|e| e.post == post) {
+            e.likes = likes;
+            return;
+        }
+        let entry = TopEntry { post, owner, likes };
+        if len < TOP_POSTS_LEN {
+            self.entries[len] = entry;
+            self.len += 1;
+            return;
+        }
+        let (min_i, min) = self
+            .entries
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, e)| e.likes)
+            .map(|(i, e)| (i, e.likes))
+            .unwrap();
+        if likes > min {
+            self.entries[min_i] = entry;
+        }
+    }
+}
+
+const _: () = assert!(TopPosts::SIZE == std::mem::size_of::<TopPosts>());
+
+#[account]
+pub struct CycleRecord {
+    pub cycle_index: u64,           // 8
+    pub start_ts: i64,              // 8
+    pub end_ts: i64,                // 8
+    pub total_karma: u128,          // 16
+    pub total_minted: u64,          // 8
+    pub peers_rewarded: u32,        // 4
+    pub top_recipient: Pubkey,      // 32
+    pub top_reward: u64,            // 8
+    pub onchain_rewards: bool,      // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl CycleRecord {
+    pub const SIZE: usize = 8 + 8 + 8 + 16 + 8 + 4 + 32 + 8 + 1 + 1 + 15;
+}
+
+#[account]
+pub struct Like {
+    pub liker: Pubkey,              // 32
+    pub post: Pubkey,               // 32
+    pub ts: i64,                    // 8
+    pub cycle_index: u64,           // 8
+    pub counted: bool,              // 1
+    pub target: LikeTarget,         // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl Like {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1 + 1 + 1 + 15;
+}
+
+#[account]
+pub struct TagCounter {
+    pub tag: [u8; TAG_LEN],         // TAG_LEN
+    pub posts: u64,                 // 8
+    pub bump: u8,                   // 1
+    pub reward_budget: u64,         // 8, per-cycle pool; 0 routes likes to the general pool
+    pub cycle_index: u64,           // 8
+    pub cycle_karma: u64,           // 8
+    pub closed_cycle_index: u64,    // 8
+    pub closed_karma: u64,          // 8
+    pub closed_budget: u64,         // 8
+    pub closed_minted: u64,         // 8
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
+
+impl TagCounter {
+    pub const SIZE: usize = TAG_LEN + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 15;
+
+    /// Snapshots the running cycle once a later cycle touches the counter.
+    pub fn roll(&mut self, cycle_index: u64) {
+        if self.cycle_index < cycle_index {
+            self.closed_cycle_index = self.cycle_index;
+            self.closed_karma = self.cycle_karma;
+            self.closed_budget = self.reward_budget;
+            self.closed_minted = 0;
+            self.cycle_index = cycle_index;
+            self.cycle_karma = 0;
+        }
+    }
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[account]
+pub struct TagKarma {
+    pub tag: [u8; TAG_LEN],         // TAG_LEN
+    pub user: Pubkey,               // 32
+    pub cycle_index: u64,           // 8
+    pub karma: u64,                 // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
 }
 
-#[derive(Accounts)]
-pub struct MintNft<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+impl TagKarma {
+    pub const SIZE: usize = TAG_LEN + 32 + 8 + 8 + 1 + 1 + 15;
+}
 
-    #[account(
-        seeds = [STATE_SEED],
-        bump,
-    )]
-    pub state: Account<'info, State>,
+#[account]
+pub struct Report {
+    pub reporter: Pubkey,           // 32
+    pub post: Pubkey,               // 32
+    pub bond: u64,                  // 8
+    pub reason_hash: [u8; 32],      // 32
+    pub created_ts: i64,            // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(
-        mut,
-        seeds = [PEER_SEED, user.key().as_ref()],
-        bump,
-        constraint = peer.user == user.key() @ SentinelError::InvalidAccount,
-    )]
-    pub peer: Account<'info, PeerState>,
+impl Report {
+    pub const SIZE: usize = 32 + 32 + 8 + 32 + 8 + 1 + 1 + 15;
+}
 
-    #[account(
-        mut,
-        constraint = nft_mint.decimals == 0 @ SentinelError::InvalidNftMint,
-        constraint = nft_mint.mint_authority == COption::Some(user.key()) @ SentinelError::InvalidNftMint,
-    )]
-    pub nft_mint: Account<'info, Mint>,
+#[account]
+pub struct StorageProvider {
+    pub provider: Pubkey,           // 32
+    pub registered_ts: i64,         // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(
-        init,
-        payer = user,
-        associated_token::mint = nft_mint,
-        associated_token::authority = user,
-    )]
-    pub user_nft_ata: Account<'info, TokenAccount>,
+impl StorageProvider {
+    pub const SIZE: usize = 32 + 8 + 1 + 1 + 15;
+}
 
-    #[account(
-        init,
-        payer = user,
-        seeds = [POST_SEED, nft_mint.key().as_ref()],
-        bump,
-        space = 8 + Post::SIZE,
-    )]
-    pub post: Account<'info, Post>,
+#[account]
+pub struct PostCommit {
+    pub owner: Pubkey,              // 32
+    pub commitment: [u8; 32],       // 32, sha256(hash || salt)
+    pub slot: u64,                  // 8
+    pub ts: i64,                    // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+impl PostCommit {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1 + 1 + 15;
 }
 
-#[derive(Accounts)]
-pub struct LikeNft<'info> {
-    #[account(mut)]
-    pub liker: Signer<'info>,
+#[account]
+pub struct Comment {
+    pub post: Pubkey,               // 32
+    pub parent: Pubkey,             // 32, default for top-level comments
+    pub author: Pubkey,             // 32
+    pub hash: [u8; 32],             // 32
+    pub index: u64,                 // 8
+    pub likes: u64,                 // 8
+    pub counted_likes: u64,         // 8
+    pub cycle_index: u64,           // 8
+    pub created_ts: i64,            // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(
-        seeds = [STATE_SEED],
-        bump,
-    )]
-    pub state: Account<'info, State>,
+impl Comment {
+    pub const SIZE: usize = 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 15;
+}
 
-    #[account(
-        init,
-        payer = liker,
-        seeds = [LIKE_SEED, liker.key().as_ref(), post.key().as_ref()],
-        bump,
-        space = 8 + Like::SIZE,
-    )]
-    pub like: Account<'info, Like>,
+#[account]
+pub struct Listing {
+    pub seller: Pubkey,             // 32
+    pub post: Pubkey,               // 32
+    pub nft_mint: Pubkey,           // 32
+    pub price: u64,                 // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(mut)]
-    pub post: Account<'info, Post>,
+impl Listing {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 1 + 1 + 15;
+}
 
-    #[account(
-        mut,
-        seeds = [PEER_SEED, post.owner.as_ref()],
-        bump,
-        constraint = liked_peer.user == post.owner @ SentinelError::InvalidAccount,
-    )]
-    pub liked_peer: Account<'info, PeerState>,
+#[account]
+pub struct Username {
+    pub owner: Pubkey,              // 32
+    pub name: String,               // 4 + MAX_USERNAME_LEN
+    pub registered_ts: i64,         // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(
-        seeds = [PEER_SEED, liker.key().as_ref()],
-        bump,
-        constraint = liker_peer.user == liker.key() @ SentinelError::InvalidAccount,
-        constraint = liker_peer.active @ SentinelError::NotPeer,
-    )]
-    pub liker_peer: Account<'info, PeerState>,
+impl Username {
+    pub const SIZE: usize = 32 + (4 + MAX_USERNAME_LEN) + 8 + 1 + 1 + 15;
+}
 
-    pub system_program: Program<'info, System>,
+#[account]
+pub struct Achievement {
+    pub id: u32,                    // 4
+    pub metric: AchievementMetric,  // 1
+    pub threshold: u64,             // 8
+    pub karma_bonus: u64,           // 8
+    pub claims: u64,                // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
 }
 
-#[derive(Accounts)]
-pub struct FinalizeCycle<'info> {
-    #[account(
-        mut, 
-        address = state.authority @ SentinelError::Unauthorized
-    )]
-    pub authority: Signer<'info>,
+impl Achievement {
+    pub const SIZE: usize = 4 + 1 + 8 + 8 + 8 + 1 + 1 + 15;
+}
 
-    #[account(
-        mut, 
-        seeds = [STATE_SEED], 
-        bump
-    )]
-    pub state: Account<'info, State>,
+/// Soulbound proof that a peer unlocked an achievement.
+#[account]
+pub struct Badge {
+    pub owner: Pubkey,              // 32
+    pub achievement: Pubkey,        // 32
+    pub achievement_id: u32,        // 4
+    pub claimed_ts: i64,            // 8
+    pub bump: u8,                   // 1
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(
-        mut,
-        constraint = sentinel_mint.key() == state.sentinel_mint @ SentinelError::InvalidAccount,
-    )]
-    pub sentinel_mint: Account<'info, Mint>,
+impl Badge {
+    pub const SIZE: usize = 32 + 32 + 4 + 8 + 1 + 1 + 15;
+}
 
-    pub token_program: Program<'info, Token>,
+#[account]
+pub struct Invite {
+    pub inviter: Pubkey,            // 32
+    pub invitee: Pubkey,            // 32
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
 }
 
-#[derive(Accounts)]
-pub struct ResetKarma<'info> {
-    #[account(
-        mut,
-        address = state.authority @ SentinelError::Unauthorized
-    )]
-    pub authority: Signer<'info>,
+impl Invite {
+    pub const SIZE: usize = 32 + 32 + 1 + 15;
+}
 
-    #[account(
-        seeds = [STATE_SEED],
-        bump,
-    )]
-    pub state: Account<'info, State>,
+#[account]
+pub struct LikePair {
+    pub liker: Pubkey,              // 32
+    pub author: Pubkey,             // 32
+    pub cycle_index: u64,           // 8
+    pub count: u32,                 // 4
+    pub version: u8,                // 1
+    pub _reserved: [u8; 15],        // 15
+}
 
-    #[account(
-        mut,
-        seeds = [PEER_SEED, peer.user.as_ref()],
-        bump,
-    )]
-    pub peer: Account<'info, PeerState>,
+impl LikePair {
+    pub const SIZE: usize = 32 + 32 + 8 + 4 + 1 + 15;
 }
 
+sentinelkarma_core::versioned!(PeerState, PEER_VERSION, 8 + PeerState::SIZE);
+// The rest gained `version` and `_reserved` at version 0; older ones only lack that tail
+sentinelkarma_core::versioned!(Vesting, 0, 8 + Vesting::SIZE);
+sentinelkarma_core::versioned!(TreasuryVault, 0, 8 + TreasuryVault::SIZE);
+sentinelkarma_core::versioned!(PostIndex, 0, 8 + PostIndex::SIZE);
+sentinelkarma_core::versioned!(AuthorIndex, 0, 8 + AuthorIndex::SIZE);
+sentinelkarma_core::versioned!(UserPost, 0, 8 + UserPost::SIZE);
+sentinelkarma_core::versioned!(KarmaHistory, 0, 8 + KarmaHistory::SIZE);
+sentinelkarma_core::versioned!(TopPosts, 0, 8 + TopPosts::SIZE);
+sentinelkarma_core::versioned!(CycleRecord, 0, 8 + CycleRecord::SIZE);
+sentinelkarma_core::versioned!(Like, 0, 8 + Like::SIZE);
+sentinelkarma_core::versioned!(TagCounter, 0, 8 + TagCounter::SIZE);
+sentinelkarma_core::versioned!(TagKarma, 0, 8 + TagKarma::SIZE);
+sentinelkarma_core::versioned!(Report, 0, 8 + Report::SIZE);
+sentinelkarma_core::versioned!(StorageProvider, 0, 8 + StorageProvider::SIZE);
+sentinelkarma_core::versioned!(PostCommit, 0, 8 + PostCommit::SIZE);
+sentinelkarma_core::versioned!(Comment, 0, 8 + Comment::SIZE);
+sentinelkarma_core::versioned!(Listing, 0, 8 + Listing::SIZE);
+sentinelkarma_core::versioned!(Username, 0, 8 + Username::SIZE);
+sentinelkarma_core::versioned!(Achievement, 0, 8 + Achievement::SIZE);
+sentinelkarma_core::versioned!(Badge, 0, 8 + Badge::SIZE);
+sentinelkarma_core::versioned!(Invite, 0, 8 + Invite::SIZE);
+sentinelkarma_core::versioned!(LikePair, 0, 8 + LikePair::SIZE);
+
 // ============================================================================
-// Account Structures
+// Events
 // ============================================================================
 
-#[account]
-pub struct State {
-    pub authority: Pubkey,          // 32
-    pub sentinel_mint: Pubkey,      // 32
-    pub treasury_vault: Pubkey,     // 32
-    pub cycle_start_ts: i64,        // 8
-    pub cycle_index: u64,           // 8
+#[event]
+pub struct PeerJoined {
+    pub user: Pubkey,
+    pub join_cost: u64,
+    pub burned: u64,
+    pub to_treasury: u64,
 }
 
-impl State {
-    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8;
+#[event]
+pub struct InviteCreated {
+    pub inviter: Pubkey,
+    pub invitee: Pubkey,
 }
 
-#[account]
-pub struct TreasuryVault {}
+#[event]
+pub struct PeerJoinedWithSol {
+    pub user: Pubkey,
+    pub lamports: u64,
+}
 
-impl TreasuryVault {
-    pub const SIZE: usize = 0;
+#[event]
+pub struct PeerLeft {
+    pub user: Pubkey,
+    pub refund: u64,
+    pub in_sol: bool,
 }
 
-#[account]
-pub struct PeerState {
-    pub user: Pubkey,               // 32
-    pub active: bool,               // 1
-    pub karma: u64,                 // 8
+#[event]
+pub struct SolWithdrawn {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub lamports: u64,
 }
 
-impl PeerState {
-    pub const SIZE: usize = 32 + 1 + 8;
+#[event]
+pub struct UsernameRegistered {
+    pub owner: Pubkey,
+    pub name: String,
+    pub fee: u64,
 }
 
-#[account]
-pub struct Post {
-    pub owner: Pubkey,              // 32
-    pub nft_mint: Pubkey,           // 32
-    pub hash: [u8; 32],             // 32
-    pub db_addr: Pubkey,            // 32
-    pub likes: u64,                 // 8
-    pub cycle_index: u64,           // 8
+#[event]
+pub struct UsernameReleased {
+    pub owner: Pubkey,
+    pub name: String,
 }
 
-impl Post {
-    pub const SIZE: usize = 32 + 32 + 32 + 32 + 8 + 8;
+#[event]
+pub struct AchievementCreated {
+    pub id: u32,
+    pub metric: AchievementMetric,
+    pub threshold: u64,
+    pub karma_bonus: u64,
 }
 
-#[account]
-pub struct Like {
-    pub liker: Pubkey,              // 32
-    pub post: Pubkey,               // 32
+#[event]
+pub struct AchievementClaimed {
+    pub id: u32,
+    pub owner: Pubkey,
+    pub progress: u64,
+    pub karma_bonus: u64,
 }
 
-impl Like {
-    pub const SIZE: usize = 32 + 32;
+#[event]
+pub struct CommentCreated {
+    pub comment: Pubkey,
+    pub post: Pubkey,
+    pub parent: Pubkey,
+    pub author: Pubkey,
+    pub hash: [u8; 32],
+}
+
+#[event]
+pub struct CommentLiked {
+    pub comment: Pubkey,
+    pub liker: Pubkey,
+    pub author: Pubkey,
+    pub karma: u64,
+}
+
+#[event]
+pub struct TagBudgetSet {
+    pub tag: [u8; TAG_LEN],
+    pub reward_budget: u64,
+}
+
+#[event]
+pub struct TagRewardClaimed {
+    pub tag: [u8; TAG_LEN],
+    pub user: Pubkey,
+    pub cycle_index: u64,
+    pub karma: u64,
+    pub reward: u64,
+}
+
+#[event]
+pub struct ReportFiled {
+    pub report: Pubkey,
+    pub post: Pubkey,
+    pub reporter: Pubkey,
+    pub bond: u64,
+    pub reason_hash: [u8; 32],
+}
+
+#[event]
+pub struct ReportResolved {
+    pub report: Pubkey,
+    pub post: Pubkey,
+    pub reporter: Pubkey,
+    pub upheld: bool,
+    pub bounty: u64,
+    pub forfeited: u64,
+}
+
+#[event]
+pub struct PostRemoved {
+    pub post: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+pub struct PostClosed {
+    pub post: Pubkey,
+    pub closed_by: Pubkey,
+}
+
+#[event]
+pub struct PostDeleted {
+    pub post: Pubkey,
+    pub owner: Pubkey,
+    pub karma_removed: u64,
+}
+
+#[event]
+pub struct PostOwnerSynced {
+    pub post: Pubkey,
+    pub previous_owner: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct PostListed {
+    pub post: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct ListingCancelled {
+    pub post: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct PostSold {
+    pub post: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub creator: Pubkey,
+    pub price: u64,
+    pub seller_proceeds: u64,
+    pub royalty: u64,
+    pub treasury_fee: u64,
+}
+
+#[event]
+pub struct PostUpdated {
+    pub post: Pubkey,
+    pub previous_hash: [u8; 32],
+    pub hash: [u8; 32],
+    pub content: ContentAddress,
+    pub edit_count: u32,
+}
+
+#[event]
+pub struct BondRefunded {
+    pub post: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct BondSlashed {
+    pub post: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct StorageProviderAdded {
+    pub provider: Pubkey,
+}
+
+#[event]
+pub struct StorageProviderRemoved {
+    pub provider: Pubkey,
+}
+
+#[event]
+pub struct CommitteeUpdated {
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct AuthorityNominated {
+    pub current: Pubkey,
+    pub pending: Pubkey,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub previous: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PauseChanged {
+    pub paused: bool,
+}
+
+#[event]
+pub struct TopPostBonusClaimed {
+    pub cycle_index: u64,
+    pub post: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CycleFinalized {
+    pub cycle_index: u64,
+    pub total_karma: u128,
+    pub total_minted: u64,
+    pub peers_rewarded: u32,
+    pub top_recipient: Pubkey,
+    pub top_reward: u64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TreasuryBurned {
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct VestedSupplyClaimed {
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct GenesisDistributed {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub reason_code: u16,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct KarmaSyncedToSeka {
+    pub user: Pubkey,
+    pub karma: u64,
+    pub points: u64,
+}
+
+#[event]
+pub struct TransferFeeUpdated {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+#[event]
+pub struct TransferFeesHarvested {
+    pub amount: u64,
+    pub sources: u32,
 }
 
 // ============================================================================
 // Error Codes
 // ============================================================================
 
-#[error_code]
-pub enum SentinelError {
-    #[msg("Math overflow")]
-    Overflow,
-    
-    #[msg("User is not an active peer")]
-    NotPeer,
-    
-    #[msg("Cycle not ended yet")]
-    CycleNotEnded,
-    
-    #[msg("Invalid input vectors")]
-    InvalidInput,
-    
-    #[msg("Missing remaining account for reward ATA")]
-    MissingAccount,
-    
-    #[msg("Invalid account data")]
-    InvalidAccount,
-    
-    #[msg("Invalid NFT mint (must be 0 decimals with user as authority)")]
-    InvalidNftMint,
-    
-    #[msg("Cannot like your own post")]
-    CannotLikeSelf,
-    
-    #[msg("Unauthorized")]
-    Unauthorized,
+// Codes are grouped by subsystem; see `sentinelkarma_core::error`
+sentinelkarma_core::sentinel_error!(anchor SentinelError);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        /// Shares of one pool never exceed it in sum, nor the per-peer cap each.
+        #[test]
+        fn reward_shares_stay_within_pool(
+            karmas in prop::collection::vec(any::<u64>(), 1..64),
+            reward_total in any::<u64>(),
+        ) {
+            let total_karma: u128 = karmas.iter().map(|&k| k as u128).sum();
+            prop_assume!(total_karma > 0);
+            let cap = reward_total as u128 * MAX_PEER_REWARD_PCT as u128 / 100;
+            let mut paid: u128 = 0;
+            for &karma in karmas.iter().filter(|&&k| k > 0) {
+                let share = reward_share(karma as u128, total_karma, reward_total);
+                prop_assert!(share as u128 <= cap);
+                paid += share as u128;
+            }
+            prop_assert!(paid <= reward_total as u128);
+        }
+
+        /// More karma in the same pool never earns less.
+        #[test]
+        fn reward_share_is_monotonic(
+            a in any::<u64>(),
+            b in any::<u64>(),
+            rest in any::<u64>(),
+            reward_total in any::<u64>(),
+        ) {
+            let (low, high) = (a.min(b) as u128, a.max(b) as u128);
+            let total_karma = high + rest as u128;
+            prop_assume!(total_karma > 0);
+            prop_assert!(
+                reward_share(low, total_karma, reward_total)
+                    <= reward_share(high, total_karma, reward_total)
+            );
+        }
+
+        /// A sole earner takes the capped share, not the whole pool.
+        #[test]
+        fn sole_earner_is_capped(karma in 1..=u64::MAX, reward_total in any::<u64>()) {
+            prop_assert_eq!(
+                reward_share(karma as u128, karma as u128, reward_total),
+                (reward_total as u128 * MAX_PEER_REWARD_PCT as u128 / 100) as u64
+            );
+        }
+
+        /// A like written before versioning, once grown by `migrate_accounts`, reads back
+        /// as the same like at version 0.
+        #[test]
+        fn pre_versioning_like_reads_after_growth(
+            liker in any::<[u8; 32]>(),
+            post in any::<[u8; 32]>(),
+            ts in any::<i64>(),
+            cycle_index in any::<u64>(),
+            counted in any::<bool>(),
+        ) {
+            let like = Like {
+                liker: Pubkey::new_from_array(liker),
+                post: Pubkey::new_from_array(post),
+                ts,
+                cycle_index,
+                counted,
+                target: LikeTarget::Comment,
+                version: 0,
+                _reserved: [0; 15],
+            };
+            let mut current = Vec::new();
+            like.try_serialize(&mut current).unwrap();
+            // The old layout is the current one without `version` and `_reserved`
+            let mut grown = current[..current.len() - 16].to_vec();
+            grown.resize(<Like as Versioned>::SPACE, 0);
+
+            let mut read = Like::try_deserialize(&mut &grown[..]).unwrap();
+            read.migrate();
+            let mut rewritten = Vec::new();
+            read.try_serialize(&mut rewritten).unwrap();
+            prop_assert_eq!(rewritten, current);
+        }
+
+        /// The state as first deployed, grown and migrated, gets `init`'s defaults
+        /// wherever zero would stop the program working.
+        #[test]
+        fn baseline_state_migrates_to_usable_defaults(
+            authority in any::<[u8; 32]>(),
+            cycle_start_ts in any::<i64>(),
+            cycle_index in any::<u64>(),
+        ) {
+            // `{ authority, sentinel_mint, treasury_vault, cycle_start_ts, cycle_index }`
+            let mut baseline = State::DISCRIMINATOR.to_vec();
+            baseline.extend_from_slice(&authority);
+            baseline.extend_from_slice(&[1; 64]);
+            baseline.extend_from_slice(&cycle_start_ts.to_le_bytes());
+            baseline.extend_from_slice(&cycle_index.to_le_bytes());
+            prop_assert_eq!(baseline.len(), 8 + 112);
+            baseline.resize(<State as Versioned>::SPACE, 0);
+
+            let mut state = State::try_deserialize(&mut &baseline[..]).unwrap();
+            state.migrate();
+            let authority = Pubkey::new_from_array(authority);
+            prop_assert_eq!(state.authority, authority);
+            prop_assert_eq!((state.cycle_start_ts, state.cycle_index), (cycle_start_ts, cycle_index));
+            prop_assert_eq!(state.moderator, authority);
+            prop_assert_eq!(state.max_likes_per_cycle, DEFAULT_MAX_LIKES_PER_CYCLE);
+            prop_assert_eq!(state.max_likes_per_pair, DEFAULT_MAX_LIKES_PER_PAIR);
+            prop_assert_eq!(state.invites_per_peer, DEFAULT_INVITES_PER_PEER);
+            prop_assert_eq!(state.join_cost_base, JOIN_COST);
+            prop_assert_eq!(state.comment_like_bps, DEFAULT_COMMENT_LIKE_BPS);
+            prop_assert_eq!(state.cycle_seconds, CYCLE_SECONDS);
+            prop_assert_eq!(state.version, STATE_VERSION);
+        }
+
+        /// A peer as first deployed keeps its user, `active` and karma once rebuilt in
+        /// the zero-copy layout.
+        #[test]
+        fn baseline_peer_migrates_to_zero_copy(
+            user in any::<[u8; 32]>(),
+            active in any::<bool>(),
+            karma in any::<u64>(),
+        ) {
+            let legacy = LegacyPeerState { user: Pubkey::new_from_array(user), active, karma };
+            let bytes = legacy.try_to_vec().unwrap();
+            prop_assert_eq!(Some(8 + bytes.len()), PeerState::LEGACY_LEN);
+
+            let mut peer: PeerState = bytemuck::Zeroable::zeroed();
+            peer.read_legacy(&bytes).unwrap();
+            peer.migrate();
+            prop_assert_eq!(peer.user, legacy.user);
+            prop_assert_eq!(peer.active, active as u8);
+            prop_assert_eq!(peer.karma, karma);
+            prop_assert_eq!(peer.version, PEER_VERSION);
+        }
+
+        /// A post as first deployed keeps its fields, its `db_addr` becoming a legacy
+        /// content address.
+        #[test]
+        fn baseline_post_migrates_to_zero_copy(
+            owner in any::<[u8; 32]>(),
+            hash in any::<[u8; 32]>(),
+            db_addr in any::<[u8; 32]>(),
+            likes in any::<u64>(),
+            cycle_index in any::<u64>(),
+        ) {
+            prop_assume!(db_addr != [0; 32]);
+            let legacy = LegacyPost {
+                owner: Pubkey::new_from_array(owner),
+                nft_mint: Pubkey::new_from_array(hash),
+                hash,
+                db_addr: Pubkey::new_from_array(db_addr),
+                likes,
+                cycle_index,
+            };
+            let bytes = legacy.try_to_vec().unwrap();
+            prop_assert_eq!(Some(8 + bytes.len()), Post::LEGACY_LEN);
+
+            let mut post: Post = bytemuck::Zeroable::zeroed();
+            post.read_legacy(&bytes).unwrap();
+            post.migrate();
+            prop_assert_eq!((post.owner, post.creator), (legacy.owner, legacy.owner));
+            prop_assert_eq!((post.nft_mint, post.hash), (legacy.nft_mint, hash));
+            prop_assert_eq!((post.likes, post.cycle_index), (likes, cycle_index));
+            prop_assert_eq!(post.content_scheme, ContentScheme::Legacy as u8);
+            prop_assert_eq!(&post.content_addr[..post.content_len as usize], &db_addr[..]);
+            prop_assert_eq!(post.version, POST_VERSION);
+        }
+    }
 }